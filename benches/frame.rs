@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use ethercrab::{Command, MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+use ethercrab::{Command, ConfiguredAddress, MainDevice, MainDeviceConfig, PduStorage, Timeouts};
 use futures_lite::FutureExt;
 use std::{future::poll_fn, pin::pin, task::Poll};
 
@@ -15,7 +15,8 @@ pub fn push_pdu(c: &mut Criterion) {
 
     c.bench_function("frame push pdu", |b| {
         b.iter(|| {
-            let mut f = pin!(Command::fpwr(0x5678, 0x1234).send_receive_slice(&maindevice, &DATA));
+            let mut f = pin!(Command::fpwr(ConfiguredAddress::new(0x5678), 0x1234)
+                .send_receive_slice(&maindevice, &DATA));
 
             cassette::block_on(poll_fn(|ctx| {
                 let _ = f.poll(ctx);
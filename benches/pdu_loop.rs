@@ -1,7 +1,7 @@
 use core::future::poll_fn;
 use core::task::Poll;
 use criterion::{criterion_group, criterion_main, Bencher, Criterion, Throughput};
-use ethercrab::{Command, MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+use ethercrab::{Command, ConfiguredAddress, MainDevice, MainDeviceConfig, PduStorage, Timeouts};
 use futures_lite::FutureExt;
 use std::{pin::pin, time::Duration};
 
@@ -29,8 +29,8 @@ fn do_bench(b: &mut Bencher) {
     b.iter(|| {
         //  --- Prepare frame
 
-        let mut frame_fut =
-            pin!(Command::fpwr(0x5678, 0x1234).send_receive::<()>(&maindevice, &DATA));
+        let mut frame_fut = pin!(Command::fpwr(ConfiguredAddress::new(0x5678), 0x1234)
+            .send_receive::<()>(&maindevice, &DATA));
 
         // Poll future once to register it with sender
         cassette::block_on(poll_fn(|ctx| {
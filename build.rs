@@ -0,0 +1,49 @@
+fn main() {
+    #[cfg(feature = "dpdk")]
+    dpdk();
+}
+
+/// Compile the DPDK wrapper functions and link against the DPDK libraries found with `pkg-config`.
+#[cfg(feature = "dpdk")]
+fn dpdk() {
+    use std::process::Command;
+
+    println!("cargo:rerun-if-changed=src/std/dpdk/shim.c");
+
+    // The transport is only built for Linux targets
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("linux") {
+        return;
+    }
+
+    // Without DPDK, build a `tx_rx_task_dpdk` that always returns an error instead of failing the
+    // whole build, so e.g. `cargo check --all-features` still works.
+    if pkg_config::Config::new()
+        .atleast_version("21.11")
+        .probe("libdpdk")
+        .is_err()
+    {
+        println!(
+            "cargo:warning=DPDK 21.11 or newer was not found with pkg-config, so tx_rx_task_dpdk will always return an error"
+        );
+        println!("cargo:rustc-cfg=ethercrab_dpdk_missing");
+
+        return;
+    }
+
+    // `pkg_config` doesn't expose the `-march` and `-include rte_config.h` flags DPDK's headers
+    // need, so get the full set of compiler flags directly.
+    let cflags = Command::new(std::env::var("PKG_CONFIG").unwrap_or_else(|_| "pkg-config".into()))
+        .args(["--cflags", "libdpdk"])
+        .output()
+        .expect("run pkg-config");
+
+    let cflags = String::from_utf8(cflags.stdout).expect("pkg-config output is not UTF-8");
+
+    let mut build = cc::Build::new();
+
+    for flag in cflags.split_whitespace() {
+        build.flag(flag);
+    }
+
+    build.file("src/std/dpdk/shim.c").compile("ethercrab_dpdk");
+}
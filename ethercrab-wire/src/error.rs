@@ -3,6 +3,7 @@
 /// Wire encode/decode errors.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt-03", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WireError {
     /// The buffer to extract a type from is too short to do so.
     ReadBufferTooShort,
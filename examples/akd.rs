@@ -4,7 +4,7 @@ use env_logger::Env;
 use ethercrab::{
     error::{Error, MailboxError},
     std::{ethercat_now, tx_rx_task},
-    MainDevice, MainDeviceConfig, PduStorage, Timeouts,
+    MainDevice, MainDeviceConfig, PdiData, PduStorage, Timeouts,
 };
 use std::{sync::Arc, time::Duration};
 use tokio::time::MissedTickBehavior;
@@ -211,7 +211,7 @@ async fn main() -> Result<(), Error> {
             let (i, _o) = subdevice.io_raw_mut();
 
             let status = {
-                let status = u16::from_le_bytes(i[4..=5].try_into().unwrap());
+                let status = i.get_at::<u16>(4).unwrap();
 
                 AkdStatusWord::from_bits_truncate(status)
             };
@@ -245,7 +245,7 @@ async fn main() -> Result<(), Error> {
             let (i, o) = subdevice.io_raw_mut();
 
             let status = {
-                let status = u16::from_le_bytes(i[4..=5].try_into().unwrap());
+                let status = i.get_at::<u16>(4).unwrap();
 
                 AkdStatusWord::from_bits_truncate(status)
             };
@@ -278,8 +278,8 @@ async fn main() -> Result<(), Error> {
         let (i, o) = subdevice.io_raw_mut();
 
         let (pos, status) = {
-            let pos = u32::from_le_bytes(i[0..=3].try_into().unwrap());
-            let status = u16::from_le_bytes(i[4..=5].try_into().unwrap());
+            let pos = i.get_at::<u32>(0).unwrap();
+            let status = i.get_at::<u16>(4).unwrap();
 
             let status = AkdStatusWord::from_bits_truncate(status);
 
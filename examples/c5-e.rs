@@ -7,7 +7,7 @@ use env_logger::Env;
 use ethercrab::{
     ds402::{Ds402, Ds402Sm, StatusWord},
     std::{ethercat_now, tx_rx_task},
-    MainDevice, MainDeviceConfig, PduStorage, Timeouts,
+    MainDevice, MainDeviceConfig, PdiData, PduStorage, Timeouts,
 };
 use std::{
     array::from_ref,
@@ -241,8 +241,8 @@ async fn main() -> anyhow::Result<()> {
             let (i, o) = servo.subdevice().io_raw_mut();
 
             let (pos, vel) = {
-                let pos = i32::from_le_bytes(i[2..=5].try_into().unwrap());
-                let vel = i32::from_le_bytes(i[6..=9].try_into().unwrap());
+                let pos = i.get_at::<i32>(2).unwrap();
+                let vel = i.get_at::<i32>(6).unwrap();
 
                 (pos, vel)
             };
@@ -317,8 +317,8 @@ async fn main() -> anyhow::Result<()> {
         }
 
         let (pos, vel) = {
-            let pos = i32::from_le_bytes(i[2..=5].try_into().unwrap());
-            let vel = i32::from_le_bytes(i[6..=9].try_into().unwrap());
+            let pos = i.get_at::<i32>(2).unwrap();
+            let vel = i.get_at::<i32>(6).unwrap();
 
             (pos, vel)
         };
@@ -6,7 +6,8 @@ use ethercrab::{
     error::Error,
     std::{ethercat_now, tx_rx_task},
     subdevice_group::{CycleInfo, DcConfiguration},
-    DcSync, MainDevice, MainDeviceConfig, PduStorage, RegisterAddress, SubDeviceGroup, Timeouts,
+    ConfiguredAddress, DcSync, MainDevice, MainDeviceConfig, PduStorage, RegisterAddress,
+    SubDeviceGroup, Timeouts,
 };
 use futures_lite::StreamExt;
 use std::{
@@ -61,7 +62,7 @@ fn main() -> Result<(), Error> {
             .init::<MAX_SUBDEVICES, (SubDeviceGroup<1, 32>, SubDeviceGroup<1, 32>)>(
                 ethercat_now,
                 |groups, s| {
-                    if s.configured_address() == 0x1000 {
+                    if s.configured_address() == ConfiguredAddress::new(0x1000) {
                         Ok(&groups.0)
                     } else {
                         Ok(&groups.1)
@@ -179,7 +180,7 @@ fn main() -> Result<(), Error> {
                                 value as i32
                             }
                         }
-                        Err(Error::WorkingCounter { .. }) => 0,
+                        Err(e) if matches!(e.cause(), Error::WorkingCounter { .. }) => 0,
                         Err(e) => return Err(e),
                     };
 
@@ -238,7 +239,7 @@ fn main() -> Result<(), Error> {
                             }
                         }) {
                         Ok(diff) => diff,
-                        Err(Error::WorkingCounter { .. }) => 0,
+                        Err(e) if matches!(e.cause(), Error::WorkingCounter { .. }) => 0,
                         Err(e) => return Err(e),
                     };
 
@@ -273,7 +273,7 @@ fn main() -> Result<(), Error> {
                                 value as i32
                             }
                         }
-                        Err(Error::WorkingCounter { .. }) => 0,
+                        Err(e) if matches!(e.cause(), Error::WorkingCounter { .. }) => 0,
                         Err(e) => return Err(e),
                     };
 
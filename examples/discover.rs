@@ -1,20 +1,15 @@
 //! Discover devices connected to the network.
 
 use env_logger::Env;
-use ethercrab::{
-    std::{ethercat_now, tx_rx_task},
-    MainDevice, MainDeviceConfig, PduStorage, Timeouts,
-};
+use ethercrab::{std::tx_rx_task, MainDevice, PduStorage};
 use std::{str::FromStr, sync::Arc};
 
-/// Maximum number of SubDevices that can be stored. This must be a power of 2 greater than 1.
+/// Maximum number of SubDevices that can be stored.
 const MAX_SUBDEVICES: usize = 128;
 /// Maximum PDU data payload size - set this to the max PDI size or higher.
 const MAX_PDU_DATA: usize = PduStorage::element_size(1100);
 /// Maximum number of EtherCAT frames that can be in flight at any one time.
 const MAX_FRAMES: usize = 16;
-/// Maximum total PDI length.
-const PDI_LEN: usize = 64;
 
 static PDU_STORAGE: PduStorage<MAX_FRAMES, MAX_PDU_DATA> = PduStorage::new();
 
@@ -29,37 +24,38 @@ fn main() {
 
     let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
 
-    let maindevice = Arc::new(MainDevice::new(
-        pdu_loop,
-        Timeouts::default(),
-        MainDeviceConfig {
-            dc_static_sync_iterations: 0,
-            ..MainDeviceConfig::default()
-        },
-    ));
+    let maindevice = Arc::new(
+        MainDevice::builder(pdu_loop)
+            .dc_static_sync_iterations(0)
+            .build()
+            .expect("Invalid MainDevice config"),
+    );
 
     smol::block_on(async {
         smol::spawn(tx_rx_task(&interface, tx, rx).expect("spawn TX/RX task")).detach();
 
-        let mut group = maindevice
-            .init_single_group::<MAX_SUBDEVICES, PDI_LEN>(ethercat_now)
-            .await
-            .expect("Init");
+        let subdevices = maindevice.scan::<MAX_SUBDEVICES>().await.expect("Scan");
 
-        log::info!("Discovered {} SubDevices", group.len());
+        log::info!("Discovered {} SubDevices", subdevices.len());
 
-        for subdevice in group.iter(&maindevice) {
+        for subdevice in subdevices.iter() {
             log::info!(
                 "--> SubDevice {:#06x} name {}, description {}, {}",
                 subdevice.configured_address(),
                 subdevice.name(),
                 subdevice
-                    .description()
+                    .description(&maindevice)
                     .await
                     .expect("Failed to read description")
                     .unwrap_or(heapless::String::<64>::from_str("[no description]").unwrap()),
                 subdevice.identity()
             );
+            log::info!(
+                "    alias address {:#06x}, {:?}, parent {:?}",
+                subdevice.alias_address(),
+                subdevice.topology(),
+                subdevice.parent_index()
+            );
         }
     });
 
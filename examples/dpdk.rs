@@ -0,0 +1,70 @@
+//! Discover devices using the busy-polling DPDK TX/RX loop.
+//!
+//! This example requires DPDK 21.11 or newer, configured hugepages and a NIC bound to a DPDK
+//! compatible driver like `vfio-pci`. Pass the device's PCI address as the first argument, e.g.
+//! `cargo run --example dpdk --features dpdk --release -- 0000:01:00.0`.
+
+#[cfg(not(target_os = "linux"))]
+fn main() {
+    eprintln!("This example is only supported on Linux systems");
+}
+
+#[cfg(target_os = "linux")]
+fn main() {
+    use env_logger::Env;
+    use ethercrab::{std::tx_rx_task_dpdk, MainDevice, PduStorage};
+    use std::{sync::Arc, thread};
+
+    /// Maximum number of SubDevices that can be stored.
+    const MAX_SUBDEVICES: usize = 128;
+    /// Maximum PDU data payload size - set this to the max PDI size or higher.
+    const MAX_PDU_DATA: usize = PduStorage::element_size(1100);
+    /// Maximum number of EtherCAT frames that can be in flight at any one time.
+    const MAX_FRAMES: usize = 16;
+
+    static PDU_STORAGE: PduStorage<MAX_FRAMES, MAX_PDU_DATA> = PduStorage::new();
+
+    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+
+    let device = std::env::args()
+        .nth(1)
+        .expect("Provide DPDK device PCI address as first argument.");
+
+    log::info!("Discovering EtherCAT devices on {} using DPDK...", device);
+
+    let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+
+    let maindevice = Arc::new(
+        MainDevice::builder(pdu_loop)
+            .dc_static_sync_iterations(0)
+            .build()
+            .expect("Invalid MainDevice config"),
+    );
+
+    thread::Builder::new()
+        .name("tx-rx-thread".into())
+        .spawn(move || {
+            // Only use the given device, and run the EAL on the first core
+            let eal_args = ["-l", "0", "-a", device.as_str()];
+
+            tx_rx_task_dpdk(&eal_args, &device, tx, rx).expect("TX/RX task")
+        })
+        .expect("Build TX/RX thread");
+
+    smol::block_on(async {
+        let subdevices = maindevice.scan::<MAX_SUBDEVICES>().await.expect("Scan");
+
+        log::info!("Discovered {} SubDevices", subdevices.len());
+
+        for subdevice in subdevices.iter() {
+            log::info!(
+                "--> SubDevice {:#06x} name {}, {}",
+                subdevice.configured_address(),
+                subdevice.name(),
+                subdevice.identity()
+            );
+        }
+    });
+
+    log::info!("Done.");
+}
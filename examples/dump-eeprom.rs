@@ -85,13 +85,11 @@ async fn main() -> Result<(), Error> {
         subdevice.identity()
     );
 
-    let base_address = 0x1000;
-
     let mut len_buf = [0u8; 2];
 
     // ETG2020 page 7: 0x003e is the EEPROM address size register in kilobit minus 1 (u16).
     ChunkReader::new(
-        DeviceEeprom::new(&maindevice, base_address + index),
+        DeviceEeprom::new(&maindevice, subdevice.configured_address()),
         0x003e,
         2,
     )
@@ -100,14 +98,17 @@ async fn main() -> Result<(), Error> {
     .expect("Could not read EEPROM len");
 
     // Kilobits to bits to bytes, and undoing the offset
-    let len = ((u16::from_le_bytes(len_buf) + 1) * 1024) / 8;
+    let len = ((u32::from(u16::from_le_bytes(len_buf)) + 1) * 1024) / 8;
 
     log::info!("--> Device EEPROM is {} bytes long", len);
 
-    let mut provider =
-        ChunkReader::new(DeviceEeprom::new(&maindevice, base_address + index), 0, len);
+    let mut provider = ChunkReader::new(
+        DeviceEeprom::new(&maindevice, subdevice.configured_address()),
+        0,
+        len,
+    );
 
-    let mut buf = vec![0u8; usize::from(len)];
+    let mut buf = vec![0u8; len as usize];
 
     provider.read_exact(&mut buf).await.expect("Read");
 
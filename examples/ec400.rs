@@ -9,7 +9,7 @@
 
 use env_logger::Env;
 use ethercrab::{
-    ds402::{Ds402, Ds402Sm},
+    ds402::{Ds402, OperationMode, PdoLayout},
     error::Error,
     std::{ethercat_now, tx_rx_task},
     MainDevice, MainDeviceConfig, PduStorage, Timeouts,
@@ -65,38 +65,14 @@ async fn main() -> Result<(), Error> {
         .await
         .expect("Init");
 
+    let mut layout = PdoLayout::default();
+
     for subdevice in group.iter(&maindevice) {
         if subdevice.name() == "ELP-EC400S" {
-            // CSV described a bit better in section 7.6.2.2 Related Objects of the manual
-            subdevice.sdo_write(0x1600, 0, 0u8).await?;
-            // Control word, u16
-            // NOTE: The lower word specifies the field length
-            subdevice.sdo_write(0x1600, 1, 0x6040_0010u32).await?;
-            // Target velocity, i32
-            subdevice.sdo_write(0x1600, 2, 0x60ff_0020u32).await?;
-            subdevice.sdo_write(0x1600, 0, 2u8).await?;
-
-            subdevice.sdo_write(0x1a00, 0, 0u8).await?;
-            // Status word, u16
-            subdevice.sdo_write(0x1a00, 1, 0x6041_0010u32).await?;
-            // Actual position, i32
-            subdevice.sdo_write(0x1a00, 2, 0x6064_0020u32).await?;
-            // Actual velocity, i32
-            subdevice.sdo_write(0x1a00, 3, 0x606c_0020u32).await?;
-            subdevice.sdo_write(0x1a00, 0, 0x03u8).await?;
-
-            subdevice.sdo_write(0x1c12, 0, 0u8).await?;
-            subdevice.sdo_write(0x1c12, 1, 0x1600).await?;
-            subdevice.sdo_write(0x1c12, 0, 1u8).await?;
-
-            subdevice.sdo_write(0x1c13, 0, 0u8).await?;
-            subdevice.sdo_write(0x1c13, 1, 0x1a00).await?;
-            subdevice.sdo_write(0x1c13, 0, 1u8).await?;
-
-            // Opmode - Cyclic Synchronous Position
-            // subdevice.write_sdo(0x6060, 0, 0x08).await?;
-            // Opmode - Cyclic Synchronous Velocity
-            subdevice.sdo_write(0x6060, 0, 0x09u8).await?;
+            // Control word and target velocity out, status word, actual position and actual
+            // velocity in. CSV is described in section 7.6.2.2 Related Objects of the manual.
+            layout =
+                PdoLayout::configure(&subdevice, OperationMode::CyclicSynchronousVelocity).await?;
         }
     }
 
@@ -142,7 +118,9 @@ async fn main() -> Result<(), Error> {
     cyclic_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
     let subdevice = group.subdevice(&maindevice, 0).expect("No servo!");
-    let mut servo = Ds402Sm::new(Ds402::new(subdevice).expect("Failed to gather DS402"));
+    let mut servo = Ds402::new(subdevice).expect("Failed to gather DS402");
+
+    servo.set_pdo_layout(layout);
 
     let mut velocity: i32 = 0;
 
@@ -152,78 +130,50 @@ async fn main() -> Result<(), Error> {
     signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&term))
         .expect("Register hook");
 
-    loop {
-        group.tx_rx(&maindevice).await.expect("TX/RX");
+    // Exchange process data once per cycle while the drive steps through the DS402 states
+    let cycle = || async {
+        group.tx_rx(&maindevice).await?;
 
-        if servo.tick() {
-            // // Opmode - Cyclic Synchronous Position
-            // servo
-            //     .sm
-            //     .context()
-            //     .subdevice
-            //     .write_sdo(&maindevice, 0x6060, 0, 0x08u8)
-            //     .await?;
-
-            let status = servo.status_word();
-            let (i, o) = servo.subdevice().io_raw_mut();
-
-            let (pos, vel) = {
-                let pos = i32::from_le_bytes(i[2..=5].try_into().unwrap());
-                let vel = i32::from_le_bytes(i[6..=9].try_into().unwrap());
-
-                (pos, vel)
-            };
-
-            println!(
-                "Position: {pos}, velocity: {vel}, status: {status:?} | {:?}",
-                o
-            );
-
-            let pos_cmd = &mut o[2..=5];
-
-            pos_cmd.copy_from_slice(&velocity.to_le_bytes());
-
-            if term.load(Ordering::Relaxed) {
-                if vel < 200_000 {
-                    velocity += accel;
-                }
-            } else if vel > 0 {
-                velocity -= accel;
-            } else {
-                break;
-            }
-        }
+        tokio::time::sleep(cycle_time).await;
 
-        cyclic_interval.tick().await;
-    }
+        Ok(())
+    };
 
-    log::info!("Servo stopped, shutting drive down");
+    servo.enable(cycle).await?;
+
+    log::info!("Drive is enabled");
 
     loop {
         group.tx_rx(&maindevice).await.expect("TX/RX");
 
-        if servo.tick_shutdown() {
-            break;
-        }
-
         let status = servo.status_word();
-        let (i, o) = servo.subdevice().io_raw_mut();
-
-        let (pos, vel) = {
-            let pos = i32::from_le_bytes(i[2..=5].try_into().unwrap());
-            let vel = i32::from_le_bytes(i[6..=9].try_into().unwrap());
-
-            (pos, vel)
-        };
+        let pos = servo.position_actual()?;
+        let vel = servo.velocity_actual()?;
 
         println!(
             "Position: {pos}, velocity: {vel}, status: {status:?} | {:?}",
-            o
+            servo.subdevice.outputs_raw()
         );
 
+        servo.set_target_velocity(velocity)?;
+
+        if term.load(Ordering::Relaxed) {
+            if vel < 200_000 {
+                velocity += accel;
+            }
+        } else if vel > 0 {
+            velocity -= accel;
+        } else {
+            break;
+        }
+
         cyclic_interval.tick().await;
     }
 
+    log::info!("Servo stopped, shutting drive down");
+
+    servo.disable(cycle).await?;
+
     log::info!("Drive is shut down");
 
     Ok(())
@@ -4,7 +4,7 @@
 use core::{future::poll_fn, task::Poll};
 use defmt_rtt as _;
 use embassy_executor::Spawner;
-use embassy_net::driver::{Driver, LinkState, RxToken, TxToken};
+use embassy_net::driver::{Driver, LinkState};
 use embassy_stm32::{
     bind_interrupts,
     eth::{self, generic_smi::GenericSMI, Ethernet, PacketQueue},
@@ -14,7 +14,10 @@ use embassy_stm32::{
     Config,
 };
 use embassy_time::{Duration, Instant, Timer};
-use ethercrab::{MainDevice, MainDeviceConfig, PduRx, PduStorage, PduTx, SendableFrame, Timeouts};
+use ethercrab::{
+    embassy::{ethercat_now, tx_rx_task},
+    MainDevice, MainDeviceConfig, PduRx, PduStorage, PduTx, Timeouts,
+};
 use panic_probe as _;
 use static_cell::StaticCell;
 
@@ -34,52 +37,16 @@ const PDI_LEN: usize = 256;
 static PDU_STORAGE: PduStorage<MAX_FRAMES, MAX_PDU_DATA> = PduStorage::new();
 
 #[embassy_executor::task]
-async fn tx_rx_task(
-    mut device: Ethernet<'static, ETH, GenericSMI>,
-    mut pdu_tx: PduTx<'static>,
-    mut pdu_rx: PduRx<'static>,
+async fn ethercat_tx_rx(
+    device: Ethernet<'static, ETH, GenericSMI>,
+    pdu_tx: PduTx<'static>,
+    pdu_rx: PduRx<'static>,
 ) -> ! {
     defmt::info!("Spawn TX/RX");
 
-    #[inline(always)]
-    fn send_ecat(tx: embassy_stm32::eth::TxToken<'_, '_>, frame: SendableFrame<'_>) {
-        tx.consume(frame.len(), |tx_buf| {
-            defmt::unwrap!(frame
-                .send_blocking(|ethernet_frame| {
-                    tx_buf[0..ethernet_frame.len()].copy_from_slice(ethernet_frame);
-
-                    Ok(ethernet_frame.len())
-                })
-                .map_err(|e| defmt::error!("Send blocking: {}", e)));
-        });
-    }
-
-    poll_fn(|ctx| {
-        pdu_tx.replace_waker(ctx.waker());
-
-        if let Some((rx, tx)) = device.receive(ctx) {
-            rx.consume(|frame| {
-                defmt::unwrap!(pdu_rx.receive_frame(frame));
-            });
-
-            if let Some(ethercat_frame) = pdu_tx.next_sendable_frame() {
-                send_ecat(tx, ethercat_frame);
-
-                // Wake the poll_fn again so that any still-queued frames can be sent.
-                ctx.waker().wake_by_ref();
-            }
-        } else if let Some(tx) = device.transmit(ctx) {
-            if let Some(ethercat_frame) = pdu_tx.next_sendable_frame() {
-                send_ecat(tx, ethercat_frame);
-
-                // Wake the poll_fn again so that any still-queued frames can be sent.
-                ctx.waker().wake_by_ref();
-            }
-        }
+    let result = tx_rx_task(device, pdu_tx, pdu_rx).await;
 
-        Poll::Pending
-    })
-    .await
+    defmt::panic!("TX/RX task exited: {}", result);
 }
 
 #[embassy_executor::task]
@@ -162,7 +129,7 @@ async fn main(spawner: Spawner) {
         device
     };
 
-    defmt::unwrap!(spawner.spawn(tx_rx_task(device, tx, rx)));
+    defmt::unwrap!(spawner.spawn(ethercat_tx_rx(device, tx, rx)));
 
     let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
 
@@ -170,7 +137,7 @@ async fn main(spawner: Spawner) {
 
     let group = defmt::unwrap!(
         maindevice
-            .init_single_group::<MAX_SUBDEVICES, PDI_LEN>(|| Instant::now().as_micros() * 1000)
+            .init_single_group::<MAX_SUBDEVICES, PDI_LEN>(ethercat_now)
             .await
     );
 
@@ -17,7 +17,7 @@ fn main() -> Result<(), ethercrab::error::Error> {
     use env_logger::{Env, TimestampPrecision};
     use ethercrab::{
         error::Error,
-        std::{ethercat_now, tx_rx_task_io_uring},
+        std::{ethercat_now, tx_rx_task_io_uring, RealtimeConfig},
         MainDevice, MainDeviceConfig, PduStorage, SubDeviceGroup, Timeouts,
     };
     use std::{
@@ -25,9 +25,6 @@ fn main() -> Result<(), ethercrab::error::Error> {
         thread,
         time::{Duration, Instant},
     };
-    use thread_priority::{
-        RealtimeThreadSchedulePolicy, ThreadPriority, ThreadPriorityValue, ThreadSchedulePolicy,
-    };
     use timerfd::{SetTimeFlags, TimerFd, TimerState};
 
     /// Maximum number of SubDevices that can be stored. This must be a power of 2 greater than 1.
@@ -69,36 +66,26 @@ fn main() -> Result<(), ethercrab::error::Error> {
 
     let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
 
-    let core_ids = core_affinity::get_core_ids().expect("Couldn't get core IDs");
-
-    let tx_rx_core = core_ids
-        .first()
-        .copied()
-        .expect("At least one core is required. Are you running on a potato?");
-    let slow_core = core_ids
-        .get(1)
-        .copied()
-        .expect("At least 2 cores are required.");
-    let fast_core = core_ids
-        .get(2)
-        .copied()
-        .expect("At least 3 cores are required.");
-
-    thread_priority::ThreadBuilder::default()
-        .name("tx-rx-thread")
-        // Might need to set `<user> hard rtprio 99` and `<user> soft rtprio 99` in `/etc/security/limits.conf`
-        // Check limits with `ulimit -Hr` or `ulimit -Sr`
-        .priority(ThreadPriority::Crossplatform(
-            ThreadPriorityValue::try_from(49u8).unwrap(),
-        ))
-        // NOTE: Requires a realtime kernel
-        .policy(ThreadSchedulePolicy::Realtime(
-            RealtimeThreadSchedulePolicy::Fifo,
-        ))
-        .spawn(move |_| {
-            core_affinity::set_for_current(tx_rx_core)
-                .then_some(())
-                .expect("Set TX/RX thread core");
+    let cores = thread::available_parallelism()
+        .expect("Couldn't get core count")
+        .get();
+
+    assert!(cores >= 3, "At least 3 cores are required.");
+
+    thread::Builder::new()
+        .name("tx-rx-thread".into())
+        .spawn(move || {
+            // Might need to set `<user> hard rtprio 99` and `<user> soft rtprio 99` in
+            // `/etc/security/limits.conf`. Check limits with `ulimit -Hr` or `ulimit -Sr`.
+            //
+            // NOTE: Requires a realtime kernel
+            RealtimeConfig {
+                priority: Some(49),
+                cpu: Some(0),
+                lock_memory: true,
+            }
+            .apply()
+            .expect("Set TX/RX thread real-time config");
 
             // Blocking io_uring
             tx_rx_task_io_uring(&interface, tx, rx).expect("TX/RX task");
@@ -128,21 +115,20 @@ fn main() -> Result<(), ethercrab::error::Error> {
 
     let maindevice_slow = maindevice.clone();
 
-    let slow = thread_priority::ThreadBuilder::default()
-        .name("slow-task")
-        // Might need to set `<user> hard rtprio 99` and `<user> soft rtprio 99` in `/etc/security/limits.conf`
-        // Check limits with `ulimit -Hr` or `ulimit -Sr`
-        .priority(ThreadPriority::Crossplatform(
-            ThreadPriorityValue::try_from(48u8).unwrap(),
-        ))
-        // NOTE: Requires a realtime kernel
-        .policy(ThreadSchedulePolicy::Realtime(
-            RealtimeThreadSchedulePolicy::Fifo,
-        ))
-        .spawn(move |_| {
-            core_affinity::set_for_current(slow_core)
-                .then_some(())
-                .expect("Set slow thread core");
+    let slow = thread::Builder::new()
+        .name("slow-task".into())
+        .spawn(move || {
+            // Might need to set `<user> hard rtprio 99` and `<user> soft rtprio 99` in
+            // `/etc/security/limits.conf`. Check limits with `ulimit -Hr` or `ulimit -Sr`.
+            //
+            // NOTE: Requires a realtime kernel
+            RealtimeConfig {
+                priority: Some(48),
+                cpu: Some(1),
+                lock_memory: true,
+            }
+            .apply()
+            .expect("Set slow thread real-time config");
 
             futures_lite::future::block_on::<Result<(), Error>>(async {
                 let slow_outputs = slow_outputs
@@ -197,21 +183,20 @@ fn main() -> Result<(), ethercrab::error::Error> {
         })
         .unwrap();
 
-    let fast = thread_priority::ThreadBuilder::default()
-        .name("fast-task")
-        // Might need to set `<user> hard rtprio 99` and `<user> soft rtprio 99` in `/etc/security/limits.conf`
-        // Check limits with `ulimit -Hr` or `ulimit -Sr`
-        .priority(ThreadPriority::Crossplatform(
-            ThreadPriorityValue::try_from(48u8).unwrap(),
-        ))
-        // NOTE: Requires a realtime kernel
-        .policy(ThreadSchedulePolicy::Realtime(
-            RealtimeThreadSchedulePolicy::Fifo,
-        ))
-        .spawn(move |_| {
-            core_affinity::set_for_current(fast_core)
-                .then_some(())
-                .expect("Set fast thread core");
+    let fast = thread::Builder::new()
+        .name("fast-task".into())
+        .spawn(move || {
+            // Might need to set `<user> hard rtprio 99` and `<user> soft rtprio 99` in
+            // `/etc/security/limits.conf`. Check limits with `ulimit -Hr` or `ulimit -Sr`.
+            //
+            // NOTE: Requires a realtime kernel
+            RealtimeConfig {
+                priority: Some(48),
+                cpu: Some(2),
+                lock_memory: true,
+            }
+            .apply()
+            .expect("Set fast thread real-time config");
 
             futures_lite::future::block_on::<Result<(), Error>>(async {
                 let mut fast_outputs = fast_outputs
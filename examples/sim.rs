@@ -0,0 +1,133 @@
+//! Run EtherCrab against a simulated network of SubDevices, without any hardware.
+//!
+//! Run with e.g.
+//!
+//! ```bash
+//! RUST_LOG=debug cargo run --example sim --features sim
+//! ```
+
+use env_logger::Env;
+use ethercrab::{
+    error::Error,
+    sim::{SimNetwork, SimSubDevice},
+    std::ethercat_now,
+    tx_rx_device, MainDevice, MainDeviceConfig, PduStorage, SubDeviceIdentity, Timeouts,
+};
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+
+/// Maximum number of SubDevices that can be stored. This must be a power of 2 greater than 1.
+const MAX_SUBDEVICES: usize = 16;
+/// Maximum PDU data payload size - set this to the max PDI size or higher.
+const MAX_PDU_DATA: usize = PduStorage::element_size(1100);
+/// Maximum number of EtherCAT frames that can be in flight at any one time.
+const MAX_FRAMES: usize = 16;
+/// Maximum total PDI length.
+const PDI_LEN: usize = 64;
+
+static PDU_STORAGE: PduStorage<MAX_FRAMES, MAX_PDU_DATA> = PduStorage::new();
+
+fn beckhoff(product_id: u32) -> SubDeviceIdentity {
+    SubDeviceIdentity {
+        vendor_id: 0x2,
+        product_id,
+        revision: 0x0011_0000,
+        serial: 0,
+    }
+}
+
+// Responses are returned as soon as a frame is sent, so the TX/RX task must run on the same thread
+// as the application to give PDU futures a chance to register their wakers first.
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Error> {
+    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+
+    log::info!("Starting simulated network demo...");
+
+    // An EK1100 coupler with a 4 channel digital output module, 4 channel digital input module and
+    // a CoE device with 2 bytes of outputs and 4 bytes of inputs.
+    let network = SimNetwork::new([
+        SimSubDevice::new("EK1100", beckhoff(0x044c2c52)),
+        SimSubDevice::new("EL2004", beckhoff(0x07d43052)).with_outputs(1),
+        SimSubDevice::new("EL1004", beckhoff(0x03ec3052)).with_inputs(1),
+        SimSubDevice::new("Sim drive", beckhoff(0x0000_1234))
+            .with_outputs(2)
+            .with_inputs(4)
+            .with_coe(),
+    ]);
+
+    let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+
+    let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+
+    tokio::spawn(tx_rx_device(network.clone(), tx, rx));
+
+    let group = maindevice
+        .init_single_group::<MAX_SUBDEVICES, PDI_LEN>(ethercat_now)
+        .await
+        .expect("Init");
+
+    log::info!("Discovered {} SubDevices", group.len());
+
+    {
+        let drive = group.subdevice(&maindevice, 3)?;
+
+        log::info!(
+            "-> {} vendor ID {:#010x} from SDO 0x1018:1",
+            drive.name(),
+            drive.sdo_read::<u32>(0x1018, 1).await?
+        );
+    }
+
+    let mut group = group.into_op(&maindevice).await.expect("PRE-OP -> OP");
+
+    for subdevice in group.iter(&maindevice) {
+        let (i, o) = subdevice.io_raw();
+
+        log::info!(
+            "-> SubDevice {:#06x} {} inputs: {} bytes, outputs: {} bytes",
+            subdevice.configured_address(),
+            subdevice.name(),
+            i.len(),
+            o.len()
+        );
+    }
+
+    let mut tick_interval = tokio::time::interval(Duration::from_millis(5));
+    tick_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    for cycle in 0u8..10 {
+        // Simulate a changing input signal
+        network.with_subdevice(2, |subdevice| subdevice.inputs_mut()[0] = cycle);
+
+        group.tx_rx(&maindevice).await.expect("TX/RX");
+
+        let mut el2004 = group.subdevice(&maindevice, 1)?;
+
+        // Echo inputs to outputs
+        el2004.outputs_raw_mut()[0] = group.subdevice(&maindevice, 2)?.inputs_raw()[0];
+
+        tick_interval.tick().await;
+    }
+
+    log::info!(
+        "EL2004 outputs after 10 cycles: {:?}",
+        network.with_subdevice(1, |subdevice| subdevice.outputs().to_vec())
+    );
+
+    let group = group
+        .into_safe_op(&maindevice)
+        .await
+        .expect("OP -> SAFE-OP");
+
+    let group = group
+        .into_pre_op(&maindevice)
+        .await
+        .expect("SAFE-OP -> PRE-OP");
+
+    let _group = group.into_init(&maindevice).await.expect("PRE-OP -> INIT");
+
+    log::info!("PRE-OP -> INIT, shutdown complete");
+
+    Ok(())
+}
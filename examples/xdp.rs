@@ -0,0 +1,67 @@
+//! Discover devices using the blocking `AF_XDP` TX/RX loop.
+//!
+//! This example requires Linux 5.9 or newer and must be run as root, or with the `CAP_NET_ADMIN`,
+//! `CAP_NET_RAW` and `CAP_BPF` capabilities.
+
+#[cfg(not(target_os = "linux"))]
+fn main() {
+    eprintln!("This example is only supported on Linux systems");
+}
+
+#[cfg(target_os = "linux")]
+fn main() {
+    use env_logger::Env;
+    use ethercrab::{std::tx_rx_task_xdp, MainDevice, PduStorage};
+    use std::{sync::Arc, thread};
+
+    /// Maximum number of SubDevices that can be stored.
+    const MAX_SUBDEVICES: usize = 128;
+    /// Maximum PDU data payload size - set this to the max PDI size or higher.
+    const MAX_PDU_DATA: usize = PduStorage::element_size(1100);
+    /// Maximum number of EtherCAT frames that can be in flight at any one time.
+    const MAX_FRAMES: usize = 16;
+
+    static PDU_STORAGE: PduStorage<MAX_FRAMES, MAX_PDU_DATA> = PduStorage::new();
+
+    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+
+    let interface = std::env::args()
+        .nth(1)
+        .expect("Provide network interface as first argument.");
+
+    log::info!(
+        "Discovering EtherCAT devices on {} using AF_XDP...",
+        interface
+    );
+
+    let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+
+    let maindevice = Arc::new(
+        MainDevice::builder(pdu_loop)
+            .dc_static_sync_iterations(0)
+            .build()
+            .expect("Invalid MainDevice config"),
+    );
+
+    thread::Builder::new()
+        .name("tx-rx-thread".into())
+        .spawn(move || tx_rx_task_xdp(&interface, tx, rx).expect("TX/RX task"))
+        .expect("Build TX/RX thread");
+
+    smol::block_on(async {
+        let subdevices = maindevice.scan::<MAX_SUBDEVICES>().await.expect("Scan");
+
+        log::info!("Discovered {} SubDevices", subdevices.len());
+
+        for subdevice in subdevices.iter() {
+            log::info!(
+                "--> SubDevice {:#06x} name {}, {}",
+                subdevice.configured_address(),
+                subdevice.name(),
+                subdevice.identity()
+            );
+        }
+    });
+
+    log::info!("Done.");
+}
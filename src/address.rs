@@ -0,0 +1,185 @@
+//! Typed SubDevice addresses.
+//!
+//! EtherCAT addresses SubDevices in several ways which are all plain integers on the wire. Each
+//! has its own type here so that e.g. a SubDevice's position in the network can't be passed where
+//! its configured station address is expected.
+
+use crate::BASE_SUBDEVICE_ADDRESS;
+
+macro_rules! address {
+    ($(#[$meta:meta])* $name:ident($inner:ty), $display:literal) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(transparent)
+        )]
+        #[repr(transparent)]
+        pub struct $name($inner);
+
+        impl $name {
+            /// Create an address from its raw value.
+            pub const fn new(raw: $inner) -> Self {
+                Self(raw)
+            }
+
+            /// Get the raw value of this address.
+            pub const fn get(self) -> $inner {
+                self.0
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                write!(f, concat!(stringify!($name), "(", $display, ")"), self.0)
+            }
+        }
+
+        impl core::fmt::Display for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                write!(f, $display, self.0)
+            }
+        }
+
+        #[cfg(feature = "defmt")]
+        impl defmt::Format for $name {
+            fn format(&self, f: defmt::Formatter<'_>) {
+                defmt::write!(f, $display, self.0)
+            }
+        }
+
+        impl core::fmt::LowerHex for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                core::fmt::LowerHex::fmt(&self.0, f)
+            }
+        }
+
+        impl core::fmt::UpperHex for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                core::fmt::UpperHex::fmt(&self.0, f)
+            }
+        }
+
+        impl ethercrab_wire::EtherCrabWireWrite for $name {
+            fn pack_to_slice_unchecked<'buf>(&self, buf: &'buf mut [u8]) -> &'buf [u8] {
+                self.0.pack_to_slice_unchecked(buf)
+            }
+
+            fn packed_len(&self) -> usize {
+                self.0.packed_len()
+            }
+        }
+
+        impl ethercrab_wire::EtherCrabWireRead for $name {
+            fn unpack_from_slice(buf: &[u8]) -> Result<Self, ethercrab_wire::WireError> {
+                <$inner>::unpack_from_slice(buf).map(Self)
+            }
+        }
+
+        impl ethercrab_wire::EtherCrabWireSized for $name {
+            const PACKED_LEN: usize = <$inner>::PACKED_LEN;
+
+            type Buffer = <$inner as ethercrab_wire::EtherCrabWireSized>::Buffer;
+
+            fn buffer() -> Self::Buffer {
+                <$inner>::buffer()
+            }
+        }
+
+        impl ethercrab_wire::EtherCrabWireWriteSized for $name {
+            fn pack(&self) -> Self::Buffer {
+                self.0.pack()
+            }
+        }
+    };
+}
+
+address!(
+    /// A SubDevice's configured station address, assigned by EtherCrab during initialisation.
+    ///
+    /// Used with `FPRD`, `FPWR` and `FRMW` commands, e.g. [`Command::fprd`](crate::Command::fprd).
+    ConfiguredAddress(u16),
+    "{:#06x}"
+);
+
+address!(
+    /// A SubDevice's station alias, read from its EEPROM and usually set by e.g. a rotary switch
+    /// on the device.
+    AliasAddress(u16),
+    "{:#06x}"
+);
+
+address!(
+    /// A SubDevice's position in the network, starting from 0 for the SubDevice closest to the
+    /// MainDevice.
+    ///
+    /// Used with `APRD` and `APWR` commands, e.g. [`Command::aprd`](crate::Command::aprd). The
+    /// value sent on the wire is calculated from the position by the command.
+    AutoIncrementAddress(u16),
+    "{}"
+);
+
+address!(
+    /// An address in the 32 bit logical address space that FMMUs map SubDevice process data into.
+    ///
+    /// Used with `LRD`, `LWR` and `LRW` commands, e.g. [`Command::lrw`](crate::Command::lrw).
+    LogicalAddress(u32),
+    "{:#010x}"
+);
+
+impl LogicalAddress {
+    /// The address `bytes` after this one.
+    pub(crate) const fn offset(self, bytes: u32) -> Self {
+        Self(self.0 + bytes)
+    }
+}
+
+impl ConfiguredAddress {
+    /// The address EtherCrab assigns to the SubDevice at the given position in the network.
+    pub(crate) const fn from_index(index: u16) -> Self {
+        Self(BASE_SUBDEVICE_ADDRESS.wrapping_add(index))
+    }
+
+    /// The position in the network of the SubDevice EtherCrab assigned this address to, if it is
+    /// in the range EtherCrab assigns addresses from.
+    pub(crate) fn index(self) -> Option<u16> {
+        self.0.checked_sub(BASE_SUBDEVICE_ADDRESS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        assert_eq!(
+            format!("{:?}", ConfiguredAddress::new(0x1001)),
+            "ConfiguredAddress(0x1001)"
+        );
+        assert_eq!(format!("{}", AliasAddress::new(0x0a)), "0x000a");
+        assert_eq!(
+            format!("{:?}", AutoIncrementAddress::new(3)),
+            "AutoIncrementAddress(3)"
+        );
+        assert_eq!(format!("{}", LogicalAddress::new(0x1000)), "0x00001000");
+        assert_eq!(format!("{:#06x}", ConfiguredAddress::new(0x1001)), "0x1001");
+    }
+
+    #[test]
+    fn index() {
+        assert_eq!(
+            ConfiguredAddress::from_index(2),
+            ConfiguredAddress::new(0x1002)
+        );
+        assert_eq!(ConfiguredAddress::new(0x1002).index(), Some(2));
+        assert_eq!(ConfiguredAddress::new(0x0002).index(), None);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::subdevice_state::SubDeviceState;
 
-/// The AL control/status word for an individual SubDevice.
+/// The AL control/status word for an individual SubDevice, registers `0x0120` and `0x0130`.
 ///
 /// Defined in ETG1000.6 Table 9 - AL Control Description.
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ethercrab_wire::EtherCrabWireReadWrite)]
@@ -19,6 +19,7 @@ pub struct AlControl {
 }
 
 impl AlControl {
+    /// Request a state.
     pub fn new(state: SubDeviceState) -> Self {
         Self {
             state,
@@ -27,6 +28,16 @@ impl AlControl {
         }
     }
 
+    /// Request a state while acknowledging any error indicated by the SubDevice.
+    pub fn acknowledge(state: SubDeviceState) -> Self {
+        Self {
+            state,
+            error: true,
+            ..Default::default()
+        }
+    }
+
+    /// Request INIT and acknowledge any error.
     pub fn reset() -> Self {
         Self {
             state: SubDeviceState::Init,
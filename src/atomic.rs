@@ -0,0 +1,17 @@
+//! Atomic types used throughout EtherCrab.
+//!
+//! With the `critical-section` feature enabled, these are provided by `portable-atomic`, which
+//! falls back to critical sections on targets without compare-and-swap instructions, e.g.
+//! `thumbv6m-none-eabi`.
+
+#[cfg(not(feature = "critical-section"))]
+#[allow(unused_imports)]
+pub(crate) use core::sync::atomic::{
+    AtomicBool, AtomicPtr, AtomicU16, AtomicU32, AtomicU8, AtomicUsize, Ordering,
+};
+
+#[cfg(feature = "critical-section")]
+#[allow(unused_imports)]
+pub(crate) use portable_atomic::{
+    AtomicBool, AtomicPtr, AtomicU16, AtomicU32, AtomicU8, AtomicUsize, Ordering,
+};
@@ -0,0 +1,405 @@
+//! Blocking wrappers around EtherCrab's `async` API, for applications that don't use an async
+//! runtime, e.g. existing control software or C FFI layers.
+//!
+//! [`BlockingMainDevice`] wraps a [`MainDevice`] and runs initialisation, state transitions, SDO
+//! reads and writes and process data exchanges to completion on the calling thread. How frames are
+//! sent and received while a call blocks is decided by its [`Driver`]:
+//!
+//! - [`DeviceDriver`] sends and receives frames with a [`RawEthernetDevice`] on the calling thread,
+//!   so no other thread or interrupt is needed. This works on `no_std` targets too.
+//! - `ThreadDriver` (requires `std`) parks the calling thread while a TX/RX task running on another
+//!   thread, e.g. `std::tx_rx_task`, sends and receives frames.
+//!
+//! Anything not wrapped by a method can be run with [`BlockingMainDevice::block_on`].
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use ethercrab::{
+//!     blocking::{BlockingMainDevice, ThreadDriver},
+//!     std::{ethercat_now, tx_rx_task},
+//!     MainDevice, MainDeviceConfig, PduStorage, Timeouts,
+//! };
+//!
+//! static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+//!
+//! let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+//!
+//! let tx_rx = tx_rx_task("eth0", tx, rx).expect("spawn TX/RX task");
+//!
+//! std::thread::spawn(move || futures_lite::future::block_on(tx_rx));
+//!
+//! let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+//!
+//! let mut maindevice = BlockingMainDevice::new(&maindevice, ThreadDriver);
+//!
+//! let mut group = maindevice
+//!     .init_single_group::<16, 64>(ethercat_now)
+//!     .expect("Init");
+//!
+//! for subdevice in group.iter(maindevice.maindevice()) {
+//!     let serial = maindevice.sdo_read::<u32, _>(&subdevice, 0x1018, 4).expect("Read serial");
+//!
+//!     println!("{} serial number {}", subdevice.name(), serial);
+//! }
+//!
+//! let group = maindevice.into_op(group).expect("PRE-OP -> OP");
+//!
+//! loop {
+//!     maindevice.tx_rx(&group).expect("TX/RX");
+//!
+//!     // Process inputs and outputs
+//!
+//!     std::thread::sleep(std::time::Duration::from_millis(5));
+//! }
+//! ```
+
+use crate::{
+    error::Error,
+    subdevice_group::{HasPdi, Op, PreOp, SafeOp},
+    MainDevice, PduRx, PduTx, RawEthernetDevice, SubDevice, SubDeviceGroup, SubDeviceGroupHandle,
+    SubDeviceRef, SubIndex,
+};
+use core::{future::Future, ops::Deref};
+use ethercrab_wire::{EtherCrabWireReadSized, EtherCrabWireWrite};
+
+/// Runs EtherCrab futures to completion on the current thread, making sure their frames are sent
+/// and received.
+pub trait Driver {
+    /// Run `future` to completion, blocking the current thread.
+    ///
+    /// Errors from `future` are returned in the `Ok` variant. An `Err` is only returned if frames
+    /// could not be sent or received.
+    fn block_on<F>(&mut self, future: F) -> Result<F::Output, Error>
+    where
+        F: Future;
+}
+
+/// A [`Driver`] that sends and receives frames with a [`RawEthernetDevice`] on the current thread.
+///
+/// The device and the future are polled in a busy loop with
+/// [`send_receive_blocking`](crate::send_receive_blocking), so the calling thread is kept busy for
+/// as long as a call blocks.
+pub struct DeviceDriver<'sto, D> {
+    device: D,
+    pdu_tx: PduTx<'sto>,
+    pdu_rx: PduRx<'sto>,
+}
+
+impl<'sto, D> DeviceDriver<'sto, D>
+where
+    D: RawEthernetDevice,
+{
+    /// Create a driver that sends and receives frames queued in `pdu_tx` and `pdu_rx` with
+    /// `device`.
+    pub fn new(device: D, pdu_tx: PduTx<'sto>, pdu_rx: PduRx<'sto>) -> Self {
+        Self {
+            device,
+            pdu_tx,
+            pdu_rx,
+        }
+    }
+
+    /// Get the network device back, along with the PDU loop's TX and RX halves.
+    pub fn into_inner(self) -> (D, PduTx<'sto>, PduRx<'sto>) {
+        (self.device, self.pdu_tx, self.pdu_rx)
+    }
+}
+
+impl<D> Driver for DeviceDriver<'_, D>
+where
+    D: RawEthernetDevice,
+{
+    fn block_on<F>(&mut self, future: F) -> Result<F::Output, Error>
+    where
+        F: Future,
+    {
+        crate::send_receive_blocking(&mut self.device, &mut self.pdu_tx, &mut self.pdu_rx, future)
+    }
+}
+
+/// A [`Driver`] that parks the current thread until the future is woken by a TX/RX task running on
+/// another thread, e.g. [`tx_rx_task`](crate::std::tx_rx_task).
+///
+/// With the `tokio` feature enabled, EtherCrab's timers require a `tokio` runtime, so calls must be
+/// made from a thread that has entered one with `Runtime::enter`.
+#[cfg(feature = "std")]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ThreadDriver;
+
+#[cfg(feature = "std")]
+impl Driver for ThreadDriver {
+    fn block_on<F>(&mut self, future: F) -> Result<F::Output, Error>
+    where
+        F: Future,
+    {
+        Ok(futures_lite::future::block_on(future))
+    }
+}
+
+/// A [`MainDevice`] with blocking methods, run by a [`Driver`].
+///
+/// See the [module documentation](crate::blocking) for an example.
+#[derive(Debug)]
+pub struct BlockingMainDevice<'a, D> {
+    maindevice: &'a MainDevice<'a>,
+    driver: D,
+}
+
+impl<'a, D> BlockingMainDevice<'a, D>
+where
+    D: Driver,
+{
+    /// Wrap `maindevice`, running each call with `driver`.
+    pub fn new(maindevice: &'a MainDevice<'a>, driver: D) -> Self {
+        Self { maindevice, driver }
+    }
+
+    /// Get the wrapped [`MainDevice`], e.g. to borrow SubDevices from a group.
+    pub fn maindevice(&self) -> &'a MainDevice<'a> {
+        self.maindevice
+    }
+
+    /// Get the driver back.
+    pub fn into_driver(self) -> D {
+        self.driver
+    }
+
+    /// Run any EtherCrab future to completion.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{blocking::{BlockingMainDevice, ThreadDriver}, MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+    /// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// let mut maindevice = BlockingMainDevice::new(&maindevice, ThreadDriver);
+    ///
+    /// let subdevices = maindevice.block_on(maindevice.maindevice().scan::<16>())??;
+    /// # Ok::<(), ethercrab::error::Error>(())
+    /// ```
+    pub fn block_on<F>(&mut self, future: F) -> Result<F::Output, Error>
+    where
+        F: Future,
+    {
+        self.driver.block_on(future)
+    }
+
+    /// Detect SubDevices, set their configured station addresses, assign to groups and configure
+    /// SubDevice mailboxes.
+    ///
+    /// See [`MainDevice::init`] for details.
+    pub fn init<const MAX_SUBDEVICES: usize, G>(
+        &mut self,
+        now: impl Fn() -> u64 + Copy,
+        group_filter: impl for<'g> FnMut(
+            &'g G,
+            &SubDevice,
+        ) -> Result<&'g dyn SubDeviceGroupHandle, Error>,
+    ) -> Result<G, Error>
+    where
+        G: Default,
+    {
+        self.block_on(self.maindevice.init::<MAX_SUBDEVICES, G>(now, group_filter))?
+    }
+
+    /// A convenience method to allow the quicker creation of a single group containing all
+    /// discovered SubDevices.
+    ///
+    /// See [`MainDevice::init_single_group`] for details.
+    pub fn init_single_group<const MAX_SUBDEVICES: usize, const MAX_PDI: usize>(
+        &mut self,
+        now: impl Fn() -> u64 + Copy,
+    ) -> Result<SubDeviceGroup<MAX_SUBDEVICES, MAX_PDI, PreOp>, Error> {
+        self.block_on(
+            self.maindevice
+                .init_single_group::<MAX_SUBDEVICES, MAX_PDI>(now),
+        )?
+    }
+
+    /// Transition a group from PRE-OP to SAFE-OP.
+    pub fn into_safe_op<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>(
+        &mut self,
+        group: SubDeviceGroup<MAX_SUBDEVICES, MAX_PDI, PreOp, DC>,
+    ) -> Result<SubDeviceGroup<MAX_SUBDEVICES, MAX_PDI, SafeOp, DC>, Error> {
+        self.block_on(group.into_safe_op(self.maindevice))?
+    }
+
+    /// Transition a group from PRE-OP to SAFE-OP, then to OP.
+    pub fn into_op<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>(
+        &mut self,
+        group: SubDeviceGroup<MAX_SUBDEVICES, MAX_PDI, PreOp, DC>,
+    ) -> Result<SubDeviceGroup<MAX_SUBDEVICES, MAX_PDI, Op, DC>, Error> {
+        self.block_on(group.into_op(self.maindevice))?
+    }
+
+    /// Send the group's outputs and receive its inputs, returning the working counter.
+    ///
+    /// See [`SubDeviceGroup::tx_rx`] for details.
+    pub fn tx_rx<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S, DC>(
+        &mut self,
+        group: &SubDeviceGroup<MAX_SUBDEVICES, MAX_PDI, S, DC>,
+    ) -> Result<u16, Error>
+    where
+        S: HasPdi,
+    {
+        self.block_on(group.tx_rx(self.maindevice))?
+    }
+
+    /// Read a value from an SDO of the given SubDevice.
+    ///
+    /// See [`SubDeviceRef::sdo_read`] for details.
+    pub fn sdo_read<T, S>(
+        &mut self,
+        subdevice: &SubDeviceRef<'_, S>,
+        index: u16,
+        sub_index: impl Into<SubIndex>,
+    ) -> Result<T, Error>
+    where
+        T: EtherCrabWireReadSized,
+        S: Deref<Target = SubDevice>,
+    {
+        self.block_on(subdevice.sdo_read(index, sub_index))?
+    }
+
+    /// Write a value to an SDO of the given SubDevice.
+    ///
+    /// See [`SubDeviceRef::sdo_write`] for details.
+    pub fn sdo_write<T, S>(
+        &mut self,
+        subdevice: &SubDeviceRef<'_, S>,
+        index: u16,
+        sub_index: impl Into<SubIndex>,
+        value: T,
+    ) -> Result<(), Error>
+    where
+        T: EtherCrabWireWrite,
+        S: Deref<Target = SubDevice>,
+    {
+        self.block_on(subdevice.sdo_write(index, sub_index, value))?
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod tests {
+    use super::*;
+    use crate::{
+        sim::{SimNetwork, SimSubDevice},
+        MainDeviceConfig, PduStorage, SubDeviceIdentity, Timeouts,
+    };
+
+    fn identity(product_id: u32) -> SubDeviceIdentity {
+        SubDeviceIdentity {
+            vendor_id: 0x2,
+            product_id,
+            revision: 0x0011_0000,
+            serial: 0,
+        }
+    }
+
+    #[test]
+    fn device_driver() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(256) }> = PduStorage::new();
+
+        let network = SimNetwork::new([
+            SimSubDevice::new("EL1004", identity(0x03ec_3052)).with_inputs(1),
+            SimSubDevice::new("Drive", identity(0x1234))
+                .with_outputs(2)
+                .with_coe()
+                .with_sdo(0x2000, 0, 0x1234u16),
+        ]);
+
+        network.with_subdevice(0, |subdevice| subdevice.inputs_mut()[0] = 0x5a);
+
+        let (tx, rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let maindevice =
+            MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+
+        let mut maindevice =
+            BlockingMainDevice::new(&maindevice, DeviceDriver::new(network.clone(), tx, rx));
+
+        let group = maindevice
+            .init_single_group::<4, 16>(crate::sim::ethercat_now)
+            .expect("init");
+
+        {
+            let drive = group.subdevice(maindevice.maindevice(), 1).unwrap();
+
+            assert_eq!(maindevice.sdo_read::<u16, _>(&drive, 0x2000, 0), Ok(0x1234));
+            assert_eq!(maindevice.sdo_write(&drive, 0x2000, 0, 0xabcdu16), Ok(()));
+            assert_eq!(maindevice.sdo_read::<u16, _>(&drive, 0x2000, 0), Ok(0xabcd));
+        }
+
+        let group = maindevice.into_op(group).expect("PRE-OP -> OP");
+
+        // Both SubDevices read and write process data
+        assert_eq!(maindevice.tx_rx(&group), Ok(3));
+
+        assert_eq!(
+            group
+                .subdevice(maindevice.maindevice(), 0)
+                .unwrap()
+                .inputs_raw()[0],
+            0x5a
+        );
+    }
+
+    #[test]
+    fn thread_driver() {
+        let mut driver = ThreadDriver;
+
+        assert_eq!(driver.block_on(async { 1 + 1 }), Ok(2));
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        send_receive_blocking, sim::fixture::*, MainDevice, MainDeviceConfig, PduStorage, Timeouts,
+    };
+    use core::time::Duration;
+
+    #[test]
+    fn blocking() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let mut network = network();
+
+        network.with_subdevice(2, |subdevice| subdevice.inputs_mut()[0] = 0x5a);
+
+        let (mut tx, mut rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let maindevice = MainDevice::new(
+            pdu_loop,
+            Timeouts {
+                pdu: Duration::from_millis(100),
+                state_transition: Duration::from_millis(500),
+                mailbox_response: Duration::from_millis(500),
+                ..Timeouts::default()
+            },
+            MainDeviceConfig::default(),
+        );
+
+        let group = send_receive_blocking(&mut network, &mut tx, &mut rx, async {
+            maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await?
+                .into_op(&maindevice)
+                .await
+        })
+        .expect("device")
+        .expect("init");
+
+        let wkc = send_receive_blocking(&mut network, &mut tx, &mut rx, group.tx_rx(&maindevice))
+            .expect("device")
+            .expect("TX/RX");
+
+        assert_eq!(wkc, 2 + 1 + (1 + 2));
+        assert_eq!(
+            group.subdevice(&maindevice, 2).unwrap().inputs_raw(),
+            &[0x5a]
+        );
+    }
+}
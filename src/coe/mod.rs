@@ -78,6 +78,39 @@ pub struct SegmentSdoHeader {
     command: CoeCommand,
 }
 
+/// SDO Information service OpCode.
+///
+/// Defined in ETG1000.6 Section 5.6.3.2 Table 43 – SDO Information.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ethercrab_wire::EtherCrabWireReadWrite)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[wire(bits = 7)]
+#[repr(u8)]
+pub enum SdoInfoOpCode {
+    GetOdListRequest = 0x01,
+    GetOdListResponse = 0x02,
+    GetObjectDescriptionRequest = 0x03,
+    GetObjectDescriptionResponse = 0x04,
+    GetEntryDescriptionRequest = 0x05,
+    GetEntryDescriptionResponse = 0x06,
+    ErrorRequest = 0x07,
+}
+
+/// Defined in ETG1000.6 Section 5.6.3.2
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ethercrab_wire::EtherCrabWireReadWrite)]
+#[wire(bytes = 4)]
+pub struct SdoInfoHeader {
+    #[wire(bits = 7)]
+    pub op_code: SdoInfoOpCode,
+
+    /// More fragments of the response follow this one.
+    #[wire(bits = 1, post_skip = 8)]
+    pub incomplete: bool,
+
+    /// Number of fragments of the response still to be sent after this one.
+    #[wire(bytes = 2)]
+    pub fragments_left: u16,
+}
+
 /// Subindex access.
 #[derive(Copy, Clone, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -131,4 +164,16 @@ mod tests {
             Ok(CoeService::SdoRequest)
         );
     }
+
+    #[test]
+    fn sdo_info_header() {
+        let header = SdoInfoHeader {
+            op_code: SdoInfoOpCode::GetOdListResponse,
+            incomplete: true,
+            fragments_left: 3,
+        };
+
+        assert_eq!(header.pack(), [0x82, 0x00, 0x03, 0x00]);
+        assert_eq!(SdoInfoHeader::unpack_from_slice(&header.pack()), Ok(header));
+    }
 }
@@ -1,4 +1,4 @@
-use super::{CoeService, InitSdoHeader, SegmentSdoHeader, SubIndex};
+use super::{CoeService, InitSdoHeader, SdoInfoHeader, SdoInfoOpCode, SegmentSdoHeader, SubIndex};
 use crate::mailbox::{MailboxHeader, MailboxType, Priority};
 use core::fmt::Display;
 
@@ -81,6 +81,27 @@ impl Display for SdoSegmented {
     }
 }
 
+/// An SDO Information request.
+///
+/// See ETG1000.6 Section 5.6.3 SDO Information.
+#[derive(Debug, Copy, Clone, PartialEq, ethercrab_wire::EtherCrabWireReadWrite)]
+#[wire(bytes = 16)]
+pub struct SdoInfo {
+    #[wire(bytes = 8)]
+    pub header: MailboxHeader,
+    #[wire(bytes = 4)]
+    pub info_header: SdoInfoHeader,
+    /// Request data, e.g. the index of the object to describe. Unused bytes are zero.
+    #[wire(bytes = 4)]
+    pub data: [u8; 4],
+}
+
+impl Display for SdoInfo {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "SDO info({:?})", self.info_header.op_code)
+    }
+}
+
 /// Must be implemented for any type used to send a CoE service.
 pub trait CoeServiceRequest:
     ethercrab_wire::EtherCrabWireReadWrite + ethercrab_wire::EtherCrabWireWriteSized
@@ -196,11 +217,67 @@ pub fn upload(counter: u8, index: u16, access: SubIndex) -> SdoNormal {
     }
 }
 
+fn sdo_info(counter: u8, op_code: SdoInfoOpCode, data: [u8; 4], len: u16) -> SdoInfo {
+    SdoInfo {
+        header: MailboxHeader {
+            // CoE header, SDO Information header and request data
+            length: 0x06 + len,
+            // address: 0x0000,
+            priority: Priority::Lowest,
+            mailbox_type: MailboxType::Coe,
+            counter,
+            service: CoeService::SdoInformation,
+        },
+        info_header: SdoInfoHeader {
+            op_code,
+            incomplete: false,
+            fragments_left: 0,
+        },
+        data,
+    }
+}
+
+/// Request the list of all object indices in the object dictionary.
+pub fn get_od_list(counter: u8) -> SdoInfo {
+    // List type 0x01: all objects
+    sdo_info(
+        counter,
+        SdoInfoOpCode::GetOdListRequest,
+        [0x01, 0x00, 0, 0],
+        2,
+    )
+}
+
+pub fn get_object_description(counter: u8, index: u16) -> SdoInfo {
+    let [index_lo, index_hi] = index.to_le_bytes();
+
+    sdo_info(
+        counter,
+        SdoInfoOpCode::GetObjectDescriptionRequest,
+        [index_lo, index_hi, 0, 0],
+        2,
+    )
+}
+
+pub fn get_entry_description(counter: u8, index: u16, sub_index: u8) -> SdoInfo {
+    let [index_lo, index_hi] = index.to_le_bytes();
+
+    // Value info is zero as only the description and name are used
+    sdo_info(
+        counter,
+        SdoInfoOpCode::GetEntryDescriptionRequest,
+        [index_lo, index_hi, sub_index, 0x00],
+        4,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::error::CoeAbortCode;
-    use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized, EtherCrabWireWrite};
+    use ethercrab_wire::{
+        EtherCrabWireRead, EtherCrabWireSized, EtherCrabWireWrite, EtherCrabWireWriteSized,
+    };
 
     #[test]
     fn decode_sdo_response_normal() {
@@ -397,4 +474,17 @@ mod tests {
 
         pretty_assertions::assert_eq!(parsed, Ok(expected));
     }
+
+    #[test]
+    fn entry_description_request() {
+        let request = get_entry_description(3, 0x1018, 2);
+
+        pretty_assertions::assert_eq!(
+            request.pack(),
+            [
+                0x0a, 0x00, 0x00, 0x00, 0x00, 0x33, 0x00, 0x80, 0x05, 0x00, 0x00, 0x00, 0x18, 0x10,
+                0x02, 0x00
+            ]
+        );
+    }
 }
@@ -3,6 +3,7 @@
 mod reads;
 mod writes;
 
+use crate::{AutoIncrementAddress, ConfiguredAddress, LogicalAddress};
 use ethercrab_wire::{EtherCrabWireSized, EtherCrabWireWriteSized};
 
 pub use reads::{Reads, WrappedRead};
@@ -39,13 +40,13 @@ const LRW: u8 = 0x0c;
 ///
 /// ```rust
 /// # use ethercrab::{ std::tx_rx_task, MainDevice, MainDeviceConfig, PduStorage, Timeouts };
-/// use ethercrab::{ Command, RegisterAddress };
+/// use ethercrab::{ Command, ConfiguredAddress, RegisterAddress };
 /// # static PDU_STORAGE: PduStorage<16, 1100> = PduStorage::new();
 /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
 /// let maindevice = /* ... */
 /// # MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
 ///
-/// let configured_address = 0x1001u16;
+/// let configured_address = ConfiguredAddress::new(0x1001);
 ///
 /// # async {
 /// let value = Command::fprd(configured_address, RegisterAddress::SiiData.into())
@@ -59,13 +60,13 @@ const LRW: u8 = 0x0c;
 ///
 /// ```rust
 /// # use ethercrab::{ std::tx_rx_task, MainDevice, MainDeviceConfig, PduStorage, Timeouts };
-/// use ethercrab::{ Command, RegisterAddress };
+/// use ethercrab::{ Command, ConfiguredAddress, RegisterAddress };
 /// # static PDU_STORAGE: PduStorage<16, 1100> = PduStorage::new();
 /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
 /// let maindevice = /* ... */
 /// # MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
 ///
-/// let configured_address = 0x1001u16;
+/// let configured_address = ConfiguredAddress::new(0x1001);
 /// let register = 0x1234u16;
 ///
 /// let data = [ 0xaau8, 0xbb, 0xcc, 0xdd ];
@@ -186,27 +187,33 @@ impl Command {
     }
 
     /// FPRD.
-    pub fn fprd(address: u16, register: u16) -> WrappedRead {
-        WrappedRead::new(Reads::Fprd { address, register })
+    pub fn fprd(address: ConfiguredAddress, register: u16) -> WrappedRead {
+        WrappedRead::new(Reads::Fprd {
+            address: address.get(),
+            register,
+        })
     }
 
     /// FPWR.
-    pub fn fpwr(address: u16, register: u16) -> WrappedWrite {
-        WrappedWrite::new(Writes::Fpwr { address, register })
+    pub fn fpwr(address: ConfiguredAddress, register: u16) -> WrappedWrite {
+        WrappedWrite::new(Writes::Fpwr {
+            address: address.get(),
+            register,
+        })
     }
 
     /// APRD.
-    pub fn aprd(address: u16, register: u16) -> WrappedRead {
+    pub fn aprd(address: AutoIncrementAddress, register: u16) -> WrappedRead {
         WrappedRead::new(Reads::Aprd {
-            address: 0u16.wrapping_sub(address),
+            address: 0u16.wrapping_sub(address.get()),
             register,
         })
     }
 
     /// APWR.
-    pub fn apwr(address: u16, register: u16) -> WrappedWrite {
+    pub fn apwr(address: AutoIncrementAddress, register: u16) -> WrappedWrite {
         WrappedWrite::new(Writes::Apwr {
-            address: 0u16.wrapping_sub(address),
+            address: 0u16.wrapping_sub(address.get()),
             register,
         })
     }
@@ -215,18 +222,32 @@ impl Command {
     ///
     /// This can be used to distribute a value from one SubDevice to all others on the network, e.g.
     /// with distributed clocks.
-    pub fn frmw(address: u16, register: u16) -> WrappedRead {
-        WrappedRead::new(Reads::Frmw { address, register })
+    pub fn frmw(address: ConfiguredAddress, register: u16) -> WrappedRead {
+        WrappedRead::new(Reads::Frmw {
+            address: address.get(),
+            register,
+        })
     }
 
     /// Logical Read Write (LRW), used mainly for sending and receiving PDI.
-    pub fn lrw(address: u32) -> WrappedWrite {
-        WrappedWrite::new(Writes::Lrw { address })
+    pub fn lrw(address: LogicalAddress) -> WrappedWrite {
+        WrappedWrite::new(Writes::Lrw {
+            address: address.get(),
+        })
+    }
+
+    /// Logical Read (LRD).
+    pub fn lrd(address: LogicalAddress) -> WrappedRead {
+        WrappedRead::new(Reads::Lrd {
+            address: address.get(),
+        })
     }
 
     /// Logical Write (LWR).
-    pub fn lwr(address: u32) -> WrappedWrite {
-        WrappedWrite::new(Writes::Lwr { address })
+    pub fn lwr(address: LogicalAddress) -> WrappedWrite {
+        WrappedWrite::new(Writes::Lwr {
+            address: address.get(),
+        })
     }
 
     /// Get just the command code for a command.
@@ -253,6 +274,34 @@ impl Command {
     }
 }
 
+impl Command {
+    /// Decode a command from its command code and the raw address field of a PDU header.
+    ///
+    /// Returns `None` for command codes that EtherCrab doesn't send, e.g. `ARMW`.
+    pub(crate) fn parse(code: u8, raw: [u8; 4]) -> Option<Self> {
+        let address = u16::from_le_bytes([raw[0], raw[1]]);
+        let register = u16::from_le_bytes([raw[2], raw[3]]);
+        let logical = u32::from_le_bytes(raw);
+
+        let command = match code {
+            NOP => Self::Nop,
+            APRD => Reads::Aprd { address, register }.into(),
+            FPRD => Reads::Fprd { address, register }.into(),
+            BRD => Reads::Brd { address, register }.into(),
+            LRD => Reads::Lrd { address: logical }.into(),
+            FRMW => Reads::Frmw { address, register }.into(),
+            BWR => Writes::Bwr { address, register }.into(),
+            APWR => Writes::Apwr { address, register }.into(),
+            FPWR => Writes::Fpwr { address, register }.into(),
+            LWR => Writes::Lwr { address: logical }.into(),
+            LRW => Writes::Lrw { address: logical }.into(),
+            _ => return None,
+        };
+
+        Some(command)
+    }
+}
+
 impl From<Reads> for Command {
     fn from(value: Reads) -> Self {
         Self::Read(value)
@@ -283,7 +332,7 @@ mod tests {
 
     #[test]
     fn pack_command_data() {
-        let address = 0x1004;
+        let address = ConfiguredAddress::new(0x1004);
         let register = 0x0980;
 
         // Two little endian u16s
@@ -294,4 +343,64 @@ mod tests {
             expected
         );
     }
+
+    #[test]
+    fn parse_round_trip() {
+        let commands: [Command; 4] = [
+            Command::fpwr(ConfiguredAddress::new(0x1004), 0x0980).into(),
+            Command::aprd(AutoIncrementAddress::new(0xfffe), 0x0130).into(),
+            Command::lrw(LogicalAddress::new(0x0001_0000)).into(),
+            Command::Nop,
+        ];
+
+        for command in commands {
+            assert_eq!(
+                Command::parse(command.code(), command.pack()),
+                Some(command)
+            );
+        }
+
+        assert_eq!(Command::parse(0x03, [0; 4]), None);
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{sim::fixture::*, AutoIncrementAddress, Command, PduStorage};
+
+    #[test]
+    fn chunked_read_write() {
+        // Frames much smaller than the data so it's split across many PDUs
+        static STORAGE: PduStorage<4, { PduStorage::element_size(64) }> = PduStorage::new();
+
+        let network = network();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let data = (0..500u16).map(|i| i as u8).collect::<Vec<_>>();
+
+            Command::apwr(AutoIncrementAddress::new(1), 0x2000)
+                .with_wkc(1)
+                .send_slice(&maindevice, &data)
+                .await
+                .expect("write");
+
+            let mut buf = vec![0u8; data.len()];
+
+            Command::aprd(AutoIncrementAddress::new(1), 0x2000)
+                .with_wkc(1)
+                .receive_into(&maindevice, &mut buf)
+                .await
+                .expect("read");
+
+            assert_eq!(buf, data);
+
+            // Each chunk lands at the right address
+            assert_eq!(
+                Command::aprd(AutoIncrementAddress::new(1), 0x2000 + 300)
+                    .receive::<[u8; 4]>(&maindevice)
+                    .await,
+                Ok([44, 45, 46, 47])
+            );
+        });
+    }
 }
@@ -50,10 +50,36 @@ impl Reads {
     pub fn wrap(self) -> WrappedRead {
         WrappedRead::new(self)
     }
+
+    /// Move the memory location accessed by this command forward by `bytes`.
+    pub(crate) fn offset(self, bytes: u16) -> Self {
+        match self {
+            Reads::Aprd { address, register } => Reads::Aprd {
+                address,
+                register: register.wrapping_add(bytes),
+            },
+            Reads::Fprd { address, register } => Reads::Fprd {
+                address,
+                register: register.wrapping_add(bytes),
+            },
+            Reads::Brd { address, register } => Reads::Brd {
+                address,
+                register: register.wrapping_add(bytes),
+            },
+            Reads::Lrd { address } => Reads::Lrd {
+                address: address.wrapping_add(u32::from(bytes)),
+            },
+            Reads::Frmw { address, register } => Reads::Frmw {
+                address,
+                register: register.wrapping_add(bytes),
+            },
+        }
+    }
 }
 
 /// A wrapped version of a [`Reads`] exposing a builder API used to send/receive data over the wire.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct WrappedRead {
     /// EtherCAT command.
     pub command: Reads,
@@ -94,7 +120,8 @@ impl WrappedRead {
     {
         self.common(maindevice, T::PACKED_LEN as u16)
             .await?
-            .maybe_wkc(self.wkc)
+            .maybe_wkc(self.wkc, self.command.into())
+            .inspect_err(|e| maindevice.pdu_loop.record_error(e))
             .and_then(|data| Ok(T::unpack_from_slice(&data)?))
     }
 
@@ -104,7 +131,58 @@ impl WrappedRead {
         maindevice: &'maindevice MainDevice<'maindevice>,
         len: u16,
     ) -> Result<ReceivedPdu<'maindevice>, Error> {
-        self.common(maindevice, len).await?.maybe_wkc(self.wkc)
+        self.common(maindevice, len)
+            .await?
+            .maybe_wkc(self.wkc, self.command.into())
+            .inspect_err(|e| maindevice.pdu_loop.record_error(e))
+    }
+
+    /// Read `buf.len()` bytes into `buf`.
+    ///
+    /// Reads longer than can fit in a single frame are split into multiple PDUs, each reading the
+    /// next part of the SubDevice or logical memory. Response data is written directly into `buf`
+    /// from the network, so `buf` can be larger than the PDU storage frames. The expected working
+    /// counter is checked for every PDU.
+    ///
+    /// # Examples
+    ///
+    /// Read the first 4KiB of a SubDevice's process data RAM:
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{Command, ConfiguredAddress, MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+    /// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # async fn example() -> Result<(), ethercrab::error::Error> {
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// let mut ram = vec![0u8; 4096];
+    ///
+    /// Command::fprd(ConfiguredAddress::new(0x1000), 0x1000)
+    ///     .receive_into(&maindevice, &mut ram)
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn receive_into<'maindevice>(
+        self,
+        maindevice: &'maindevice MainDevice<'maindevice>,
+        buf: &mut [u8],
+    ) -> Result<(), Error> {
+        let chunk_len = maindevice.pdu_loop.max_pdu_data().max(1);
+
+        let mut offset = 0u16;
+
+        for chunk in buf.chunks_mut(chunk_len) {
+            let len = chunk.len() as u16;
+
+            maindevice
+                .single_pdu_direct_rx(self.command.offset(offset).into(), chunk)
+                .await?
+                .maybe_wkc(self.wkc, self.command.offset(offset).into())
+                .inspect_err(|e| maindevice.pdu_loop.record_error(e))?;
+
+            offset = offset.wrapping_add(len);
+        }
+
+        Ok(())
     }
 
     /// Receive only the working counter.
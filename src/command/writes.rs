@@ -43,9 +43,36 @@ pub enum Writes {
     },
 }
 
+impl Writes {
+    /// Move the memory location accessed by this command forward by `bytes`.
+    pub(crate) fn offset(self, bytes: u16) -> Self {
+        match self {
+            Writes::Bwr { address, register } => Writes::Bwr {
+                address,
+                register: register.wrapping_add(bytes),
+            },
+            Writes::Apwr { address, register } => Writes::Apwr {
+                address,
+                register: register.wrapping_add(bytes),
+            },
+            Writes::Fpwr { address, register } => Writes::Fpwr {
+                address,
+                register: register.wrapping_add(bytes),
+            },
+            Writes::Lwr { address } => Writes::Lwr {
+                address: address.wrapping_add(u32::from(bytes)),
+            },
+            Writes::Lrw { address } => Writes::Lrw {
+                address: address.wrapping_add(u32::from(bytes)),
+            },
+        }
+    }
+}
+
 /// A wrapped version of a [`Writes`] exposing a builder API used to send/receive data over the
 /// wire.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct WrappedWrite {
     /// EtherCAT command.
     pub command: Writes,
@@ -100,6 +127,33 @@ impl WrappedWrite {
         Ok(())
     }
 
+    /// Write all of `data`.
+    ///
+    /// Writes longer than can fit in a single frame are split into multiple PDUs, each writing the
+    /// next part of the SubDevice or logical memory. The expected working counter is checked for
+    /// every PDU. Any length set with [`with_len`](WrappedWrite::with_len) is ignored.
+    pub async fn send_slice<'maindevice>(
+        self,
+        maindevice: &'maindevice MainDevice<'maindevice>,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        let chunk_len = maindevice.pdu_loop.max_pdu_data().max(1);
+
+        let mut offset = 0u16;
+
+        for chunk in data.chunks(chunk_len) {
+            maindevice
+                .single_pdu(self.command.offset(offset).into(), chunk, None)
+                .await?
+                .maybe_wkc(self.wkc, self.command.offset(offset).into())
+                .inspect_err(|e| maindevice.pdu_loop.record_error(e))?;
+
+            offset = offset.wrapping_add(chunk.len() as u16);
+        }
+
+        Ok(())
+    }
+
     /// Send a value, returning the response returned from the network.
     pub async fn send_receive<'data, 'maindevice, T>(
         self,
@@ -111,7 +165,8 @@ impl WrappedWrite {
     {
         self.common(maindevice, value, None)
             .await?
-            .maybe_wkc(self.wkc)
+            .maybe_wkc(self.wkc, self.command.into())
+            .inspect_err(|e| maindevice.pdu_loop.record_error(e))
             .and_then(|data| Ok(T::unpack_from_slice(&data)?))
     }
 
@@ -123,7 +178,8 @@ impl WrappedWrite {
     ) -> Result<ReceivedPdu<'maindevice>, Error> {
         self.common(maindevice, value, None)
             .await?
-            .maybe_wkc(self.wkc)
+            .maybe_wkc(self.wkc, self.command.into())
+            .inspect_err(|e| maindevice.pdu_loop.record_error(e))
     }
 
     // Some manual monomorphisation
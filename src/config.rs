@@ -0,0 +1,838 @@
+//! Declarative network configuration, loaded from a TOML or YAML file.
+//!
+//! A [`NetworkConfig`] describes the SubDevices expected on the network in the order they are
+//! connected, which group each one belongs to, and how each should be configured in PRE-OP: PDO
+//! assignment and mapping, startup SDO writes and DC sync mode. Machine variants can then be
+//! described by data instead of code.
+//!
+//! Groups are still declared in code, as their sizes are compile time constants. The group names
+//! in the file are mapped to them in the closure passed to [`MainDevice::init`], using
+//! [`NetworkConfig::group_of`]. Once initialised, each group in PRE-OP is configured with
+//! [`NetworkConfig::configure_group`].
+//!
+//! Requires the `config` feature.
+//!
+//! # Examples
+//!
+//! ```toml
+//! [maindevice]
+//! retry_behaviour = { Count = 3 }
+//!
+//! # Optional DC settings for each group
+//! [groups.drives.dc]
+//! start_delay = { secs = 0, nanos = 100_000_000 }
+//! sync0_period = { secs = 0, nanos = 1_000_000 }
+//! sync0_shift = { secs = 0, nanos = 0 }
+//!
+//! # SubDevices in network order
+//! [[subdevices]]
+//! name = "EK1100"
+//! vendor_id = 0x2
+//! product_id = 0x044c2c52
+//! group = "io"
+//!
+//! [[subdevices]]
+//! name = "EL2004"
+//! vendor_id = 0x2
+//! product_id = 0x07d43052
+//! group = "io"
+//!
+//! [[subdevices]]
+//! vendor_id = 0x100000
+//! product_id = 0xc0108
+//! group = "drives"
+//! dc_sync = "Sync0"
+//! # Cyclic synchronous position mode
+//! sdo_writes = [{ index = 0x6060, i8 = 8 }]
+//! output_pdos = [
+//!     { index = 0x1600, entries = [
+//!         { index = 0x6040, bit_len = 16 },
+//!         { index = 0x607a, bit_len = 32 },
+//!     ] },
+//! ]
+//! input_pdos = [
+//!     { index = 0x1a00, entries = [
+//!         { index = 0x6041, bit_len = 16 },
+//!         { index = 0x6064, bit_len = 32 },
+//!     ] },
+//! ]
+//! ```
+//!
+//! ```rust,no_run
+//! use ethercrab::{
+//!     config::NetworkConfig, error::Error, std::ethercat_now, MainDevice, PduStorage,
+//!     SubDeviceGroup,
+//! };
+//!
+//! static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+//!
+//! #[derive(Default)]
+//! struct Groups {
+//!     io: SubDeviceGroup<8, 16>,
+//!     drives: SubDeviceGroup<4, 64>,
+//! }
+//!
+//! # async fn case() -> Result<(), Box<dyn std::error::Error>> {
+//! let config = NetworkConfig::load("machine.toml")?;
+//!
+//! let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+//!
+//! let maindevice = MainDevice::new(pdu_loop, config.timeouts, config.maindevice_config());
+//!
+//! let groups = maindevice
+//!     .init::<16, _>(ethercat_now, |groups: &Groups, subdevice| {
+//!         match config.group_of(subdevice)? {
+//!             "io" => Ok(&groups.io),
+//!             "drives" => Ok(&groups.drives),
+//!             _ => Err(Error::UnknownSubDevice),
+//!         }
+//!     })
+//!     .await?;
+//!
+//! config.configure_group(&maindevice, &groups.io).await?;
+//! config.configure_group(&maindevice, &groups.drives).await?;
+//!
+//! let io = groups.io.into_op(&maindevice).await?;
+//!
+//! let dc = config
+//!     .dc_configuration("drives")
+//!     .ok_or("drives group must have DC settings")?;
+//!
+//! let drives = groups.drives.configure_dc_sync(&maindevice, dc).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::Error, fmt, subdevice_group::DcConfiguration, subdevice_group::PreOp, DcSync,
+    MainDevice, MainDeviceConfig, SubDevice, SubDeviceGroup, SubDeviceIdentity, SubDeviceRef,
+    Timeouts,
+};
+use core::ops::DerefMut;
+use std::{collections::BTreeMap, path::Path, string::String, vec::Vec};
+
+/// PDO assignment object for outputs (RxPDOs).
+const OUTPUT_ASSIGNMENT: u16 = 0x1c12;
+
+/// PDO assignment object for inputs (TxPDOs).
+const INPUT_ASSIGNMENT: u16 = 0x1c13;
+
+/// A description of an EtherCAT network and how to configure it.
+///
+/// See the [module documentation](crate::config) for an example.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// MainDevice options.
+    ///
+    /// Use [`maindevice_config`](NetworkConfig::maindevice_config) to also check the number of
+    /// SubDevices on the network.
+    pub maindevice: MainDeviceConfig,
+
+    /// MainDevice timeouts.
+    pub timeouts: Timeouts,
+
+    /// Settings for each group, by name.
+    pub groups: BTreeMap<String, GroupConfig>,
+
+    /// The SubDevices expected on the network, in the order they are connected.
+    pub subdevices: Vec<ExpectedSubDevice>,
+}
+
+/// Settings for a group of SubDevices.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GroupConfig {
+    /// Distributed clock settings, passed to
+    /// [`SubDeviceGroup::configure_dc_sync`](crate::SubDeviceGroup::configure_dc_sync).
+    pub dc: Option<DcConfiguration>,
+}
+
+/// A SubDevice expected at a position in the network.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectedSubDevice {
+    /// The name read from the SubDevice's EEPROM, e.g. `EL2004`. Not checked if `None`.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Vendor ID.
+    pub vendor_id: u32,
+
+    /// Product ID.
+    pub product_id: u32,
+
+    /// Product revision. Not checked if `None`.
+    #[serde(default)]
+    pub revision: Option<u32>,
+
+    /// The name of the group to add this SubDevice to.
+    pub group: String,
+
+    /// The PDOs to assign to the outputs (`0x1c12`). The assignment is left unchanged if `None`.
+    #[serde(default)]
+    pub output_pdos: Option<Vec<Pdo>>,
+
+    /// The PDOs to assign to the inputs (`0x1c13`). The assignment is left unchanged if `None`.
+    #[serde(default)]
+    pub input_pdos: Option<Vec<Pdo>>,
+
+    /// SDOs to write in PRE-OP, in order, after any PDOs are assigned.
+    #[serde(default)]
+    pub sdo_writes: Vec<SdoWrite>,
+
+    /// DC sync mode. Only used if the SubDevice's group has DC configured.
+    #[serde(default)]
+    pub dc_sync: DcSync,
+}
+
+impl ExpectedSubDevice {
+    /// Whether the given SubDevice is the one described here.
+    fn matches(&self, identity: SubDeviceIdentity, name: &str) -> bool {
+        identity.vendor_id == self.vendor_id
+            && identity.product_id == self.product_id
+            && self
+                .revision
+                .map_or(true, |revision| identity.revision == revision)
+            && self
+                .name
+                .as_deref()
+                .map_or(true, |expected| expected == name)
+    }
+}
+
+/// A PDO and the objects mapped into it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pdo {
+    /// PDO mapping object index, e.g. `0x1600`.
+    pub index: u16,
+
+    /// Objects mapped into the PDO, in order. The existing mapping is kept if this is empty, e.g.
+    /// for SubDevices with fixed PDOs.
+    #[serde(default)]
+    pub entries: Vec<PdoEntry>,
+}
+
+/// An object mapped into a [`Pdo`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PdoEntry {
+    /// Object index.
+    pub index: u16,
+
+    /// Object sub-index.
+    #[serde(default)]
+    pub sub_index: u8,
+
+    /// Object length in bits.
+    pub bit_len: u8,
+}
+
+impl PdoEntry {
+    /// The mapping entry written to the PDO mapping object.
+    fn raw(&self) -> u32 {
+        // Index in the high WORD, then sub index and bit length, ETG1000.6 Table 74
+        u32::from(self.index) << 16 | u32::from(self.sub_index) << 8 | u32::from(self.bit_len)
+    }
+}
+
+/// A value to write to an SDO.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SdoWrite {
+    /// Object index.
+    pub index: u16,
+
+    /// Object sub-index.
+    #[serde(default)]
+    pub sub_index: u8,
+
+    /// The value to write, with its type, e.g. `u16 = 1500`.
+    #[serde(flatten)]
+    pub value: SdoValue,
+}
+
+/// A typed SDO value.
+///
+/// Only expedited SDO downloads of up to 4 bytes are supported.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SdoValue {
+    /// Boolean.
+    Bool(bool),
+    /// Unsigned 8 bit integer.
+    U8(u8),
+    /// Unsigned 16 bit integer.
+    U16(u16),
+    /// Unsigned 32 bit integer.
+    U32(u32),
+    /// Signed 8 bit integer.
+    I8(i8),
+    /// Signed 16 bit integer.
+    I16(i16),
+    /// Signed 32 bit integer.
+    I32(i32),
+}
+
+impl SdoWrite {
+    async fn apply<S>(&self, subdevice: &SubDeviceRef<'_, S>) -> Result<(), Error>
+    where
+        S: DerefMut<Target = SubDevice>,
+    {
+        let Self {
+            index, sub_index, ..
+        } = *self;
+
+        match self.value {
+            SdoValue::Bool(value) => subdevice.sdo_write(index, sub_index, value).await,
+            SdoValue::U8(value) => subdevice.sdo_write(index, sub_index, value).await,
+            SdoValue::U16(value) => subdevice.sdo_write(index, sub_index, value).await,
+            SdoValue::U32(value) => subdevice.sdo_write(index, sub_index, value).await,
+            SdoValue::I8(value) => subdevice.sdo_write(index, sub_index, value).await,
+            SdoValue::I16(value) => subdevice.sdo_write(index, sub_index, value).await,
+            SdoValue::I32(value) => subdevice.sdo_write(index, sub_index, value).await,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// Parse a configuration from a TOML string.
+    pub fn from_toml(s: &str) -> Result<Self, LoadError> {
+        toml::from_str(s).map_err(LoadError::Toml)
+    }
+
+    /// Parse a configuration from a YAML string.
+    pub fn from_yaml(s: &str) -> Result<Self, LoadError> {
+        serde_yaml::from_str(s).map_err(LoadError::Yaml)
+    }
+
+    /// Load a configuration from a `.toml`, `.yaml` or `.yml` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+
+        let parse = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml,
+            Some("yaml" | "yml") => Self::from_yaml,
+            _ => return Err(LoadError::UnknownFormat),
+        };
+
+        parse(&std::fs::read_to_string(path).map_err(LoadError::Io)?)
+    }
+
+    /// The MainDevice configuration, expecting the number of SubDevices in
+    /// [`subdevices`](NetworkConfig::subdevices) unless
+    /// [`expected_subdevices`](MainDeviceConfig::expected_subdevices) is already set.
+    pub fn maindevice_config(&self) -> MainDeviceConfig {
+        MainDeviceConfig {
+            expected_subdevices: self
+                .maindevice
+                .expected_subdevices
+                .or_else(|| u16::try_from(self.subdevices.len()).ok()),
+            ..self.maindevice
+        }
+    }
+
+    /// Get the name of the group a SubDevice should be added to, for use in the closure passed to
+    /// [`MainDevice::init`].
+    ///
+    /// Returns [`Error::UnknownSubDevice`] if the SubDevice is not the one expected at its position
+    /// in the network.
+    pub fn group_of(&self, subdevice: &SubDevice) -> Result<&str, Error> {
+        self.expected(subdevice.index(), subdevice.identity(), subdevice.name())
+            .map(|expected| expected.group.as_str())
+    }
+
+    /// DC settings for the given group, if any.
+    pub fn dc_configuration(&self, group: &str) -> Option<DcConfiguration> {
+        self.groups.get(group).and_then(|group| group.dc)
+    }
+
+    /// Assign PDOs, write startup SDOs and set the DC sync mode of a SubDevice in PRE-OP.
+    ///
+    /// Returns [`Error::UnknownSubDevice`] if the SubDevice is not the one expected at its position
+    /// in the network.
+    pub async fn configure<S>(&self, subdevice: &mut SubDeviceRef<'_, S>) -> Result<(), Error>
+    where
+        S: DerefMut<Target = SubDevice>,
+    {
+        let expected = self.expected(subdevice.index(), subdevice.identity(), subdevice.name())?;
+
+        for (assignment, pdos) in [
+            (OUTPUT_ASSIGNMENT, &expected.output_pdos),
+            (INPUT_ASSIGNMENT, &expected.input_pdos),
+        ] {
+            let Some(pdos) = pdos else {
+                continue;
+            };
+
+            // Mappings can't be changed while their PDOs are assigned
+            subdevice.sdo_write(assignment, 0, 0u8).await?;
+
+            for pdo in pdos.iter().filter(|pdo| !pdo.entries.is_empty()) {
+                let entries = pdo.entries.iter().map(PdoEntry::raw).collect::<Vec<_>>();
+
+                subdevice.sdo_write_array(pdo.index, entries).await?;
+            }
+
+            let indices = pdos.iter().map(|pdo| pdo.index).collect::<Vec<_>>();
+
+            subdevice.sdo_write_array(assignment, indices).await?;
+        }
+
+        for write in expected.sdo_writes.iter() {
+            write.apply(subdevice).await?;
+        }
+
+        subdevice.set_dc_sync(expected.dc_sync);
+
+        fmt::debug!(
+            "Configured SubDevice {:#06x} from network config",
+            subdevice.configured_address()
+        );
+
+        Ok(())
+    }
+
+    /// [`configure`](NetworkConfig::configure) every SubDevice in a group.
+    pub async fn configure_group<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>(
+        &self,
+        maindevice: &MainDevice<'_>,
+        group: &SubDeviceGroup<MAX_SUBDEVICES, MAX_PDI, PreOp, DC>,
+    ) -> Result<(), Error> {
+        for index in 0..group.len() {
+            self.configure(&mut group.subdevice(maindevice, index)?)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    fn expected(
+        &self,
+        index: u16,
+        identity: SubDeviceIdentity,
+        name: &str,
+    ) -> Result<&ExpectedSubDevice, Error> {
+        self.subdevices
+            .get(usize::from(index))
+            .filter(|expected| expected.matches(identity, name))
+            .ok_or_else(|| {
+                fmt::error!(
+                    "SubDevice {} {} ({}) is not expected at this position in the network config",
+                    index,
+                    name,
+                    identity
+                );
+
+                Error::UnknownSubDevice
+            })
+    }
+}
+
+/// An error loading a [`NetworkConfig`].
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be read.
+    Io(std::io::Error),
+    /// The file is not valid TOML, or doesn't describe a network.
+    Toml(toml::de::Error),
+    /// The file is not valid YAML, or doesn't describe a network.
+    Yaml(serde_yaml::Error),
+    /// The file extension is not `.toml`, `.yaml` or `.yml`.
+    UnknownFormat,
+}
+
+impl core::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to read network config: {}", e),
+            Self::Toml(e) => write!(f, "invalid network config: {}", e),
+            Self::Yaml(e) => write!(f, "invalid network config: {}", e),
+            Self::UnknownFormat => f.write_str("network config must be a TOML or YAML file"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Toml(e) => Some(e),
+            Self::Yaml(e) => Some(e),
+            Self::UnknownFormat => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::time::Duration;
+
+    const TOML: &str = r#"
+        [maindevice]
+        retry_behaviour = { Count = 3 }
+
+        [groups.drives.dc]
+        start_delay = { secs = 0, nanos = 100_000_000 }
+        sync0_period = { secs = 0, nanos = 1_000_000 }
+        sync0_shift = { secs = 0, nanos = 0 }
+
+        [[subdevices]]
+        name = "EK1100"
+        vendor_id = 0x2
+        product_id = 0x044c2c52
+        group = "io"
+
+        [[subdevices]]
+        vendor_id = 0x100000
+        product_id = 0xc0108
+        revision = 1
+        group = "drives"
+        dc_sync = "Sync0"
+        sdo_writes = [{ index = 0x6060, i8 = 8 }, { index = 0x8010, sub_index = 1, u16 = 1500 }]
+        output_pdos = [
+            { index = 0x1600, entries = [
+                { index = 0x6040, bit_len = 16 },
+                { index = 0x607a, bit_len = 32 },
+            ] },
+        ]
+    "#;
+
+    const YAML: &str = r#"
+        maindevice:
+          retry_behaviour: !Count 3
+        groups:
+          drives:
+            dc:
+              start_delay: { secs: 0, nanos: 100000000 }
+              sync0_period: { secs: 0, nanos: 1000000 }
+              sync0_shift: { secs: 0, nanos: 0 }
+        subdevices:
+          - name: EK1100
+            vendor_id: 0x2
+            product_id: 0x044c2c52
+            group: io
+          - vendor_id: 0x100000
+            product_id: 0xc0108
+            revision: 1
+            group: drives
+            dc_sync: Sync0
+            sdo_writes:
+              - { index: 0x6060, i8: 8 }
+              - { index: 0x8010, sub_index: 1, u16: 1500 }
+            output_pdos:
+              - index: 0x1600
+                entries:
+                  - { index: 0x6040, bit_len: 16 }
+                  - { index: 0x607a, bit_len: 32 }
+    "#;
+
+    fn expected() -> NetworkConfig {
+        NetworkConfig {
+            maindevice: MainDeviceConfig {
+                retry_behaviour: crate::RetryBehaviour::Count(3),
+                ..MainDeviceConfig::default()
+            },
+            timeouts: Timeouts::default(),
+            groups: BTreeMap::from([(
+                String::from("drives"),
+                GroupConfig {
+                    dc: Some(DcConfiguration {
+                        start_delay: Duration::from_millis(100),
+                        sync0_period: Duration::from_millis(1),
+                        sync0_shift: Duration::ZERO,
+                    }),
+                },
+            )]),
+            subdevices: Vec::from([
+                ExpectedSubDevice {
+                    name: Some(String::from("EK1100")),
+                    vendor_id: 0x2,
+                    product_id: 0x044c2c52,
+                    revision: None,
+                    group: String::from("io"),
+                    output_pdos: None,
+                    input_pdos: None,
+                    sdo_writes: Vec::new(),
+                    dc_sync: DcSync::Disabled,
+                },
+                ExpectedSubDevice {
+                    name: None,
+                    vendor_id: 0x100000,
+                    product_id: 0xc0108,
+                    revision: Some(1),
+                    group: String::from("drives"),
+                    output_pdos: Some(Vec::from([Pdo {
+                        index: 0x1600,
+                        entries: Vec::from([
+                            PdoEntry {
+                                index: 0x6040,
+                                sub_index: 0,
+                                bit_len: 16,
+                            },
+                            PdoEntry {
+                                index: 0x607a,
+                                sub_index: 0,
+                                bit_len: 32,
+                            },
+                        ]),
+                    }])),
+                    input_pdos: None,
+                    sdo_writes: Vec::from([
+                        SdoWrite {
+                            index: 0x6060,
+                            sub_index: 0,
+                            value: SdoValue::I8(8),
+                        },
+                        SdoWrite {
+                            index: 0x8010,
+                            sub_index: 1,
+                            value: SdoValue::U16(1500),
+                        },
+                    ]),
+                    dc_sync: DcSync::Sync0,
+                },
+            ]),
+        }
+    }
+
+    #[test]
+    fn parse_toml() {
+        pretty_assertions::assert_eq!(NetworkConfig::from_toml(TOML).unwrap(), expected());
+    }
+
+    #[test]
+    fn parse_yaml() {
+        pretty_assertions::assert_eq!(NetworkConfig::from_yaml(YAML).unwrap(), expected());
+    }
+
+    #[test]
+    fn round_trip() {
+        let config = expected();
+
+        let yaml = serde_yaml::to_string(&config).unwrap();
+
+        assert_eq!(NetworkConfig::from_yaml(&yaml).unwrap(), config);
+    }
+
+    #[test]
+    fn unknown_field() {
+        assert!(matches!(
+            NetworkConfig::from_toml(
+                "[[subdevices]]\nvendor_id = 1\nproduct_id = 2\ngroup = \"a\"\nbogus = 1"
+            ),
+            Err(LoadError::Toml(_))
+        ));
+    }
+
+    #[test]
+    fn expected_subdevice_count() {
+        let config = expected();
+
+        assert_eq!(config.maindevice_config().expected_subdevices, Some(2));
+
+        let config = NetworkConfig {
+            maindevice: MainDeviceConfig {
+                expected_subdevices: Some(5),
+                ..MainDeviceConfig::default()
+            },
+            ..config
+        };
+
+        assert_eq!(config.maindevice_config().expected_subdevices, Some(5));
+    }
+
+    #[test]
+    fn match_identity() {
+        let config = expected();
+
+        let ek1100 = SubDeviceIdentity {
+            vendor_id: 0x2,
+            product_id: 0x044c2c52,
+            revision: 0x00120000,
+            serial: 0,
+        };
+
+        assert_eq!(
+            config
+                .expected(0, ek1100, "EK1100")
+                .map(|e| e.group.as_str()),
+            Ok("io")
+        );
+        assert_eq!(
+            config
+                .expected(0, ek1100, "EK1101")
+                .map(|e| e.group.as_str()),
+            Err(Error::UnknownSubDevice),
+            "name mismatch"
+        );
+        assert_eq!(
+            config
+                .expected(1, ek1100, "EK1100")
+                .map(|e| e.group.as_str()),
+            Err(Error::UnknownSubDevice),
+            "identity mismatch"
+        );
+        assert_eq!(
+            config
+                .expected(2, ek1100, "EK1100")
+                .map(|e| e.group.as_str()),
+            Err(Error::UnknownSubDevice),
+            "too many SubDevices"
+        );
+    }
+
+    #[test]
+    fn pdo_entry() {
+        let entry = PdoEntry {
+            index: 0x6040,
+            sub_index: 0,
+            bit_len: 16,
+        };
+
+        assert_eq!(entry.raw(), 0x6040_0010);
+
+        let entry = PdoEntry {
+            index: 0x6000,
+            sub_index: 0x11,
+            bit_len: 1,
+        };
+
+        assert_eq!(entry.raw(), 0x6000_1101);
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use super::NetworkConfig;
+    use crate::{
+        error::Error,
+        sim::{ethercat_now, fixture::*, SimNetwork, SimSubDevice},
+        PduStorage, SubDeviceGroup,
+    };
+
+    #[test]
+    fn network_config() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        #[derive(Default)]
+        struct Groups {
+            io: SubDeviceGroup<2, 16>,
+            drives: SubDeviceGroup<2, 32>,
+        }
+
+        let config = NetworkConfig::from_toml(
+            r#"
+            [[subdevices]]
+            name = "EK1100"
+            vendor_id = 0x2
+            product_id = 0x044c2c52
+            group = "io"
+
+            [[subdevices]]
+            vendor_id = 0x2
+            product_id = 0x0400
+            group = "drives"
+            sdo_writes = [{ index = 0x6060, i8 = 9 }, { index = 0x2000, u16 = 0xabcd }]
+            output_pdos = [
+                { index = 0x1600, entries = [
+                    { index = 0x6040, bit_len = 16 },
+                    { index = 0x60ff, bit_len = 32 },
+                ] },
+            ]
+            input_pdos = [
+                { index = 0x1a00, entries = [
+                    { index = 0x6041, bit_len = 16 },
+                    { index = 0x6064, bit_len = 32 },
+                ] },
+            ]
+            "#,
+        )
+        .expect("parse");
+
+        let network =
+            SimNetwork::new([SimSubDevice::new("EK1100", identity(0x044c_2c52)), servo()]);
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let groups = maindevice
+                .init::<4, _>(ethercat_now, |groups: &Groups, subdevice| {
+                    match config.group_of(subdevice)? {
+                        "io" => Ok(&groups.io),
+                        "drives" => Ok(&groups.drives),
+                        _ => Err(Error::UnknownSubDevice),
+                    }
+                })
+                .await
+                .expect("init");
+
+            assert_eq!(groups.io.len(), 1);
+            assert_eq!(groups.drives.len(), 1);
+
+            config
+                .configure_group(&maindevice, &groups.io)
+                .await
+                .expect("configure IO");
+            config
+                .configure_group(&maindevice, &groups.drives)
+                .await
+                .expect("configure drives");
+
+            let drives = groups
+                .drives
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            let servo = drives.subdevice(&maindevice, 0).unwrap();
+
+            assert_eq!(servo.outputs_raw().len(), 2 + 4);
+            assert_eq!(servo.inputs_raw().len(), 2 + 4);
+        });
+
+        let sdo = |index, sub_index| {
+            net.with_subdevice(1, |subdevice| {
+                subdevice.sdo(index, sub_index).map(<[u8]>::to_vec)
+            })
+            .flatten()
+        };
+
+        assert_eq!(sdo(0x1600, 0), Some(vec![2]));
+        assert_eq!(sdo(0x1600, 2), Some(0x60ff_0020u32.to_le_bytes().to_vec()));
+        assert_eq!(sdo(0x1a00, 0), Some(vec![2]));
+        assert_eq!(sdo(0x1a00, 2), Some(0x6064_0020u32.to_le_bytes().to_vec()));
+        assert_eq!(sdo(0x1c12, 1), Some(0x1600u16.to_le_bytes().to_vec()));
+        assert_eq!(sdo(0x6060, 0), Some(vec![9]));
+    }
+
+    #[test]
+    fn network_config_mismatch() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let config = NetworkConfig::from_yaml(
+            r#"
+            subdevices:
+              - { name: EK1100, vendor_id: 0x2, product_id: 0x044c2c52, group: io }
+              - { name: EL1004, vendor_id: 0x2, product_id: 0x03ec3052, group: io }
+            "#,
+        )
+        .expect("parse");
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let result = maindevice
+                .init::<8, _>(ethercat_now, |group: &SubDeviceGroup<8, 32>, subdevice| {
+                    config.group_of(subdevice)?;
+
+                    Ok(group)
+                })
+                .await;
+
+            // The EL2004 at position 1 is not the expected EL1004
+            assert_eq!(result.map(|_| ()), Err(Error::UnknownSubDevice));
+        });
+    }
+}
@@ -0,0 +1,233 @@
+//! Measure EtherCrab timeouts with a Cortex-M core timer.
+//!
+//! Requires the `cortex-m` feature.
+//!
+//! With this feature enabled, EtherCrab timeouts are measured by polling a hardware counter, so no
+//! `embassy-time` driver or other time source needs to be set up. Call one of the following once
+//! at startup, before any EtherCrab futures are polled:
+//!
+//! - [`init_dwt`] uses the DWT cycle counter. It does not occupy any timer, but is not available
+//!   on ARMv6-M cores like the Cortex-M0 and M0+.
+//! - [`init_systick`] uses SysTick, which is available on every Cortex-M core.
+//!
+//! Both counters run at the core clock, giving sub-microsecond resolution, and are extended to 64
+//! bits in software each time the clock is read. The clock must therefore be read at least once
+//! per counter period: 2<sup>32</sup> cycles for the DWT cycle counter, e.g. 25 s at 168 MHz,
+//! and 2<sup>24</sup> cycles for SysTick, e.g. 100 ms at 168 MHz.
+//!
+//! A counter can't wake a task when a timeout expires, so [`wake_timers`] must be called
+//! periodically, e.g. from the SysTick exception handler. It wakes every task whose timeout has
+//! expired, and also reads the clock often enough to keep it extended. Timeouts are therefore
+//! detected up to one call period late.
+//!
+//! This feature is opt-in: on `no_std` targets it replaces `embassy-time` for all EtherCrab
+//! timeouts. The `embassy`, `std` and `rtic` features take precedence over it.
+//!
+//! The application must provide a [`critical-section`](https://docs.rs/critical-section)
+//! implementation, e.g. with the `critical-section-single-core` feature of `cortex-m`.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use cortex_m::Peripherals;
+//! use ethercrab::{cortex_m::ethercat_now, MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+//!
+//! static PDU_STORAGE: PduStorage<8, { PduStorage::element_size(256) }> = PduStorage::new();
+//!
+//! let mut core = Peripherals::take().unwrap();
+//!
+//! // Core clock frequency as configured by the HAL
+//! ethercrab::cortex_m::init_dwt(&mut core.DCB, &mut core.DWT, 168_000_000);
+//!
+//! let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+//!
+//! let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+//!
+//! // Run the TX/RX loop with `tx` and `rx`, then initialise as usual
+//! let group = maindevice
+//!     .init_single_group::<16, 256>(ethercat_now)
+//!     .await
+//!     .expect("init");
+//! ```
+
+use crate::atomic::{AtomicU32, AtomicU8, Ordering};
+use ::cortex_m::peripheral::{syst::SystClkSource, DCB, DWT, SYST};
+use core::ptr;
+
+/// DWT control register.
+const DWT_CTRL: *mut u32 = 0xe000_1000 as *mut u32;
+
+/// DWT cycle count register.
+const DWT_CYCCNT: *const u32 = 0xe000_1004 as *const u32;
+
+/// `DWT_CTRL.NOCYCCNT`, set if the cycle counter is not implemented.
+const DWT_CTRL_NOCYCCNT: u32 = 1 << 25;
+
+/// `DWT_CTRL.CYCCNTENA`, enables the cycle counter.
+const DWT_CTRL_CYCCNTENA: u32 = 1 << 0;
+
+/// Largest SysTick reload value.
+const SYST_RELOAD: u32 = 0x00ff_ffff;
+
+/// The counter used by the clock.
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+enum Source {
+    None = 0,
+    Dwt = 1,
+    SysTick = 2,
+}
+
+static SOURCE: AtomicU8 = AtomicU8::new(Source::None as u8);
+
+/// Counter frequency in Hz.
+static FREQUENCY: AtomicU32 = AtomicU32::new(0);
+
+/// Counter value the last time the clock was read, used to detect wraparound.
+static LAST: AtomicU32 = AtomicU32::new(0);
+
+/// Number of counter periods elapsed.
+static PERIODS: AtomicU32 = AtomicU32::new(0);
+
+/// Measure EtherCrab timeouts with the DWT cycle counter.
+///
+/// `core_clock_hz` is the frequency of the core clock, which the cycle counter is incremented
+/// by.
+///
+/// # Panics
+///
+/// This function will panic if the core does not implement the DWT cycle counter, e.g. any
+/// ARMv6-M core. Use [`init_systick`] on those instead.
+pub fn init_dwt(dcb: &mut DCB, _dwt: &mut DWT, core_clock_hz: u32) {
+    dcb.enable_trace();
+
+    // SAFETY: `DWT_CTRL` is an architecturally defined register and access to it is guarded by the
+    // `&mut DWT` we hold. The cycle counter register is not touched if it is not implemented.
+    unsafe {
+        let ctrl = ptr::read_volatile(DWT_CTRL);
+
+        assert!(
+            ctrl & DWT_CTRL_NOCYCCNT == 0,
+            "DWT cycle counter is not implemented on this core"
+        );
+
+        ptr::write_volatile(DWT_CTRL, ctrl | DWT_CTRL_CYCCNTENA);
+    }
+
+    start(Source::Dwt, core_clock_hz);
+}
+
+/// Measure EtherCrab timeouts with SysTick.
+///
+/// SysTick is configured to count down from its maximum value at the core clock frequency,
+/// `core_clock_hz`. The SysTick interrupt is left as is, so it may be enabled to read the clock
+/// periodically as described in the [module documentation](self). SysTick must not be
+/// reconfigured after this function is called.
+pub fn init_systick(syst: &mut SYST, core_clock_hz: u32) {
+    syst.disable_counter();
+    syst.set_clock_source(SystClkSource::Core);
+    syst.set_reload(SYST_RELOAD);
+    syst.clear_current();
+    syst.enable_counter();
+
+    start(Source::SysTick, core_clock_hz);
+}
+
+fn start(source: Source, frequency: u32) {
+    critical_section::with(|_| {
+        FREQUENCY.store(frequency, Ordering::Relaxed);
+        LAST.store(0, Ordering::Relaxed);
+        PERIODS.store(0, Ordering::Relaxed);
+        SOURCE.store(source as u8, Ordering::Release);
+    });
+}
+
+/// Read the configured counter as a number of elapsed cycles.
+fn count(source: Source) -> u32 {
+    match source {
+        Source::None => 0,
+        // SAFETY: The cycle counter is enabled and known to be implemented once `SOURCE` is set.
+        Source::Dwt => unsafe { ptr::read_volatile(DWT_CYCCNT) },
+        // SysTick counts down
+        Source::SysTick => SYST_RELOAD - SYST::get_current(),
+    }
+}
+
+/// Get the time in nanoseconds since the clock was started with [`init_dwt`] or
+/// [`init_systick`].
+///
+/// This can be passed to e.g. [`MainDevice::init`](crate::MainDevice::init).
+///
+/// # Panics
+///
+/// This function will panic if the clock has not been started with [`init_dwt`] or
+/// [`init_systick`].
+pub fn ethercat_now() -> u64 {
+    let source = match SOURCE.load(Ordering::Acquire) {
+        1 => Source::Dwt,
+        2 => Source::SysTick,
+        _ => panic!("no EtherCrab clock started, call cortex_m::init_dwt or init_systick first"),
+    };
+
+    let period_bits = match source {
+        Source::SysTick => 24,
+        _ => 32,
+    };
+
+    let cycles = critical_section::with(|_| {
+        let count = count(source);
+
+        let mut periods = PERIODS.load(Ordering::Relaxed);
+
+        if count < LAST.load(Ordering::Relaxed) {
+            periods = periods.wrapping_add(1);
+
+            PERIODS.store(periods, Ordering::Relaxed);
+        }
+
+        LAST.store(count, Ordering::Relaxed);
+
+        (u64::from(periods) << period_bits) | u64::from(count)
+    });
+
+    cycles_to_nanos(cycles, FREQUENCY.load(Ordering::Relaxed))
+}
+
+fn cycles_to_nanos(cycles: u64, frequency: u32) -> u64 {
+    let frequency = u64::from(frequency).max(1);
+
+    // Split into whole seconds and remainder so the multiplication can't overflow
+    (cycles / frequency) * 1_000_000_000 + (cycles % frequency) * 1_000_000_000 / frequency
+}
+
+#[cfg(all(not(feature = "rtic"), not(feature = "std"), not(feature = "embassy")))]
+pub(crate) fn now() -> core::time::Duration {
+    core::time::Duration::from_nanos(ethercat_now())
+}
+
+/// Wake every task waiting for an EtherCrab timeout that has expired.
+///
+/// Call this periodically, e.g. from the SysTick exception handler, as described in the
+/// [module documentation](self).
+pub fn wake_timers() {
+    crate::timer_factory::wake_poll_timers()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nanos() {
+        assert_eq!(cycles_to_nanos(168, 168_000_000), 1_000);
+        assert_eq!(cycles_to_nanos(1, 48_000_000), 20);
+
+        // A day at 480 MHz would overflow if multiplied before dividing
+        let cycles = 480_000_000 * 86_400;
+
+        assert_eq!(
+            cycles_to_nanos(cycles + 480, 480_000_000),
+            86_400 * 1_000_000_000 + 1_000
+        );
+    }
+}
@@ -409,18 +409,30 @@ fn assign_parent_relationships(subdevices: &mut [SubDevice]) -> Result<(), Error
     Ok(())
 }
 
+/// Discover the network topology and compute propagation delays for the given SubDevices.
+///
+/// This latches DC receive times but does not write any DC configuration to the SubDevices.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+pub(crate) async fn detect_topology(
+    maindevice: &MainDevice<'_>,
+    subdevices: &mut [SubDevice],
+) -> Result<(), Error> {
+    latch_dc_times(maindevice, subdevices).await?;
+
+    assign_parent_relationships(subdevices)
+}
+
 /// Configure distributed clocks.
 ///
 /// This method walks through the discovered list of devices and sets the system time offset and
 /// transmission delay of each device.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
 pub(crate) async fn configure_dc<'subdevices>(
     maindevice: &MainDevice<'_>,
     subdevices: &'subdevices mut [SubDevice],
     now: impl Fn() -> u64,
 ) -> Result<Option<&'subdevices SubDevice>, Error> {
-    latch_dc_times(maindevice, subdevices).await?;
-
-    assign_parent_relationships(subdevices)?;
+    detect_topology(maindevice, subdevices).await?;
 
     let first_dc_subdevice = subdevices
         .iter()
@@ -449,6 +461,14 @@ pub(crate) async fn configure_dc<'subdevices>(
 
 /// Send `iterations` FRMW frames to synchronise the network with the reference clock in the
 /// designated DC SubDevice.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(configured_address = %dc_reference_subdevice.configured_address(), iterations)
+    )
+)]
 pub(crate) async fn run_dc_static_sync(
     maindevice: &MainDevice<'_>,
     dc_reference_subdevice: &SubDevice,
@@ -482,6 +502,7 @@ mod tests {
     use crate::{
         register::SupportFlags,
         subdevice::ports::{tests::make_ports, Port, Ports},
+        ConfiguredAddress,
     };
 
     // A SubDevice in the middle of the chain
@@ -503,7 +524,7 @@ mod tests {
     #[test]
     fn parent_is_ek1100() {
         let subdevice_defaults = SubDevice {
-            configured_address: 0x0000,
+            configured_address: ConfiguredAddress::new(0x0000),
             ports: Ports::default(),
             name: "Default".try_into().unwrap(),
             flags: SupportFlags::default(),
@@ -516,28 +537,28 @@ mod tests {
 
         let parents = [
             SubDevice {
-                configured_address: 0x1000,
+                configured_address: ConfiguredAddress::new(0x1000),
                 ports: ports_passthrough(),
                 name: "LAN9252".try_into().unwrap(),
                 index: 0,
                 ..subdevice_defaults.clone()
             },
             SubDevice {
-                configured_address: 0x1100,
+                configured_address: ConfiguredAddress::new(0x1100),
                 ports: ports_fork(),
                 name: "EK1100".try_into().unwrap(),
                 index: 1,
                 ..subdevice_defaults.clone()
             },
             SubDevice {
-                configured_address: 0x2004,
+                configured_address: ConfiguredAddress::new(0x2004),
                 ports: ports_passthrough(),
                 name: "EL2004".try_into().unwrap(),
                 index: 2,
                 ..subdevice_defaults.clone()
             },
             SubDevice {
-                configured_address: 0x3004,
+                configured_address: ConfiguredAddress::new(0x3004),
                 ports: ports_eol(),
                 name: "EL3004".try_into().unwrap(),
                 index: 3,
@@ -546,7 +567,7 @@ mod tests {
         ];
 
         let me = SubDevice {
-            configured_address: 0x9252,
+            configured_address: ConfiguredAddress::new(0x9252),
             ports: ports_eol(),
             name: "LAN9252".try_into().unwrap(),
             index: 4,
@@ -562,7 +583,7 @@ mod tests {
     #[test]
     fn two_ek1100() {
         let subdevice_defaults = SubDevice {
-            configured_address: 0x0000,
+            configured_address: ConfiguredAddress::new(0x0000),
             ports: Ports::default(),
             name: "Default".try_into().unwrap(),
             flags: SupportFlags::default(),
@@ -575,42 +596,42 @@ mod tests {
 
         let parents = [
             SubDevice {
-                configured_address: 0x1100,
+                configured_address: ConfiguredAddress::new(0x1100),
                 ports: ports_fork(),
                 name: "EK1100".try_into().unwrap(),
                 index: 1,
                 ..subdevice_defaults.clone()
             },
             SubDevice {
-                configured_address: 0x2004,
+                configured_address: ConfiguredAddress::new(0x2004),
                 ports: ports_passthrough(),
                 name: "EL2004".try_into().unwrap(),
                 index: 2,
                 ..subdevice_defaults.clone()
             },
             SubDevice {
-                configured_address: 0x3004,
+                configured_address: ConfiguredAddress::new(0x3004),
                 ports: ports_eol(),
                 name: "EL3004".try_into().unwrap(),
                 index: 3,
                 ..subdevice_defaults.clone()
             },
             SubDevice {
-                configured_address: 0x1100,
+                configured_address: ConfiguredAddress::new(0x1100),
                 ports: ports_fork(),
                 name: "EK1100_2".try_into().unwrap(),
                 index: 4,
                 ..subdevice_defaults.clone()
             },
             SubDevice {
-                configured_address: 0x2004,
+                configured_address: ConfiguredAddress::new(0x2004),
                 ports: ports_passthrough(),
                 name: "EL2828".try_into().unwrap(),
                 index: 5,
                 ..subdevice_defaults.clone()
             },
             SubDevice {
-                configured_address: 0x3004,
+                configured_address: ConfiguredAddress::new(0x3004),
                 ports: ports_eol(),
                 name: "EL2889".try_into().unwrap(),
                 index: 6,
@@ -637,7 +658,7 @@ mod tests {
     #[test]
     fn first_in_chain() {
         let subdevice_defaults = SubDevice {
-            configured_address: 0x1000,
+            configured_address: ConfiguredAddress::new(0x1000),
             ports: Ports::default(),
             name: "Default".try_into().unwrap(),
             flags: SupportFlags::default(),
@@ -651,7 +672,7 @@ mod tests {
         let parents = [];
 
         let me = SubDevice {
-            configured_address: 0x1100,
+            configured_address: ConfiguredAddress::new(0x1100),
             ports: ports_eol(),
             name: "EK1100".try_into().unwrap(),
             index: 4,
@@ -696,7 +717,7 @@ mod tests {
         assert_eq!(ports.topology(), Topology::LineEnd);
 
         let mut subdevice = SubDevice {
-            configured_address: 0x1000,
+            configured_address: ConfiguredAddress::new(0x1000),
             ports,
             name: "Default".try_into().unwrap(),
             flags: SupportFlags::default(),
@@ -742,7 +763,7 @@ mod tests {
         let _ = env_logger::builder().is_test(true).try_init();
 
         let defaults = SubDevice {
-            configured_address: 0x999,
+            configured_address: ConfiguredAddress::new(0x999),
             name: "CHANGEME".try_into().unwrap(),
             ports: Ports::default(),
             dc_receive_time: 0,
@@ -764,7 +785,7 @@ mod tests {
         let mut subdevices = [
             SubDevice {
                 index: 0,
-                configured_address: 0x1000,
+                configured_address: ConfiguredAddress::new(0x1000),
                 name: "EK1100".try_into().unwrap(),
                 ports: ports(
                     true, 3380373882, false, 1819436374, true, 3380374482, true, 3380375762,
@@ -774,7 +795,7 @@ mod tests {
             },
             SubDevice {
                 index: 1,
-                configured_address: 0x1001,
+                configured_address: ConfiguredAddress::new(0x1001),
                 name: "EK1122".try_into().unwrap(),
                 ports: ports(
                     true, 3384116362, false, 1819436374, false, 1717989224, true, 3384116672,
@@ -784,7 +805,7 @@ mod tests {
             },
             SubDevice {
                 index: 2,
-                configured_address: 0x1002,
+                configured_address: ConfiguredAddress::new(0x1002),
                 name: "EL9560".try_into().unwrap(),
                 ports: ports(
                     true, 3383862982, false, 1819436374, false, 1717989224, false, 0,
@@ -794,7 +815,7 @@ mod tests {
             },
             SubDevice {
                 index: 3,
-                configured_address: 0x1003,
+                configured_address: ConfiguredAddress::new(0x1003),
                 name: "EK1914".try_into().unwrap(),
                 ports: ports(
                     true, 3373883962, false, 1819436374, true, 3373884272, false, 0,
@@ -804,7 +825,7 @@ mod tests {
             },
             SubDevice {
                 index: 4,
-                configured_address: 0x1004,
+                configured_address: ConfiguredAddress::new(0x1004),
                 name: "EL1008".try_into().unwrap(),
                 ports: ports(
                     true, 3375060602, false, 1819436374, false, 1717989224, false, 0,
@@ -852,7 +873,7 @@ mod tests {
         let _ = env_logger::builder().is_test(true).try_init();
 
         let defaults = SubDevice {
-            configured_address: 0x999,
+            configured_address: ConfiguredAddress::new(0x999),
             name: "CHANGEME".try_into().unwrap(),
             ports: Ports::default(),
             dc_receive_time: 0,
@@ -867,7 +888,7 @@ mod tests {
         let mut subdevices = [
             SubDevice {
                 index: 0,
-                configured_address: 0x1000,
+                configured_address: ConfiguredAddress::new(0x1000),
                 name: "EK1100".try_into().unwrap(),
                 ports: ports(
                     true, 3493061450, false, 1819436374, true, 3493064460, false, 0,
@@ -877,7 +898,7 @@ mod tests {
             },
             SubDevice {
                 index: 1,
-                configured_address: 0x1001,
+                configured_address: ConfiguredAddress::new(0x1001),
                 name: "EK1122".try_into().unwrap(),
                 ports: ports(
                     true, 3493293220, true, 3493294570, true, 3493295650, true, 3493295940,
@@ -887,7 +908,7 @@ mod tests {
             },
             SubDevice {
                 index: 2,
-                configured_address: 0x1002,
+                configured_address: ConfiguredAddress::new(0x1002),
                 name: "EK1914".try_into().unwrap(),
                 ports: ports(
                     true, 3485337450, false, 1819436374, true, 3485337760, false, 0,
@@ -897,7 +918,7 @@ mod tests {
             },
             SubDevice {
                 index: 3,
-                configured_address: 0x1003,
+                configured_address: ConfiguredAddress::new(0x1003),
                 name: "EL1008".try_into().unwrap(),
                 ports: ports(
                     true, 3488375400, false, 1819436374, false, 1717989224, false, 0,
@@ -907,7 +928,7 @@ mod tests {
             },
             SubDevice {
                 index: 4,
-                configured_address: 0x1004,
+                configured_address: ConfiguredAddress::new(0x1004),
                 name: "EK1101".try_into().unwrap(),
                 ports: ports(
                     true, 3485087810, false, 1819436374, false, 1717989224, false, 0,
@@ -917,7 +938,7 @@ mod tests {
             },
             SubDevice {
                 index: 5,
-                configured_address: 0x1005,
+                configured_address: ConfiguredAddress::new(0x1005),
                 name: "EL9560".try_into().unwrap(),
                 ports: ports(
                     true, 3494335890, false, 1819436374, false, 1717989224, false, 0,
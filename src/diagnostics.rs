@@ -0,0 +1,235 @@
+//! A read-only handle for monitoring the network from other tasks.
+
+use crate::{
+    error::Error, subdevice_group::SubDeviceGroup, AlStatusSummary, Command, ConfiguredAddress,
+    ErrorCounters, LatencyHistogram, LinkBreak, MainDevice, MetricsSnapshot, NetworkTopology,
+    Statistics, SubIndex,
+};
+use ethercrab_wire::EtherCrabWireReadSized;
+
+/// A cloneable handle to a [`MainDevice`] for reading registers, SDOs and statistics from other
+/// tasks or threads.
+///
+/// A `Diagnostics` handle is created with [`MainDevice::diagnostics`]. It is `Copy`, `Send` and
+/// `Sync`, so it can be given to as many tasks or threads as required while the process data cycle
+/// runs elsewhere. All handles share the same PDU loop, which sends PDUs from each task in their own
+/// frames and routes every response back to the task that sent it. Process data frames are always
+/// sent ahead of diagnostics requests.
+///
+/// Unlike a shared `&MainDevice`, this handle only exposes methods that don't change the state of
+/// the network.
+///
+/// Mailbox transactions to the same SubDevice, e.g. an SDO read from a `Diagnostics` handle
+/// while the application task writes an SDO, are serialised so they can't interfere with each
+/// other.
+///
+/// # Examples
+///
+/// Log bus statistics and a SubDevice's AL status code from a separate thread:
+///
+/// ```rust,no_run
+/// # use ethercrab::{ConfiguredAddress, MainDevice, MainDeviceConfig, PduStorage, Timeouts, RegisterAddress};
+/// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+/// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+/// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+/// let diagnostics = maindevice.diagnostics();
+///
+/// std::thread::scope(|s| {
+///     s.spawn(move || {
+///         smol::block_on(async {
+///             loop {
+///                 let status_code = diagnostics
+///                     .register_read::<u16>(
+///                         ConfiguredAddress::new(0x1000),
+///                         RegisterAddress::AlStatusCode,
+///                     )
+///                     .await;
+///
+///                 log::info!("{:?}, status code {:?}", diagnostics.statistics(), status_code);
+///
+///                 smol::Timer::after(std::time::Duration::from_secs(1)).await;
+///             }
+///         })
+///     });
+///
+///     // Run the process data cycle with `maindevice` here
+/// });
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Diagnostics<'maindevice> {
+    maindevice: &'maindevice MainDevice<'maindevice>,
+}
+
+impl<'maindevice> Diagnostics<'maindevice> {
+    pub(crate) fn new(maindevice: &'maindevice MainDevice<'maindevice>) -> Self {
+        Self { maindevice }
+    }
+
+    pub(crate) fn maindevice(&self) -> &'maindevice MainDevice<'maindevice> {
+        self.maindevice
+    }
+
+    /// Get network communication statistics. See [`MainDevice::statistics`].
+    pub fn statistics(&self) -> Statistics {
+        self.maindevice.statistics()
+    }
+
+    /// Get a histogram of PDU round trip times. See [`MainDevice::latency_histogram`].
+    pub fn latency_histogram(&self) -> LatencyHistogram {
+        self.maindevice.latency_histogram()
+    }
+
+    /// Get the number of SubDevices discovered during init. See [`MainDevice::num_subdevices`].
+    pub fn num_subdevices(&self) -> usize {
+        self.maindevice.num_subdevices()
+    }
+
+    /// Read the AL status of every SubDevice on the network with a single `BRD`. See
+    /// [`MainDevice::al_status_summary`].
+    pub async fn al_status_summary(&self) -> Result<AlStatusSummary, Error> {
+        self.maindevice.al_status_summary().await
+    }
+
+    /// Read the error counters of every SubDevice on the network. See
+    /// [`MainDevice::error_counters`].
+    pub async fn error_counters<const MAX_SUBDEVICES: usize>(
+        &self,
+    ) -> Result<heapless::Vec<ErrorCounters, MAX_SUBDEVICES>, Error> {
+        self.maindevice.error_counters().await
+    }
+
+    /// Collect statistics and error counters for export to a monitoring system. See
+    /// [`MainDevice::metrics`].
+    pub async fn metrics<const MAX_SUBDEVICES: usize>(
+        &self,
+    ) -> Result<MetricsSnapshot<MAX_SUBDEVICES>, Error> {
+        self.maindevice.metrics().await
+    }
+
+    /// Find where SubDevices in `expected` can no longer be reached. See
+    /// [`MainDevice::locate_breaks`].
+    pub async fn locate_breaks<const MAX_SUBDEVICES: usize>(
+        &self,
+        expected: &NetworkTopology<MAX_SUBDEVICES>,
+    ) -> Result<heapless::Vec<LinkBreak, MAX_SUBDEVICES>, Error> {
+        self.maindevice.locate_breaks(expected).await
+    }
+
+    /// Read a register from the SubDevice with the given configured station address.
+    pub async fn register_read<T>(
+        &self,
+        configured_address: ConfiguredAddress,
+        register: impl Into<u16>,
+    ) -> Result<T, Error>
+    where
+        T: EtherCrabWireReadSized,
+    {
+        Command::fprd(configured_address, register.into())
+            .receive(self.maindevice)
+            .await
+    }
+
+    /// Read `buf.len()` bytes of memory from the SubDevice with the given configured station
+    /// address, starting at `register`. See [`WrappedRead::receive_into`](crate::WrappedRead::receive_into).
+    pub async fn register_read_into(
+        &self,
+        configured_address: ConfiguredAddress,
+        register: impl Into<u16>,
+        buf: &mut [u8],
+    ) -> Result<(), Error> {
+        Command::fprd(configured_address, register.into())
+            .receive_into(self.maindevice, buf)
+            .await
+    }
+
+    /// Read an SDO from the SubDevice at `subdevice_index` in `group`.
+    ///
+    /// The SubDevice is only borrowed immutably, however this method will return
+    /// [`Error::Borrow`] if it is currently borrowed with
+    /// [`SubDeviceGroup::subdevice`]. See [`SubDeviceRef::sdo_read`](crate::SubDeviceRef::sdo_read).
+    pub async fn sdo_read<T, const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S, DC>(
+        &self,
+        group: &SubDeviceGroup<MAX_SUBDEVICES, MAX_PDI, S, DC>,
+        subdevice_index: usize,
+        index: u16,
+        sub_index: impl Into<SubIndex>,
+    ) -> Result<T, Error>
+    where
+        T: EtherCrabWireReadSized,
+    {
+        group
+            .subdevice_shared(self.maindevice, subdevice_index)?
+            .sdo_read(index, sub_index)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<MainDevice<'static>>();
+        assert_send_sync::<Diagnostics<'static>>();
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{sim::fixture::*, ConfiguredAddress, PduStorage, RegisterAddress, SubDeviceState};
+
+    #[test]
+    fn concurrent_diagnostics() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            let diagnostics = maindevice.diagnostics();
+
+            let cycle = async {
+                for _ in 0..20 {
+                    group.tx_rx(&maindevice).await.expect("TX/RX");
+                }
+            };
+
+            // Two tasks reading from the same mailbox at once
+            let sdo_a = diagnostics.sdo_read::<u16, 8, 32, _, _>(&group, 3, 0x2000, 0);
+            let sdo_b = diagnostics.sdo_read::<u32, 8, 32, _, _>(&group, 3, 0x1018, 2);
+
+            let registers = async {
+                (
+                    diagnostics
+                        .register_read::<u16>(
+                            ConfiguredAddress::new(0x1003),
+                            RegisterAddress::ConfiguredStationAddress,
+                        )
+                        .await,
+                    diagnostics.al_status_summary().await.map(|s| s.state),
+                )
+            };
+
+            let (((), (sdo_a, sdo_b)), registers) = futures_lite::future::zip(
+                futures_lite::future::zip(cycle, futures_lite::future::zip(sdo_a, sdo_b)),
+                registers,
+            )
+            .await;
+
+            assert_eq!(sdo_a, Ok(0x1234));
+            assert_eq!(sdo_b, Ok(0x1234));
+            assert_eq!(registers, (Ok(0x1003), Ok(SubDeviceState::Op)));
+            assert_eq!(diagnostics.num_subdevices(), 4);
+        });
+    }
+}
@@ -0,0 +1,503 @@
+//! Human readable rendering of EtherCAT frames and PDUs for logging.
+
+use crate::{
+    al_control::AlControl,
+    al_status_code::AlStatusCode,
+    dl_status::DlStatus,
+    error_counters::{ErrorCounters, ERROR_COUNTERS_LEN},
+    ethernet::EthernetFrame,
+    fmmu::Fmmu,
+    pdu_loop::{frame_header::EthercatFrameHeader, pdu_header::PduHeader},
+    sync_manager_channel::SyncManagerChannel,
+    Command, ConfiguredAddress, FrameDirection, Reads, Writes, ETHERCAT_ETHERTYPE,
+};
+use core::fmt;
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
+
+/// Maximum number of payload bytes shown for PDUs without a decoded payload.
+const MAX_HEX_BYTES: usize = 16;
+
+/// Renders a complete Ethernet II frame containing EtherCAT PDUs in a human readable form.
+///
+/// Each PDU is shown with its index, command and address, payload length and working counter. The
+/// payload of reads and writes of well known registers, e.g. AL status, DL status or sync manager
+/// configuration, is decoded. Other payloads are shown as hex, shortened to 16 bytes.
+///
+/// PDUs are separated by `; ` so a frame fits on one log line. Use the alternate flag (`{:#}`) to
+/// put each PDU on its own line instead.
+///
+/// Frames are classed as returned by the SubDevices if their source MAC address has the locally
+/// administered bit set. Read payloads are only decoded in returned frames, as they are empty when
+/// sent.
+///
+/// # Examples
+///
+/// ```rust
+/// use ethercrab::FrameDissector;
+///
+/// let frame = [
+///     // Ethernet II header, SubDevices set the locally administered bit in the source address
+///     0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x12, 0x10, 0x10, 0x10, 0x10, 0x10, 0x88, 0xa4,
+///     // EtherCAT header
+///     0x0e, 0x10,
+///     // FPRD of the AL status register of SubDevice 0x1001, returning OP
+///     0x04, 0x01, 0x01, 0x10, 0x30, 0x01, 0x02, 0x00, 0x00, 0x00, 0x08, 0x00, 0x01, 0x00,
+/// ];
+///
+/// assert_eq!(
+///     FrameDissector::new(&frame).to_string(),
+///     "12-10-10-10-10-10 -> ff-ff-ff-ff-ff-ff received, 14 bytes: \
+///     #1 FPRD(addr 0x1001, reg 0x0130) AL status, 2 bytes, WKC 1: Operational"
+/// );
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct FrameDissector<'frame> {
+    ethernet_frame: &'frame [u8],
+}
+
+impl<'frame> FrameDissector<'frame> {
+    /// Render the given Ethernet II frame.
+    pub fn new(ethernet_frame: &'frame [u8]) -> Self {
+        Self { ethernet_frame }
+    }
+}
+
+impl fmt::Display for FrameDissector<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Ok(frame) = EthernetFrame::new_checked(self.ethernet_frame) else {
+            return write!(
+                f,
+                "truncated Ethernet frame, {} bytes",
+                self.ethernet_frame.len()
+            );
+        };
+
+        let src = frame.src_addr();
+        let dst = frame.dst_addr();
+
+        if frame.inner_ethertype() != ETHERCAT_ETHERTYPE {
+            return write!(
+                f,
+                "{} -> {} ethertype {:#06x}, not EtherCAT",
+                src,
+                dst,
+                frame.inner_ethertype()
+            );
+        }
+
+        let direction = if src.0[0] & 0x02 != 0 {
+            FrameDirection::Received
+        } else {
+            FrameDirection::Sent
+        };
+
+        let payload = frame.inner_payload();
+
+        let Ok(header) = EthercatFrameHeader::unpack_from_slice(payload) else {
+            return write!(f, "{} -> {} truncated EtherCAT header", src, dst);
+        };
+
+        let pdus = payload
+            .get(EthercatFrameHeader::PACKED_LEN..)
+            .unwrap_or_default();
+        let pdus = pdus.get(..usize::from(header.payload_len)).unwrap_or(pdus);
+
+        write!(
+            f,
+            "{} -> {} {}, {} bytes:",
+            src,
+            dst,
+            match direction {
+                FrameDirection::Sent => "sent",
+                FrameDirection::Received => "received",
+            },
+            header.payload_len
+        )?;
+
+        if !f.alternate() {
+            f.write_str(" ")?;
+        }
+
+        fmt::Display::fmt(&PduDissector::new(direction, pdus), f)
+    }
+}
+
+/// Renders the PDUs of an EtherCAT frame, without the Ethernet and EtherCAT headers.
+///
+/// The PDUs are rendered the same way as [`FrameDissector`]. `direction` controls whether read
+/// payloads are decoded, as they only contain data in frames returned by the SubDevices.
+#[derive(Debug, Copy, Clone)]
+pub struct PduDissector<'frame> {
+    direction: FrameDirection,
+    pdus: &'frame [u8],
+    /// Omit PDU indices and show all payload bytes, one PDU per line, so renderings of the same
+    /// traffic can be compared.
+    transcript: bool,
+}
+
+impl<'frame> PduDissector<'frame> {
+    /// Render the given PDUs.
+    pub fn new(direction: FrameDirection, pdus: &'frame [u8]) -> Self {
+        Self {
+            direction,
+            pdus,
+            transcript: false,
+        }
+    }
+
+    /// Render PDUs returned by the SubDevices for a transcript: one PDU per line, without PDU
+    /// indices, which depend on PDU storage, and with complete payloads.
+    #[cfg(feature = "sim")]
+    pub(crate) fn transcript(pdus: &'frame [u8]) -> Self {
+        Self {
+            direction: FrameDirection::Received,
+            pdus,
+            transcript: true,
+        }
+    }
+}
+
+impl fmt::Display for PduDissector<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pdus = self.pdus;
+        let mut first = true;
+
+        while !pdus.is_empty() {
+            if self.transcript {
+                if !first {
+                    f.write_str("\n")?;
+                }
+            } else if f.alternate() {
+                f.write_str("\n    ")?;
+            } else if !first {
+                f.write_str("; ")?;
+            }
+
+            first = false;
+
+            let Ok(header) = PduHeader::unpack_from_slice(pdus) else {
+                return write!(f, "truncated PDU header");
+            };
+
+            let data_len = usize::from(header.flags.len());
+
+            let (Some(data), Some(working_counter)) = (
+                pdus.get(PduHeader::PACKED_LEN..(PduHeader::PACKED_LEN + data_len)),
+                pdus.get((PduHeader::PACKED_LEN + data_len)..)
+                    .and_then(|rest| u16::unpack_from_slice(rest).ok()),
+            ) else {
+                return write!(f, "#{} truncated PDU, {} bytes", header.index, data_len);
+            };
+
+            if !self.transcript {
+                write!(f, "#{} ", header.index)?;
+            }
+
+            match Command::parse(header.command_code, header.command_raw) {
+                Some(command) => {
+                    write!(f, "{}", command)?;
+
+                    let register = register(command);
+
+                    if let Some(name) = register.and_then(register_name) {
+                        write!(f, " {}", name)?;
+                    }
+
+                    write!(f, ", {} bytes, WKC {}", data_len, working_counter)?;
+
+                    // Read payloads are empty until the frame has passed through the network
+                    let has_data = match command {
+                        Command::Read(_) => self.direction == FrameDirection::Received,
+                        _ => true,
+                    };
+
+                    if has_data && !data.is_empty() {
+                        f.write_str(": ")?;
+
+                        let max_bytes = if self.transcript {
+                            usize::MAX
+                        } else {
+                            MAX_HEX_BYTES
+                        };
+
+                        match register.and_then(|register| decode(f, register, data)) {
+                            Some(result) => result?,
+                            None => hex(f, data, max_bytes)?,
+                        }
+                    }
+                }
+                None => {
+                    write!(
+                        f,
+                        "unknown command {:#04x}, {} bytes, WKC {}",
+                        header.command_code, data_len, working_counter
+                    )?;
+                }
+            }
+
+            if !header.flags.more_follows {
+                break;
+            }
+
+            pdus = pdus
+                .get((PduHeader::PACKED_LEN + data_len + 2)..)
+                .unwrap_or_default();
+        }
+
+        Ok(())
+    }
+}
+
+/// The register addressed by a physical command, or `None` for logical commands.
+fn register(command: Command) -> Option<u16> {
+    match command {
+        Command::Read(
+            Reads::Aprd { register, .. }
+            | Reads::Fprd { register, .. }
+            | Reads::Brd { register, .. }
+            | Reads::Frmw { register, .. },
+        )
+        | Command::Write(
+            Writes::Apwr { register, .. }
+            | Writes::Fpwr { register, .. }
+            | Writes::Bwr { register, .. },
+        ) => Some(register),
+        _ => None,
+    }
+}
+
+fn register_name(register: u16) -> Option<&'static str> {
+    let name = match register {
+        0x0000 => "ESC type",
+        0x0010 => "station address",
+        0x0012 => "station alias",
+        0x0101 => "DL control",
+        0x0110 => "DL status",
+        0x0120 => "AL control",
+        0x0130 => "AL status",
+        0x0134 => "AL status code",
+        0x0300 => "error counters",
+        0x0400 => "watchdog divider",
+        0x0440 => "SM watchdog status",
+        0x0500 => "SII config",
+        0x0502 => "SII control",
+        0x0504 => "SII address",
+        0x0508 => "SII data",
+        0x0600..=0x06ff if register % 16 == 0 => "FMMU",
+        0x0800..=0x087f if register % 8 == 0 => "SM",
+        0x0900 => "DC receive time port 0",
+        0x0910 => "DC system time",
+        0x0918 => "DC receive time",
+        0x0920 => "DC system time offset",
+        0x0928 => "DC transmission delay",
+        0x092c => "DC system time difference",
+        0x0980 => "DC cyclic unit control",
+        0x0981 => "DC sync active",
+        0x0990 => "DC start time",
+        0x09a0 => "DC SYNC0 cycle time",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// Decode the payload of a well known register, returning `None` without writing anything if the
+/// payload isn't understood.
+fn decode(f: &mut fmt::Formatter<'_>, register: u16, data: &[u8]) -> Option<fmt::Result> {
+    let u16_le = || {
+        data.get(0..2)
+            .map(|raw| u16::from_le_bytes([raw[0], raw[1]]))
+    };
+    let u32_le = || {
+        data.get(0..4)
+            .map(|raw| u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]))
+    };
+
+    let result = match register {
+        0x0010 | 0x0012 => write!(f, "{:#06x}", u16_le()?),
+        0x0110 => {
+            let status = DlStatus::unpack_from_slice(data).ok()?;
+            let link = |port| if status.link(port) { '1' } else { '-' };
+
+            write!(f, "link {} {} {} {}", link(0), link(1), link(2), link(3))
+        }
+        0x0120 | 0x0130 => {
+            let control = AlControl::unpack_from_slice(data).ok()?;
+
+            let flag = match (control.error, register) {
+                (false, _) => "",
+                (true, 0x0120) => " (ack)",
+                (true, _) => " (error)",
+            };
+
+            write!(f, "{}{}", control.state, flag)
+        }
+        0x0134 => write!(f, "{}", AlStatusCode::unpack_from_slice(data).ok()?),
+        0x0300 if data.len() >= usize::from(ERROR_COUNTERS_LEN) => write!(
+            f,
+            "{} RX errors",
+            ErrorCounters::parse(ConfiguredAddress::new(0), data)
+                .ok()?
+                .total_rx_errors()
+        ),
+        0x0440 => f.write_str(if u16_le()? & 1 == 0 {
+            "expired"
+        } else {
+            "running"
+        }),
+        0x0600..=0x06ff if register % 16 == 0 && data.len() == Fmmu::PACKED_LEN => {
+            write!(f, "{}", Fmmu::unpack_from_slice(data).ok()?)
+        }
+        0x0800..=0x087f if register % 8 == 0 && data.len() == SyncManagerChannel::PACKED_LEN => {
+            write!(f, "{}", SyncManagerChannel::unpack_from_slice(data).ok()?)
+        }
+        0x0910 | 0x0918 | 0x0990 if data.len() == 8 => {
+            write!(f, "{} ns", u64::unpack_from_slice(data).ok()?)
+        }
+        0x0900 | 0x0910 if data.len() == 4 => write!(f, "{} ns", u32_le()?),
+        0x092c => {
+            let raw = u32_le()?;
+            let magnitude = raw & 0x7fff_ffff;
+
+            if raw & 0x8000_0000 != 0 {
+                write!(f, "-{} ns", magnitude)
+            } else {
+                write!(f, "{} ns", magnitude)
+            }
+        }
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+fn hex(f: &mut fmt::Formatter<'_>, data: &[u8], max_bytes: usize) -> fmt::Result {
+    for (i, byte) in data.iter().take(max_bytes).enumerate() {
+        if i > 0 {
+            f.write_str(" ")?;
+        }
+
+        write!(f, "{:02x}", byte)?;
+    }
+
+    if data.len() > max_bytes {
+        write!(f, " ... ({} more)", data.len() - max_bytes)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PDU with the given command code, address words, index and data.
+    fn pdu(command: u8, index: u8, raw: [u8; 4], data: &[u8], wkc: u16, more: bool) -> Vec<u8> {
+        let mut pdu = vec![command, index];
+
+        pdu.extend_from_slice(&raw);
+
+        let flags = data.len() as u16 | if more { 0x8000 } else { 0 };
+
+        pdu.extend_from_slice(&flags.to_le_bytes());
+        pdu.extend_from_slice(&[0, 0]);
+        pdu.extend_from_slice(data);
+        pdu.extend_from_slice(&wkc.to_le_bytes());
+
+        pdu
+    }
+
+    #[test]
+    fn decode_registers() {
+        let mut pdus = Vec::new();
+
+        // FPRD AL status code
+        pdus.extend(pdu(
+            0x04,
+            1,
+            [0x00, 0x10, 0x34, 0x01],
+            &[0x1b, 0x00],
+            1,
+            true,
+        ));
+        // BWR AL control, INIT with ack
+        pdus.extend(pdu(
+            0x08,
+            2,
+            [0x00, 0x00, 0x20, 0x01],
+            &[0x11, 0x00],
+            3,
+            true,
+        ));
+        // LRW process data
+        pdus.extend(pdu(0x0c, 3, [0x00, 0x00, 0x00, 0x00], &[0xaa; 20], 3, true));
+        // FPRD DC system time difference
+        pdus.extend(pdu(
+            0x04,
+            4,
+            [0x01, 0x10, 0x2c, 0x09],
+            &[0x64, 0x00, 0x00, 0x80],
+            1,
+            false,
+        ));
+
+        let received = PduDissector::new(FrameDirection::Received, &pdus).to_string();
+
+        assert_eq!(
+            received,
+            format!(
+                "#1 FPRD(addr 0x1000, reg 0x0134) AL status code, 2 bytes, WKC 1: {}; \
+                #2 BWR(addr 0x0000, reg 0x0120) AL control, 2 bytes, WKC 3: {} (ack); \
+                #3 LRW(addr 0x00000000), 20 bytes, WKC 3: \
+                aa aa aa aa aa aa aa aa aa aa aa aa aa aa aa aa ... (4 more); \
+                #4 FPRD(addr 0x1001, reg 0x092c) DC system time difference, 4 bytes, WKC 1: -100 ns",
+                AlStatusCode::SyncManagerWatchdog,
+                crate::SubDeviceState::Init,
+            )
+        );
+
+        // Reads are not decoded before they've been through the network
+        let sent = PduDissector::new(FrameDirection::Sent, &pdus[..14]).to_string();
+
+        assert_eq!(
+            sent,
+            "#1 FPRD(addr 0x1000, reg 0x0134) AL status code, 2 bytes, WKC 1"
+        );
+    }
+
+    #[test]
+    fn alternate() {
+        let mut pdus = Vec::new();
+
+        pdus.extend(pdu(0x07, 1, [0x00, 0x00, 0x30, 0x01], &[0x00; 2], 0, true));
+        pdus.extend(pdu(
+            0x05,
+            2,
+            [0x00, 0x00, 0x10, 0x00],
+            &[0x01, 0x10],
+            1,
+            false,
+        ));
+
+        assert_eq!(
+            format!("{:#}", PduDissector::new(FrameDirection::Sent, &pdus)),
+            "\n    #1 BRD(addr 0x0000, reg 0x0130) AL status, 2 bytes, WKC 0\
+            \n    #2 FPWR(addr 0x0000, reg 0x0010) station address, 2 bytes, WKC 1: 0x1001"
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let pdus = pdu(0x04, 1, [0x00, 0x10, 0x30, 0x01], &[0x08, 0x00], 1, false);
+
+        assert_eq!(
+            PduDissector::new(FrameDirection::Received, &pdus[..12]).to_string(),
+            "#1 truncated PDU, 2 bytes"
+        );
+
+        assert_eq!(
+            FrameDissector::new(&[0u8; 4]).to_string(),
+            "truncated Ethernet frame, 4 bytes"
+        );
+    }
+}
@@ -1,4 +1,7 @@
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// DL status, register `0x0110`.
+///
+/// Defined in ETG1000.4 Table 34.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(not(test), derive(ethercrab_wire::EtherCrabWireRead))]
 #[cfg_attr(
     test,
@@ -6,10 +9,13 @@
 )]
 #[wire(bytes = 2)]
 pub struct DlStatus {
+    /// The EEPROM has been loaded and the PDI is operational.
     #[wire(bits = 1)]
     pub pdi_operational: bool,
+    /// The PDI watchdog has not expired.
     #[wire(bits = 1)]
     pub watchdog_ok: bool,
+    /// Enhanced link detection is enabled.
     #[wire(bits = 1, post_skip = 1)]
     pub extended_link_detection: bool,
     // pub _reserved: bool,
@@ -52,6 +58,19 @@ pub struct DlStatus {
     pub signal_port3: bool,
 }
 
+impl DlStatus {
+    /// Whether a physical link is present on the given port number.
+    pub(crate) fn link(&self, port: u8) -> bool {
+        match port {
+            0 => self.link_port0,
+            1 => self.link_port1,
+            2 => self.link_port2,
+            3 => self.link_port3,
+            _ => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
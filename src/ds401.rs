@@ -0,0 +1,508 @@
+//! Experimental CiA401 (DS401) generic I/O device profile.
+//!
+//! Digital and analog I/O SubDevices expose one object per channel in the object dictionary,
+//! starting at `0x6000` for inputs and `0x7000` for outputs, with each channel `0x10` indices after
+//! the previous one. [`IoDevice`] discovers these channels over CoE and reads and writes their
+//! values in the PDI.
+//!
+//! # Experimental
+//!
+//! Please note the `ds401` module is experimental and may change at any time as the API evolves.
+
+use crate::{
+    error::{saturate_u16, Error as EthercrabError, Item, MailboxError},
+    fmt,
+    subdevice::{pdi::SubDevicePdi, SubDeviceRef},
+};
+
+/// Maximum number of input or output channels of an [`IoDevice`].
+pub const MAX_CHANNELS: usize = 32;
+
+/// Object index of the first input channel.
+const INPUT_AREA: u16 = 0x6000;
+
+/// Object index of the first output channel.
+const OUTPUT_AREA: u16 = 0x7000;
+
+/// Object index offset between consecutive channels.
+const CHANNEL_STRIDE: u16 = 0x10;
+
+/// An input or output channel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Channel {
+    /// Object index of the channel, e.g. `0x6010` for the second input channel.
+    pub index: u16,
+    /// The position of the channel value in the PDI, or `None` if it is not mapped.
+    pub mapping: Option<ChannelMapping>,
+}
+
+/// The position of a channel value in the PDI.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChannelMapping {
+    /// Sub index of the mapped object, e.g. `0x01` for a digital value or `0x11` for an analog
+    /// value.
+    pub sub_index: u8,
+    /// Offset in bits from the start of the SubDevice's inputs or outputs.
+    pub bit_offset: usize,
+    /// Length of the value in bits.
+    pub bit_len: u8,
+}
+
+/// Linear scaling between raw analog values and engineering units.
+///
+/// For example, a ±10V input terminal with a 16 bit value can be scaled with
+/// `AnalogScale::new(-32768, 32767, -10.0, 10.0)`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AnalogScale {
+    /// Raw value corresponding to `min`.
+    pub raw_min: i32,
+    /// Raw value corresponding to `max`.
+    pub raw_max: i32,
+    /// Value in engineering units at `raw_min`.
+    pub min: f32,
+    /// Value in engineering units at `raw_max`.
+    pub max: f32,
+}
+
+impl AnalogScale {
+    /// Create a new scale mapping `raw_min..=raw_max` onto `min..=max`.
+    pub const fn new(raw_min: i32, raw_max: i32, min: f32, max: f32) -> Self {
+        Self {
+            raw_min,
+            raw_max,
+            min,
+            max,
+        }
+    }
+
+    /// Convert a raw value into engineering units.
+    pub fn to_value(&self, raw: i32) -> f32 {
+        self.min + (raw as f32 - self.raw_min as f32) * self.gain()
+    }
+
+    /// Convert a value in engineering units into the nearest raw value, clamped to
+    /// `raw_min..=raw_max`.
+    pub fn to_raw(&self, value: f32) -> i32 {
+        let raw = self.raw_min as f32 + (value - self.min) / self.gain();
+
+        let (lo, hi) = if self.raw_min <= self.raw_max {
+            (self.raw_min, self.raw_max)
+        } else {
+            (self.raw_max, self.raw_min)
+        };
+
+        // Round half away from zero without `f32::round`, which isn't available in `core`.
+        let rounded = if raw >= 0.0 { raw + 0.5 } else { raw - 0.5 };
+
+        (rounded as i32).clamp(lo, hi)
+    }
+
+    fn gain(&self) -> f32 {
+        (self.max - self.min) / (self.raw_max as f32 - self.raw_min as f32)
+    }
+}
+
+/// Input and output channels of an I/O SubDevice.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct ChannelLayout {
+    inputs: heapless::Vec<Channel, MAX_CHANNELS>,
+    outputs: heapless::Vec<Channel, MAX_CHANNELS>,
+}
+
+impl ChannelLayout {
+    fn new(num_inputs: usize, num_outputs: usize) -> Self {
+        let channels = |area: u16, count: usize| {
+            (0..count.min(MAX_CHANNELS))
+                .map(|n| Channel {
+                    index: area + n as u16 * CHANNEL_STRIDE,
+                    mapping: None,
+                })
+                .collect()
+        };
+
+        Self {
+            inputs: channels(INPUT_AREA, num_inputs),
+            outputs: channels(OUTPUT_AREA, num_outputs),
+        }
+    }
+
+    /// Record the position of a mapped PDO entry.
+    ///
+    /// Entries that don't belong to a channel are ignored. If more than one entry of a channel is
+    /// mapped, e.g. status bits alongside an analog value, the widest one is the channel value.
+    fn insert(&mut self, index: u16, sub_index: u8, bit_offset: usize, bit_len: u8) {
+        let area = index & 0xf000;
+
+        let channels = match area {
+            INPUT_AREA => &mut self.inputs,
+            OUTPUT_AREA => &mut self.outputs,
+            _ => return,
+        };
+
+        if index % CHANNEL_STRIDE != 0 || bit_len == 0 {
+            return;
+        }
+
+        let Some(channel) = channels.get_mut(usize::from((index - area) / CHANNEL_STRIDE)) else {
+            return;
+        };
+
+        if channel
+            .mapping
+            .map_or(true, |mapping| bit_len > mapping.bit_len)
+        {
+            channel.mapping = Some(ChannelMapping {
+                sub_index,
+                bit_offset,
+                bit_len,
+            });
+        }
+    }
+}
+
+/// A CiA401 style I/O SubDevice with indexed input and output channels.
+pub struct IoDevice<'a> {
+    /// The EtherCat SubDevice.
+    pub subdevice: SubDeviceRef<'a, SubDevicePdi<'a>>,
+
+    layout: ChannelLayout,
+}
+
+impl<'a> IoDevice<'a> {
+    /// Discover the channels of a SubDevice.
+    ///
+    /// Channel objects are counted from `0x6000` (inputs) and `0x7000` (outputs) until an object
+    /// doesn't exist, then the PDO assignment (`0x1c12` and `0x1c13`) and mapping are read over CoE
+    /// to find each channel's value in the PDI.
+    pub async fn discover(
+        subdevice: SubDeviceRef<'a, SubDevicePdi<'a>>,
+    ) -> Result<Self, EthercrabError> {
+        let num_inputs = Self::count_channels(&subdevice, INPUT_AREA).await?;
+        let num_outputs = Self::count_channels(&subdevice, OUTPUT_AREA).await?;
+
+        let mut layout = ChannelLayout::new(num_inputs, num_outputs);
+
+        for assignment in [0x1c12u16, 0x1c13] {
+            let mut bit_offset = 0usize;
+
+            let num_pdos = subdevice.sdo_read::<u8>(assignment, 0).await?;
+
+            for pdo_idx in 1..=num_pdos {
+                let pdo = subdevice.sdo_read::<u16>(assignment, pdo_idx).await?;
+
+                let num_entries = subdevice.sdo_read::<u8>(pdo, 0).await?;
+
+                for entry_idx in 1..=num_entries {
+                    // Index in the high WORD, then sub index and bit length, ETG1000.6 Table 74
+                    let entry = subdevice.sdo_read::<u32>(pdo, entry_idx).await?;
+
+                    let index = (entry >> 16) as u16;
+                    let sub_index = (entry >> 8) as u8;
+                    let bit_len = entry as u8;
+
+                    layout.insert(index, sub_index, bit_offset, bit_len);
+
+                    bit_offset += usize::from(bit_len);
+                }
+            }
+        }
+
+        fmt::debug!("DS401 channel layout {:?}", layout);
+
+        Ok(Self { subdevice, layout })
+    }
+
+    async fn count_channels(
+        subdevice: &SubDeviceRef<'a, SubDevicePdi<'a>>,
+        area: u16,
+    ) -> Result<usize, EthercrabError> {
+        for n in 0..MAX_CHANNELS {
+            let index = area + n as u16 * CHANNEL_STRIDE;
+
+            match subdevice.sdo_read::<u8>(index, 0).await {
+                Ok(_) => (),
+                Err(e) => match e.cause() {
+                    EthercrabError::Mailbox(MailboxError::Aborted { .. }) => return Ok(n),
+                    _ => return Err(e),
+                },
+            }
+        }
+
+        fmt::warn!(
+            "SubDevice {:#06x} has more than {} channels from {:#06x}",
+            subdevice.configured_address(),
+            MAX_CHANNELS,
+            area
+        );
+
+        Ok(MAX_CHANNELS)
+    }
+
+    /// Input channels, in order.
+    pub fn inputs(&self) -> &[Channel] {
+        &self.layout.inputs
+    }
+
+    /// Output channels, in order.
+    pub fn outputs(&self) -> &[Channel] {
+        &self.layout.outputs
+    }
+
+    /// Get the state of a digital input channel.
+    pub fn digital_input(&self, channel: usize) -> Result<bool, EthercrabError> {
+        let mapping = mapping(&self.layout.inputs, channel)?;
+
+        read_bits(self.subdevice.inputs_raw(), mapping)
+            .map(|raw| raw != 0)
+            .ok_or_else(|| not_mapped(channel))
+    }
+
+    /// Set the state of a digital output channel.
+    pub fn set_digital_output(
+        &mut self,
+        channel: usize,
+        value: bool,
+    ) -> Result<(), EthercrabError> {
+        let mapping = mapping(&self.layout.outputs, channel)?;
+
+        write_bits(self.subdevice.outputs_raw_mut(), mapping, u32::from(value))
+            .ok_or_else(|| not_mapped(channel))
+    }
+
+    /// Get the raw, sign extended value of an analog input channel.
+    pub fn analog_input(&self, channel: usize) -> Result<i32, EthercrabError> {
+        let mapping = mapping(&self.layout.inputs, channel)?;
+
+        read_bits(self.subdevice.inputs_raw(), mapping)
+            .map(|raw| sign_extend(raw, mapping.bit_len))
+            .ok_or_else(|| not_mapped(channel))
+    }
+
+    /// Set the raw value of an analog output channel.
+    ///
+    /// Bits that don't fit in the channel value are discarded.
+    pub fn set_analog_output(&mut self, channel: usize, value: i32) -> Result<(), EthercrabError> {
+        let mapping = mapping(&self.layout.outputs, channel)?;
+
+        write_bits(self.subdevice.outputs_raw_mut(), mapping, value as u32)
+            .ok_or_else(|| not_mapped(channel))
+    }
+
+    /// Get the value of an analog input channel in engineering units.
+    pub fn scaled_input(&self, channel: usize, scale: &AnalogScale) -> Result<f32, EthercrabError> {
+        self.analog_input(channel).map(|raw| scale.to_value(raw))
+    }
+
+    /// Set the value of an analog output channel in engineering units.
+    pub fn set_scaled_output(
+        &mut self,
+        channel: usize,
+        scale: &AnalogScale,
+        value: f32,
+    ) -> Result<(), EthercrabError> {
+        self.set_analog_output(channel, scale.to_raw(value))
+    }
+}
+
+fn mapping(channels: &[Channel], channel: usize) -> Result<ChannelMapping, EthercrabError> {
+    channels
+        .get(channel)
+        .and_then(|channel| channel.mapping)
+        .ok_or_else(|| not_mapped(channel))
+}
+
+fn not_mapped(channel: usize) -> EthercrabError {
+    fmt::error!("DS401 channel {} is not mapped into the PDI", channel);
+
+    EthercrabError::NotFound {
+        item: Item::PdoEntry,
+        index: Some(saturate_u16(channel)),
+    }
+}
+
+/// Read a little endian value of up to 32 bits, or `None` if it doesn't fit in `data`.
+fn read_bits(data: &[u8], mapping: ChannelMapping) -> Option<u32> {
+    let mut value = 0u32;
+
+    for bit in 0..usize::from(mapping.bit_len.min(32)) {
+        let pos = mapping.bit_offset + bit;
+
+        let byte = data.get(pos / 8)?;
+
+        value |= u32::from(byte >> (pos % 8) & 1) << bit;
+    }
+
+    Some(value)
+}
+
+/// Write a little endian value of up to 32 bits, or return `None` if it doesn't fit in `data`.
+fn write_bits(data: &mut [u8], mapping: ChannelMapping, value: u32) -> Option<()> {
+    let bit_len = usize::from(mapping.bit_len.min(32));
+
+    // Don't write anything if the value is cut off
+    data.get((mapping.bit_offset + bit_len - 1) / 8)?;
+
+    for bit in 0..bit_len {
+        let pos = mapping.bit_offset + bit;
+
+        let byte = &mut data[pos / 8];
+
+        if value >> bit & 1 == 1 {
+            *byte |= 1 << (pos % 8);
+        } else {
+            *byte &= !(1 << (pos % 8));
+        }
+    }
+
+    Some(())
+}
+
+fn sign_extend(raw: u32, bit_len: u8) -> i32 {
+    let shift = 32 - u32::from(bit_len.clamp(1, 32));
+
+    ((raw << shift) as i32) >> shift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout() {
+        // EL3102 style analog inputs and EL2002 style digital outputs
+        let mut layout = ChannelLayout::new(2, 2);
+
+        let mut bit_offset = 0;
+
+        for (index, sub_index, bit_len) in [
+            (0x6000, 0x01, 1),
+            (0x6000, 0x02, 1),
+            (0x0000, 0x00, 14),
+            (0x6000, 0x11, 16),
+            (0x6010, 0x01, 1),
+            (0x6010, 0x02, 1),
+            (0x0000, 0x00, 14),
+            (0x6010, 0x11, 16),
+            // Not a channel
+            (0x6001, 0x01, 8),
+        ] {
+            layout.insert(index, sub_index, bit_offset, bit_len);
+
+            bit_offset += usize::from(bit_len);
+        }
+
+        layout.insert(0x7000, 0x01, 0, 1);
+        layout.insert(0x7010, 0x01, 1, 1);
+        // More channels mapped than exist
+        layout.insert(0x7020, 0x01, 2, 1);
+
+        assert_eq!(
+            layout.inputs.as_slice(),
+            &[
+                Channel {
+                    index: 0x6000,
+                    mapping: Some(ChannelMapping {
+                        sub_index: 0x11,
+                        bit_offset: 16,
+                        bit_len: 16
+                    })
+                },
+                Channel {
+                    index: 0x6010,
+                    mapping: Some(ChannelMapping {
+                        sub_index: 0x11,
+                        bit_offset: 48,
+                        bit_len: 16
+                    })
+                },
+            ]
+        );
+
+        assert_eq!(
+            layout.outputs.as_slice(),
+            &[
+                Channel {
+                    index: 0x7000,
+                    mapping: Some(ChannelMapping {
+                        sub_index: 0x01,
+                        bit_offset: 0,
+                        bit_len: 1
+                    })
+                },
+                Channel {
+                    index: 0x7010,
+                    mapping: Some(ChannelMapping {
+                        sub_index: 0x01,
+                        bit_offset: 1,
+                        bit_len: 1
+                    })
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn bits() {
+        let mut data = [0u8; 4];
+
+        let digital = ChannelMapping {
+            sub_index: 0x01,
+            bit_offset: 3,
+            bit_len: 1,
+        };
+
+        assert_eq!(write_bits(&mut data, digital, 1), Some(()));
+        assert_eq!(data, [0b0000_1000, 0, 0, 0]);
+        assert_eq!(read_bits(&data, digital), Some(1));
+
+        let unaligned = ChannelMapping {
+            sub_index: 0x11,
+            bit_offset: 4,
+            bit_len: 16,
+        };
+
+        assert_eq!(write_bits(&mut data, unaligned, (-2i32) as u32), Some(()));
+        assert_eq!(data, [0b1110_1000, 0xff, 0x0f, 0]);
+        assert_eq!(
+            read_bits(&data, unaligned).map(|raw| sign_extend(raw, 16)),
+            Some(-2)
+        );
+
+        // Digital channel is unchanged
+        assert_eq!(read_bits(&data, digital), Some(1));
+
+        let out_of_range = ChannelMapping {
+            sub_index: 0x11,
+            bit_offset: 24,
+            bit_len: 16,
+        };
+
+        assert_eq!(read_bits(&data, out_of_range), None);
+        assert_eq!(write_bits(&mut data, out_of_range, 0xffff), None);
+        assert_eq!(data[3], 0);
+    }
+
+    #[test]
+    fn scale() {
+        let scale = AnalogScale::new(-32768, 32767, -10.0, 10.0);
+
+        assert_eq!(scale.to_value(-32768), -10.0);
+        assert_eq!(scale.to_value(32767), 10.0);
+        assert_eq!(scale.to_raw(10.0), 32767);
+        assert_eq!(scale.to_raw(-10.0), -32768);
+        assert_eq!(scale.to_raw(20.0), 32767);
+        assert_eq!(scale.to_raw(-20.0), -32768);
+
+        // 4-20mA input with 0..32767
+        let scale = AnalogScale::new(0, 32767, 4.0, 20.0);
+
+        assert_eq!(scale.to_raw(12.0), 16384);
+        assert!((scale.to_value(16384) - 12.0).abs() < 0.001);
+    }
+}
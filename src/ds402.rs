@@ -9,11 +9,14 @@
 //! Use at your own risk.
 
 use crate::{
-    error::Error as EthercrabError,
+    error::{Ds402Error, Error as EthercrabError, Item, MailboxError},
     fmt,
     subdevice::{pdi::SubDevicePdi, SubDeviceRef},
+    timer_factory::IntoTimeout,
+    SubDevice,
 };
-use ethercrab_wire::EtherCrabWireRead;
+use core::{future::Future, ops::Deref, time::Duration};
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireReadSized};
 
 smlang::statemachine! {
     transitions: {
@@ -97,6 +100,23 @@ impl core::fmt::Debug for States {
     }
 }
 
+#[cfg(feature = "defmt")]
+impl defmt::Format for States {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::Fault => defmt::write!(f, "Fault"),
+            Self::FaultReactionActive => defmt::write!(f, "FaultReactionActive"),
+            Self::NotReadyToSwitchOn => defmt::write!(f, "NotReadyToSwitchOn"),
+            Self::OpEnable => defmt::write!(f, "OpEnable"),
+            Self::QuickStopActive => defmt::write!(f, "QuickStopActive"),
+            Self::ReadyToSwitchOn => defmt::write!(f, "ReadyToSwitchOn"),
+            Self::ResettingFault => defmt::write!(f, "ResettingFault"),
+            Self::SwitchOnDisabled => defmt::write!(f, "SwitchOnDisabled"),
+            Self::SwitchedOn => defmt::write!(f, "SwitchedOn"),
+        }
+    }
+}
+
 impl Clone for States {
     fn clone(&self) -> Self {
         match self {
@@ -118,12 +138,384 @@ impl Clone for States {
 pub struct Ds402<'a> {
     /// The EtherCat SubDevice.
     pub subdevice: SubDeviceRef<'a, SubDevicePdi<'a>>,
+
+    layout: PdoLayout,
+
+    scaling: UnitScaling,
 }
 
 impl<'a> Ds402<'a> {
     /// Create a new DS402 state machine.
     pub fn new(subdevice: SubDeviceRef<'a, SubDevicePdi<'a>>) -> Result<Self, EthercrabError> {
-        Ok(Self { subdevice })
+        Ok(Self {
+            subdevice,
+            layout: PdoLayout::default(),
+            scaling: UnitScaling::default(),
+        })
+    }
+
+    /// Get the positions of DS402 objects in the process data, used by the typed accessors like
+    /// [`set_target_position`](Ds402::set_target_position).
+    pub fn pdo_layout(&self) -> PdoLayout {
+        self.layout
+    }
+
+    /// Set the positions of DS402 objects in the process data, e.g. for SubDevices whose PDO
+    /// mapping can't be read with [`read_pdo_layout`](Ds402::read_pdo_layout).
+    pub fn set_pdo_layout(&mut self, layout: PdoLayout) {
+        self.layout = layout;
+    }
+
+    /// Find DS402 objects in the SubDevice's process data by reading its PDO assignment (`0x1c12`
+    /// and `0x1c13`) and mapping over CoE.
+    ///
+    /// The layout is stored and used by the typed accessors like
+    /// [`set_target_position`](Ds402::set_target_position). Objects that don't start on a byte
+    /// boundary are ignored.
+    pub async fn read_pdo_layout(&mut self) -> Result<PdoLayout, EthercrabError> {
+        let mut layout = PdoLayout::default();
+
+        for assignment in [0x1c12u16, 0x1c13] {
+            let mut bit_offset = 0usize;
+
+            let num_pdos = self.subdevice.sdo_read::<u8>(assignment, 0).await?;
+
+            for pdo_idx in 1..=num_pdos {
+                let pdo = self.subdevice.sdo_read::<u16>(assignment, pdo_idx).await?;
+
+                let num_entries = self.subdevice.sdo_read::<u8>(pdo, 0).await?;
+
+                for entry_idx in 1..=num_entries {
+                    // Index in the high WORD, then sub index and bit length, ETG1000.6 Table 74
+                    let entry = self.subdevice.sdo_read::<u32>(pdo, entry_idx).await?;
+
+                    let index = (entry >> 16) as u16;
+                    let bit_len = (entry & 0xff) as usize;
+
+                    if bit_offset % 8 == 0 {
+                        layout.insert(index, bit_offset / 8);
+                    }
+
+                    bit_offset += bit_len;
+                }
+            }
+        }
+
+        fmt::debug!("DS402 PDO layout {:?}", layout);
+
+        self.layout = layout;
+
+        Ok(layout)
+    }
+
+    /// Read the current mode of operation from object `0x6061`.
+    pub async fn operation_mode(&self) -> Result<OperationMode, EthercrabError> {
+        self.subdevice.sdo_read(0x6061, 0).await
+    }
+
+    /// Switch the drive into another mode of operation.
+    ///
+    /// The mode is written to object `0x6060`, as well as to the outputs if it is mapped there, and
+    /// this method waits until the drive reports the new mode in object `0x6061`.
+    ///
+    /// Returns [`Error::Timeout`](crate::error::Error::Timeout) if the drive does not switch modes
+    /// within the [`state_transition`](crate::Timeouts::state_transition) timeout.
+    pub async fn set_operation_mode(&mut self, mode: OperationMode) -> Result<(), EthercrabError> {
+        if self.layout.operation_mode.is_some() {
+            self.write_output(self.layout.operation_mode, 0x6060, &[i8::from(mode) as u8])?;
+        }
+
+        self.subdevice.sdo_write(0x6060, 0, mode).await?;
+
+        let timeouts = self.subdevice.maindevice.timeouts;
+
+        async {
+            loop {
+                if self.operation_mode().await? == mode {
+                    break Ok(());
+                }
+
+                timeouts.loop_tick().await;
+            }
+        }
+        .timeout(timeouts.state_transition)
+        .await
+    }
+
+    /// Home the axis using the CiA402 homing mode.
+    ///
+    /// This switches to [`OperationMode::Homing`], writes the homing parameters over CoE, enables
+    /// the drive if necessary and starts homing with the control word. The mode of operation is
+    /// left as homing when this method returns.
+    ///
+    /// Returns [`Ds402Error::Homing`] if the drive reports a homing error,
+    /// [`Ds402Error::Fault`] if the drive faults, or [`Error::Timeout`](crate::error::Error::Timeout)
+    /// if homing does not complete within [`HomingConfig::timeout`].
+    ///
+    /// See [`enable`](Ds402::enable) for a description of `cycle`.
+    pub async fn home<F, Fut>(
+        &mut self,
+        config: HomingConfig,
+        mut cycle: F,
+    ) -> Result<(), EthercrabError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), EthercrabError>>,
+    {
+        self.set_operation_mode(OperationMode::Homing).await?;
+
+        self.subdevice.sdo_write(0x6098, 0, config.method).await?;
+        self.subdevice
+            .sdo_write(0x6099, 1, config.switch_search_speed)
+            .await?;
+        self.subdevice
+            .sdo_write(0x6099, 2, config.zero_search_speed)
+            .await?;
+        self.subdevice
+            .sdo_write(0x609a, 0, config.acceleration)
+            .await?;
+        self.subdevice.sdo_write(0x607c, 0, config.offset).await?;
+
+        self.enable(&mut cycle).await?;
+
+        let result = async {
+            // Homing starts on a rising edge of the start bit
+            self.set_control_word(&ControlWord::STATE_ENABLE_OP);
+
+            cycle().await?;
+
+            self.set_control_word(&(ControlWord::STATE_ENABLE_OP | ControlWord::HOMING_START));
+
+            // The status of a previous homing run may still be reported in the first cycle after
+            // homing is started, so it is ignored.
+            cycle().await?;
+
+            loop {
+                cycle().await?;
+
+                if let Some(result) = self.status_word().homing_result() {
+                    break result.map_err(EthercrabError::Ds402);
+                }
+            }
+        }
+        .timeout(config.timeout)
+        .await;
+
+        self.set_control_word(&ControlWord::STATE_ENABLE_OP);
+
+        result
+    }
+
+    /// Set the target position (`0x607a`) used in the PP, IP and CSP modes.
+    pub fn set_target_position(&mut self, position: i32) -> Result<(), EthercrabError> {
+        self.write_output(self.layout.target_position, 0x607a, &position.to_le_bytes())
+    }
+
+    /// Set the target velocity (`0x60ff`) used in the PV and CSV modes.
+    pub fn set_target_velocity(&mut self, velocity: i32) -> Result<(), EthercrabError> {
+        self.write_output(self.layout.target_velocity, 0x60ff, &velocity.to_le_bytes())
+    }
+
+    /// Set the target torque (`0x6071`) used in the PT and CST modes.
+    pub fn set_target_torque(&mut self, torque: i16) -> Result<(), EthercrabError> {
+        self.write_output(self.layout.target_torque, 0x6071, &torque.to_le_bytes())
+    }
+
+    /// Get the actual position (`0x6064`).
+    pub fn position_actual(&self) -> Result<i32, EthercrabError> {
+        self.read_input(self.layout.position_actual, 0x6064)
+    }
+
+    /// Get the actual velocity (`0x606c`).
+    pub fn velocity_actual(&self) -> Result<i32, EthercrabError> {
+        self.read_input(self.layout.velocity_actual, 0x606c)
+    }
+
+    /// Get the actual torque (`0x6077`).
+    pub fn torque_actual(&self) -> Result<i16, EthercrabError> {
+        self.read_input(self.layout.torque_actual, 0x6077)
+    }
+
+    /// Get the scaling used by the `*_units` accessors like
+    /// [`set_target_position_units`](Ds402::set_target_position_units).
+    pub fn unit_scaling(&self) -> UnitScaling {
+        self.scaling
+    }
+
+    /// Set the scaling used by the `*_units` accessors.
+    pub fn set_unit_scaling(&mut self, scaling: UnitScaling) {
+        self.scaling = scaling;
+    }
+
+    /// Read the position encoder resolution (`0x608f`), gear ratio (`0x6091`) and feed constant
+    /// (`0x6092`) over CoE and use them for the `*_units` accessors.
+    ///
+    /// Objects the drive doesn't support are left at `1`. The velocity time base of the current
+    /// scaling is kept.
+    ///
+    /// This is only useful for drives that report positions in encoder increments and leave it to
+    /// the MainDevice to apply these factors.
+    pub async fn read_unit_scaling(&mut self) -> Result<UnitScaling, EthercrabError> {
+        let mut scaling = UnitScaling {
+            velocity_time_base: self.scaling.velocity_time_base,
+            ..UnitScaling::default()
+        };
+
+        if let Some(resolution) = self.read_ratio(0x608f).await? {
+            scaling.encoder_resolution = resolution;
+        }
+
+        if let Some(gear_ratio) = self.read_ratio(0x6091).await? {
+            scaling.gear_ratio = gear_ratio;
+        }
+
+        if let Some(feed_constant) = self.read_ratio(0x6092).await? {
+            scaling.feed_constant = feed_constant;
+        }
+
+        fmt::debug!("DS402 unit scaling {:?}", scaling);
+
+        self.scaling = scaling;
+
+        Ok(scaling)
+    }
+
+    /// Read a ratio stored as a numerator in sub index 1 and denominator in sub index 2, or `None`
+    /// if the object doesn't exist or the denominator is zero.
+    async fn read_ratio(&self, index: u16) -> Result<Option<f64>, EthercrabError> {
+        let read = async {
+            let numerator = self.subdevice.sdo_read::<u32>(index, 1).await?;
+            let denominator = self.subdevice.sdo_read::<u32>(index, 2).await?;
+
+            Ok((denominator != 0).then(|| f64::from(numerator) / f64::from(denominator)))
+        };
+
+        let result: Result<_, EthercrabError> = read.await;
+
+        match result {
+            Err(e) => match e.cause() {
+                EthercrabError::Mailbox(MailboxError::Aborted { .. }) => Ok(None),
+                _ => Err(e),
+            },
+            result => result,
+        }
+    }
+
+    /// Set the target position (`0x607a`) in engineering units.
+    pub fn set_target_position_units(&mut self, position: f64) -> Result<(), EthercrabError> {
+        self.set_target_position(self.scaling.position_to_raw(position))
+    }
+
+    /// Set the target velocity (`0x60ff`) in engineering units.
+    pub fn set_target_velocity_units(&mut self, velocity: f64) -> Result<(), EthercrabError> {
+        self.set_target_velocity(self.scaling.velocity_to_raw(velocity))
+    }
+
+    /// Get the actual position (`0x6064`) in engineering units.
+    pub fn position_actual_units(&self) -> Result<f64, EthercrabError> {
+        self.position_actual()
+            .map(|raw| self.scaling.position_to_units(raw))
+    }
+
+    /// Get the actual velocity (`0x606c`) in engineering units.
+    pub fn velocity_actual_units(&self) -> Result<f64, EthercrabError> {
+        self.velocity_actual()
+            .map(|raw| self.scaling.velocity_to_units(raw))
+    }
+
+    /// Configure a touch probe, or disable it if `config` is `None`.
+    ///
+    /// The touch probe function (`0x60b8`) must be mapped into the outputs. The configuration of
+    /// the other probe is left unchanged.
+    pub fn set_touch_probe(
+        &mut self,
+        probe: TouchProbe,
+        config: Option<TouchProbeConfig>,
+    ) -> Result<(), EthercrabError> {
+        let offset = self.layout.touch_probe_function;
+
+        let current = offset
+            .and_then(|offset| self.subdevice.outputs_raw().get(offset..))
+            .ok_or_else(|| not_mapped(0x60b8))?;
+
+        let mut function = u16::unpack_from_slice(current)?.to_le_bytes();
+
+        function[probe.byte()] = config.map(|config| config.bits()).unwrap_or(0);
+
+        self.write_output(offset, 0x60b8, &function)
+    }
+
+    /// Get the touch probe status (`0x60b9`).
+    pub fn touch_probe_status(&self) -> Result<TouchProbeStatus, EthercrabError> {
+        self.read_input::<u16>(self.layout.touch_probe_status, 0x60b9)
+            .map(TouchProbeStatus::from_bits_truncate)
+    }
+
+    /// Get the position latched by a touch probe on the given edge, or `None` if no position has
+    /// been stored since the probe was enabled.
+    pub fn touch_probe_position(
+        &self,
+        probe: TouchProbe,
+        edge: TouchProbeEdge,
+    ) -> Result<Option<i32>, EthercrabError> {
+        let (stored, offset, index) = match (probe, edge) {
+            (TouchProbe::One, TouchProbeEdge::Positive) => (
+                TouchProbeStatus::PROBE_1_POSITIVE_STORED,
+                self.layout.touch_probe_1_positive,
+                0x60ba,
+            ),
+            (TouchProbe::One, TouchProbeEdge::Negative) => (
+                TouchProbeStatus::PROBE_1_NEGATIVE_STORED,
+                self.layout.touch_probe_1_negative,
+                0x60bb,
+            ),
+            (TouchProbe::Two, TouchProbeEdge::Positive) => (
+                TouchProbeStatus::PROBE_2_POSITIVE_STORED,
+                self.layout.touch_probe_2_positive,
+                0x60bc,
+            ),
+            (TouchProbe::Two, TouchProbeEdge::Negative) => (
+                TouchProbeStatus::PROBE_2_NEGATIVE_STORED,
+                self.layout.touch_probe_2_negative,
+                0x60bd,
+            ),
+        };
+
+        if !self.touch_probe_status()?.contains(stored) {
+            return Ok(None);
+        }
+
+        self.read_input(offset, index).map(Some)
+    }
+
+    fn write_output(
+        &mut self,
+        offset: Option<usize>,
+        index: u16,
+        value: &[u8],
+    ) -> Result<(), EthercrabError> {
+        offset
+            .and_then(|offset| {
+                self.subdevice
+                    .outputs_raw_mut()
+                    .get_mut(offset..)?
+                    .get_mut(..value.len())
+            })
+            .ok_or_else(|| not_mapped(index))?
+            .copy_from_slice(value);
+
+        Ok(())
+    }
+
+    fn read_input<T>(&self, offset: Option<usize>, index: u16) -> Result<T, EthercrabError>
+    where
+        T: EtherCrabWireReadSized,
+    {
+        let data = offset
+            .and_then(|offset| self.subdevice.inputs_raw().get(offset..))
+            .ok_or_else(|| not_mapped(index))?;
+
+        Ok(T::unpack_from_slice(data)?)
     }
 
     fn set_and_read(&mut self, set: &ControlWord, read: StatusWord) -> Result<(), ()> {
@@ -143,6 +535,257 @@ impl<'a> Ds402<'a> {
         StatusWord::from_bits_truncate(status)
     }
 
+    /// Wait until `condition` returns `true` for the status word, returning the status word that
+    /// satisfied it.
+    ///
+    /// The status word is checked each time the group's process data is exchanged by another task.
+    /// See [`SubDeviceRef::wait_for`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::ds402::{Ds402, StatusWord};
+    /// # async fn case(servo: Ds402<'_>) {
+    /// let status = servo
+    ///     .wait_for_status(|status| status.contains(StatusWord::TARGET_REACHED))
+    ///     .await;
+    /// # }
+    /// ```
+    pub async fn wait_for_status<F>(&self, mut condition: F) -> StatusWord
+    where
+        F: FnMut(StatusWord) -> bool,
+    {
+        let mut status = StatusWord::empty();
+
+        self.subdevice
+            .wait_for(|inputs| {
+                status =
+                    StatusWord::from_bits_truncate(fmt::unwrap!(u16::unpack_from_slice(inputs)));
+
+                condition(status)
+            })
+            .await;
+
+        status
+    }
+
+    /// Get the current state of the drive's power state machine.
+    pub fn state(&self) -> PowerState {
+        self.status_word().state()
+    }
+
+    /// Get the DS402 control word currently written to the outputs.
+    pub fn control_word(&self) -> ControlWord {
+        let control = fmt::unwrap!(u16::unpack_from_slice(self.subdevice.outputs_raw()));
+
+        ControlWord::from_bits_truncate(control)
+    }
+
+    /// Move the drive through the power state machine into
+    /// [`OperationEnabled`](PowerState::OperationEnabled), resetting any faults on the way.
+    ///
+    /// `cycle` is called after each new control word is written and must exchange the process data
+    /// of the group this SubDevice belongs to, e.g. by calling
+    /// [`SubDeviceGroup::tx_rx`](crate::SubDeviceGroup::tx_rx) and waiting for the next cycle.
+    ///
+    /// Returns [`Error::Timeout`](crate::error::Error::Timeout) if the drive does not become
+    /// enabled within the [`state_transition`](crate::Timeouts::state_transition) timeout.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts, std::ethercat_now};
+    /// use ethercrab::ds402::Ds402;
+    /// use std::time::Duration;
+    ///
+    /// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # async fn example() -> Result<(), ethercrab::error::Error> {
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// let group = maindevice
+    ///     .init_single_group::<8, 32>(ethercat_now)
+    ///     .await?
+    ///     .into_op(&maindevice)
+    ///     .await?;
+    ///
+    /// let mut drive = Ds402::new(group.subdevice(&maindevice, 0)?)?;
+    ///
+    /// let cycle = || async {
+    ///     group.tx_rx(&maindevice).await?;
+    ///
+    ///     smol::Timer::after(Duration::from_millis(1)).await;
+    ///
+    ///     Ok(())
+    /// };
+    ///
+    /// drive.enable(cycle).await?;
+    ///
+    /// // Drive is now enabled. Run the application...
+    ///
+    /// drive.disable(cycle).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn enable<F, Fut>(&mut self, cycle: F) -> Result<(), EthercrabError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), EthercrabError>>,
+    {
+        self.transition(PowerState::enable_command, cycle).await
+    }
+
+    /// Move the drive through the power state machine into
+    /// [`SwitchOnDisabled`](PowerState::SwitchOnDisabled), turning off its power stage.
+    ///
+    /// A drive in the [`Fault`](PowerState::Fault) state is also considered disabled.
+    ///
+    /// See [`enable`](Ds402::enable) for a description of `cycle` and usage examples.
+    pub async fn disable<F, Fut>(&mut self, cycle: F) -> Result<(), EthercrabError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), EthercrabError>>,
+    {
+        self.transition(PowerState::disable_command, cycle).await
+    }
+
+    /// Returns `true` if the drive is reacting to or has stopped because of a fault.
+    pub fn is_faulted(&self) -> bool {
+        self.status_word().contains(StatusWord::FAULT)
+    }
+
+    /// Read details of the drive's current fault over CoE.
+    ///
+    /// The error code is read from object `0x603f`, or taken from an emergency message if the
+    /// drive sends one instead. Recent error codes are read from the pre-defined error field
+    /// (`0x1003`). Either is left empty if the drive doesn't support the object.
+    pub async fn read_fault(&self) -> Result<DriveFault, EthercrabError> {
+        let error_code = match self.subdevice.sdo_read::<u16>(0x603f, 0).await {
+            Ok(code) => Some(code),
+            Err(e) => match e.cause() {
+                EthercrabError::Mailbox(MailboxError::Emergency { error_code, .. }) => {
+                    Some(error_code)
+                }
+                EthercrabError::Mailbox(MailboxError::Aborted { .. }) => None,
+                _ => return Err(e),
+            },
+        };
+
+        let mut history = heapless::Vec::new();
+
+        match self.subdevice.sdo_read::<u8>(0x1003, 0).await {
+            Ok(count) => {
+                for sub_index in (1..=count).take(history.capacity()) {
+                    let entry = self.subdevice.sdo_read::<u32>(0x1003, sub_index).await?;
+
+                    // Can't fail as the number of reads is limited to the capacity
+                    let _ = history.push(entry);
+                }
+            }
+            Err(e) => match e.cause() {
+                EthercrabError::Mailbox(MailboxError::Aborted { .. }) => (),
+                _ => return Err(e),
+            },
+        }
+
+        Ok(DriveFault {
+            error_code,
+            history,
+        })
+    }
+
+    /// Reset a fault, if the drive has one.
+    ///
+    /// The fault is read with [`read_fault`](Ds402::read_fault) before it is reset by a rising
+    /// edge of the fault reset bit in the control word. This method waits until the fault clears
+    /// and leaves the drive in [`SwitchOnDisabled`](PowerState::SwitchOnDisabled). Returns the
+    /// fault that was reset, or `None` if the drive was not faulted.
+    ///
+    /// Returns [`Error::Timeout`](crate::error::Error::Timeout) if the fault does not clear within
+    /// the [`state_transition`](crate::Timeouts::state_transition) timeout, e.g. because its
+    /// cause is still present.
+    ///
+    /// See [`enable`](Ds402::enable) for a description of `cycle`.
+    pub async fn reset_fault<F, Fut>(
+        &mut self,
+        mut cycle: F,
+    ) -> Result<Option<DriveFault>, EthercrabError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), EthercrabError>>,
+    {
+        if !self.is_faulted() {
+            return Ok(None);
+        }
+
+        let fault = self.read_fault().await?;
+
+        fmt::warn!(
+            "Resetting DS402 fault {:?}, status word {:#06x}",
+            fault.error_code,
+            self.status_word().bits()
+        );
+
+        let timeout = self.subdevice.maindevice.timeouts.state_transition;
+
+        async {
+            // The drive only resets on a rising edge, so clear a reset bit left over from earlier
+            if self.control_word().contains(ControlWord::RESET_FAULT) {
+                self.set_control_word(&ControlWord::STATE_DISABLE_VOLTAGE);
+
+                cycle().await?;
+            }
+
+            self.set_control_word(&ControlWord::STATE_FAULT_RESET);
+
+            while self.is_faulted() {
+                cycle().await?;
+            }
+
+            self.set_control_word(&ControlWord::STATE_DISABLE_VOLTAGE);
+
+            cycle().await
+        }
+        .timeout(timeout)
+        .await?;
+
+        Ok(Some(fault))
+    }
+
+    /// Write the control word given by `command` until it returns `None`.
+    async fn transition<F, Fut>(
+        &mut self,
+        command: fn(PowerState, ControlWord) -> Option<ControlWord>,
+        mut cycle: F,
+    ) -> Result<(), EthercrabError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), EthercrabError>>,
+    {
+        let timeout = self.subdevice.maindevice.timeouts.state_transition;
+
+        async {
+            loop {
+                let state = self.state();
+
+                let Some(control) = command(state, self.control_word()) else {
+                    break Ok(());
+                };
+
+                fmt::trace!(
+                    "DS402 state {:?}, control word {:#06x}",
+                    state,
+                    control.bits()
+                );
+
+                self.set_control_word(&control);
+
+                cycle().await?;
+            }
+        }
+        .timeout(timeout)
+        .await
+    }
+
     fn set_control_word(&mut self, state: &ControlWord) {
         let (control, _rest) = self.subdevice.outputs_raw_mut().split_at_mut(2);
 
@@ -152,6 +795,198 @@ impl<'a> Ds402<'a> {
     }
 }
 
+fn not_mapped(index: u16) -> EthercrabError {
+    fmt::error!("DS402 object {:#06x} is not mapped into the PDI", index);
+
+    EthercrabError::NotFound {
+        item: Item::PdoEntry,
+        index: Some(index),
+    }
+}
+
+/// A per-cycle setpoint for one axis of a [`MultiAxis`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Setpoint {
+    /// Target position (`0x607a`), used in the CSP mode.
+    Position(i32),
+    /// Target velocity (`0x60ff`), used in the CSV mode.
+    Velocity(i32),
+    /// Target torque (`0x6071`), used in the CST mode.
+    Torque(i16),
+}
+
+/// Coordinated control of several DS402 axes.
+///
+/// The axes are stepped through the power state machine together and receive a setpoint from a
+/// shared trajectory every cycle. If any axis faults or otherwise leaves
+/// [`OperationEnabled`](PowerState::OperationEnabled) while running, all axes are stopped.
+///
+/// The axes should be in the same group, synchronised with distributed clocks, so setpoints take
+/// effect on all axes at the same time. Like [`Ds402::enable`], every method takes a `cycle`
+/// closure that exchanges the group's process data and waits for the next cycle.
+pub struct MultiAxis<'a, const N: usize> {
+    axes: [Ds402<'a>; N],
+}
+
+impl<'a, const N: usize> MultiAxis<'a, N> {
+    /// Coordinate the given axes.
+    pub fn new(axes: [Ds402<'a>; N]) -> Self {
+        Self { axes }
+    }
+
+    /// Get the axes.
+    pub fn axes(&self) -> &[Ds402<'a>; N] {
+        &self.axes
+    }
+
+    /// Get the axes mutably, e.g. to change their mode of operation.
+    pub fn axes_mut(&mut self) -> &mut [Ds402<'a>; N] {
+        &mut self.axes
+    }
+
+    /// Return the axes.
+    pub fn into_inner(self) -> [Ds402<'a>; N] {
+        self.axes
+    }
+
+    /// Move all axes into [`OperationEnabled`](PowerState::OperationEnabled), resetting any
+    /// faults. Each axis is given the next command every cycle, so the axes are enabled together.
+    pub async fn enable<F, Fut>(&mut self, cycle: F) -> Result<(), EthercrabError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), EthercrabError>>,
+    {
+        self.transition(PowerState::enable_command, cycle).await
+    }
+
+    /// Move all axes into [`SwitchOnDisabled`](PowerState::SwitchOnDisabled).
+    pub async fn disable<F, Fut>(&mut self, cycle: F) -> Result<(), EthercrabError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), EthercrabError>>,
+    {
+        self.transition(PowerState::disable_command, cycle).await
+    }
+
+    /// Quick stop all axes that are switched on, waiting until none of them is in
+    /// [`OperationEnabled`](PowerState::OperationEnabled) any more.
+    ///
+    /// Depending on the drive's quick stop option code (`0x605a`), axes either stay in
+    /// [`QuickStopActive`](PowerState::QuickStopActive) or move into
+    /// [`SwitchOnDisabled`](PowerState::SwitchOnDisabled) once stopped.
+    pub async fn quick_stop<F, Fut>(&mut self, cycle: F) -> Result<(), EthercrabError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), EthercrabError>>,
+    {
+        self.transition(PowerState::quick_stop_command, cycle).await
+    }
+
+    /// Run a coordinated motion.
+    ///
+    /// Every cycle, `trajectory` is called with the cycle count, starting at zero, and the axes.
+    /// It returns a setpoint for each axis, or `None` to end the motion. The axes are left enabled
+    /// when the motion ends.
+    ///
+    /// If any axis is not in [`OperationEnabled`](PowerState::OperationEnabled) at the start of a
+    /// cycle, or a setpoint can't be written because it isn't mapped into the PDI, all axes are
+    /// stopped with [`quick_stop`](MultiAxis::quick_stop). A fault is reported with
+    /// [`Ds402Error::Fault`].
+    pub async fn run<F, Fut, T>(
+        &mut self,
+        mut cycle: F,
+        mut trajectory: T,
+    ) -> Result<(), EthercrabError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), EthercrabError>>,
+        T: FnMut(u64, &[Ds402<'a>; N]) -> Option<[Setpoint; N]>,
+    {
+        for tick in 0u64.. {
+            if let Some((axis, state)) = self
+                .axes
+                .iter()
+                .map(|axis| axis.state())
+                .enumerate()
+                .find(|(_, state)| *state != PowerState::OperationEnabled)
+            {
+                fmt::error!(
+                    "Axis {} is in state {:?} at cycle {}, stopping all axes",
+                    axis,
+                    state,
+                    tick
+                );
+
+                self.quick_stop(&mut cycle).await?;
+
+                return Err(EthercrabError::Ds402(Ds402Error::Fault));
+            }
+
+            let Some(setpoints) = trajectory(tick, &self.axes) else {
+                break;
+            };
+
+            let written =
+                self.axes.iter_mut().zip(setpoints).try_for_each(
+                    |(axis, setpoint)| match setpoint {
+                        Setpoint::Position(position) => axis.set_target_position(position),
+                        Setpoint::Velocity(velocity) => axis.set_target_velocity(velocity),
+                        Setpoint::Torque(torque) => axis.set_target_torque(torque),
+                    },
+                );
+
+            if let Err(e) = written {
+                self.quick_stop(&mut cycle).await?;
+
+                return Err(e);
+            }
+
+            cycle().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn transition<F, Fut>(
+        &mut self,
+        command: fn(PowerState, ControlWord) -> Option<ControlWord>,
+        mut cycle: F,
+    ) -> Result<(), EthercrabError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), EthercrabError>>,
+    {
+        let Some(first) = self.axes.first() else {
+            return Ok(());
+        };
+
+        let timeout = first.subdevice.maindevice.timeouts.state_transition;
+
+        async {
+            loop {
+                let mut done = true;
+
+                for axis in self.axes.iter_mut() {
+                    if let Some(control) = command(axis.state(), axis.control_word()) {
+                        axis.set_control_word(&control);
+
+                        done = false;
+                    }
+                }
+
+                if done {
+                    break Ok(());
+                }
+
+                cycle().await?;
+            }
+        }
+        .timeout(timeout)
+        .await
+    }
+}
+
 /// DS402 state machine.
 pub struct Ds402Sm<'a> {
     sm: StateMachine<Ds402<'a>>,
@@ -210,6 +1045,7 @@ impl<'a> Ds402Sm<'a> {
 
 bitflags::bitflags! {
     /// AKD EtherCAT Communications Manual section 5.3.55
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
     pub struct ControlWord: u16 {
         /// Switch on
         const SWITCH_ON = 1 << 0;
@@ -230,6 +1066,9 @@ bitflags::bitflags! {
         /// Pause/halt
         const PAUSE = 1 << 8;
 
+        /// Start homing in homing mode. Same bit as [`OP_SPECIFIC_1`](Self::OP_SPECIFIC_1).
+        const HOMING_START = Self::OP_SPECIFIC_1.bits();
+
         /// Shutdown state.
         const STATE_SHUTDOWN = Self::QUICK_STOP.bits() | Self::DISABLE_VOLTAGE.bits();
         /// Switched on state.
@@ -247,6 +1086,14 @@ bitflags::bitflags! {
     }
 }
 
+// Can't derive, so manual impl
+#[cfg(feature = "defmt")]
+impl defmt::Format for ControlWord {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=u16:b}", self.bits())
+    }
+}
+
 bitflags::bitflags! {
     #[derive(Debug, Copy, Clone, PartialEq, Eq)]
     /// AKD EtherCAT Communications Manual section   5.3.56
@@ -279,6 +1126,10 @@ bitflags::bitflags! {
         const OP_SPECIFIC_1 = 1 << 12;
         /// Operation mode specific (reserved)
         const OP_SPECIFIC_2 = 1 << 13;
+        /// Homing attained in homing mode. Same bit as [`OP_SPECIFIC_1`](Self::OP_SPECIFIC_1).
+        const HOMING_ATTAINED = Self::OP_SPECIFIC_1.bits();
+        /// Homing error in homing mode. Same bit as [`OP_SPECIFIC_2`](Self::OP_SPECIFIC_2).
+        const HOMING_ERROR = Self::OP_SPECIFIC_2.bits();
         /// Manufacturer-specific (reserved)
         const MAN_SPECIFIC_1 = 1 << 14;
         /// Manufacturer-specific (reserved)
@@ -320,4 +1171,1003 @@ impl StatusWord {
     fn mandatory(self) -> Self {
         self.intersection(Self::MANDATORY)
     }
+
+    /// The outcome of homing, or `None` if homing is still in progress.
+    fn homing_result(&self) -> Option<Result<(), Ds402Error>> {
+        if self.contains(Self::FAULT) {
+            Some(Err(Ds402Error::Fault))
+        } else if self.contains(Self::HOMING_ERROR) {
+            Some(Err(Ds402Error::Homing))
+        } else if self.contains(Self::HOMING_ATTAINED | Self::TARGET_REACHED) {
+            Some(Ok(()))
+        } else {
+            None
+        }
+    }
+
+    /// Decode the state of the power state machine as per ETG6010 Table 4.
+    pub fn state(&self) -> PowerState {
+        let bits = self.bits();
+
+        // The quick stop bit is only relevant in some states
+        match (bits & 0b0100_1111, bits & 0b0110_1111) {
+            (_, 0b0010_0001) => PowerState::ReadyToSwitchOn,
+            (_, 0b0010_0011) => PowerState::SwitchedOn,
+            (_, 0b0010_0111) => PowerState::OperationEnabled,
+            (_, 0b0000_0111) => PowerState::QuickStopActive,
+            (0b0100_0000, _) => PowerState::SwitchOnDisabled,
+            (0b0000_1111, _) => PowerState::FaultReactionActive,
+            (0b0000_1000, _) => PowerState::Fault,
+            _ => PowerState::NotReadyToSwitchOn,
+        }
+    }
+}
+
+/// A state of the DS402 power state machine.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PowerState {
+    /// The drive is initialising. This state is left automatically.
+    NotReadyToSwitchOn,
+    /// Initialisation is complete. The power stage is off.
+    SwitchOnDisabled,
+    /// The drive is ready to switch on its power stage.
+    ReadyToSwitchOn,
+    /// The power stage is on but the drive function is disabled.
+    SwitchedOn,
+    /// The drive is operating normally.
+    OperationEnabled,
+    /// The drive is executing a quick stop.
+    QuickStopActive,
+    /// The drive is reacting to a fault. This state is left automatically.
+    FaultReactionActive,
+    /// The drive has a fault and must be reset.
+    Fault,
+}
+
+impl PowerState {
+    /// The control word to write to move from this state towards `OperationEnabled`, or `None` if
+    /// the drive is already enabled.
+    fn enable_command(self, current: ControlWord) -> Option<ControlWord> {
+        match self {
+            PowerState::OperationEnabled => None,
+            PowerState::SwitchOnDisabled => Some(ControlWord::STATE_SHUTDOWN),
+            PowerState::ReadyToSwitchOn => Some(ControlWord::STATE_SWITCH_ON),
+            PowerState::SwitchedOn => Some(ControlWord::STATE_ENABLE_OP),
+            PowerState::QuickStopActive => Some(ControlWord::STATE_DISABLE_VOLTAGE),
+            // Faults are reset on a rising edge of the reset bit
+            PowerState::Fault if current.contains(ControlWord::RESET_FAULT) => {
+                Some(ControlWord::STATE_DISABLE_VOLTAGE)
+            }
+            PowerState::Fault => Some(ControlWord::STATE_FAULT_RESET),
+            PowerState::NotReadyToSwitchOn | PowerState::FaultReactionActive => Some(current),
+        }
+    }
+
+    /// The control word to write to move from this state towards `SwitchOnDisabled`, or `None` if
+    /// the drive is already disabled.
+    fn disable_command(self, current: ControlWord) -> Option<ControlWord> {
+        match self {
+            PowerState::SwitchOnDisabled | PowerState::Fault => None,
+            PowerState::OperationEnabled => Some(ControlWord::STATE_DISABLE_OP),
+            PowerState::SwitchedOn => Some(ControlWord::STATE_SHUTDOWN),
+            PowerState::ReadyToSwitchOn | PowerState::QuickStopActive => {
+                Some(ControlWord::STATE_DISABLE_VOLTAGE)
+            }
+            PowerState::NotReadyToSwitchOn | PowerState::FaultReactionActive => Some(current),
+        }
+    }
+
+    /// The control word to write to quick stop a drive that is switched on, or `None` if it is not.
+    fn quick_stop_command(self, _current: ControlWord) -> Option<ControlWord> {
+        match self {
+            PowerState::OperationEnabled | PowerState::SwitchedOn | PowerState::ReadyToSwitchOn => {
+                Some(ControlWord::STATE_QUICK_STOP)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// One of the two CiA402 touch probes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TouchProbe {
+    /// Touch probe 1.
+    One,
+    /// Touch probe 2.
+    Two,
+}
+
+impl TouchProbe {
+    /// Index of the byte holding this probe's bits in the function and status words.
+    fn byte(self) -> usize {
+        match self {
+            TouchProbe::One => 0,
+            TouchProbe::Two => 1,
+        }
+    }
+}
+
+/// The edge of the trigger signal a touch probe latches the position on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TouchProbeEdge {
+    /// Rising edge.
+    Positive,
+    /// Falling edge.
+    Negative,
+}
+
+/// The signal that triggers a touch probe.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TouchProbeSource {
+    /// The probe's touch probe input.
+    Input = 0b00,
+    /// The zero impulse of the position encoder.
+    ZeroImpulse = 0b01,
+    /// The source selected in object `0x60d0` (probe 1) or `0x60d1` (probe 2).
+    Selected = 0b10,
+}
+
+/// Touch probe configuration, written to the touch probe function (`0x60b8`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TouchProbeConfig {
+    /// Latch a position on every trigger instead of only the first one after the probe is enabled.
+    pub continuous: bool,
+    /// Trigger signal.
+    pub source: TouchProbeSource,
+    /// Latch a position on the rising edge of the trigger signal.
+    pub positive_edge: bool,
+    /// Latch a position on the falling edge of the trigger signal.
+    pub negative_edge: bool,
+}
+
+impl TouchProbeConfig {
+    /// The function bits for a single probe, with the probe enabled.
+    fn bits(&self) -> u8 {
+        0b1 | u8::from(self.continuous) << 1
+            | (self.source as u8) << 2
+            | u8::from(self.positive_edge) << 4
+            | u8::from(self.negative_edge) << 5
+    }
+}
+
+bitflags::bitflags! {
+    /// Touch probe status (`0x60b9`).
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct TouchProbeStatus: u16 {
+        /// Touch probe 1 is enabled.
+        const PROBE_1_ENABLED = 1 << 0;
+        /// Touch probe 1 has stored a position on the positive edge.
+        const PROBE_1_POSITIVE_STORED = 1 << 1;
+        /// Touch probe 1 has stored a position on the negative edge.
+        const PROBE_1_NEGATIVE_STORED = 1 << 2;
+        /// Touch probe 2 is enabled.
+        const PROBE_2_ENABLED = 1 << 8;
+        /// Touch probe 2 has stored a position on the positive edge.
+        const PROBE_2_POSITIVE_STORED = 1 << 9;
+        /// Touch probe 2 has stored a position on the negative edge.
+        const PROBE_2_NEGATIVE_STORED = 1 << 10;
+    }
+}
+
+// Can't derive, so manual impl
+#[cfg(feature = "defmt")]
+impl defmt::Format for TouchProbeStatus {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=u16:b}", self.bits())
+    }
+}
+
+/// Conversion between raw drive positions and velocities in encoder increments and engineering
+/// units like mm, degrees or rpm.
+///
+/// The default scaling is `1` for all factors, so engineering units are encoder increments and
+/// increments per second.
+///
+/// # Examples
+///
+/// A 20 bit encoder driving a 5mm pitch ball screw through a 3:1 gearbox, with positions in mm and
+/// velocities in mm/s:
+///
+/// ```rust
+/// use ethercrab::ds402::UnitScaling;
+///
+/// let scaling = UnitScaling {
+///     encoder_resolution: f64::from(1 << 20),
+///     gear_ratio: 3.0,
+///     feed_constant: 5.0,
+///     ..UnitScaling::default()
+/// };
+///
+/// assert_eq!(scaling.position_to_raw(5.0), 3 << 20);
+/// ```
+///
+/// The same motor with positions in motor revolutions and velocities in rpm:
+///
+/// ```rust
+/// use ethercrab::ds402::UnitScaling;
+///
+/// let scaling = UnitScaling {
+///     encoder_resolution: f64::from(1 << 20),
+///     velocity_time_base: 60.0,
+///     ..UnitScaling::default()
+/// };
+///
+/// assert_eq!(scaling.velocity_to_units(1 << 20), 60.0);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UnitScaling {
+    /// Encoder increments per motor revolution.
+    pub encoder_resolution: f64,
+    /// Motor revolutions per driving shaft revolution.
+    pub gear_ratio: f64,
+    /// Engineering units moved per driving shaft revolution, e.g. the pitch of a ball screw or
+    /// `360.0` for degrees.
+    pub feed_constant: f64,
+    /// Seconds per time unit of engineering velocities, e.g. `60.0` for units per minute. Raw
+    /// velocities are in increments per second.
+    pub velocity_time_base: f64,
+}
+
+impl Default for UnitScaling {
+    fn default() -> Self {
+        Self {
+            encoder_resolution: 1.0,
+            gear_ratio: 1.0,
+            feed_constant: 1.0,
+            velocity_time_base: 1.0,
+        }
+    }
+}
+
+impl UnitScaling {
+    /// Encoder increments per engineering unit.
+    pub fn increments_per_unit(&self) -> f64 {
+        self.encoder_resolution * self.gear_ratio / self.feed_constant
+    }
+
+    /// Convert a raw position into engineering units.
+    pub fn position_to_units(&self, raw: i32) -> f64 {
+        f64::from(raw) / self.increments_per_unit()
+    }
+
+    /// Convert a position in engineering units into the nearest raw position.
+    pub fn position_to_raw(&self, position: f64) -> i32 {
+        round_saturating(position * self.increments_per_unit())
+    }
+
+    /// Convert a raw velocity into engineering units.
+    pub fn velocity_to_units(&self, raw: i32) -> f64 {
+        f64::from(raw) / self.increments_per_unit() * self.velocity_time_base
+    }
+
+    /// Convert a velocity in engineering units into the nearest raw velocity.
+    pub fn velocity_to_raw(&self, velocity: f64) -> i32 {
+        round_saturating(velocity * self.increments_per_unit() / self.velocity_time_base)
+    }
+}
+
+/// Round half away from zero without `f64::round`, which isn't available in `core`. Values out of
+/// range saturate.
+fn round_saturating(value: f64) -> i32 {
+    (if value >= 0.0 {
+        value + 0.5
+    } else {
+        value - 0.5
+    }) as i32
+}
+
+/// Parameters for [`Ds402::home`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HomingConfig {
+    /// Homing method (`0x6098`). The supported methods are listed in the drive's manual, e.g. `35`
+    /// or `37` to use the current position as home.
+    pub method: i8,
+    /// Speed during search for switch (`0x6099:01`).
+    pub switch_search_speed: u32,
+    /// Speed during search for zero (`0x6099:02`).
+    pub zero_search_speed: u32,
+    /// Homing acceleration (`0x609a`).
+    pub acceleration: u32,
+    /// Home offset (`0x607c`).
+    pub offset: i32,
+    /// How long to wait for homing to complete.
+    pub timeout: Duration,
+}
+
+/// A fault reported by a DS402 drive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DriveFault {
+    /// Error code of the current fault.
+    pub error_code: Option<u16>,
+
+    /// Entries of the pre-defined error field, most recent first.
+    ///
+    /// The lower 16 bits of each entry hold an error code, and the upper 16 bits hold manufacturer
+    /// specific information.
+    pub history: heapless::Vec<u32, 8>,
+}
+
+/// A DS402 mode of operation, written to object `0x6060`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ethercrab_wire::EtherCrabWireReadWrite)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(i8)]
+pub enum OperationMode {
+    /// No mode selected.
+    NoMode = 0,
+    /// Profile Position (PP).
+    ProfilePosition = 1,
+    /// Velocity (VL).
+    Velocity = 2,
+    /// Profile Velocity (PV).
+    ProfileVelocity = 3,
+    /// Profile Torque (PT).
+    ProfileTorque = 4,
+    /// Homing (HM).
+    Homing = 6,
+    /// Interpolated Position (IP).
+    InterpolatedPosition = 7,
+    /// Cyclic Synchronous Position (CSP).
+    CyclicSynchronousPosition = 8,
+    /// Cyclic Synchronous Velocity (CSV).
+    CyclicSynchronousVelocity = 9,
+    /// Cyclic Synchronous Torque (CST).
+    CyclicSynchronousTorque = 10,
+    /// A reserved or manufacturer specific (negative) mode.
+    #[wire(catch_all)]
+    Other(i8),
+}
+
+/// An object index and its length in bits, mapped into a PDO.
+type MappedObject = (u16, u8);
+
+impl OperationMode {
+    /// Objects and their lengths in bits mapped into the outputs and inputs by
+    /// [`PdoLayout::configure`], or `None` if the mode isn't supported.
+    fn pdo_entries(self) -> Option<(&'static [MappedObject], &'static [MappedObject])> {
+        const CONTROL_WORD: MappedObject = (0x6040, 16);
+        const STATUS_WORD: MappedObject = (0x6041, 16);
+        const POSITION_ACTUAL: MappedObject = (0x6064, 32);
+        const VELOCITY_ACTUAL: MappedObject = (0x606c, 32);
+
+        let outputs: &'static [MappedObject] = match self {
+            OperationMode::ProfilePosition | OperationMode::CyclicSynchronousPosition => {
+                &[CONTROL_WORD, (0x607a, 32)]
+            }
+            OperationMode::ProfileVelocity | OperationMode::CyclicSynchronousVelocity => {
+                &[CONTROL_WORD, (0x60ff, 32)]
+            }
+            OperationMode::ProfileTorque | OperationMode::CyclicSynchronousTorque => {
+                &[CONTROL_WORD, (0x6071, 16)]
+            }
+            OperationMode::Homing => &[CONTROL_WORD],
+            _ => return None,
+        };
+
+        let inputs: &'static [MappedObject] = match self {
+            OperationMode::ProfileTorque | OperationMode::CyclicSynchronousTorque => {
+                &[STATUS_WORD, POSITION_ACTUAL, VELOCITY_ACTUAL, (0x6077, 16)]
+            }
+            _ => &[STATUS_WORD, POSITION_ACTUAL, VELOCITY_ACTUAL],
+        };
+
+        Some((outputs, inputs))
+    }
+}
+
+/// Byte offsets of DS402 objects in a SubDevice's inputs or outputs.
+///
+/// `None` means the object is not mapped into the process data. The control word (`0x6040`) and
+/// status word (`0x6041`) must be mapped at the start of the outputs and inputs respectively.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PdoLayout {
+    /// Modes of operation (`0x6060`) in the outputs.
+    pub operation_mode: Option<usize>,
+    /// Target position (`0x607a`) in the outputs.
+    pub target_position: Option<usize>,
+    /// Target velocity (`0x60ff`) in the outputs.
+    pub target_velocity: Option<usize>,
+    /// Target torque (`0x6071`) in the outputs.
+    pub target_torque: Option<usize>,
+    /// Position actual value (`0x6064`) in the inputs.
+    pub position_actual: Option<usize>,
+    /// Velocity actual value (`0x606c`) in the inputs.
+    pub velocity_actual: Option<usize>,
+    /// Torque actual value (`0x6077`) in the inputs.
+    pub torque_actual: Option<usize>,
+    /// Touch probe function (`0x60b8`) in the outputs.
+    pub touch_probe_function: Option<usize>,
+    /// Touch probe status (`0x60b9`) in the inputs.
+    pub touch_probe_status: Option<usize>,
+    /// Touch probe 1 positive edge position (`0x60ba`) in the inputs.
+    pub touch_probe_1_positive: Option<usize>,
+    /// Touch probe 1 negative edge position (`0x60bb`) in the inputs.
+    pub touch_probe_1_negative: Option<usize>,
+    /// Touch probe 2 positive edge position (`0x60bc`) in the inputs.
+    pub touch_probe_2_positive: Option<usize>,
+    /// Touch probe 2 negative edge position (`0x60bd`) in the inputs.
+    pub touch_probe_2_negative: Option<usize>,
+}
+
+impl PdoLayout {
+    /// Map the objects needed to run a drive in the given mode of operation into the process
+    /// data, then switch the drive to that mode (`0x6060`).
+    ///
+    /// RxPDO `0x1600` maps the control word and the mode's target position, velocity or torque.
+    /// TxPDO `0x1a00` maps the status word, actual position and actual velocity, plus the actual
+    /// torque in torque modes. These are assigned as the only PDOs in `0x1c12` and `0x1c13`, so the
+    /// drive must support dynamic PDO mapping.
+    ///
+    /// This must be called while the SubDevice is in PRE-OP, before the group's PDI is
+    /// configured. The returned layout can be passed to [`Ds402::set_pdo_layout`] once the group
+    /// is in SAFE-OP or OP.
+    ///
+    /// Returns [`Ds402Error::UnsupportedMode`] for modes other than PP, PV, PT, homing, CSP, CSV
+    /// and CST.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{
+    /// #     ds402::{Ds402, OperationMode, PdoLayout}, error::Error, MainDevice, MainDeviceConfig,
+    /// #     PduStorage, Timeouts,
+    /// # };
+    /// # async fn case() -> Result<(), Error> {
+    /// # static PDU_STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// let mut group = maindevice
+    ///     .init_single_group::<8, 64>(ethercrab::std::ethercat_now)
+    ///     .await?;
+    ///
+    /// let layout = PdoLayout::configure(
+    ///     &group.subdevice(&maindevice, 0)?,
+    ///     OperationMode::CyclicSynchronousVelocity,
+    /// )
+    /// .await?;
+    ///
+    /// let group = group.into_op(&maindevice).await?;
+    ///
+    /// let mut servo = Ds402::new(group.subdevice(&maindevice, 0)?)?;
+    ///
+    /// servo.set_pdo_layout(layout);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn configure<S>(
+        subdevice: &SubDeviceRef<'_, S>,
+        mode: OperationMode,
+    ) -> Result<Self, EthercrabError>
+    where
+        S: Deref<Target = SubDevice>,
+    {
+        let (outputs, inputs) = mode
+            .pdo_entries()
+            .ok_or(EthercrabError::Ds402(Ds402Error::UnsupportedMode))?;
+
+        // Mappings can't be changed while their PDOs are assigned
+        subdevice.sdo_write(0x1c12, 0, 0u8).await?;
+        subdevice.sdo_write(0x1c13, 0, 0u8).await?;
+
+        for (pdo, entries) in [(0x1600u16, outputs), (0x1a00, inputs)] {
+            // Index in the high WORD, then sub index and bit length, ETG1000.6 Table 74
+            let entries = entries
+                .iter()
+                .map(|(index, bit_len)| u32::from(*index) << 16 | u32::from(*bit_len))
+                .collect::<heapless::Vec<u32, 4>>();
+
+            subdevice.sdo_write_array(pdo, entries).await?;
+        }
+
+        subdevice.sdo_write_array(0x1c12, [0x1600u16]).await?;
+        subdevice.sdo_write_array(0x1c13, [0x1a00u16]).await?;
+
+        subdevice.sdo_write(0x6060, 0, mode).await?;
+
+        let layout = Self::from_entries(outputs, inputs);
+
+        fmt::debug!("DS402 PDO layout for {:?}: {:?}", mode, layout);
+
+        Ok(layout)
+    }
+
+    /// The layout of byte aligned objects mapped in order into the outputs and inputs.
+    fn from_entries(outputs: &[MappedObject], inputs: &[MappedObject]) -> Self {
+        let mut layout = Self::default();
+
+        for entries in [outputs, inputs] {
+            let mut offset = 0;
+
+            for (index, bit_len) in entries {
+                layout.insert(*index, offset);
+
+                offset += usize::from(*bit_len) / 8;
+            }
+        }
+
+        layout
+    }
+
+    fn insert(&mut self, index: u16, offset: usize) {
+        let field = match index {
+            0x6060 => &mut self.operation_mode,
+            0x607a => &mut self.target_position,
+            0x60ff => &mut self.target_velocity,
+            0x6071 => &mut self.target_torque,
+            0x6064 => &mut self.position_actual,
+            0x606c => &mut self.velocity_actual,
+            0x6077 => &mut self.torque_actual,
+            0x60b8 => &mut self.touch_probe_function,
+            0x60b9 => &mut self.touch_probe_status,
+            0x60ba => &mut self.touch_probe_1_positive,
+            0x60bb => &mut self.touch_probe_1_negative,
+            0x60bc => &mut self.touch_probe_2_positive,
+            0x60bd => &mut self.touch_probe_2_negative,
+            _ => return,
+        };
+
+        *field = Some(offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethercrab_wire::EtherCrabWireWriteSized;
+
+    #[test]
+    fn homing_result() {
+        for (status, result) in [
+            // Operation enabled, homing in progress
+            (0x0237, None),
+            // Homing interrupted, target not reached
+            (0x1237, None),
+            (0x1637, Some(Ok(()))),
+            (0x2237, Some(Err(Ds402Error::Homing))),
+            (0x0218, Some(Err(Ds402Error::Fault))),
+        ] {
+            assert_eq!(
+                StatusWord::from_bits_truncate(status).homing_result(),
+                result,
+                "{:#06x}",
+                status
+            );
+        }
+    }
+
+    #[test]
+    fn configured_layout() {
+        let (outputs, inputs) = OperationMode::CyclicSynchronousVelocity
+            .pdo_entries()
+            .unwrap();
+
+        assert_eq!(
+            PdoLayout::from_entries(outputs, inputs),
+            PdoLayout {
+                target_velocity: Some(2),
+                position_actual: Some(2),
+                velocity_actual: Some(6),
+                ..PdoLayout::default()
+            }
+        );
+
+        let (outputs, inputs) = OperationMode::CyclicSynchronousTorque
+            .pdo_entries()
+            .unwrap();
+
+        assert_eq!(
+            PdoLayout::from_entries(outputs, inputs),
+            PdoLayout {
+                target_torque: Some(2),
+                position_actual: Some(2),
+                velocity_actual: Some(6),
+                torque_actual: Some(10),
+                ..PdoLayout::default()
+            }
+        );
+
+        assert_eq!(OperationMode::InterpolatedPosition.pdo_entries(), None);
+    }
+
+    #[test]
+    fn unit_scaling() {
+        assert_eq!(UnitScaling::default().position_to_raw(1234.0), 1234);
+        assert_eq!(UnitScaling::default().velocity_to_units(-1234), -1234.0);
+
+        // 10000 increment encoder, 2:1 gearbox, 4mm per revolution of the gearbox output
+        let scaling = UnitScaling {
+            encoder_resolution: 10000.0,
+            gear_ratio: 2.0,
+            feed_constant: 4.0,
+            velocity_time_base: 60.0,
+        };
+
+        assert_eq!(scaling.increments_per_unit(), 5000.0);
+        assert_eq!(scaling.position_to_raw(1.5), 7500);
+        assert_eq!(scaling.position_to_raw(-0.0004), -2);
+        assert_eq!(scaling.position_to_units(-7500), -1.5);
+        // 600 mm/min = 10 mm/s
+        assert_eq!(scaling.velocity_to_raw(600.0), 50000);
+        assert_eq!(scaling.velocity_to_units(50000), 600.0);
+
+        assert_eq!(scaling.position_to_raw(1e9), i32::MAX);
+        assert_eq!(scaling.position_to_raw(-1e9), i32::MIN);
+    }
+
+    #[test]
+    fn touch_probe_function() {
+        let config = TouchProbeConfig {
+            continuous: true,
+            source: TouchProbeSource::Input,
+            positive_edge: true,
+            negative_edge: false,
+        };
+
+        assert_eq!(config.bits(), 0b0001_0011);
+
+        let config = TouchProbeConfig {
+            continuous: false,
+            source: TouchProbeSource::ZeroImpulse,
+            positive_edge: false,
+            negative_edge: true,
+        };
+
+        assert_eq!(config.bits(), 0b0010_0101);
+    }
+
+    #[test]
+    fn operation_mode_wire() {
+        assert_eq!(OperationMode::CyclicSynchronousPosition.pack(), [0x08]);
+        assert_eq!(OperationMode::Other(-1).pack(), [0xff]);
+
+        assert_eq!(
+            OperationMode::unpack_from_slice(&[0x0a]),
+            Ok(OperationMode::CyclicSynchronousTorque)
+        );
+        assert_eq!(
+            OperationMode::unpack_from_slice(&[0xfe]),
+            Ok(OperationMode::Other(-2))
+        );
+    }
+
+    #[test]
+    fn pdo_layout() {
+        let mut layout = PdoLayout::default();
+
+        // Control word, target position, target velocity and an unrelated object
+        for (index, offset) in [(0x6040, 0), (0x607a, 2), (0x60ff, 6), (0x2000, 10)] {
+            layout.insert(index, offset);
+        }
+
+        assert_eq!(
+            layout,
+            PdoLayout {
+                target_position: Some(2),
+                target_velocity: Some(6),
+                ..PdoLayout::default()
+            }
+        );
+    }
+
+    #[test]
+    fn pdo_layout_control_status_words() {
+        let mut layout = PdoLayout::default();
+
+        // The control and status words are always read from the start of the process data, so
+        // their positions are not recorded, even when mapped elsewhere.
+        for (index, offset) in [(0x607a, 0), (0x6040, 4), (0x6064, 0), (0x6041, 4)] {
+            layout.insert(index, offset);
+        }
+
+        assert_eq!(
+            layout,
+            PdoLayout {
+                target_position: Some(0),
+                position_actual: Some(0),
+                ..PdoLayout::default()
+            }
+        );
+    }
+
+    #[test]
+    fn decode_state() {
+        for (status, state) in [
+            (0x0000, PowerState::NotReadyToSwitchOn),
+            (0x0250, PowerState::SwitchOnDisabled),
+            (0x0231, PowerState::ReadyToSwitchOn),
+            (0x0233, PowerState::SwitchedOn),
+            (0x1637, PowerState::OperationEnabled),
+            (0x0217, PowerState::QuickStopActive),
+            (0x001f, PowerState::FaultReactionActive),
+            (0x0218, PowerState::Fault),
+            (0x0238, PowerState::Fault),
+        ] {
+            assert_eq!(
+                StatusWord::from_bits_truncate(status).state(),
+                state,
+                "{:#06x}",
+                status
+            );
+        }
+    }
+
+    /// Run a transition against a simulated drive that follows every valid command immediately.
+    fn run(
+        mut state: PowerState,
+        command: fn(PowerState, ControlWord) -> Option<ControlWord>,
+    ) -> Vec<PowerState> {
+        let mut control = ControlWord::empty();
+        let mut states = vec![state];
+
+        while let Some(next) = command(state, control) {
+            let reset_edge = next.contains(ControlWord::RESET_FAULT)
+                && !control.contains(ControlWord::RESET_FAULT);
+
+            control = next;
+
+            state = match (state, control.bits() & 0b1111) {
+                (PowerState::Fault, _) if reset_edge => PowerState::SwitchOnDisabled,
+                (PowerState::Fault, _) => PowerState::Fault,
+                (_, 0b0000) => PowerState::SwitchOnDisabled,
+                (PowerState::OperationEnabled, 0b0010) => PowerState::QuickStopActive,
+                (_, 0b0010) => PowerState::SwitchOnDisabled,
+                (_, 0b0110) => PowerState::ReadyToSwitchOn,
+                (_, 0b0111) => PowerState::SwitchedOn,
+                (_, 0b1111) => PowerState::OperationEnabled,
+                _ => unreachable!("invalid control word {:?}", control),
+            };
+
+            states.push(state);
+        }
+
+        states
+    }
+
+    #[test]
+    fn enable() {
+        assert_eq!(
+            run(PowerState::SwitchOnDisabled, PowerState::enable_command),
+            [
+                PowerState::SwitchOnDisabled,
+                PowerState::ReadyToSwitchOn,
+                PowerState::SwitchedOn,
+                PowerState::OperationEnabled
+            ]
+        );
+    }
+
+    #[test]
+    fn enable_from_fault() {
+        assert_eq!(
+            run(PowerState::Fault, PowerState::enable_command),
+            [
+                PowerState::Fault,
+                PowerState::SwitchOnDisabled,
+                PowerState::ReadyToSwitchOn,
+                PowerState::SwitchedOn,
+                PowerState::OperationEnabled
+            ]
+        );
+    }
+
+    #[test]
+    fn disable() {
+        assert_eq!(
+            run(PowerState::OperationEnabled, PowerState::disable_command),
+            [
+                PowerState::OperationEnabled,
+                PowerState::SwitchedOn,
+                PowerState::ReadyToSwitchOn,
+                PowerState::SwitchOnDisabled
+            ]
+        );
+
+        assert_eq!(
+            run(PowerState::Fault, PowerState::disable_command),
+            [PowerState::Fault]
+        );
+    }
+
+    #[test]
+    fn quick_stop() {
+        assert_eq!(
+            run(PowerState::OperationEnabled, PowerState::quick_stop_command),
+            [PowerState::OperationEnabled, PowerState::QuickStopActive]
+        );
+
+        assert_eq!(
+            run(PowerState::SwitchedOn, PowerState::quick_stop_command),
+            [PowerState::SwitchedOn, PowerState::SwitchOnDisabled]
+        );
+
+        assert_eq!(
+            run(PowerState::Fault, PowerState::quick_stop_command),
+            [PowerState::Fault]
+        );
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        sim::{fixture::*, sleep, SdoAccess, SimNetwork, SimObject},
+        PduStorage,
+    };
+    use core::time::Duration;
+
+    #[test]
+    fn ds402_reset_fault() {
+        use crate::{
+            base_data_types::PrimitiveDataType::U16,
+            ds402::{DriveFault, Ds402},
+        };
+        use core::cell::RefCell;
+
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = SimNetwork::new([servo().with_object(
+            0x603f,
+            0,
+            SimObject::new(U16, 0x2310u16).with_access(SdoAccess::ReadOnly),
+        )]);
+
+        // Fault status word
+        network.with_subdevice(0, |subdevice| {
+            subdevice
+                .inputs_mut()
+                .copy_from_slice(&0x0218u16.to_le_bytes())
+        });
+
+        let net = network.clone();
+
+        // Control words seen by the drive, one per cycle
+        let seen = RefCell::new(Vec::new());
+        let seen_ref = &seen;
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            let (group, maindevice, net, seen) = (&group, &maindevice, &net, seen_ref);
+
+            // Simulates a drive that only resets its fault on a rising edge of the reset bit
+            let cycle = move || async move {
+                group.tx_rx(maindevice).await?;
+
+                net.with_subdevice(0, |subdevice| {
+                    let control =
+                        u16::from_le_bytes([subdevice.outputs()[0], subdevice.outputs()[1]]);
+
+                    let mut seen = seen.borrow_mut();
+
+                    if control & 0x80 != 0 && seen.last().is_some_and(|last| last & 0x80 == 0) {
+                        subdevice
+                            .inputs_mut()
+                            .copy_from_slice(&0x0250u16.to_le_bytes());
+                    }
+
+                    seen.push(control);
+                });
+
+                sleep(Duration::from_millis(1)).await;
+
+                Ok(())
+            };
+
+            let mut servo = Ds402::new(group.subdevice(maindevice, 0).unwrap()).unwrap();
+
+            // Fault reset bit left over from an earlier reset
+            servo.subdevice.outputs_raw_mut()[0..2].copy_from_slice(&0x0080u16.to_le_bytes());
+
+            cycle().await.expect("cycle");
+
+            assert!(servo.is_faulted());
+
+            assert_eq!(
+                servo.reset_fault(cycle).await,
+                Ok(Some(DriveFault {
+                    error_code: Some(0x2310),
+                    history: heapless::Vec::new(),
+                }))
+            );
+
+            assert!(!servo.is_faulted());
+        });
+
+        let seen = seen.into_inner();
+
+        // The leftover reset bit is cleared for a cycle before it is set again
+        assert_eq!(seen[0..3], [0x0080, 0x0000, 0x0080]);
+        assert_eq!(seen.last(), Some(&0x0000));
+    }
+
+    #[test]
+    fn ds402_read_fault() {
+        use crate::{
+            base_data_types::PrimitiveDataType::U32,
+            ds402::{DriveFault, Ds402},
+        };
+
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        // No error code object, but an error history
+        let network = SimNetwork::new([servo()
+            .with_sdo(0x1003, 0, 2u8)
+            .with_object(0x1003, 1, SimObject::new(U32, 0x0001_7500u32))
+            .with_object(0x1003, 2, SimObject::new(U32, 0x0000_2310u32))]);
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            let servo = Ds402::new(group.subdevice(&maindevice, 0).unwrap()).unwrap();
+
+            let history = heapless::Vec::from_slice(&[0x0001_7500, 0x0000_2310]).unwrap();
+
+            // Reading 0x603f is aborted
+            assert_eq!(
+                servo.read_fault().await,
+                Ok(DriveFault {
+                    error_code: None,
+                    history: history.clone(),
+                })
+            );
+
+            // The drive sends an emergency instead of the response
+            net.with_subdevice(0, |subdevice| subdevice.emergency(0x7500, 0x01));
+
+            assert_eq!(
+                servo.read_fault().await,
+                Ok(DriveFault {
+                    error_code: Some(0x7500),
+                    history,
+                })
+            );
+        });
+
+        // Without an error history either, the fault is empty
+        static STORAGE_2: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = SimNetwork::new([servo()]);
+
+        run(&network, &STORAGE_2, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            let group = group.into_op(&maindevice).await.expect("PRE-OP -> OP");
+
+            let servo = Ds402::new(group.subdevice(&maindevice, 0).unwrap()).unwrap();
+
+            assert_eq!(
+                servo.read_fault().await,
+                Ok(DriveFault {
+                    error_code: None,
+                    history: heapless::Vec::new(),
+                })
+            );
+        });
+    }
 }
@@ -0,0 +1,320 @@
+//! A cache of SII reads made while a SubDevice is being initialised.
+//!
+//! Reading the EEPROM is slow, and each configuration phase looks up the same categories. The
+//! cache remembers where each category starts as well as the most recently read chunks so repeated
+//! lookups don't have to go back to the SubDevice.
+
+use crate::{
+    eeprom::{types::CategoryType, EepromDataProvider},
+    error::Error,
+};
+use atomic_refcell::AtomicRefCell;
+use core::ops::Deref;
+
+/// Maximum number of category start positions to cache.
+const MAX_CATEGORIES: usize = 16;
+
+/// Maximum number of chunks to cache. The oldest chunk is evicted when the cache is full.
+const MAX_CHUNKS: usize = 8;
+
+/// The position of a category in the EEPROM.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct CachedCategory {
+    category: CategoryType,
+    /// Word address of the first word of category data, after the header.
+    start_word: u32,
+    len_words: u32,
+}
+
+/// A chunk of 4 or 8 bytes read from the EEPROM.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CachedChunk {
+    start_word: u32,
+    len: u8,
+    data: [u8; 8],
+}
+
+impl CachedChunk {
+    pub(crate) fn new(start_word: u32, chunk: &[u8]) -> Self {
+        let len = chunk.len().min(8);
+
+        let mut data = [0u8; 8];
+
+        data[0..len].copy_from_slice(&chunk[0..len]);
+
+        Self {
+            start_word,
+            len: len as u8,
+            data,
+        }
+    }
+}
+
+impl Deref for CachedChunk {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.data[0..usize::from(self.len)]
+    }
+}
+
+/// Category positions and chunks read from a single SubDevice's EEPROM.
+#[derive(Debug, Default)]
+pub struct EepromCache {
+    categories: heapless::Vec<CachedCategory, MAX_CATEGORIES>,
+    chunks: heapless::Deque<CachedChunk, MAX_CHUNKS>,
+}
+
+impl EepromCache {
+    /// Find the data start word and length in words of a category.
+    ///
+    /// Returns `Some(None)` if the category is known not to exist, or `None` if the EEPROM must be
+    /// searched.
+    fn category(&self, category: CategoryType) -> Option<Option<(u32, u32)>> {
+        let mut found_end = false;
+
+        for cached in self.categories.iter() {
+            if cached.category == category {
+                return Some(Some((cached.start_word, cached.len_words)));
+            }
+
+            found_end |= cached.category == CategoryType::End;
+        }
+
+        // All categories before the end marker have been seen, so this one doesn't exist.
+        found_end.then_some(None)
+    }
+
+    fn insert_category(&mut self, category: CategoryType, start_word: u32, len_words: u32) {
+        // Only the first category of a given type is ever returned by a search.
+        if self.categories.iter().any(|c| c.category == category) {
+            return;
+        }
+
+        // If the cache is full, further categories will be searched for in the EEPROM.
+        let _ = self.categories.push(CachedCategory {
+            category,
+            start_word,
+            len_words,
+        });
+    }
+
+    fn chunk(&self, start_word: u32) -> Option<CachedChunk> {
+        self.chunks
+            .iter()
+            .find(|chunk| chunk.start_word == start_word)
+            .copied()
+    }
+
+    fn insert_chunk(&mut self, chunk: CachedChunk) {
+        if self.chunks.is_full() {
+            self.chunks.pop_front();
+        }
+
+        // Can't fail as we made room above
+        let _ = self.chunks.push_back(chunk);
+    }
+
+    /// Forget everything read so far.
+    pub fn clear(&mut self) {
+        self.categories.clear();
+        self.chunks.clear();
+    }
+}
+
+/// An EEPROM data provider that serves reads from an [`EepromCache`] where possible.
+///
+/// If no cache is given, all reads are passed through to the inner provider.
+#[derive(Clone)]
+pub struct CachedEeprom<'cache, P> {
+    provider: P,
+    cache: Option<&'cache AtomicRefCell<EepromCache>>,
+}
+
+impl<'cache, P> CachedEeprom<'cache, P> {
+    /// Wrap a data provider, storing reads in the given cache.
+    pub fn new(provider: P, cache: Option<&'cache AtomicRefCell<EepromCache>>) -> Self {
+        Self { provider, cache }
+    }
+}
+
+impl<P> EepromDataProvider for CachedEeprom<'_, P>
+where
+    P: EepromDataProvider,
+{
+    async fn read_chunk(&mut self, start_word: u32) -> Result<impl Deref<Target = [u8]>, Error> {
+        if let Some(chunk) = self
+            .cache
+            .and_then(|cache| cache.borrow().chunk(start_word))
+        {
+            return Ok(chunk);
+        }
+
+        let chunk = CachedChunk::new(start_word, &self.provider.read_chunk(start_word).await?);
+
+        if let Some(cache) = self.cache {
+            cache.borrow_mut().insert_chunk(chunk);
+        }
+
+        Ok(chunk)
+    }
+
+    async fn clear_errors(&self) -> Result<(), Error> {
+        self.provider.clear_errors().await
+    }
+
+    fn cached_category(&self, category: CategoryType) -> Option<Option<(u32, u32)>> {
+        self.cache
+            .and_then(|cache| cache.borrow().category(category))
+    }
+
+    fn cache_category(&self, category: CategoryType, start_word: u32, len_words: u32) {
+        if let Some(cache) = self.cache {
+            cache
+                .borrow_mut()
+                .insert_category(category, start_word, len_words);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eeprom::file_reader::EepromFile, subdevice::eeprom::SubDeviceEeprom};
+    use std::{
+        rc::Rc,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    /// Counts the number of chunks read from the inner provider.
+    #[derive(Clone)]
+    struct CountingEeprom {
+        inner: EepromFile<8>,
+        reads: Rc<AtomicUsize>,
+    }
+
+    impl EepromDataProvider for CountingEeprom {
+        async fn read_chunk(
+            &mut self,
+            start_word: u32,
+        ) -> Result<impl Deref<Target = [u8]>, Error> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+
+            // Copy out of the file reader so the borrow doesn't outlive this method.
+            Ok(CachedChunk::new(
+                start_word,
+                &self.inner.read_chunk(start_word).await?,
+            ))
+        }
+
+        async fn clear_errors(&self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    fn counting(path: &str) -> (CountingEeprom, Rc<AtomicUsize>) {
+        let reads = Rc::new(AtomicUsize::new(0));
+
+        (
+            CountingEeprom {
+                inner: EepromFile::new(path),
+                reads: reads.clone(),
+            },
+            reads,
+        )
+    }
+
+    #[tokio::test]
+    async fn repeated_reads_are_cached() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let (provider, reads) = counting("dumps/eeprom/akd.hex");
+        let cache = AtomicRefCell::new(EepromCache::default());
+
+        let e = SubDeviceEeprom::new(CachedEeprom::new(provider.clone(), Some(&cache)));
+
+        let sms = e.sync_managers().await;
+        let general = e.general().await;
+
+        let first_reads = reads.load(Ordering::Relaxed);
+
+        assert!(first_reads > 0);
+
+        assert_eq!(e.sync_managers().await, sms);
+        assert_eq!(e.general().await, general);
+
+        // Everything came from the cache the second time around
+        assert_eq!(reads.load(Ordering::Relaxed), first_reads);
+
+        // Same results as an uncached reader
+        let uncached = SubDeviceEeprom::new(provider);
+
+        assert_eq!(uncached.sync_managers().await, sms);
+        assert_eq!(uncached.general().await, general);
+    }
+
+    #[tokio::test]
+    async fn category_positions_are_cached() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let (provider, reads) = counting("dumps/eeprom/el2889.hex");
+        let cache = AtomicRefCell::new(EepromCache::default());
+
+        let e = SubDeviceEeprom::new(CachedEeprom::new(provider, Some(&cache)));
+
+        // No DC category in this EEPROM, so the whole category list is searched
+        assert_eq!(e.distributed_clock::<4>().await, Ok(heapless::Vec::new()));
+
+        let search_reads = reads.load(Ordering::Relaxed);
+
+        // Evict all header chunks
+        cache.borrow_mut().chunks.clear();
+
+        assert_eq!(e.distributed_clock::<4>().await, Ok(heapless::Vec::new()));
+
+        // A missing category is known to be missing without searching again
+        assert_eq!(reads.load(Ordering::Relaxed), search_reads);
+
+        // Only the category data is read, not the headers before it
+        e.fmmus().await.unwrap();
+
+        assert_eq!(reads.load(Ordering::Relaxed), search_reads + 1);
+    }
+
+    #[tokio::test]
+    async fn disabled() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let (provider, reads) = counting("dumps/eeprom/el2889.hex");
+
+        let e = SubDeviceEeprom::new(CachedEeprom::new(provider, None));
+
+        e.identity().await.unwrap();
+
+        let first_reads = reads.load(Ordering::Relaxed);
+
+        e.identity().await.unwrap();
+
+        assert_eq!(reads.load(Ordering::Relaxed), first_reads * 2);
+    }
+
+    #[tokio::test]
+    async fn clear() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let (provider, reads) = counting("dumps/eeprom/el2889.hex");
+        let cache = AtomicRefCell::new(EepromCache::default());
+
+        let e = SubDeviceEeprom::new(CachedEeprom::new(provider, Some(&cache)));
+
+        e.identity().await.unwrap();
+
+        let first_reads = reads.load(Ordering::Relaxed);
+
+        cache.borrow_mut().clear();
+
+        e.identity().await.unwrap();
+
+        assert_eq!(reads.load(Ordering::Relaxed), first_reads * 2);
+    }
+}
@@ -1,13 +1,14 @@
 use crate::{
+    atomic::{AtomicBool, AtomicU16, Ordering},
     eeprom::{
         types::{SiiControl, SiiRequest},
         EepromDataProvider,
     },
-    error::{EepromError, Error},
+    error::{EepromError, Error, ErrorContext, Operation},
     fmt,
     register::RegisterAddress,
     timer_factory::IntoTimeout,
-    Command, MainDevice,
+    Command, ConfiguredAddress, MainDevice,
 };
 
 /// The address of the first proper category, positioned after the fixed fields defined in ETG2010
@@ -16,33 +17,98 @@ use crate::{
 /// SII EEPROM is WORD-addressed.
 pub(crate) const SII_FIRST_CATEGORY_START: u16 = 0x0040u16;
 
+/// The number of SubDevices [`InterruptedEeproms`] can track at once.
+///
+/// If more EEPROM operations are interrupted than this, readers of every SubDevice wait for the
+/// ESC to be idle instead.
+const MAX_INTERRUPTED: usize = 16;
+
+/// An empty [`InterruptedEeproms`] slot. EtherCrab never assigns configured address `0`.
+const EMPTY: u16 = 0;
+
+/// The SubDevices whose last EEPROM operation was cancelled or failed part way through.
+///
+/// The ESC may still be busy with that operation, so the next reader of the SubDevice waits for it
+/// to finish before starting a new one. A SubDevice is removed once its ESC has been seen idle.
+#[derive(Debug)]
+pub(crate) struct InterruptedEeproms {
+    /// Configured addresses of interrupted SubDevices, or [`EMPTY`].
+    slots: [AtomicU16; MAX_INTERRUPTED],
+    /// Set if an interrupted SubDevice could not be given a slot.
+    overflow: AtomicBool,
+}
+
+impl InterruptedEeproms {
+    pub(crate) const fn new() -> Self {
+        Self {
+            slots: [const { AtomicU16::new(EMPTY) }; MAX_INTERRUPTED],
+            overflow: AtomicBool::new(false),
+        }
+    }
+
+    fn insert(&self, address: ConfiguredAddress) {
+        let address = address.get();
+
+        if self
+            .slots
+            .iter()
+            .any(|slot| slot.load(Ordering::Acquire) == address)
+        {
+            return;
+        }
+
+        let inserted = self.slots.iter().any(|slot| {
+            slot.compare_exchange(EMPTY, address, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        });
+
+        if !inserted {
+            self.overflow.store(true, Ordering::Release);
+        }
+    }
+
+    fn contains(&self, address: ConfiguredAddress) -> bool {
+        let address = address.get();
+
+        self.overflow.load(Ordering::Acquire)
+            || self
+                .slots
+                .iter()
+                .any(|slot| slot.load(Ordering::Acquire) == address)
+    }
+
+    fn remove(&self, address: ConfiguredAddress) {
+        let address = address.get();
+
+        for slot in self.slots.iter() {
+            let _ = slot.compare_exchange(address, EMPTY, Ordering::AcqRel, Ordering::Relaxed);
+        }
+    }
+}
+
 /// EEPROM data provider that communicates with a physical sub device.
 #[derive(Clone)]
 pub struct DeviceEeprom<'subdevice> {
     maindevice: &'subdevice MainDevice<'subdevice>,
-    configured_address: u16,
+    configured_address: ConfiguredAddress,
 }
 
 impl<'subdevice> DeviceEeprom<'subdevice> {
     /// Create a new EEPROM reader instance.
-    pub fn new(maindevice: &'subdevice MainDevice<'subdevice>, configured_address: u16) -> Self {
+    pub fn new(
+        maindevice: &'subdevice MainDevice<'subdevice>,
+        configured_address: ConfiguredAddress,
+    ) -> Self {
         Self {
             maindevice,
             configured_address,
         }
     }
-}
-
-impl<'subdevice> EepromDataProvider for DeviceEeprom<'subdevice> {
-    async fn read_chunk(
-        &mut self,
-        start_word: u16,
-    ) -> Result<impl core::ops::Deref<Target = [u8]>, Error> {
-        Command::fpwr(self.configured_address, RegisterAddress::SiiControl.into())
-            .send_receive(self.maindevice, SiiRequest::read(start_word))
-            .await?;
 
-        let status = async {
+    /// Wait for the current EEPROM operation to complete, returning the final SII control register
+    /// value.
+    async fn wait_while_busy(&self) -> Result<SiiControl, Error> {
+        async {
             loop {
                 let control: SiiControl =
                     Command::fprd(self.configured_address, RegisterAddress::SiiControl.into())
@@ -50,6 +116,10 @@ impl<'subdevice> EepromDataProvider for DeviceEeprom<'subdevice> {
                         .await?;
 
                 if !control.busy {
+                    self.maindevice
+                        .eeprom_interrupted
+                        .remove(self.configured_address);
+
                     break Ok(control);
                 }
 
@@ -57,19 +127,163 @@ impl<'subdevice> EepromDataProvider for DeviceEeprom<'subdevice> {
             }
         }
         .timeout(self.maindevice.timeouts.eeprom)
-        .await?;
+        .await
+    }
+
+    /// Write a single WORD to the given WORD address.
+    pub(crate) async fn write_word(&self, word_addr: u32, value: u16) -> Result<(), Error> {
+        let context = ErrorContext::new(
+            self.configured_address,
+            Operation::EepromWrite {
+                word: u16::try_from(word_addr).unwrap_or(u16::MAX),
+            },
+        );
 
-        Command::fprd(self.configured_address, RegisterAddress::SiiData.into())
-            .receive_slice(self.maindevice, status.read_size.chunk_len())
+        self.write_word_inner(word_addr, value)
             .await
-            .map(|data| {
-                #[cfg(not(feature = "defmt"))]
-                fmt::trace!("Read addr {:#06x}: {:02x?}", start_word, data);
-                #[cfg(feature = "defmt")]
-                fmt::trace!("Read addr {:#06x}: {=[u8]}", start_word, data);
+            .map_err(|e| e.with_context(context))
+    }
+
+    async fn write_word_inner(&self, word_addr: u32, value: u16) -> Result<(), Error> {
+        // A previous operation may not have completed if its future was dropped. The ESC ignores
+        // commands while it's busy, so wait for it to finish first.
+        let status = self.wait_while_busy().await?;
 
-                data
-            })
+        self.clear_errors().await?;
+
+        // Writing past the end of the addressable range would wrap around and overwrite the start
+        // of the EEPROM, so the address size must be checked before the write is started.
+        check_address(status, word_addr)?;
+
+        Command::fpwr(self.configured_address, RegisterAddress::SiiData.into())
+            .send(self.maindevice, value)
+            .await?;
+
+        let operation = OperationGuard::new(self);
+
+        Command::fpwr(self.configured_address, RegisterAddress::SiiControl.into())
+            .send_receive(self.maindevice, SiiRequest::write(word_addr))
+            .await?;
+
+        let status = self.wait_while_busy().await?;
+
+        operation.complete();
+
+        if status.command_error || status.write_error {
+            fmt::error!(
+                "Failed to write EEPROM addr {:#06x}: {:?}",
+                word_addr,
+                status
+            );
+
+            return Err(Error::Eeprom(EepromError::Write));
+        }
+
+        fmt::trace!("Wrote addr {:#06x}: {:#06x}", word_addr, value);
+
+        Ok(())
+    }
+}
+
+/// Marks the SubDevice in [`InterruptedEeproms`] if an EEPROM operation is dropped or fails before
+/// [`complete`](OperationGuard::complete) is called.
+struct OperationGuard<'a> {
+    interrupted: &'a InterruptedEeproms,
+    configured_address: ConfiguredAddress,
+    complete: bool,
+}
+
+impl<'a> OperationGuard<'a> {
+    fn new(eeprom: &'a DeviceEeprom<'_>) -> Self {
+        Self {
+            interrupted: &eeprom.maindevice.eeprom_interrupted,
+            configured_address: eeprom.configured_address,
+            complete: false,
+        }
+    }
+
+    /// The ESC has finished the operation.
+    fn complete(mut self) {
+        self.complete = true;
+    }
+}
+
+impl Drop for OperationGuard<'_> {
+    fn drop(&mut self) {
+        if !self.complete {
+            self.interrupted.insert(self.configured_address);
+        }
+    }
+}
+
+/// Check that a WORD address can be reached with the address size reported by the ESC.
+fn check_address(status: SiiControl, word_addr: u32) -> Result<(), Error> {
+    let max_words = status.address_type.max_words();
+
+    if word_addr >= max_words {
+        fmt::error!(
+            "EEPROM address {:#06x} is out of range for {:?} addressing ({} WORDs)",
+            word_addr,
+            status.address_type,
+            max_words
+        );
+
+        return Err(Error::Eeprom(EepromError::AddressOutOfRange));
+    }
+
+    Ok(())
+}
+
+impl<'subdevice> EepromDataProvider for DeviceEeprom<'subdevice> {
+    async fn read_chunk(
+        &mut self,
+        start_word: u32,
+    ) -> Result<impl core::ops::Deref<Target = [u8]>, Error> {
+        let context = ErrorContext::new(
+            self.configured_address,
+            Operation::EepromRead {
+                word: u16::try_from(start_word).unwrap_or(u16::MAX),
+            },
+        );
+
+        async {
+            // The ESC ignores commands while busy, e.g. with a read from a dropped future
+            if self
+                .maindevice
+                .eeprom_interrupted
+                .contains(self.configured_address)
+            {
+                self.wait_while_busy().await?;
+            }
+
+            let operation = OperationGuard::new(self);
+
+            Command::fpwr(self.configured_address, RegisterAddress::SiiControl.into())
+                .send_receive(self.maindevice, SiiRequest::read(start_word))
+                .await?;
+
+            let status = self.wait_while_busy().await?;
+
+            operation.complete();
+
+            // Small EEPROMs ignore the upper address bits, so reads past their end return data
+            // from the start of the EEPROM instead.
+            check_address(status, start_word)?;
+
+            Command::fprd(self.configured_address, RegisterAddress::SiiData.into())
+                .receive_slice(self.maindevice, status.read_size.chunk_len())
+                .await
+                .map(|data| {
+                    #[cfg(not(feature = "defmt"))]
+                    fmt::trace!("Read addr {:#06x}: {:02x?}", start_word, data);
+                    #[cfg(feature = "defmt")]
+                    fmt::trace!("Read addr {:#06x}: {=[u8]}", start_word, data);
+
+                    data
+                })
+        }
+        .await
+        .map_err(|e| e.with_context(context))
     }
 
     async fn clear_errors(&self) -> Result<(), Error> {
@@ -95,3 +309,85 @@ impl<'subdevice> EepromDataProvider for DeviceEeprom<'subdevice> {
         }
     }
 }
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use super::*;
+    use crate::{
+        sim::{fixture::*, SimNetwork},
+        PduStorage,
+    };
+
+    /// Read the start of a SubDevice's EEPROM, returning the first PDU sent.
+    async fn first_pdu(
+        network: &SimNetwork,
+        maindevice: &MainDevice<'_>,
+        address: ConfiguredAddress,
+    ) -> String {
+        network.record_transcript();
+
+        DeviceEeprom::new(maindevice, address)
+            .read_chunk(0)
+            .await
+            .expect("read");
+
+        let transcript = network.take_transcript();
+        let first = transcript.lines().next().unwrap().to_string();
+
+        first
+    }
+
+    #[test]
+    fn interrupted_read() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+        let recorder = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            maindevice
+                .init_single_group::<4, 16>(crate::sim::ethercat_now)
+                .await
+                .expect("init");
+
+            let a = ConfiguredAddress::from_index(0);
+            let b = ConfiguredAddress::from_index(1);
+
+            // Dropped after the read has been started, but before it has completed
+            let interrupted = futures_lite::future::or(
+                async {
+                    let _ = DeviceEeprom::new(&maindevice, a).read_chunk(0).await;
+
+                    false
+                },
+                async {
+                    futures_lite::future::yield_now().await;
+
+                    true
+                },
+            )
+            .await;
+
+            assert!(interrupted);
+
+            assert!(
+                first_pdu(&recorder, &maindevice, a)
+                    .await
+                    .starts_with("FPRD(addr 0x1000, reg 0x0502)"),
+                "interrupted SubDevice should wait for its ESC to be idle"
+            );
+            assert!(
+                first_pdu(&recorder, &maindevice, b)
+                    .await
+                    .starts_with("FPWR(addr 0x1001, reg 0x0502)"),
+                "other SubDevices should not wait"
+            );
+            assert!(
+                first_pdu(&recorder, &maindevice, a)
+                    .await
+                    .starts_with("FPWR(addr 0x1000, reg 0x0502)"),
+                "SubDevice should not wait once its ESC has been seen idle"
+            );
+        });
+    }
+}
@@ -0,0 +1,156 @@
+//! Expected SII contents from an EtherCAT SubDevice Information (ESI) file, and the differences
+//! found when comparing them against a SubDevice's EEPROM.
+
+use crate::eeprom::types::{PdoType, SyncManager, SyncManagerEnable};
+use ethercrab_wire::EtherCrabWireWriteSized;
+
+/// The parts of an ESI `<Device>` description that are checked against a SubDevice's SII.
+///
+/// EtherCrab does not parse ESI XML itself. Fill this in from the `<Device>` element of the ESI
+/// file with the XML parser of your choice, then pass it to
+/// [`Eeprom::check_esi`](crate::sii::Eeprom::check_esi).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EsiDevice<'esi> {
+    /// `<Vendor><Id>`.
+    pub vendor_id: u32,
+    /// `<Type ProductCode="...">`.
+    pub product_code: u32,
+    /// `<Type RevisionNo="...">`.
+    pub revision: u32,
+    /// `<Sm>` elements, in order.
+    pub sync_managers: &'esi [EsiSyncManager],
+    /// `<RxPdo>` elements (outputs).
+    pub rx_pdos: &'esi [EsiPdo<'esi>],
+    /// `<TxPdo>` elements (inputs).
+    pub tx_pdos: &'esi [EsiPdo<'esi>],
+}
+
+/// An ESI `<Sm>` element.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EsiSyncManager {
+    /// `StartAddress` attribute.
+    pub start_addr: u16,
+    /// `DefaultSize` attribute.
+    ///
+    /// Process data SyncManagers usually don't have a default size, in which case this should be
+    /// `None` and the length is not checked.
+    pub length: Option<u16>,
+    /// `ControlByte` attribute.
+    pub control: u8,
+    /// `Enable` attribute.
+    pub enable: bool,
+}
+
+impl From<SyncManager> for EsiSyncManager {
+    fn from(value: SyncManager) -> Self {
+        Self {
+            start_addr: value.start_addr,
+            length: Some(value.length),
+            control: value.control.pack()[0],
+            enable: value.enable.contains(SyncManagerEnable::ENABLE),
+        }
+    }
+}
+
+/// An ESI `<RxPdo>` or `<TxPdo>` element.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EsiPdo<'esi> {
+    /// `<Index>`.
+    pub index: u16,
+    /// `Sm` attribute.
+    ///
+    /// PDOs without a SyncManager are optional and are not expected to be present in the SII.
+    pub sync_manager: Option<u8>,
+    /// `<Entry>` elements, in order.
+    pub entries: &'esi [EsiPdoEntry],
+}
+
+/// An ESI PDO `<Entry>` element.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EsiPdoEntry {
+    /// `<Index>`, or `0` for padding entries.
+    pub index: u16,
+    /// `<SubIndex>`.
+    pub sub_index: u8,
+    /// `<BitLen>`.
+    pub bit_len: u8,
+}
+
+/// A difference between an ESI description and a SubDevice's SII contents.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SiiMismatch {
+    /// The vendor ID differs.
+    VendorId {
+        /// Expected value from the ESI file.
+        esi: u32,
+        /// Value read from the SII.
+        sii: u32,
+    },
+    /// The product code differs.
+    ProductCode {
+        /// Expected value from the ESI file.
+        esi: u32,
+        /// Value read from the SII.
+        sii: u32,
+    },
+    /// The revision differs, e.g. because the SII is outdated.
+    Revision {
+        /// Expected value from the ESI file.
+        esi: u32,
+        /// Value read from the SII.
+        sii: u32,
+    },
+    /// A different number of SyncManagers is defined.
+    SyncManagerCount {
+        /// Expected count from the ESI file.
+        esi: usize,
+        /// Count read from the SII.
+        sii: usize,
+    },
+    /// A SyncManager's configuration differs.
+    SyncManager {
+        /// SyncManager index.
+        index: u8,
+        /// Expected configuration from the ESI file.
+        esi: EsiSyncManager,
+        /// Configuration read from the SII.
+        sii: EsiSyncManager,
+    },
+    /// A PDO assigned to a SyncManager in the ESI file is not present in the SII.
+    MissingPdo {
+        /// Whether the PDO is an input or output.
+        pdo_type: PdoType,
+        /// PDO index.
+        index: u16,
+    },
+    /// A PDO in the SII is not described in the ESI file.
+    UnexpectedPdo {
+        /// Whether the PDO is an input or output.
+        pdo_type: PdoType,
+        /// PDO index.
+        index: u16,
+    },
+    /// A PDO is assigned to a different SyncManager.
+    PdoSyncManager {
+        /// Whether the PDO is an input or output.
+        pdo_type: PdoType,
+        /// PDO index.
+        index: u16,
+        /// Expected SyncManager from the ESI file.
+        esi: u8,
+        /// SyncManager read from the SII.
+        sii: u8,
+    },
+    /// A PDO maps different objects, or objects with different lengths.
+    PdoEntries {
+        /// Whether the PDO is an input or output.
+        pdo_type: PdoType,
+        /// PDO index.
+        index: u16,
+    },
+}
@@ -58,7 +58,7 @@ impl EepromFile<4> {
 impl<const CHUNK: usize> EepromDataProvider for EepromFile<CHUNK> {
     async fn read_chunk(
         &mut self,
-        start_word: u16,
+        start_word: u32,
     ) -> Result<impl core::ops::Deref<Target = [u8]>, Error> {
         let file_len = self.file.metadata().unwrap().len() as usize;
 
@@ -68,7 +68,7 @@ impl<const CHUNK: usize> EepromDataProvider for EepromFile<CHUNK> {
 
         // Make sure a partial read off the end of the file is ok, e.g. 8 byte buffer but 4 byte
         // read.
-        let buf_len = self.buf.len().min(file_len - usize::from(start_word * 2));
+        let buf_len = self.buf.len().min(file_len - start_word as usize * 2);
 
         let buf = &mut self.buf[0..buf_len];
 
@@ -1,12 +1,15 @@
 use core::ops::Deref;
 
 use crate::{
+    eeprom::types::CategoryType,
     error::{EepromError, Error},
     fmt,
 };
 use embedded_io_async::{ErrorType, Read, ReadExactError};
 
+pub mod cache;
 pub mod device_reader;
+pub mod esi;
 pub mod types;
 
 #[cfg(feature = "std")]
@@ -16,11 +19,22 @@ pub mod file_reader;
 pub trait EepromDataProvider: Clone {
     /// Read a chunk of either 4 or 8 bytes from the backing store.
     #[cfg_attr(feature = "__internals", allow(async_fn_in_trait))]
-    async fn read_chunk(&mut self, start_word: u16) -> Result<impl Deref<Target = [u8]>, Error>;
+    async fn read_chunk(&mut self, start_word: u32) -> Result<impl Deref<Target = [u8]>, Error>;
 
     /// Attempt to clear any errors in the EEPROM source.
     #[cfg_attr(feature = "__internals", allow(async_fn_in_trait))]
     async fn clear_errors(&self) -> Result<(), Error>;
+
+    /// Get the data start word and length in words of a previously found category.
+    ///
+    /// Returns `Some(None)` if the category is known not to exist, or `None` if the category
+    /// headers must be searched.
+    fn cached_category(&self, _category: CategoryType) -> Option<Option<(u32, u32)>> {
+        None
+    }
+
+    /// Remember the position of a category found while searching the category headers.
+    fn cache_category(&self, _category: CategoryType, _start_word: u32, _len_words: u32) {}
 }
 
 impl embedded_io_async::Error for Error {
@@ -39,6 +53,23 @@ impl From<ReadExactError<Error>> for Error {
     }
 }
 
+/// WORD address of the configuration area checksum, ETG2010 Table 2.
+pub(crate) const SII_CHECKSUM_WORD: u16 = 0x0007;
+
+/// CRC8 checksum of the configuration area, i.e. the first 7 words of the EEPROM, as defined in
+/// ETG2010 Table 2.
+pub(crate) fn sii_checksum(config_area: &[u8]) -> u8 {
+    config_area.iter().fold(0xff, |crc, byte| {
+        (0..8).fold(crc ^ byte, |crc, _| {
+            if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
 /// An abstraction over a provider of EEPROM bytes that only allows a certain range to be read.
 ///
 /// The provider `P` should be as simple as possible, simply returning chunks of data either 4 or 8
@@ -52,10 +83,10 @@ pub struct ChunkReader<P> {
     ///
     /// This is the last byte that was returned to the caller by the reader, and should be used as a
     /// base for skip offsets.
-    pos: u16,
+    pos: u32,
 
     /// The last byte address we're allowed to access.
-    end: u16,
+    end: u32,
 }
 
 impl<P> ChunkReader<P>
@@ -63,7 +94,7 @@ where
     P: EepromDataProvider,
 {
     /// Create a new `ChunkReader`.
-    pub fn new(reader: P, start_word: u16, len_words: u16) -> Self {
+    pub fn new(reader: P, start_word: u32, len_words: u32) -> Self {
         Self {
             reader,
             pos: start_word * 2,
@@ -77,14 +108,14 @@ where
             "Skip EEPROM from pos {:#06x} by {} bytes to {:#06x}",
             self.pos,
             skip,
-            self.pos + skip,
+            self.pos + u32::from(skip),
         );
 
-        if self.pos + skip >= self.end {
+        if self.pos + u32::from(skip) >= self.end {
             return Err(EepromError::SectionOverrun);
         }
 
-        self.pos += skip;
+        self.pos += u32::from(skip);
 
         Ok(())
     }
@@ -97,7 +128,7 @@ where
 
         // pos is in bytes, but we're reading words. If the current pos is odd, we must skip the
         // first byte of the returned word.
-        let skip = usize::from(self.pos % 2);
+        let skip = (self.pos % 2) as usize;
 
         // Advance by one byte
         self.pos += 1;
@@ -115,7 +146,7 @@ where
 
         let requested_read_len = buf.len();
 
-        let max_read = usize::from(self.end - self.pos);
+        let max_read = (self.end - self.pos) as usize;
 
         let mut bytes_read = 0;
 
@@ -139,7 +170,7 @@ where
 
             // If position is odd, we must skip the first received byte as the reader operates on
             // WORD addresses.
-            let skip = usize::from(self.pos % 2);
+            let skip = (self.pos % 2) as usize;
 
             // Fix any odd addressing offsets
             let chunk = chunk.get(skip..).ok_or(Error::Internal)?;
@@ -149,7 +180,7 @@ where
                 let (chunk, _rest) = chunk.split_at(buf.len());
 
                 bytes_read += chunk.len();
-                self.pos += chunk.len() as u16;
+                self.pos += chunk.len() as u32;
 
                 buf.copy_from_slice(chunk);
 
@@ -157,7 +188,7 @@ where
             }
 
             bytes_read += chunk.len();
-            self.pos += chunk.len() as u16;
+            self.pos += chunk.len() as u32;
 
             // Buffer is not full. Write another chunk into the beginning of it.
             let (buf_start, buf_rest) = buf.split_at_mut(chunk.len());
@@ -4,6 +4,7 @@ use crate::{base_data_types::PrimitiveDataType, coe::SdoExpedited, sync_manager_
 use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ethercrab_wire::EtherCrabWireReadWrite)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum SiiOwner {
     /// EEPROM access rights are assigned to PDI during state change from Init to PreOp, Init to
@@ -17,6 +18,7 @@ pub enum SiiOwner {
 
 /// Defined in ETG1000.4 6.4.3
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ethercrab_wire::EtherCrabWireReadWrite)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bytes = 2)]
 pub struct SiiControl {
     // First byte, but second octet because little endian
@@ -69,9 +71,19 @@ impl SiiControl {
             ..Default::default()
         }
     }
+
+    /// Write command. Write access must be enabled in the same frame as the command is sent.
+    fn write() -> Self {
+        Self {
+            access: SiiAccess::ReadWrite,
+            write: true,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ethercrab_wire::EtherCrabWireReadWrite)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum SiiAccess {
     #[default]
@@ -80,6 +92,7 @@ pub enum SiiAccess {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ethercrab_wire::EtherCrabWireReadWrite)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum SiiReadSize {
     /// Read 4 octets at a time.
@@ -100,23 +113,35 @@ impl SiiReadSize {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default, ethercrab_wire::EtherCrabWireReadWrite)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum SiiAddressSize {
+    /// One address byte, used by EEPROMs from 1 kbit to 16 kbit.
     #[default]
     U8 = 0x00,
+    /// Two address bytes, used by EEPROMs from 32 kbit to 4 Mbit.
     U16 = 0x01,
 }
 
+impl SiiAddressSize {
+    /// The number of WORDs that can be addressed in an EEPROM using this address size.
+    pub fn max_words(&self) -> u32 {
+        match self {
+            SiiAddressSize::U8 => 16 * 1024 / 16,
+            SiiAddressSize::U16 => 4 * 1024 * 1024 / 16,
+        }
+    }
+}
+
 #[derive(PartialEq, ethercrab_wire::EtherCrabWireReadWrite)]
 #[wire(bytes = 6)]
 pub struct SiiRequest {
     #[wire(bytes = 2)]
     control: SiiControl,
-    // Post skip is required to send the correct amount of bytes on the wire. This is weird because
-    // addressing is all a single WORD, but the SII read request expects a low AND high WORD, hence
-    // the extra 16 bits of padding here for the unusedhigh WORD.
-    #[wire(bytes = 2, post_skip = 16)]
-    address: u16,
+    /// WORD address. The SII address register is 32 bits wide so EEPROMs larger than 64k WORDs can
+    /// be addressed.
+    #[wire(bytes = 4)]
+    address: u32,
 }
 
 impl core::fmt::Debug for SiiRequest {
@@ -129,12 +154,20 @@ impl core::fmt::Debug for SiiRequest {
 }
 
 impl SiiRequest {
-    pub fn read(address: u16) -> Self {
+    pub fn read(address: u32) -> Self {
         Self {
             control: SiiControl::read(),
             address,
         }
     }
+
+    /// Write the WORD in the SII data register to the given WORD address.
+    pub fn write(address: u32) -> Self {
+        Self {
+            control: SiiControl::write(),
+            address,
+        }
+    }
 }
 
 /// SII register address.
@@ -239,7 +272,8 @@ pub enum CategoryType {
 }
 
 /// The type of PDO to search for.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PdoType {
     /// SubDevice send, MainDevice receive.
     Tx = 50,
@@ -257,15 +291,21 @@ impl From<PdoType> for CategoryType {
     }
 }
 
+/// FMMU usage, from the SII "FMMU" category.
+///
 /// ETG1000.6 Table 23
 #[derive(Debug, Copy, Clone, PartialEq, Eq, ethercrab_wire::EtherCrabWireRead)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum FmmuUsage {
+    /// The FMMU is not used.
     #[wire(alternatives = [0xff])]
     Unused = 0x00,
+    /// The FMMU maps process data outputs.
     Outputs = 0x01,
+    /// The FMMU maps process data inputs.
     Inputs = 0x02,
+    /// The FMMU maps the SyncManager status.
     SyncManagerStatus = 0x03,
 }
 
@@ -281,6 +321,7 @@ pub struct FmmuEx {
     pub sync_manager: u8,
 }
 
+/// The physical layer of each of the four SubDevice ports.
 #[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PortStatuses(pub [PortStatus; 4]);
@@ -318,71 +359,104 @@ impl EtherCrabWireRead for PortStatuses {
 
 /// SII "General" category.
 ///
+/// String indices can be resolved with [`Eeprom::string`](crate::sii::Eeprom::string).
+///
 /// Defined in ETG1000.6 Table 21
-#[derive(Debug, PartialEq, Eq, ethercrab_wire::EtherCrabWireRead)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ethercrab_wire::EtherCrabWireRead)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bytes = 18)]
 pub struct SiiGeneral {
+    /// Index into EEPROM Strings section for the device group, e.g. `DigOut`.
     #[wire(bytes = 1)]
-    pub(crate) group_string_idx: u8,
+    pub group_string_idx: u8,
+    /// Index into EEPROM Strings section for the device image name.
     #[wire(bytes = 1)]
-    pub(crate) image_string_idx: u8,
+    pub image_string_idx: u8,
+    /// Index into EEPROM Strings section for the device order number, e.g. `EL2889`.
     #[wire(bytes = 1)]
-    pub(crate) order_string_idx: u8,
+    pub order_string_idx: u8,
+    /// Index into EEPROM Strings section for the device name.
     #[wire(bytes = 1, post_skip_bytes = 1)]
     pub name_string_idx: u8,
     // reserved: u8,
+    /// CANopen over EtherCAT capabilities.
     #[wire(bytes = 1)]
     pub coe_details: CoeDetails,
+    /// Whether File access over EtherCAT is supported.
     #[wire(bytes = 1)]
-    pub(crate) foe_enabled: bool,
+    pub foe_enabled: bool,
+    /// Whether Ethernet over EtherCAT is supported.
     #[wire(bytes = 1, post_skip_bytes = 3)]
-    pub(crate) eoe_enabled: bool,
+    pub eoe_enabled: bool,
     // Following 3 fields marked as reserved
     // soe_channels: u8,
     // ds402_channels: u8,
     // sysman_class: u8,
+    /// Miscellaneous device flags.
     #[wire(bytes = 1)]
-    pub(crate) flags: Flags,
+    pub flags: Flags,
     /// EBus Current Consumption in mA.
     ///
     /// A negative Values means feeding in current feed in sets the available current value to the
     /// given value
     #[wire(bytes = 2)]
-    pub(crate) ebus_current: i16,
+    pub ebus_current: i16,
     // reserved: u8,
+    /// Physical layer of each port.
     #[wire(bytes = 2)]
-    pub(crate) ports: PortStatuses,
+    pub ports: PortStatuses,
     /// defines the ESC memory address where the Identification ID is saved if Identification Method
-    /// [`IDENT_PHY_M`] is set.
+    /// [`IDENT_PHY_M`](Flags::IDENT_PHY_M) is set.
     #[wire(bytes = 2)]
-    pub(crate) physical_memory_addr: u16,
+    pub physical_memory_addr: u16,
     // reserved2: [u8; 12]
 }
 
+/// The physical layer of a SubDevice port.
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, ethercrab_wire::EtherCrabWireRead)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum PortStatus {
+    /// The port is not used.
     #[default]
     Unused = 0x00,
+    /// Media Independent Interface, e.g. a 100BASE-TX Ethernet port.
     Mii = 0x01,
+    /// Reserved.
     Reserved = 0x02,
+    /// E-Bus.
     Ebus = 0x03,
+    /// MII with fast hot connect support.
     FastHotConnect = 0x04,
 }
 
 bitflags::bitflags! {
-    #[derive(Debug, PartialEq, Eq)]
+    /// Flags in the SII "General" category.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
     pub struct Flags: u8 {
+        /// Bit 0: Enable SafeOp.
         const ENABLE_SAFE_OP = 0x01;
+        /// Bit 1: Enable notLRW.
         const ENABLE_NOT_LRW = 0x02;
+        /// Bit 2: Mailbox data link layer.
         const MAILBOX_DLL = 0x04;
+        /// Bit 3: Identification value is read from the AL Status Code register.
         const IDENT_AL_STATUS = 0x08;
+        /// Bit 4: Identification value is read from the physical memory address given in
+        /// [`SiiGeneral::physical_memory_addr`].
         const IDENT_PHY_M = 0x10;
 
     }
 }
 
+// Can't derive, so manual impl
+#[cfg(feature = "defmt")]
+impl defmt::Format for Flags {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=u8:b}", self.bits())
+    }
+}
+
 impl EtherCrabWireSized for Flags {
     const PACKED_LEN: usize = 1;
 
@@ -401,7 +475,8 @@ impl EtherCrabWireRead for Flags {
 }
 
 bitflags::bitflags! {
-    #[derive(Debug, PartialEq, Eq)]
+    /// CANopen over EtherCAT capabilities in the SII "General" category.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
     pub struct CoeDetails: u8 {
         /// Bit 0: Enable SDO
         const ENABLE_SDO = 0x01;
@@ -418,6 +493,14 @@ bitflags::bitflags! {
     }
 }
 
+// Can't derive, so manual impl
+#[cfg(feature = "defmt")]
+impl defmt::Format for CoeDetails {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=u8:b}", self.bits())
+    }
+}
+
 impl EtherCrabWireSized for CoeDetails {
     const PACKED_LEN: usize = 1;
 
@@ -435,20 +518,28 @@ impl EtherCrabWireRead for CoeDetails {
     }
 }
 
+/// SyncManager configuration, from the SII "SyncManager" category.
+///
+/// Defined in ETG1000.6 Table 24
 #[derive(Copy, Clone, PartialEq, Eq, ethercrab_wire::EtherCrabWireRead)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bytes = 8)]
 pub struct SyncManager {
+    /// Physical start address in the SubDevice's memory.
     #[wire(bytes = 2)]
-    pub(crate) start_addr: u16,
+    pub start_addr: u16,
+    /// Length in bytes.
     #[wire(bytes = 2)]
-    pub(crate) length: u16,
+    pub length: u16,
+    /// Initial value of the SyncManager control register.
     #[wire(bytes = 1, post_skip_bytes = 1)]
-    pub(crate) control: sync_manager_channel::Control,
+    pub control: sync_manager_channel::Control,
+    /// Enable flags.
     #[wire(bytes = 1)]
-    pub(crate) enable: SyncManagerEnable,
+    pub enable: SyncManagerEnable,
+    /// What the SyncManager is used for.
     #[wire(bytes = 1)]
-    pub(crate) usage_type: SyncManagerType,
+    pub usage_type: SyncManagerType,
 }
 
 impl core::fmt::Debug for SyncManager {
@@ -464,6 +555,7 @@ impl core::fmt::Debug for SyncManager {
 }
 
 bitflags::bitflags! {
+    /// SyncManager enable flags in the SII "SyncManager" category.
     #[derive(Debug, Copy, Clone, PartialEq, Eq)]
     pub struct SyncManagerEnable: u8 {
         /// Bit 0: enable.
@@ -502,6 +594,7 @@ impl defmt::Format for SyncManagerEnable {
     }
 }
 
+/// SyncManager usage.
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, ethercrab_wire::EtherCrabWireRead)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
@@ -522,23 +615,30 @@ pub enum SyncManagerType {
 impl SdoExpedited for SyncManagerType {}
 
 /// Defined in ETG2010 Table 14 – Structure Category TXPDO and RXPDO for each PDO
-#[derive(Clone, PartialEq, ethercrab_wire::EtherCrabWireRead)]
+#[derive(Copy, Clone, PartialEq, ethercrab_wire::EtherCrabWireRead)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bytes = 8)]
 pub struct Pdo {
+    /// PDO index, e.g. `0x1a00`.
     #[wire(bytes = 2)]
-    pub(crate) index: u16,
+    pub index: u16,
+    /// The number of entries in this PDO.
+    ///
+    /// Entries can be read with [`Eeprom::pdo_entries`](crate::sii::Eeprom::pdo_entries).
     #[wire(bytes = 1)]
-    pub(crate) num_entries: u8,
+    pub num_entries: u8,
+    /// The SyncManager this PDO is assigned to.
     #[wire(bytes = 1)]
-    pub(crate) sync_manager: u8,
+    pub sync_manager: u8,
+    /// Reference to DC synchronisation.
     #[wire(bytes = 1)]
-    pub(crate) dc_sync: u8,
+    pub dc_sync: u8,
     /// Index into EEPROM Strings section for PDO name.
     #[wire(bytes = 1)]
-    pub(crate) name_string_idx: u8,
+    pub name_string_idx: u8,
+    /// PDO flags.
     #[wire(bytes = 2)]
-    pub(crate) flags: PdoFlags,
+    pub flags: PdoFlags,
 
     // NOTE: Field is only used to sum up `bit_len`, so we don't need to read or store it.
     // Definition is left here in case we need it later.
@@ -548,8 +648,9 @@ pub struct Pdo {
 
     // NOTE: This field is skipped during parsing from the wire and is populated from all the
     // `PdoEntry`s later.
+    /// The total length of all entries in this PDO in bits.
     #[wire(skip)]
-    pub(crate) bit_len: u16,
+    pub bit_len: u16,
 }
 
 impl core::fmt::Debug for Pdo {
@@ -578,23 +679,32 @@ impl core::fmt::Debug for Pdo {
 //     }
 // }
 
-#[derive(Clone, PartialEq, ethercrab_wire::EtherCrabWireRead)]
+/// An entry in a [`Pdo`].
+///
+/// Defined in ETG2010 Table 14 – Structure Category TXPDO and RXPDO for each PDO entry
+#[derive(Copy, Clone, PartialEq, ethercrab_wire::EtherCrabWireRead)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bytes = 8)]
 pub struct PdoEntry {
+    /// Index of the object mapped by this entry, e.g. `0x6000`.
     #[wire(bytes = 2)]
-    pub(crate) index: u16,
+    pub index: u16,
+    /// Sub index of the object mapped by this entry.
     #[wire(bytes = 1)]
-    pub(crate) sub_index: u8,
+    pub sub_index: u8,
+    /// Index into EEPROM Strings section for the entry name.
     #[wire(bytes = 1)]
-    pub(crate) name_string_idx: u8,
+    pub name_string_idx: u8,
     // See page 103 of ETG2000
+    /// Data type of the entry.
     #[wire(bytes = 1)]
-    pub(crate) data_type: PrimitiveDataType,
+    pub data_type: PrimitiveDataType,
+    /// Length of the entry in bits.
     #[wire(bytes = 1)]
-    pub(crate) data_length_bits: u8,
+    pub data_length_bits: u8,
+    /// Reserved for future use.
     #[wire(bytes = 2)]
-    pub(crate) flags: u16,
+    pub flags: u16,
 }
 
 impl core::fmt::Debug for PdoEntry {
@@ -721,6 +831,9 @@ impl defmt::Format for MailboxProtocols {
     }
 }
 
+/// Standard mailbox configuration.
+///
+/// Defined in ETG2010 Table 2.
 #[derive(Copy, Clone, Default, PartialEq, ethercrab_wire::EtherCrabWireRead)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bytes = 10)]
@@ -743,6 +856,7 @@ pub struct DefaultMailbox {
 }
 
 impl DefaultMailbox {
+    /// Whether the SubDevice has a mailbox.
     pub fn has_mailbox(&self) -> bool {
         !self.supported_protocols.is_empty() && self.subdevice_receive_size > 0
             || self.subdevice_send_size > 0
@@ -773,6 +887,91 @@ impl core::fmt::Debug for DefaultMailbox {
     }
 }
 
+/// Mailbox configuration used in the BOOT state, e.g. for firmware updates over FoE.
+///
+/// Defined in ETG2010 Table 2.
+#[derive(Copy, Clone, Default, PartialEq, Eq, ethercrab_wire::EtherCrabWireRead)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[wire(bytes = 8)]
+pub struct BootstrapMailbox {
+    /// Master to SubDevice receive mailbox address offset.
+    #[wire(bytes = 2)]
+    pub subdevice_receive_offset: u16,
+    /// Master to SubDevice receive mailbox size.
+    #[wire(bytes = 2)]
+    pub subdevice_receive_size: u16,
+    /// SubDevice to master send mailbox address offset.
+    #[wire(bytes = 2)]
+    pub subdevice_send_offset: u16,
+    /// SubDevice to master send mailbox size.
+    #[wire(bytes = 2)]
+    pub subdevice_send_size: u16,
+}
+
+impl BootstrapMailbox {
+    /// Whether the SubDevice supports the BOOT state with a mailbox.
+    pub fn has_mailbox(&self) -> bool {
+        self.subdevice_receive_size > 0 && self.subdevice_send_size > 0
+    }
+}
+
+impl core::fmt::Debug for BootstrapMailbox {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("BootstrapMailbox")
+            .field(
+                "subdevice_receive_offset",
+                &format_args!("{:#06x}", self.subdevice_receive_offset),
+            )
+            .field(
+                "subdevice_receive_size",
+                &format_args!("{:#06x}", self.subdevice_receive_size),
+            )
+            .field(
+                "subdevice_send_offset",
+                &format_args!("{:#06x}", self.subdevice_send_offset),
+            )
+            .field(
+                "subdevice_send_size",
+                &format_args!("{:#06x}", self.subdevice_send_size),
+            )
+            .finish()
+    }
+}
+
+/// Distributed Clock operation mode, from the SII "DC" category.
+///
+/// Defined in ETG2010 Table 16 – Structure Category DC for each operation mode
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ethercrab_wire::EtherCrabWireRead)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[wire(bytes = 24)]
+pub struct SiiDistributedClock {
+    /// SYNC0 cycle time in ns.
+    #[wire(bytes = 4)]
+    pub cycle_time_0: u32,
+    /// SYNC0 shift time in ns.
+    #[wire(bytes = 4)]
+    pub shift_time_0: u32,
+    /// SYNC1 shift time in ns.
+    #[wire(bytes = 4)]
+    pub shift_time_1: u32,
+    /// SYNC1 cycle time as a factor of the SYNC0 cycle time.
+    #[wire(bytes = 2)]
+    pub sync1_cycle_factor: i16,
+    /// Value to write to the DC "Assign/Activate" register (`0x0980`) in this mode.
+    #[wire(bytes = 2)]
+    pub assign_activate: u16,
+    /// SYNC0 cycle time as a factor of the application cycle time.
+    #[wire(bytes = 2)]
+    pub sync0_cycle_factor: i16,
+    /// Index into EEPROM Strings section for the operation mode name, e.g. `DcSync`.
+    #[wire(bytes = 1)]
+    pub name_string_idx: u8,
+    /// Index into EEPROM Strings section for the operation mode description.
+    #[wire(bytes = 1, post_skip_bytes = 4)]
+    pub description_string_idx: u8,
+    // reserved: [u8; 4]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -805,6 +1004,28 @@ mod tests {
         assert_eq!(packed, [0x00, 0x01, 0x34, 0x12, 0x00, 0x00]);
     }
 
+    #[test]
+    fn sii_request_write_pack() {
+        let packed = SiiRequest::write(0x1234).pack();
+
+        assert_eq!(packed, [0x01, 0x02, 0x34, 0x12, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn sii_request_large_address() {
+        let packed = SiiRequest::read(0x0001_2345).pack();
+
+        assert_eq!(packed, [0x00, 0x01, 0x45, 0x23, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn sii_address_size() {
+        // 16 kbit
+        assert_eq!(SiiAddressSize::U8.max_words(), 0x0400);
+        // 4 Mbit, past the range of a 16 bit WORD address
+        assert_eq!(SiiAddressSize::U16.max_words(), 0x0004_0000);
+    }
+
     #[test]
     fn sii_control_unpack() {
         let ctl = SiiControl {
@@ -0,0 +1,124 @@
+//! Integration with the [`embassy`](https://embassy.dev) ecosystem.
+//!
+//! Requires the `embassy` feature.
+//!
+//! On `no_std` targets, all EtherCrab timeouts use [`embassy-time`](https://docs.rs/embassy-time),
+//! so an `embassy-time` driver must be linked into the application, e.g. the one provided by the
+//! HAL. With the `std` feature, timeouts use the `std` timers instead, and a driver is only needed
+//! if [`ethercat_now`] is called.
+//!
+//! # Examples
+//!
+//! Embassy tasks cannot be generic, so a small task wrapping [`tx_rx_task`] must be written for the
+//! network device in use.
+//!
+//! ```rust,ignore
+//! use ethercrab::{
+//!     embassy::{ethercat_now, tx_rx_task},
+//!     MainDevice, MainDeviceConfig, PduRx, PduStorage, PduTx, Timeouts,
+//! };
+//!
+//! static PDU_STORAGE: PduStorage<8, { PduStorage::element_size(256) }> = PduStorage::new();
+//!
+//! #[embassy_executor::task]
+//! async fn ethercat_tx_rx(
+//!     device: Ethernet<'static, ETH, GenericSMI>,
+//!     pdu_tx: PduTx<'static>,
+//!     pdu_rx: PduRx<'static>,
+//! ) {
+//!     if let Err(e) = tx_rx_task(device, pdu_tx, pdu_rx).await {
+//!         defmt::error!("TX/RX task failed: {}", e);
+//!     }
+//! }
+//!
+//! #[embassy_executor::main]
+//! async fn main(spawner: Spawner) {
+//!     let (pdu_tx, pdu_rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+//!
+//!     // Any embassy-net-driver device, e.g. from the HAL
+//!     let device = board::ethernet_device();
+//!
+//!     spawner.must_spawn(ethercat_tx_rx(device, pdu_tx, pdu_rx));
+//!
+//!     let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+//!
+//!     let group = maindevice
+//!         .init_single_group::<16, 256>(ethercat_now)
+//!         .await
+//!         .expect("init");
+//! }
+//! ```
+
+use crate::{error::Error, tx_rx_device, PduRx, PduTx, RawEthernetDevice};
+use core::task::{Context, Poll};
+use embassy_net_driver::{Driver, RxToken, TxToken};
+
+/// A [`RawEthernetDevice`] adapter for any [`embassy_net_driver::Driver`].
+///
+/// Drivers wake the TX/RX task when a frame is received or a transmit buffer becomes available,
+/// so no busy polling is required.
+#[derive(Debug)]
+pub struct EmbassyDevice<D>(pub D);
+
+impl<D> EmbassyDevice<D>
+where
+    D: Driver,
+{
+    /// Get the wrapped driver back.
+    pub fn into_inner(self) -> D {
+        self.0
+    }
+}
+
+impl<D> RawEthernetDevice for EmbassyDevice<D>
+where
+    D: Driver,
+{
+    fn poll_transmit(&mut self, cx: &mut Context<'_>, frame: &[u8]) -> Poll<Result<(), Error>> {
+        match self.0.transmit(cx) {
+            Some(tx) => {
+                tx.consume(frame.len(), |buf| buf.copy_from_slice(frame));
+
+                Poll::Ready(Ok(()))
+            }
+            None => Poll::Pending,
+        }
+    }
+
+    fn poll_receive(
+        &mut self,
+        cx: &mut Context<'_>,
+        on_frame: impl FnOnce(&[u8]),
+    ) -> Poll<Result<(), Error>> {
+        match self.0.receive(cx) {
+            Some((rx, _tx)) => {
+                rx.consume(|frame| on_frame(frame));
+
+                Poll::Ready(Ok(()))
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Send and receive EtherCAT frames using an [`embassy_net_driver::Driver`].
+///
+/// This is [`tx_rx_device`] with the driver wrapped in an [`EmbassyDevice`]. It must be run
+/// concurrently with the application, e.g. from its own embassy task, and only returns if an
+/// error occurs.
+pub async fn tx_rx_task<D>(driver: D, pdu_tx: PduTx<'_>, pdu_rx: PduRx<'_>) -> Result<(), Error>
+where
+    D: Driver,
+{
+    tx_rx_device(EmbassyDevice(driver), pdu_tx, pdu_rx).await
+}
+
+/// Get the time in nanoseconds since the `embassy-time` driver started.
+///
+/// This clock is monotonic and can be passed to e.g. [`MainDevice::init`](crate::MainDevice::init).
+/// Its resolution is that of the `embassy-time` tick rate.
+pub fn ethercat_now() -> u64 {
+    let ticks = u128::from(embassy_time::Instant::now().as_ticks());
+
+    (ticks * 1_000_000_000 / u128::from(embassy_time::TICK_HZ)) as u64
+}
@@ -1,8 +1,9 @@
 //! EtherCrab error types.
 
 pub use crate::coe::abort_code::CoeAbortCode;
-use crate::{command::Command, fmt, AlStatusCode, SubDeviceState};
+use crate::{command::Command, fmt, AlStatusCode, ConfiguredAddress, SubDeviceState};
 use core::{cell::BorrowError, num::TryFromIntError};
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireWriteSized};
 
 /// An EtherCrab error.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -17,6 +18,11 @@ pub enum Error {
         expected: u16,
         /// The actual value received.
         received: u16,
+        /// The command of the PDU with the wrong working counter.
+        ///
+        /// This is a `NOP` if the error was not caused by a single PDU, e.g. a group cycle checked
+        /// by [`SafeOpFallback`](crate::subdevice_group::SafeOpFallback).
+        command: PduCommand,
     },
     /// Failed to borrow an item. This likely points to a race condition.
     Borrow,
@@ -29,9 +35,9 @@ pub enum Error {
     /// A string was too long to fit in a fixed size buffer.
     StringTooLong {
         /// The length of the fixed size buffer.
-        max_length: usize,
+        max_length: u16,
         /// The length of the input string.
-        string_length: usize,
+        string_length: u16,
     },
     /// A mailbox error was encountered.
     Mailbox(MailboxError),
@@ -42,10 +48,10 @@ pub enum Error {
     /// A frame was only partially sent.
     PartialSend {
         /// Frame length in bytes.
-        len: usize,
+        len: u16,
 
         /// The number of bytes sent.
-        sent: usize,
+        sent: u16,
     },
     /// A value may be too large or otherwise could not be converted into a target type.
     ///
@@ -55,10 +61,10 @@ pub enum Error {
     /// SubDevices in the group.
     PdiTooLong {
         /// Maximum PDI length.
-        max_length: usize,
+        max_length: u32,
 
         /// Actual PDI length.
-        desired_length: usize,
+        desired_length: u32,
     },
     /// An item in a list could not be found.
     NotFound {
@@ -66,15 +72,44 @@ pub enum Error {
         item: Item,
 
         /// An index into a list of items.
-        index: Option<usize>,
+        index: Option<u16>,
     },
     /// An internal error occurred. This indicates something that shouldn't happen within EtherCrab.
     Internal,
     /// There is a problem with the discovered EtherCAT SubDevice topology.
     Topology,
-    /// An error was read back from one or more SubDevices when attempting to transition to a new
-    /// state.
-    StateTransition,
+    /// An error was read back from a SubDevice when attempting to transition to a new state.
+    ///
+    /// The SubDevice's AL status code (register `0x0134`) is read when the failure is detected to
+    /// give more detail on why the transition was refused, e.g.
+    /// [`AlStatusCode::InvalidSyncManagerConfiguration`].
+    StateTransition {
+        /// SubDevice address.
+        configured_address: ConfiguredAddress,
+
+        /// The state the SubDevice was requested to transition to.
+        requested: SubDeviceState,
+
+        /// The state the SubDevice is currently in.
+        current: SubDeviceState,
+
+        /// The AL status code read from the SubDevice.
+        status_code: AlStatusCode,
+    },
+    /// A SubDevice refused a state transition on every attempt allowed by
+    /// [`MainDeviceConfig::state_transition_retry`](crate::MainDeviceConfig::state_transition_retry).
+    ///
+    /// Each refusal was acknowledged before the transition was requested again.
+    StateTransitionRetries {
+        /// SubDevice address.
+        configured_address: ConfiguredAddress,
+
+        /// The state the SubDevice was requested to transition to.
+        requested: SubDeviceState,
+
+        /// The state and AL status code read from the SubDevice after each refused attempt.
+        history: StateTransitionHistory,
+    },
     /// An unknown SubDevice was encountered during device discovery/initialisation.
     UnknownSubDevice,
     /// An invalid state was encountered.
@@ -86,7 +121,7 @@ pub enum Error {
         actual: SubDeviceState,
 
         /// SubDevice address.
-        configured_address: u16,
+        configured_address: ConfiguredAddress,
     },
 
     /// An error occurred encoding or decoding an item.
@@ -97,6 +132,81 @@ pub enum Error {
 
     /// A distributed clock error occurred.
     DistributedClock(DistributedClockError),
+
+    /// The number of SubDevices discovered on the network did not match
+    /// [`MainDeviceConfig::expected_subdevices`](crate::MainDeviceConfig::expected_subdevices).
+    SubDeviceCount {
+        /// The expected number of SubDevices.
+        expected: u16,
+
+        /// The number of SubDevices discovered on the network.
+        found: u16,
+    },
+
+    /// The MainDevice configuration is invalid.
+    Config(ConfigError),
+
+    /// A DS402 drive failed to complete an operation.
+    Ds402(Ds402Error),
+
+    /// An operation on a SubDevice failed.
+    ///
+    /// Use [`Error::cause`] to get the underlying error, e.g. to match on it regardless of where it
+    /// occurred.
+    ///
+    /// Only errors that can be stored compactly alongside the context are wrapped: timeouts,
+    /// frame, PDU and working counter errors, and EEPROM, wire, AL status and mailbox errors.
+    /// Errors that already identify what failed, e.g. [`Error::StateTransition`] or
+    /// [`Error::NotFound`], mailbox errors for a different SDO than the one being accessed and
+    /// [`PduValidationError::CommandMismatch`] are returned without context.
+    Context {
+        /// The SubDevice and operation that failed.
+        context: ErrorContext,
+
+        /// The underlying error.
+        cause: Cause,
+    },
+}
+
+impl Error {
+    /// Attach the SubDevice and operation this error occurred in.
+    ///
+    /// Errors that already have context, or that carry too much data to be stored alongside it
+    /// while keeping `Error` small, are returned unchanged.
+    pub(crate) fn with_context(self, context: ErrorContext) -> Self {
+        match Cause::new(self, context) {
+            Some(cause) => Error::Context { context, cause },
+            None => self,
+        }
+    }
+
+    /// Get the SubDevice and operation this error occurred in, if known.
+    pub fn context(&self) -> Option<ErrorContext> {
+        match self {
+            Error::Context { context, .. } => Some(*context),
+            _ => None,
+        }
+    }
+
+    /// Get the underlying error, without any [`ErrorContext`].
+    ///
+    /// Errors without context are returned as is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ethercrab::error::{Error, MailboxError};
+    ///
+    /// fn is_missing_object(e: Error) -> bool {
+    ///     matches!(e.cause(), Error::Mailbox(MailboxError::Aborted { .. }))
+    /// }
+    /// ```
+    pub fn cause(&self) -> Error {
+        match self {
+            Error::Context { context, cause } => cause.into_error(*context),
+            e => *e,
+        }
+    }
 }
 
 #[cfg(feature = "std")]
@@ -106,13 +216,23 @@ impl core::fmt::Display for Error {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Error::Pdu(e) => write!(f, "pdu: {}", e),
-            Error::WorkingCounter { expected, received } => {
-                write!(f, "working counter expected {}, got {}", expected, received)
+            Error::WorkingCounter {
+                expected,
+                received,
+                command,
+            } => {
+                write!(f, "working counter expected {}, got {}", expected, received)?;
+
+                if command.is_nop() {
+                    Ok(())
+                } else {
+                    write!(f, " ({})", command)
+                }
             }
             Error::Borrow => f.write_str("already borrowed"),
             Error::Timeout => f.write_str("timeout"),
             Error::Eeprom(e) => write!(f, "eeprom: {}", e),
-            Error::Capacity(item) => write!(f, "not enough capacity for {:?}", item),
+            Error::Capacity(item) => write!(f, "not enough capacity for {}", item),
             Error::StringTooLong {
                 max_length,
                 string_length,
@@ -136,14 +256,34 @@ impl core::fmt::Display for Error {
                 "Process Data Image is too long ({} bytes), max length is {}",
                 desired_length, max_length
             ),
-            Error::NotFound { item, index } => {
-                write!(f, "item kind {:?} not found (index: {:?})", item, index)
-            }
+            Error::NotFound { item, index } => match index {
+                Some(index) => write!(f, "{} {} not found", item, index),
+                None => write!(f, "{} not found", item),
+            },
             Error::Internal => f.write_str("internal error"),
             Error::Topology => f.write_str("topology"),
-            Error::StateTransition => {
-                f.write_str("a SubDevice failed to transition to a new state")
-            }
+            Error::StateTransition {
+                configured_address,
+                requested,
+                current,
+                status_code,
+            } => write!(
+                f,
+                "SubDevice {:#06x} failed to transition to {} (currently {}): {}",
+                configured_address, requested, current, status_code
+            ),
+            Error::StateTransitionRetries {
+                configured_address,
+                requested,
+                history,
+            } => write!(
+                f,
+                "SubDevice {:#06x} failed to transition to {} after {} attempts: {}",
+                configured_address,
+                requested,
+                history.attempts(),
+                history
+            ),
             Error::UnknownSubDevice => f.write_str("unknown SubDevice"),
             Error::InvalidState {
                 expected,
@@ -157,6 +297,14 @@ impl core::fmt::Display for Error {
             Error::Wire(e) => write!(f, "wire encode/decode error: {}", e),
             Error::SubDevice(e) => write!(f, "subdevice error: {}", e),
             Error::DistributedClock(e) => write!(f, "distributed clock: {}", e),
+            Error::SubDeviceCount { expected, found } => write!(
+                f,
+                "expected {} SubDevices, found {} on the network",
+                expected, found
+            ),
+            Error::Config(e) => write!(f, "config: {}", e),
+            Error::Ds402(e) => write!(f, "ds402: {}", e),
+            Error::Context { context, .. } => write!(f, "{}: {}", context, self.cause()),
         }
     }
 }
@@ -167,6 +315,410 @@ impl From<BorrowError> for Error {
     }
 }
 
+/// The SubDevice and operation an [`Error::Context`] occurred in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ErrorContext {
+    /// Configured station address of the SubDevice.
+    pub configured_address: ConfiguredAddress,
+
+    /// The operation being performed on the SubDevice.
+    pub operation: Operation,
+}
+
+impl ErrorContext {
+    pub(crate) fn new(configured_address: ConfiguredAddress, operation: Operation) -> Self {
+        Self {
+            configured_address,
+            operation,
+        }
+    }
+}
+
+impl core::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "SubDevice {:#06x} {}",
+            self.configured_address, self.operation
+        )
+    }
+}
+
+/// An operation performed on a SubDevice, used as [`ErrorContext`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Operation {
+    /// An SDO read (upload).
+    SdoRead {
+        /// SDO index.
+        index: u16,
+        /// SDO sub-index.
+        sub_index: u8,
+    },
+    /// An SDO write (download).
+    SdoWrite {
+        /// SDO index.
+        index: u16,
+        /// SDO sub-index.
+        sub_index: u8,
+    },
+    /// An EEPROM read.
+    EepromRead {
+        /// Word address the read started at, saturating at `u16::MAX`.
+        word: u16,
+    },
+    /// An EEPROM write.
+    EepromWrite {
+        /// Word address written to, saturating at `u16::MAX`.
+        word: u16,
+    },
+    /// A state transition.
+    StateTransition {
+        /// The state the SubDevice was requested to transition to.
+        requested: SubDeviceState,
+    },
+    /// A register read.
+    RegisterRead {
+        /// Register address.
+        register: u16,
+    },
+    /// A register write.
+    RegisterWrite {
+        /// Register address.
+        register: u16,
+    },
+}
+
+impl Operation {
+    /// SDO index and sub-index, if this is an SDO operation.
+    fn sdo(&self) -> Option<(u16, u8)> {
+        match *self {
+            Operation::SdoRead { index, sub_index } | Operation::SdoWrite { index, sub_index } => {
+                Some((index, sub_index))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Display for Operation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Operation::SdoRead { index, sub_index } => {
+                write!(f, "SDO {:#06x}:{} read", index, sub_index)
+            }
+            Operation::SdoWrite { index, sub_index } => {
+                write!(f, "SDO {:#06x}:{} write", index, sub_index)
+            }
+            Operation::EepromRead { word } => write!(f, "EEPROM read at word {:#06x}", word),
+            Operation::EepromWrite { word } => write!(f, "EEPROM write at word {:#06x}", word),
+            Operation::StateTransition { requested } => {
+                write!(f, "state transition to {}", requested)
+            }
+            Operation::RegisterRead { register } => write!(f, "register {:#06x} read", register),
+            Operation::RegisterWrite { register } => write!(f, "register {:#06x} write", register),
+        }
+    }
+}
+
+/// The underlying error of an [`Error::Context`].
+///
+/// This is stored compactly, so use [`Error::cause`] to get it as an [`Error`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Cause(CauseInner);
+
+/// Errors that can be stored alongside an [`ErrorContext`] without growing [`Error`].
+///
+/// SDO addresses are taken from the context's [`Operation`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+enum CauseInner {
+    Timeout,
+    Borrow,
+    SendFrame,
+    ReceiveFrame,
+    Internal,
+    Eeprom(EepromError),
+    Wire(ethercrab_wire::WireError),
+    SubDevice(AlStatusCode),
+    /// A raw [`CoeAbortCode`], which has no niche to store the other variants in.
+    Aborted(u32),
+    TooLong,
+    NoMailbox,
+    SdoResponseInvalid,
+    InvalidCount,
+    Emergency {
+        error_code: u16,
+        error_register: u8,
+    },
+    WorkingCounter {
+        expected: u8,
+        received: u8,
+        command: PduCommand,
+    },
+    Pdu(PduCause),
+}
+
+/// A [`PduError`] without [`PduValidationError::CommandMismatch`], which is too large to store.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+enum PduCause {
+    Decode,
+    Ethernet,
+    TooLong,
+    CreateFrame,
+    InvalidIndex(u8),
+    IndexMismatch { sent: u8, received: u8 },
+    InvalidFrameState,
+    SwapState,
+    NoWaker,
+}
+
+impl PduCause {
+    fn new(error: PduError) -> Option<Self> {
+        let cause = match error {
+            PduError::Decode => PduCause::Decode,
+            PduError::Ethernet => PduCause::Ethernet,
+            PduError::TooLong => PduCause::TooLong,
+            PduError::CreateFrame => PduCause::CreateFrame,
+            PduError::InvalidIndex(index) => PduCause::InvalidIndex(index),
+            PduError::Validation(PduValidationError::IndexMismatch { sent, received }) => {
+                PduCause::IndexMismatch { sent, received }
+            }
+            PduError::Validation(PduValidationError::CommandMismatch { .. }) => return None,
+            PduError::InvalidFrameState => PduCause::InvalidFrameState,
+            PduError::SwapState => PduCause::SwapState,
+            PduError::NoWaker => PduCause::NoWaker,
+        };
+
+        Some(cause)
+    }
+
+    fn into_error(self) -> PduError {
+        match self {
+            PduCause::Decode => PduError::Decode,
+            PduCause::Ethernet => PduError::Ethernet,
+            PduCause::TooLong => PduError::TooLong,
+            PduCause::CreateFrame => PduError::CreateFrame,
+            PduCause::InvalidIndex(index) => PduError::InvalidIndex(index),
+            PduCause::IndexMismatch { sent, received } => {
+                PduError::Validation(PduValidationError::IndexMismatch { sent, received })
+            }
+            PduCause::InvalidFrameState => PduError::InvalidFrameState,
+            PduCause::SwapState => PduError::SwapState,
+            PduCause::NoWaker => PduError::NoWaker,
+        }
+    }
+}
+
+impl Cause {
+    fn new(error: Error, context: ErrorContext) -> Option<Self> {
+        let sdo = context.operation.sdo();
+
+        let inner = match error {
+            Error::Timeout => CauseInner::Timeout,
+            Error::Borrow => CauseInner::Borrow,
+            Error::SendFrame => CauseInner::SendFrame,
+            Error::ReceiveFrame => CauseInner::ReceiveFrame,
+            Error::Internal => CauseInner::Internal,
+            Error::Eeprom(e) => CauseInner::Eeprom(e),
+            Error::Wire(e) => CauseInner::Wire(e),
+            Error::SubDevice(code) => CauseInner::SubDevice(code),
+            Error::Mailbox(MailboxError::Aborted {
+                code,
+                address,
+                sub_index,
+            }) if sdo == Some((address, sub_index)) => {
+                CauseInner::Aborted(u32::from_le_bytes(code.pack()))
+            }
+            Error::Mailbox(MailboxError::TooLong { address, sub_index })
+                if sdo == Some((address, sub_index)) =>
+            {
+                CauseInner::TooLong
+            }
+            Error::Mailbox(MailboxError::SdoResponseInvalid { address, sub_index })
+                if sdo == Some((address, sub_index)) =>
+            {
+                CauseInner::SdoResponseInvalid
+            }
+            Error::Mailbox(MailboxError::NoMailbox) => CauseInner::NoMailbox,
+            Error::Mailbox(MailboxError::InvalidCount) => CauseInner::InvalidCount,
+            Error::Mailbox(MailboxError::Emergency {
+                error_code,
+                error_register,
+            }) => CauseInner::Emergency {
+                error_code,
+                error_register,
+            },
+            // Working counters of PDUs addressed to a single SubDevice are small
+            Error::WorkingCounter {
+                expected,
+                received,
+                command,
+            } => CauseInner::WorkingCounter {
+                expected: u8::try_from(expected).ok()?,
+                received: u8::try_from(received).ok()?,
+                command,
+            },
+            Error::Pdu(e) => CauseInner::Pdu(PduCause::new(e)?),
+            _ => return None,
+        };
+
+        Some(Self(inner))
+    }
+
+    fn into_error(self, context: ErrorContext) -> Error {
+        // `Cause::new` only stores SDO errors for SDO operations
+        let (address, sub_index) = context.operation.sdo().unwrap_or_default();
+
+        match self.0 {
+            CauseInner::Timeout => Error::Timeout,
+            CauseInner::Borrow => Error::Borrow,
+            CauseInner::SendFrame => Error::SendFrame,
+            CauseInner::ReceiveFrame => Error::ReceiveFrame,
+            CauseInner::Internal => Error::Internal,
+            CauseInner::Eeprom(e) => Error::Eeprom(e),
+            CauseInner::Wire(e) => Error::Wire(e),
+            CauseInner::SubDevice(code) => Error::SubDevice(code),
+            CauseInner::Aborted(code) => Error::Mailbox(MailboxError::Aborted {
+                code: CoeAbortCode::unpack_from_slice(&code.to_le_bytes())
+                    .unwrap_or(CoeAbortCode::Unknown(code)),
+                address,
+                sub_index,
+            }),
+            CauseInner::TooLong => Error::Mailbox(MailboxError::TooLong { address, sub_index }),
+            CauseInner::NoMailbox => Error::Mailbox(MailboxError::NoMailbox),
+            CauseInner::SdoResponseInvalid => {
+                Error::Mailbox(MailboxError::SdoResponseInvalid { address, sub_index })
+            }
+            CauseInner::InvalidCount => Error::Mailbox(MailboxError::InvalidCount),
+            CauseInner::Emergency {
+                error_code,
+                error_register,
+            } => Error::Mailbox(MailboxError::Emergency {
+                error_code,
+                error_register,
+            }),
+            CauseInner::WorkingCounter {
+                expected,
+                received,
+                command,
+            } => Error::WorkingCounter {
+                expected: expected.into(),
+                received: received.into(),
+                command,
+            },
+            CauseInner::Pdu(cause) => Error::Pdu(cause.into_error()),
+        }
+    }
+}
+
+/// A refused attempt to transition a SubDevice to a new state.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StateTransitionAttempt {
+    /// The state the SubDevice was in after refusing the transition.
+    pub current: SubDeviceState,
+
+    /// The AL status code read from the SubDevice.
+    pub status_code: AlStatusCode,
+}
+
+impl core::fmt::Display for StateTransitionAttempt {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} ({})", self.current, self.status_code)
+    }
+}
+
+/// The refused attempts of a SubDevice state transition, returned in
+/// [`Error::StateTransitionRetries`].
+///
+/// The total number of attempts is always counted, but only the most recent
+/// [`CAPACITY`](StateTransitionHistory::CAPACITY) attempts are kept. Attempts are stored in their
+/// raw 3 byte register form to keep [`Error`] small.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StateTransitionHistory {
+    attempts: [u8; 2],
+    recent: [[u8; 3]; Self::CAPACITY],
+}
+
+impl StateTransitionHistory {
+    /// The number of attempts kept in the history.
+    pub const CAPACITY: usize = 3;
+
+    pub(crate) const fn new() -> Self {
+        Self {
+            attempts: [0; 2],
+            recent: [[0; 3]; Self::CAPACITY],
+        }
+    }
+
+    /// Record a refused attempt, replacing the oldest one if the history is full.
+    pub(crate) fn push(&mut self, attempt: StateTransitionAttempt) {
+        let attempts = self.attempts();
+        let [code_lo, code_hi] = u16::from(attempt.status_code).to_le_bytes();
+
+        self.recent[usize::from(attempts) % Self::CAPACITY] =
+            [u8::from(attempt.current), code_lo, code_hi];
+
+        self.attempts = attempts.saturating_add(1).to_le_bytes();
+    }
+
+    /// The total number of refused attempts, including any no longer kept in the history.
+    pub fn attempts(&self) -> u16 {
+        u16::from_le_bytes(self.attempts)
+    }
+
+    /// The most recent refused attempt.
+    pub fn last(&self) -> Option<StateTransitionAttempt> {
+        self.iter().last()
+    }
+
+    /// Iterate over the kept attempts, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = StateTransitionAttempt> + '_ {
+        let attempts = usize::from(self.attempts());
+        let len = attempts.min(Self::CAPACITY);
+        let start = attempts.saturating_sub(Self::CAPACITY);
+
+        (start..start + len).map(|index| {
+            let [state, code_lo, code_hi] = self.recent[index % Self::CAPACITY];
+
+            StateTransitionAttempt {
+                current: SubDeviceState::from(state),
+                status_code: AlStatusCode::from(u16::from_le_bytes([code_lo, code_hi])),
+            }
+        })
+    }
+}
+
+impl core::fmt::Display for StateTransitionHistory {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if usize::from(self.attempts()) > Self::CAPACITY {
+            f.write_str("..., ")?;
+        }
+
+        for (i, attempt) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{}", attempt)?;
+        }
+
+        Ok(())
+    }
+}
+
 /// The kind of item being looked for.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -186,6 +738,88 @@ pub enum Item {
     FmmuEx,
     /// A user-defined SubDevice group.
     Group,
+    /// An object in a SubDevice's CoE object dictionary.
+    Object,
+}
+
+impl core::fmt::Display for Item {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Item::SubDevice => "SubDevice",
+            Item::Pdo => "PDO",
+            Item::Fmmu => "FMMU",
+            Item::SyncManager => "sync manager",
+            Item::PdoEntry => "PDO entry",
+            Item::FmmuEx => "FMMU extension",
+            Item::Group => "group",
+            Item::Object => "object",
+        })
+    }
+}
+
+/// The command of the PDU an error occurred in, e.g. `FPRD(addr 0x1001, reg 0x0130)`.
+///
+/// This is stored as the raw command code and address field of the PDU header to keep [`Error`]
+/// small. Use [`command`](PduCommand::command) to decode it.
+#[derive(Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PduCommand {
+    code: u8,
+    raw: [u8; 4],
+}
+
+impl PduCommand {
+    /// A `NOP` command, used when an error did not occur in a single PDU.
+    pub const NOP: Self = Self {
+        code: 0,
+        raw: [0; 4],
+    };
+
+    /// Decode the command.
+    ///
+    /// Returns `None` if the command code is not one EtherCrab sends.
+    pub fn command(&self) -> Option<Command> {
+        Command::parse(self.code, self.raw)
+    }
+
+    /// Whether this is a `NOP` command.
+    pub fn is_nop(&self) -> bool {
+        *self == Self::NOP
+    }
+}
+
+impl From<Command> for PduCommand {
+    fn from(command: Command) -> Self {
+        Self {
+            code: command.code(),
+            raw: command.pack(),
+        }
+    }
+}
+
+impl core::fmt::Debug for PduCommand {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(self, f)
+    }
+}
+
+impl core::fmt::Display for PduCommand {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.command() {
+            Some(command) => write!(f, "{}", command),
+            None => write!(f, "unknown command {:#04x}", self.code),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for PduCommand {
+    fn format(&self, f: defmt::Formatter) {
+        match self.command() {
+            Some(command) => defmt::write!(f, "{}", command),
+            None => defmt::write!(f, "unknown command {=u8:#04x}", self.code),
+        }
+    }
 }
 
 /// Low-level PDU (Process Data Unit) error.
@@ -252,6 +886,69 @@ impl core::fmt::Display for DistributedClockError {
     }
 }
 
+/// An error reported by a DS402 drive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Ds402Error {
+    /// The drive set the homing error bit in its status word.
+    Homing,
+    /// The drive faulted. Use [`Ds402::read_fault`](crate::ds402::Ds402::read_fault) for details.
+    Fault,
+    /// The requested mode of operation is not supported by this operation.
+    UnsupportedMode,
+}
+
+impl core::fmt::Display for Ds402Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Homing => f.write_str("homing error"),
+            Self::Fault => f.write_str("drive fault"),
+            Self::UnsupportedMode => f.write_str("unsupported mode of operation"),
+        }
+    }
+}
+
+/// An invalid MainDevice configuration was passed to
+/// [`MainDeviceBuilder::build`](crate::MainDeviceBuilder::build).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ConfigError {
+    /// The PDU response timeout is zero.
+    ZeroPduTimeout,
+    /// The initial delay of an exponential retry backoff is larger than its maximum.
+    BackoffRange,
+    /// The expected number of SubDevices is zero.
+    NoExpectedSubDevices,
+    /// The VLAN ID is larger than 4094, or the priority code point is larger than 7.
+    InvalidVlanTag,
+    /// The number of frames in runtime allocated PDU storage is zero, larger than 255 or not a
+    /// power of two.
+    InvalidStorageSize,
+    /// The PDU storage has already been split into TX, RX and PDU loop handles.
+    StorageAlreadySplit,
+}
+
+impl core::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::ZeroPduTimeout => f.write_str("PDU timeout must be greater than zero"),
+            Self::BackoffRange => {
+                f.write_str("initial retry backoff must not be larger than maximum backoff")
+            }
+            Self::NoExpectedSubDevices => {
+                f.write_str("expected number of SubDevices must be greater than zero")
+            }
+            Self::InvalidVlanTag => f.write_str("VLAN ID or priority out of range"),
+            Self::InvalidStorageSize => {
+                f.write_str("number of PDU storage frames must be a power of two up to 128")
+            }
+            Self::StorageAlreadySplit => f.write_str("PDU storage can only be split once"),
+        }
+    }
+}
+
 /// CoE mailbox error.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -342,6 +1039,18 @@ pub enum EepromError {
     SectionUnderrun,
     /// An attempt to clear errors on the device failed.
     ClearErrors,
+    /// The SubDevice rejected an EEPROM write or failed to complete it.
+    Write,
+    /// The given address is past the end of the range addressable by the SubDevice's EEPROM.
+    AddressOutOfRange,
+    /// The configuration area checksum stored in the EEPROM does not match its contents, e.g.
+    /// because a write was interrupted.
+    Checksum {
+        /// Checksum calculated from the configuration area.
+        expected: u8,
+        /// Checksum stored in the EEPROM.
+        actual: u8,
+    },
 }
 
 impl core::fmt::Display for EepromError {
@@ -352,6 +1061,13 @@ impl core::fmt::Display for EepromError {
             EepromError::NoCategory => f.write_str("category not found"),
             EepromError::SectionUnderrun => f.write_str("section too short to fill buffer"),
             EepromError::ClearErrors => f.write_str("clear device errors failed"),
+            EepromError::Write => f.write_str("write failed"),
+            EepromError::AddressOutOfRange => f.write_str("address out of range"),
+            EepromError::Checksum { expected, actual } => write!(
+                f,
+                "configuration area checksum mismatch: expected {:#04x}, got {:#04x}",
+                expected, actual
+            ),
         }
     }
 }
@@ -395,9 +1111,9 @@ pub enum PduValidationError {
     /// The received command does not match the one sent.
     CommandMismatch {
         /// Sent command.
-        sent: Command,
+        sent: PduCommand,
         /// Received command.
-        received: Command,
+        received: PduCommand,
     },
 }
 
@@ -422,6 +1138,16 @@ impl core::fmt::Display for PduValidationError {
     }
 }
 
+/// Narrow a length or index to fit in an error field, saturating instead of panicking.
+pub(crate) fn saturate_u16(value: usize) -> u16 {
+    u16::try_from(value).unwrap_or(u16::MAX)
+}
+
+/// Narrow a length to fit in an error field, saturating instead of panicking.
+pub(crate) fn saturate_u32(value: usize) -> u32 {
+    u32::try_from(value).unwrap_or(u32::MAX)
+}
+
 impl From<PduError> for Error {
     fn from(e: PduError) -> Self {
         Self::Pdu(e)
@@ -434,6 +1160,12 @@ impl From<EepromError> for Error {
     }
 }
 
+impl From<ConfigError> for Error {
+    fn from(e: ConfigError) -> Self {
+        Self::Config(e)
+    }
+}
+
 impl From<DistributedClockError> for Error {
     fn from(e: DistributedClockError) -> Self {
         Self::DistributedClock(e)
@@ -459,3 +1191,205 @@ impl From<ethercrab_wire::WireError> for Error {
         Self::Wire(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_transition_history() {
+        let attempt = |status_code| StateTransitionAttempt {
+            current: SubDeviceState::PreOp,
+            status_code,
+        };
+
+        let mut history = StateTransitionHistory::new();
+
+        assert_eq!(history.last(), None);
+        assert_eq!(history.iter().count(), 0);
+
+        history.push(attempt(AlStatusCode::NoValidOutputs));
+        history.push(attempt(AlStatusCode::NoValidInputsAvailable));
+
+        assert_eq!(history.attempts(), 2);
+        assert_eq!(
+            history.last(),
+            Some(attempt(AlStatusCode::NoValidInputsAvailable))
+        );
+
+        let codes = [
+            AlStatusCode::SubDeviceNeedsInit,
+            AlStatusCode::SubDeviceNeedsPreop,
+            AlStatusCode::SubDeviceNeedsSafeop,
+            AlStatusCode::SubDeviceNeedsColdStart,
+        ];
+
+        for code in codes {
+            history.push(attempt(code));
+        }
+
+        // Oldest three attempts are dropped
+        assert_eq!(history.attempts(), 6);
+        assert!(history
+            .iter()
+            .map(|attempt| attempt.status_code)
+            .eq(codes[1..].iter().copied()));
+    }
+
+    #[test]
+    fn size() {
+        // Errors are returned from every network operation, so must stay cheap to move around on
+        // embedded targets
+        assert_eq!(core::mem::size_of::<Error>(), 16);
+    }
+
+    #[test]
+    fn context_round_trip() {
+        let context = ErrorContext::new(
+            ConfiguredAddress::new(0x1001),
+            Operation::SdoWrite {
+                index: 0x1c12,
+                sub_index: 1,
+            },
+        );
+
+        let errors = [
+            Error::Timeout,
+            Error::Wire(ethercrab_wire::WireError::ReadBufferTooShort),
+            Error::Eeprom(EepromError::Checksum {
+                expected: 0x12,
+                actual: 0x34,
+            }),
+            Error::Mailbox(MailboxError::Aborted {
+                code: CoeAbortCode::Unknown(0x1234_5678),
+                address: 0x1c12,
+                sub_index: 1,
+            }),
+            Error::Mailbox(MailboxError::Emergency {
+                error_code: 0x8130,
+                error_register: 0x11,
+            }),
+            Error::Mailbox(MailboxError::Aborted {
+                code: CoeAbortCode::NotFound,
+                address: 0x1c12,
+                sub_index: 1,
+            }),
+            Error::WorkingCounter {
+                expected: 1,
+                received: 0,
+                command: Command::from(Command::fpwr(ConfiguredAddress::new(0x1001), 0x1800))
+                    .into(),
+            },
+            Error::Pdu(PduError::Decode),
+            Error::Pdu(PduError::Validation(PduValidationError::IndexMismatch {
+                sent: 3,
+                received: 4,
+            })),
+        ];
+
+        for error in errors {
+            let with_context = error.with_context(context);
+
+            assert_eq!(with_context.context(), Some(context));
+            assert_eq!(with_context.cause(), error);
+        }
+    }
+
+    #[test]
+    fn context_unchanged() {
+        let context = ErrorContext::new(
+            ConfiguredAddress::new(0x1001),
+            Operation::SdoRead {
+                index: 0x1c12,
+                sub_index: 1,
+            },
+        );
+
+        let errors = [
+            // Already identifies the SubDevice
+            Error::NotFound {
+                item: Item::SubDevice,
+                index: Some(1),
+            },
+            // Too large to store
+            Error::WorkingCounter {
+                expected: 300,
+                received: 0,
+                command: PduCommand::NOP,
+            },
+            Error::Pdu(PduError::Validation(PduValidationError::CommandMismatch {
+                sent: Command::from(Command::fprd(ConfiguredAddress::new(0x1001), 0x0130)).into(),
+                received: PduCommand::NOP,
+            })),
+            // A different SDO than the context
+            Error::Mailbox(MailboxError::TooLong {
+                address: 0x1c13,
+                sub_index: 1,
+            }),
+            Error::Timeout.with_context(context),
+        ];
+
+        for error in errors {
+            assert_eq!(error.with_context(context), error);
+        }
+
+        assert_eq!(Error::Timeout.context(), None);
+        assert_eq!(Error::Timeout.cause(), Error::Timeout);
+    }
+
+    #[test]
+    fn display_context() {
+        assert_eq!(
+            Error::WorkingCounter {
+                expected: 1,
+                received: 0,
+                command: Command::from(Command::fprd(ConfiguredAddress::new(0x1001), 0x0130))
+                    .into(),
+            }
+            .to_string(),
+            "working counter expected 1, got 0 (FPRD(addr 0x1001, reg 0x0130))"
+        );
+        assert_eq!(
+            Error::WorkingCounter {
+                expected: 3,
+                received: 2,
+                command: PduCommand::NOP,
+            }
+            .to_string(),
+            "working counter expected 3, got 2"
+        );
+        assert_eq!(
+            Error::NotFound {
+                item: Item::Fmmu,
+                index: Some(16)
+            }
+            .to_string(),
+            "FMMU 16 not found"
+        );
+        assert_eq!(
+            Error::Mailbox(MailboxError::Aborted {
+                code: CoeAbortCode::Incompatible,
+                address: 0x1c12,
+                sub_index: 1,
+            })
+            .with_context(ErrorContext::new(
+                ConfiguredAddress::new(0x1001),
+                Operation::SdoWrite {
+                    index: 0x1c12,
+                    sub_index: 1,
+                },
+            ))
+            .to_string(),
+            "SubDevice 0x1001 SDO 0x1c12:1 write: mailbox: 0x1c12:1 aborted: 0x06040043: General parameter incompatibility reason"
+        );
+        assert_eq!(
+            Error::Timeout
+                .with_context(ErrorContext::new(
+                    ConfiguredAddress::new(0x1002),
+                    Operation::EepromRead { word: 0x0040 },
+                ))
+                .to_string(),
+            "SubDevice 0x1002 EEPROM read at word 0x0040: timeout"
+        );
+    }
+}
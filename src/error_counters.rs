@@ -0,0 +1,228 @@
+//! Per-port error counters (ESC registers `0x0300` - `0x0313`).
+
+use crate::{error::Error, register::RegisterAddress, ConfiguredAddress};
+
+/// Number of bytes read from the ESC, starting at [`RegisterAddress::RxErrorCounter`].
+pub(crate) const ERROR_COUNTERS_LEN: u16 = 0x0314 - 0x0300;
+
+/// Error counters for a single SubDevice port.
+///
+/// All counters saturate at `255` until they are reset with
+/// [`MainDevice::reset_error_counters`](crate::MainDevice::reset_error_counters).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortErrors {
+    /// Frames received on this port with an invalid frame format, e.g. a wrong CRC.
+    pub invalid_frames: u8,
+
+    /// Physical layer RX errors inside or outside of a frame on this port.
+    pub rx_errors: u8,
+
+    /// Frames received on this port that were already marked as invalid by a previous SubDevice.
+    ///
+    /// A rising forwarded error count with a steady [`invalid_frames`](PortErrors::invalid_frames)
+    /// count means the fault is further upstream.
+    pub forwarded_rx_errors: u8,
+
+    /// The number of times the link on this port was lost.
+    pub lost_links: u8,
+}
+
+impl PortErrors {
+    /// Returns `true` if every counter on this port is zero.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A snapshot of the error counters of a single SubDevice.
+///
+/// Returned by [`MainDevice::error_counters`](crate::MainDevice::error_counters).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorCounters {
+    /// The configured station address of the SubDevice the counters were read from.
+    pub configured_address: ConfiguredAddress,
+
+    /// Error counters for ports 0 to 3.
+    pub ports: [PortErrors; 4],
+
+    /// Frames that could not be processed by the ESC, e.g. because of a malformed PDU.
+    pub processing_unit_errors: u8,
+
+    /// Errors on the Process Data Interface between the ESC and the application.
+    pub pdi_errors: u8,
+}
+
+impl ErrorCounters {
+    /// Parse the raw register contents starting at [`RegisterAddress::RxErrorCounter`].
+    pub(crate) fn parse(configured_address: ConfiguredAddress, raw: &[u8]) -> Result<Self, Error> {
+        let raw = raw
+            .get(..usize::from(ERROR_COUNTERS_LEN))
+            .ok_or(Error::Internal)?;
+
+        let base = u16::from(RegisterAddress::RxErrorCounter);
+        let offset = |register: RegisterAddress| usize::from(u16::from(register) - base);
+
+        let rx = offset(RegisterAddress::RxErrorCounter);
+        let forwarded = offset(RegisterAddress::ForwardedRxErrorCounter);
+        let lost_link = offset(RegisterAddress::LostLinkCounter);
+
+        let ports = core::array::from_fn(|port| PortErrors {
+            invalid_frames: raw[rx + port * 2],
+            rx_errors: raw[rx + port * 2 + 1],
+            forwarded_rx_errors: raw[forwarded + port],
+            lost_links: raw[lost_link + port],
+        });
+
+        Ok(Self {
+            configured_address,
+            ports,
+            processing_unit_errors: raw[offset(RegisterAddress::ProcessingUnitErrorCounter)],
+            pdi_errors: raw[offset(RegisterAddress::PdiErrorCounter)],
+        })
+    }
+
+    /// Returns `true` if no errors have been counted on any port or in the ESC.
+    pub fn is_empty(&self) -> bool {
+        self.ports.iter().all(PortErrors::is_empty)
+            && self.processing_unit_errors == 0
+            && self.pdi_errors == 0
+    }
+
+    /// The sum of invalid frame and RX errors counted on every port of this SubDevice.
+    ///
+    /// Forwarded errors and lost links are not included.
+    pub fn total_rx_errors(&self) -> u32 {
+        self.ports
+            .iter()
+            .map(|port| u32::from(port.invalid_frames) + u32::from(port.rx_errors))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let raw = [
+            // Invalid frame, RX error pairs for ports 0-3
+            0x01, 0x02, 0x03, 0x04, 0x00, 0x00, 0x00, 0x05, //
+            // Forwarded RX errors
+            0x00, 0x06, 0x00, 0x00, //
+            // Processing unit, PDI
+            0x07, 0x08, //
+            // Reserved
+            0xff, 0xff, //
+            // Lost links
+            0x09, 0x0a, 0x00, 0x00,
+        ];
+
+        let counters = ErrorCounters::parse(ConfiguredAddress::new(0x1001), &raw).unwrap();
+
+        assert_eq!(
+            counters,
+            ErrorCounters {
+                configured_address: ConfiguredAddress::new(0x1001),
+                ports: [
+                    PortErrors {
+                        invalid_frames: 1,
+                        rx_errors: 2,
+                        forwarded_rx_errors: 0,
+                        lost_links: 9,
+                    },
+                    PortErrors {
+                        invalid_frames: 3,
+                        rx_errors: 4,
+                        forwarded_rx_errors: 6,
+                        lost_links: 10,
+                    },
+                    PortErrors::default(),
+                    PortErrors {
+                        invalid_frames: 0,
+                        rx_errors: 5,
+                        forwarded_rx_errors: 0,
+                        lost_links: 0,
+                    },
+                ],
+                processing_unit_errors: 7,
+                pdi_errors: 8,
+            }
+        );
+
+        assert_eq!(counters.total_rx_errors(), 15);
+        assert!(!counters.is_empty());
+        assert!(counters.ports[2].is_empty());
+    }
+
+    #[test]
+    fn parse_short() {
+        assert_eq!(
+            ErrorCounters::parse(ConfiguredAddress::new(0x1000), &[0u8; 8]),
+            Err(Error::Internal)
+        );
+        assert!(
+            ErrorCounters::parse(ConfiguredAddress::new(0x1000), &[0u8; 20])
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{error::Error, sim::fixture::*, ErrorCounters, PduStorage};
+
+    #[test]
+    fn error_counters() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            net.with_subdevice(2, |subdevice| {
+                subdevice.rx_error(1);
+                subdevice.rx_error(1);
+            });
+
+            let report = maindevice.error_counters::<8>().await.expect("read");
+
+            assert_eq!(report.len(), 4);
+            assert_eq!(
+                report
+                    .iter()
+                    .map(|counters| counters.configured_address.get())
+                    .collect::<Vec<_>>(),
+                [0x1000, 0x1001, 0x1002, 0x1003]
+            );
+            assert_eq!(report[2].ports[1].rx_errors, 2);
+            assert_eq!(report[2].total_rx_errors(), 2);
+            assert_eq!(
+                report.iter().filter(|counters| counters.is_empty()).count(),
+                3
+            );
+
+            assert_eq!(
+                maindevice.error_counters::<2>().await,
+                Err(Error::Capacity(crate::error::Item::SubDevice))
+            );
+
+            maindevice.reset_error_counters().await.expect("reset");
+
+            let report = maindevice.diagnostics().error_counters::<8>().await;
+
+            assert!(report.expect("read").iter().all(ErrorCounters::is_empty));
+        });
+    }
+}
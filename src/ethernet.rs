@@ -74,11 +74,20 @@ mod field {
     pub const SOURCE: Range<usize> = 6..12;
     pub const ETHERTYPE: Range<usize> = 12..14;
     pub const PAYLOAD: RangeFrom<usize> = 14..;
+
+    pub const VLAN_ETHERTYPE: Range<usize> = 16..18;
+    pub const VLAN_PAYLOAD: RangeFrom<usize> = 18..;
 }
 
 /// The Ethernet header length
 pub const ETHERNET_HEADER_LEN: usize = field::PAYLOAD.start;
 
+/// The EtherType (TPID) of an IEEE 802.1Q VLAN tag.
+pub const VLAN_ETHERTYPE: u16 = 0x8100;
+
+/// The length of an IEEE 802.1Q VLAN tag.
+pub const VLAN_TAG_LEN: usize = field::VLAN_PAYLOAD.start - field::PAYLOAD.start;
+
 impl<T: AsRef<[u8]>> EthernetFrame<T> {
     /// Imbue a raw octet buffer with Ethernet frame structure.
     pub const fn new_unchecked(buffer: T) -> EthernetFrame<T> {
@@ -148,6 +157,20 @@ impl<T: AsRef<[u8]>> EthernetFrame<T> {
             // unparseable ethertypes is fine here (imo, lol)
             .unwrap_or(0)
     }
+
+    /// Return the EtherType of the payload, skipping over an 802.1Q tag if present.
+    #[inline]
+    pub fn inner_ethertype(&self) -> u16 {
+        if self.ethertype() != VLAN_ETHERTYPE {
+            return self.ethertype();
+        }
+
+        self.buffer
+            .as_ref()
+            .get(field::VLAN_ETHERTYPE)
+            .map(|res| u16::from_be_bytes(res.try_into().unwrap()))
+            .unwrap_or(0)
+    }
 }
 
 impl<'a, T: AsRef<[u8]> + ?Sized> EthernetFrame<&'a T> {
@@ -157,6 +180,21 @@ impl<'a, T: AsRef<[u8]> + ?Sized> EthernetFrame<&'a T> {
         let data = self.buffer.as_ref();
         &data[field::PAYLOAD]
     }
+
+    /// Return a pointer to the payload after an 802.1Q tag if present.
+    ///
+    /// An empty slice is returned if the frame is too short to hold a complete tag.
+    #[inline]
+    pub fn inner_payload(&self) -> &'a [u8] {
+        if self.ethertype() != VLAN_ETHERTYPE {
+            return self.payload();
+        }
+
+        self.buffer
+            .as_ref()
+            .get(field::VLAN_PAYLOAD)
+            .unwrap_or_default()
+    }
 }
 
 impl<T: AsRef<[u8]> + AsMut<[u8]>> EthernetFrame<T> {
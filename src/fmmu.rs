@@ -1,8 +1,31 @@
+use crate::LogicalAddress;
+
 /// Fieldbus Memory Management Unit (FMMU).
 ///
 /// Used to map segments of the Process Data Image (PDI) to various parts of the SubDevice memory space.
 
 /// ETG1000.4 Table 56 – Fieldbus memory management unit (FMMU) entity.
+///
+/// EtherCrab configures FMMUs to map each SubDevice's process data into its group's PDI. An FMMU
+/// can also be configured manually with
+/// [`SubDeviceRef::configure_fmmu`](crate::SubDeviceRef::configure_fmmu) to build a custom logical
+/// memory layout, e.g. to map a SubDevice's inputs into a second area of logical memory, or to pack
+/// bit-oriented SubDevices tightly together.
+///
+/// # Examples
+///
+/// Map 4 bits of a SubDevice's inputs, starting at physical bit `0x1000:0`, to logical bits
+/// `0x0001_0000:4` to `0x0001_0000:7`.
+///
+/// ```rust
+/// use ethercrab::{Fmmu, LogicalAddress};
+///
+/// let fmmu = Fmmu::inputs(LogicalAddress::new(0x0001_0000), 0x1000, 1).with_bits(4, 0, 4);
+///
+/// assert_eq!(fmmu.length_bytes, 1);
+/// assert_eq!(fmmu.logical_end_bit, 7);
+/// assert_eq!(fmmu.bit_len(), 4);
+/// ```
 #[derive(Default, Copy, Clone, PartialEq, Eq, ethercrab_wire::EtherCrabWireReadWrite)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bytes = 16)]
@@ -10,34 +33,116 @@ pub struct Fmmu {
     /// This parameter shall contain the start address in octets in the logical memory area of the
     /// memory translation.
     #[wire(bytes = 4)]
-    pub logical_start_address: u32,
+    pub logical_start_address: LogicalAddress,
 
+    /// Number of logical octets covered by the mapping, including partially mapped first and last
+    /// octets.
     #[wire(bytes = 2)]
     pub length_bytes: u16,
 
+    /// First mapped bit (0 - 7) of the first logical octet.
     #[wire(bits = 3, post_skip = 5)]
     pub logical_start_bit: u8,
 
+    /// Last mapped bit (0 - 7) of the last logical octet.
     #[wire(bits = 3, post_skip = 5)]
     pub logical_end_bit: u8,
 
+    /// SubDevice memory address the mapping starts at, usually the start of a process data sync
+    /// manager.
     #[wire(bytes = 2)]
     pub physical_start_address: u16,
 
+    /// First mapped bit (0 - 7) of the physical start address.
     #[wire(bits = 3, post_skip = 5)]
     pub physical_start_bit: u8,
 
+    /// Logical reads (`LRD`, `LRW`) copy SubDevice memory into the frame.
     #[wire(bits = 1)]
     pub read_enable: bool,
 
+    /// Logical writes (`LWR`, `LRW`) copy frame data into SubDevice memory.
     #[wire(bits = 1, post_skip = 6)]
     pub write_enable: bool,
 
+    /// Whether the mapping is active.
     // Lots of spare bytes after this one!
     #[wire(bits = 1, post_skip = 31)]
     pub enable: bool,
 }
 
+impl Fmmu {
+    /// Create an enabled, byte-aligned mapping of `length_bytes` of SubDevice memory starting at
+    /// `physical_start_address` which is read by logical reads.
+    pub const fn inputs(
+        logical_start_address: LogicalAddress,
+        physical_start_address: u16,
+        length_bytes: u16,
+    ) -> Self {
+        Self {
+            logical_start_address,
+            length_bytes,
+            logical_start_bit: 0,
+            logical_end_bit: 7,
+            physical_start_address,
+            physical_start_bit: 0,
+            read_enable: true,
+            write_enable: false,
+            enable: true,
+        }
+    }
+
+    /// Create an enabled, byte-aligned mapping of `length_bytes` of SubDevice memory starting at
+    /// `physical_start_address` which is written by logical writes.
+    pub const fn outputs(
+        logical_start_address: LogicalAddress,
+        physical_start_address: u16,
+        length_bytes: u16,
+    ) -> Self {
+        Self {
+            read_enable: false,
+            write_enable: true,
+            ..Self::inputs(logical_start_address, physical_start_address, length_bytes)
+        }
+    }
+
+    /// Map `bit_len` bits starting at the given bit offsets instead of whole bytes.
+    ///
+    /// [`length_bytes`](Fmmu::length_bytes) and [`logical_end_bit`](Fmmu::logical_end_bit) are
+    /// computed from the logical start bit and `bit_len`.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if either start bit is greater than 7 or `bit_len` is zero.
+    pub const fn with_bits(
+        self,
+        logical_start_bit: u8,
+        physical_start_bit: u8,
+        bit_len: u16,
+    ) -> Self {
+        assert!(logical_start_bit < 8, "Logical start bit must be 0 - 7");
+        assert!(physical_start_bit < 8, "Physical start bit must be 0 - 7");
+        assert!(bit_len > 0, "FMMU must map at least one bit");
+
+        let last_bit = logical_start_bit as u32 + bit_len as u32 - 1;
+
+        Self {
+            length_bytes: (last_bit / 8 + 1) as u16,
+            logical_start_bit,
+            logical_end_bit: (last_bit % 8) as u8,
+            physical_start_bit,
+            ..self
+        }
+    }
+
+    /// The number of logical bits covered by this mapping.
+    pub const fn bit_len(&self) -> u32 {
+        (self.length_bytes as u32 * 8)
+            .saturating_sub(self.logical_start_bit as u32)
+            .saturating_sub(7 - (self.logical_end_bit as u32 & 7))
+    }
+}
+
 impl core::fmt::Debug for Fmmu {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Fmmu")
@@ -119,7 +224,7 @@ mod tests {
         assert_eq!(
             fmmu,
             Fmmu {
-                logical_start_address: 0,
+                logical_start_address: LogicalAddress::new(0),
                 length_bytes: 1,
                 logical_start_bit: 0,
                 logical_end_bit: 3,
@@ -131,4 +236,97 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn bit_mapping() {
+        let fmmu = Fmmu::outputs(LogicalAddress::new(0x10), 0x0f00, 4);
+
+        assert_eq!(fmmu.bit_len(), 32);
+        assert!(fmmu.write_enable && !fmmu.read_enable);
+
+        let fmmu = fmmu.with_bits(6, 1, 3);
+
+        assert_eq!(
+            fmmu,
+            Fmmu {
+                logical_start_address: LogicalAddress::new(0x10),
+                length_bytes: 2,
+                logical_start_bit: 6,
+                logical_end_bit: 0,
+                physical_start_address: 0x0f00,
+                physical_start_bit: 1,
+                read_enable: false,
+                write_enable: true,
+                enable: true,
+            }
+        );
+        assert_eq!(fmmu.bit_len(), 3);
+
+        let fmmu = Fmmu::inputs(LogicalAddress::new(0x10), 0x1000, 1).with_bits(0, 0, 16);
+
+        assert_eq!((fmmu.length_bytes, fmmu.logical_end_bit), (2, 7));
+        assert_eq!(fmmu.bit_len(), 16);
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        error::{Error, Item},
+        sim::fixture::*,
+        Command, Fmmu, LogicalAddress, PduStorage,
+    };
+
+    #[test]
+    fn manual_fmmu() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        network.with_subdevice(2, |subdevice| subdevice.inputs_mut()[0] = 0x5a);
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_safe_op(&maindevice)
+                .await
+                .expect("PRE-OP -> SAFE-OP");
+
+            let outputs = group.subdevice(&maindevice, 1).unwrap();
+            let inputs = group.subdevice(&maindevice, 2).unwrap();
+
+            // Map both SubDevices into a single area of logical memory after the group PDI
+            let input_fmmu = Fmmu::inputs(LogicalAddress::new(0x0001_0000), 0x1400, 1);
+            let output_fmmu = Fmmu::outputs(LogicalAddress::new(0x0001_0001), 0x1100, 1);
+
+            assert_eq!(inputs.configure_fmmu(3, input_fmmu).await, Ok(()));
+            assert_eq!(outputs.configure_fmmu(3, output_fmmu).await, Ok(()));
+            assert_eq!(inputs.fmmu(3).await, Ok(input_fmmu));
+
+            assert_eq!(
+                Command::lrw(LogicalAddress::new(0x0001_0000))
+                    .with_wkc(3)
+                    .send_receive::<[u8; 2]>(&maindevice, [0x00, 0x0f])
+                    .await,
+                Ok([0x5a, 0x0f])
+            );
+
+            assert_eq!(
+                inputs.configure_fmmu(16, input_fmmu).await,
+                Err(Error::NotFound {
+                    item: Item::Fmmu,
+                    index: Some(16)
+                })
+            );
+        });
+
+        assert_eq!(
+            net.with_subdevice(1, |subdevice| subdevice.outputs()[0]),
+            Some(0x0f)
+        );
+    }
 }
@@ -0,0 +1,707 @@
+//! Periodically sample SubDevice health and report threshold crossings.
+
+use crate::{
+    al_control::AlControl,
+    error::{Error, Item},
+    error_counters::{ErrorCounters, ERROR_COUNTERS_LEN},
+    register::RegisterAddress,
+    timer_factory::timer,
+    AlStatusCode, Command, ConfiguredAddress, Diagnostics, SubDeviceState,
+};
+use core::time::Duration;
+use ethercrab_wire::EtherCrabWireRead;
+
+/// Number of bytes read from the ESC, starting at [`RegisterAddress::AlStatus`].
+const AL_STATUS_LEN: u16 = 0x0136 - 0x0130;
+
+/// Thresholds and sample interval used by a [`HealthMonitor`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HealthConfig {
+    /// Time to wait between samples of the whole network.
+    ///
+    /// Defaults to 1 second.
+    pub interval: Duration,
+
+    /// Minimum number of new invalid frame and RX errors counted on a single port between two
+    /// samples to emit a [`HealthEvent::RxErrors`].
+    ///
+    /// Defaults to 1.
+    pub rx_errors: u8,
+
+    /// Largest allowed difference between a SubDevice's local DC system time and the reference
+    /// clock before a [`HealthEvent::DcSyncError`] is emitted, or `None` to skip reading the DC
+    /// registers.
+    ///
+    /// Defaults to 1 µs.
+    pub dc_sync_error: Option<Duration>,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            rx_errors: 1,
+            dc_sync_error: Some(Duration::from_micros(1)),
+        }
+    }
+}
+
+/// A change in the health of a SubDevice, emitted by a [`HealthMonitor`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HealthEvent {
+    /// The AL state of the SubDevice changed, or its error flag was set or cleared.
+    StateChanged {
+        /// SubDevice configured station address.
+        configured_address: ConfiguredAddress,
+        /// State read in the previous sample.
+        previous: SubDeviceState,
+        /// Current state.
+        current: SubDeviceState,
+        /// `true` if the SubDevice has its AL error flag set.
+        error: bool,
+        /// The AL status code, describing the error if `error` is set.
+        status_code: AlStatusCode,
+    },
+
+    /// The SubDevice did not respond to a sample.
+    Unreachable {
+        /// SubDevice configured station address.
+        configured_address: ConfiguredAddress,
+    },
+
+    /// A previously unreachable SubDevice responded again.
+    Reachable {
+        /// SubDevice configured station address.
+        configured_address: ConfiguredAddress,
+    },
+
+    /// At least [`HealthConfig::rx_errors`] invalid frame or RX errors were counted on a port
+    /// since the previous sample.
+    RxErrors {
+        /// SubDevice configured station address.
+        configured_address: ConfiguredAddress,
+        /// Port number.
+        port: u8,
+        /// Number of new errors.
+        errors: u16,
+    },
+
+    /// The link on a port was lost since the previous sample.
+    LinkLost {
+        /// SubDevice configured station address.
+        configured_address: ConfiguredAddress,
+        /// Port number.
+        port: u8,
+    },
+
+    /// The SubDevice's DC system time differs from the reference clock by more than
+    /// [`HealthConfig::dc_sync_error`].
+    DcSyncError {
+        /// SubDevice configured station address.
+        configured_address: ConfiguredAddress,
+        /// Local system time minus the reference clock time in nanoseconds.
+        difference_ns: i32,
+    },
+
+    /// The DC system time difference of a SubDevice that previously emitted a
+    /// [`HealthEvent::DcSyncError`] is within [`HealthConfig::dc_sync_error`] again.
+    DcSyncRestored {
+        /// SubDevice configured station address.
+        configured_address: ConfiguredAddress,
+        /// Local system time minus the reference clock time in nanoseconds.
+        difference_ns: i32,
+    },
+}
+
+impl core::fmt::Display for HealthEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HealthEvent::StateChanged {
+                configured_address,
+                previous,
+                current,
+                error,
+                status_code,
+            } => {
+                write!(
+                    f,
+                    "SubDevice {:#06x} state changed from {} to {}",
+                    configured_address, previous, current
+                )?;
+
+                if *error {
+                    write!(f, " with error: {}", status_code)?;
+                }
+
+                Ok(())
+            }
+            HealthEvent::Unreachable { configured_address } => {
+                write!(f, "SubDevice {:#06x} unreachable", configured_address)
+            }
+            HealthEvent::Reachable { configured_address } => {
+                write!(f, "SubDevice {:#06x} reachable again", configured_address)
+            }
+            HealthEvent::RxErrors {
+                configured_address,
+                port,
+                errors,
+            } => write!(
+                f,
+                "SubDevice {:#06x} port {}: {} new RX error(s)",
+                configured_address, port, errors
+            ),
+            HealthEvent::LinkLost {
+                configured_address,
+                port,
+            } => write!(
+                f,
+                "SubDevice {:#06x} port {}: link lost",
+                configured_address, port
+            ),
+            HealthEvent::DcSyncError {
+                configured_address,
+                difference_ns,
+            } => write!(
+                f,
+                "SubDevice {:#06x} DC out of sync by {} ns",
+                configured_address, difference_ns
+            ),
+            HealthEvent::DcSyncRestored {
+                configured_address,
+                difference_ns,
+            } => write!(
+                f,
+                "SubDevice {:#06x} DC back in sync, difference {} ns",
+                configured_address, difference_ns
+            ),
+        }
+    }
+}
+
+/// One sample of a single SubDevice. `al_status` is `None` if the SubDevice did not respond.
+#[derive(Debug, Copy, Clone)]
+struct Sample {
+    al_status: Option<(AlControl, AlStatusCode)>,
+    counters: Option<ErrorCounters>,
+    dc_difference_ns: Option<i32>,
+}
+
+/// The last known health of a single SubDevice.
+#[derive(Debug, Copy, Clone)]
+struct SubDeviceHealth {
+    configured_address: ConfiguredAddress,
+    al_status: Option<AlControl>,
+    counters: Option<ErrorCounters>,
+    reachable: bool,
+    dc_sync_error: bool,
+}
+
+impl SubDeviceHealth {
+    fn new(configured_address: ConfiguredAddress) -> Self {
+        Self {
+            configured_address,
+            al_status: None,
+            counters: None,
+            reachable: true,
+            dc_sync_error: false,
+        }
+    }
+
+    /// Compare `sample` to the previous one, calling `emit` for every threshold crossed.
+    fn update(
+        &mut self,
+        sample: Sample,
+        config: &HealthConfig,
+        emit: &mut impl FnMut(HealthEvent),
+    ) {
+        let configured_address = self.configured_address;
+
+        let Some((al_status, status_code)) = sample.al_status else {
+            if self.reachable {
+                self.reachable = false;
+
+                emit(HealthEvent::Unreachable { configured_address });
+            }
+
+            return;
+        };
+
+        if !self.reachable {
+            self.reachable = true;
+
+            emit(HealthEvent::Reachable { configured_address });
+        }
+
+        // The first sample is only used as a baseline
+        if let Some(previous) = self.al_status.replace(al_status) {
+            if previous.state != al_status.state || previous.error != al_status.error {
+                emit(HealthEvent::StateChanged {
+                    configured_address,
+                    previous: previous.state,
+                    current: al_status.state,
+                    error: al_status.error,
+                    status_code,
+                });
+            }
+        }
+
+        if let Some(counters) = sample.counters {
+            if let Some(previous) = self.counters.replace(counters) {
+                // Counters saturate, and start from zero again if they were reset since the last
+                // sample
+                let delta = |previous: u8, current: u8| {
+                    if current >= previous {
+                        current - previous
+                    } else {
+                        current
+                    }
+                };
+
+                for (port, (previous, current)) in
+                    previous.ports.iter().zip(counters.ports.iter()).enumerate()
+                {
+                    let port = port as u8;
+
+                    let errors = u16::from(delta(previous.invalid_frames, current.invalid_frames))
+                        + u16::from(delta(previous.rx_errors, current.rx_errors));
+
+                    if errors > 0 && errors >= u16::from(config.rx_errors) {
+                        emit(HealthEvent::RxErrors {
+                            configured_address,
+                            port,
+                            errors,
+                        });
+                    }
+
+                    if delta(previous.lost_links, current.lost_links) > 0 {
+                        emit(HealthEvent::LinkLost {
+                            configured_address,
+                            port,
+                        });
+                    }
+                }
+            }
+        }
+
+        if let (Some(difference_ns), Some(threshold)) =
+            (sample.dc_difference_ns, config.dc_sync_error)
+        {
+            let out_of_sync = u128::from(difference_ns.unsigned_abs()) > threshold.as_nanos();
+
+            if out_of_sync && !self.dc_sync_error {
+                emit(HealthEvent::DcSyncError {
+                    configured_address,
+                    difference_ns,
+                });
+            } else if !out_of_sync && self.dc_sync_error {
+                emit(HealthEvent::DcSyncRestored {
+                    configured_address,
+                    difference_ns,
+                });
+            }
+
+            self.dc_sync_error = out_of_sync;
+        }
+    }
+}
+
+/// Decode the DC system time difference register: bit 31 is set if the local time is smaller
+/// than the reference time, bits 0-30 hold the magnitude in nanoseconds.
+fn dc_difference_ns(raw: u32) -> i32 {
+    let magnitude = (raw & 0x7fff_ffff) as i32;
+
+    if raw & 0x8000_0000 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Periodically samples the AL status, error counters and DC system time difference of every
+/// SubDevice on the network and emits a [`HealthEvent`] when a threshold in [`HealthConfig`] is
+/// crossed.
+///
+/// All reads are sent through a [`Diagnostics`] handle, so they use the low priority frame lane
+/// and never delay process data. Each SubDevice costs three small reads per sample.
+///
+/// Events are only emitted on changes, so the first sample is used as a baseline and a SubDevice
+/// that stays in an error state is only reported once.
+///
+/// # Examples
+///
+/// Log health events from a separate thread while the process data cycle runs:
+///
+/// ```rust,no_run
+/// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts, HealthMonitor, HealthConfig};
+/// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+/// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+/// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+/// let diagnostics = maindevice.diagnostics();
+///
+/// std::thread::scope(|s| {
+///     s.spawn(move || {
+///         let mut monitor = HealthMonitor::<16>::new(HealthConfig::default());
+///
+///         let result = smol::block_on(monitor.run(diagnostics, |event| log::warn!("{}", event)));
+///
+///         log::error!("Health monitor stopped: {:?}", result);
+///     });
+///
+///     // Run the process data cycle with `maindevice` here
+/// });
+/// ```
+#[derive(Debug)]
+pub struct HealthMonitor<const MAX_SUBDEVICES: usize> {
+    config: HealthConfig,
+    subdevices: heapless::Vec<SubDeviceHealth, MAX_SUBDEVICES>,
+}
+
+impl<const MAX_SUBDEVICES: usize> HealthMonitor<MAX_SUBDEVICES> {
+    /// Create a new monitor with the given thresholds.
+    pub fn new(config: HealthConfig) -> Self {
+        Self {
+            config,
+            subdevices: heapless::Vec::new(),
+        }
+    }
+
+    /// Sample every SubDevice once, calling `on_event` for every threshold crossed since the
+    /// previous sample.
+    ///
+    /// SubDevices that don't respond are reported with [`HealthEvent::Unreachable`] instead of
+    /// returning an error. This method will return [`Error::Capacity`] if there are more than
+    /// `MAX_SUBDEVICES` SubDevices on the network.
+    pub async fn poll(
+        &mut self,
+        diagnostics: &Diagnostics<'_>,
+        mut on_event: impl FnMut(HealthEvent),
+    ) -> Result<(), Error> {
+        let num_subdevices = diagnostics.num_subdevices();
+
+        if num_subdevices > MAX_SUBDEVICES {
+            return Err(Error::Capacity(Item::SubDevice));
+        }
+
+        for idx in self.subdevices.len()..num_subdevices {
+            let configured_address = ConfiguredAddress::from_index(idx as u16);
+
+            // Capacity checked above
+            let _ = self
+                .subdevices
+                .push(SubDeviceHealth::new(configured_address));
+        }
+
+        for subdevice in self.subdevices.iter_mut() {
+            let sample =
+                Self::sample(diagnostics, subdevice.configured_address, &self.config).await?;
+
+            subdevice.update(sample, &self.config, &mut on_event);
+        }
+
+        Ok(())
+    }
+
+    /// Sample the network every [`HealthConfig::interval`], calling `on_event` for every
+    /// threshold crossed.
+    ///
+    /// This method only returns if sampling fails with an error other than an unreachable
+    /// SubDevice.
+    pub async fn run(
+        &mut self,
+        diagnostics: Diagnostics<'_>,
+        mut on_event: impl FnMut(HealthEvent),
+    ) -> Result<(), Error> {
+        loop {
+            self.poll(&diagnostics, &mut on_event).await?;
+
+            timer(self.config.interval).await;
+        }
+    }
+
+    async fn sample(
+        diagnostics: &Diagnostics<'_>,
+        configured_address: ConfiguredAddress,
+        config: &HealthConfig,
+    ) -> Result<Sample, Error> {
+        let maindevice = diagnostics.maindevice();
+
+        let unreachable = Sample {
+            al_status: None,
+            counters: None,
+            dc_difference_ns: None,
+        };
+
+        let al_status = match Command::fprd(configured_address, RegisterAddress::AlStatus.into())
+            .ignore_wkc()
+            .receive_slice(maindevice, AL_STATUS_LEN)
+            .await
+        {
+            Ok(response) if response.working_counter == 1 => (
+                AlControl::unpack_from_slice(&response[0..2])?,
+                AlStatusCode::unpack_from_slice(&response[4..6])?,
+            ),
+            Ok(_) | Err(Error::Timeout) => return Ok(unreachable),
+            Err(e) => return Err(e),
+        };
+
+        let counters = Command::fprd(configured_address, RegisterAddress::RxErrorCounter.into())
+            .ignore_wkc()
+            .receive_slice(maindevice, ERROR_COUNTERS_LEN)
+            .await?;
+
+        let counters = (counters.working_counter == 1)
+            .then(|| ErrorCounters::parse(configured_address, &counters))
+            .transpose()?;
+
+        let dc_difference_ns = if config.dc_sync_error.is_some() {
+            // SubDevices without DC support don't respond
+            let difference = Command::fprd(
+                configured_address,
+                RegisterAddress::DcSystemTimeDifference.into(),
+            )
+            .ignore_wkc()
+            .receive_slice(maindevice, 4)
+            .await?;
+
+            (difference.working_counter == 1)
+                .then(|| u32::unpack_from_slice(&difference).map(dc_difference_ns))
+                .transpose()?
+        } else {
+            None
+        };
+
+        Ok(Sample {
+            al_status: Some(al_status),
+            counters,
+            dc_difference_ns,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PortErrors;
+
+    fn sample(state: SubDeviceState, rx_errors: u8, dc_difference_ns: Option<i32>) -> Sample {
+        let mut counters = ErrorCounters {
+            configured_address: ConfiguredAddress::new(0x1000),
+            ports: [PortErrors::default(); 4],
+            processing_unit_errors: 0,
+            pdi_errors: 0,
+        };
+
+        counters.ports[1].rx_errors = rx_errors;
+
+        Sample {
+            al_status: Some((AlControl::new(state), AlStatusCode::NoError)),
+            counters: Some(counters),
+            dc_difference_ns,
+        }
+    }
+
+    fn update(health: &mut SubDeviceHealth, sample: Sample) -> Vec<HealthEvent> {
+        let mut events = Vec::new();
+
+        health.update(sample, &HealthConfig::default(), &mut |event| {
+            events.push(event)
+        });
+
+        events
+    }
+
+    #[test]
+    fn dc_difference() {
+        assert_eq!(dc_difference_ns(0x0000_0064), 100);
+        assert_eq!(dc_difference_ns(0x8000_0064), -100);
+    }
+
+    #[test]
+    fn baseline_then_changes() {
+        let mut health = SubDeviceHealth::new(ConfiguredAddress::new(0x1000));
+
+        assert_eq!(
+            update(&mut health, sample(SubDeviceState::Op, 5, Some(10))),
+            []
+        );
+        assert_eq!(
+            update(&mut health, sample(SubDeviceState::Op, 5, Some(10))),
+            []
+        );
+
+        assert_eq!(
+            update(&mut health, sample(SubDeviceState::SafeOp, 7, Some(-2000))),
+            [
+                HealthEvent::StateChanged {
+                    configured_address: ConfiguredAddress::new(0x1000),
+                    previous: SubDeviceState::Op,
+                    current: SubDeviceState::SafeOp,
+                    error: false,
+                    status_code: AlStatusCode::NoError,
+                },
+                HealthEvent::RxErrors {
+                    configured_address: ConfiguredAddress::new(0x1000),
+                    port: 1,
+                    errors: 2,
+                },
+                HealthEvent::DcSyncError {
+                    configured_address: ConfiguredAddress::new(0x1000),
+                    difference_ns: -2000,
+                },
+            ]
+        );
+
+        // Still out of sync, but already reported
+        assert_eq!(
+            update(&mut health, sample(SubDeviceState::SafeOp, 7, Some(3000))),
+            []
+        );
+
+        // Counters reset
+        assert_eq!(
+            update(&mut health, sample(SubDeviceState::SafeOp, 0, Some(5))),
+            [HealthEvent::DcSyncRestored {
+                configured_address: ConfiguredAddress::new(0x1000),
+                difference_ns: 5,
+            }]
+        );
+    }
+
+    #[test]
+    fn unreachable() {
+        let mut health = SubDeviceHealth::new(ConfiguredAddress::new(0x1000));
+
+        let lost = Sample {
+            al_status: None,
+            counters: None,
+            dc_difference_ns: None,
+        };
+
+        assert_eq!(
+            update(&mut health, lost),
+            [HealthEvent::Unreachable {
+                configured_address: ConfiguredAddress::new(0x1000)
+            }]
+        );
+        assert_eq!(update(&mut health, lost), []);
+        assert_eq!(
+            update(&mut health, sample(SubDeviceState::Op, 0, None)),
+            [HealthEvent::Reachable {
+                configured_address: ConfiguredAddress::new(0x1000)
+            }]
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            HealthEvent::StateChanged {
+                configured_address: ConfiguredAddress::new(0x1001),
+                previous: SubDeviceState::Op,
+                current: SubDeviceState::SafeOp,
+                error: true,
+                status_code: AlStatusCode::SyncManagerWatchdog,
+            }
+            .to_string(),
+            format!(
+                "SubDevice 0x1001 state changed from {} to {} with error: {}",
+                SubDeviceState::Op,
+                SubDeviceState::SafeOp,
+                AlStatusCode::SyncManagerWatchdog
+            )
+        );
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        error::Error, sim::fixture::*, ConfiguredAddress, HealthConfig, HealthEvent, HealthMonitor,
+        PduStorage, SubDeviceState,
+    };
+
+    #[test]
+    fn health_monitor() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            let diagnostics = maindevice.diagnostics();
+
+            let mut monitor = HealthMonitor::<8>::new(HealthConfig::default());
+            let mut events = Vec::new();
+
+            // Baseline
+            monitor
+                .poll(&diagnostics, |event| events.push(event))
+                .await
+                .expect("poll");
+
+            assert_eq!(events, []);
+
+            let _group = group.into_safe_op(&maindevice).await.expect("SAFE-OP");
+
+            net.with_subdevice(2, |subdevice| subdevice.rx_error(0));
+
+            monitor
+                .poll(&diagnostics, |event| events.push(event))
+                .await
+                .expect("poll");
+
+            assert_eq!(
+                events
+                    .iter()
+                    .filter(|event| matches!(
+                        event,
+                        HealthEvent::StateChanged {
+                            previous: SubDeviceState::PreOp,
+                            current: SubDeviceState::SafeOp,
+                            error: false,
+                            ..
+                        }
+                    ))
+                    .count(),
+                4
+            );
+            assert!(events.contains(&HealthEvent::RxErrors {
+                configured_address: ConfiguredAddress::new(0x1002),
+                port: 0,
+                errors: 1
+            }));
+            assert_eq!(events.len(), 5);
+
+            events.clear();
+
+            net.disconnect(2);
+
+            monitor
+                .poll(&diagnostics, |event| events.push(event))
+                .await
+                .expect("poll");
+
+            assert_eq!(
+                events,
+                [HealthEvent::Unreachable {
+                    configured_address: ConfiguredAddress::new(0x1003)
+                }]
+            );
+
+            assert_eq!(
+                HealthMonitor::<2>::new(HealthConfig::default())
+                    .poll(&diagnostics, |_| ())
+                    .await,
+                Err(Error::Capacity(crate::error::Item::SubDevice))
+            );
+        });
+    }
+}
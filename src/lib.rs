@@ -12,10 +12,49 @@
 //!
 //! - `std` (enabled by default) - exposes the [`std`] module, containing helpers to run the TX/RX
 //!   loop on desktop operating systems.
-//! - `defmt` - enable logging with the [`defmt`](https://docs.rs/defmt) crate.
+//! - `alloc` - adds `PduStorage::new_boxed` to allocate PDU storage at runtime on targets with a
+//!   global allocator. This is enabled by default when the `std` feature is enabled.
+//! - `config` - adds the `config` module to describe the expected network, group assignment
+//!   and SubDevice configuration in a TOML or YAML file. Enables `std` and `serde`.
+//! - `cortex-m` - adds the `cortex_m` module to measure all timeouts with the DWT cycle counter or
+//!   SysTick on Cortex-M cores, so no other time source needs to be set up. This takes precedence
+//!   over `embassy-time` on `no_std` targets, but not the `embassy`, `std` or `rtic` features.
+//! - `critical-section` - use [`portable-atomic`](https://docs.rs/portable-atomic) for all
+//!   atomics, falling back to [`critical-section`](https://docs.rs/critical-section) on targets
+//!   without compare-and-swap instructions like `thumbv6m-none-eabi`. The application must provide
+//!   a `critical-section` implementation, e.g. with the `critical-section-single-core` feature of
+//!   `cortex-m`.
+//! - `defmt` - enable logging with the [`defmt`](https://docs.rs/defmt) crate. Public error, status
+//!   and configuration types implement `defmt::Format`. This feature cannot be enabled at the same
+//!   time as `log`, so must be used with `--no-default-features`.
+//! - `dpdk` - Linux only: adds `std::tx_rx_task_dpdk`, a TX/RX loop that busy-polls a NIC bound to
+//!   a [DPDK](https://www.dpdk.org) poll mode driver. Requires DPDK 21.11 or newer to be installed.
+//!   If DPDK isn't found at build time, the build prints a warning and `tx_rx_task_dpdk` always
+//!   returns an error.
+//! - `embassy` - adds the `embassy` module to run the TX/RX loop over any
+//!   [`embassy-net-driver`](https://docs.rs/embassy-net-driver) network device from an `embassy`
+//!   executor. `embassy-time` is used for all timeouts on `no_std` targets, taking precedence over
+//!   the `rtic` and `cortex-m` features. With the `std` feature, the `std` timers are used instead.
 //! - `log` - enable logging with the [`log`](https://docs.rs/log) crate. This is enabled by default
 //!   when the `std` feature is enabled.
+//! - `rtic` - adds the `rtic` module to run EtherCrab from [RTIC](https://rtic.rs) tasks without an
+//!   async executor. All timeouts are measured with a user-registered monotonic clock. This takes
+//!   precedence over `embassy-time` on `no_std` targets, but not the `embassy` or `std` features.
 //! - `serde` - enable `serde` impls for some public items.
+//! - `smoltcp` - run the TX/RX loop over any [`smoltcp`](https://docs.rs/smoltcp) network device
+//!   with `SmoltcpDevice`.
+//! - `tokio` - adds `std::tx_rx_task_tokio`, a TX/RX task driven by the `tokio` reactor instead of
+//!   `async-io`. Timeouts are not affected, so EtherCrab futures can still be run on any executor.
+//! - `tracing` - wrap initialisation phases, SubDevice state transitions, SDO transactions and
+//!   cyclic process data exchanges in [`tracing`](https://docs.rs/tracing) spans. Spans carry the
+//!   SubDevice configured address or group ID as a field. Cyclic exchange spans are at `TRACE`
+//!   level, everything else at `DEBUG`.
+//! - `pcap` - adds `std::PcapCapture` to record all sent and received frames to a `.pcapng` file,
+//!   and `std::tx_rx_task_replay` to replay a recording in place of a network interface.
+//! - `sim` - adds the `sim` module, a simulated network of SubDevices that can be used in place of
+//!   a network interface to run EtherCrab in tests and examples without any hardware, and
+//!   `sim::block_on` to test timeouts and retries in virtual time. `sim::FaultyDevice` injects
+//!   network faults into any device to test recovery from them.
 //!
 //! For `no_std` targets, it is recommended to add this crate with
 //!
@@ -23,6 +62,15 @@
 //! cargo add --no-default-features --features defmt
 //! ```
 //!
+//! # Cancel safety
+//!
+//! Futures returned by EtherCrab, e.g. from [`SubDeviceGroup::tx_rx`], [`SubDeviceRef::sdo_read`]
+//! or EEPROM reads, can be dropped at any point, e.g. when another branch of a `select!` completes
+//! first or a timeout expires. The PDU frames used by a dropped future are released for reuse once
+//! the TX/RX task is done with them. Mailbox and EEPROM operations started by a dropped future may
+//! still complete on the SubDevice, so the next operation waits for, and discards, any late
+//! result before starting. The dropped operation itself may or may not have taken effect.
+//!
 //! # Examples
 //!
 //! This example increments the output bytes of all detected SubDevices every tick. It is tested on an
@@ -42,6 +90,9 @@
 //! $env:RUST_LOG="debug" ; cargo run --example ek1100 --release -- '\Device\NPF_{FF0ACEE6-E8CD-48D5-A399-619CD2340465}'
 //! ```
 //!
+//! The adapter's friendly name as shown in the network settings, e.g. `'Ethernet 2'`, can also be
+//! used. Call `ethercrab::std::interfaces()` to list all available interfaces.
+//!
 //! ```rust,no_run
 //! use env_logger::Env;
 //! use ethercrab::{
@@ -163,31 +214,64 @@
 // MUST go first so everything else can see the macros inside
 pub(crate) mod fmt;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod address;
 mod al_control;
 mod al_status_code;
+mod atomic;
 mod base_data_types;
+pub mod blocking;
 mod coe;
 mod command;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "cortex-m")]
+pub mod cortex_m;
 mod dc;
+mod diagnostics;
+mod dissector;
 mod dl_status;
+pub mod ds401;
 pub mod ds402;
 mod eeprom;
+#[cfg(feature = "embassy")]
+pub mod embassy;
 pub mod error;
+mod error_counters;
 mod ethernet;
 mod fmmu;
 mod generate;
+mod health;
 mod mailbox;
 mod maindevice;
+mod maindevice_builder;
 mod maindevice_config;
+mod metrics;
+mod monitor;
+mod network_topology;
+pub mod parse;
 mod pdi;
+mod pdi_data;
 mod pdu_loop;
-mod register;
+mod raw_device;
+pub mod register;
+#[cfg(feature = "rtic")]
+pub mod rtic;
+pub mod sii;
+#[cfg(feature = "sim")]
+pub mod sim;
+#[cfg(feature = "smoltcp")]
+mod smoltcp_device;
+mod state_events;
 mod subdevice;
 pub mod subdevice_group;
 mod subdevice_state;
 mod sync_manager_channel;
 mod timer_factory;
 mod vendors;
+mod waiters;
 
 #[cfg(feature = "__internals")]
 pub mod internals;
@@ -195,19 +279,46 @@ pub mod internals;
 #[cfg(feature = "std")]
 pub mod std;
 
+pub use address::{AliasAddress, AutoIncrementAddress, ConfiguredAddress, LogicalAddress};
 pub use al_status_code::AlStatusCode;
 pub use coe::SubIndex;
 pub use command::{Command, Reads, WrappedRead, WrappedWrite, Writes};
+pub use diagnostics::Diagnostics;
+pub use dissector::{FrameDissector, PduDissector};
+pub use error_counters::{ErrorCounters, PortErrors};
 pub use ethercrab_wire::{
     EtherCrabWireRead, EtherCrabWireReadSized, EtherCrabWireReadWrite, EtherCrabWireSized,
     EtherCrabWireWrite, EtherCrabWireWriteSized,
 };
 use ethernet::EthernetAddress;
-pub use maindevice::MainDevice;
-pub use maindevice_config::{MainDeviceConfig, RetryBehaviour};
-pub use pdu_loop::{PduLoop, PduRx, PduStorage, PduTx, SendableFrame};
+pub use fmmu::Fmmu;
+pub use health::{HealthConfig, HealthEvent, HealthMonitor};
+pub use maindevice::{AlStatusSummary, MainDevice};
+pub use maindevice_builder::MainDeviceBuilder;
+pub use maindevice_config::{
+    MainDeviceConfig, PdiTransfer, RetryBackoff, RetryBehaviour, StateTransitionRetry, VlanTag,
+    WireLogging,
+};
+pub use metrics::{Label, LabelValue, Metric, MetricKind, MetricsSink, MetricsSnapshot};
+pub use monitor::{Monitor, MonitorEvent};
+pub use network_topology::{LinkBreak, NetworkTopology, PortLink, TopologyNode};
+pub use pdi_data::PdiData;
+#[cfg(feature = "alloc")]
+pub use pdu_loop::BoxedPduStorage;
+pub use pdu_loop::{
+    FrameDirection, FrameHooks, FrameTap, LatencyHistogram, PduLoop, PduRx, PduStorage, PduTx,
+    SendableFrame, Statistics,
+};
+pub use raw_device::{send_receive_blocking, tx_rx_device, RawEthernetDevice};
 pub use register::{DcSupport, RegisterAddress};
-pub use subdevice::{DcSync, SubDevice, SubDeviceIdentity, SubDevicePdi, SubDeviceRef};
+#[cfg(feature = "smoltcp")]
+pub use smoltcp_device::SmoltcpDevice;
+pub use state_events::{StateEvent, StateEvents};
+pub use subdevice::{
+    DcDefaults, DcSync, ObjectAccess, ObjectCode, ObjectDictionary, ObjectEntry, RegisterWrite,
+    SubDevice, SubDeviceDiagnostics, SubDeviceIdentity, SubDeviceOverride, SubDevicePdi,
+    SubDeviceRef, SyncManagerStatus, Topology, WatchdogStatus,
+};
 pub use subdevice_group::{GroupId, GroupSubDeviceIterator, SubDeviceGroup, SubDeviceGroupHandle};
 pub use subdevice_state::SubDeviceState;
 pub use timer_factory::Timeouts;
@@ -1,30 +1,148 @@
 use crate::{
     al_control::AlControl,
-    al_status_code::AlStatusCode,
+    atomic::{AtomicU16, Ordering},
     command::Command,
     dc,
-    error::{Error, Item},
+    diagnostics::Diagnostics,
+    eeprom::device_reader::InterruptedEeproms,
+    error::{Error, Item, PduError},
+    error_counters::{ErrorCounters, ERROR_COUNTERS_LEN},
     fmt,
+    network_topology::{LinkBreak, NetworkTopology},
     pdi::PdiOffset,
-    pdu_loop::{PduLoop, ReceivedPdu},
+    pdu_loop::{FramePriority, PduLoop, ReceivedPdu},
     register::RegisterAddress,
-    subdevice::SubDevice,
+    subdevice::{SubDevice, SubDeviceRef},
     subdevice_group::{self, SubDeviceGroupHandle},
     subdevice_state::SubDeviceState,
     timer_factory::IntoTimeout,
-    MainDeviceConfig, SubDeviceGroup, Timeouts, BASE_SUBDEVICE_ADDRESS,
+    AutoIncrementAddress, ConfiguredAddress, LatencyHistogram, MainDeviceBuilder, MainDeviceConfig,
+    MetricsSnapshot, Statistics, SubDeviceGroup, Timeouts,
 };
-use core::{
-    ops::Range,
-    sync::atomic::{AtomicU16, Ordering},
-};
-use ethercrab_wire::EtherCrabWireWrite;
+#[cfg(feature = "alloc")]
+use crate::{error::ConfigError, BoxedPduStorage, PduRx, PduTx};
+use core::{ops::Range, ptr::NonNull};
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized, EtherCrabWireWrite};
 use heapless::FnvIndexMap;
 
+/// The combined AL status of every SubDevice on the network, read with a single `BRD`.
+///
+/// Returned by [`MainDevice::al_status_summary`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AlStatusSummary {
+    /// The AL state of every SubDevice ORed together.
+    ///
+    /// If SubDevices are in different states, this will be [`SubDeviceState::Other`], e.g.
+    /// `Other(0x0c)` if some SubDevices are in SAFE-OP and others in OP.
+    pub state: SubDeviceState,
+
+    /// Whether any SubDevice has its AL status error flag set.
+    pub error: bool,
+
+    /// The number of SubDevices that responded.
+    pub working_counter: u16,
+}
+
+impl AlStatusSummary {
+    /// Returns `true` if `expected_subdevices` SubDevices responded, all are in `state` and none
+    /// have their error flag set.
+    pub fn all_in_state(&self, state: SubDeviceState, expected_subdevices: usize) -> bool {
+        self.state == state
+            && !self.error
+            && usize::from(self.working_counter) == expected_subdevices
+    }
+}
+
 /// The main EtherCAT controller.
 ///
 /// The `MainDevice` is passed by reference to [`SubDeviceGroup`]s to drive their TX/RX methods. It
 /// also provides direct access to EtherCAT PDUs like `BRD`, `LRW`, etc.
+///
+/// # Thread safety
+///
+/// `MainDevice` is `Send` and `Sync`, and all its methods take `&self`, so it can be shared between
+/// tasks on a multi-threaded executor like `tokio`'s, or between OS threads, with an `Arc`, a
+/// `&'static` reference or scoped threads. No locking is required by the application:
+///
+/// - Futures returned by `MainDevice`, [`SubDeviceGroup`] and [`SubDeviceRef`](crate::SubDeviceRef)
+///   methods are `Send`, so they can be spawned on work-stealing executors and moved between
+///   threads while they are pending.
+/// - PDUs sent concurrently from different tasks or threads are sent in their own frames, or packed
+///   together if [`MainDeviceConfig::frame_packing`] is enabled, and each response is routed back to
+///   the task that sent it.
+/// - Mailbox transactions to the same SubDevice, e.g. SDO reads and writes, are serialised so they
+///   can't interfere with each other.
+/// - Group process data frames are sent before any other queued frames, so acyclic traffic from
+///   other tasks doesn't delay the process data cycle.
+///
+/// The TX/RX task, e.g. [`tx_rx_task`](crate::std::tx_rx_task), may also run on any thread.
+///
+/// A [`SubDeviceGroup`]'s process data should be exchanged and accessed by one task at a time. Other
+/// tasks can use a [`Diagnostics`] handle to read SDOs and registers of SubDevices in the group
+/// while the process data cycle runs.
+///
+/// ## Examples
+///
+/// Run the process data cycle and read an SDO in separate tasks on `tokio`'s multi-threaded
+/// runtime:
+///
+/// ```rust,no_run
+/// use ethercrab::{
+///     std::{ethercat_now, tx_rx_task},
+///     MainDevice, MainDeviceConfig, PduStorage, Timeouts,
+/// };
+/// use std::{sync::Arc, time::Duration};
+///
+/// static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+///
+/// #[tokio::main(flavor = "multi_thread")]
+/// async fn main() -> Result<(), ethercrab::error::Error> {
+///     let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+///     tokio::spawn(tx_rx_task("eth0", tx, rx).expect("spawn TX/RX task"));
+///
+///     let maindevice = Arc::new(MainDevice::new(
+///         pdu_loop,
+///         Timeouts::default(),
+///         MainDeviceConfig::default(),
+///     ));
+///
+///     let group = maindevice
+///         .init_single_group::<16, 64>(ethercat_now)
+///         .await?
+///         .into_op(&maindevice)
+///         .await?;
+///
+///     let group = Arc::new(group);
+///
+///     tokio::spawn({
+///         let maindevice = maindevice.clone();
+///         let group = group.clone();
+///
+///         async move {
+///             let mut tick = tokio::time::interval(Duration::from_millis(1));
+///
+///             loop {
+///                 group.tx_rx(&maindevice).await.expect("TX/RX");
+///
+///                 tick.tick().await;
+///             }
+///         }
+///     });
+///
+///     // Meanwhile, on whichever thread this task is running on
+///     let vendor_id = maindevice
+///         .diagnostics()
+///         .sdo_read::<u32, 16, 64, _, _>(&group, 0, 0x1018, 1)
+///         .await?;
+///
+///     println!("First SubDevice vendor ID {:#010x}", vendor_id);
+///
+///     Ok(())
+/// }
+/// ```
 #[doc(alias = "Client")]
 #[doc(alias = "Master")]
 #[derive(Debug)]
@@ -39,11 +157,22 @@ pub struct MainDevice<'sto> {
     ///
     /// If no DC subdevices are found, this will be `0`.
     dc_reference_configured_address: AtomicU16,
+    /// SubDevices whose ESC may still be busy with a cancelled EEPROM operation.
+    pub(crate) eeprom_interrupted: InterruptedEeproms,
     pub(crate) timeouts: Timeouts,
     pub(crate) config: MainDeviceConfig,
 }
 
-unsafe impl<'sto> Sync for MainDevice<'sto> {}
+// Checked here rather than implemented manually so a field that isn't thread safe can't be added
+// without breaking the guarantees documented above.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    const fn assert_send<T: Send>() {}
+
+    assert_send_sync::<MainDevice<'static>>();
+    assert_send::<crate::PduTx<'static>>();
+    assert_send::<crate::PduRx<'static>>();
+};
 
 impl<'sto> MainDevice<'sto> {
     /// Create a new EtherCrab MainDevice.
@@ -53,14 +182,48 @@ impl<'sto> MainDevice<'sto> {
         config: MainDeviceConfig,
     ) -> Self {
         Self {
-            pdu_loop,
+            pdu_loop: pdu_loop
+                .with_wire_logging(config.wire_logging)
+                .with_vlan(config.vlan)
+                .with_frame_packing(config.frame_packing),
             num_subdevices: AtomicU16::new(0),
             dc_reference_configured_address: AtomicU16::new(0),
+            eeprom_interrupted: InterruptedEeproms::new(),
             timeouts,
             config,
         }
     }
 
+    /// Create a new EtherCrab MainDevice backed by heap allocated PDU storage.
+    ///
+    /// The storage is split and the TX and RX handles are returned with the MainDevice, ready to be
+    /// passed to a TX/RX task. See [`PduStorage::new_boxed`](crate::PduStorage::new_boxed) for an
+    /// example.
+    ///
+    /// Requires the `alloc` feature, which is enabled by `std`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::StorageAlreadySplit`](crate::error::ConfigError::StorageAlreadySplit)
+    /// if the storage has already been used.
+    #[cfg(feature = "alloc")]
+    pub fn from_storage(
+        storage: &'sto BoxedPduStorage,
+        timeouts: Timeouts,
+        config: MainDeviceConfig,
+    ) -> Result<(PduTx<'sto>, PduRx<'sto>, Self), Error> {
+        let (tx, rx, pdu_loop) = storage
+            .try_split()
+            .map_err(|_| Error::Config(ConfigError::StorageAlreadySplit))?;
+
+        Ok((tx, rx, Self::new(pdu_loop, timeouts, config)))
+    }
+
+    /// Create a [`MainDeviceBuilder`] to configure a new EtherCrab MainDevice.
+    pub fn builder(pdu_loop: PduLoop<'sto>) -> MainDeviceBuilder<'sto> {
+        MainDeviceBuilder::new(pdu_loop)
+    }
+
     /// Write zeroes to every SubDevice's memory in chunks.
     async fn blank_memory(&self, start: impl Into<u16>, len: u16) -> Result<(), Error> {
         let step = self.pdu_loop.max_frame_data();
@@ -74,6 +237,7 @@ impl<'sto> MainDevice<'sto> {
                     chunk_len,
                     self.timeouts.pdu,
                     self.config.retry_behaviour.retry_count(),
+                    self.config.retry_backoff,
                 )
                 .await?;
         }
@@ -83,6 +247,7 @@ impl<'sto> MainDevice<'sto> {
 
     // FIXME: When adding a powered on SubDevice to the network, something breaks. Maybe need to reset
     // the configured address? But this broke other stuff so idk...
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     async fn reset_subdevices(&self) -> Result<(), Error> {
         fmt::debug!("Beginning reset");
 
@@ -185,7 +350,9 @@ impl<'sto> MainDevice<'sto> {
     /// `MAX_SUBDEVICES` must be a power of 2 greater than 1.
     ///
     /// Note that the sum of the PDI data length for all [`SubDeviceGroup`]s must not exceed the
-    /// value of `MAX_PDU_DATA`.
+    /// value of `MAX_PDU_DATA`. [`PduStorage::pdi_data_len`](crate::PduStorage::pdi_data_len) and
+    /// [`PduStorage::assert_fits`](crate::PduStorage::assert_fits) can be used to check this at
+    /// compile time.
     ///
     /// # Examples
     ///
@@ -231,6 +398,7 @@ impl<'sto> MainDevice<'sto> {
     ///     .expect("Init");
     /// # };
     /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub async fn init<const MAX_SUBDEVICES: usize, G>(
         &self,
         now: impl Fn() -> u64 + Copy,
@@ -250,6 +418,21 @@ impl<'sto> MainDevice<'sto> {
 
         fmt::debug!("Discovered {} SubDevices", num_subdevices);
 
+        if let Some(expected) = self.config.expected_subdevices {
+            if num_subdevices != expected {
+                fmt::error!(
+                    "Expected {} SubDevices, discovered {}",
+                    expected,
+                    num_subdevices
+                );
+
+                return Err(Error::SubDeviceCount {
+                    expected,
+                    found: num_subdevices,
+                });
+            }
+        }
+
         if num_subdevices == 0 {
             fmt::warn!("No SubDevices were discovered. Check NIC device, connections and PDU response timeouts");
 
@@ -266,13 +449,13 @@ impl<'sto> MainDevice<'sto> {
 
         // Set configured address for all discovered SubDevices
         for subdevice_idx in 0..num_subdevices {
-            let configured_address = BASE_SUBDEVICE_ADDRESS.wrapping_add(subdevice_idx);
+            let configured_address = ConfiguredAddress::from_index(subdevice_idx);
 
             Command::apwr(
-                subdevice_idx,
+                AutoIncrementAddress::new(subdevice_idx),
                 RegisterAddress::ConfiguredStationAddress.into(),
             )
-            .send(self, configured_address)
+            .send(self, configured_address.get())
             .await?;
 
             let subdevice = SubDevice::new(self, subdevice_idx, configured_address).await?;
@@ -282,18 +465,26 @@ impl<'sto> MainDevice<'sto> {
                 .map_err(|_| Error::Capacity(Item::SubDevice))?;
         }
 
-        fmt::debug!("Configuring topology/distributed clocks");
+        if self.config.distributed_clocks {
+            fmt::debug!("Configuring topology/distributed clocks");
 
-        // Configure distributed clock offsets/propagation delays, perform static drift
-        // compensation. We need the SubDevices in a single list so we can read the topology.
-        let dc_master = dc::configure_dc(self, subdevices.as_mut_slices().0, now).await?;
+            // Configure distributed clock offsets/propagation delays, perform static drift
+            // compensation. We need the SubDevices in a single list so we can read the topology.
+            let dc_master = dc::configure_dc(self, subdevices.as_mut_slices().0, now).await?;
 
-        // If there are SubDevices that support distributed clocks, run static drift compensation
-        if let Some(dc_master) = dc_master {
-            self.dc_reference_configured_address
-                .store(dc_master.configured_address(), Ordering::Relaxed);
+            // If there are SubDevices that support distributed clocks, run static drift
+            // compensation
+            if let Some(dc_master) = dc_master {
+                self.dc_reference_configured_address
+                    .store(dc_master.configured_address().get(), Ordering::Relaxed);
 
-            dc::run_dc_static_sync(self, dc_master, self.config.dc_static_sync_iterations).await?;
+                dc::run_dc_static_sync(self, dc_master, self.config.dc_static_sync_iterations)
+                    .await?;
+            }
+        } else {
+            fmt::debug!("Configuring topology, distributed clocks are disabled");
+
+            dc::detect_topology(self, subdevices.as_mut_slices().0).await?;
         }
 
         // This block is to reduce the lifetime of the groups map references
@@ -322,7 +513,7 @@ impl<'sto> MainDevice<'sto> {
                 fmt::debug!("After group ID {} offset: {:?}", id, offset);
             }
 
-            fmt::debug!("Total PDI {} bytes", offset.start_address);
+            fmt::debug!("Total PDI {} bytes", offset.start_address.get());
         }
 
         // Check that all SubDevices reached PRE-OP
@@ -420,6 +611,164 @@ impl<'sto> MainDevice<'sto> {
             .await
     }
 
+    /// Detect all SubDevices on the network and read their identity, alias address and position
+    /// in the network topology.
+    ///
+    /// Unlike [`init`](MainDevice::init), this method does not reset SubDevices, configure
+    /// mailboxes, FMMUs or sync managers, or request any state changes. Configured station
+    /// addresses are assigned so that each SubDevice's EEPROM can be read. This makes it suitable
+    /// for tooling that needs to know what is connected to the network without bringing it up.
+    ///
+    /// The returned SubDevices are in network order and are for information only; they cannot be
+    /// added to a [`SubDeviceGroup`]. Call [`init`](MainDevice::init) to configure the network for
+    /// use.
+    ///
+    /// # Errors
+    ///
+    /// An [`Error::Capacity`] is returned if more than `MAX_SUBDEVICES` SubDevices are discovered.
+    /// The network is not modified in this case.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+    ///
+    /// const MAX_SUBDEVICES: usize = 16;
+    /// const MAX_PDU_DATA: usize = PduStorage::element_size(1100);
+    /// const MAX_FRAMES: usize = 16;
+    ///
+    /// static PDU_STORAGE: PduStorage<MAX_FRAMES, MAX_PDU_DATA> = PduStorage::new();
+    ///
+    /// let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    ///
+    /// let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    ///
+    /// # async {
+    /// let subdevices = maindevice.scan::<MAX_SUBDEVICES>().await.expect("Scan");
+    ///
+    /// for subdevice in subdevices.iter() {
+    ///     log::info!(
+    ///         "#{} {} alias {:#06x}, {}, {:?}, parent {:?}",
+    ///         subdevice.index(),
+    ///         subdevice.name(),
+    ///         subdevice.alias_address(),
+    ///         subdevice.identity(),
+    ///         subdevice.topology(),
+    ///         subdevice.parent_index(),
+    ///     );
+    /// }
+    /// # };
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub async fn scan<const MAX_SUBDEVICES: usize>(
+        &self,
+    ) -> Result<heapless::Vec<SubDevice, MAX_SUBDEVICES>, Error> {
+        let num_subdevices = self.count_subdevices().await?;
+
+        fmt::debug!("Scan discovered {} SubDevices", num_subdevices);
+
+        // Fail before any addresses are assigned instead of returning a partial scan
+        if usize::from(num_subdevices) > MAX_SUBDEVICES {
+            fmt::error!(
+                "Discovered {} SubDevices, but scan can only hold {}",
+                num_subdevices,
+                MAX_SUBDEVICES
+            );
+
+            return Err(Error::Capacity(Item::SubDevice));
+        }
+
+        let mut subdevices = heapless::Vec::<SubDevice, MAX_SUBDEVICES>::new();
+
+        for subdevice_idx in 0..num_subdevices {
+            let configured_address = ConfiguredAddress::from_index(subdevice_idx);
+
+            Command::apwr(
+                AutoIncrementAddress::new(subdevice_idx),
+                RegisterAddress::ConfiguredStationAddress.into(),
+            )
+            .send(self, configured_address.get())
+            .await?;
+
+            let subdevice =
+                SubDevice::read_details(self, subdevice_idx, configured_address).await?;
+
+            subdevices
+                .push(subdevice)
+                .map_err(|_| Error::Capacity(Item::SubDevice))?;
+        }
+
+        dc::detect_topology(self, &mut subdevices).await?;
+
+        Ok(subdevices)
+    }
+
+    /// Scan the network and build a map of which port of which SubDevice is connected to which
+    /// neighbour.
+    ///
+    /// This calls [`scan`](MainDevice::scan), so configured station addresses are reassigned in
+    /// network order. If SubDevices have dropped off the network, the remaining SubDevices may
+    /// be given different addresses, so this method should not be called while a
+    /// [`SubDeviceGroup`] is exchanging process data.
+    ///
+    /// See [`NetworkTopology`] for examples.
+    ///
+    /// # Errors
+    ///
+    /// An [`Error::Capacity`] is returned if more than `MAX_SUBDEVICES` SubDevices are discovered.
+    pub async fn topology<const MAX_SUBDEVICES: usize>(
+        &self,
+    ) -> Result<NetworkTopology<MAX_SUBDEVICES>, Error> {
+        let subdevices = self.scan::<MAX_SUBDEVICES>().await?;
+
+        NetworkTopology::new(&subdevices)
+    }
+
+    /// Find where SubDevices in `expected` can no longer be reached.
+    ///
+    /// The DL status register of every SubDevice in `expected` is read by configured address,
+    /// starting at the first SubDevice. When a SubDevice doesn't respond, the link between it and
+    /// its parent is reported as a [`LinkBreak`], along with whether the parent still detects a
+    /// physical link on that port. SubDevices further down the same branch are not read.
+    ///
+    /// `expected` should be captured with [`topology`](MainDevice::topology) before
+    /// [`init`](MainDevice::init) is called, so configured addresses match. Unlike `topology`, this
+    /// method only reads from the network, so can be called while process data is being exchanged,
+    /// e.g. when a group's working counter drops.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+    /// # static PDU_STORAGE: PduStorage<2, { PduStorage::element_size(32) }> = PduStorage::new();
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # async {
+    /// let expected = maindevice.topology::<16>().await?;
+    ///
+    /// let group = maindevice.init_single_group::<16, 64>(|| 0).await?;
+    /// let group = group.into_op(&maindevice).await?;
+    ///
+    /// let expected_wkc = group.tx_rx(&maindevice).await?;
+    ///
+    /// loop {
+    ///     if group.tx_rx(&maindevice).await? != expected_wkc {
+    ///         for link in maindevice.locate_breaks(&expected).await? {
+    ///             // E.g. "link lost between SubDevice 7 port 1 and SubDevice 8 port 0, ..."
+    ///             log::error!("{}", link);
+    ///         }
+    ///     }
+    /// }
+    /// # Ok::<(), ethercrab::error::Error>(())
+    /// # };
+    /// ```
+    pub async fn locate_breaks<const MAX_SUBDEVICES: usize>(
+        &self,
+        expected: &NetworkTopology<MAX_SUBDEVICES>,
+    ) -> Result<heapless::Vec<LinkBreak, MAX_SUBDEVICES>, Error> {
+        expected.probe_links(self).await
+    }
+
     /// Count the number of SubDevices on the network.
     async fn count_subdevices(&self) -> Result<u16, Error> {
         Command::brd(RegisterAddress::Type.into())
@@ -435,18 +784,160 @@ impl<'sto> MainDevice<'sto> {
         usize::from(self.num_subdevices.load(Ordering::Relaxed))
     }
 
+    /// Read the AL status of every SubDevice on the network with a single `BRD`.
+    ///
+    /// This is a cheap health check that can be run alongside the process data cycle. Use
+    /// [`AlStatusSummary::all_in_state`] with [`num_subdevices`](MainDevice::num_subdevices) to
+    /// check that every SubDevice is still present and in the expected state.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, SubDeviceState, Timeouts};
+    /// # static PDU_STORAGE: PduStorage<2, { PduStorage::element_size(32) }> = PduStorage::new();
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # async {
+    /// let summary = maindevice.al_status_summary().await?;
+    ///
+    /// if !summary.all_in_state(SubDeviceState::Op, maindevice.num_subdevices()) {
+    ///     log::warn!("Network is unhealthy: {:?}", summary);
+    /// }
+    /// # Ok::<(), ethercrab::error::Error>(())
+    /// # };
+    /// ```
+    pub async fn al_status_summary(&self) -> Result<AlStatusSummary, Error> {
+        let response = Command::brd(RegisterAddress::AlStatus.into())
+            .ignore_wkc()
+            .receive_slice(self, AlControl::PACKED_LEN as u16)
+            .await?;
+
+        let status = AlControl::unpack_from_slice(&response)?;
+
+        Ok(AlStatusSummary {
+            state: status.state,
+            error: status.error,
+            working_counter: response.working_counter,
+        })
+    }
+
+    /// Read the RX error, forwarded error and lost link counters of every SubDevice on the
+    /// network.
+    ///
+    /// Counters are read from each SubDevice in network order with an `FPRD`, so
+    /// [`init`](MainDevice::init) must have been called first. Errors on a port point to a bad
+    /// cable or connector between that port and its neighbour. Use
+    /// [`reset_error_counters`](MainDevice::reset_error_counters) to start counting from zero.
+    ///
+    /// This method will return [`Error::Capacity`] if there are more than `MAX_SUBDEVICES`
+    /// SubDevices on the network.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+    /// # static PDU_STORAGE: PduStorage<2, { PduStorage::element_size(32) }> = PduStorage::new();
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # async {
+    /// for counters in maindevice.error_counters::<16>().await? {
+    ///     for (port, errors) in counters.ports.iter().enumerate() {
+    ///         if !errors.is_empty() {
+    ///             log::warn!(
+    ///                 "SubDevice {:#06x} port {}: {:?}",
+    ///                 counters.configured_address,
+    ///                 port,
+    ///                 errors
+    ///             );
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// maindevice.reset_error_counters().await?;
+    /// # Ok::<(), ethercrab::error::Error>(())
+    /// # };
+    /// ```
+    pub async fn error_counters<const MAX_SUBDEVICES: usize>(
+        &self,
+    ) -> Result<heapless::Vec<ErrorCounters, MAX_SUBDEVICES>, Error> {
+        let mut report = heapless::Vec::new();
+
+        for subdevice_idx in 0..self.num_subdevices.load(Ordering::Relaxed) {
+            let configured_address = ConfiguredAddress::from_index(subdevice_idx);
+
+            let raw = Command::fprd(configured_address, RegisterAddress::RxErrorCounter.into())
+                .receive_slice(self, ERROR_COUNTERS_LEN)
+                .await?;
+
+            report
+                .push(ErrorCounters::parse(configured_address, &raw)?)
+                .map_err(|_| Error::Capacity(Item::SubDevice))?;
+        }
+
+        Ok(report)
+    }
+
+    /// Reset the error counters read by [`error_counters`](MainDevice::error_counters) to zero on
+    /// every SubDevice with a single `BWR`.
+    pub async fn reset_error_counters(&self) -> Result<(), Error> {
+        Command::bwr(RegisterAddress::RxErrorCounter.into())
+            .ignore_wkc()
+            .send(self, [0u8; ERROR_COUNTERS_LEN as usize])
+            .await
+    }
+
+    /// Collect network statistics, the PDU round trip histogram and the error counters of every
+    /// SubDevice into a [`MetricsSnapshot`] that can be exported to a monitoring system.
+    ///
+    /// This method will return [`Error::Capacity`] if there are more than `MAX_SUBDEVICES`
+    /// SubDevices on the network.
+    ///
+    /// # Examples
+    ///
+    /// Render metrics in the Prometheus text format, e.g. to serve to a Prometheus scraper:
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts, std::PrometheusEncoder};
+    /// # static PDU_STORAGE: PduStorage<2, { PduStorage::element_size(32) }> = PduStorage::new();
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # async {
+    /// let snapshot = maindevice.metrics::<16>().await?;
+    ///
+    /// let mut encoder = PrometheusEncoder::new();
+    ///
+    /// snapshot.write(&mut encoder);
+    ///
+    /// println!("{}", encoder.finish());
+    /// # Ok::<(), ethercrab::error::Error>(())
+    /// # };
+    /// ```
+    pub async fn metrics<const MAX_SUBDEVICES: usize>(
+        &self,
+    ) -> Result<MetricsSnapshot<MAX_SUBDEVICES>, Error> {
+        Ok(MetricsSnapshot {
+            statistics: self.statistics(),
+            latency: Some(self.latency_histogram()),
+            error_counters: self.error_counters().await?,
+        })
+    }
+
     /// Get the configured address of the designated DC reference subdevice.
-    pub(crate) fn dc_ref_address(&self) -> Option<u16> {
+    pub(crate) fn dc_ref_address(&self) -> Option<ConfiguredAddress> {
         let addr = self.dc_reference_configured_address.load(Ordering::Relaxed);
 
         if addr > 0 {
-            Some(addr)
+            Some(ConfiguredAddress::new(addr))
         } else {
             None
         }
     }
 
     /// Wait for all SubDevices on the network to reach a given state.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(state = %desired_state))
+    )]
     pub async fn wait_for_state(&self, desired_state: SubDeviceState) -> Result<(), Error> {
         let num_subdevices = self.num_subdevices.load(Ordering::Relaxed);
 
@@ -465,24 +956,33 @@ impl<'sto> MainDevice<'sto> {
                         desired_state,
                     );
 
-                    for subdevice_addr in BASE_SUBDEVICE_ADDRESS
-                        ..(BASE_SUBDEVICE_ADDRESS + self.num_subdevices() as u16)
-                    {
-                        let status =
-                            Command::fprd(subdevice_addr, RegisterAddress::AlStatusCode.into())
-                                .ignore_wkc()
-                                .receive::<AlStatusCode>(self)
-                                .await
-                                .unwrap_or(AlStatusCode::UnspecifiedError);
-
-                        fmt::error!(
-                            "--> SubDevice {:#06x} status code {}",
-                            subdevice_addr,
-                            status
+                    let mut first_error = None;
+
+                    for subdevice_idx in 0..self.num_subdevices() as u16 {
+                        let subdevice = SubDeviceRef::new(
+                            self,
+                            ConfiguredAddress::from_index(subdevice_idx),
+                            (),
                         );
+
+                        let status = subdevice
+                            .read(RegisterAddress::AlStatus)
+                            .ignore_wkc()
+                            .receive::<AlControl>(self)
+                            .await?;
+
+                        if status.error {
+                            let e = subdevice.state_transition_error(desired_state).await;
+
+                            first_error.get_or_insert(e);
+                        }
                     }
 
-                    return Err(Error::StateTransition);
+                    // The error flag may have been cleared between the broadcast and individual
+                    // reads, in which case we keep waiting.
+                    if let Some(e) = first_error {
+                        return Err(e);
+                    }
                 }
 
                 if status.state == desired_state {
@@ -496,6 +996,54 @@ impl<'sto> MainDevice<'sto> {
         .await
     }
 
+    /// Get network communication statistics, e.g. the number of frames sent and received, PDU
+    /// retries and working counter errors.
+    pub fn statistics(&self) -> Statistics {
+        self.pdu_loop.statistics()
+    }
+
+    /// Get a histogram of PDU round trip times since the PDU storage was created or the histogram
+    /// was last reset.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+    /// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// let latency = maindevice.latency_histogram();
+    ///
+    /// println!(
+    ///     "{} round trips, p50 {:?}, p99 {:?}, max {:?}",
+    ///     latency.len(),
+    ///     latency.percentile(50.0),
+    ///     latency.percentile(99.0),
+    ///     latency.max()
+    /// );
+    ///
+    /// for (range, count) in latency.buckets() {
+    ///     println!("{:?}: {}", range, count);
+    /// }
+    /// ```
+    pub fn latency_histogram(&self) -> LatencyHistogram {
+        self.pdu_loop.latency_histogram()
+    }
+
+    /// Clear all samples from the PDU round trip time histogram, e.g. to only measure latency
+    /// under a certain load.
+    pub fn reset_latency_histogram(&self) {
+        self.pdu_loop.reset_latency_histogram()
+    }
+
+    /// Get a cloneable handle for reading registers, SDOs and statistics from other tasks or
+    /// threads while this MainDevice runs the process data cycle.
+    ///
+    /// See [`Diagnostics`] for more information.
+    pub fn diagnostics(&self) -> Diagnostics<'_> {
+        Diagnostics::new(self)
+    }
+
     pub(crate) fn max_frame_data(&self) -> usize {
         self.pdu_loop.max_frame_data()
     }
@@ -507,7 +1055,10 @@ impl<'sto> MainDevice<'sto> {
         data: impl EtherCrabWireWrite,
         len_override: Option<u16>,
     ) -> Result<ReceivedPdu<'sto>, Error> {
-        let mut frame = self.pdu_loop.alloc_frame()?;
+        let mut frame = self
+            .pdu_loop
+            .alloc_frame_wait(self.timeouts.pdu, FramePriority::Low)
+            .await?;
 
         let handle = frame.push_pdu(command, data, len_override)?;
 
@@ -515,6 +1066,39 @@ impl<'sto> MainDevice<'sto> {
             &self.pdu_loop,
             self.timeouts.pdu,
             self.config.retry_behaviour.retry_count(),
+            self.config.retry_backoff,
+        );
+
+        self.pdu_loop.wake_sender();
+
+        frame.await?.first_pdu(handle)
+    }
+
+    /// Send a single PDU in a frame, writing its response data directly into `dest`.
+    ///
+    /// The returned PDU's data is the data that was sent, not the response.
+    pub(crate) async fn single_pdu_direct_rx(
+        &'sto self,
+        command: Command,
+        dest: &mut [u8],
+    ) -> Result<ReceivedPdu<'sto>, Error> {
+        let mut frame = self
+            .pdu_loop
+            .alloc_frame_wait(self.timeouts.pdu, FramePriority::Low)
+            .await?;
+
+        let len = u16::try_from(dest.len()).map_err(|_| PduError::TooLong)?;
+
+        // SAFETY: `dest` is mutably borrowed until the frame future below completes or is dropped,
+        // and `ReceiveFrameFut` waits for any in-progress receive to finish when dropped.
+        let handle =
+            unsafe { frame.push_pdu_direct_rx(command, (), Some(len), NonNull::from(dest))? };
+
+        let frame = frame.mark_sendable(
+            &self.pdu_loop,
+            self.timeouts.pdu,
+            self.config.retry_behaviour.retry_count(),
+            self.config.retry_backoff,
         );
 
         self.pdu_loop.wake_sender();
@@ -550,6 +1134,35 @@ fn blank_mem_iter(
 mod tests {
     use super::*;
 
+    #[test]
+    fn al_status_summary_all_in_state() {
+        let summary = AlStatusSummary {
+            state: SubDeviceState::Op,
+            error: false,
+            working_counter: 3,
+        };
+
+        assert!(summary.all_in_state(SubDeviceState::Op, 3));
+        // SubDevice missing
+        assert!(!summary.all_in_state(SubDeviceState::Op, 4));
+        assert!(!summary.all_in_state(SubDeviceState::SafeOp, 3));
+
+        // OP | SAFE-OP when ORed together by the BRD
+        let mixed = AlStatusSummary {
+            state: SubDeviceState::Other(0x0c),
+            ..summary
+        };
+
+        assert!(!mixed.all_in_state(SubDeviceState::Op, 3));
+
+        let error = AlStatusSummary {
+            error: true,
+            ..summary
+        };
+
+        assert!(!error.all_in_state(SubDeviceState::Op, 3));
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     fn blank_mem_fuzz() {
@@ -583,3 +1196,176 @@ mod tests {
         });
     }
 }
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        error::{Error, Item},
+        sim::fixture::*,
+        tx_rx_device, AutoIncrementAddress, Command, ConfiguredAddress, MainDevice,
+        MainDeviceConfig, PduStorage, RegisterAddress, SubDeviceState, Timeouts,
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn shared_between_threads() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let (tx, rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let maindevice = Arc::new(MainDevice::new(
+            pdu_loop,
+            Timeouts::default(),
+            MainDeviceConfig::default(),
+        ));
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(4)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        runtime.spawn(tx_rx_device(network(), tx, rx));
+
+        runtime.block_on(async {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            let group = Arc::new(group);
+
+            let cycle = tokio::spawn({
+                let maindevice = maindevice.clone();
+                let group = group.clone();
+
+                async move {
+                    for _ in 0..100 {
+                        group.tx_rx(&maindevice).await.expect("TX/RX");
+                    }
+                }
+            });
+
+            let sdo_tasks = (0..4)
+                .map(|_| {
+                    let maindevice = maindevice.clone();
+                    let group = group.clone();
+
+                    tokio::spawn(async move {
+                        for _ in 0..10 {
+                            assert_eq!(
+                                maindevice
+                                    .diagnostics()
+                                    .sdo_read::<u16, 8, 32, _, _>(&group, 3, 0x2000, 0)
+                                    .await,
+                                Ok(0x1234)
+                            );
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            // Not running on the runtime at all
+            let thread = std::thread::spawn({
+                let maindevice = maindevice.clone();
+
+                move || futures_lite::future::block_on(maindevice.al_status_summary())
+            });
+
+            cycle.await.expect("cycle task");
+
+            for task in sdo_tasks {
+                task.await.expect("SDO task");
+            }
+
+            assert_eq!(
+                thread.join().expect("thread").map(|status| status.state),
+                Ok(SubDeviceState::Op)
+            );
+        });
+    }
+
+    #[test]
+    fn scan() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let subdevices = maindevice.scan::<8>().await.expect("scan");
+
+            assert_eq!(
+                subdevices
+                    .iter()
+                    .map(|subdevice| (
+                        subdevice.index(),
+                        subdevice.configured_address(),
+                        subdevice.name(),
+                        subdevice.identity().product_id
+                    ))
+                    .collect::<Vec<_>>(),
+                [
+                    (0, ConfiguredAddress::new(0x1000), "EK1100", 0x044c_2c52),
+                    (1, ConfiguredAddress::new(0x1001), "EL2004", 0x07d4_3052),
+                    (2, ConfiguredAddress::new(0x1002), "EL1004", 0x03ec_3052),
+                    (3, ConfiguredAddress::new(0x1003), "Drive", 0x1234),
+                ]
+            );
+            assert_eq!(
+                subdevices
+                    .iter()
+                    .map(|subdevice| subdevice.parent_index())
+                    .collect::<Vec<_>>(),
+                [None, Some(0), Some(1), Some(2)]
+            );
+        });
+
+        // No state changes are requested
+        for index in 0..network.len() {
+            assert_eq!(
+                network.with_subdevice(index, |subdevice| subdevice.state()),
+                Some(SubDeviceState::Init)
+            );
+        }
+    }
+
+    #[test]
+    fn scan_capacity() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            // The network is not truncated to fit
+            assert_eq!(
+                maindevice
+                    .scan::<3>()
+                    .await
+                    .map(|subdevices| subdevices.len()),
+                Err(Error::Capacity(Item::SubDevice))
+            );
+
+            // Nothing is written to the network before the error is returned
+            assert_eq!(
+                Command::aprd(
+                    AutoIncrementAddress::new(0),
+                    RegisterAddress::ConfiguredStationAddress.into()
+                )
+                .receive::<u16>(&maindevice)
+                .await,
+                Ok(0)
+            );
+
+            assert_eq!(
+                maindevice
+                    .scan::<4>()
+                    .await
+                    .map(|subdevices| subdevices.len()),
+                Ok(4)
+            );
+        });
+    }
+}
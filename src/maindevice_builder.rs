@@ -0,0 +1,284 @@
+//! Builder for [`MainDevice`](crate::MainDevice).
+
+use crate::{
+    error::{ConfigError, Error},
+    MainDevice, MainDeviceConfig, PdiTransfer, PduLoop, RetryBackoff, RetryBehaviour,
+    StateTransitionRetry, SubDeviceOverride, Timeouts, VlanTag, WireLogging,
+};
+
+/// Builder for a [`MainDevice`], created with [`MainDevice::builder`].
+///
+/// Options that are not set use the values from [`Timeouts::default`] and
+/// [`MainDeviceConfig::default`]. The configuration is checked when calling
+/// [`build`](MainDeviceBuilder::build).
+///
+/// # Examples
+///
+/// ```rust
+/// use core::time::Duration;
+/// use ethercrab::{MainDevice, PduStorage, PdiTransfer, RetryBehaviour, Timeouts};
+///
+/// static PDU_STORAGE: PduStorage<2, { PduStorage::element_size(32) }> = PduStorage::new();
+///
+/// let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// let maindevice = MainDevice::builder(pdu_loop)
+///     .timeouts(Timeouts {
+///         pdu: Duration::from_millis(10),
+///         ..Timeouts::default()
+///     })
+///     .retry_behaviour(RetryBehaviour::Count(3))
+///     .distributed_clocks(false)
+///     .pdi_transfer(PdiTransfer::LrdLwr)
+///     .expected_subdevices(4)
+///     .build()
+///     .expect("Invalid config");
+/// ```
+#[derive(Debug)]
+pub struct MainDeviceBuilder<'sto> {
+    pdu_loop: PduLoop<'sto>,
+    timeouts: Timeouts,
+    config: MainDeviceConfig,
+}
+
+impl<'sto> MainDeviceBuilder<'sto> {
+    pub(crate) fn new(pdu_loop: PduLoop<'sto>) -> Self {
+        Self {
+            pdu_loop,
+            timeouts: Timeouts::default(),
+            config: MainDeviceConfig::default(),
+        }
+    }
+
+    /// Set all timeouts.
+    pub fn timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+
+        self
+    }
+
+    /// Replace the entire configuration.
+    ///
+    /// Options set before this method is called are overwritten.
+    pub fn config(mut self, config: MainDeviceConfig) -> Self {
+        self.config = config;
+
+        self
+    }
+
+    /// Set the PDU retry behaviour. See [`MainDeviceConfig::retry_behaviour`].
+    pub fn retry_behaviour(mut self, retry_behaviour: RetryBehaviour) -> Self {
+        self.config.retry_behaviour = retry_behaviour;
+
+        self
+    }
+
+    /// Set the delay before resending a timed out PDU. See [`MainDeviceConfig::retry_backoff`].
+    pub fn retry_backoff(mut self, retry_backoff: RetryBackoff) -> Self {
+        self.config.retry_backoff = retry_backoff;
+
+        self
+    }
+
+    /// Enable or disable Distributed Clocks configuration. See
+    /// [`MainDeviceConfig::distributed_clocks`].
+    pub fn distributed_clocks(mut self, enabled: bool) -> Self {
+        self.config.distributed_clocks = enabled;
+
+        self
+    }
+
+    /// Set the number of static DC sync iterations. See
+    /// [`MainDeviceConfig::dc_static_sync_iterations`].
+    pub fn dc_static_sync_iterations(mut self, iterations: u32) -> Self {
+        self.config.dc_static_sync_iterations = iterations;
+
+        self
+    }
+
+    /// Set the commands used to exchange process data. See [`MainDeviceConfig::pdi_transfer`].
+    pub fn pdi_transfer(mut self, pdi_transfer: PdiTransfer) -> Self {
+        self.config.pdi_transfer = pdi_transfer;
+
+        self
+    }
+
+    /// Set how much of each sent and received frame is logged. See
+    /// [`MainDeviceConfig::wire_logging`].
+    pub fn wire_logging(mut self, wire_logging: WireLogging) -> Self {
+        self.config.wire_logging = wire_logging;
+
+        self
+    }
+
+    /// Set the number of SubDevices expected on the network. See
+    /// [`MainDeviceConfig::expected_subdevices`].
+    pub fn expected_subdevices(mut self, count: u16) -> Self {
+        self.config.expected_subdevices = Some(count);
+
+        self
+    }
+
+    /// Tag every sent frame with the given VLAN. See [`MainDeviceConfig::vlan`].
+    pub fn vlan(mut self, vlan: VlanTag) -> Self {
+        self.config.vlan = Some(vlan);
+
+        self
+    }
+
+    /// Send PDUs queued by different tasks in a single frame where they fit. See
+    /// [`MainDeviceConfig::frame_packing`].
+    pub fn frame_packing(mut self, frame_packing: bool) -> Self {
+        self.config.frame_packing = frame_packing;
+
+        self
+    }
+
+    /// Cache SubDevice EEPROM reads during initialisation. See
+    /// [`MainDeviceConfig::eeprom_cache`].
+    pub fn eeprom_cache(mut self, enabled: bool) -> Self {
+        self.config.eeprom_cache = enabled;
+
+        self
+    }
+
+    /// Acknowledge and retry refused SubDevice state transitions. See
+    /// [`MainDeviceConfig::state_transition_retry`].
+    pub fn state_transition_retry(mut self, retry: StateTransitionRetry) -> Self {
+        self.config.state_transition_retry = retry;
+
+        self
+    }
+
+    /// Replace parts of the EEPROM configuration of matching SubDevices. See
+    /// [`MainDeviceConfig::subdevice_overrides`].
+    pub fn subdevice_overrides(mut self, overrides: &'static [SubDeviceOverride]) -> Self {
+        self.config.subdevice_overrides = overrides;
+
+        self
+    }
+
+    /// Validate the configuration and create a [`MainDevice`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Config`] if any option is invalid.
+    pub fn build(self) -> Result<MainDevice<'sto>, Error> {
+        validate(&self.timeouts, &self.config)?;
+
+        Ok(MainDevice::new(self.pdu_loop, self.timeouts, self.config))
+    }
+}
+
+fn validate(timeouts: &Timeouts, config: &MainDeviceConfig) -> Result<(), ConfigError> {
+    if timeouts.pdu.is_zero() {
+        return Err(ConfigError::ZeroPduTimeout);
+    }
+
+    for backoff in [config.retry_backoff, config.state_transition_retry.backoff] {
+        if let RetryBackoff::Exponential { initial, max } = backoff {
+            if initial > max {
+                return Err(ConfigError::BackoffRange);
+            }
+        }
+    }
+
+    if config.expected_subdevices == Some(0) {
+        return Err(ConfigError::NoExpectedSubDevices);
+    }
+
+    if let Some(vlan) = config.vlan {
+        if vlan.vid > VlanTag::MAX_VID || vlan.pcp > VlanTag::MAX_PCP {
+            return Err(ConfigError::InvalidVlanTag);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::time::Duration;
+
+    #[test]
+    fn default_is_valid() {
+        assert_eq!(
+            validate(&Timeouts::default(), &MainDeviceConfig::default()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn zero_pdu_timeout() {
+        let timeouts = Timeouts {
+            pdu: Duration::ZERO,
+            ..Timeouts::default()
+        };
+
+        assert_eq!(
+            validate(&timeouts, &MainDeviceConfig::default()),
+            Err(ConfigError::ZeroPduTimeout)
+        );
+    }
+
+    #[test]
+    fn backoff_range() {
+        let config = MainDeviceConfig {
+            retry_backoff: RetryBackoff::Exponential {
+                initial: Duration::from_millis(10),
+                max: Duration::from_millis(1),
+            },
+            ..MainDeviceConfig::default()
+        };
+
+        assert_eq!(
+            validate(&Timeouts::default(), &config),
+            Err(ConfigError::BackoffRange)
+        );
+
+        let config = MainDeviceConfig {
+            state_transition_retry: StateTransitionRetry {
+                retries: 3,
+                backoff: RetryBackoff::Exponential {
+                    initial: Duration::from_millis(10),
+                    max: Duration::from_millis(1),
+                },
+            },
+            ..MainDeviceConfig::default()
+        };
+
+        assert_eq!(
+            validate(&Timeouts::default(), &config),
+            Err(ConfigError::BackoffRange)
+        );
+    }
+
+    #[test]
+    fn zero_expected_subdevices() {
+        let config = MainDeviceConfig {
+            expected_subdevices: Some(0),
+            ..MainDeviceConfig::default()
+        };
+
+        assert_eq!(
+            validate(&Timeouts::default(), &config),
+            Err(ConfigError::NoExpectedSubDevices)
+        );
+    }
+
+    #[test]
+    fn invalid_vlan_tag() {
+        for vlan in [VlanTag { vid: 0xfff, pcp: 0 }, VlanTag { vid: 1, pcp: 8 }] {
+            let config = MainDeviceConfig {
+                vlan: Some(vlan),
+                ..MainDeviceConfig::default()
+            };
+
+            assert_eq!(
+                validate(&Timeouts::default(), &config),
+                Err(ConfigError::InvalidVlanTag)
+            );
+        }
+    }
+}
@@ -1,7 +1,16 @@
 //! Configuration passed to [`MainDevice`](crate::MainDevice).
 
+use crate::SubDeviceOverride;
+use core::time::Duration;
+
 /// Configuration passed to [`MainDevice`](crate::MainDevice).
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct MainDeviceConfig {
     /// The number of `FRMW` packets to send during the static phase of Distributed Clocks (DC)
     /// synchronisation.
@@ -13,6 +22,75 @@ pub struct MainDeviceConfig {
 
     /// EtherCAT packet (PDU) network retry behaviour.
     pub retry_behaviour: RetryBehaviour,
+
+    /// Delay between a PDU timing out and it being resent.
+    ///
+    /// Only used if [`retry_behaviour`](MainDeviceConfig::retry_behaviour) allows retries.
+    pub retry_backoff: RetryBackoff,
+
+    /// Whether to configure Distributed Clocks (DC) during initialisation.
+    ///
+    /// If this is `false`, no DC reference clock is chosen, no static drift compensation is
+    /// performed and [`dc_static_sync_iterations`](MainDeviceConfig::dc_static_sync_iterations) is
+    /// ignored.
+    ///
+    /// Defaults to `true`.
+    pub distributed_clocks: bool,
+
+    /// The PDU commands used to exchange process data with SubDevice groups.
+    pub pdi_transfer: PdiTransfer,
+
+    /// Log every EtherCAT frame sent and received by the MainDevice.
+    pub wire_logging: WireLogging,
+
+    /// The number of SubDevices expected to be present on the network.
+    ///
+    /// If this is set, [`MainDevice::init`](crate::MainDevice::init) will return an
+    /// [`Error::SubDeviceCount`](crate::error::Error::SubDeviceCount) if a different number of
+    /// SubDevices is discovered.
+    ///
+    /// Defaults to `None`, allowing any number of SubDevices.
+    pub expected_subdevices: Option<u16>,
+
+    /// Add an IEEE 802.1Q VLAN tag to every sent frame.
+    ///
+    /// Tags are removed from received frames whether or not this is set.
+    ///
+    /// Defaults to `None`, sending untagged frames.
+    pub vlan: Option<VlanTag>,
+
+    /// Send PDUs queued by different tasks in a single Ethernet frame where they fit.
+    ///
+    /// When enabled, the TX task appends other frames that are ready to send to the frame it is
+    /// sending, up to a standard 1514 byte Ethernet frame. For example a group's cyclic `LRW`, a DC
+    /// `FRMW` and a mailbox read issued by another task can share one frame, reducing the number of
+    /// frames sent each cycle. Responses are split back into their original frames when received.
+    ///
+    /// Defaults to `false`, sending every frame as it was queued.
+    pub frame_packing: bool,
+
+    /// Cache SubDevice EEPROM category positions and data during initialisation.
+    ///
+    /// Each configuration phase reads the same parts of the EEPROM, which is slow. When enabled,
+    /// reads are remembered per SubDevice until it has been configured, which can noticeably shorten
+    /// startup times on large networks.
+    ///
+    /// Defaults to `true`.
+    pub eeprom_cache: bool,
+
+    /// Acknowledge and retry SubDevice state transitions that are refused with an error.
+    ///
+    /// Defaults to no retries.
+    pub state_transition_retry: StateTransitionRetry,
+
+    /// Hand-tuned configuration for SubDevices whose EEPROM contents are wrong or incomplete.
+    ///
+    /// See [`SubDeviceOverride`] for details.
+    ///
+    /// Defaults to no overrides. Overrides are not serialized, as they are borrowed from static
+    /// data.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub subdevice_overrides: &'static [SubDeviceOverride],
 }
 
 impl Default for MainDeviceConfig {
@@ -20,14 +98,27 @@ impl Default for MainDeviceConfig {
         Self {
             dc_static_sync_iterations: 10_000,
             retry_behaviour: RetryBehaviour::default(),
+            retry_backoff: RetryBackoff::default(),
+            distributed_clocks: true,
+            pdi_transfer: PdiTransfer::default(),
+            wire_logging: WireLogging::default(),
+            expected_subdevices: None,
+            vlan: None,
+            frame_packing: false,
+            eeprom_cache: true,
+            state_transition_retry: StateTransitionRetry::default(),
+            subdevice_overrides: &[],
         }
     }
 }
 
 /// Network communication retry policy.
 ///
-/// Retries will be performed at the rate defined by [`Timeouts::pdu`](crate::Timeouts::pdu).
+/// Retries will be performed at the rate defined by [`Timeouts::pdu`](crate::Timeouts::pdu), with
+/// an optional delay between retries set by [`MainDeviceConfig::retry_backoff`].
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RetryBehaviour {
     /// Do not attempt to retry timed out packet sends (default).
     ///
@@ -61,6 +152,145 @@ impl RetryBehaviour {
     }
 }
 
+/// Delay to wait before resending a timed out PDU.
+///
+/// The delay is waited after [`Timeouts::pdu`](crate::Timeouts::pdu) has elapsed. A response that
+/// arrives during the delay is still accepted.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RetryBackoff {
+    /// Resend the PDU as soon as it times out (default).
+    #[default]
+    None,
+
+    /// Wait a fixed duration before each resend.
+    Fixed(Duration),
+
+    /// Wait `initial` before the first resend, doubling the delay for each subsequent resend up to
+    /// a maximum of `max`.
+    Exponential {
+        /// Delay before the first resend.
+        initial: Duration,
+        /// Upper bound on the delay between resends.
+        max: Duration,
+    },
+}
+
+impl RetryBackoff {
+    /// Delay before resend number `attempt`, starting from zero.
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        match self {
+            RetryBackoff::None => Duration::ZERO,
+            RetryBackoff::Fixed(delay) => *delay,
+            RetryBackoff::Exponential { initial, max } => initial
+                .checked_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX))
+                .map_or(*max, |delay| delay.min(*max)),
+        }
+    }
+}
+
+/// Retry policy for SubDevice state transitions refused with an error.
+///
+/// Some SubDevices occasionally refuse a state request, e.g. if their application is not ready
+/// yet, and set the error flag in their AL status register. When a transition requested of a single
+/// SubDevice is refused, the error is acknowledged, [`backoff`](StateTransitionRetry::backoff) is
+/// waited, then the transition is requested again.
+///
+/// If the transition is refused more than [`retries`](StateTransitionRetry::retries) times, an
+/// [`Error::StateTransitionRetries`](crate::error::Error::StateTransitionRetries) is returned
+/// containing the state and AL status code read after each attempt. With the default of no retries
+/// the first refusal is returned as an
+/// [`Error::StateTransition`](crate::error::Error::StateTransition).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateTransitionRetry {
+    /// The number of times a refused transition is requested again.
+    ///
+    /// Defaults to 0.
+    pub retries: u8,
+
+    /// Delay between acknowledging a refused transition and requesting it again.
+    ///
+    /// Defaults to [`RetryBackoff::None`].
+    pub backoff: RetryBackoff,
+}
+
+/// The PDU commands used to exchange process data with SubDevice groups.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PdiTransfer {
+    /// Read inputs and write outputs with a single `LRW` (default).
+    #[default]
+    Lrw,
+
+    /// Read inputs with an `LRD` and write outputs with an `LWR`, sent in the same frame.
+    ///
+    /// Some SubDevices or network topologies do not support `LRW`. The working counter returned
+    /// by the process data methods is computed as if an `LRW` was sent, i.e. `LRD` working counter
+    /// plus twice the `LWR` working counter.
+    LrdLwr,
+}
+
+/// Logging of EtherCAT frames sent and received by the MainDevice.
+///
+/// Frames are logged at `debug` level, so the `log` or `defmt` logger must also be configured to
+/// show debug messages.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WireLogging {
+    /// Do not log frames (default).
+    #[default]
+    Off,
+
+    /// Log the index and length of each frame.
+    Summary,
+
+    /// Log the index, length and full PDU payload of each frame.
+    Full,
+
+    /// Log each frame with its PDUs decoded by [`PduDissector`](crate::PduDissector), e.g. the
+    /// command, address, working counter and interpreted payload of well known registers.
+    Dissect,
+}
+
+/// An IEEE 802.1Q VLAN tag.
+///
+/// Some managed networks require the EtherCAT segment to be on a tagged VLAN. The tag is inserted
+/// after the source address of each sent frame.
+///
+/// On Linux it is often simpler to create a VLAN interface, e.g. `eth0.100`, and pass that to the
+/// TX/RX task instead, as the NIC or kernel may strip tags from received frames before they reach
+/// a raw socket.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VlanTag {
+    /// VLAN identifier, from 0 to 4094.
+    ///
+    /// A VLAN ID of 0 only sets the frame priority.
+    pub vid: u16,
+
+    /// Priority code point, from 0 (lowest) to 7 (highest).
+    pub pcp: u8,
+}
+
+impl VlanTag {
+    /// The highest valid VLAN ID. `0xfff` is reserved.
+    pub(crate) const MAX_VID: u16 = 0xffe;
+
+    /// The highest valid priority code point.
+    pub(crate) const MAX_PCP: u8 = 7;
+
+    /// Tag control information: PCP in the upper 3 bits, a zero DEI bit, then the VLAN ID.
+    pub(crate) const fn tci(&self) -> u16 {
+        ((self.pcp as u16 & 0x07) << 13) | (self.vid & 0x0fff)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,4 +301,34 @@ mod tests {
         assert_eq!(RetryBehaviour::Count(10).retry_count(), 10);
         assert_eq!(RetryBehaviour::Forever.retry_count(), usize::MAX);
     }
+
+    #[test]
+    fn exponential_backoff() {
+        let backoff = RetryBackoff::Exponential {
+            initial: Duration::from_millis(1),
+            max: Duration::from_millis(10),
+        };
+
+        assert_eq!(backoff.delay(0), Duration::from_millis(1));
+        assert_eq!(backoff.delay(1), Duration::from_millis(2));
+        assert_eq!(backoff.delay(3), Duration::from_millis(8));
+        assert_eq!(backoff.delay(4), Duration::from_millis(10));
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn vlan_tci() {
+        assert_eq!(VlanTag { vid: 100, pcp: 0 }.tci(), 0x0064);
+        assert_eq!(VlanTag { vid: 0xffe, pcp: 7 }.tci(), 0xeffe);
+        assert_eq!(VlanTag { vid: 1, pcp: 5 }.tci(), 0xa001);
+    }
+
+    #[test]
+    fn fixed_backoff() {
+        let backoff = RetryBackoff::Fixed(Duration::from_millis(5));
+
+        assert_eq!(backoff.delay(0), Duration::from_millis(5));
+        assert_eq!(backoff.delay(100), Duration::from_millis(5));
+        assert_eq!(RetryBackoff::None.delay(100), Duration::ZERO);
+    }
 }
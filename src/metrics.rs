@@ -0,0 +1,497 @@
+//! Export bus health statistics to a metrics system.
+
+use crate::{ConfiguredAddress, ErrorCounters, LatencyHistogram, PortErrors, Statistics};
+use core::time::Duration;
+
+/// The type of a [`Metric`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MetricKind {
+    /// A value that only ever increases, apart from wrapping on overflow.
+    Counter,
+    /// A value that can go up and down.
+    Gauge,
+}
+
+/// The name and description of a metric written to a [`MetricsSink`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Metric {
+    /// Metric name, e.g. `ethercrab_wkc_errors_total`.
+    ///
+    /// Names follow the Prometheus naming conventions: counters end in `_total` and durations
+    /// are given in seconds.
+    pub name: &'static str,
+
+    /// A one line description of the metric.
+    pub help: &'static str,
+
+    /// Whether the metric is a counter or gauge.
+    pub kind: MetricKind,
+}
+
+/// The value of a [`Label`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LabelValue {
+    /// A SubDevice configured station address, displayed as e.g. `0x1001`.
+    Address(ConfiguredAddress),
+    /// A SubDevice port number.
+    Port(u8),
+    /// A quantile between `0.0` and `1.0`.
+    Quantile(f64),
+}
+
+impl core::fmt::Display for LabelValue {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LabelValue::Address(address) => write!(f, "{:#06x}", address),
+            LabelValue::Port(port) => write!(f, "{}", port),
+            LabelValue::Quantile(quantile) => write!(f, "{}", quantile),
+        }
+    }
+}
+
+/// A name/value pair identifying one sample of a [`Metric`], e.g. the SubDevice address.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Label {
+    /// Label name, e.g. `address`.
+    pub name: &'static str,
+
+    /// Label value.
+    pub value: LabelValue,
+}
+
+/// A destination for metrics written by [`MetricsSnapshot::write`].
+///
+/// Implement this trait to forward metrics to a monitoring system. For every metric,
+/// [`describe`](MetricsSink::describe) is called once, followed by one call to
+/// [`sample`](MetricsSink::sample) for each label set. On `std` targets,
+/// [`PrometheusEncoder`](crate::std::PrometheusEncoder) renders metrics in the Prometheus text
+/// exposition format.
+pub trait MetricsSink {
+    /// Called once before the samples of each metric.
+    ///
+    /// Does nothing by default.
+    fn describe(&mut self, metric: &Metric) {
+        let _ = metric;
+    }
+
+    /// Record a single value of `metric`.
+    fn sample(&mut self, metric: &Metric, labels: &[Label], value: f64);
+}
+
+const FRAMES_SENT: Metric = Metric {
+    name: "ethercrab_frames_sent_total",
+    help: "Frames sent over the network, including resent frames.",
+    kind: MetricKind::Counter,
+};
+
+const FRAMES_RECEIVED: Metric = Metric {
+    name: "ethercrab_frames_received_total",
+    help: "EtherCAT frames received from the network.",
+    kind: MetricKind::Counter,
+};
+
+const PDU_RETRIES: Metric = Metric {
+    name: "ethercrab_pdu_retries_total",
+    help: "Timed out frames that were resent.",
+    kind: MetricKind::Counter,
+};
+
+const PDU_TIMEOUTS: Metric = Metric {
+    name: "ethercrab_pdu_timeouts_total",
+    help: "Frames that timed out with no retries remaining.",
+    kind: MetricKind::Counter,
+};
+
+const WKC_ERRORS: Metric = Metric {
+    name: "ethercrab_wkc_errors_total",
+    help: "PDU responses with an unexpected working counter.",
+    kind: MetricKind::Counter,
+};
+
+const MAILBOX_ERRORS: Metric = Metric {
+    name: "ethercrab_mailbox_errors_total",
+    help: "Mailbox responses indicating an error.",
+    kind: MetricKind::Counter,
+};
+
+const STATE_TRANSITION_FAILURES: Metric = Metric {
+    name: "ethercrab_state_transition_failures_total",
+    help: "SubDevice state transitions that failed with an error reported by the SubDevice.",
+    kind: MetricKind::Counter,
+};
+
+const IGNORED_RESPONSES: Metric = Metric {
+    name: "ethercrab_ignored_responses_total",
+    help: "Received PDUs with no frame waiting for them.",
+    kind: MetricKind::Counter,
+};
+
+const ROUND_TRIPS: Metric = Metric {
+    name: "ethercrab_round_trips_total",
+    help: "Frame round trips recorded in the latency histogram.",
+    kind: MetricKind::Counter,
+};
+
+const ROUND_TRIP: Metric = Metric {
+    name: "ethercrab_round_trip_seconds",
+    help: "Frame round trip time quantiles.",
+    kind: MetricKind::Gauge,
+};
+
+const ROUND_TRIP_MIN: Metric = Metric {
+    name: "ethercrab_round_trip_min_seconds",
+    help: "Shortest frame round trip time.",
+    kind: MetricKind::Gauge,
+};
+
+const ROUND_TRIP_MAX: Metric = Metric {
+    name: "ethercrab_round_trip_max_seconds",
+    help: "Longest frame round trip time.",
+    kind: MetricKind::Gauge,
+};
+
+const ROUND_TRIP_JITTER: Metric = Metric {
+    name: "ethercrab_round_trip_jitter_seconds",
+    help: "Difference between the longest and shortest frame round trip time.",
+    kind: MetricKind::Gauge,
+};
+
+const PORT_INVALID_FRAMES: Metric = Metric {
+    name: "ethercrab_port_invalid_frames",
+    help: "Frames with an invalid format received on a SubDevice port.",
+    kind: MetricKind::Gauge,
+};
+
+const PORT_RX_ERRORS: Metric = Metric {
+    name: "ethercrab_port_rx_errors",
+    help: "Physical layer RX errors on a SubDevice port.",
+    kind: MetricKind::Gauge,
+};
+
+const PORT_FORWARDED_RX_ERRORS: Metric = Metric {
+    name: "ethercrab_port_forwarded_rx_errors",
+    help: "Frames received on a SubDevice port already marked invalid by a previous SubDevice.",
+    kind: MetricKind::Gauge,
+};
+
+const PORT_LOST_LINKS: Metric = Metric {
+    name: "ethercrab_port_lost_links",
+    help: "Times the link on a SubDevice port was lost.",
+    kind: MetricKind::Gauge,
+};
+
+const PROCESSING_UNIT_ERRORS: Metric = Metric {
+    name: "ethercrab_processing_unit_errors",
+    help: "Frames that could not be processed by a SubDevice ESC.",
+    kind: MetricKind::Gauge,
+};
+
+const PDI_ERRORS: Metric = Metric {
+    name: "ethercrab_pdi_errors",
+    help: "Errors on the process data interface of a SubDevice.",
+    kind: MetricKind::Gauge,
+};
+
+/// Reads one error counter of a SubDevice port.
+type PortField = fn(&PortErrors) -> u8;
+
+/// Reads one error counter of a SubDevice.
+type SubDeviceField = fn(&ErrorCounters) -> u8;
+
+/// Quantiles of the round trip time histogram written as [`LabelValue::Quantile`].
+const QUANTILES: [f64; 4] = [0.5, 0.9, 0.99, 0.999];
+
+/// A snapshot of bus health statistics to be written to a [`MetricsSink`].
+///
+/// A snapshot is collected with [`MainDevice::metrics`](crate::MainDevice::metrics) or
+/// [`Diagnostics::metrics`](crate::Diagnostics::metrics). The counters in
+/// [`ErrorCounters`] saturate instead of wrapping so are exported as gauges, labelled with the
+/// SubDevice address and port number.
+///
+/// # Examples
+///
+/// ```rust
+/// use ethercrab::{Label, Metric, MetricsSink, MetricsSnapshot, Statistics};
+///
+/// struct LogSink;
+///
+/// impl MetricsSink for LogSink {
+///     fn sample(&mut self, metric: &Metric, labels: &[Label], value: f64) {
+///         log::info!("{} {:?} = {}", metric.name, labels, value);
+///     }
+/// }
+///
+/// let snapshot = MetricsSnapshot::<16> {
+///     statistics: Statistics::default(),
+///     latency: None,
+///     error_counters: heapless::Vec::new(),
+/// };
+///
+/// snapshot.write(&mut LogSink);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MetricsSnapshot<const MAX_SUBDEVICES: usize> {
+    /// Network communication statistics.
+    pub statistics: Statistics,
+
+    /// PDU round trip time histogram, used to export round trip time quantiles and jitter, or
+    /// `None` to leave out round trip metrics.
+    pub latency: Option<LatencyHistogram>,
+
+    /// Per-port error counters of every SubDevice.
+    pub error_counters: heapless::Vec<ErrorCounters, MAX_SUBDEVICES>,
+}
+
+impl<const MAX_SUBDEVICES: usize> MetricsSnapshot<MAX_SUBDEVICES> {
+    /// Write every metric in this snapshot to `sink`.
+    pub fn write(&self, sink: &mut impl MetricsSink) {
+        let statistics = &self.statistics;
+
+        for (metric, value) in [
+            (FRAMES_SENT, statistics.frames_sent),
+            (FRAMES_RECEIVED, statistics.frames_received),
+            (PDU_RETRIES, statistics.pdu_retries),
+            (PDU_TIMEOUTS, statistics.pdu_timeouts),
+            (WKC_ERRORS, statistics.wkc_errors),
+            (MAILBOX_ERRORS, statistics.mailbox_errors),
+            (
+                STATE_TRANSITION_FAILURES,
+                statistics.state_transition_failures,
+            ),
+            (IGNORED_RESPONSES, statistics.ignored_responses),
+        ] {
+            sink.describe(&metric);
+            sink.sample(&metric, &[], f64::from(value));
+        }
+
+        if let Some(latency) = &self.latency {
+            Self::write_latency(latency, sink);
+        }
+
+        let port_metrics: [(Metric, PortField); 4] = [
+            (PORT_INVALID_FRAMES, |port| port.invalid_frames),
+            (PORT_RX_ERRORS, |port| port.rx_errors),
+            (PORT_FORWARDED_RX_ERRORS, |port| port.forwarded_rx_errors),
+            (PORT_LOST_LINKS, |port| port.lost_links),
+        ];
+
+        for (metric, field) in port_metrics {
+            sink.describe(&metric);
+
+            for counters in self.error_counters.iter() {
+                for (port, errors) in counters.ports.iter().enumerate() {
+                    let labels = [
+                        Label {
+                            name: "address",
+                            value: LabelValue::Address(counters.configured_address),
+                        },
+                        Label {
+                            name: "port",
+                            value: LabelValue::Port(port as u8),
+                        },
+                    ];
+
+                    sink.sample(&metric, &labels, f64::from(field(errors)));
+                }
+            }
+        }
+
+        let subdevice_metrics: [(Metric, SubDeviceField); 2] = [
+            (PROCESSING_UNIT_ERRORS, |counters| {
+                counters.processing_unit_errors
+            }),
+            (PDI_ERRORS, |counters| counters.pdi_errors),
+        ];
+
+        for (metric, field) in subdevice_metrics {
+            sink.describe(&metric);
+
+            for counters in self.error_counters.iter() {
+                let labels = [Label {
+                    name: "address",
+                    value: LabelValue::Address(counters.configured_address),
+                }];
+
+                sink.sample(&metric, &labels, f64::from(field(counters)));
+            }
+        }
+    }
+
+    fn write_latency(latency: &LatencyHistogram, sink: &mut impl MetricsSink) {
+        sink.describe(&ROUND_TRIPS);
+        sink.sample(&ROUND_TRIPS, &[], latency.len() as f64);
+
+        // Nothing meaningful to report until the first round trip
+        let (Some(min), Some(max)) = (latency.min(), latency.max()) else {
+            return;
+        };
+
+        sink.describe(&ROUND_TRIP);
+
+        for quantile in QUANTILES {
+            if let Some(value) = latency.percentile(quantile * 100.0) {
+                let labels = [Label {
+                    name: "quantile",
+                    value: LabelValue::Quantile(quantile),
+                }];
+
+                sink.sample(&ROUND_TRIP, &labels, value.as_secs_f64());
+            }
+        }
+
+        for (metric, value) in [
+            (ROUND_TRIP_MIN, min),
+            (ROUND_TRIP_MAX, max),
+            (ROUND_TRIP_JITTER, max.saturating_sub(min)),
+        ] {
+            sink.describe(&metric);
+            sink.sample(&metric, &[], Duration::as_secs_f64(&value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        described: Vec<&'static str>,
+        samples: Vec<(&'static str, Vec<Label>, f64)>,
+    }
+
+    impl MetricsSink for Recorder {
+        fn describe(&mut self, metric: &Metric) {
+            self.described.push(metric.name);
+        }
+
+        fn sample(&mut self, metric: &Metric, labels: &[Label], value: f64) {
+            self.samples.push((metric.name, labels.to_vec(), value));
+        }
+    }
+
+    #[test]
+    fn label_display() {
+        assert_eq!(
+            LabelValue::Address(ConfiguredAddress::new(0x1001)).to_string(),
+            "0x1001"
+        );
+        assert_eq!(LabelValue::Port(3).to_string(), "3");
+        assert_eq!(LabelValue::Quantile(0.99).to_string(), "0.99");
+    }
+
+    #[test]
+    fn write_snapshot() {
+        let mut error_counters = heapless::Vec::new();
+
+        error_counters
+            .push(ErrorCounters {
+                configured_address: ConfiguredAddress::new(0x1000),
+                ports: [
+                    PortErrors {
+                        rx_errors: 3,
+                        ..PortErrors::default()
+                    },
+                    PortErrors::default(),
+                    PortErrors::default(),
+                    PortErrors::default(),
+                ],
+                processing_unit_errors: 0,
+                pdi_errors: 1,
+            })
+            .unwrap();
+
+        let snapshot = MetricsSnapshot::<4> {
+            statistics: Statistics {
+                frames_sent: 10,
+                wkc_errors: 2,
+                ..Statistics::default()
+            },
+            latency: None,
+            error_counters,
+        };
+
+        let mut recorder = Recorder::default();
+
+        snapshot.write(&mut recorder);
+
+        // Every metric is described once, even with no samples
+        assert_eq!(recorder.described.len(), 14);
+        assert!(!recorder.described.contains(&ROUND_TRIPS.name));
+
+        assert!(recorder
+            .samples
+            .contains(&(FRAMES_SENT.name, Vec::new(), 10.0)));
+        assert!(recorder
+            .samples
+            .contains(&(WKC_ERRORS.name, Vec::new(), 2.0)));
+        assert!(recorder.samples.contains(&(
+            PORT_RX_ERRORS.name,
+            vec![
+                Label {
+                    name: "address",
+                    value: LabelValue::Address(ConfiguredAddress::new(0x1000))
+                },
+                Label {
+                    name: "port",
+                    value: LabelValue::Port(0)
+                }
+            ],
+            3.0
+        )));
+        assert!(recorder.samples.contains(&(
+            PDI_ERRORS.name,
+            vec![Label {
+                name: "address",
+                value: LabelValue::Address(ConfiguredAddress::new(0x1000))
+            }],
+            1.0
+        )));
+
+        // 8 statistics, 4 port metrics for 4 ports, 2 SubDevice metrics
+        assert_eq!(recorder.samples.len(), 8 + 4 * 4 + 2);
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{sim::fixture::*, PduStorage};
+
+    #[test]
+    fn metrics() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            net.with_subdevice(1, |subdevice| subdevice.rx_error(0));
+
+            let snapshot = maindevice.diagnostics().metrics::<8>().await.expect("read");
+
+            assert_eq!(snapshot.error_counters.len(), 4);
+            assert!(snapshot.latency.is_some_and(|latency| !latency.is_empty()));
+
+            let mut encoder = crate::std::PrometheusEncoder::new();
+
+            snapshot.write(&mut encoder);
+
+            let text = encoder.finish();
+
+            assert!(text.contains("ethercrab_port_rx_errors{address=\"0x1001\",port=\"0\"} 1\n"));
+            assert!(text.contains("ethercrab_round_trip_seconds{quantile=\"0.99\"} "));
+            assert!(text.contains("# TYPE ethercrab_round_trip_jitter_seconds gauge\n"));
+        });
+    }
+}
@@ -0,0 +1,432 @@
+//! Passively decode EtherCAT traffic sent by another MainDevice.
+
+use crate::{
+    error::{Error, PduError},
+    ethernet::EthernetFrame,
+    fmt,
+    pdu_loop::{frame_header::EthercatFrameHeader, pdu_header::PduHeader},
+    Command, ConfiguredAddress, FrameDirection, LogicalAddress, Reads, RegisterAddress,
+    SubDeviceState, Writes, ETHERCAT_ETHERTYPE,
+};
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
+use heapless::FnvIndexMap;
+
+/// Something observed on the network by a [`Monitor`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MonitorEvent<'frame> {
+    /// A PDU was seen in a frame.
+    ///
+    /// This event is emitted for every PDU in both directions. Other events derived from the PDU
+    /// follow it.
+    Pdu {
+        /// Whether the PDU was sent by the MainDevice or returned by the SubDevices.
+        direction: FrameDirection,
+        /// The PDU command and address.
+        command: Command,
+        /// PDU payload.
+        data: &'frame [u8],
+        /// PDU working counter. Always zero for PDUs sent by the MainDevice.
+        working_counter: u16,
+    },
+
+    /// Process data was returned from a logical read or write, e.g. a group `LRW`.
+    ///
+    /// The data contains both inputs and outputs in the layout of the process data image.
+    ProcessData {
+        /// `LRW`, `LRD` or `LWR` command.
+        command: Command,
+        /// Logical start address.
+        address: LogicalAddress,
+        /// Process data image.
+        data: &'frame [u8],
+        /// Working counter returned by the SubDevices.
+        working_counter: u16,
+    },
+
+    /// The MainDevice requested a state change by writing to the AL control register.
+    StateRequested {
+        /// Configured station address, or `None` for a broadcast write.
+        address: Option<ConfiguredAddress>,
+        /// Requested state.
+        state: SubDeviceState,
+    },
+
+    /// A SubDevice's AL status read by the MainDevice differs from the previous read.
+    StateChanged {
+        /// SubDevice configured station address.
+        address: ConfiguredAddress,
+        /// Last known state, or [`SubDeviceState::None`] if this is the first time the state of
+        /// this SubDevice was seen.
+        previous: SubDeviceState,
+        /// New state.
+        current: SubDeviceState,
+    },
+}
+
+/// Listen-only decoder for frames captured from a mirror port or network tap.
+///
+/// A `Monitor` never sends anything. Each captured Ethernet frame is passed to
+/// [`process_frame`](Monitor::process_frame), which reports every PDU, process data exchange and
+/// AL state change it contains as [`MonitorEvent`]s. This allows a second EtherCrab instance to
+/// drive a monitoring dashboard for a network controlled by any other MainDevice.
+///
+/// Frames are classed as returned by the SubDevices if their source MAC address has the locally
+/// administered bit set, as SubDevices set this bit in every frame they forward.
+///
+/// State changes are tracked by configured station address for up to `MAX_SUBDEVICES` SubDevices,
+/// which must be a power of two. SubDevice states are only updated when the MainDevice reads their
+/// AL status register with `FPRD`.
+///
+/// With the `std` feature enabled on Unix, `std::monitor_task` captures frames from a network
+/// interface.
+///
+/// # Examples
+///
+/// ```rust
+/// use ethercrab::{Monitor, MonitorEvent};
+///
+/// let mut monitor = Monitor::<16>::new();
+///
+/// # let captured_frame = [0u8; 64];
+/// monitor
+///     .process_frame(&captured_frame, |event| {
+///         if let MonitorEvent::StateChanged { address, current, .. } = event {
+///             println!("SubDevice {:#06x} is now {}", address, current);
+///         }
+///     })
+///     .ok();
+/// ```
+#[derive(Debug)]
+pub struct Monitor<const MAX_SUBDEVICES: usize> {
+    states: FnvIndexMap<ConfiguredAddress, SubDeviceState, MAX_SUBDEVICES>,
+}
+
+impl<const MAX_SUBDEVICES: usize> Default for Monitor<MAX_SUBDEVICES> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const MAX_SUBDEVICES: usize> Monitor<MAX_SUBDEVICES> {
+    /// Create a new monitor with no known SubDevices.
+    pub const fn new() -> Self {
+        Self {
+            states: FnvIndexMap::new(),
+        }
+    }
+
+    /// The last known state of the SubDevice with the given configured station address.
+    pub fn state(&self, address: ConfiguredAddress) -> Option<SubDeviceState> {
+        self.states.get(&address).copied()
+    }
+
+    /// Decode a complete Ethernet II frame, calling `on_event` for everything observed in it.
+    ///
+    /// Frames that aren't EtherCAT frames are ignored.
+    pub fn process_frame(
+        &mut self,
+        ethernet_frame: &[u8],
+        mut on_event: impl FnMut(MonitorEvent<'_>),
+    ) -> Result<(), Error> {
+        let frame = EthernetFrame::new_checked(ethernet_frame)?;
+
+        if frame.inner_ethertype() != ETHERCAT_ETHERTYPE {
+            return Ok(());
+        }
+
+        let direction = if frame.src_addr().0[0] & 0x02 != 0 {
+            FrameDirection::Received
+        } else {
+            FrameDirection::Sent
+        };
+
+        let payload = frame.inner_payload();
+
+        let header = EthercatFrameHeader::unpack_from_slice(payload)?;
+
+        let mut pdus = payload
+            .get(
+                EthercatFrameHeader::PACKED_LEN
+                    ..(EthercatFrameHeader::PACKED_LEN + usize::from(header.payload_len)),
+            )
+            .ok_or(Error::Pdu(PduError::Decode))?;
+
+        while !pdus.is_empty() {
+            let pdu_header = PduHeader::unpack_from_slice(pdus)?;
+
+            let data_len = usize::from(pdu_header.flags.len());
+
+            let data = pdus
+                .get(PduHeader::PACKED_LEN..(PduHeader::PACKED_LEN + data_len))
+                .ok_or(Error::Pdu(PduError::TooLong))?;
+
+            let working_counter = pdus
+                .get((PduHeader::PACKED_LEN + data_len)..)
+                .ok_or(Error::Pdu(PduError::TooLong))
+                .and_then(|rest| u16::unpack_from_slice(rest).map_err(Error::from))?;
+
+            match Command::parse(pdu_header.command_code, pdu_header.command_raw) {
+                Some(command) => {
+                    on_event(MonitorEvent::Pdu {
+                        direction,
+                        command,
+                        data,
+                        working_counter,
+                    });
+
+                    self.decode(direction, command, data, working_counter, &mut on_event);
+                }
+                None => {
+                    fmt::trace!("Ignoring PDU with command {:#04x}", pdu_header.command_code);
+                }
+            }
+
+            if !pdu_header.flags.more_follows {
+                break;
+            }
+
+            pdus = pdus
+                .get((PduHeader::PACKED_LEN + data_len + 2)..)
+                .unwrap_or_default();
+        }
+
+        Ok(())
+    }
+
+    /// Emit events derived from a single PDU.
+    fn decode(
+        &mut self,
+        direction: FrameDirection,
+        command: Command,
+        data: &[u8],
+        working_counter: u16,
+        on_event: &mut impl FnMut(MonitorEvent<'_>),
+    ) {
+        let al_state = || {
+            data.first()
+                .and_then(|state| SubDeviceState::unpack_from_slice(&[state & 0x0f]).ok())
+        };
+
+        match (direction, command) {
+            (
+                FrameDirection::Received,
+                Command::Read(Reads::Lrd { address })
+                | Command::Write(Writes::Lwr { address } | Writes::Lrw { address }),
+            ) => on_event(MonitorEvent::ProcessData {
+                command,
+                address: LogicalAddress::new(address),
+                data,
+                working_counter,
+            }),
+
+            (FrameDirection::Sent, Command::Write(Writes::Fpwr { address, register }))
+                if register == u16::from(RegisterAddress::AlControl) =>
+            {
+                if let Some(state) = al_state() {
+                    on_event(MonitorEvent::StateRequested {
+                        address: Some(ConfiguredAddress::new(address)),
+                        state,
+                    });
+                }
+            }
+
+            (FrameDirection::Sent, Command::Write(Writes::Bwr { register, .. }))
+                if register == u16::from(RegisterAddress::AlControl) =>
+            {
+                if let Some(state) = al_state() {
+                    on_event(MonitorEvent::StateRequested {
+                        address: None,
+                        state,
+                    });
+                }
+            }
+
+            (FrameDirection::Received, Command::Read(Reads::Fprd { address, register }))
+                if register == u16::from(RegisterAddress::AlStatus) && working_counter == 1 =>
+            {
+                let Some(current) = al_state() else {
+                    return;
+                };
+
+                let address = ConfiguredAddress::new(address);
+
+                let previous = self.state(address).unwrap_or(SubDeviceState::None);
+
+                if previous == current {
+                    return;
+                }
+
+                if self.states.insert(address, current).is_err() {
+                    fmt::warn!(
+                        "Monitor can only track {} SubDevices, ignoring {:#06x}",
+                        MAX_SUBDEVICES,
+                        address
+                    );
+
+                    return;
+                }
+
+                on_event(MonitorEvent::StateChanged {
+                    address,
+                    previous,
+                    current,
+                });
+            }
+
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ethernet::EthernetAddress, MASTER_ADDR};
+    use ethercrab_wire::EtherCrabWireWrite;
+
+    /// Build a single PDU frame, as it would appear on the wire.
+    fn frame(
+        direction: FrameDirection,
+        command: Command,
+        data: &[u8],
+        working_counter: u16,
+    ) -> Vec<u8> {
+        let mut buf = vec![0u8; 14 + 2 + PduHeader::PACKED_LEN + data.len() + 2];
+
+        let mut ethernet = EthernetFrame::new_unchecked(&mut buf);
+
+        ethernet.set_dst_addr(EthernetAddress::BROADCAST);
+        ethernet.set_src_addr(match direction {
+            FrameDirection::Sent => MASTER_ADDR,
+            FrameDirection::Received => EthernetAddress([0x12, 0x10, 0x10, 0x10, 0x10, 0x10]),
+        });
+        ethernet.set_ethertype(ETHERCAT_ETHERTYPE);
+
+        let payload = ethernet.payload_mut();
+
+        EthercatFrameHeader::pdu((PduHeader::PACKED_LEN + data.len() + 2) as u16)
+            .pack_to_slice_unchecked(payload);
+
+        payload[2] = command.code();
+        payload[4..8].copy_from_slice(&ethercrab_wire::EtherCrabWireWriteSized::pack(&command));
+        payload[8..10].copy_from_slice(&(data.len() as u16).to_le_bytes());
+
+        payload[12..(12 + data.len())].copy_from_slice(data);
+        payload[(12 + data.len())..].copy_from_slice(&working_counter.to_le_bytes());
+
+        buf
+    }
+
+    fn events(monitor: &mut Monitor<4>, frame: &[u8]) -> Vec<String> {
+        let mut events = Vec::new();
+
+        monitor
+            .process_frame(frame, |event| events.push(format!("{:?}", event)))
+            .expect("process frame");
+
+        events
+    }
+
+    #[test]
+    fn process_data() {
+        let mut monitor = Monitor::<4>::new();
+
+        let lrw = frame(
+            FrameDirection::Received,
+            Command::lrw(LogicalAddress::new(0x1000)).into(),
+            &[0xaa, 0xbb],
+            3,
+        );
+
+        let mut seen = None;
+
+        monitor
+            .process_frame(&lrw, |event| {
+                if let MonitorEvent::ProcessData {
+                    address,
+                    data,
+                    working_counter,
+                    ..
+                } = event
+                {
+                    seen = Some((address, data.to_vec(), working_counter));
+                }
+            })
+            .unwrap();
+
+        assert_eq!(
+            seen,
+            Some((LogicalAddress::new(0x1000), vec![0xaa, 0xbb], 3))
+        );
+    }
+
+    #[test]
+    fn state_changes() {
+        let mut monitor = Monitor::<4>::new();
+
+        let request = frame(
+            FrameDirection::Sent,
+            Command::fpwr(
+                ConfiguredAddress::new(0x1001),
+                RegisterAddress::AlControl.into(),
+            )
+            .into(),
+            &[0x08, 0x00],
+            0,
+        );
+
+        assert_eq!(events(&mut monitor, &request).len(), 2);
+
+        let status = |state: u8| {
+            frame(
+                FrameDirection::Received,
+                Command::fprd(
+                    ConfiguredAddress::new(0x1001),
+                    RegisterAddress::AlStatus.into(),
+                )
+                .into(),
+                &[state, 0x00],
+                1,
+            )
+        };
+
+        let mut changes = Vec::new();
+
+        for state in [0x04, 0x04, 0x08] {
+            monitor
+                .process_frame(&status(state), |event| {
+                    if let MonitorEvent::StateChanged {
+                        previous, current, ..
+                    } = event
+                    {
+                        changes.push((previous, current));
+                    }
+                })
+                .unwrap();
+        }
+
+        assert_eq!(
+            changes,
+            [
+                (SubDeviceState::None, SubDeviceState::SafeOp),
+                (SubDeviceState::SafeOp, SubDeviceState::Op)
+            ]
+        );
+        assert_eq!(
+            monitor.state(ConfiguredAddress::new(0x1001)),
+            Some(SubDeviceState::Op)
+        );
+    }
+
+    #[test]
+    fn ignore_other_traffic() {
+        let mut monitor = Monitor::<4>::new();
+
+        let mut arp = vec![0u8; 60];
+
+        EthernetFrame::new_unchecked(&mut arp).set_ethertype(0x0806);
+
+        assert!(events(&mut monitor, &arp).is_empty());
+    }
+}
@@ -0,0 +1,762 @@
+//! A port-level map of how SubDevices are connected to each other.
+
+use crate::{
+    dl_status::DlStatus,
+    error::{Error, Item},
+    register::RegisterAddress,
+    Command, ConfiguredAddress, MainDevice, SubDevice, SubDeviceIdentity, Topology,
+};
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
+
+/// EtherCAT port processing order.
+const PORT_ORDER: [u8; 4] = [0, 3, 1, 2];
+
+/// What a single port of a SubDevice is connected to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PortLink {
+    /// No link is detected on this port.
+    Closed,
+    /// The port EtherCAT frames enter the SubDevice through, connected towards the MainDevice.
+    Upstream,
+    /// The port is connected to the SubDevice with the given index.
+    Downstream(u16),
+    /// The port has a link, but no SubDevice was found behind it.
+    ///
+    /// This can be caused by a SubDevice that is not responding, or a cable that is connected to a
+    /// device that isn't an EtherCAT SubDevice.
+    Open,
+}
+
+impl PortLink {
+    /// Returns `true` if a link was detected on this port.
+    pub fn is_open(&self) -> bool {
+        *self != Self::Closed
+    }
+}
+
+/// A single SubDevice in a [`NetworkTopology`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TopologyNode {
+    /// The position of the SubDevice in the network, starting at `0` for the SubDevice closest to
+    /// the MainDevice.
+    pub index: u16,
+
+    /// The SubDevice's configured station address.
+    pub configured_address: ConfiguredAddress,
+
+    /// The SubDevice's identity.
+    pub identity: SubDeviceIdentity,
+
+    /// The topology of this SubDevice, determined by its number of open ports.
+    pub topology: Topology,
+
+    /// The index of the SubDevice this SubDevice is connected to, or `None` if it is connected
+    /// directly to the MainDevice.
+    pub parent_index: Option<u16>,
+
+    /// The port number of the parent SubDevice this SubDevice is connected to.
+    pub parent_port: Option<u8>,
+
+    /// What each port is connected to, indexed by port number.
+    pub ports: [PortLink; 4],
+
+    /// Propagation delay from the first SubDevice in the network in nanoseconds.
+    ///
+    /// This is `0` for SubDevices that don't support Distributed Clocks.
+    pub propagation_delay: u32,
+}
+
+impl TopologyNode {
+    /// The port number EtherCAT frames enter this SubDevice through.
+    pub fn entry_port(&self) -> Option<u8> {
+        self.ports
+            .iter()
+            .position(|link| *link == PortLink::Upstream)
+            .map(|port| port as u8)
+    }
+
+    /// Iterate over the ports connected to other SubDevices as `(port number, SubDevice index)`
+    /// pairs, in the order EtherCAT frames are forwarded through them.
+    pub fn downstream(&self) -> impl Iterator<Item = (u8, u16)> + '_ {
+        PORT_ORDER
+            .into_iter()
+            .filter_map(|port| match self.ports[usize::from(port)] {
+                PortLink::Downstream(index) => Some((port, index)),
+                _ => None,
+            })
+    }
+}
+
+/// A point in the network where SubDevices that were present in an earlier [`NetworkTopology`]
+/// can no longer be reached.
+///
+/// Returned by [`NetworkTopology::locate_breaks`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkBreak {
+    /// The index of the last reachable SubDevice before the break, or `None` if the break is
+    /// between the MainDevice and the first SubDevice.
+    pub upstream_index: Option<u16>,
+
+    /// The port of the upstream SubDevice the missing SubDevice was connected to.
+    pub upstream_port: Option<u8>,
+
+    /// Whether the upstream SubDevice still detects a physical link on
+    /// [`upstream_port`](LinkBreak::upstream_port), or `None` if this is not known.
+    ///
+    /// A link with no reachable SubDevice behind it points to a SubDevice that has lost power or
+    /// stopped forwarding frames, rather than a disconnected cable.
+    pub upstream_link: Option<bool>,
+
+    /// The index of the first SubDevice behind the break.
+    pub downstream_index: u16,
+
+    /// The port of the first SubDevice behind the break that was connected to the upstream
+    /// SubDevice.
+    pub downstream_port: Option<u8>,
+
+    /// The number of SubDevices that can no longer be reached through this link.
+    pub lost: usize,
+}
+
+impl core::fmt::Display for LinkBreak {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("link lost between ")?;
+
+        match self.upstream_index {
+            Some(index) => write!(f, "SubDevice {}", index)?,
+            None => f.write_str("MainDevice")?,
+        }
+
+        if let Some(port) = self.upstream_port {
+            write!(f, " port {}", port)?;
+        }
+
+        write!(f, " and SubDevice {}", self.downstream_index)?;
+
+        if let Some(port) = self.downstream_port {
+            write!(f, " port {}", port)?;
+        }
+
+        write!(f, ", {} SubDevice(s) unreachable", self.lost)
+    }
+}
+
+/// A map of which port of which SubDevice is connected to which neighbour.
+///
+/// The map is built from the DL status, port and DC receive time information gathered during
+/// [`MainDevice::init`](crate::MainDevice::init) or [`MainDevice::scan`](crate::MainDevice::scan),
+/// and includes branches created by junctions like the EK1100 or EK1122.
+///
+/// A topology captured while the network is healthy can later be compared with the current
+/// network using [`locate_breaks`](NetworkTopology::locate_breaks) to find broken cables.
+///
+/// # Examples
+///
+/// Print the network as a tree:
+///
+/// ```rust,no_run
+/// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+/// # static PDU_STORAGE: PduStorage<2, { PduStorage::element_size(32) }> = PduStorage::new();
+/// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+/// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+/// # async {
+/// let topology = maindevice.topology::<16>().await?;
+///
+/// for node in topology.nodes() {
+///     let depth = topology.path_to(node.index).count() - 1;
+///
+///     log::info!(
+///         "{:indent$}{:#06x} via port {:?}, {:?}",
+///         "",
+///         node.configured_address,
+///         node.parent_port,
+///         node.topology,
+///         indent = depth * 2
+///     );
+/// }
+/// # Ok::<(), ethercrab::error::Error>(())
+/// # };
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetworkTopology<const MAX_SUBDEVICES: usize> {
+    nodes: heapless::Vec<TopologyNode, MAX_SUBDEVICES>,
+}
+
+impl<const MAX_SUBDEVICES: usize> NetworkTopology<MAX_SUBDEVICES> {
+    /// Build a topology map from SubDevices in network order, e.g. as returned by
+    /// [`MainDevice::scan`](crate::MainDevice::scan).
+    ///
+    /// This method will return [`Error::Capacity`] if there are more than `MAX_SUBDEVICES`
+    /// SubDevices.
+    pub fn new(subdevices: &[SubDevice]) -> Result<Self, Error> {
+        let mut nodes = heapless::Vec::new();
+
+        for subdevice in subdevices {
+            let open_ports = || subdevice.ports.0.iter().filter(|port| port.active);
+
+            // A SubDevice that responded must have at least one open port
+            let entry_port = open_ports()
+                .min_by_key(|port| port.dc_receive_time)
+                .map(|port| port.number);
+
+            let mut ports = [PortLink::Closed; 4];
+
+            for port in open_ports() {
+                ports[usize::from(port.number)] = match port.downstream_to {
+                    _ if Some(port.number) == entry_port => PortLink::Upstream,
+                    Some(index) => PortLink::Downstream(index),
+                    None => PortLink::Open,
+                };
+            }
+
+            let parent_port = subdevice
+                .parent_index
+                .and_then(|parent_index| {
+                    subdevices
+                        .iter()
+                        .find(|parent| parent.index == parent_index)
+                })
+                .and_then(|parent| parent.ports.port_assigned_to(subdevice))
+                .map(|port| port.number);
+
+            nodes
+                .push(TopologyNode {
+                    index: subdevice.index,
+                    configured_address: subdevice.configured_address,
+                    identity: subdevice.identity,
+                    topology: subdevice.ports.topology(),
+                    parent_index: subdevice.parent_index,
+                    parent_port,
+                    ports,
+                    propagation_delay: subdevice.propagation_delay,
+                })
+                .map_err(|_| Error::Capacity(Item::SubDevice))?;
+        }
+
+        Ok(Self { nodes })
+    }
+
+    /// All SubDevices in network order.
+    pub fn nodes(&self) -> &[TopologyNode] {
+        &self.nodes
+    }
+
+    /// Get the SubDevice with the given index.
+    pub fn get(&self, index: u16) -> Option<&TopologyNode> {
+        self.nodes.iter().find(|node| node.index == index)
+    }
+
+    /// Get the SubDevice the SubDevice with the given index is connected to.
+    ///
+    /// Returns `None` if the SubDevice is connected directly to the MainDevice or doesn't exist.
+    pub fn parent(&self, index: u16) -> Option<&TopologyNode> {
+        self.get(index)
+            .and_then(|node| node.parent_index)
+            .and_then(|parent_index| self.get(parent_index))
+    }
+
+    /// Iterate over the SubDevices connected directly to the downstream ports of the SubDevice
+    /// with the given index, in the order EtherCAT frames are forwarded to them.
+    pub fn children(&self, index: u16) -> impl Iterator<Item = &TopologyNode> + '_ {
+        self.get(index)
+            .into_iter()
+            .flat_map(|node| node.downstream())
+            .filter_map(|(_port, child)| self.get(child))
+    }
+
+    /// Iterate from the SubDevice with the given index back up to the first SubDevice in the
+    /// network.
+    ///
+    /// The first item is the SubDevice itself. The iterator is empty if the SubDevice doesn't
+    /// exist.
+    pub fn path_to(&self, index: u16) -> impl Iterator<Item = &TopologyNode> + '_ {
+        // Limit iterations so a malformed tree can't loop forever
+        core::iter::successors(self.get(index), |node| {
+            node.parent_index.and_then(|parent| self.get(parent))
+        })
+        .take(self.nodes.len())
+    }
+
+    /// Iterate over every SubDevice reached through `port` of the SubDevice with the given index.
+    ///
+    /// These are the SubDevices that would be lost if the cable plugged into that port broke.
+    pub fn behind(&self, index: u16, port: u8) -> impl Iterator<Item = &TopologyNode> + '_ {
+        self.nodes.iter().filter(move |node| {
+            // Find the ancestor of this node, if any, that is directly connected to the given port
+            self.path_to(node.index).any(|ancestor| {
+                ancestor.parent_index == Some(index) && ancestor.parent_port == Some(port)
+            })
+        })
+    }
+
+    /// Compare this topology with the `current` state of the network to find where SubDevices
+    /// have been disconnected.
+    ///
+    /// SubDevices are matched by their position in the tree, i.e. the port of their parent they
+    /// are connected to. Each returned [`LinkBreak`] is the first link along a branch that is no
+    /// longer present. SubDevices behind a break are not reported separately, but are counted in
+    /// [`LinkBreak::lost`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+    /// # static PDU_STORAGE: PduStorage<2, { PduStorage::element_size(32) }> = PduStorage::new();
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # async {
+    /// let expected = maindevice.topology::<16>().await?;
+    ///
+    /// // Later, after SubDevices drop out
+    /// let current = maindevice.topology::<16>().await?;
+    ///
+    /// for link in expected.locate_breaks(&current) {
+    ///     match link.upstream_index.and_then(|index| expected.get(index)) {
+    ///         Some(upstream) => log::error!(
+    ///             "Link lost after {:#06x} port {:?}, {} SubDevices unreachable",
+    ///             upstream.configured_address,
+    ///             link.upstream_port,
+    ///             link.lost
+    ///         ),
+    ///         None => log::error!("Link to first SubDevice lost"),
+    ///     }
+    /// }
+    /// # Ok::<(), ethercrab::error::Error>(())
+    /// # };
+    /// ```
+    pub fn locate_breaks<const N: usize>(
+        &self,
+        current: &NetworkTopology<N>,
+    ) -> heapless::Vec<LinkBreak, MAX_SUBDEVICES> {
+        // Index of the matching node in `current` for every node in `self`
+        let mut matches = heapless::Vec::<Option<u16>, MAX_SUBDEVICES>::new();
+        let mut breaks = heapless::Vec::new();
+
+        for node in self.nodes.iter() {
+            let matched = match node.parent_index {
+                None => current
+                    .nodes
+                    .iter()
+                    .find(|candidate| candidate.parent_index.is_none())
+                    .map(|candidate| candidate.index),
+                Some(parent_index) => {
+                    let parent_match = self
+                        .nodes
+                        .iter()
+                        .position(|parent| parent.index == parent_index)
+                        .and_then(|position| matches.get(position).copied().flatten());
+
+                    // The parent is already unreachable and counted in an earlier break
+                    let Some(parent_match) = parent_match else {
+                        // Capacity is the same as `self.nodes`
+                        let _ = matches.push(None);
+
+                        continue;
+                    };
+
+                    let matched = current
+                        .nodes
+                        .iter()
+                        .find(|candidate| {
+                            candidate.parent_index == Some(parent_match)
+                                && candidate.parent_port == node.parent_port
+                        })
+                        .map(|candidate| candidate.index);
+
+                    if matched.is_none() {
+                        let upstream_link = current
+                            .get(parent_match)
+                            .zip(node.parent_port)
+                            .map(|(parent, port)| parent.ports[usize::from(port)].is_open());
+
+                        // Capacity is the same as `self.nodes`
+                        let _ = breaks.push(self.link_break(node, upstream_link));
+                    }
+
+                    matched
+                }
+            };
+
+            if matched.is_none() && node.parent_index.is_none() {
+                let _ = breaks.push(self.link_break(node, None));
+            }
+
+            let _ = matches.push(matched);
+        }
+
+        breaks
+    }
+
+    /// Read the DL status of every SubDevice in this topology to find links that are no longer
+    /// passing frames. See [`MainDevice::locate_breaks`].
+    pub(crate) async fn probe_links(
+        &self,
+        maindevice: &MainDevice<'_>,
+    ) -> Result<heapless::Vec<LinkBreak, MAX_SUBDEVICES>, Error> {
+        // DL status of every node in `self`, or `None` if the SubDevice is unreachable
+        let mut statuses = heapless::Vec::<Option<DlStatus>, MAX_SUBDEVICES>::new();
+        let mut breaks = heapless::Vec::new();
+
+        for node in self.nodes.iter() {
+            let parent_status = match node.parent_index {
+                None => None,
+                Some(parent_index) => {
+                    let status = self
+                        .nodes
+                        .iter()
+                        .position(|parent| parent.index == parent_index)
+                        .and_then(|position| statuses.get(position).copied().flatten());
+
+                    // SubDevices behind an unreachable parent are counted in an earlier break
+                    if status.is_none() {
+                        // Capacity is the same as `self.nodes`
+                        let _ = statuses.push(None);
+
+                        continue;
+                    }
+
+                    status
+                }
+            };
+
+            let status = Command::fprd(node.configured_address, RegisterAddress::DlStatus.into())
+                .ignore_wkc()
+                .receive_slice(maindevice, DlStatus::PACKED_LEN as u16)
+                .await;
+
+            let status = match status {
+                Ok(response) if response.working_counter == 1 => {
+                    Some(DlStatus::unpack_from_slice(&response)?)
+                }
+                // No response from the SubDevice, or the frame didn't come back at all
+                Ok(_) | Err(Error::Timeout) => None,
+                Err(e) => return Err(e),
+            };
+
+            if status.is_none() {
+                let upstream_link = parent_status
+                    .zip(node.parent_port)
+                    .map(|(parent, port)| parent.link(port));
+
+                let _ = breaks.push(self.link_break(node, upstream_link));
+            }
+
+            let _ = statuses.push(status);
+        }
+
+        Ok(breaks)
+    }
+
+    fn link_break(&self, node: &TopologyNode, upstream_link: Option<bool>) -> LinkBreak {
+        let lost = match (node.parent_index, node.parent_port) {
+            (Some(parent_index), Some(port)) => self.behind(parent_index, port).count(),
+            _ => self.nodes.len(),
+        };
+
+        LinkBreak {
+            upstream_index: node.parent_index,
+            upstream_port: node.parent_port,
+            upstream_link,
+            downstream_index: node.index,
+            downstream_port: node.entry_port(),
+            lost,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subdevice::ports::tests::make_ports;
+
+    /// EK1100 -> EL2004 -> EK1122 [port 3 -> EL1004, port 1 -> EK1100 -> EL2004], EK1122 port 2
+    /// -> EL3004.
+    fn subdevices() -> Vec<SubDevice> {
+        let subdevice = |index: u16, parent_index: Option<u16>, ports| SubDevice {
+            configured_address: ConfiguredAddress::new(0x1000 + index),
+            index,
+            parent_index,
+            ports,
+            ..SubDevice::default()
+        };
+
+        vec![
+            subdevice(
+                0,
+                None,
+                make_ports(true, false, true, false).set_downstreams(None, None, Some(1), None),
+            ),
+            subdevice(
+                1,
+                Some(0),
+                make_ports(true, false, true, false).set_downstreams(None, None, Some(2), None),
+            ),
+            // EK1122 with both junction ports used
+            subdevice(
+                2,
+                Some(1),
+                make_ports(true, true, true, true).set_downstreams(None, Some(3), Some(4), Some(6)),
+            ),
+            subdevice(3, Some(2), make_ports(true, false, false, false)),
+            subdevice(
+                4,
+                Some(2),
+                make_ports(true, false, true, false).set_downstreams(None, None, Some(5), None),
+            ),
+            subdevice(5, Some(4), make_ports(true, false, false, false)),
+            subdevice(6, Some(2), make_ports(true, false, false, false)),
+        ]
+    }
+
+    #[test]
+    fn build() {
+        let topology = NetworkTopology::<8>::new(&subdevices()).unwrap();
+
+        let ek1122 = topology.get(2).unwrap();
+
+        assert_eq!(ek1122.topology, Topology::Cross);
+        assert_eq!(ek1122.entry_port(), Some(0));
+        assert_eq!(
+            ek1122.ports,
+            [
+                PortLink::Upstream,
+                PortLink::Downstream(4),
+                PortLink::Downstream(6),
+                PortLink::Downstream(3),
+            ]
+        );
+        assert_eq!(
+            ek1122.downstream().collect::<Vec<_>>(),
+            [(3, 3), (1, 4), (2, 6)]
+        );
+
+        assert_eq!(topology.get(6).unwrap().parent_port, Some(2));
+        assert_eq!(topology.get(0).unwrap().parent_port, None);
+        assert_eq!(topology.get(5).unwrap().ports[1], PortLink::Closed);
+
+        assert_eq!(
+            NetworkTopology::<4>::new(&subdevices()),
+            Err(Error::Capacity(Item::SubDevice))
+        );
+    }
+
+    #[test]
+    fn queries() {
+        let topology = NetworkTopology::<8>::new(&subdevices()).unwrap();
+
+        let indices = |nodes: &mut dyn Iterator<Item = &TopologyNode>| {
+            nodes.map(|node| node.index).collect::<Vec<_>>()
+        };
+
+        assert_eq!(topology.parent(5).map(|node| node.index), Some(4));
+        assert_eq!(topology.parent(0), None);
+        assert_eq!(indices(&mut topology.children(2)), [3, 4, 6]);
+        assert_eq!(indices(&mut topology.path_to(5)), [5, 4, 2, 1, 0]);
+        assert_eq!(indices(&mut topology.path_to(99)), Vec::<u16>::new());
+        assert_eq!(indices(&mut topology.behind(2, 1)), [4, 5]);
+        assert_eq!(indices(&mut topology.behind(1, 1)), [2, 3, 4, 5, 6]);
+        assert_eq!(indices(&mut topology.behind(2, 0)), Vec::<u16>::new());
+    }
+
+    #[test]
+    fn open_port() {
+        let mut subdevices = subdevices();
+
+        // Last SubDevice has a link on its output port, but nothing responds behind it
+        subdevices[6].ports = make_ports(true, false, true, false);
+
+        let topology = NetworkTopology::<8>::new(&subdevices).unwrap();
+
+        assert_eq!(
+            topology.get(6).unwrap().ports,
+            [
+                PortLink::Upstream,
+                PortLink::Open,
+                PortLink::Closed,
+                PortLink::Closed
+            ]
+        );
+    }
+
+    #[test]
+    fn locate_breaks() {
+        let expected = NetworkTopology::<8>::new(&subdevices()).unwrap();
+
+        assert_eq!(expected.locate_breaks(&expected), []);
+
+        // Cable from EK1122 port 1 removed, so EK1122 port 1 closes and SubDevices 4 and 5
+        // disappear. The remaining SubDevice behind port 2 is renumbered.
+        let mut current = subdevices();
+
+        current.truncate(4);
+        current[2].ports =
+            make_ports(true, true, false, true).set_downstreams(None, Some(3), None, Some(4));
+        current.push(SubDevice {
+            configured_address: ConfiguredAddress::new(0x1004),
+            index: 4,
+            parent_index: Some(2),
+            ports: make_ports(true, false, false, false),
+            ..SubDevice::default()
+        });
+
+        let current = NetworkTopology::<8>::new(&current).unwrap();
+
+        assert_eq!(
+            expected.locate_breaks(&current),
+            [LinkBreak {
+                upstream_index: Some(2),
+                upstream_port: Some(1),
+                upstream_link: Some(false),
+                downstream_index: 4,
+                downstream_port: Some(0),
+                lost: 2,
+            }]
+        );
+
+        // Nothing responds at all
+        let empty = NetworkTopology::<8>::new(&[]).unwrap();
+
+        assert_eq!(
+            expected.locate_breaks(&empty),
+            [LinkBreak {
+                upstream_index: None,
+                upstream_port: None,
+                upstream_link: None,
+                downstream_index: 0,
+                downstream_port: Some(0),
+                lost: 7,
+            }]
+        );
+    }
+
+    #[test]
+    fn display_break() {
+        let link = LinkBreak {
+            upstream_index: Some(7),
+            upstream_port: Some(1),
+            upstream_link: Some(false),
+            downstream_index: 8,
+            downstream_port: Some(0),
+            lost: 3,
+        };
+
+        assert_eq!(
+            link.to_string(),
+            "link lost between SubDevice 7 port 1 and SubDevice 8 port 0, 3 SubDevice(s) unreachable"
+        );
+
+        let first = LinkBreak {
+            upstream_index: None,
+            upstream_port: None,
+            upstream_link: None,
+            downstream_index: 0,
+            downstream_port: Some(0),
+            lost: 9,
+        };
+
+        assert_eq!(
+            first.to_string(),
+            "link lost between MainDevice and SubDevice 0 port 0, 9 SubDevice(s) unreachable"
+        );
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{sim::fixture::*, LinkBreak, PduStorage, PortLink};
+
+    #[test]
+    fn topology() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let topology = maindevice.topology::<8>().await.expect("topology");
+
+            assert_eq!(topology.nodes().len(), 4);
+            assert_eq!(
+                topology
+                    .nodes()
+                    .iter()
+                    .map(|node| (node.parent_index, node.parent_port))
+                    .collect::<Vec<_>>(),
+                [
+                    (None, None),
+                    (Some(0), Some(1)),
+                    (Some(1), Some(1)),
+                    (Some(2), Some(1))
+                ]
+            );
+            assert_eq!(
+                topology.get(3).map(|node| node.ports),
+                Some([
+                    PortLink::Upstream,
+                    PortLink::Closed,
+                    PortLink::Closed,
+                    PortLink::Closed
+                ])
+            );
+            assert_eq!(topology.behind(1, 1).count(), 2);
+            assert_eq!(topology.locate_breaks(&topology), []);
+        });
+    }
+
+    #[test]
+    fn locate_breaks() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let expected = maindevice.topology::<8>().await.expect("topology");
+
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            let expected_wkc = group.tx_rx(&maindevice).await.expect("TX/RX");
+
+            assert_eq!(
+                maindevice.locate_breaks(&expected).await,
+                Ok(heapless::Vec::new())
+            );
+
+            net.disconnect(1);
+
+            assert_ne!(group.tx_rx(&maindevice).await, Ok(expected_wkc));
+
+            let breaks = maindevice.locate_breaks(&expected).await.expect("locate");
+
+            assert_eq!(
+                breaks,
+                [LinkBreak {
+                    upstream_index: Some(1),
+                    upstream_port: Some(1),
+                    upstream_link: Some(false),
+                    downstream_index: 2,
+                    downstream_port: Some(0),
+                    lost: 2,
+                }]
+            );
+            assert_eq!(
+                breaks[0].to_string(),
+                "link lost between SubDevice 1 port 1 and SubDevice 2 port 0, 2 SubDevice(s) unreachable"
+            );
+        });
+    }
+}
@@ -0,0 +1,862 @@
+//! Parsers for data received from the network.
+//!
+//! These functions take nothing but a byte slice, so they can be used to decode captured traffic
+//! or EEPROM dumps without a [`MainDevice`](crate::MainDevice), and to fuzz the same code EtherCrab
+//! uses to handle responses from real SubDevices. Malformed input is reported as an [`Error`] and
+//! never causes a panic.
+//!
+//! # Examples
+//!
+//! Print the PDUs in a captured Ethernet frame:
+//!
+//! ```rust
+//! use ethercrab::parse;
+//!
+//! # let ethernet_frame = [
+//! #     0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x12, 0x10, 0x10, 0x10, 0x10, 0x10, 0x88, 0xa4,
+//! #     0x0c, 0x10, 0x07, 0x00, 0x00, 0x00, 0x30, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+//! #     0x00,
+//! # ];
+//! for pdu in parse::frame(&ethernet_frame)? {
+//!     let pdu = pdu?;
+//!
+//!     println!("{:?}: {:02x?} (WKC {})", pdu.command, pdu.data, pdu.working_counter);
+//! }
+//! # Ok::<(), ethercrab::error::Error>(())
+//! ```
+
+use crate::{
+    coe::{
+        self,
+        abort_code::CoeAbortCode,
+        services::{CoeServiceRequest, SdoNormal, SdoSegmented},
+        CoeCommand, CoeService, SdoInfoHeader, SdoInfoOpCode, SubIndex,
+    },
+    eeprom::{
+        cache::CachedChunk,
+        types::{
+            DefaultMailbox, FmmuEx, FmmuUsage, Pdo, PdoType, SiiDistributedClock, SiiGeneral,
+            SyncManager,
+        },
+        EepromDataProvider,
+    },
+    error::{EepromError, Error, MailboxError, PduError},
+    ethernet::EthernetFrame,
+    fmt,
+    mailbox::{MailboxHeader, MailboxType},
+    pdu_loop::{frame_header::EthercatFrameHeader, pdu_header::PduHeader},
+    subdevice::eeprom::SubDeviceEeprom,
+    Command, SubDeviceIdentity, ETHERCAT_ETHERTYPE,
+};
+use core::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll},
+};
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
+
+/// A single PDU parsed from an EtherCAT frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Pdu<'a> {
+    /// PDU index.
+    pub index: u8,
+    /// The command and address, or `None` if the command code is not one EtherCrab sends, e.g.
+    /// `ARMW`.
+    pub command: Option<Command>,
+    /// IRQ field.
+    pub irq: u16,
+    /// PDU payload.
+    pub data: &'a [u8],
+    /// Working counter.
+    pub working_counter: u16,
+}
+
+/// An iterator over the PDUs in an EtherCAT frame, created by [`frame`].
+///
+/// Iteration stops after the first error.
+#[derive(Debug, Clone)]
+pub struct Pdus<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for Pdus<'a> {
+    type Item = Result<Pdu<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        match split_pdu(self.rest) {
+            Ok(pdu) => {
+                self.rest = pdu.rest;
+
+                Some(Ok(Pdu {
+                    index: pdu.header.index,
+                    command: Command::parse(pdu.header.command_code, pdu.header.command_raw),
+                    irq: pdu.header.irq,
+                    data: pdu.data,
+                    working_counter: pdu.working_counter,
+                }))
+            }
+            Err(e) => {
+                self.rest = &[];
+
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Parse the PDUs in a complete Ethernet II frame, with or without an 802.1Q VLAN tag.
+///
+/// Returns an error if the frame does not contain an EtherCAT payload. Errors in individual PDUs
+/// are returned by the iterator.
+pub fn frame(ethernet_frame: &[u8]) -> Result<Pdus<'_>, Error> {
+    let frame = EthernetFrame::new_checked(ethernet_frame)?;
+
+    if frame.inner_ethertype() != ETHERCAT_ETHERTYPE {
+        return Err(Error::Pdu(PduError::Ethernet));
+    }
+
+    Ok(Pdus {
+        rest: ethercat_pdus(frame.inner_payload())?,
+    })
+}
+
+/// Get the PDUs from an EtherCAT frame payload, i.e. the Ethernet payload after any VLAN tag.
+pub(crate) fn ethercat_pdus(ethercat_payload: &[u8]) -> Result<&[u8], Error> {
+    let header = EthercatFrameHeader::unpack_from_slice(ethercat_payload)?;
+
+    ethercat_payload
+        .get(EthercatFrameHeader::PACKED_LEN..)
+        .and_then(|pdus| pdus.get(..usize::from(header.payload_len)))
+        .ok_or(Error::ReceiveFrame)
+}
+
+/// The first PDU in a buffer, split by [`split_pdu`].
+pub(crate) struct RawPdu<'a> {
+    pub header: PduHeader,
+    pub data: &'a [u8],
+    pub working_counter: u16,
+    /// The bytes following this PDU.
+    pub rest: &'a [u8],
+}
+
+/// Split the first PDU from `pdus`.
+pub(crate) fn split_pdu(pdus: &[u8]) -> Result<RawPdu<'_>, Error> {
+    let header = PduHeader::unpack_from_slice(pdus)?;
+
+    let payload_len = usize::from(header.flags.len());
+
+    // If buffer isn't long enough to hold payload and WKC, this is probably a corrupt PDU or
+    // someone is committing epic haxx.
+    let (data, rest) = pdus
+        .get(PduHeader::PACKED_LEN..)
+        .filter(|rest| rest.len() >= payload_len + 2)
+        .map(|rest| rest.split_at(payload_len))
+        .ok_or(Error::Pdu(PduError::TooLong))?;
+
+    let (working_counter, rest) = rest.split_at(2);
+
+    Ok(RawPdu {
+        header,
+        data,
+        working_counter: u16::from_le_bytes([working_counter[0], working_counter[1]]),
+        rest,
+    })
+}
+
+/// The start of an SDO upload response, parsed by [`sdo_upload`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SdoUpload<'a> {
+    /// An expedited transfer of up to 4 bytes, held in the response itself.
+    Expedited(&'a [u8]),
+    /// A normal transfer, where all data fits in the response.
+    Normal {
+        /// The complete size of the object in bytes.
+        complete_size: u32,
+        /// Object data.
+        data: &'a [u8],
+    },
+    /// A segmented transfer. The data must be requested one segment at a time, with each response
+    /// parsed by [`sdo_upload_segment`].
+    Segmented {
+        /// The complete size of the object in bytes.
+        complete_size: u32,
+    },
+}
+
+/// A segment of a segmented SDO upload, parsed by [`sdo_upload_segment`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SdoSegment<'a> {
+    /// Segment data.
+    pub data: &'a [u8],
+    /// Whether this is the last segment of the transfer.
+    pub is_last: bool,
+    /// The toggle bit, which alternates between segments.
+    pub toggle: bool,
+}
+
+/// Parse the response to an SDO upload (read) request for the given index and sub-index from the
+/// contents of a SubDevice's read mailbox.
+///
+/// Emergency and abort responses are returned as [`MailboxError::Emergency`] and
+/// [`MailboxError::Aborted`] respectively.
+pub fn sdo_upload(
+    mailbox: &[u8],
+    index: u16,
+    sub_index: impl Into<SubIndex>,
+) -> Result<SdoUpload<'_>, Error> {
+    let headers = coe_response(&coe::services::upload(0, index, sub_index.into()), mailbox)?;
+
+    upload_response(&headers, mailbox)
+}
+
+/// Parse the response to an SDO upload segment request from the contents of a SubDevice's read
+/// mailbox.
+pub fn sdo_upload_segment(mailbox: &[u8]) -> Result<SdoSegment<'_>, Error> {
+    let headers = coe_response(&coe::services::upload_segmented(0, false), mailbox)?;
+
+    upload_segment_response(&headers, mailbox)
+}
+
+/// Parse the response to an SDO download (write) request for the given index and sub-index from
+/// the contents of a SubDevice's read mailbox.
+pub fn sdo_download(
+    mailbox: &[u8],
+    index: u16,
+    sub_index: impl Into<SubIndex>,
+) -> Result<(), Error> {
+    coe_response(
+        &coe::services::download(0, index, sub_index.into(), [0u8; 4], 0),
+        mailbox,
+    )
+    .map(|_| ())
+}
+
+/// A super generalised version of the various header shapes for responses, extracting only what
+/// we need to validate a response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ethercrab_wire::EtherCrabWireRead)]
+#[wire(bytes = 12)]
+struct HeadersRaw {
+    #[wire(bytes = 8)]
+    header: MailboxHeader,
+
+    #[wire(pre_skip = 5, bits = 3)]
+    command: CoeCommand,
+
+    // 9 bytes up to here
+
+    // SAFETY: These fields will be garbage (but not invalid) if the response is NOT an
+    // abort transfer request. Use with caution!
+    #[wire(bytes = 2)]
+    address: u16,
+    #[wire(bytes = 1)]
+    sub_index: u8,
+}
+
+/// Check that the contents of a SubDevice's read mailbox are a valid response to `request`, and
+/// parse its headers.
+///
+/// The mailbox counter is not checked.
+pub(crate) fn coe_response<R>(request: &R, mailbox: &[u8]) -> Result<R, Error>
+where
+    R: CoeServiceRequest,
+{
+    let header = MailboxHeader::unpack_from_slice(mailbox)?;
+
+    // Checked before parsing the SDO headers, as emergency data is not a valid SDO header
+    if header.service == CoeService::Emergency {
+        return Err(emergency(mailbox));
+    }
+
+    let headers = HeadersRaw::unpack_from_slice(mailbox)?;
+
+    if headers.command == CoeCommand::Abort {
+        // The abort code follows the SDO header
+        let code = mailbox
+            .get(HeadersRaw::PACKED_LEN..)
+            .and_then(|code| CoeAbortCode::unpack_from_slice(code).ok())
+            .unwrap_or(CoeAbortCode::Incompatible);
+
+        Err(Error::Mailbox(MailboxError::Aborted {
+            code,
+            address: headers.address,
+            sub_index: headers.sub_index,
+        }))
+    }
+    // Validate that the mailbox response is to the request we just sent
+    else if headers.header.mailbox_type != MailboxType::Coe
+        || !request.validate_response(headers.address, headers.sub_index)
+    {
+        fmt::error!(
+            "Invalid SDO response. Type: {:?} (expected {:?}), index {}, subindex {}",
+            headers.header.mailbox_type,
+            MailboxType::Coe,
+            headers.address,
+            headers.sub_index,
+        );
+
+        Err(Error::Mailbox(MailboxError::SdoResponseInvalid {
+            address: headers.address,
+            sub_index: headers.sub_index,
+        }))
+    } else {
+        Ok(R::unpack_from_slice(mailbox)?)
+    }
+}
+
+/// Decode an emergency message received instead of the response to a request.
+fn emergency(mailbox: &[u8]) -> Error {
+    #[derive(Debug, Copy, Clone, ethercrab_wire::EtherCrabWireRead)]
+    #[wire(bytes = 8)]
+    struct EmergencyData {
+        #[wire(bytes = 2)]
+        error_code: u16,
+        #[wire(bytes = 1)]
+        error_register: u8,
+        #[wire(bytes = 5)]
+        extra_data: [u8; 5],
+    }
+
+    // Emergency data directly follows the CoE header
+    let decoded = match EmergencyData::unpack_from_slice(
+        mailbox.get(MailboxHeader::PACKED_LEN..).unwrap_or_default(),
+    ) {
+        Ok(decoded) => decoded,
+        Err(e) => return e.into(),
+    };
+
+    #[cfg(not(feature = "defmt"))]
+    fmt::error!(
+        "Mailbox emergency code {:#06x}, register {:#04x}, data {:#04x?}",
+        decoded.error_code,
+        decoded.error_register,
+        decoded.extra_data
+    );
+    #[cfg(feature = "defmt")]
+    fmt::error!(
+        "Mailbox emergency code {:#06x}, register {:#04x}, data {=[u8]}",
+        decoded.error_code,
+        decoded.error_register,
+        decoded.extra_data
+    );
+
+    Error::Mailbox(MailboxError::Emergency {
+        error_code: decoded.error_code,
+        error_register: decoded.error_register,
+    })
+}
+
+/// Check that the contents of a SubDevice's read mailbox are an SDO Information response with the
+/// given OpCode, and return its header and data.
+///
+/// SDO Information errors are returned as [`MailboxError::Aborted`] for the given index and
+/// sub-index.
+pub(crate) fn sdo_info_response(
+    mailbox: &[u8],
+    op_code: SdoInfoOpCode,
+    index: u16,
+    sub_index: u8,
+) -> Result<(SdoInfoHeader, &[u8]), Error> {
+    let header = MailboxHeader::unpack_from_slice(mailbox)?;
+
+    if header.service == CoeService::Emergency {
+        return Err(emergency(mailbox));
+    }
+
+    let invalid = Error::Mailbox(MailboxError::SdoResponseInvalid {
+        address: index,
+        sub_index,
+    });
+
+    if header.mailbox_type != MailboxType::Coe || header.service != CoeService::SdoInformation {
+        fmt::error!(
+            "Invalid SDO Information response. Type: {:?}, service {:?}",
+            header.mailbox_type,
+            header.service
+        );
+
+        return Err(invalid);
+    }
+
+    let info_header = SdoInfoHeader::unpack_from_slice(
+        mailbox.get(MailboxHeader::PACKED_LEN..).unwrap_or_default(),
+    )?;
+
+    // The mailbox length counts from the end of the 6 byte mailbox header and includes the 2 byte
+    // CoE header.
+    let data = usize::from(header.length)
+        .checked_sub(2 + SdoInfoHeader::PACKED_LEN)
+        .and_then(|len| {
+            mailbox
+                .get((MailboxHeader::PACKED_LEN + SdoInfoHeader::PACKED_LEN)..)?
+                .get(0..len)
+        })
+        .ok_or(Error::Pdu(PduError::Decode))?;
+
+    if info_header.op_code == SdoInfoOpCode::ErrorRequest {
+        let code = CoeAbortCode::unpack_from_slice(data).unwrap_or(CoeAbortCode::Incompatible);
+
+        return Err(Error::Mailbox(MailboxError::Aborted {
+            code,
+            address: index,
+            sub_index,
+        }));
+    }
+
+    if info_header.op_code != op_code {
+        fmt::error!(
+            "Invalid SDO Information response OpCode {:?} (expected {:?})",
+            info_header.op_code,
+            op_code
+        );
+
+        return Err(invalid);
+    }
+
+    Ok((info_header, data))
+}
+
+/// Decode the data in an SDO upload response whose headers have already been validated.
+pub(crate) fn upload_response<'a>(
+    headers: &SdoNormal,
+    mailbox: &'a [u8],
+) -> Result<SdoUpload<'a>, Error> {
+    let data = mailbox.get(SdoNormal::PACKED_LEN..).unwrap_or_default();
+
+    // Expedited transfers where the data is 4 bytes or less long, denoted in the SDO header
+    // size value.
+    if headers.sdo_header.expedited_transfer {
+        let data_len = 4usize.saturating_sub(usize::from(headers.sdo_header.size));
+
+        return data
+            .get(0..data_len)
+            .map(SdoUpload::Expedited)
+            .ok_or(Error::Pdu(PduError::Decode));
+    }
+
+    // Data is either a normal upload or a segmented upload
+    let data_length = headers.header.length.saturating_sub(0x0a);
+
+    let complete_size = u32::unpack_from_slice(data)?;
+
+    // If it's a normal upload, the response payload is returned in the initial mailbox read
+    if complete_size <= u32::from(data_length) {
+        let data = data
+            .get(u32::PACKED_LEN..)
+            .and_then(|data| data.get(0..usize::from(data_length)))
+            .ok_or(Error::Pdu(PduError::Decode))?;
+
+        Ok(SdoUpload::Normal {
+            complete_size,
+            data,
+        })
+    } else {
+        Ok(SdoUpload::Segmented { complete_size })
+    }
+}
+
+/// Decode the data in an SDO upload segment response whose headers have already been validated.
+pub(crate) fn upload_segment_response<'a>(
+    headers: &SdoSegmented,
+    mailbox: &'a [u8],
+) -> Result<SdoSegment<'a>, Error> {
+    // The spec defines the data length as n-3, so we'll just go with that magic number...
+    let mut chunk_len = usize::from(
+        headers
+            .header
+            .length
+            .checked_sub(3)
+            .ok_or(Error::Pdu(PduError::Decode))?,
+    );
+
+    // Special case as per spec: Minimum response size is 7 bytes. For smaller responses, we must
+    // remove the number of unused bytes at the end of the response. Extremely weird.
+    if chunk_len == 7 {
+        chunk_len -= usize::from(headers.sdo_header.segment_data_size);
+    }
+
+    // Segment data directly follows the 1 byte segment header
+    let data = mailbox
+        .get(SdoSegmented::PACKED_LEN..)
+        .and_then(|data| data.get(0..chunk_len))
+        .ok_or(Error::Pdu(PduError::Decode))?;
+
+    Ok(SdoSegment {
+        data,
+        is_last: headers.sdo_header.is_last_segment,
+        toggle: headers.sdo_header.toggle,
+    })
+}
+
+/// The SubDevice Information Interface (SII) categories in an EEPROM image, parsed by [`sii`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sii {
+    /// Vendor ID, product ID, revision and serial number.
+    pub identity: SubDeviceIdentity,
+    /// Standard mailbox configuration.
+    pub mailbox: DefaultMailbox,
+    /// The `General` category, if present.
+    pub general: Option<SiiGeneral>,
+    /// Sync manager configuration.
+    pub sync_managers: heapless::Vec<SyncManager, 8>,
+    /// FMMU usage.
+    pub fmmus: heapless::Vec<FmmuUsage, 16>,
+    /// FMMU to sync manager mappings.
+    pub fmmu_mappings: heapless::Vec<FmmuEx, 16>,
+    /// RxPDOs, i.e. MainDevice outputs.
+    pub rx_pdos: heapless::Vec<Pdo, 64>,
+    /// TxPDOs, i.e. MainDevice inputs.
+    pub tx_pdos: heapless::Vec<Pdo, 64>,
+    /// Distributed Clock operation modes.
+    pub distributed_clock: heapless::Vec<SiiDistributedClock, 16>,
+}
+
+/// Parse the SII categories from an EEPROM image, e.g. a dump read from a SubDevice.
+///
+/// Bytes past the end of the image read as `0xff`, as they would from an erased EEPROM.
+pub fn sii(image: &[u8]) -> Result<Sii, Error> {
+    let eeprom = SubDeviceEeprom::new(SliceEeprom { image });
+
+    let general = match poll_once(eeprom.general()) {
+        Ok(general) => Some(general),
+        Err(Error::Eeprom(EepromError::NoCategory)) => None,
+        Err(e) => return Err(e),
+    };
+
+    Ok(Sii {
+        identity: poll_once(eeprom.identity())?,
+        mailbox: poll_once(eeprom.mailbox_config())?,
+        general,
+        sync_managers: poll_once(eeprom.sync_managers())?,
+        fmmus: poll_once(eeprom.fmmus())?,
+        fmmu_mappings: poll_once(eeprom.fmmu_mappings())?,
+        rx_pdos: poll_once(eeprom.pdos(PdoType::Rx))?,
+        tx_pdos: poll_once(eeprom.pdos(PdoType::Tx))?,
+        distributed_clock: poll_once(eeprom.distributed_clock())?,
+    })
+}
+
+/// An EEPROM backed by an in-memory image.
+#[derive(Clone)]
+struct SliceEeprom<'a> {
+    image: &'a [u8],
+}
+
+impl EepromDataProvider for SliceEeprom<'_> {
+    async fn read_chunk(
+        &mut self,
+        start_word: u32,
+    ) -> Result<impl core::ops::Deref<Target = [u8]>, Error> {
+        let mut chunk = [0xffu8; 8];
+
+        let start = usize::try_from(start_word)
+            .ok()
+            .and_then(|word| word.checked_mul(2))
+            .unwrap_or(usize::MAX);
+
+        let image = self.image.get(start..).unwrap_or_default();
+        let len = image.len().min(chunk.len());
+
+        chunk[0..len].copy_from_slice(&image[0..len]);
+
+        Ok(CachedChunk::new(start_word, &chunk))
+    }
+
+    async fn clear_errors(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Run a future that never waits, such as an EEPROM read from [`SliceEeprom`].
+fn poll_once<T>(future: impl Future<Output = Result<T, Error>>) -> Result<T, Error> {
+    let waker = crate::raw_device::noop_waker();
+
+    match pin!(future).poll(&mut Context::from_waker(&waker)) {
+        Poll::Ready(result) => result,
+        Poll::Pending => Err(Error::Internal),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Reads;
+
+    /// Ethernet II frame holding a single `BRD` PDU.
+    fn brd_frame(data: &[u8], wkc: u16) -> Vec<u8> {
+        let mut frame = vec![
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x12, 0x10, 0x10, 0x10, 0x10, 0x10, 0x88, 0xa4,
+        ];
+
+        let pdu_len = PduHeader::PACKED_LEN + data.len() + 2;
+
+        frame.extend_from_slice(&(0x1000 | pdu_len as u16).to_le_bytes());
+        frame.extend_from_slice(&[0x07, 0x05, 0x00, 0x00, 0x30, 0x01]);
+        frame.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        frame.extend_from_slice(&[0x00, 0x00]);
+        frame.extend_from_slice(data);
+        frame.extend_from_slice(&wkc.to_le_bytes());
+
+        frame
+    }
+
+    #[test]
+    fn parse_frame() {
+        let frame = brd_frame(&[0xaa, 0xbb], 3);
+
+        let pdus = super::frame(&frame)
+            .expect("frame")
+            .collect::<Result<Vec<_>, _>>();
+
+        assert_eq!(
+            pdus,
+            Ok(vec![Pdu {
+                index: 0x05,
+                command: Some(
+                    Reads::Brd {
+                        address: 0,
+                        register: 0x0130
+                    }
+                    .into()
+                ),
+                irq: 0,
+                data: &[0xaa, 0xbb],
+                working_counter: 3,
+            }])
+        );
+    }
+
+    #[test]
+    fn truncated_frame() {
+        let frame = brd_frame(&[0xaa, 0xbb], 3);
+
+        // Cutting the frame short makes the EtherCAT header length invalid
+        assert_eq!(
+            super::frame(&frame[0..frame.len() - 1]).map(|_| ()),
+            Err(Error::ReceiveFrame)
+        );
+
+        // A PDU claiming to be longer than the EtherCAT payload
+        let mut frame = frame;
+        frame[22] = 0xff;
+
+        assert_eq!(
+            super::frame(&frame).map(|pdus| pdus.collect::<Vec<_>>()),
+            Ok(vec![Err(Error::Pdu(PduError::TooLong))])
+        );
+    }
+
+    #[test]
+    fn not_ethercat() {
+        let mut frame = brd_frame(&[], 0);
+        frame[12] = 0x08;
+        frame[13] = 0x00;
+
+        assert_eq!(
+            super::frame(&frame).map(|_| ()),
+            Err(Error::Pdu(PduError::Ethernet))
+        );
+    }
+
+    #[test]
+    fn sdo_expedited_upload() {
+        let raw = [10u8, 0, 0, 0, 0, 83, 0, 48, 79, 0, 28, 4, 0xaa, 0, 0, 0];
+
+        assert_eq!(
+            sdo_upload(&raw, 0x1c00, 4),
+            Ok(SdoUpload::Expedited(&[0xaa]))
+        );
+
+        assert_eq!(
+            sdo_upload(&raw, 0x1c00, 3),
+            Err(Error::Mailbox(MailboxError::SdoResponseInvalid {
+                address: 0x1c00,
+                sub_index: 4
+            }))
+        );
+    }
+
+    #[test]
+    fn sdo_normal_upload() {
+        let raw = [
+            16u8, 0, 0, 0, 0, 99, 0, 48, 65, 8, 16, 0, 6, 0, 0, 0, 69, 75, 49, 57, 49, 52,
+        ];
+
+        assert_eq!(
+            sdo_upload(&raw, 0x1008, 0),
+            Ok(SdoUpload::Normal {
+                complete_size: 6,
+                data: b"EK1914"
+            })
+        );
+
+        // Response is shorter than its mailbox header says
+        assert_eq!(
+            sdo_upload(&raw[0..20], 0x1008, 0),
+            Err(Error::Pdu(PduError::Decode))
+        );
+    }
+
+    #[test]
+    fn sdo_segment() {
+        // Length 10 = 3 byte header + 7 bytes of which 4 are unused
+        // Segment header 0x79: upload segment, toggle set, 4 unused bytes, last segment
+        let raw = [10u8, 0, 0, 0, 0, 99, 0, 48, 0x79, 1, 2, 3, 0, 0, 0, 0];
+
+        assert_eq!(
+            sdo_upload_segment(&raw),
+            Ok(SdoSegment {
+                data: &[1, 2, 3],
+                is_last: true,
+                toggle: true,
+            })
+        );
+
+        // A length of less than 3 is invalid
+        let mut raw = raw;
+        raw[0] = 2;
+
+        assert_eq!(sdo_upload_segment(&raw), Err(Error::Pdu(PduError::Decode)));
+    }
+
+    #[test]
+    fn sdo_abort() {
+        let raw = [
+            0x0a, 0x00, 0x00, 0x00, 0x00, 0x63, 0x00, 0x20, 0x80, 0x01, 0x10, 0x00, 0x00, 0x00,
+            0x02, 0x06,
+        ];
+
+        assert_eq!(
+            sdo_download(&raw, 0x1001, 0),
+            Err(Error::Mailbox(MailboxError::Aborted {
+                code: CoeAbortCode::NotFound,
+                address: 0x1001,
+                sub_index: 0
+            }))
+        );
+    }
+
+    #[test]
+    fn sdo_emergency() {
+        let raw = [
+            0x0a, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x10, 0x10, 0x82, 0x11, 1, 2, 3, 4, 5,
+        ];
+
+        assert_eq!(
+            sdo_upload(&raw, 0x1000, 0),
+            Err(Error::Mailbox(MailboxError::Emergency {
+                error_code: 0x8210,
+                error_register: 0x11,
+            }))
+        );
+    }
+
+    #[test]
+    fn sdo_info_fragment() {
+        // Second to last fragment of an object list
+        let raw = [
+            0x0a, 0x00, 0x00, 0x00, 0x00, 0x23, 0x00, 0x80, 0x82, 0x00, 0x01, 0x00, 0x00, 0x10,
+            0x18, 0x10, 0xff, 0xff,
+        ];
+
+        let (header, data) =
+            sdo_info_response(&raw, SdoInfoOpCode::GetOdListResponse, 0, 0).expect("parse");
+
+        assert!(header.incomplete);
+        assert_eq!(header.fragments_left, 1);
+        assert_eq!(data, &[0x00, 0x10, 0x18, 0x10]);
+    }
+
+    #[test]
+    fn sdo_info_error() {
+        let raw = [
+            0x0a, 0x00, 0x00, 0x00, 0x00, 0x23, 0x00, 0x80, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x02, 0x06,
+        ];
+
+        assert_eq!(
+            sdo_info_response(&raw, SdoInfoOpCode::GetObjectDescriptionResponse, 0x2000, 0),
+            Err(Error::Mailbox(MailboxError::Aborted {
+                code: CoeAbortCode::NotFound,
+                address: 0x2000,
+                sub_index: 0
+            }))
+        );
+    }
+
+    #[test]
+    fn sii_akd() {
+        let image = std::fs::read("dumps/eeprom/akd.hex").expect("read EEPROM dump");
+
+        let sii = sii(&image).expect("parse SII");
+
+        assert_eq!(
+            sii.identity,
+            SubDeviceIdentity {
+                vendor_id: 0x0000006a,
+                product_id: 0x00414b44,
+                revision: 2,
+                serial: 2575499411,
+            }
+        );
+        assert!(sii.general.is_some());
+        assert!(!sii.sync_managers.is_empty());
+    }
+
+    #[test]
+    fn sii_no_categories() {
+        let image = std::fs::read("dumps/eeprom/akd.hex").expect("read EEPROM dump");
+
+        // Only the configuration area and fixed fields before the first category
+        let sii = sii(&image[0..128]).expect("parse SII");
+
+        assert_eq!(sii.identity.product_id, 0x00414b44);
+
+        assert_eq!(sii.general, None);
+        assert!(sii.sync_managers.is_empty());
+        assert!(sii.rx_pdos.is_empty());
+    }
+
+    #[test]
+    fn no_panic_frame() {
+        heckcheck::check(|data: Vec<u8>| {
+            if let Ok(pdus) = super::frame(&data) {
+                pdus.for_each(drop);
+            }
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn no_panic_pdus() {
+        heckcheck::check(|data: Vec<u8>| {
+            Pdus { rest: &data }.for_each(drop);
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn no_panic_mailbox() {
+        heckcheck::check(|data: Vec<u8>| {
+            let _ = sdo_upload(&data, 0x1000, 0);
+            let _ = sdo_upload_segment(&data);
+            let _ = sdo_download(&data, 0x1000, SubIndex::Complete);
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn no_panic_sii() {
+        heckcheck::check(|data: Vec<u8>| {
+            let _ = sii(&data);
+
+            Ok(())
+        });
+    }
+}
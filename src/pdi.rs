@@ -1,3 +1,4 @@
+use crate::LogicalAddress;
 use core::ops::Range;
 
 /// An accumulator that stores the bit and byte offsets in the PDI so SubDevice IO data can be mapped
@@ -7,7 +8,7 @@ use core::ops::Range;
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PdiOffset {
-    pub start_address: u32,
+    pub start_address: LogicalAddress,
     // // Unused, but will become useful if we support bit-packed PDI mappings in the future.
     // start_bit: u8,
 }
@@ -37,7 +38,7 @@ impl PdiOffset {
         // };
 
         Self {
-            start_address: self.start_address + u32::from(inc_bytes),
+            start_address: self.start_address.offset(u32::from(inc_bytes)),
             // start_bit,
         }
     }
@@ -54,7 +55,7 @@ impl PdiOffset {
 
     /// Compute an index range between this offset (inclusive) and another (exclusive).
     pub fn up_to(self, other: Self) -> Range<usize> {
-        self.start_address as usize..other.start_address as usize
+        self.start_address.get() as usize..other.start_address.get() as usize
     }
 
     // Maybe one day we support packed PDIs. In that instance, uncomment this and the tests below.
@@ -117,18 +118,32 @@ mod tests {
 
         let input = input.increment_byte_aligned(4);
 
-        assert_eq!(input, PdiOffset { start_address: 1 }, "first increment");
+        assert_eq!(
+            input,
+            PdiOffset {
+                start_address: LogicalAddress::new(1)
+            },
+            "first increment"
+        );
 
         let input = input.increment_byte_aligned(4);
 
-        assert_eq!(input, PdiOffset { start_address: 2 }, "second increment");
+        assert_eq!(
+            input,
+            PdiOffset {
+                start_address: LogicalAddress::new(2)
+            },
+            "second increment"
+        );
     }
 
     #[test]
     #[cfg_attr(miri, ignore)]
     fn fuzz_pdi_segment() {
         heckcheck::check(|(start_address, incr_bits): (u32, u16)| {
-            let offset = PdiOffset { start_address };
+            let offset = PdiOffset {
+                start_address: LogicalAddress::new(start_address),
+            };
 
             let new = offset.increment_byte_aligned(incr_bits);
 
@@ -136,8 +151,8 @@ mod tests {
             let incr_bytes = (incr_bits + 7) / 8;
 
             assert_eq!(
-                new.start_address,
-                offset.start_address + incr_bytes,
+                new.start_address.get(),
+                offset.start_address.get() + incr_bytes,
                 "incorrect increment"
             );
             // assert_eq!(new.start_bit, 0, "not byte aligned");
@@ -0,0 +1,202 @@
+//! Typed, bounds checked access to process data.
+
+use crate::error::Error;
+use ethercrab_wire::{EtherCrabWireReadSized, EtherCrabWireWriteSized, WireError};
+
+/// Read and write values in a SubDevice's process data by byte or bit offset.
+///
+/// This trait is implemented for `[u8]`, so can be used with the slices returned by e.g.
+/// [`SubDeviceRef::inputs_raw`](crate::SubDeviceRef::inputs_raw) and
+/// [`SubDeviceRef::outputs_raw_mut`](crate::SubDeviceRef::outputs_raw_mut). Values are encoded
+/// little-endian, as they are on the wire. Any type that implements [`EtherCrabWireReadSized`] or
+/// [`EtherCrabWireWriteSized`] can be used, including the primitive integer and float types and
+/// structs deriving the `ethercrab-wire` traits.
+///
+/// Accessing data past the end of the slice returns
+/// [`WireError::ReadBufferTooShort`] or [`WireError::WriteBufferTooShort`] instead of panicking.
+///
+/// `get_at` and `set_at` are named so they don't clash with the slice's own `get` method.
+///
+/// # Examples
+///
+/// ```rust
+/// use ethercrab::PdiData;
+///
+/// // E.g. a drive mapping a status word followed by the actual position
+/// let inputs = [0x37, 0x02, 0x10, 0x27, 0x00, 0x00];
+///
+/// assert_eq!(inputs.get_at::<u16>(0), Ok(0x0237));
+/// assert_eq!(inputs.get_at::<i32>(2), Ok(10_000));
+/// assert_eq!(inputs.get_bit(1), Ok(true));
+///
+/// // Reading past the end of the PDI is an error
+/// assert!(inputs.get_at::<u32>(4).is_err());
+///
+/// let mut outputs = [0u8; 6];
+///
+/// outputs.set_at::<u16>(0, 0x000f)?;
+/// outputs.set_at::<i32>(2, -1)?;
+/// outputs.set_bit(7, true)?;
+///
+/// assert_eq!(outputs, [0x8f, 0x00, 0xff, 0xff, 0xff, 0xff]);
+/// # Ok::<(), ethercrab::error::Error>(())
+/// ```
+pub trait PdiData {
+    /// Read a value starting at the given byte offset.
+    fn get_at<T>(&self, offset: usize) -> Result<T, Error>
+    where
+        T: EtherCrabWireReadSized;
+
+    /// Write a value starting at the given byte offset.
+    ///
+    /// Nothing is written if the value doesn't fit in the remaining data.
+    fn set_at<T>(&mut self, offset: usize, value: T) -> Result<(), Error>
+    where
+        T: EtherCrabWireWriteSized;
+
+    /// Read a single bit.
+    ///
+    /// Bits are counted from the least significant bit of the first byte, so bit `10` is bit `2`
+    /// of byte `1`.
+    fn get_bit(&self, bit_offset: usize) -> Result<bool, Error>;
+
+    /// Set or clear a single bit, counted the same way as [`get_bit`](PdiData::get_bit).
+    fn set_bit(&mut self, bit_offset: usize, value: bool) -> Result<(), Error>;
+}
+
+impl PdiData for [u8] {
+    fn get_at<T>(&self, offset: usize) -> Result<T, Error>
+    where
+        T: EtherCrabWireReadSized,
+    {
+        let buf = offset
+            .checked_add(T::PACKED_LEN)
+            .and_then(|end| self.get(offset..end))
+            .ok_or(Error::Wire(WireError::ReadBufferTooShort))?;
+
+        T::unpack_from_slice(buf).map_err(Error::Wire)
+    }
+
+    fn set_at<T>(&mut self, offset: usize, value: T) -> Result<(), Error>
+    where
+        T: EtherCrabWireWriteSized,
+    {
+        let buf = offset
+            .checked_add(T::PACKED_LEN)
+            .and_then(|end| self.get_mut(offset..end))
+            .ok_or(Error::Wire(WireError::WriteBufferTooShort))?;
+
+        buf.copy_from_slice(value.pack().as_ref());
+
+        Ok(())
+    }
+
+    fn get_bit(&self, bit_offset: usize) -> Result<bool, Error> {
+        let byte = self
+            .get(bit_offset / 8)
+            .ok_or(Error::Wire(WireError::ReadBufferTooShort))?;
+
+        Ok(byte >> (bit_offset % 8) & 1 == 1)
+    }
+
+    fn set_bit(&mut self, bit_offset: usize, value: bool) -> Result<(), Error> {
+        let byte = self
+            .get_mut(bit_offset / 8)
+            .ok_or(Error::Wire(WireError::WriteBufferTooShort))?;
+
+        if value {
+            *byte |= 1 << (bit_offset % 8);
+        } else {
+            *byte &= !(1 << (bit_offset % 8));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_le() {
+        let data = [0x01, 0x02, 0x03, 0x04, 0x05];
+
+        assert_eq!(data.get_at::<u8>(4), Ok(0x05));
+        assert_eq!(data.get_at::<u16>(1), Ok(0x0302));
+        assert_eq!(data.get_at::<u32>(0), Ok(0x04030201));
+        assert_eq!(data.get_at::<i32>(1), Ok(0x05040302));
+        assert_eq!([0xff, 0xff].get_at::<i16>(0), Ok(-1));
+        assert_eq!(
+            1.5f32.to_le_bytes().get_at::<f32>(0),
+            Ok(1.5),
+            "floats are supported too"
+        );
+    }
+
+    #[test]
+    fn get_out_of_bounds() {
+        let data = [0u8; 4];
+
+        assert_eq!(
+            data.get_at::<u32>(1),
+            Err(Error::Wire(WireError::ReadBufferTooShort))
+        );
+        assert_eq!(
+            data.get_at::<u8>(4),
+            Err(Error::Wire(WireError::ReadBufferTooShort))
+        );
+        assert_eq!(
+            data.get_at::<u16>(usize::MAX),
+            Err(Error::Wire(WireError::ReadBufferTooShort))
+        );
+        assert_eq!(
+            data.get_bit(32),
+            Err(Error::Wire(WireError::ReadBufferTooShort))
+        );
+    }
+
+    #[test]
+    fn set_le() {
+        let mut data = [0u8; 6];
+
+        data.set_at::<u16>(0, 0xabcd).unwrap();
+        data.set_at::<i32>(2, -2).unwrap();
+
+        assert_eq!(data, [0xcd, 0xab, 0xfe, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn set_out_of_bounds() {
+        let mut data = [0u8; 4];
+
+        assert_eq!(
+            data.set_at::<u32>(2, 0xffff_ffff),
+            Err(Error::Wire(WireError::WriteBufferTooShort))
+        );
+        assert_eq!(
+            data.set_bit(32, true),
+            Err(Error::Wire(WireError::WriteBufferTooShort))
+        );
+
+        // Nothing was partially written
+        assert_eq!(data, [0u8; 4]);
+    }
+
+    #[test]
+    fn bits() {
+        let mut data = [0u8; 2];
+
+        data.set_bit(0, true).unwrap();
+        data.set_bit(10, true).unwrap();
+        data.set_bit(15, true).unwrap();
+
+        assert_eq!(data, [0b0000_0001, 0b1000_0100]);
+        assert_eq!(data.get_bit(10), Ok(true));
+        assert_eq!(data.get_bit(11), Ok(false));
+
+        data.set_bit(15, false).unwrap();
+
+        assert_eq!(data, [0b0000_0001, 0b0000_0100]);
+    }
+}
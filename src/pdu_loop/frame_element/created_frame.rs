@@ -3,14 +3,18 @@ use crate::{
     fmt,
     generate::write_packed,
     pdu_loop::{
-        frame_element::{receiving_frame::ReceiveFrameFut, FrameBox, FrameElement, FrameState},
+        frame_element::{
+            receiving_frame::ReceiveFrameFut, DirectRx, FrameBox, FrameElement, FrameState,
+        },
         frame_header::EthercatFrameHeader,
         pdu_flags::PduFlags,
         pdu_header::PduHeader,
+        storage::PduStorageRef,
+        FramePriority,
     },
-    Command, PduLoop,
+    Command, FrameDirection, PduLoop, RetryBackoff,
 };
-use core::{ptr::NonNull, sync::atomic::AtomicU8, time::Duration};
+use core::{ptr::NonNull, time::Duration};
 use ethercrab_wire::{
     EtherCrabWireRead, EtherCrabWireSized, EtherCrabWireWrite, EtherCrabWireWriteSized,
 };
@@ -19,9 +23,13 @@ use ethercrab_wire::{
 ///
 /// This typestate may only be created by
 /// [`alloc_frame`](crate::pdu_loop::storage::PduStorageRef::alloc_frame).
+///
+/// The frame is released for reuse if it is dropped before being marked as sendable, e.g. when
+/// pushing a PDU fails or the future building the frame is cancelled.
 #[derive(Debug)]
 pub struct CreatedFrame<'sto> {
     inner: FrameBox<'sto>,
+    storage: PduStorageRef<'sto>,
     pdu_count: u8,
     /// Position of the last frame's header in the payload.
     ///
@@ -33,17 +41,19 @@ impl<'sto> CreatedFrame<'sto> {
     pub(in crate::pdu_loop) fn claim_created(
         frame: NonNull<FrameElement<0>>,
         frame_index: u8,
-        pdu_idx: &'sto AtomicU8,
-        frame_data_len: usize,
+        storage: PduStorageRef<'sto>,
+        priority: FramePriority,
     ) -> Result<Self, PduError> {
         let frame = unsafe { FrameElement::claim_created(frame, frame_index)? };
 
-        let mut inner = FrameBox::new(frame, pdu_idx, frame_data_len);
+        let mut inner = FrameBox::new(frame, storage.pdu_idx, storage.frame_data_len);
 
         inner.init();
+        inner.set_priority(priority);
 
         Ok(Self {
             inner,
+            storage,
             pdu_count: 0,
             last_header_location: None,
         })
@@ -55,22 +65,40 @@ impl<'sto> CreatedFrame<'sto> {
     /// This method returns a future that should be fulfilled when a response to the sent frame is
     /// received.
     pub fn mark_sendable(
-        mut self,
+        self,
         pdu_loop: &'sto PduLoop<'sto>,
         timeout: Duration,
         retries: usize,
+        backoff: RetryBackoff,
     ) -> ReceiveFrameFut<'sto> {
-        EthercatFrameHeader::pdu(self.inner.pdu_payload_len() as u16)
-            .pack_to_slice_unchecked(self.inner.ecat_frame_header_mut());
+        let mut inner = self.inner;
+
+        // Ownership of the frame passes to the returned future, which releases it when dropped
+        core::mem::forget(self);
+
+        EthercatFrameHeader::pdu(inner.pdu_payload_len() as u16)
+            .pack_to_slice_unchecked(inner.ecat_frame_header_mut());
 
-        self.inner.set_state(FrameState::Sendable);
+        inner.set_vlan(pdu_loop.vlan());
+        inner.set_packable(pdu_loop.frame_packing());
+
+        pdu_loop.log_frame(
+            FrameDirection::Sent,
+            inner.frame_index(),
+            &inner.pdu_buf()[0..inner.pdu_payload_len()],
+        );
+
+        inner.set_state(FrameState::Sendable);
 
         ReceiveFrameFut {
-            frame: Some(self.inner),
+            frame: Some(inner),
             pdu_loop,
             timeout_timer: crate::timer_factory::timer(timeout),
             timeout,
             retries_left: retries,
+            backoff,
+            backoff_timer: None,
+            attempt: 0,
         }
     }
 
@@ -169,6 +197,48 @@ impl<'sto> CreatedFrame<'sto> {
             command_code: command.code(),
         })
     }
+
+    /// Push a PDU into this frame, writing the start of its response data directly into `dest`
+    /// when a response is received.
+    ///
+    /// The response data is not stored in the frame, so the PDU's data read from the received
+    /// frame will be the data that was sent. Only one PDU per frame can be received this way.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PduError::TooLong`] if the PDU does not fit in the frame or `dest` is longer than
+    /// the PDU data.
+    ///
+    /// # Safety
+    ///
+    /// `dest` must remain valid, and must not be accessed by anything else, until the future
+    /// returned by [`mark_sendable`](CreatedFrame::mark_sendable) completes.
+    pub(crate) unsafe fn push_pdu_direct_rx(
+        &mut self,
+        command: Command,
+        data: impl EtherCrabWireWrite,
+        len_override: Option<u16>,
+        dest: NonNull<[u8]>,
+    ) -> Result<PduResponseHandle, PduError> {
+        let pdu_start = self.inner.pdu_payload_len();
+
+        let data_len =
+            len_override.map_or(data.packed_len(), |l| usize::from(l).max(data.packed_len()));
+
+        if dest.len() > data_len {
+            return Err(PduError::TooLong);
+        }
+
+        let handle = self.push_pdu(command, data, len_override)?;
+
+        self.inner.set_direct_rx(Some(DirectRx {
+            pdu_start,
+            pdu_len: data_len as u16,
+            dest,
+        }));
+
+        Ok(handle)
+    }
 }
 
 // SAFETY: This unsafe impl is required due to `FrameBox` containing a `NonNull`, however this impl
@@ -180,6 +250,20 @@ impl<'sto> CreatedFrame<'sto> {
 // a 'static bound.
 unsafe impl<'sto> Send for CreatedFrame<'sto> {}
 
+// Frames that are marked as sendable are released by their `ReceiveFrameFut` instead.
+impl<'sto> Drop for CreatedFrame<'sto> {
+    fn drop(&mut self) {
+        fmt::trace!("Releasing unsent frame index {}", self.inner.frame_index());
+
+        // Clear the first PDU index before releasing the frame so a newly allocated frame isn't
+        // cleared by mistake.
+        self.inner.clear_first_pdu();
+        self.inner.set_state(FrameState::None);
+
+        self.storage.frame_released(self.inner.priority());
+    }
+}
+
 #[derive(Debug)]
 pub struct PduResponseHandle {
     pub index_in_frame: u8,
@@ -192,21 +276,19 @@ pub struct PduResponseHandle {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::atomic::AtomicU16;
     use crate::pdu_loop::frame_element::{AtomicFrameState, FrameElement, FIRST_PDU_EMPTY};
+    use crate::{ConfiguredAddress, PduStorage};
     use atomic_waker::AtomicWaker;
-    use core::{
-        cell::UnsafeCell,
-        ptr::NonNull,
-        sync::atomic::{AtomicU16, AtomicU8},
-    };
+    use core::{cell::UnsafeCell, ptr::NonNull};
 
     #[test]
     fn too_long() {
         let _ = env_logger::builder().is_test(true).try_init();
 
-        const BUF_LEN: usize = 16;
+        const BUF_LEN: usize = PduStorage::element_size(0);
 
-        let pdu_idx = AtomicU8::new(0);
+        let storage = PduStorage::<1, BUF_LEN>::new();
 
         let frames = UnsafeCell::new([FrameElement {
             frame_index: 0xab,
@@ -215,17 +297,26 @@ mod tests {
             ethernet_frame: [0u8; BUF_LEN],
             pdu_payload_len: 0,
             first_pdu: AtomicU16::new(FIRST_PDU_EMPTY),
+            vlan: None,
+            packable: false,
+            direct_rx: None,
+            sent_at: Duration::ZERO,
+            priority: FramePriority::Low,
         }]);
 
         let mut created = CreatedFrame::claim_created(
             unsafe { NonNull::new_unchecked(frames.get().cast()) },
             0xab,
-            &pdu_idx,
-            BUF_LEN,
+            storage.as_ref(),
+            FramePriority::Low,
         )
         .expect("Claim created");
 
-        let handle = created.push_pdu(Command::fpwr(0x1000, 0x0918).into(), [0xffu8; 9], None);
+        let handle = created.push_pdu(
+            Command::fpwr(ConfiguredAddress::new(0x1000), 0x0918).into(),
+            [0xffu8; 9],
+            None,
+        );
 
         assert_eq!(handle.unwrap_err(), PduError::TooLong);
     }
@@ -236,7 +327,7 @@ mod tests {
 
         const BUF_LEN: usize = 64;
 
-        let pdu_idx = AtomicU8::new(0);
+        let storage = PduStorage::<1, BUF_LEN>::new();
 
         let frames = UnsafeCell::new([FrameElement {
             frame_index: 0xab,
@@ -245,23 +336,40 @@ mod tests {
             ethernet_frame: [0u8; BUF_LEN],
             pdu_payload_len: 0,
             first_pdu: AtomicU16::new(FIRST_PDU_EMPTY),
+            vlan: None,
+            packable: false,
+            direct_rx: None,
+            sent_at: Duration::ZERO,
+            priority: FramePriority::Low,
         }]);
 
         let mut created = CreatedFrame::claim_created(
             unsafe { NonNull::new_unchecked(frames.get().cast()) },
             0xab,
-            &pdu_idx,
-            BUF_LEN,
+            storage.as_ref(),
+            FramePriority::Low,
         )
         .expect("Claim created");
 
-        let handle = created.push_pdu(Command::fpwr(0x1000, 0x0918).into(), (), None);
+        let handle = created.push_pdu(
+            Command::fpwr(ConfiguredAddress::new(0x1000), 0x0918).into(),
+            (),
+            None,
+        );
         assert!(handle.is_ok());
 
-        let handle = created.push_pdu(Command::fpwr(0x1001, 0x0918).into(), (), None);
+        let handle = created.push_pdu(
+            Command::fpwr(ConfiguredAddress::new(0x1001), 0x0918).into(),
+            (),
+            None,
+        );
         assert!(handle.is_ok());
 
-        let handle = created.push_pdu(Command::fpwr(0x1002, 0x0918).into(), (), None);
+        let handle = created.push_pdu(
+            Command::fpwr(ConfiguredAddress::new(0x1002), 0x0918).into(),
+            (),
+            None,
+        );
         assert!(handle.is_ok());
 
         const FLAGS_OFFSET: usize = 6;
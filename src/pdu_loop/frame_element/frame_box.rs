@@ -1,18 +1,20 @@
 use crate::{
+    atomic::{AtomicU8, Ordering},
     ethernet::{EthernetAddress, EthernetFrame},
     pdu_loop::{
-        frame_element::{FrameElement, FrameState},
+        frame_element::{DirectRx, FrameElement, FrameState},
         frame_header::EthercatFrameHeader,
+        FramePriority,
     },
-    ETHERCAT_ETHERTYPE, MASTER_ADDR,
+    VlanTag, ETHERCAT_ETHERTYPE, MASTER_ADDR,
 };
 use atomic_waker::AtomicWaker;
 use core::{
     fmt::Debug,
     marker::PhantomData,
     ptr::{addr_of, addr_of_mut, NonNull},
-    sync::atomic::{AtomicU8, Ordering},
     task::Waker,
+    time::Duration,
 };
 use ethercrab_wire::EtherCrabWireSized;
 
@@ -158,6 +160,50 @@ impl<'sto> FrameBox<'sto> {
         unsafe { *addr_of!((*self.frame.as_ptr()).pdu_payload_len) }
     }
 
+    pub fn vlan(&self) -> Option<VlanTag> {
+        unsafe { *addr_of!((*self.frame.as_ptr()).vlan) }
+    }
+
+    pub fn set_vlan(&mut self, vlan: Option<VlanTag>) {
+        unsafe { *addr_of_mut!((*self.frame.as_ptr()).vlan) = vlan };
+    }
+
+    pub fn packable(&self) -> bool {
+        unsafe { *addr_of!((*self.frame.as_ptr()).packable) }
+    }
+
+    pub fn set_packable(&mut self, packable: bool) {
+        unsafe { *addr_of_mut!((*self.frame.as_ptr()).packable) = packable };
+    }
+
+    pub fn sent_at(&self) -> Duration {
+        unsafe { *addr_of!((*self.frame.as_ptr()).sent_at) }
+    }
+
+    pub fn set_sent_at(&self, sent_at: Duration) {
+        unsafe { *addr_of_mut!((*self.frame.as_ptr()).sent_at) = sent_at };
+    }
+
+    pub fn priority(&self) -> FramePriority {
+        unsafe { *addr_of!((*self.frame.as_ptr()).priority) }
+    }
+
+    pub fn set_priority(&mut self, priority: FramePriority) {
+        unsafe { *addr_of_mut!((*self.frame.as_ptr()).priority) = priority };
+    }
+
+    pub fn direct_rx(&self) -> Option<DirectRx> {
+        unsafe { *addr_of!((*self.frame.as_ptr()).direct_rx) }
+    }
+
+    pub fn set_direct_rx(&mut self, direct_rx: Option<DirectRx>) {
+        unsafe { *addr_of_mut!((*self.frame.as_ptr()).direct_rx) = direct_rx };
+    }
+
+    pub fn state(&self) -> FrameState {
+        unsafe { FrameElement::state(self.frame) }
+    }
+
     pub fn set_state(&self, to: FrameState) {
         unsafe { FrameElement::set_state(self.frame, to) };
     }
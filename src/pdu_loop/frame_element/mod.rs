@@ -5,13 +5,20 @@ pub mod receiving_frame;
 pub mod sendable_frame;
 
 use crate::{
-    error::PduError, ethernet::EthernetFrame, fmt, pdu_loop::frame_header::EthercatFrameHeader,
+    atomic::{AtomicU16, AtomicU8, Ordering},
+    error::PduError,
+    ethernet::EthernetFrame,
+    fmt,
+    pdu_loop::{frame_header::EthercatFrameHeader, pdu_header::PduHeader, FramePriority},
+    VlanTag,
 };
 use atomic_waker::AtomicWaker;
 use core::{
+    ops::Range,
     ptr::{addr_of, addr_of_mut, NonNull},
-    sync::atomic::{AtomicU16, Ordering},
+    time::Duration,
 };
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
 use frame_box::FrameBox;
 
 /// A marker value for empty frames with no pushed PDUs.
@@ -20,9 +27,9 @@ use frame_box::FrameBox;
 pub const FIRST_PDU_EMPTY: u16 = 0xff00;
 
 /// Frame state.
-#[atomic_enum::atomic_enum]
-#[derive(PartialEq, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
 pub enum FrameState {
     // SAFETY: Because we create a bunch of `Frame`s with `MaybeUninit::zeroed`, the `None` state
     // MUST be equal to zero. All other fields in `Frame` are overridden in `replace`, so there
@@ -46,6 +53,96 @@ pub enum FrameState {
     RxDone = 6,
     /// The frame TX/RX is complete, but the frame memory is still held by calling code.
     RxProcessing = 7,
+    /// The frame's future was dropped while the frame was being sent. The TX loop releases the
+    /// frame once it is done with it.
+    Abandoned = 8,
+}
+
+impl FrameState {
+    const fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::None,
+            1 => Self::Created,
+            2 => Self::Sendable,
+            3 => Self::Sending,
+            4 => Self::Sent,
+            5 => Self::RxBusy,
+            6 => Self::RxDone,
+            7 => Self::RxProcessing,
+            8 => Self::Abandoned,
+            // Only valid states are ever stored in an `AtomicFrameState`
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// A [`FrameState`] that can be shared between threads.
+pub struct AtomicFrameState(AtomicU8);
+
+impl AtomicFrameState {
+    pub const fn new(state: FrameState) -> Self {
+        Self(AtomicU8::new(state as u8))
+    }
+
+    pub fn load(&self, order: Ordering) -> FrameState {
+        FrameState::from_u8(self.0.load(order))
+    }
+
+    pub fn store(&self, state: FrameState, order: Ordering) {
+        self.0.store(state as u8, order)
+    }
+
+    pub fn compare_exchange(
+        &self,
+        current: FrameState,
+        new: FrameState,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<FrameState, FrameState> {
+        self.0
+            .compare_exchange(current as u8, new as u8, success, failure)
+            .map(FrameState::from_u8)
+            .map_err(FrameState::from_u8)
+    }
+}
+
+impl core::fmt::Debug for AtomicFrameState {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&self.load(Ordering::SeqCst), f)
+    }
+}
+
+/// The response data of one PDU in a frame that is written straight into a buffer outside the
+/// frame, e.g. a group's PDI, saving a copy through the frame buffer.
+#[derive(Debug, Copy, Clone)]
+pub struct DirectRx {
+    /// Offset of the PDU header from the start of the frame's PDU buffer.
+    pub pdu_start: usize,
+    /// Data length of the PDU as sent.
+    pub pdu_len: u16,
+    /// Where to write the first `dest.len()` bytes of the PDU's response data.
+    pub dest: NonNull<[u8]>,
+}
+
+// SAFETY: The creator of a `DirectRx` guarantees `dest` remains valid and unaliased until the
+// frame's response future completes, regardless of which thread receives the response.
+unsafe impl Send for DirectRx {}
+
+impl DirectRx {
+    /// The range of response data in `pdus` to write to `dest`, if `pdus` contains a PDU of the
+    /// expected length at the expected position.
+    pub fn data_range(&self, pdus: &[u8]) -> Option<Range<usize>> {
+        let header = PduHeader::unpack_from_slice(pdus.get(self.pdu_start..)?).ok()?;
+
+        if header.flags.len() != self.pdu_len {
+            return None;
+        }
+
+        let start = self.pdu_start + PduHeader::PACKED_LEN;
+        let end = start + self.dest.len();
+
+        (end <= pdus.len()).then_some(start..end)
+    }
 }
 
 /// An individual frame state, PDU header config, and data buffer.
@@ -69,6 +166,8 @@ pub enum FrameState {
 //    FrameState::RxBusy -->|"Validation/processing complete\nReceivingFrame::mark_received()\nWake frame waker"| FrameState::RxDone
 //    FrameState::RxDone -->|"Wake future\nCalling code can now use response data"| FrameState::RxProcessing
 //    FrameState::RxProcessing -->|"Calling code is done with frame\nReceivedFrame::drop()"| FrameState::None
+//    FrameState::Sending -->|"Future dropped while sending\nReceiveFrameFut::drop()"| FrameState::Abandoned
+//    FrameState::Abandoned -->|"TX loop is done with frame"| FrameState::None
 //    ```
 #[derive(Debug)]
 #[repr(C)]
@@ -89,6 +188,21 @@ pub struct FrameElement<const N: usize> {
     /// whether the PDU has been set or not.
     first_pdu: AtomicU16,
 
+    /// VLAN tag to insert when sending this frame.
+    vlan: Option<VlanTag>,
+
+    /// Whether this frame may be sent in the same Ethernet frame as other frames.
+    packable: bool,
+
+    /// Response data to write directly to another buffer instead of this frame.
+    direct_rx: Option<DirectRx>,
+
+    /// When this frame was last sent, used to measure its round trip time.
+    sent_at: Duration,
+
+    /// The lane this frame is sent in.
+    priority: FramePriority,
+
     // MUST be the last element otherwise pointer arithmetic doesn't work for
     // `NonNull<FrameElement<0>>`.
     ethernet_frame: [u8; N],
@@ -102,11 +216,33 @@ impl<const N: usize> Default for FrameElement<N> {
             frame_index: 0,
             pdu_payload_len: 0,
             first_pdu: AtomicU16::new(FIRST_PDU_EMPTY),
+            vlan: None,
+            packable: false,
+            direct_rx: None,
+            sent_at: Duration::ZERO,
+            priority: FramePriority::Low,
             waker: AtomicWaker::default(),
         }
     }
 }
 
+impl FrameElement<0> {
+    /// The layout of a `FrameElement` with an Ethernet frame buffer of `len` bytes.
+    ///
+    /// This is the same as `Layout::new::<FrameElement<len>>()`, but for lengths only known at
+    /// runtime.
+    #[cfg(feature = "alloc")]
+    pub(in crate::pdu_loop) fn layout_with_data(
+        len: usize,
+    ) -> Result<core::alloc::Layout, core::alloc::LayoutError> {
+        core::alloc::Layout::from_size_align(
+            core::mem::offset_of!(FrameElement<0>, ethernet_frame) + len,
+            core::mem::align_of::<FrameElement<0>>(),
+        )
+        .map(|layout| layout.pad_to_align())
+    }
+}
+
 impl<const N: usize> FrameElement<N> {
     /// Get pointer to entire data: the Ethernet frame including header and all subsequent EtherCAT
     /// payload.
@@ -135,6 +271,13 @@ impl<const N: usize> FrameElement<N> {
         (*addr_of_mut!((*fptr).status)).store(state, Ordering::Release);
     }
 
+    /// Get the frame's current state.
+    unsafe fn state(this: NonNull<FrameElement<N>>) -> FrameState {
+        let fptr = this.as_ptr();
+
+        (*addr_of!((*fptr).status)).load(Ordering::Acquire)
+    }
+
     /// Atomically swap the frame state from `from` to `to`.
     ///
     /// If the frame is not currently in the given `from` state, this method will return an error
@@ -180,6 +323,12 @@ impl<const N: usize> FrameElement<N> {
 
         (*addr_of_mut!((*this.as_ptr()).frame_index)) = frame_index;
         (*addr_of_mut!((*this.as_ptr()).pdu_payload_len)) = 0;
+        // Storage is zeroed, which isn't necessarily a valid `Option`, so write without reading
+        addr_of_mut!((*this.as_ptr()).vlan).write(None);
+        addr_of_mut!((*this.as_ptr()).packable).write(false);
+        addr_of_mut!((*this.as_ptr()).direct_rx).write(None);
+        addr_of_mut!((*this.as_ptr()).sent_at).write(Duration::ZERO);
+        addr_of_mut!((*this.as_ptr()).priority).write(FramePriority::Low);
 
         Ok(this)
     }
@@ -191,7 +340,8 @@ impl<const N: usize> FrameElement<N> {
     unsafe fn claim_receiving(this: NonNull<FrameElement<N>>) -> Option<NonNull<FrameElement<N>>> {
         Self::swap_state(this, FrameState::Sent, FrameState::RxBusy)
             .map_err(|actual_state| {
-                fmt::error!(
+                // Expected for late or duplicated responses, which are ignored
+                fmt::debug!(
                     "Failed to claim receiving frame {}: expected state {:?}, but got {:?}",
                     (*addr_of_mut!((*this.as_ptr()).frame_index)),
                     FrameState::Sent,
@@ -216,7 +366,19 @@ impl<const N: usize> FrameElement<N> {
         u16::from(search) == raw
     }
 
-    /// If no PDUs are present in the frame, set the first PDU index to the given value.
+    /// The index of the first PDU in the frame, if it has been set.
+    /// Whether a claimed frame holds `search` as its first PDU index.
+    ///
+    /// Unclaimed frames are skipped as their `first_pdu` may be stale, or zero if they've never
+    /// been used.
+    pub(in crate::pdu_loop) unsafe fn first_pdu_in_use(
+        this: NonNull<FrameElement<0>>,
+        search: u8,
+    ) -> bool {
+        (*addr_of!((*this.as_ptr()).status)).load(Ordering::Acquire) != FrameState::None
+            && Self::first_pdu_is(this, search)
+    }
+
     unsafe fn set_first_pdu(this: NonNull<FrameElement<0>>, value: u8) {
         let first_pdu = &mut *addr_of_mut!((*this.as_ptr()).first_pdu);
 
@@ -242,9 +404,41 @@ impl<const N: usize> FrameElement<N> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::atomic::AtomicU16;
     use crate::pdu_loop::frame_element::{AtomicFrameState, FrameElement, FIRST_PDU_EMPTY};
     use atomic_waker::AtomicWaker;
-    use core::{ptr::NonNull, sync::atomic::AtomicU16};
+    use core::ptr::NonNull;
+
+    #[test]
+    fn atomic_frame_state() {
+        let state = AtomicFrameState::new(FrameState::Sent);
+
+        assert_eq!(
+            state.compare_exchange(
+                FrameState::None,
+                FrameState::Created,
+                Ordering::AcqRel,
+                Ordering::Relaxed
+            ),
+            Err(FrameState::Sent)
+        );
+
+        assert_eq!(
+            state.compare_exchange(
+                FrameState::Sent,
+                FrameState::RxBusy,
+                Ordering::AcqRel,
+                Ordering::Relaxed
+            ),
+            Ok(FrameState::Sent)
+        );
+
+        assert_eq!(state.load(Ordering::Acquire), FrameState::RxBusy);
+
+        state.store(FrameState::RxProcessing, Ordering::Release);
+
+        assert_eq!(state.load(Ordering::Acquire), FrameState::RxProcessing);
+    }
 
     #[test]
     fn set_first_pdu_only_once() {
@@ -259,6 +453,11 @@ mod tests {
             ethernet_frame: [0u8; BUF_LEN],
             pdu_payload_len: 0,
             first_pdu: AtomicU16::new(FIRST_PDU_EMPTY),
+            vlan: None,
+            packable: false,
+            direct_rx: None,
+            sent_at: Duration::ZERO,
+            priority: FramePriority::Low,
         };
 
         let frame_ptr = NonNull::from(&frame);
@@ -282,6 +481,11 @@ mod tests {
             ethernet_frame: [0u8; BUF_LEN],
             pdu_payload_len: 0,
             first_pdu: AtomicU16::new(FIRST_PDU_EMPTY),
+            vlan: None,
+            packable: false,
+            direct_rx: None,
+            sent_at: Duration::ZERO,
+            priority: FramePriority::Low,
         };
 
         let frame_ptr = NonNull::from(&frame);
@@ -305,6 +509,11 @@ mod tests {
             ethernet_frame: [0u8; BUF_LEN],
             pdu_payload_len: 0,
             first_pdu: AtomicU16::new(FIRST_PDU_EMPTY),
+            vlan: None,
+            packable: false,
+            direct_rx: None,
+            sent_at: Duration::ZERO,
+            priority: FramePriority::Low,
         };
 
         let frame_ptr = NonNull::from(&frame);
@@ -330,6 +539,11 @@ mod tests {
             ethernet_frame: [0u8; BUF_LEN],
             pdu_payload_len: 0,
             first_pdu: AtomicU16::new(FIRST_PDU_EMPTY),
+            vlan: None,
+            packable: false,
+            direct_rx: None,
+            sent_at: Duration::ZERO,
+            priority: FramePriority::Low,
         };
 
         let frame_ptr_0 = NonNull::from(&frame_0);
@@ -345,6 +559,11 @@ mod tests {
             ethernet_frame: [0u8; BUF_LEN],
             pdu_payload_len: 0,
             first_pdu: AtomicU16::new(FIRST_PDU_EMPTY),
+            vlan: None,
+            packable: false,
+            direct_rx: None,
+            sent_at: Duration::ZERO,
+            priority: FramePriority::Low,
         };
 
         let frame_ptr_1 = NonNull::from(&frame_1);
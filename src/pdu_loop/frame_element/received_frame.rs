@@ -1,13 +1,16 @@
 use crate::{
+    command::Command,
     error::{Error, PduError},
     fmt,
+    parse::split_pdu,
     pdu_loop::{
         frame_element::{created_frame::PduResponseHandle, FrameBox, FrameState},
         pdu_header::PduHeader,
+        PduLoop,
     },
 };
 use core::{marker::PhantomData, ops::Deref, ptr::NonNull};
-use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
+use ethercrab_wire::EtherCrabWireSized;
 
 /// A frame element where response data has been received from the EtherCAT network.
 ///
@@ -16,54 +19,36 @@ use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
 #[derive(Debug)]
 pub struct ReceivedFrame<'sto> {
     pub(in crate::pdu_loop::frame_element) inner: FrameBox<'sto>,
+    /// Used to wake tasks waiting for a free frame once this frame is released.
+    pdu_loop: &'sto PduLoop<'sto>,
 }
 
 impl<'sto> ReceivedFrame<'sto> {
-    pub(in crate::pdu_loop) fn new(inner: FrameBox<'sto>) -> ReceivedFrame<'sto> {
-        Self { inner }
+    pub(in crate::pdu_loop) fn new(
+        inner: FrameBox<'sto>,
+        pdu_loop: &'sto PduLoop<'sto>,
+    ) -> ReceivedFrame<'sto> {
+        Self { inner, pdu_loop }
     }
 
     pub fn first_pdu(self, handle: PduResponseHandle) -> Result<ReceivedPdu<'sto>, Error> {
-        let buf = self.inner.pdu_buf();
+        let pdu = split_pdu(self.inner.pdu_buf())?;
 
-        let pdu_header = PduHeader::unpack_from_slice(buf)?;
-
-        let payload_len = usize::from(pdu_header.flags.len());
-
-        // If buffer isn't long enough to hold payload and WKC, this is probably a corrupt PDU or
-        // someone is committing epic haxx.
-        if buf.len() < payload_len + 2 {
-            return Err(Error::Pdu(PduError::TooLong));
-        }
-
-        if pdu_header.command_code != handle.command_code {
+        if pdu.header.command_code != handle.command_code {
             return Err(Error::Pdu(PduError::Decode));
         }
 
-        if pdu_header.index != handle.pdu_idx {
-            return Err(Error::Pdu(PduError::InvalidIndex(pdu_header.index)));
+        if pdu.header.index != handle.pdu_idx {
+            return Err(Error::Pdu(PduError::InvalidIndex(pdu.header.index)));
         }
 
-        let payload_ptr = unsafe {
-            NonNull::new_unchecked(
-                buf.get(PduHeader::PACKED_LEN..)
-                    .ok_or(Error::Internal)?
-                    .as_ptr()
-                    .cast_mut(),
-            )
-        };
+        let mut received = ReceivedPdu::new(pdu.data, pdu.working_counter);
 
-        let working_counter = u16::unpack_from_slice(
-            buf.get((PduHeader::PACKED_LEN + payload_len)..)
-                .ok_or(Error::Internal)?,
-        )?;
+        // The response data points into this frame, so it must not be released and reused by
+        // another task, possibly on another thread, until the response is dropped.
+        received.frame = Some(self);
 
-        Ok(ReceivedPdu {
-            data_start: payload_ptr,
-            len: payload_len,
-            working_counter,
-            _storage: PhantomData,
-        })
+        Ok(received)
     }
 
     pub fn pdu<'pdu>(&'sto self, handle: PduResponseHandle) -> Result<ReceivedPdu<'pdu>, Error>
@@ -74,53 +59,21 @@ impl<'sto> ReceivedFrame<'sto> {
 
         // Skip over any preceding PDUs
         for _ in 0..handle.index_in_frame {
-            let pdu_header = PduHeader::unpack_from_slice(buf)?;
-            let payload_len = usize::from(pdu_header.flags.len());
-            let this_pdu_len = PduHeader::PACKED_LEN + payload_len + 2;
-
             // Start buffer at beginning of next PDU
-            buf = buf.get(this_pdu_len..).ok_or(Error::Internal)?;
+            buf = split_pdu(buf)?.rest;
         }
 
-        // This checks for buffer min length
-        let pdu_header = PduHeader::unpack_from_slice(buf)?;
+        let pdu = split_pdu(buf)?;
 
-        if pdu_header.command_code != handle.command_code {
+        if pdu.header.command_code != handle.command_code {
             return Err(Error::Pdu(PduError::Decode));
         }
 
-        if pdu_header.index != handle.pdu_idx {
-            return Err(Error::Pdu(PduError::InvalidIndex(pdu_header.index)));
-        }
-
-        let payload_len = usize::from(pdu_header.flags.len());
-
-        // If buffer isn't long enough to hold payload and WKC, this is probably a corrupt PDU or
-        // someone is committing epic haxx.
-        if buf.len() < payload_len + 2 {
-            return Err(Error::Pdu(PduError::TooLong));
+        if pdu.header.index != handle.pdu_idx {
+            return Err(Error::Pdu(PduError::InvalidIndex(pdu.header.index)));
         }
 
-        let payload_ptr = unsafe {
-            NonNull::new_unchecked(
-                buf.get(PduHeader::PACKED_LEN..)
-                    .ok_or(Error::Internal)?
-                    .as_ptr()
-                    .cast_mut(),
-            )
-        };
-
-        let working_counter = u16::unpack_from_slice(
-            buf.get((PduHeader::PACKED_LEN + payload_len)..)
-                .ok_or(Error::Internal)?,
-        )?;
-
-        Ok(ReceivedPdu {
-            data_start: payload_ptr,
-            len: payload_len,
-            working_counter,
-            _storage: PhantomData,
-        })
+        Ok(ReceivedPdu::new(pdu.data, pdu.working_counter))
     }
 
     pub fn into_iter(self) -> ReceivedPduIter<'sto> {
@@ -141,6 +94,8 @@ impl<'sto> Drop for ReceivedFrame<'sto> {
 
         // Set frame empty sentinel so we don't get false-positive matches when receiving frames
         self.inner.clear_first_pdu();
+
+        self.pdu_loop.frame_released(self.inner.priority());
     }
 }
 
@@ -156,42 +111,17 @@ impl<'sto> Iterator for ReceivedPduIter<'sto> {
     fn next(&mut self) -> Option<Self::Item> {
         let buf = self.frame.inner.pdu_buf().get(self.buf_pos..)?;
 
-        let pdu_header = match PduHeader::unpack_from_slice(buf) {
-            Ok(h) => h,
-            Err(e) => return Some(Err(e.into())),
-        };
-
-        let payload_len = usize::from(pdu_header.flags.len());
-        let this_pdu_len = PduHeader::PACKED_LEN + payload_len + 2;
-
-        // If buffer isn't long enough to hold payload and WKC, this is probably a corrupt PDU or
-        // someone is committing epic haxx.
-        if buf.len() < payload_len + 2 {
-            return Some(Err(Error::Pdu(PduError::TooLong)));
-        }
-
-        let payload_ptr = unsafe {
-            NonNull::new_unchecked(buf.get(PduHeader::PACKED_LEN..)?.as_ptr().cast_mut())
-        };
-
-        let working_counter = match buf
-            .get((PduHeader::PACKED_LEN + payload_len)..)
-            .ok_or(Error::Internal)
-            .and_then(|b| u16::unpack_from_slice(b).map_err(Error::from))
-        {
-            Ok(wkc) => wkc,
+        let pdu = match split_pdu(buf) {
+            Ok(pdu) => pdu,
             Err(e) => return Some(Err(e)),
         };
 
-        let res = Ok(ReceivedPdu {
-            data_start: payload_ptr,
-            len: payload_len,
-            working_counter,
-            _storage: PhantomData,
-        });
+        let this_pdu_len = PduHeader::PACKED_LEN + pdu.data.len() + 2;
+
+        let res = Ok(ReceivedPdu::new(pdu.data, pdu.working_counter));
 
         // Update buffer pos for next iteration if there are more PDUs to come
-        if pdu_header.flags.more_follows {
+        if pdu.header.flags.more_follows {
             self.buf_pos += this_pdu_len;
         }
         // No more frames, so quit the next time round by trying to read way off the end of the
@@ -209,34 +139,43 @@ pub struct ReceivedPdu<'sto> {
     data_start: NonNull<u8>,
     len: usize,
     pub(crate) working_counter: u16,
+    /// The frame holding the response data, if this PDU owns it.
+    frame: Option<ReceivedFrame<'sto>>,
     _storage: PhantomData<&'sto ()>,
 }
 
 impl<'sto> ReceivedPdu<'sto> {
-    pub fn len(&self) -> usize {
-        self.len
+    /// `data` must point into the PDU storage borrowed for `'sto`.
+    fn new(data: &[u8], working_counter: u16) -> Self {
+        Self {
+            data_start: NonNull::from(data).cast(),
+            len: data.len(),
+            working_counter,
+            frame: None,
+            _storage: PhantomData,
+        }
     }
 
-    pub fn trim_front(&mut self, ct: usize) {
-        let ct = ct.min(self.len());
-
-        self.data_start = unsafe { NonNull::new_unchecked(self.data_start.as_ptr().add(ct)) };
+    pub fn len(&self) -> usize {
+        self.len
     }
 
-    pub fn wkc(self, expected: u16) -> Result<Self, Error> {
+    /// Check the working counter, adding the command that was sent to any error.
+    pub fn wkc(self, expected: u16, command: Command) -> Result<Self, Error> {
         if self.working_counter == expected {
             Ok(self)
         } else {
             Err(Error::WorkingCounter {
                 expected,
                 received: self.working_counter,
+                command: command.into(),
             })
         }
     }
 
-    pub fn maybe_wkc(self, expected: Option<u16>) -> Result<Self, Error> {
+    pub fn maybe_wkc(self, expected: Option<u16>, command: Command) -> Result<Self, Error> {
         match expected {
-            Some(expected) => self.wkc(expected),
+            Some(expected) => self.wkc(expected, command),
             None => Ok(self),
         }
     }
@@ -1,10 +1,15 @@
 use crate::{
+    atomic::AtomicU8,
     error::{Error, PduError},
     fmt,
-    pdu_loop::frame_element::{received_frame::ReceivedFrame, FrameBox, FrameElement, FrameState},
-    PduLoop,
+    pdu_loop::{
+        frame_element::{received_frame::ReceivedFrame, FrameBox, FrameElement, FrameState},
+        pdu_header::PduHeader,
+    },
+    FrameDirection, PduLoop, RetryBackoff,
 };
-use core::{future::Future, ptr::NonNull, sync::atomic::AtomicU8, task::Poll, time::Duration};
+use core::{future::Future, ptr::NonNull, task::Poll, time::Duration};
+use ethercrab_wire::EtherCrabWireRead;
 use futures_lite::FutureExt;
 
 /// A frame has been sent and is now waiting for a response from the network.
@@ -32,7 +37,11 @@ impl<'sto> ReceivingFrame<'sto> {
     ///
     /// This method may only be called once the frame response (header and data) has been validated
     /// and stored in the frame element.
-    pub(in crate::pdu_loop) fn mark_received(&self) -> Result<(), PduError> {
+    ///
+    /// If the frame's future has no waker registered and `keep_unwoken` is set, the frame is left
+    /// received for the future to pick up the next time it is polled instead of returning
+    /// [`PduError::NoWaker`].
+    pub(in crate::pdu_loop) fn mark_received(&self, keep_unwoken: bool) -> Result<(), PduError> {
         // Frame state must be updated BEFORE the waker is awoken so the future impl returns
         // `Poll::Ready`. The future will poll, see the `FrameState` as RxDone and return
         // Poll::Ready.
@@ -54,6 +63,17 @@ impl<'sto> ReceivingFrame<'sto> {
         // If the wake fails, release the receiving claim so the frame receive can possibly be
         // reattempted at a later time.
         if let Err(()) = self.inner.wake() {
+            // The future checks for `RxDone` after registering its waker every time it is polled,
+            // so it can't miss the response.
+            if keep_unwoken {
+                fmt::trace!(
+                    "Frame {:#04x} has no waker, leaving it for the next poll",
+                    self.frame_index()
+                );
+
+                return Ok(());
+            }
+
             fmt::trace!("Failed to wake frame {:#04x}: no waker", self.frame_index());
 
             // Restore frame state to `Sent`, which is what `PduStorageRef::claim_receiving`
@@ -67,24 +87,14 @@ impl<'sto> ReceivingFrame<'sto> {
             match self.inner.swap_state(FrameState::RxDone, FrameState::Sent) {
                 Ok(()) => (),
                 // Frame is being processed. We don't need to retry the receive
-                Err(bad_state)
-                    if matches!(bad_state, FrameState::RxProcessing | FrameState::None) =>
-                {
+                //
+                // The future may also have been dropped in the meantime, releasing the frame,
+                // after which it may even have been claimed again for another request.
+                Err(bad_state) => {
                     fmt::trace!("--> Frame is {:?}, no need to wake", bad_state);
 
                     return Ok(());
                 }
-                Err(bad_state) => {
-                    fmt::error!(
-                        "Failed to set frame {:#04x} state from RxDone -> Sent, got {:?}",
-                        self.frame_index(),
-                        bad_state
-                    );
-
-                    // Logic bug if the swap failed - no other threads should be using this
-                    // frame, and the code just above this block sets the state to `RxDone`.
-                    unreachable!();
-                }
             }
 
             Err(PduError::NoWaker)
@@ -93,12 +103,88 @@ impl<'sto> ReceivingFrame<'sto> {
         }
     }
 
-    pub(in crate::pdu_loop) fn buf_mut(&mut self) -> &mut [u8] {
-        self.inner.pdu_buf_mut()
+    /// The length of the PDUs sent in this frame.
+    /// When this frame was last sent.
+    pub(in crate::pdu_loop) fn sent_at(&self) -> Duration {
+        self.inner.sent_at()
+    }
+
+    pub(in crate::pdu_loop) fn pdu_payload_len(&self) -> usize {
+        self.inner.pdu_payload_len()
+    }
+
+    /// Store the PDUs of a received frame.
+    ///
+    /// If the frame has a [`DirectRx`](super::DirectRx) target and the response PDU has the
+    /// expected length, its response data is written to the target instead of the frame buffer.
+    pub(in crate::pdu_loop) fn write_response(&mut self, pdus: &[u8]) -> Result<(), Error> {
+        let direct = self
+            .inner
+            .direct_rx()
+            .and_then(|direct| Some((direct.dest, direct.data_range(pdus)?)));
+
+        let buf = self
+            .inner
+            .pdu_buf_mut()
+            .get_mut(0..pdus.len())
+            .ok_or(Error::Internal)?;
+
+        let Some((dest, range)) = direct else {
+            buf.copy_from_slice(pdus);
+
+            return Ok(());
+        };
+
+        buf[0..range.start].copy_from_slice(&pdus[0..range.start]);
+        buf[range.end..].copy_from_slice(&pdus[range.end..]);
+
+        // SAFETY: The creator of the `DirectRx` guarantees `dest` is valid and unaliased while the
+        // frame is in flight. `data_range` checks the range is the same length as `dest`.
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                pdus[range].as_ptr(),
+                dest.as_ptr().cast::<u8>(),
+                dest.len(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Whether the first PDU in `pdus` is the response to the first PDU sent in this frame.
+    ///
+    /// The 8 bit PDU index wraps, so a late or duplicated response to an earlier frame may have
+    /// the same index. Such a response is detected by a different command, address or length.
+    pub(in crate::pdu_loop) fn is_response(&self, pdus: &[u8]) -> bool {
+        let (Ok(sent), Ok(received)) = (
+            PduHeader::unpack_from_slice(self.inner.pdu_buf()),
+            PduHeader::unpack_from_slice(pdus),
+        ) else {
+            return false;
+        };
+
+        // SubDevices increment the position address of auto increment and broadcast commands, so
+        // only the register address, or the upper half of a logical address, is compared.
+        sent.command_code == received.command_code
+            && sent.index == received.index
+            && sent.command_raw[2..] == received.command_raw[2..]
+            && sent.flags.len() == received.flags.len()
+    }
+
+    /// Give the frame back to its future to wait for the correct response, if `pdus` was not a
+    /// response to this frame.
+    pub(in crate::pdu_loop) fn release_unmatched(self) {
+        if let Err(bad) = self.inner.swap_state(FrameState::RxBusy, FrameState::Sent) {
+            fmt::error!(
+                "Failed to set frame {:#04x} state from RxBusy -> Sent, got {:?}",
+                self.frame_index(),
+                bad
+            );
+        }
     }
 
     /// Ethernet frame index.
-    fn frame_index(&self) -> u8 {
+    pub(in crate::pdu_loop) fn frame_index(&self) -> u8 {
         self.inner.frame_index()
     }
 }
@@ -109,6 +195,11 @@ pub struct ReceiveFrameFut<'sto> {
     pub(in crate::pdu_loop::frame_element) timeout_timer: crate::timer_factory::Timer,
     pub(in crate::pdu_loop::frame_element) timeout: Duration,
     pub(in crate::pdu_loop::frame_element) retries_left: usize,
+    pub(in crate::pdu_loop::frame_element) backoff: RetryBackoff,
+    /// Set while waiting to resend a timed out frame.
+    pub(in crate::pdu_loop::frame_element) backoff_timer: Option<crate::timer_factory::Timer>,
+    /// Number of resends performed so far, used to compute the backoff delay.
+    pub(in crate::pdu_loop::frame_element) attempt: u32,
 }
 
 impl<'sto> ReceiveFrameFut<'sto> {
@@ -128,9 +219,61 @@ impl<'sto> ReceiveFrameFut<'sto> {
         &b.into_inner()[0..len]
     }
 
-    fn release(r: FrameBox<'sto>) {
-        // Make frame available for reuse if this future is dropped.
-        r.set_state(FrameState::None);
+    /// Mark the frame as sendable again and restart the response timeout.
+    fn resend(&mut self, rxin: &FrameBox<'sto>, cx: &mut core::task::Context<'_>) {
+        // Assign new timeout
+        self.timeout_timer = crate::timer_factory::timer(self.timeout);
+        // Poll timer once to register with the executor
+        let _ = self.timeout_timer.poll(cx);
+
+        // Mark frame as sendable once more. If the frame is still queued or being sent, or a
+        // response is being received, there is nothing to resend.
+        if let Err(state) = rxin.swap_state(FrameState::Sent, FrameState::Sendable) {
+            fmt::trace!(
+                "Frame index {} is {:?}, not resending",
+                rxin.frame_index(),
+                state
+            );
+
+            return;
+        }
+
+        // Wake frame sender so it picks up this frame we've just marked
+        self.pdu_loop.wake_sender();
+    }
+
+    fn release(&self, r: FrameBox<'sto>) {
+        // Make frame available for reuse if this future is dropped. Response data may be written
+        // directly into a buffer borrowed by the caller, so any receive in progress must finish
+        // before the frame, and with it the buffer, is released. A frame that is being sent is
+        // still in use by the TX loop, so it is marked as abandoned for the TX loop to release
+        // once it's done.
+        //
+        // Responses can't be matched to this frame any more once its first PDU index is cleared,
+        // and a frame reallocated after the release below must not have its index cleared.
+        r.clear_first_pdu();
+
+        let released = loop {
+            let current = r.state();
+
+            let to = match current {
+                FrameState::RxBusy => {
+                    core::hint::spin_loop();
+
+                    continue;
+                }
+                FrameState::Sending => FrameState::Abandoned,
+                _ => FrameState::None,
+            };
+
+            if r.swap_state(current, to).is_ok() {
+                break to == FrameState::None;
+            }
+        };
+
+        if released {
+            self.pdu_loop.frame_released(r.priority());
+        }
     }
 }
 
@@ -167,48 +310,66 @@ impl<'sto> Future for ReceiveFrameFut<'sto> {
             Ok(_) => {
                 fmt::trace!("frame index {} is ready", frame_idx);
 
-                return Poll::Ready(Ok(ReceivedFrame::new(rxin)));
+                self.pdu_loop.log_frame(
+                    FrameDirection::Received,
+                    frame_idx,
+                    &rxin.pdu_buf()[0..rxin.pdu_payload_len()],
+                );
+
+                return Poll::Ready(Ok(ReceivedFrame::new(rxin, self.pdu_loop)));
             }
             Err(e) => e,
         };
 
         fmt::trace!("frame index {} not ready yet ({:?})", frame_idx, was);
 
+        if let Some(backoff_timer) = self.backoff_timer.as_mut() {
+            // Waiting to resend a timed out frame. A late response can still be picked up above.
+            if backoff_timer.poll(cx).is_ready() {
+                self.backoff_timer = None;
+
+                self.resend(&rxin, cx);
+            }
+        }
         // Timeout checked after frame handling so we get at least one chance to receive reply from
         // network. This should mitigate race conditions when timeout expires just as the frame is
         // received.
-        match self.timeout_timer.poll(cx) {
-            Poll::Ready(_) => {
-                // We timed out
-                fmt::trace!(
-                    "PDU response timeout with {} retries remaining",
-                    self.retries_left
-                );
+        else if self.timeout_timer.poll(cx).is_ready() {
+            // We timed out
+            fmt::trace!(
+                "PDU response timeout with {} retries remaining",
+                self.retries_left
+            );
 
-                if self.retries_left == 0 {
-                    // Release frame and PDU slots for reuse
-                    Self::release(rxin);
+            if self.retries_left == 0 {
+                self.pdu_loop.record_timeout();
 
-                    return Poll::Ready(Err(Error::Timeout));
-                }
+                // Release frame and PDU slots for reuse
+                self.release(rxin);
 
-                // If we have retry loops left:
+                return Poll::Ready(Err(Error::Timeout));
+            }
 
-                // Assign new timeout
-                self.timeout_timer = crate::timer_factory::timer(self.timeout);
-                // Poll timer once to register with the executor
-                let _ = self.timeout_timer.poll(cx);
+            // If we have retry loops left:
+            self.retries_left -= 1;
 
-                // Mark frame as sendable once more
-                rxin.set_state(FrameState::Sendable);
-                // Wake frame sender so it picks up this frame we've just marked
-                self.pdu_loop.wake_sender();
+            self.pdu_loop.record_retry();
 
-                self.retries_left -= 1;
-            }
-            Poll::Pending => {
-                // Haven't timed out yet. Nothing to do - still waiting to be woken from the network
-                // response.
+            let delay = self.backoff.delay(self.attempt);
+
+            self.attempt = self.attempt.saturating_add(1);
+
+            if delay.is_zero() {
+                self.resend(&rxin, cx);
+            } else {
+                fmt::trace!("Resending frame index {} in {:?}", frame_idx, delay);
+
+                let mut backoff_timer = crate::timer_factory::timer(delay);
+
+                // Poll timer once to register with the executor
+                let _ = backoff_timer.poll(cx);
+
+                self.backoff_timer = Some(backoff_timer);
             }
         }
 
@@ -236,7 +397,7 @@ impl<'sto> Drop for ReceiveFrameFut<'sto> {
         if let Some(r) = self.frame.take() {
             fmt::debug!("Dropping in-flight future, possibly caused by timeout");
 
-            Self::release(r);
+            self.release(r);
         }
     }
 }
@@ -1,14 +1,28 @@
 use crate::{
-    error::Error,
-    ethernet::EthernetFrame,
+    error::{saturate_u16, Error, PduError},
+    ethernet::{EthernetFrame, VLAN_ETHERTYPE, VLAN_TAG_LEN},
     fmt,
     pdu_loop::{
         frame_element::{FrameBox, FrameElement, FrameState},
         frame_header::EthercatFrameHeader,
+        pdu_flags::PduFlags,
+        pdu_header::PduHeader,
+        storage::PduStorageRef,
+        FramePriority, FrameTap,
     },
 };
-use core::{ptr::NonNull, sync::atomic::AtomicU8};
-use ethercrab_wire::EtherCrabWireSized;
+use core::ptr::NonNull;
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized, EtherCrabWireWrite};
+
+/// The largest untagged frame that frames are packed into. FCS is not included.
+const MAX_PACKED_FRAME_LEN: usize = 1514;
+
+/// The largest VLAN tagged frame that can be sent: a standard 1514 byte Ethernet frame plus the
+/// tag. FCS is not included.
+const MAX_TAGGED_FRAME_LEN: usize = MAX_PACKED_FRAME_LEN + VLAN_TAG_LEN;
+
+/// The maximum number of other frames that can be packed into one sent frame.
+const MAX_PACKED_FRAMES: usize = 8;
 
 /// An EtherCAT frame that is ready to be sent over the network.
 ///
@@ -47,9 +61,22 @@ use ethercrab_wire::EtherCrabWireSized;
 ///     Poll::<()>::Pending
 /// });
 /// ```
-#[derive(Debug)]
 pub struct SendableFrame<'sto> {
     pub(in crate::pdu_loop) inner: FrameBox<'sto>,
+    /// Other frames whose PDUs are sent after this frame's PDUs in the same Ethernet frame.
+    packed: heapless::Vec<FrameBox<'sto>, MAX_PACKED_FRAMES>,
+    storage: PduStorageRef<'sto>,
+    tap: Option<&'sto dyn FrameTap>,
+}
+
+impl core::fmt::Debug for SendableFrame<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SendableFrame")
+            .field("inner", &self.inner)
+            .field("packed", &self.packed)
+            .field("statistics", &self.storage.statistics)
+            .finish_non_exhaustive()
+    }
 }
 
 unsafe impl<'sto> Send for SendableFrame<'sto> {}
@@ -57,21 +84,84 @@ unsafe impl<'sto> Send for SendableFrame<'sto> {}
 impl<'sto> SendableFrame<'sto> {
     pub(crate) fn claim_sending(
         frame: NonNull<FrameElement<0>>,
-        pdu_idx: &'sto AtomicU8,
-        frame_data_len: usize,
+        storage: PduStorageRef<'sto>,
+        tap: Option<&'sto dyn FrameTap>,
     ) -> Option<Self> {
         let frame = unsafe { FrameElement::claim_sending(frame)? };
 
         Some(Self {
-            inner: FrameBox::new(frame, pdu_idx, frame_data_len),
+            inner: FrameBox::new(frame, storage.pdu_idx, storage.frame_data_len),
+            packed: heapless::Vec::new(),
+            storage,
+            tap,
         })
     }
 
+    /// Send the PDUs of `other` in the same Ethernet frame as this one.
+    ///
+    /// `other` is returned if either frame doesn't allow packing, or if the combined frame would
+    /// be too long.
+    pub(in crate::pdu_loop) fn pack(&mut self, other: Self) -> Result<(), Self> {
+        if !self.inner.packable() || !other.inner.packable() || !other.packed.is_empty() {
+            return Err(other);
+        }
+
+        let packed_len = self.as_bytes().len()
+            + self
+                .packed
+                .iter()
+                .map(FrameBox::pdu_payload_len)
+                .sum::<usize>()
+            + other.inner.pdu_payload_len();
+
+        if packed_len > MAX_PACKED_FRAME_LEN {
+            return Err(other);
+        }
+
+        match self.packed.push(other.inner) {
+            Ok(()) => {
+                fmt::trace!(
+                    "Packed frame index {} into frame index {}",
+                    other.inner.frame_index(),
+                    self.inner.frame_index()
+                );
+
+                Ok(())
+            }
+            Err(_) => Err(other),
+        }
+    }
+
+    /// Whether no more frames can be packed into this one.
+    pub(in crate::pdu_loop) fn is_full(&self) -> bool {
+        !self.inner.packable() || self.packed.is_full()
+    }
+
     /// The frame has been sent by the network driver.
-    fn mark_sent(&self) {
+    fn mark_sent(&self, sent: &[u8]) {
         fmt::trace!("Frame index {} is sent", self.inner.frame_index());
 
-        self.inner.set_state(FrameState::Sent);
+        // Must be written before the frame is marked as sent so the receiver sees it
+        let sent_at = crate::timer_factory::now();
+
+        self.inner.set_sent_at(sent_at);
+        self.finish_sending(&self.inner, FrameState::Sent);
+
+        for packed in self.packed.iter() {
+            packed.set_sent_at(sent_at);
+            self.finish_sending(packed, FrameState::Sent);
+        }
+
+        self.storage.statistics.record_frame_sent();
+
+        if let Some(tap) = self.tap {
+            tap.on_frame_sent(sent, sent_at);
+        }
+    }
+
+    /// The lane this frame is sent in.
+    pub(in crate::pdu_loop) fn priority(&self) -> FramePriority {
+        self.inner.priority()
     }
 
     pub(crate) fn index(&self) -> u8 {
@@ -80,8 +170,41 @@ impl<'sto> SendableFrame<'sto> {
 
     /// Used on send failure to release the frame sending claim so the frame can attempt to be sent
     /// again, or reclaimed for reuse.
-    fn release_sending_claim(&self) {
-        self.inner.set_state(FrameState::Sendable);
+    pub(in crate::pdu_loop) fn release_sending_claim(&self) {
+        self.finish_sending(&self.inner, FrameState::Sendable);
+
+        for packed in self.packed.iter() {
+            self.finish_sending(packed, FrameState::Sendable);
+        }
+    }
+
+    /// Move a frame out of the `Sending` state.
+    ///
+    /// If the frame's future was dropped while the frame was being sent, nothing is waiting for
+    /// the frame any more so it is released for reuse instead.
+    fn finish_sending(&self, frame: &FrameBox<'sto>, to: FrameState) {
+        match frame.swap_state(FrameState::Sending, to) {
+            Ok(()) => (),
+            Err(FrameState::Abandoned) => {
+                fmt::trace!(
+                    "Frame index {} was abandoned while sending, releasing it",
+                    frame.frame_index()
+                );
+
+                frame.clear_first_pdu();
+                frame.set_state(FrameState::None);
+
+                self.storage.frame_released(frame.priority());
+            }
+            Err(bad) => {
+                fmt::error!(
+                    "Failed to set frame {:#04x} state from Sending -> {:?}, got {:?}",
+                    frame.frame_index(),
+                    to,
+                    bad
+                );
+            }
+        }
     }
 
     fn as_bytes(&self) -> &[u8] {
@@ -94,10 +217,23 @@ impl<'sto> SendableFrame<'sto> {
         &frame[0..len]
     }
 
-    /// Get the Ethernet frame length of this frame.
+    /// Get the Ethernet frame length of this frame, including its VLAN tag if one is configured
+    /// and the PDUs of any frames packed into it.
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
-        self.as_bytes().len()
+        let tag_len = if self.inner.vlan().is_some() {
+            VLAN_TAG_LEN
+        } else {
+            0
+        };
+
+        let packed_len = self
+            .packed
+            .iter()
+            .map(FrameBox::pdu_payload_len)
+            .sum::<usize>();
+
+        self.as_bytes().len() + tag_len + packed_len
     }
 
     /// Send the frame using a blocking callback.
@@ -108,11 +244,85 @@ impl<'sto> SendableFrame<'sto> {
         self,
         send: impl FnOnce(&[u8]) -> Result<usize, Error>,
     ) -> Result<usize, Error> {
-        let len = self.as_bytes().len();
+        if self.packed.is_empty() && self.inner.vlan().is_none() {
+            return self.send_bytes(self.as_bytes(), send);
+        }
+
+        let mut buf = [0u8; MAX_TAGGED_FRAME_LEN];
+
+        match self.write_frame(&mut buf) {
+            Ok(len) => self.send_bytes(&buf[0..len], send),
+            Err(e) => {
+                self.release_sending_claim();
 
-        match send(self.as_bytes()) {
+                Err(e)
+            }
+        }
+    }
+
+    /// Write this frame with its VLAN tag, if any, and the PDUs of any packed frames into `buf`.
+    ///
+    /// Returns the length of the written frame.
+    fn write_frame(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let frame = self.as_bytes();
+
+        let Some(buf) = buf.get_mut(0..self.len()) else {
+            fmt::error!("Frame of {} bytes is too long to send", self.len());
+
+            return Err(Error::Pdu(PduError::TooLong));
+        };
+
+        // Destination and source addresses, then the tag, then the original EtherType and payload
+        let (addresses, rest) = frame.split_at(12);
+
+        buf[0..12].copy_from_slice(addresses);
+
+        let mut pos = 12;
+
+        if let Some(vlan) = self.inner.vlan() {
+            buf[12..14].copy_from_slice(&VLAN_ETHERTYPE.to_be_bytes());
+            buf[14..16].copy_from_slice(&vlan.tci().to_be_bytes());
+
+            pos += VLAN_TAG_LEN;
+        }
+
+        buf[pos..(pos + rest.len())].copy_from_slice(rest);
+
+        if self.packed.is_empty() {
+            return Ok(buf.len());
+        }
+
+        // Skip the EtherType
+        let (_, ethercat) = buf.split_at_mut(pos + 2);
+        let (header, pdus) = ethercat.split_at_mut(EthercatFrameHeader::PACKED_LEN);
+
+        let mut pos = self.inner.pdu_payload_len();
+
+        for packed in self.packed.iter() {
+            let len = packed.pdu_payload_len();
+
+            pdus[pos..(pos + len)].copy_from_slice(&packed.pdu_buf()[0..len]);
+
+            pos += len;
+        }
+
+        EthercatFrameHeader::pdu(u16::try_from(pos)?).pack_to_slice(header)?;
+
+        set_more_follows(pdus)?;
+
+        Ok(buf.len())
+    }
+
+    fn send_bytes(
+        &self,
+        frame: &[u8],
+        send: impl FnOnce(&[u8]) -> Result<usize, Error>,
+    ) -> Result<usize, Error> {
+        let len = frame.len();
+
+        match send(frame) {
             Ok(bytes_sent) if bytes_sent == len => {
-                self.mark_sent();
+                self.mark_sent(frame);
 
                 Ok(bytes_sent)
             }
@@ -120,8 +330,8 @@ impl<'sto> SendableFrame<'sto> {
                 self.release_sending_claim();
 
                 Err(Error::PartialSend {
-                    len,
-                    sent: bytes_sent,
+                    len: saturate_u16(len),
+                    sent: saturate_u16(bytes_sent),
                 })
             }
             Err(res) => {
@@ -132,3 +342,31 @@ impl<'sto> SendableFrame<'sto> {
         }
     }
 }
+
+/// Set the "more follows" flag on every PDU in `pdus` except the last one.
+fn set_more_follows(pdus: &mut [u8]) -> Result<(), Error> {
+    // Offset of the flags in the PDU header, after the command, index and address fields
+    const FLAGS: usize = 6;
+
+    let total_len = pdus.len();
+
+    let mut pos = 0;
+
+    while pos < total_len {
+        let flags_buf = pdus
+            .get_mut((pos + FLAGS)..(pos + FLAGS + PduFlags::PACKED_LEN))
+            .ok_or(Error::Internal)?;
+
+        let mut flags = PduFlags::unpack_from_slice(flags_buf)?;
+
+        let next = pos + PduHeader::PACKED_LEN + usize::from(flags.len()) + 2;
+
+        flags.more_follows = next < total_len;
+
+        flags.pack_to_slice(flags_buf)?;
+
+        pos = next;
+    }
+
+    Ok(())
+}
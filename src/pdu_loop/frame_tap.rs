@@ -0,0 +1,114 @@
+use core::time::Duration;
+
+/// The direction of a frame passed to a [`FrameTap`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FrameDirection {
+    /// The frame was sent by the MainDevice.
+    Sent,
+    /// The frame was received from the network.
+    Received,
+}
+
+/// Observe every EtherCAT frame sent and received by the PDU loop, e.g. to record a capture.
+///
+/// A tap is attached with [`PduTx::set_frame_tap`](crate::PduTx::set_frame_tap) and
+/// [`PduRx::set_frame_tap`](crate::PduRx::set_frame_tap). It is called from the TX/RX task, so
+/// implementations should return quickly. No tap is attached by default, in which case sending and
+/// receiving frames has no extra cost.
+///
+/// Implement [`frame`](FrameTap::frame) to handle frames in both directions, or
+/// [`on_frame_sent`](FrameTap::on_frame_sent) and [`on_frame_received`](FrameTap::on_frame_received)
+/// to also get the time each frame was sent or received. [`FrameHooks`] implements this trait using
+/// two closures.
+pub trait FrameTap: Sync {
+    /// Called with a complete Ethernet II frame once it has been sent, or when an EtherCAT frame
+    /// is received.
+    ///
+    /// Does nothing by default.
+    fn frame(&self, direction: FrameDirection, ethernet_frame: &[u8]) {
+        let _ = (direction, ethernet_frame);
+    }
+
+    /// Called with a complete Ethernet II frame once it has been sent.
+    ///
+    /// `timestamp` is the time the frame was sent, from the same monotonic clock used for PDU
+    /// timeouts. Calls [`frame`](FrameTap::frame) by default.
+    fn on_frame_sent(&self, ethernet_frame: &[u8], timestamp: Duration) {
+        let _ = timestamp;
+
+        self.frame(FrameDirection::Sent, ethernet_frame)
+    }
+
+    /// Called with a complete Ethernet II frame when an EtherCAT frame is received, before its
+    /// PDUs are processed.
+    ///
+    /// `timestamp` is the time the frame was received, from the same clock as
+    /// [`on_frame_sent`](FrameTap::on_frame_sent). Calls [`frame`](FrameTap::frame) by default.
+    fn on_frame_received(&self, ethernet_frame: &[u8], timestamp: Duration) {
+        let _ = timestamp;
+
+        self.frame(FrameDirection::Received, ethernet_frame)
+    }
+}
+
+/// A [`FrameTap`] that calls `on_sent` for every sent frame and `on_received` for every received
+/// frame, with the raw Ethernet II frame and a timestamp.
+///
+/// The callbacks default to function pointers so a `FrameHooks` can be stored in a `static`.
+///
+/// # Examples
+///
+/// ```rust
+/// use core::time::Duration;
+/// use ethercrab::{FrameHooks, PduStorage};
+///
+/// fn sent(frame: &[u8], timestamp: Duration) {
+///     log::info!("TX {:?} {:02x?}", timestamp, frame);
+/// }
+///
+/// fn received(frame: &[u8], timestamp: Duration) {
+///     log::info!("RX {:?} {:02x?}", timestamp, frame);
+/// }
+///
+/// static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+/// static HOOKS: FrameHooks = FrameHooks::new(sent, received);
+///
+/// let (mut tx, mut rx, _pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// tx.set_frame_tap(&HOOKS);
+/// rx.set_frame_tap(&HOOKS);
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct FrameHooks<S = fn(&[u8], Duration), R = fn(&[u8], Duration)> {
+    on_sent: S,
+    on_received: R,
+}
+
+impl<S, R> FrameHooks<S, R>
+where
+    S: Fn(&[u8], Duration) + Sync,
+    R: Fn(&[u8], Duration) + Sync,
+{
+    /// Create a frame tap from a sent and received frame callback.
+    pub const fn new(on_sent: S, on_received: R) -> Self {
+        Self {
+            on_sent,
+            on_received,
+        }
+    }
+}
+
+impl<S, R> FrameTap for FrameHooks<S, R>
+where
+    S: Fn(&[u8], Duration) + Sync,
+    R: Fn(&[u8], Duration) + Sync,
+{
+    fn on_frame_sent(&self, ethernet_frame: &[u8], timestamp: Duration) {
+        (self.on_sent)(ethernet_frame, timestamp)
+    }
+
+    fn on_frame_received(&self, ethernet_frame: &[u8], timestamp: Duration) {
+        (self.on_received)(ethernet_frame, timestamp)
+    }
+}
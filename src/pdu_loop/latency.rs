@@ -0,0 +1,298 @@
+use crate::atomic::{AtomicU32, Ordering};
+use core::{ops::Range, time::Duration};
+
+/// Number of bits of precision kept for each recorded value.
+///
+/// Values are grouped into buckets no wider than 1/16th (6.25%) of the value.
+const SUB_BUCKET_BITS: u32 = 5;
+
+const SUB_BUCKET_HALF: usize = 1 << (SUB_BUCKET_BITS - 1);
+
+/// The largest round trip time in microseconds that can be recorded (about 16.7 seconds).
+///
+/// Longer round trip times are recorded as this value.
+const MAX_MICROS: u32 = (1 << 24) - 1;
+
+const NUM_BUCKETS: usize = bucket_index(MAX_MICROS) + 1;
+
+/// Index of the bucket holding `micros`.
+const fn bucket_index(micros: u32) -> usize {
+    // Position of the highest set bit
+    let magnitude = u32::BITS - 1 - (micros | 1).leading_zeros();
+    let shift = (magnitude + 1).saturating_sub(SUB_BUCKET_BITS);
+
+    shift as usize * SUB_BUCKET_HALF + (micros >> shift) as usize
+}
+
+/// Range of microsecond values held by the bucket at `index`.
+const fn bucket_range(index: usize) -> Range<u32> {
+    if index < SUB_BUCKET_HALF * 2 {
+        return index as u32..(index as u32 + 1);
+    }
+
+    let shift = index / SUB_BUCKET_HALF - 1;
+    let start = ((index - shift * SUB_BUCKET_HALF) << shift) as u32;
+
+    start..(start + (1 << shift))
+}
+
+/// A histogram of PDU round trip times, from when a frame is sent by
+/// [`PduTx`](crate::PduTx) to when its response is received by [`PduRx`](crate::PduRx).
+///
+/// Each received frame adds one sample, as all PDUs in a frame share the same round trip. Samples
+/// are counted in log-linear buckets in the style of an
+/// [HDR histogram](http://hdrhistogram.org/), so values returned by e.g.
+/// [`percentile`](LatencyHistogram::percentile) are accurate to within 6.25% with microsecond
+/// resolution. The minimum and maximum are exact.
+///
+/// A snapshot can be taken with
+/// [`MainDevice::latency_histogram`](crate::MainDevice::latency_histogram).
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LatencyHistogram {
+    counts: [u32; NUM_BUCKETS],
+    min_micros: u32,
+    max_micros: u32,
+}
+
+impl LatencyHistogram {
+    /// The total number of recorded round trips.
+    pub fn len(&self) -> u64 {
+        self.counts.iter().map(|count| u64::from(*count)).sum()
+    }
+
+    /// Whether no round trips have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(|count| *count == 0)
+    }
+
+    /// The shortest recorded round trip time.
+    pub fn min(&self) -> Option<Duration> {
+        (!self.is_empty()).then(|| Duration::from_micros(self.min_micros.into()))
+    }
+
+    /// The longest recorded round trip time.
+    pub fn max(&self) -> Option<Duration> {
+        (!self.is_empty()).then(|| Duration::from_micros(self.max_micros.into()))
+    }
+
+    /// The mean round trip time.
+    pub fn mean(&self) -> Option<Duration> {
+        let len = self.len();
+
+        if len == 0 {
+            return None;
+        }
+
+        let total = self
+            .buckets()
+            .map(|(range, count)| {
+                let mid = (range.start.as_micros() + range.end.as_micros()) / 2;
+
+                mid * u128::from(count)
+            })
+            .sum::<u128>();
+
+        Some(Duration::from_micros((total / u128::from(len)) as u64))
+    }
+
+    /// The round trip time that `percentile` percent of recorded round trips are at or below,
+    /// e.g. `percentile(99.9)`.
+    ///
+    /// `percentile` is clamped to between 0 and 100.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let len = self.len();
+
+        if len == 0 {
+            return None;
+        }
+
+        let percentile = percentile.clamp(0.0, 100.0);
+
+        // Number of samples that must be at or below the returned value, at least one
+        let target = ((percentile / 100.0 * len as f64) as u64).clamp(1, len);
+
+        let mut seen = 0u64;
+
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += u64::from(*count);
+
+            if seen >= target {
+                // Highest value in the bucket, but never more than the largest recorded value
+                let micros = (bucket_range(index).end - 1).clamp(self.min_micros, self.max_micros);
+
+                return Some(Duration::from_micros(micros.into()));
+            }
+        }
+
+        self.max()
+    }
+
+    /// Iterate over all non-empty buckets, yielding the range of round trip times each one holds
+    /// and the number of round trips recorded in it.
+    pub fn buckets(&self) -> impl Iterator<Item = (Range<Duration>, u32)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(index, count)| {
+                let range = bucket_range(index);
+
+                (
+                    Duration::from_micros(range.start.into())
+                        ..Duration::from_micros(range.end.into()),
+                    *count,
+                )
+            })
+    }
+}
+
+impl core::fmt::Debug for LatencyHistogram {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LatencyHistogram")
+            .field("len", &self.len())
+            .field("min", &self.min())
+            .field("p50", &self.percentile(50.0))
+            .field("p99", &self.percentile(99.0))
+            .field("max", &self.max())
+            .finish()
+    }
+}
+
+/// Live counters backing [`LatencyHistogram`].
+pub(crate) struct LatencyCounters {
+    counts: [AtomicU32; NUM_BUCKETS],
+    min_micros: AtomicU32,
+    max_micros: AtomicU32,
+}
+
+impl LatencyCounters {
+    pub(crate) const fn new() -> Self {
+        Self {
+            counts: [const { AtomicU32::new(0) }; NUM_BUCKETS],
+            min_micros: AtomicU32::new(u32::MAX),
+            max_micros: AtomicU32::new(0),
+        }
+    }
+
+    pub(crate) fn record(&self, round_trip: Duration) {
+        let micros = u32::try_from(round_trip.as_micros())
+            .unwrap_or(u32::MAX)
+            .min(MAX_MICROS);
+
+        self.counts[bucket_index(micros)].fetch_add(1, Ordering::Relaxed);
+        self.min_micros.fetch_min(micros, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    pub(crate) fn reset(&self) {
+        for count in self.counts.iter() {
+            count.store(0, Ordering::Relaxed);
+        }
+
+        self.min_micros.store(u32::MAX, Ordering::Relaxed);
+        self.max_micros.store(0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> LatencyHistogram {
+        LatencyHistogram {
+            counts: core::array::from_fn(|index| self.counts[index].load(Ordering::Relaxed)),
+            min_micros: self.min_micros.load(Ordering::Relaxed),
+            max_micros: self.max_micros.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl core::fmt::Debug for LatencyCounters {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LatencyCounters").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_ranges() {
+        assert_eq!(bucket_range(0), 0..1);
+        assert_eq!(bucket_range(31), 31..32);
+        assert_eq!(bucket_range(32), 32..34);
+        assert_eq!(bucket_range(NUM_BUCKETS - 1).end, MAX_MICROS + 1);
+
+        // Buckets are contiguous and every value is in the bucket it's counted in
+        for index in 1..NUM_BUCKETS {
+            assert_eq!(bucket_range(index - 1).end, bucket_range(index).start);
+        }
+
+        for micros in (0..MAX_MICROS).step_by(997).chain([MAX_MICROS]) {
+            assert!(bucket_range(bucket_index(micros)).contains(&micros));
+        }
+    }
+
+    #[test]
+    fn record() {
+        let counters = LatencyCounters::new();
+
+        let empty = counters.snapshot();
+
+        assert!(empty.is_empty());
+        assert_eq!(empty.percentile(50.0), None);
+        assert_eq!(empty.mean(), None);
+
+        for micros in 1..=1000 {
+            counters.record(Duration::from_micros(micros));
+        }
+
+        let histogram = counters.snapshot();
+
+        assert_eq!(histogram.len(), 1000);
+        assert_eq!(histogram.min(), Some(Duration::from_micros(1)));
+        assert_eq!(histogram.max(), Some(Duration::from_micros(1000)));
+        assert_eq!(histogram.percentile(0.0), Some(Duration::from_micros(1)));
+        assert_eq!(
+            histogram.percentile(100.0),
+            Some(Duration::from_micros(1000))
+        );
+
+        for (percentile, expected) in [(50.0, 500.0), (90.0, 900.0), (99.0, 990.0)] {
+            let value = histogram.percentile(percentile).unwrap().as_micros() as f64;
+
+            assert!(
+                value >= expected && value <= expected * 1.0625,
+                "p{} {} expected {}",
+                percentile,
+                value,
+                expected
+            );
+        }
+
+        let mean = histogram.mean().unwrap().as_micros();
+
+        assert!((490..=510).contains(&mean), "mean {}", mean);
+
+        assert_eq!(
+            histogram
+                .buckets()
+                .map(|(_, count)| u64::from(count))
+                .sum::<u64>(),
+            1000
+        );
+
+        counters.reset();
+
+        assert!(counters.snapshot().is_empty());
+    }
+
+    #[test]
+    fn clamp_long_round_trips() {
+        let counters = LatencyCounters::new();
+
+        counters.record(Duration::from_secs(3600));
+
+        assert_eq!(
+            counters.snapshot().max(),
+            Some(Duration::from_micros(MAX_MICROS.into()))
+        );
+    }
+}
@@ -1,20 +1,36 @@
 mod frame_element;
-mod frame_header;
+pub(crate) mod frame_header;
+mod frame_tap;
+mod latency;
 mod pdu_flags;
-mod pdu_header;
+pub(crate) mod pdu_header;
 mod pdu_rx;
 mod pdu_tx;
+mod statistics;
 // NOTE: Pub so doc links work
 pub mod storage;
 
-use crate::{command::Command, error::Error, pdu_loop::storage::PduStorageRef};
-use core::time::Duration;
+use crate::{
+    command::Command, error::Error, fmt, pdu_loop::storage::PduStorageRef,
+    timer_factory::IntoTimeout, RetryBackoff, VlanTag, WireLogging,
+};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+pub use frame_tap::{FrameDirection, FrameHooks, FrameTap};
+pub use latency::LatencyHistogram;
 pub use pdu_rx::PduRx;
 pub use pdu_tx::PduTx;
+pub use statistics::Statistics;
+#[cfg(feature = "alloc")]
+pub use storage::BoxedPduStorage;
 pub use storage::PduStorage;
 
-pub(crate) use self::frame_element::created_frame::CreatedFrame;
-pub(crate) use frame_element::received_frame::ReceivedPdu;
+pub(crate) use self::frame_element::created_frame::{CreatedFrame, PduResponseHandle};
+pub(crate) use frame_element::received_frame::{ReceivedFrame, ReceivedPdu};
 
 pub use frame_element::sendable_frame::SendableFrame;
 
@@ -23,6 +39,19 @@ pub use frame_header::EthercatFrameHeader;
 #[cfg(feature = "__internals")]
 pub use pdu_header::PduHeader;
 
+/// The lane a frame is sent in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub(crate) enum FramePriority {
+    /// Acyclic traffic, e.g. mailbox, EEPROM and register access.
+    #[default]
+    Low,
+    /// Cyclic process data.
+    ///
+    /// High priority frames are sent before any low priority frames, and one frame in the PDU
+    /// storage is always kept free for them.
+    High,
+}
+
 /// The core EtherCrab network communications driver.
 ///
 // TODO: Update the following docs. The current text is out of date.
@@ -54,6 +83,9 @@ pub use pdu_header::PduHeader;
 #[derive(Debug)]
 pub struct PduLoop<'sto> {
     storage: PduStorageRef<'sto>,
+    wire_logging: WireLogging,
+    vlan: Option<VlanTag>,
+    frame_packing: bool,
 }
 
 impl<'sto> PduLoop<'sto> {
@@ -61,18 +93,160 @@ impl<'sto> PduLoop<'sto> {
     pub(in crate::pdu_loop) const fn new(storage: PduStorageRef<'sto>) -> Self {
         assert!(storage.num_frames <= u8::MAX as usize);
 
-        Self { storage }
+        Self {
+            storage,
+            wire_logging: WireLogging::Off,
+            vlan: None,
+            frame_packing: false,
+        }
+    }
+
+    /// Set how much of each sent and received frame is logged.
+    pub(crate) const fn with_wire_logging(self, wire_logging: WireLogging) -> Self {
+        Self {
+            storage: self.storage,
+            wire_logging,
+            vlan: self.vlan,
+            frame_packing: self.frame_packing,
+        }
+    }
+
+    /// Tag every sent frame with the given VLAN.
+    pub(crate) const fn with_vlan(self, vlan: Option<VlanTag>) -> Self {
+        Self {
+            storage: self.storage,
+            wire_logging: self.wire_logging,
+            vlan,
+            frame_packing: self.frame_packing,
+        }
+    }
+
+    /// Allow frames sent by this PDU loop to be packed together with other frames.
+    pub(crate) const fn with_frame_packing(self, frame_packing: bool) -> Self {
+        Self {
+            storage: self.storage,
+            wire_logging: self.wire_logging,
+            vlan: self.vlan,
+            frame_packing,
+        }
+    }
+
+    /// The VLAN tag added to sent frames, if any.
+    pub(in crate::pdu_loop) fn vlan(&self) -> Option<VlanTag> {
+        self.vlan
+    }
+
+    /// Whether sent frames may be packed together with other frames.
+    pub(in crate::pdu_loop) fn frame_packing(&self) -> bool {
+        self.frame_packing
+    }
+
+    /// Log a sent or received frame according to the configured [`WireLogging`] level.
+    pub(in crate::pdu_loop) fn log_frame(
+        &self,
+        direction: FrameDirection,
+        frame_index: u8,
+        pdus: &[u8],
+    ) {
+        let direction_str = match direction {
+            FrameDirection::Sent => "Send",
+            FrameDirection::Received => "Received",
+        };
+
+        match self.wire_logging {
+            WireLogging::Off => (),
+            WireLogging::Summary => {
+                fmt::debug!(
+                    "{} frame index {}, {} bytes",
+                    direction_str,
+                    frame_index,
+                    pdus.len()
+                );
+            }
+            WireLogging::Full => {
+                #[cfg(not(feature = "defmt"))]
+                fmt::debug!(
+                    "{} frame index {}, {} bytes: {:02x?}",
+                    direction_str,
+                    frame_index,
+                    pdus.len(),
+                    pdus
+                );
+                #[cfg(feature = "defmt")]
+                fmt::debug!(
+                    "{} frame index {}, {} bytes: {=[u8]:02x}",
+                    direction_str,
+                    frame_index,
+                    pdus.len(),
+                    pdus
+                );
+            }
+            WireLogging::Dissect => {
+                let dissector = crate::PduDissector::new(direction, pdus);
+
+                #[cfg(not(feature = "defmt"))]
+                fmt::debug!(
+                    "{} frame index {}, {} bytes: {}",
+                    direction_str,
+                    frame_index,
+                    pdus.len(),
+                    dissector
+                );
+                #[cfg(feature = "defmt")]
+                fmt::debug!(
+                    "{} frame index {}, {} bytes: {}",
+                    direction_str,
+                    frame_index,
+                    pdus.len(),
+                    defmt::Display2Format(&dissector)
+                );
+            }
+        }
     }
 
     pub(crate) fn max_frame_data(&self) -> usize {
         self.storage.frame_data_len
     }
 
+    /// The largest PDU payload that can be sent in one frame.
+    pub(crate) fn max_pdu_data(&self) -> usize {
+        self.storage
+            .max_pdu_data()
+            .min(usize::from(crate::LEN_MASK))
+    }
+
     /// Tell the packet sender there are PDUs ready to send.
     pub(crate) fn wake_sender(&self) {
         self.storage.tx_waker.wake();
     }
 
+    /// Network communication statistics.
+    pub(crate) fn statistics(&self) -> Statistics {
+        self.storage.statistics.snapshot()
+    }
+
+    /// PDU round trip time histogram.
+    pub(crate) fn latency_histogram(&self) -> LatencyHistogram {
+        self.storage.statistics.latency_snapshot()
+    }
+
+    pub(crate) fn reset_latency_histogram(&self) {
+        self.storage.statistics.reset_latency();
+    }
+
+    pub(in crate::pdu_loop) fn record_retry(&self) {
+        self.storage.statistics.record_retry();
+    }
+
+    pub(in crate::pdu_loop) fn record_timeout(&self) {
+        self.storage.statistics.record_timeout();
+    }
+
+    /// Count an error in the network [`Statistics`], if it is of a tracked kind.
+    pub(crate) fn record_error(&self, error: &Error) {
+        self.storage.statistics.record_error(error);
+    }
+
     /// Broadcast (BWR) a packet full of zeroes, up to `payload_length`.
     pub(crate) async fn pdu_broadcast_zeros(
         &self,
@@ -80,12 +254,13 @@ impl<'sto> PduLoop<'sto> {
         payload_length: u16,
         timeout: Duration,
         retries: usize,
+        backoff: RetryBackoff,
     ) -> Result<(), Error> {
-        let mut frame = self.storage.alloc_frame()?;
+        let mut frame = self.alloc_frame_wait(timeout, FramePriority::Low).await?;
 
         frame.push_pdu(Command::bwr(register).into(), (), Some(payload_length))?;
 
-        let frame = frame.mark_sendable(self, timeout, retries);
+        let frame = frame.mark_sendable(self, timeout, retries, backoff);
 
         self.wake_sender();
 
@@ -94,25 +269,160 @@ impl<'sto> PduLoop<'sto> {
         Ok(())
     }
 
+    #[cfg(test)]
     pub(crate) fn alloc_frame(&self) -> Result<CreatedFrame<'sto>, Error> {
         self.storage.alloc_frame()
     }
+
+    /// Allocate a frame, waiting for up to `timeout` for one to become free if all frames available
+    /// to the given lane are currently in flight.
+    pub(crate) async fn alloc_frame_wait(
+        &self,
+        timeout: Duration,
+        priority: FramePriority,
+    ) -> Result<CreatedFrame<'sto>, Error> {
+        if let Some(frame) = self.storage.try_alloc_frame(priority) {
+            return Ok(frame);
+        }
+
+        fmt::trace!("No free frames, waiting for one to be released");
+
+        AllocFrameFut {
+            storage: &self.storage,
+            priority,
+            waiter_slot: None,
+        }
+        .timeout(timeout)
+        .await
+    }
+
+    /// Called when a frame is free to be allocated again.
+    pub(in crate::pdu_loop) fn frame_released(&self, priority: FramePriority) {
+        self.storage.frame_released(priority);
+    }
+}
+
+/// Waits for a frame to become free in the PDU storage.
+struct AllocFrameFut<'a, 'sto> {
+    storage: &'a PduStorageRef<'sto>,
+    priority: FramePriority,
+    waiter_slot: Option<usize>,
+}
+
+impl<'sto> Future for AllocFrameFut<'_, 'sto> {
+    type Output = Result<CreatedFrame<'sto>, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.waiter_slot.is_none() {
+            self.waiter_slot = self.storage.frame_waiters.claim();
+        }
+
+        match self.waiter_slot {
+            // Register before trying again so a frame released in between is not missed.
+            Some(slot) => self.storage.frame_waiters.register(slot, cx.waker()),
+            // All waiter slots are taken, so poll again as soon as possible instead.
+            None => cx.waker().wake_by_ref(),
+        }
+
+        match self.storage.try_alloc_frame(self.priority) {
+            Some(frame) => Poll::Ready(Ok(frame)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for AllocFrameFut<'_, '_> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.waiter_slot.take() {
+            self.storage.frame_waiters.release(slot);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::atomic::{AtomicBool, Ordering};
     use crate::ethernet::{EthernetAddress, EthernetFrame};
     use crate::{
         error::{Error, PduError},
         fmt,
-        pdu_loop::frame_element::created_frame::CreatedFrame,
+        pdu_loop::{frame_element::created_frame::CreatedFrame, FramePriority},
         timer_factory::IntoTimeout,
-        Command, PduStorage, Reads,
+        Command, ConfiguredAddress, LogicalAddress, PduStorage, PduTx, Reads, RetryBackoff,
+        Statistics, VlanTag,
     };
     use cassette::Cassette;
-    use core::{future::poll_fn, ops::Deref, pin::pin, task::Poll, time::Duration};
+    use core::{
+        future::poll_fn,
+        ops::Deref,
+        pin::pin,
+        ptr::NonNull,
+        task::{Context, Poll, Waker},
+        time::Duration,
+    };
     use futures_lite::Future;
-    use std::{sync::Arc, thread};
+    use std::{sync::Arc, task::Wake, thread};
+
+    #[test]
+    fn wait_for_free_frame() {
+        struct FlagWaker(AtomicBool);
+
+        impl Wake for FlagWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::Relaxed);
+            }
+        }
+
+        static STORAGE: PduStorage<1, { PduStorage::element_size(32) }> = PduStorage::new();
+        let (_tx, _rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let flag = Arc::new(FlagWaker(AtomicBool::new(false)));
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut frame = pdu_loop.alloc_frame().expect("Alloc");
+
+        frame
+            .push_pdu(
+                Command::frmw(ConfiguredAddress::new(0x1000), 0x0910).into(),
+                0u64,
+                None,
+            )
+            .expect("Push PDU");
+
+        let in_flight =
+            frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None);
+
+        let mut wait = pin!(pdu_loop.alloc_frame_wait(Duration::from_secs(10), FramePriority::Low));
+
+        assert!(wait.as_mut().poll(&mut cx).is_pending());
+
+        // Polling timers wake their task every time they are polled
+        #[cfg(not(any(feature = "rtic", feature = "cortex-m")))]
+        assert!(!flag.0.load(Ordering::Relaxed));
+
+        flag.0.store(false, Ordering::Relaxed);
+
+        // Releasing the in-flight frame wakes the waiting task, which can now claim it
+        drop(in_flight);
+
+        assert!(flag.0.load(Ordering::Relaxed));
+        assert!(matches!(wait.as_mut().poll(&mut cx), Poll::Ready(Ok(_))));
+    }
+
+    #[test]
+    fn wait_for_free_frame_timeout() {
+        static STORAGE: PduStorage<1, { PduStorage::element_size(32) }> = PduStorage::new();
+        let (_tx, _rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let _frame = pdu_loop.alloc_frame().expect("Alloc");
+
+        let res = cassette::block_on(
+            pdu_loop.alloc_frame_wait(Duration::from_millis(10), FramePriority::Low),
+        );
+
+        assert_eq!(res.unwrap_err(), Error::Timeout);
+    }
 
     #[test]
     fn timed_out_frame_is_reallocatable() {
@@ -134,7 +444,7 @@ mod tests {
             )
             .expect("Push PDU");
 
-        let fut = frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX);
+        let fut = frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None);
 
         let res = cassette::block_on(fut.timeout(Duration::from_secs(0)));
 
@@ -152,6 +462,115 @@ mod tests {
         assert_eq!(f2.unwrap_err(), PduError::SwapState.into());
     }
 
+    #[test]
+    fn dropped_created_frame_is_reallocatable() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        static STORAGE: PduStorage<1, { PduStorage::element_size(8) }> = PduStorage::new();
+        let (_tx, _rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let mut frame = pdu_loop
+            .storage
+            .try_alloc_frame(FramePriority::Low)
+            .expect("Alloc");
+
+        // E.g. a PDU that's too long makes the caller bail before the frame is sent
+        assert_eq!(
+            frame
+                .push_pdu(Command::Nop, [0u8; 16], None)
+                .map(|_| ())
+                .unwrap_err(),
+            PduError::TooLong
+        );
+
+        drop(frame);
+
+        // Both the frame slot and the low priority allocation are released
+        assert!(pdu_loop
+            .storage
+            .try_alloc_frame(FramePriority::Low)
+            .is_some());
+    }
+
+    #[test]
+    fn frame_dropped_while_sending_is_released() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        static STORAGE: PduStorage<1, { PduStorage::element_size(32) }> = PduStorage::new();
+        let (mut tx, _rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        for send_result in [Ok(()), Err(Error::SendFrame)] {
+            let mut frame = pdu_loop.storage.alloc_frame().expect("Alloc");
+
+            frame
+                .push_pdu(
+                    Command::fprd(ConfiguredAddress::new(0x1000), 0x0130).into(),
+                    (),
+                    Some(2),
+                )
+                .expect("Push PDU");
+
+            let fut = frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None);
+
+            let sending = tx.next_sendable_frame().expect("Sendable frame");
+
+            // E.g. a `select!` timeout fires while the TX loop is sending the frame
+            drop(fut);
+
+            // The TX loop still holds the frame so it can't be reused yet
+            assert!(pdu_loop.storage.alloc_frame().is_err());
+
+            let res = sending.send_blocking(|data| send_result.map(|()| data.len()));
+
+            assert_eq!(res.is_ok(), send_result.is_ok());
+
+            // Nothing will send the frame again
+            assert!(tx.next_sendable_frame().is_none());
+        }
+
+        assert!(pdu_loop.storage.alloc_frame().is_ok());
+    }
+
+    #[test]
+    fn retries_are_counted() {
+        static STORAGE: PduStorage<1, { PduStorage::element_size(32) }> = PduStorage::new();
+        let (_tx, _rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let mut frame = pdu_loop.storage.alloc_frame().expect("Alloc");
+
+        frame
+            .push_pdu(
+                Reads::Brd {
+                    address: 0,
+                    register: 0,
+                }
+                .into(),
+                (),
+                Some(16),
+            )
+            .expect("Push PDU");
+
+        // Nothing will respond, so the frame is resent twice before timing out
+        let fut = frame.mark_sendable(
+            &pdu_loop,
+            Duration::from_millis(1),
+            2,
+            RetryBackoff::Fixed(Duration::from_millis(1)),
+        );
+
+        let res = cassette::block_on(fut);
+
+        assert_eq!(res.unwrap_err(), Error::Timeout);
+        assert_eq!(
+            pdu_loop.statistics(),
+            Statistics {
+                pdu_retries: 2,
+                pdu_timeouts: 1,
+                ..Statistics::default()
+            }
+        );
+    }
+
     #[test]
     fn write_frame() {
         let _ = env_logger::builder().is_test(true).try_init();
@@ -164,10 +583,14 @@ mod tests {
         let mut frame = pdu_loop.storage.alloc_frame().unwrap();
 
         let _handle = frame
-            .push_pdu(Command::fpwr(0x5678, 0x1234).into(), data, None)
+            .push_pdu(
+                Command::fpwr(ConfiguredAddress::new(0x5678), 0x1234).into(),
+                data,
+                None,
+            )
             .expect("Push");
 
-        let frame = frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX);
+        let frame = frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None);
 
         assert_eq!(
             frame.buf(),
@@ -208,10 +631,15 @@ mod tests {
             let mut frame = pdu_loop.storage.alloc_frame().expect("Frame alloc");
 
             let handle = frame
-                .push_pdu(Command::fpwr(0x5678, 0x1234).into(), &data, None)
+                .push_pdu(
+                    Command::fpwr(ConfiguredAddress::new(0x5678), 0x1234).into(),
+                    data,
+                    None,
+                )
                 .expect("Push PDU");
 
-            let mut frame_fut = pin!(frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX));
+            let mut frame_fut =
+                pin!(frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None));
 
             // Poll future up to first await point. This gets the frame ready and marks it as
             // sendable so TX can pick it up, but we don't want to wait for the response so we won't
@@ -286,11 +714,15 @@ mod tests {
         let mut frame = pdu_loop.storage.alloc_frame().unwrap();
 
         let _handle = frame
-            .push_pdu(Command::fpwr(0x5678, 0x1234).into(), data, None)
+            .push_pdu(
+                Command::fpwr(ConfiguredAddress::new(0x5678), 0x1234).into(),
+                data,
+                None,
+            )
             .expect("Push PDU");
 
         // Drop frame future to reset its state to `FrameState::None`
-        drop(frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX));
+        drop(frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None));
 
         // ---
 
@@ -299,10 +731,14 @@ mod tests {
         let mut frame = pdu_loop.storage.alloc_frame().unwrap();
 
         let _handle = frame
-            .push_pdu(Command::fpwr(0x6789, 0x1234).into(), data, None)
+            .push_pdu(
+                Command::fpwr(ConfiguredAddress::new(0x6789), 0x1234).into(),
+                data,
+                None,
+            )
             .expect("Push second PDU");
 
-        let frame = frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX);
+        let frame = frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None);
 
         // ---
 
@@ -356,10 +792,15 @@ mod tests {
             let mut frame = pdu_loop.storage.alloc_frame().unwrap();
 
             let handle = frame
-                .push_pdu(Command::fpwr(0x6789, 0x1234).into(), &data_bytes, None)
+                .push_pdu(
+                    Command::fpwr(ConfiguredAddress::new(0x6789), 0x1234).into(),
+                    data_bytes,
+                    None,
+                )
                 .expect("Push PDU");
 
-            let mut frame_fut = pin!(frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX));
+            let mut frame_fut =
+                pin!(frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None));
 
             // Poll future up to first await point. This gets the frame ready and marks it as
             // sendable so TX can pick it up, but we don't want to wait for the response so we won't
@@ -397,6 +838,63 @@ mod tests {
         cassette::block_on(poller);
     }
 
+    #[test]
+    fn handle_received_frame_without_waker() {
+        let ethernet_packet = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // Broadcast address
+            0x12, 0x10, 0x10, 0x10, 0x10, 0x10, // Return to master address
+            0x88, 0xa4, // EtherCAT ethertype
+            0x10, 0x10, // EtherCAT frame header: type PDU, length 4 (plus header)
+            0x04, // Command: FPRD
+            0x00, // Frame index 0
+            0x89, 0x67, // SubDevice address,
+            0x34, 0x12, // Register address
+            0x04, 0x00, // Flags, 4 byte length
+            0x00, 0x00, // IRQ
+            0xdd, 0xcc, 0xbb, 0xaa, // Response payload, LE
+            0x01, 0x00, // Working counter
+        ];
+
+        let storage = PduStorage::<1, 128>::new();
+
+        let (mut tx, mut rx, pdu_loop) = storage.try_split().unwrap();
+
+        let mut frame = pdu_loop.storage.alloc_frame().unwrap();
+
+        let handle = frame
+            .push_pdu(
+                Command::fprd(ConfiguredAddress::new(0x6789), 0x1234).into(),
+                (),
+                Some(4),
+            )
+            .expect("Push PDU");
+
+        // The future isn't polled until after the response is received, like a lower priority task
+        // preempted by an RX interrupt, so has no waker
+        let frame_fut =
+            frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None);
+
+        tx.next_sendable_frame()
+            .expect("need a frame")
+            .send_blocking(|bytes| Ok(bytes.len()))
+            .expect("send");
+
+        assert_eq!(
+            rx.receive_frame(&ethernet_packet),
+            Err(Error::Pdu(PduError::NoWaker))
+        );
+        assert_eq!(rx.handle_received_frame(&ethernet_packet), Ok(()));
+
+        let frame = futures_lite::future::block_on(futures_lite::future::poll_once(frame_fut))
+            .expect("frame future still pending")
+            .expect("response");
+
+        assert_eq!(
+            frame.first_pdu(handle).unwrap().deref(),
+            &0xaabbccddu32.to_le_bytes()
+        );
+    }
+
     #[tokio::test]
     async fn tokio_spawn() {
         let _ = env_logger::builder().is_test(true).try_init();
@@ -456,11 +954,15 @@ mod tests {
             let mut frame = pdu_loop.storage.alloc_frame().expect("Frame alloc");
 
             let handle = frame
-                .push_pdu(Command::fpwr(0x1000, 0x980).into(), data, None)
+                .push_pdu(
+                    Command::fpwr(ConfiguredAddress::new(0x1000), 0x980).into(),
+                    data,
+                    None,
+                )
                 .expect("Push PDU");
 
             let result = frame
-                .mark_sendable(&pdu_loop, Duration::MAX, usize::MAX)
+                .mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None)
                 .await
                 .expect("Future");
 
@@ -470,6 +972,12 @@ mod tests {
         }
 
         fmt::info!("Sent all PDUs");
+
+        let stats = pdu_loop.statistics();
+
+        assert_eq!(stats.frames_sent, 32);
+        assert_eq!(stats.frames_received, 32);
+        assert_eq!(pdu_loop.latency_histogram().len(), 32);
     }
 
     #[test]
@@ -560,11 +1068,19 @@ mod tests {
                     let mut frame = pdu_loop.storage.alloc_frame().expect("Frame alloc");
 
                     let handle = frame
-                        .push_pdu(Command::fpwr(0x1000, 0x980).into(), data, None)
+                        .push_pdu(
+                            Command::fpwr(ConfiguredAddress::new(0x1000), 0x980).into(),
+                            data,
+                            None,
+                        )
                         .expect("Push PDU");
 
-                    let mut x =
-                        Cassette::new(frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX));
+                    let mut x = Cassette::new(frame.mark_sendable(
+                        &pdu_loop,
+                        Duration::MAX,
+                        usize::MAX,
+                        RetryBackoff::None,
+                    ));
 
                     let result = loop {
                         if let Some(res) = x.poll_on() {
@@ -591,4 +1107,426 @@ mod tests {
 
         fmt::info!("Sent all PDUs");
     }
+
+    #[test]
+    fn vlan_tag_inserted() {
+        static STORAGE: PduStorage<1, { PduStorage::element_size(32) }> = PduStorage::new();
+
+        let (mut tx, _rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let pdu_loop = pdu_loop.with_vlan(Some(VlanTag { vid: 100, pcp: 5 }));
+
+        let mut frame = pdu_loop.alloc_frame().unwrap();
+
+        frame
+            .push_pdu(Command::brd(0x0000).into(), (), Some(1))
+            .unwrap();
+
+        let _fut = frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None);
+
+        let frame = tx.next_sendable_frame().unwrap();
+
+        assert_eq!(frame.len(), 14 + 4 + 2 + 10 + 1 + 2);
+
+        frame
+            .send_blocking(|sent| {
+                assert_eq!(sent.len(), 33);
+                // TPID, then PCP 5 and VID 100, then the EtherCAT EtherType
+                assert_eq!(&sent[12..18], &[0x81, 0x00, 0xa0, 0x64, 0x88, 0xa4]);
+
+                Ok(sent.len())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn priority_lanes() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let storage = PduStorage::<4, { PduStorage::element_size(32) }>::new();
+
+        let (mut tx, _rx, pdu_loop) = storage.try_split().unwrap();
+
+        let mut mailbox = pdu_loop
+            .storage
+            .try_alloc_frame(FramePriority::Low)
+            .unwrap();
+        mailbox
+            .push_pdu(
+                Command::fprd(ConfiguredAddress::new(0x1001), 0x1c00).into(),
+                (),
+                Some(3),
+            )
+            .unwrap();
+        let _mailbox =
+            mailbox.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None);
+
+        let mut lrw = pdu_loop
+            .storage
+            .try_alloc_frame(FramePriority::High)
+            .unwrap();
+        lrw.push_pdu(
+            Command::lrw(LogicalAddress::new(0x1000)).into(),
+            [0xaau8, 0xbb],
+            None,
+        )
+        .unwrap();
+        let _lrw = lrw.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None);
+
+        // Process data frame is sent first even though it was queued last
+        assert_eq!(
+            tx.next_sendable_frame().map(|frame| frame.len()),
+            Some(14 + 2 + 10 + 2 + 2)
+        );
+        assert_eq!(
+            tx.next_sendable_frame().map(|frame| frame.len()),
+            Some(14 + 2 + 10 + 3 + 2)
+        );
+        assert!(tx.next_sendable_frame().is_none());
+    }
+
+    #[test]
+    fn reserve_high_priority_frame() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let storage = PduStorage::<2, { PduStorage::element_size(32) }>::new();
+
+        let (_tx, _rx, pdu_loop) = storage.try_split().unwrap();
+
+        let mut mailbox = pdu_loop
+            .storage
+            .try_alloc_frame(FramePriority::Low)
+            .expect("first low priority frame");
+
+        // Last free frame is kept for high priority frames
+        assert!(pdu_loop
+            .storage
+            .try_alloc_frame(FramePriority::Low)
+            .is_none());
+
+        mailbox
+            .push_pdu(
+                Command::fprd(ConfiguredAddress::new(0x1001), 0x1c00).into(),
+                (),
+                Some(3),
+            )
+            .unwrap();
+        let mailbox =
+            mailbox.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None);
+
+        let _lrw = pdu_loop
+            .storage
+            .try_alloc_frame(FramePriority::High)
+            .expect("high priority frame");
+
+        // Releasing the low priority frame makes room for another one
+        drop(mailbox);
+
+        assert!(pdu_loop
+            .storage
+            .try_alloc_frame(FramePriority::Low)
+            .is_some());
+    }
+
+    #[test]
+    fn frame_packing() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let storage = PduStorage::<4, { PduStorage::element_size(32) }>::new();
+
+        let (mut tx, mut rx, pdu_loop) = storage.try_split().unwrap();
+
+        let pdu_loop = pdu_loop.with_frame_packing(true);
+
+        let poller = poll_fn(|ctx| {
+            let mut lrw = pdu_loop.alloc_frame().unwrap();
+            let lrw_handle = lrw
+                .push_pdu(
+                    Command::lrw(LogicalAddress::new(0x1000)).into(),
+                    [0xaau8, 0xbb],
+                    None,
+                )
+                .unwrap();
+
+            let mut mailbox = pdu_loop.alloc_frame().unwrap();
+            let mailbox_handle = mailbox
+                .push_pdu(
+                    Command::fprd(ConfiguredAddress::new(0x1001), 0x1c00).into(),
+                    (),
+                    Some(3),
+                )
+                .unwrap();
+
+            let mut lrw =
+                pin!(lrw.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None));
+            let mut mailbox = pin!(mailbox.mark_sendable(
+                &pdu_loop,
+                Duration::MAX,
+                usize::MAX,
+                RetryBackoff::None
+            ));
+
+            // Register wakers
+            assert!(lrw.as_mut().poll(ctx).is_pending());
+            assert!(mailbox.as_mut().poll(ctx).is_pending());
+
+            let frame = tx.next_sendable_frame().expect("sendable frame");
+
+            // Both frames are sent at once
+            assert!(tx.next_sendable_frame().is_none());
+
+            assert_eq!(frame.len(), 14 + 2 + (10 + 2 + 2) + (10 + 3 + 2));
+
+            let mut sent = Vec::new();
+
+            frame
+                .send_blocking(|bytes| {
+                    sent.extend_from_slice(bytes);
+
+                    Ok(bytes.len())
+                })
+                .unwrap();
+
+            // EtherCAT header length covers both PDUs
+            assert_eq!(&sent[14..16], &[0x1d, 0x10]);
+            // First PDU has "more follows" set, second doesn't
+            assert_eq!(&sent[22..24], &[0x02, 0x80]);
+            assert_eq!(&sent[36..38], &[0x03, 0x00]);
+
+            // Respond with working counters of 3 and 1
+            sent[6] = 0x12;
+            sent[28] = 3;
+            sent[40..43].copy_from_slice(&[0x01, 0x02, 0x03]);
+            sent[43] = 1;
+
+            assert_eq!(rx.receive_frame(&sent), Ok(()));
+
+            let Poll::Ready(Ok(lrw)) = lrw.poll(ctx) else {
+                panic!("LRW response not received");
+            };
+            let Poll::Ready(Ok(mailbox)) = mailbox.poll(ctx) else {
+                panic!("mailbox response not received");
+            };
+
+            let lrw = lrw.first_pdu(lrw_handle).unwrap();
+            let mailbox = mailbox.first_pdu(mailbox_handle).unwrap();
+
+            assert_eq!((lrw.deref(), lrw.working_counter), (&[0xaa, 0xbb][..], 3));
+            assert_eq!(
+                (mailbox.deref(), mailbox.working_counter),
+                (&[0x01, 0x02, 0x03][..], 1)
+            );
+
+            Poll::Ready(())
+        });
+
+        cassette::block_on(poller);
+    }
+
+    #[test]
+    fn first_pdu_index_is_not_reused() {
+        static STORAGE: PduStorage<2, { PduStorage::element_size(32) }> = PduStorage::new();
+        let (_tx, _rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let mut frame = pdu_loop.storage.alloc_frame().unwrap();
+
+        let in_flight = frame
+            .push_pdu(
+                Command::fprd(ConfiguredAddress::new(0x1000), 0x0130).into(),
+                (),
+                Some(2),
+            )
+            .unwrap();
+
+        let _in_flight =
+            frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None);
+
+        // Wrap the 8 bit PDU index a few times while the first frame is still in flight
+        for _ in 0..1000 {
+            let mut frame = pdu_loop.storage.alloc_frame().unwrap();
+
+            let first = frame
+                .push_pdu(
+                    Command::fprd(ConfiguredAddress::new(0x1000), 0x0130).into(),
+                    (),
+                    Some(2),
+                )
+                .unwrap();
+            let second = frame
+                .push_pdu(
+                    Command::fprd(ConfiguredAddress::new(0x1001), 0x0130).into(),
+                    (),
+                    Some(2),
+                )
+                .unwrap();
+
+            assert_ne!(first.pdu_idx, in_flight.pdu_idx);
+            assert_eq!(second.pdu_idx, first.pdu_idx.wrapping_add(1));
+
+            // Release the frame
+            drop(frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None));
+        }
+    }
+
+    #[test]
+    fn unmatched_responses_are_ignored() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let storage = PduStorage::<2, { PduStorage::element_size(32) }>::new();
+
+        let (mut tx, mut rx, pdu_loop) = storage.try_split().unwrap();
+
+        let send = |tx: &mut PduTx| {
+            let mut sent = Vec::new();
+
+            tx.next_sendable_frame()
+                .unwrap()
+                .send_blocking(|bytes| {
+                    sent.extend_from_slice(bytes);
+
+                    Ok(bytes.len())
+                })
+                .unwrap();
+
+            // Response with a working counter of 1
+            sent[6] = 0x12;
+            let wkc = sent.len() - 2;
+            sent[wkc] = 1;
+
+            sent
+        };
+
+        let poller = poll_fn(|ctx| {
+            // A frame that times out before its response is received
+            let mut frame = pdu_loop.alloc_frame().unwrap();
+
+            frame
+                .push_pdu(
+                    Command::fprd(ConfiguredAddress::new(0x1000), 0x0130).into(),
+                    (),
+                    Some(2),
+                )
+                .unwrap();
+
+            let mut timed_out = Box::pin(frame.mark_sendable(
+                &pdu_loop,
+                Duration::MAX,
+                usize::MAX,
+                RetryBackoff::None,
+            ));
+
+            assert!(timed_out.as_mut().poll(ctx).is_pending());
+
+            let late = send(&mut tx);
+
+            drop(timed_out);
+
+            // A frame waiting for a response
+            let mut frame = pdu_loop.alloc_frame().unwrap();
+
+            let handle = frame
+                .push_pdu(
+                    Command::fprd(ConfiguredAddress::new(0x1000), 0x0110).into(),
+                    (),
+                    Some(2),
+                )
+                .unwrap();
+
+            let mut waiting =
+                pin!(frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None));
+
+            assert!(waiting.as_mut().poll(ctx).is_pending());
+
+            let response = send(&mut tx);
+
+            // Late response to the timed out frame
+            assert_eq!(rx.receive_frame(&late), Ok(()));
+
+            // A response with the waiting frame's PDU index but a different address
+            let mut aliased = late.clone();
+            aliased[17] = response[17];
+
+            assert_eq!(rx.receive_frame(&aliased), Ok(()));
+
+            assert!(waiting.as_mut().poll(ctx).is_pending());
+            assert_eq!(pdu_loop.statistics().ignored_responses, 2);
+
+            assert_eq!(rx.receive_frame(&response), Ok(()));
+
+            let Poll::Ready(Ok(frame)) = waiting.poll(ctx) else {
+                panic!("response not received");
+            };
+
+            assert_eq!(frame.first_pdu(handle).unwrap().working_counter, 1);
+            assert_eq!(pdu_loop.statistics().ignored_responses, 2);
+
+            Poll::Ready(())
+        });
+
+        cassette::block_on(poller);
+    }
+
+    #[test]
+    fn direct_rx() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let storage = PduStorage::<1, { PduStorage::element_size(32) }>::new();
+
+        let (mut tx, mut rx, pdu_loop) = storage.try_split().unwrap();
+
+        let mut inputs = [0u8; 2];
+
+        let poller = poll_fn(|ctx| {
+            let mut frame = pdu_loop.alloc_frame().unwrap();
+
+            let handle = unsafe {
+                frame.push_pdu_direct_rx(
+                    Command::lrw(LogicalAddress::new(0x1000)).into(),
+                    [0xaau8, 0xbb, 0xcc],
+                    None,
+                    NonNull::from(&mut inputs[..]),
+                )
+            }
+            .unwrap();
+
+            let mut frame =
+                pin!(frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None));
+
+            assert!(frame.as_mut().poll(ctx).is_pending());
+
+            let mut sent = Vec::new();
+
+            tx.next_sendable_frame()
+                .unwrap()
+                .send_blocking(|bytes| {
+                    sent.extend_from_slice(bytes);
+
+                    Ok(bytes.len())
+                })
+                .unwrap();
+
+            // Respond with new inputs and a working counter of 3
+            sent[6] = 0x12;
+            sent[26..29].copy_from_slice(&[0x01, 0x02, 0x03]);
+            sent[29] = 3;
+
+            assert_eq!(rx.receive_frame(&sent), Ok(()));
+
+            let Poll::Ready(Ok(frame)) = frame.poll(ctx) else {
+                panic!("response not received");
+            };
+
+            let pdu = frame.first_pdu(handle).unwrap();
+
+            // Inputs aren't copied into the frame, but the rest of the response is
+            assert_eq!(pdu.deref(), &[0xaa, 0xbb, 0x03]);
+            assert_eq!(pdu.working_counter, 3);
+
+            Poll::Ready(())
+        });
+
+        cassette::block_on(poller);
+
+        assert_eq!(inputs, [0x01, 0x02]);
+    }
 }
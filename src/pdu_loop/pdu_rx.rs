@@ -1,17 +1,19 @@
 use super::storage::PduStorageRef;
 use crate::ethernet::{EthernetAddress, EthernetFrame};
 use crate::{
-    error::{Error, PduError},
+    error::Error,
     fmt,
-    pdu_loop::frame_header::EthercatFrameHeader,
+    pdu_loop::{pdu_header::PduHeader, FrameTap},
     ETHERCAT_ETHERTYPE, MASTER_ADDR,
 };
+use core::time::Duration;
 use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
 
 /// EtherCAT frame receive adapter.
 pub struct PduRx<'sto> {
     storage: PduStorageRef<'sto>,
     source_mac: EthernetAddress,
+    tap: Option<&'sto dyn FrameTap>,
 }
 
 impl<'sto> PduRx<'sto> {
@@ -19,6 +21,7 @@ impl<'sto> PduRx<'sto> {
         Self {
             storage,
             source_mac: MASTER_ADDR,
+            tap: None,
         }
     }
 
@@ -31,10 +34,57 @@ impl<'sto> PduRx<'sto> {
         self.source_mac = new
     }
 
+    /// Pass every received EtherCAT frame to `tap`.
+    ///
+    /// With the `pcap` feature enabled, `std::PcapCapture` can be used to record frames to a file.
+    pub fn set_frame_tap(&mut self, tap: &'sto dyn FrameTap) {
+        self.tap = Some(tap);
+    }
+
     /// Given a complete Ethernet II frame, parse a response PDU from it and wake the future that
     /// sent the frame.
+    ///
+    /// If the future that sent the frame is being polled on another thread at the same time, this
+    /// method may return [`PduError::NoWaker`](crate::error::PduError::NoWaker). The frame can be
+    /// passed to this method again to retry. [`handle_received_frame`](PduRx::handle_received_frame)
+    /// does not need retrying.
     // NOTE: &mut self so this struct can only be used in one place.
     pub fn receive_frame(&mut self, ethernet_frame: &[u8]) -> Result<(), Error> {
+        self.receive(ethernet_frame, false)
+    }
+
+    /// Receive a complete Ethernet II frame from an Ethernet RX interrupt or DMA completion
+    /// handler.
+    ///
+    /// This is [`receive_frame`](PduRx::receive_frame), but it never returns
+    /// [`PduError::NoWaker`](crate::error::PduError::NoWaker): a response is stored even if the
+    /// future that sent the frame is not ready to be woken, and is picked up the next time that
+    /// future is polled. This method never blocks, spins or allocates, so received frames can be
+    /// passed to the PDU loop straight from the interrupt handler without an executor.
+    ///
+    /// Any [frame tap](PduRx::set_frame_tap) is also called from the handler, so must be fast.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// #[interrupt]
+    /// fn ETH() {
+    ///     let pdu_rx = unsafe { PDU_RX.as_mut().unwrap() };
+    ///
+    ///     while let Some(frame) = mac.next_rx_descriptor() {
+    ///         if let Err(e) = pdu_rx.handle_received_frame(frame.data()) {
+    ///             defmt::error!("Bad frame: {}", e);
+    ///         }
+    ///
+    ///         frame.release();
+    ///     }
+    /// }
+    /// ```
+    pub fn handle_received_frame(&mut self, ethernet_frame: &[u8]) -> Result<(), Error> {
+        self.receive(ethernet_frame, true)
+    }
+
+    fn receive(&mut self, ethernet_frame: &[u8], keep_unwoken: bool) -> Result<(), Error> {
         let raw_packet = EthernetFrame::new_checked(ethernet_frame)?;
 
         // Look for EtherCAT packets whilst ignoring broadcast packets sent from self. As per
@@ -42,68 +92,105 @@ impl<'sto> PduRx<'sto> {
         // first SubDevice will set the second bit of the MSB of the MAC address (U/L bit). This means
         // if we send e.g. 10:10:10:10:10:10, we receive 12:10:10:10:10:10 which passes through this
         // filter.
-        if raw_packet.ethertype() != ETHERCAT_ETHERTYPE || raw_packet.src_addr() == self.source_mac
+        if raw_packet.inner_ethertype() != ETHERCAT_ETHERTYPE
+            || raw_packet.src_addr() == self.source_mac
         {
             fmt::trace!("Ignore frame");
 
             return Ok(());
         }
 
-        let i = raw_packet.payload();
+        let received_at = crate::timer_factory::now();
+
+        if let Some(tap) = self.tap {
+            tap.on_frame_received(ethernet_frame, received_at);
+        }
 
-        let frame_header = EthercatFrameHeader::unpack_from_slice(i).map_err(|e| {
-            fmt::error!("Failed to parse frame header: {}", e);
+        // Strip the VLAN tag, if any, then skip EtherCAT header and get PDU(s) payload
+        let i = crate::parse::ethercat_pdus(raw_packet.inner_payload()).map_err(|e| {
+            fmt::error!("Failed to parse received frame: {}", e);
 
             e
         })?;
 
-        // Skip EtherCAT header and get PDU(s) payload
-        let i = i
-            .get(
-                EthercatFrameHeader::PACKED_LEN
-                    ..(EthercatFrameHeader::PACKED_LEN + usize::from(frame_header.payload_len)),
-            )
-            .ok_or_else(|| {
-                fmt::error!("Received frame is too short");
+        // `i` now contains the EtherCAT frame payload, consisting of one or more PDUs including
+        // their headers and payloads. If `PduTx` packed multiple frames together, the PDUs of each
+        // frame directly follow those of the previous frame.
+        let mut rest = i;
 
-                Error::ReceiveFrame
-            })?;
+        loop {
+            let len = self.receive_pdus(rest, received_at, keep_unwoken)?;
 
-        // `i` now contains the EtherCAT frame payload, consisting of one or more PDUs including
-        // their headers and payloads.
+            rest = rest.get(len..).unwrap_or_default();
+
+            if rest.is_empty() || len == 0 {
+                break;
+            }
+        }
+
+        self.storage.statistics.record_frame_received();
+
+        Ok(())
+    }
 
+    /// Copy the PDUs at the start of `pdus` into the frame that sent them and wake its future.
+    ///
+    /// Returns the number of bytes belonging to that frame.
+    fn receive_pdus(
+        &mut self,
+        pdus: &[u8],
+        received_at: Duration,
+        keep_unwoken: bool,
+    ) -> Result<usize, Error> {
         // Second byte of first PDU header is the index
-        let pdu_idx = *i.get(1).ok_or(Error::Internal)?;
+        let pdu_idx = *pdus.get(1).ok_or(Error::Internal)?;
 
-        // We're assuming all PDUs in the returned frame have the same frame index, so we can just
-        // use the first one.
+        // PDU has its own EtherCAT index. This needs mapping back to the original frame. All PDUs
+        // up to the length of the sent frame belong to the same frame.
+        let mut frame = match self.storage.claim_receiving_by_first_pdu(pdu_idx) {
+            Some(frame) if frame.is_response(pdus) => frame,
+            Some(frame) => {
+                frame.release_unmatched();
 
-        // PDU has its own EtherCAT index. This needs mapping back to the original frame.
-        let frame_index = self
-            .storage
-            .frame_index_by_first_pdu_index(pdu_idx)
-            .ok_or(Error::Pdu(PduError::Decode))?;
+                return self.ignore_pdu(pdus);
+            }
+            None => return self.ignore_pdu(pdus),
+        };
 
         fmt::trace!(
             "Receiving frame index {} (found from PDU {:#04x})",
-            frame_index,
+            frame.frame_index(),
             pdu_idx
         );
 
-        let mut frame = self
-            .storage
-            .claim_receiving(frame_index)
-            .ok_or(PduError::InvalidIndex(frame_index))?;
+        self.storage
+            .statistics
+            .record_round_trip(received_at.saturating_sub(frame.sent_at()));
 
-        let frame_data = frame.buf_mut();
+        let len = frame.pdu_payload_len().min(pdus.len());
 
-        frame_data
-            .get_mut(0..i.len())
-            .ok_or(Error::Internal)?
-            .copy_from_slice(i);
+        frame.write_response(&pdus[0..len])?;
 
-        frame.mark_received()?;
+        frame.mark_received(keep_unwoken)?;
 
-        Ok(())
+        Ok(len)
+    }
+
+    /// Skip over the first PDU in `pdus` if it is not a response to any frame waiting for one,
+    /// e.g. a late response to a frame that timed out, or a duplicated frame.
+    ///
+    /// Returns the length of the PDU so any following PDUs, which may belong to another packed
+    /// frame, are still received.
+    fn ignore_pdu(&mut self, pdus: &[u8]) -> Result<usize, Error> {
+        let header = PduHeader::unpack_from_slice(pdus)?;
+
+        fmt::warn!(
+            "Ignoring response PDU {:#04x}: no matching frame is waiting for a response",
+            header.index
+        );
+
+        self.storage.statistics.record_ignored_response();
+
+        Ok((PduHeader::PACKED_LEN + usize::from(header.flags.len()) + 2).min(pdus.len()))
     }
 }
@@ -1,14 +1,17 @@
-use super::{frame_element::sendable_frame::SendableFrame, storage::PduStorageRef};
+use super::{
+    frame_element::sendable_frame::SendableFrame, storage::PduStorageRef, FramePriority, FrameTap,
+};
 use core::task::Waker;
 
 /// EtherCAT frame transmit adapter.
 pub struct PduTx<'sto> {
     storage: PduStorageRef<'sto>,
+    tap: Option<&'sto dyn FrameTap>,
 }
 
 impl<'sto> PduTx<'sto> {
     pub(in crate::pdu_loop) fn new(storage: PduStorageRef<'sto>) -> Self {
-        Self { storage }
+        Self { storage, tap: None }
     }
 
     /// The number of frames that can be in flight at once.
@@ -16,26 +19,72 @@ impl<'sto> PduTx<'sto> {
         self.storage.num_frames
     }
 
+    /// Pass every sent frame to `tap`.
+    ///
+    /// With the `pcap` feature enabled, `std::PcapCapture` can be used to record frames to a file.
+    pub fn set_frame_tap(&mut self, tap: &'sto dyn FrameTap) {
+        self.tap = Some(tap);
+    }
+
     /// Get the next sendable frame, if any are available.
+    ///
+    /// Frames containing cyclic process data are returned before any other frames.
+    ///
+    /// If [`MainDeviceConfig::frame_packing`](crate::MainDeviceConfig::frame_packing) is enabled,
+    /// other sendable frames are packed into the returned frame where they fit. Frames of
+    /// acyclic traffic are never packed into a process data frame.
     // NOTE: Mutable so it can only be used in one task.
     pub fn next_sendable_frame(&mut self) -> Option<SendableFrame<'sto>> {
-        for idx in 0..self.storage.num_frames {
-            let frame = self.storage.frame_at_index(idx);
+        self.next_sendable_frame_in(FramePriority::High)
+            .or_else(|| self.next_sendable_frame_in(FramePriority::Low))
+    }
 
-            let Some(sending) = SendableFrame::claim_sending(
-                frame,
-                self.storage.pdu_idx,
-                self.storage.frame_data_len,
-            ) else {
+    fn next_sendable_frame_in(&self, priority: FramePriority) -> Option<SendableFrame<'sto>> {
+        for idx in 0..self.storage.num_frames {
+            let Some(mut sending) = self.claim_sending(idx) else {
                 continue;
             };
 
+            if sending.priority() != priority {
+                sending.release_sending_claim();
+
+                continue;
+            }
+
+            for other_idx in (0..self.storage.num_frames).filter(|other| *other != idx) {
+                if sending.is_full() {
+                    break;
+                }
+
+                let Some(other) = self.claim_sending(other_idx) else {
+                    continue;
+                };
+
+                if other.priority() < priority {
+                    other.release_sending_claim();
+
+                    continue;
+                }
+
+                if let Err(other) = sending.pack(other) {
+                    other.release_sending_claim();
+                }
+            }
+
             return Some(sending);
         }
 
         None
     }
 
+    fn claim_sending(&self, idx: usize) -> Option<SendableFrame<'sto>> {
+        SendableFrame::claim_sending(
+            self.storage.frame_at_index(idx),
+            self.storage.clone(),
+            self.tap,
+        )
+    }
+
     /// Set or replace the PDU loop waker.
     ///
     /// The waker must be set otherwise the future in charge of sending new packets will not be
@@ -0,0 +1,169 @@
+use crate::{
+    atomic::{AtomicU32, Ordering},
+    error::Error,
+    pdu_loop::latency::{LatencyCounters, LatencyHistogram},
+};
+use core::time::Duration;
+
+/// Network communication statistics.
+///
+/// These counters can be used to display bus health, e.g. in an HMI. Counters start at zero when
+/// the [`PduStorage`](crate::PduStorage) is created and wrap on overflow.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Statistics {
+    /// The number of frames sent over the network, including resent frames.
+    pub frames_sent: u32,
+
+    /// The number of EtherCAT frames received from the network.
+    pub frames_received: u32,
+
+    /// The number of times a timed out frame was resent.
+    ///
+    /// Retries are configured with [`MainDeviceConfig::retry_behaviour`](crate::MainDeviceConfig::retry_behaviour).
+    pub pdu_retries: u32,
+
+    /// The number of frames that timed out with no retries remaining.
+    pub pdu_timeouts: u32,
+
+    /// The number of PDU responses with an unexpected working counter.
+    pub wkc_errors: u32,
+
+    /// The number of mailbox responses indicating an error, e.g. an SDO abort or CoE emergency.
+    pub mailbox_errors: u32,
+
+    /// The number of SubDevice state transitions that failed with an error reported by the
+    /// SubDevice.
+    pub state_transition_failures: u32,
+
+    /// The number of received PDUs that were ignored because no frame was waiting for them, e.g.
+    /// late responses to timed out frames or duplicated frames.
+    pub ignored_responses: u32,
+}
+
+/// Live counters backing [`Statistics`].
+#[derive(Debug)]
+pub(crate) struct StatisticsCounters {
+    frames_sent: AtomicU32,
+    frames_received: AtomicU32,
+    pdu_retries: AtomicU32,
+    pdu_timeouts: AtomicU32,
+    wkc_errors: AtomicU32,
+    mailbox_errors: AtomicU32,
+    state_transition_failures: AtomicU32,
+    ignored_responses: AtomicU32,
+    latency: LatencyCounters,
+}
+
+impl StatisticsCounters {
+    pub(crate) const fn new() -> Self {
+        Self {
+            frames_sent: AtomicU32::new(0),
+            frames_received: AtomicU32::new(0),
+            pdu_retries: AtomicU32::new(0),
+            pdu_timeouts: AtomicU32::new(0),
+            wkc_errors: AtomicU32::new(0),
+            mailbox_errors: AtomicU32::new(0),
+            state_transition_failures: AtomicU32::new(0),
+            ignored_responses: AtomicU32::new(0),
+            latency: LatencyCounters::new(),
+        }
+    }
+
+    pub(crate) fn record_frame_sent(&self) {
+        self.frames_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_frame_received(&self) {
+        self.frames_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_round_trip(&self, round_trip: Duration) {
+        self.latency.record(round_trip);
+    }
+
+    pub(crate) fn record_ignored_response(&self) {
+        self.ignored_responses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_retry(&self) {
+        self.pdu_retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_timeout(&self) {
+        self.pdu_timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count an error if it is one of the kinds tracked by [`Statistics`].
+    pub(crate) fn record_error(&self, error: &Error) {
+        let counter = match error.cause() {
+            Error::WorkingCounter { .. } => &self.wkc_errors,
+            Error::Mailbox(_) => &self.mailbox_errors,
+            Error::StateTransition { .. } => &self.state_transition_failures,
+            _ => return,
+        };
+
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> Statistics {
+        Statistics {
+            frames_sent: self.frames_sent.load(Ordering::Relaxed),
+            frames_received: self.frames_received.load(Ordering::Relaxed),
+            pdu_retries: self.pdu_retries.load(Ordering::Relaxed),
+            pdu_timeouts: self.pdu_timeouts.load(Ordering::Relaxed),
+            wkc_errors: self.wkc_errors.load(Ordering::Relaxed),
+            mailbox_errors: self.mailbox_errors.load(Ordering::Relaxed),
+            state_transition_failures: self.state_transition_failures.load(Ordering::Relaxed),
+            ignored_responses: self.ignored_responses.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn latency_snapshot(&self) -> LatencyHistogram {
+        self.latency.snapshot()
+    }
+
+    pub(crate) fn reset_latency(&self) {
+        self.latency.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::{MailboxError, PduCommand},
+        AlStatusCode, ConfiguredAddress, SubDeviceState,
+    };
+
+    #[test]
+    fn record_errors() {
+        let counters = StatisticsCounters::new();
+
+        counters.record_error(&Error::WorkingCounter {
+            expected: 2,
+            received: 1,
+            command: PduCommand::NOP,
+        });
+        counters.record_error(&Error::Mailbox(MailboxError::NoMailbox));
+        counters.record_error(&Error::StateTransition {
+            configured_address: ConfiguredAddress::new(0x1000),
+            requested: SubDeviceState::Op,
+            current: SubDeviceState::SafeOp,
+            status_code: AlStatusCode::SyncManagerWatchdog,
+        });
+        // Not counted
+        counters.record_error(&Error::Internal);
+
+        assert_eq!(
+            counters.snapshot(),
+            Statistics {
+                wkc_errors: 1,
+                mailbox_errors: 1,
+                state_transition_failures: 1,
+                ..Statistics::default()
+            }
+        );
+    }
+}
@@ -1,48 +1,57 @@
-use super::{frame_header::EthercatFrameHeader, pdu_rx::PduRx, pdu_tx::PduTx};
+use super::{
+    frame_header::EthercatFrameHeader, pdu_rx::PduRx, pdu_tx::PduTx, statistics::StatisticsCounters,
+};
+#[cfg(feature = "alloc")]
+use crate::error::{ConfigError, Error};
 use crate::ethernet::EthernetFrame;
 use crate::{
-    error::{Error, PduError},
+    atomic::{AtomicBool, AtomicU8, Ordering},
     fmt,
     pdu_loop::{
         frame_element::{
             created_frame::CreatedFrame, receiving_frame::ReceivingFrame, FrameElement,
         },
         pdu_flags::PduFlags,
+        pdu_header::PduHeader,
+        FramePriority,
     },
-    PduLoop,
+    waiters::Waiters,
+    PdiTransfer, PduLoop,
 };
 use atomic_waker::AtomicWaker;
-use core::{
-    alloc::Layout,
-    cell::UnsafeCell,
-    marker::PhantomData,
-    mem::MaybeUninit,
-    ptr::NonNull,
-    sync::atomic::{AtomicBool, AtomicU8, Ordering},
-};
+use core::{alloc::Layout, cell::UnsafeCell, marker::PhantomData, mem::MaybeUninit, ptr::NonNull};
 use ethercrab_wire::EtherCrabWireSized;
 
 /// Smallest frame size with a data payload of 0 length
 const MIN_DATA: usize = EthernetFrame::<&[u8]>::buffer_len(
     EthercatFrameHeader::header_len()
-                    + super::pdu_header::PduHeader::PACKED_LEN
+                    + PduHeader::PACKED_LEN
                     // PDU payload
                     + PduFlags::const_default().len() as usize
                     // Working counter
                     + 2,
 );
 
+/// Overhead of each additional PDU in a frame: PDU header and working counter.
+const PDU_OVERHEAD: usize = PduHeader::PACKED_LEN + 2;
+
 /// Stores PDU frames that are currently being prepared to send, in flight, or being received and
 /// processed.
 ///
-/// The number of storage elements `N` must be a power of 2.
+/// The number of storage elements `N` must be a power of 2. If `N` is greater than 1, one element
+/// is reserved for process data frames sent by [`SubDeviceGroup`](crate::SubDeviceGroup)s so
+/// acyclic traffic can never delay them.
 pub struct PduStorage<const N: usize, const DATA: usize> {
     frames: UnsafeCell<MaybeUninit<[FrameElement<DATA>; N]>>,
     frame_idx: AtomicU8,
     pdu_idx: AtomicU8,
     is_split: AtomicBool,
+    statistics: StatisticsCounters,
     /// A waker used to wake up the TX task when a new frame is ready to be sent.
     pub(in crate::pdu_loop) tx_waker: AtomicWaker,
+    frame_waiters: Waiters,
+    /// The number of allocated low priority frames.
+    low_priority_frames: AtomicU8,
 }
 
 unsafe impl<const N: usize, const DATA: usize> Sync for PduStorage<N, DATA> {}
@@ -71,6 +80,160 @@ impl PduStorage<0, 0> {
     pub const fn element_size(data_len: usize) -> usize {
         MIN_DATA + data_len
     }
+
+    /// Calculate the process data image length of a group from the `(input bytes, output bytes)`
+    /// of each SubDevice in it.
+    ///
+    /// This can be used as the `MAX_PDI` const generic of a
+    /// [`SubDeviceGroup`](crate::SubDeviceGroup).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ethercrab::PduStorage;
+    ///
+    /// // EK1100 coupler, EL2004 with 1 output byte, EL1004 with 1 input byte and a servo drive
+    /// const PDI_LEN: usize = PduStorage::pdi_len(&[(0, 0), (0, 1), (1, 0), (14, 12)]);
+    ///
+    /// assert_eq!(PDI_LEN, 28);
+    /// ```
+    pub const fn pdi_len(subdevices: &[(usize, usize)]) -> usize {
+        let mut len = 0;
+        let mut i = 0;
+
+        while i < subdevices.len() {
+            let (inputs, outputs) = subdevices[i];
+
+            len += inputs + outputs;
+
+            i += 1;
+        }
+
+        len
+    }
+
+    /// Calculate the PDU data length required to exchange a group's process data image of
+    /// `pdi_len` bytes in a single frame.
+    ///
+    /// [`PdiTransfer::LrdLwr`] sends an extra PDU, and `dc_sync` adds the `FRMW` sent by
+    /// [`tx_rx_sync_system_time`](crate::SubDeviceGroup::tx_rx_sync_system_time). Pass the result
+    /// to [`element_size`](PduStorage::element_size) to get the `DATA` const generic.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ethercrab::{PdiTransfer, PduStorage};
+    ///
+    /// const PDI_LEN: usize = 64;
+    /// const MAX_PDU_DATA: usize =
+    ///     PduStorage::element_size(PduStorage::pdi_data_len(PDI_LEN, PdiTransfer::Lrw, true));
+    ///
+    /// // 64 byte PDI plus 12 byte PDU overhead and 8 bytes of system time for the `FRMW`
+    /// assert_eq!(MAX_PDU_DATA, PduStorage::element_size(84));
+    /// ```
+    pub const fn pdi_data_len(pdi_len: usize, transfer: PdiTransfer, dc_sync: bool) -> usize {
+        let transfer_overhead = match transfer {
+            PdiTransfer::Lrw => 0,
+            PdiTransfer::LrdLwr => PDU_OVERHEAD,
+        };
+
+        let dc_overhead = if dc_sync {
+            PDU_OVERHEAD + core::mem::size_of::<u64>()
+        } else {
+            0
+        };
+
+        pdi_len + transfer_overhead + dc_overhead
+    }
+
+    /// Calculate the number of storage elements `N` required to run `groups` SubDevice groups
+    /// concurrently.
+    ///
+    /// One frame is allowed for each group's process data, plus one for acyclic traffic like
+    /// status checks and mailbox transfers, rounded up to a power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ethercrab::PduStorage;
+    ///
+    /// assert_eq!(PduStorage::num_frames(1), 2);
+    /// assert_eq!(PduStorage::num_frames(3), 4);
+    /// assert_eq!(PduStorage::num_frames(4), 8);
+    /// ```
+    pub const fn num_frames(groups: usize) -> usize {
+        (groups + 1).next_power_of_two()
+    }
+
+    /// Allocate PDU storage on the heap, with the number of frames and their size chosen at
+    /// runtime.
+    ///
+    /// This is an alternative to a `static` [`PduStorage`] for when storage size isn't known at
+    /// compile time, e.g. when it is read from a configuration file. `frames` has the same meaning
+    /// as `N`. `pdu_len` is the maximum PDU payload length in each frame, with the header overhead
+    /// added as in [`element_size`](PduStorage::element_size).
+    ///
+    /// Requires the `alloc` feature, which is enabled by `std`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::InvalidStorageSize`] if `frames` is zero, larger than `u8::MAX` or
+    /// not a power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+    ///
+    /// let (frames, pdu_len) = (16, 1100);
+    ///
+    /// // Leak the storage so the TX/RX task and `MainDevice` can be `'static`
+    /// let storage = Box::leak(Box::new(
+    ///     PduStorage::new_boxed(frames, pdu_len).expect("invalid storage size"),
+    /// ));
+    ///
+    /// let (tx, rx, maindevice) =
+    ///     MainDevice::from_storage(storage, Timeouts::default(), MainDeviceConfig::default())
+    ///         .expect("storage already in use");
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn new_boxed(frames: usize, pdu_len: usize) -> Result<BoxedPduStorage, Error> {
+        if frames == 0 || frames > usize::from(u8::MAX) || !frames.is_power_of_two() {
+            return Err(Error::Config(ConfigError::InvalidStorageSize));
+        }
+
+        let frame_data_len = Self::element_size(pdu_len);
+
+        let element = FrameElement::<0>::layout_with_data(frame_data_len)
+            .map_err(|_| Error::Config(ConfigError::InvalidStorageSize))?;
+
+        let layout = Layout::from_size_align(element.size() * frames, element.align())
+            .map_err(|_| Error::Config(ConfigError::InvalidStorageSize))?;
+
+        // SAFETY: The layout is never zero sized as it contains at least one frame element. A
+        // zeroed `FrameElement` is in the `FrameState::None` state, the same as the `MaybeUninit`
+        // zeroed array used by `PduStorage::new`.
+        let frames_ptr = unsafe { alloc::alloc::alloc_zeroed(layout) };
+
+        let Some(frames_ptr) = NonNull::new(frames_ptr.cast()) else {
+            alloc::alloc::handle_alloc_error(layout);
+        };
+
+        Ok(BoxedPduStorage {
+            frames: frames_ptr,
+            layout,
+            frame_element_stride: element.size(),
+            num_frames: frames,
+            frame_data_len,
+            frame_idx: AtomicU8::new(0),
+            pdu_idx: AtomicU8::new(0),
+            is_split: AtomicBool::new(false),
+            statistics: StatisticsCounters::new(),
+            tx_waker: AtomicWaker::new(),
+            frame_waiters: Waiters::new(),
+            low_priority_frames: AtomicU8::new(0),
+        })
+    }
 }
 
 impl<const N: usize, const DATA: usize> PduStorage<N, DATA> {
@@ -117,10 +280,66 @@ impl<const N: usize, const DATA: usize> PduStorage<N, DATA> {
             frame_idx: AtomicU8::new(0),
             pdu_idx: AtomicU8::new(0),
             is_split: AtomicBool::new(false),
+            statistics: StatisticsCounters::new(),
             tx_waker: AtomicWaker::new(),
+            frame_waiters: Waiters::new(),
+            low_priority_frames: AtomicU8::new(0),
         }
     }
 
+    /// Check that this storage can run `groups` SubDevice groups concurrently, each with a process
+    /// data image needing at most `pdi_data_len` bytes of PDU data.
+    ///
+    /// Use [`pdi_data_len`](PduStorage::pdi_data_len) to compute `pdi_data_len`. Evaluate this
+    /// method in a `const` item to turn undersized storage into a compile error instead of a panic
+    /// or error at runtime.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if
+    ///
+    /// - `N` is smaller than [`num_frames(groups)`](PduStorage::num_frames), or
+    /// - `DATA` is smaller than [`element_size(pdi_data_len)`](PduStorage::element_size).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ethercrab::{PdiTransfer, PduStorage};
+    ///
+    /// const MAX_FRAMES: usize = 16;
+    /// const MAX_PDU_DATA: usize = PduStorage::element_size(1100);
+    /// const PDI_LEN: usize = PduStorage::pdi_len(&[(0, 0), (0, 1), (1, 0), (14, 12)]);
+    ///
+    /// static PDU_STORAGE: PduStorage<MAX_FRAMES, MAX_PDU_DATA> = PduStorage::new();
+    ///
+    /// const _: () = PduStorage::<MAX_FRAMES, MAX_PDU_DATA>::assert_fits(
+    ///     1,
+    ///     PduStorage::pdi_data_len(PDI_LEN, PdiTransfer::Lrw, true),
+    /// );
+    /// ```
+    ///
+    /// Storage that is too small fails to compile:
+    ///
+    /// ```rust,compile_fail
+    /// use ethercrab::{PdiTransfer, PduStorage};
+    ///
+    /// const _: () = PduStorage::<4, { PduStorage::element_size(64) }>::assert_fits(
+    ///     1,
+    ///     PduStorage::pdi_data_len(128, PdiTransfer::Lrw, false),
+    /// );
+    /// ```
+    pub const fn assert_fits(groups: usize, pdi_data_len: usize) {
+        assert!(
+            N >= PduStorage::num_frames(groups),
+            "Not enough storage elements for the number of groups"
+        );
+
+        assert!(
+            DATA >= PduStorage::element_size(pdi_data_len),
+            "DATA is too small to hold the process data image"
+        );
+    }
+
     /// Create a PDU loop backed by this storage.
     ///
     /// Returns a TX and RX driver, and a handle to the PDU loop. This method will return an error
@@ -132,22 +351,10 @@ impl<const N: usize, const DATA: usize> PduStorage<N, DATA> {
     /// more than once on any given `PduStorage`.
     #[allow(clippy::result_unit_err)]
     pub fn try_split(&self) -> Result<(PduTx<'_>, PduRx<'_>, PduLoop<'_>), ()> {
-        self.is_split
-            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
-            // TODO: Make try_split const when ? is allowed in const methods, tracking issue
-            // <https://github.com/rust-lang/rust/issues/74935>
-            .map_err(|_| ())?;
-
-        let storage = self.as_ref();
-
-        Ok((
-            PduTx::new(storage.clone()),
-            PduRx::new(storage.clone()),
-            PduLoop::new(storage),
-        ))
+        split(&self.is_split, self.as_ref())
     }
 
-    fn as_ref(&self) -> PduStorageRef {
+    pub(in crate::pdu_loop) fn as_ref(&self) -> PduStorageRef {
         PduStorageRef {
             frames: unsafe { NonNull::new_unchecked(self.frames.get().cast()) },
             frame_element_stride: Layout::array::<FrameElement<DATA>>(N).unwrap().size() / N,
@@ -156,11 +363,119 @@ impl<const N: usize, const DATA: usize> PduStorage<N, DATA> {
             frame_idx: &self.frame_idx,
             pdu_idx: &self.pdu_idx,
             tx_waker: &self.tx_waker,
+            statistics: &self.statistics,
+            frame_waiters: &self.frame_waiters,
+            low_priority_frames: &self.low_priority_frames,
             _lifetime: PhantomData,
         }
     }
 }
 
+/// Heap allocated PDU storage, created with [`PduStorage::new_boxed`].
+///
+/// This behaves the same as [`PduStorage`], but its size is chosen at runtime. The storage is freed
+/// when this value is dropped, which can only happen once the [`PduTx`], [`PduRx`] and [`PduLoop`]
+/// borrowed from it have been dropped.
+#[cfg(feature = "alloc")]
+pub struct BoxedPduStorage {
+    frames: NonNull<FrameElement<0>>,
+    layout: Layout,
+    frame_element_stride: usize,
+    num_frames: usize,
+    frame_data_len: usize,
+    frame_idx: AtomicU8,
+    pdu_idx: AtomicU8,
+    is_split: AtomicBool,
+    statistics: StatisticsCounters,
+    tx_waker: AtomicWaker,
+    frame_waiters: Waiters,
+    /// The number of allocated low priority frames.
+    low_priority_frames: AtomicU8,
+}
+
+#[cfg(feature = "alloc")]
+unsafe impl Send for BoxedPduStorage {}
+#[cfg(feature = "alloc")]
+unsafe impl Sync for BoxedPduStorage {}
+
+#[cfg(feature = "alloc")]
+impl BoxedPduStorage {
+    /// The number of frames in this storage.
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    /// The size of each frame element in bytes, including all headers.
+    pub fn element_size(&self) -> usize {
+        self.frame_data_len
+    }
+
+    /// Create a PDU loop backed by this storage.
+    ///
+    /// This is equivalent to [`PduStorage::try_split`].
+    ///
+    /// # Errors
+    ///
+    /// To maintain ownership and lifetime invariants, `try_split` will return an error if called
+    /// more than once on any given `BoxedPduStorage`.
+    #[allow(clippy::result_unit_err)]
+    pub fn try_split(&self) -> Result<(PduTx<'_>, PduRx<'_>, PduLoop<'_>), ()> {
+        split(
+            &self.is_split,
+            PduStorageRef {
+                frames: self.frames,
+                frame_element_stride: self.frame_element_stride,
+                num_frames: self.num_frames,
+                frame_data_len: self.frame_data_len,
+                frame_idx: &self.frame_idx,
+                pdu_idx: &self.pdu_idx,
+                tx_waker: &self.tx_waker,
+                statistics: &self.statistics,
+                frame_waiters: &self.frame_waiters,
+                low_priority_frames: &self.low_priority_frames,
+                _lifetime: PhantomData,
+            },
+        )
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Debug for BoxedPduStorage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("BoxedPduStorage")
+            .field("num_frames", &self.num_frames)
+            .field("frame_data_len", &self.frame_data_len)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Drop for BoxedPduStorage {
+    fn drop(&mut self) {
+        // SAFETY: `frames` was allocated in `PduStorage::new_boxed` with the same layout. Any
+        // handles referencing the storage borrow `self`, so must already have been dropped.
+        unsafe { alloc::alloc::dealloc(self.frames.as_ptr().cast(), self.layout) }
+    }
+}
+
+/// Split storage into TX, RX and PDU loop handles, failing if it has been split before.
+fn split<'sto>(
+    is_split: &AtomicBool,
+    storage: PduStorageRef<'sto>,
+) -> Result<(PduTx<'sto>, PduRx<'sto>, PduLoop<'sto>), ()> {
+    is_split
+        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
+        // TODO: Make try_split const when ? is allowed in const methods, tracking issue
+        // <https://github.com/rust-lang/rust/issues/74935>
+        .map_err(|_| ())?;
+
+    Ok((
+        PduTx::new(storage.clone()),
+        PduRx::new(storage.clone()),
+        PduLoop::new(storage),
+    ))
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct PduStorageRef<'sto> {
     frames: NonNull<FrameElement<0>>,
@@ -171,12 +486,80 @@ pub(crate) struct PduStorageRef<'sto> {
     frame_idx: &'sto AtomicU8,
     pub pdu_idx: &'sto AtomicU8,
     pub tx_waker: &'sto AtomicWaker,
+    pub statistics: &'sto StatisticsCounters,
+    pub frame_waiters: &'sto Waiters,
+    low_priority_frames: &'sto AtomicU8,
     _lifetime: PhantomData<&'sto ()>,
 }
 
 impl<'sto> PduStorageRef<'sto> {
+    /// The largest PDU payload that fits in a single frame.
+    pub(in crate::pdu_loop) fn max_pdu_data(&self) -> usize {
+        self.frame_data_len.saturating_sub(MIN_DATA)
+    }
+
     /// Allocate a PDU frame with the given command and data length.
-    pub(in crate::pdu_loop) fn alloc_frame(&self) -> Result<CreatedFrame<'sto>, Error> {
+    #[cfg(test)]
+    pub(in crate::pdu_loop) fn alloc_frame(
+        &self,
+    ) -> Result<CreatedFrame<'sto>, crate::error::Error> {
+        self.try_alloc_frame(FramePriority::High).ok_or_else(|| {
+            // We've searched twice and found no free slots. This means the application should
+            // either slow down its packet sends, or increase `N` in `PduStorage` as there
+            // aren't enough slots to hold all in-flight packets.
+            fmt::error!("No available frames in {} slots", self.num_frames);
+
+            crate::error::PduError::SwapState.into()
+        })
+    }
+
+    /// Allocate a PDU frame, returning `None` if no frames are free.
+    ///
+    /// Low priority frames may not use the last free frame so one is always available for high
+    /// priority frames.
+    pub(in crate::pdu_loop) fn try_alloc_frame(
+        &self,
+        priority: FramePriority,
+    ) -> Option<CreatedFrame<'sto>> {
+        if priority == FramePriority::Low {
+            // A single frame can't be reserved without blocking low priority frames entirely
+            let limit = self.num_frames.saturating_sub(1).max(1) as u8;
+
+            self.low_priority_frames
+                .fetch_update(Ordering::AcqRel, Ordering::Relaxed, |allocated| {
+                    (allocated < limit).then_some(allocated + 1)
+                })
+                .ok()?;
+        }
+
+        let frame = self.claim_free_frame(priority);
+
+        if frame.is_none() && priority == FramePriority::Low {
+            self.release_low_priority_frame();
+        }
+
+        frame
+    }
+
+    /// Called when a frame is free to be allocated again.
+    pub(in crate::pdu_loop) fn frame_released(&self, priority: FramePriority) {
+        if priority == FramePriority::Low {
+            self.release_low_priority_frame();
+        }
+
+        self.frame_waiters.wake_all();
+    }
+
+    /// Record that a low priority frame is free again.
+    fn release_low_priority_frame(&self) {
+        let _ = self.low_priority_frames.fetch_update(
+            Ordering::AcqRel,
+            Ordering::Relaxed,
+            |allocated| allocated.checked_sub(1),
+        );
+    }
+
+    fn claim_free_frame(&self, priority: FramePriority) -> Option<CreatedFrame<'sto>> {
         // Find next frame that is not currently in use.
         //
         // Escape hatch: we'll only loop through the frame storage array twice to put an upper
@@ -196,20 +579,16 @@ impl<'sto> PduStorageRef<'sto> {
             // variable in the frame, and the atomic index counter above.
             let frame = self.frame_at_index(usize::from(frame_idx));
 
-            let frame =
-                CreatedFrame::claim_created(frame, frame_idx, self.pdu_idx, self.frame_data_len);
+            let frame = CreatedFrame::claim_created(frame, frame_idx, self.clone(), priority);
 
             if let Ok(f) = frame {
-                return Ok(f);
+                self.skip_used_first_pdu_indices();
+
+                return Some(f);
             }
         }
 
-        // We've searched twice and found no free slots. This means the application should
-        // either slow down its packet sends, or increase `N` in `PduStorage` as there
-        // aren't enough slots to hold all in-flight packets.
-        fmt::error!("No available frames in {} slots", self.num_frames);
-
-        Err(PduError::SwapState.into())
+        None
     }
 
     /// Updates state from SENDING -> RX_BUSY
@@ -232,26 +611,49 @@ impl<'sto> PduStorageRef<'sto> {
         )
     }
 
-    pub(in crate::pdu_loop) fn frame_index_by_first_pdu_index(
-        &self,
-        search_pdu_idx: u8,
-    ) -> Option<u8> {
-        for frame_index in 0..self.num_frames {
-            // SAFETY: Frames pointer will always be non-null as it was created by Rust code.
-            let frame = unsafe {
-                NonNull::new_unchecked(
-                    self.frames
-                        .as_ptr()
-                        .byte_add(frame_index * self.frame_element_stride),
-                )
-            };
-
-            if unsafe { FrameElement::<0>::first_pdu_is(frame, search_pdu_idx) } {
-                return Some(frame_index as u8);
+    /// Advance the PDU index counter past any index used as the first PDU index of another frame.
+    ///
+    /// The 8 bit PDU index wraps after 256 PDUs, so a frame that stays in flight for a long time,
+    /// e.g. with deep pipelining or frame packing, could otherwise share its first PDU index with a
+    /// newer frame, mixing up their responses. The counter is only moved if the next index is in
+    /// use, so PDU indices stay sequential otherwise.
+    fn skip_used_first_pdu_indices(&self) {
+        // At most `num_frames - 1` other frames can hold an index, so a free one is always found
+        for _ in 0..self.num_frames {
+            let pdu_idx = self.pdu_idx.load(Ordering::Relaxed);
+
+            let in_use = (0..self.num_frames).any(|frame_index| unsafe {
+                FrameElement::<0>::first_pdu_in_use(self.frame_at_index(frame_index), pdu_idx)
+            });
+
+            if !in_use {
+                return;
             }
+
+            fmt::trace!("PDU index {:#04x} is still in use, skipping", pdu_idx);
+
+            // Another frame may have taken this index already, in which case check the next one
+            let _ = self.pdu_idx.compare_exchange(
+                pdu_idx,
+                pdu_idx.wrapping_add(1),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
         }
+    }
 
-        None
+    /// Claim the sent frame whose first PDU has the index `pdu_idx`, ready to store its response.
+    ///
+    /// Frames that are not waiting for a response, e.g. because they timed out, are skipped.
+    pub(in crate::pdu_loop) fn claim_receiving_by_first_pdu(
+        &self,
+        pdu_idx: u8,
+    ) -> Option<ReceivingFrame<'sto>> {
+        (0..self.num_frames)
+            .filter(|frame_index| unsafe {
+                FrameElement::<0>::first_pdu_is(self.frame_at_index(*frame_index), pdu_idx)
+            })
+            .find_map(|frame_index| self.claim_receiving(frame_index as u8))
     }
 
     /// Retrieve a frame at the given index.
@@ -280,7 +682,7 @@ unsafe impl<'sto> Sync for PduStorageRef<'sto> {}
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{pdu_loop::pdu_header::PduHeader, Command};
+    use crate::{error::PduError, Command, ConfiguredAddress, LogicalAddress, RetryBackoff};
     use core::time::Duration;
 
     #[test]
@@ -298,7 +700,7 @@ mod tests {
             .unwrap();
 
         // Drop frame future to reset its state to `FrameState::None`
-        drop(frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX));
+        drop(frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None));
 
         let mut frame = pdu_loop.alloc_frame().expect("Allocate second frame");
 
@@ -310,7 +712,7 @@ mod tests {
             + EthercatFrameHeader::header_len()
             + PduHeader::PACKED_LEN;
 
-        let frame = frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX);
+        let frame = frame.mark_sendable(&pdu_loop, Duration::MAX, usize::MAX, RetryBackoff::None);
 
         // 10 byte PDU header, 8 byte payload, 2 byte WKC
         assert_eq!(
@@ -331,10 +733,94 @@ mod tests {
         let storage: PduStorage<NUM_FRAMES, DATA> = PduStorage::new();
         let s = storage.as_ref();
 
-        for _ in 0..NUM_FRAMES {
-            assert!(s.alloc_frame().is_ok());
-        }
+        // Frames are released when dropped, so keep hold of them
+        let _frames: [_; NUM_FRAMES] =
+            core::array::from_fn(|_| s.alloc_frame().expect("Allocate frame"));
 
         assert!(s.alloc_frame().is_err());
     }
+
+    #[test]
+    fn pdi_sizing() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        const PDI_LEN: usize = PduStorage::pdi_len(&[(0, 0), (0, 1), (1, 0), (14, 12)]);
+        const DATA_LEN: usize = PduStorage::pdi_data_len(PDI_LEN, PdiTransfer::LrdLwr, true);
+
+        assert_eq!(PDI_LEN, 28);
+        assert_eq!(PduStorage::num_frames(0), 1);
+        assert_eq!(PduStorage::num_frames(2), 4);
+
+        const _: () =
+            PduStorage::<2, { PduStorage::element_size(DATA_LEN) }>::assert_fits(1, DATA_LEN);
+
+        let storage: PduStorage<2, { PduStorage::element_size(DATA_LEN) }> = PduStorage::new();
+        let s = storage.as_ref();
+
+        // Same PDUs as `SubDeviceGroup::tx_rx_sync_system_time` with `LrdLwr`, with no room to spare
+        let mut frame = s.alloc_frame().unwrap();
+
+        frame
+            .push_pdu(
+                Command::frmw(ConfiguredAddress::new(0x1000), 0x0910).into(),
+                0u64,
+                None,
+            )
+            .unwrap();
+        frame
+            .push_pdu(Command::lrd(LogicalAddress::new(0)).into(), (), Some(14))
+            .unwrap();
+        frame
+            .push_pdu(
+                Command::lwr(LogicalAddress::new(14)).into(),
+                [0u8; 14],
+                None,
+            )
+            .unwrap();
+
+        assert!(matches!(
+            frame.push_pdu(Command::Nop, (), Some(0)),
+            Err(PduError::TooLong)
+        ));
+    }
+
+    #[test]
+    fn boxed_storage() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        assert_eq!(
+            PduStorage::new_boxed(12, 128).unwrap_err(),
+            Error::Config(ConfigError::InvalidStorageSize)
+        );
+
+        let storage = PduStorage::new_boxed(4, 128).unwrap();
+
+        assert_eq!(storage.element_size(), PduStorage::element_size(128));
+
+        // Must match the stride of the equivalent static storage
+        assert_eq!(
+            storage.frame_element_stride,
+            core::mem::size_of::<FrameElement<{ PduStorage::element_size(128) }>>()
+        );
+
+        let (_tx, _rx, pdu_loop) = storage.try_split().unwrap();
+
+        let mut frames = Vec::new();
+
+        for _ in 0..4 {
+            let mut frame = pdu_loop.alloc_frame().expect("allocate frame");
+
+            frame
+                .push_pdu(Command::Nop, (), Some(128))
+                .expect("fill frame");
+
+            frames.push(frame);
+        }
+
+        assert!(pdu_loop.alloc_frame().is_err());
+
+        drop(frames);
+
+        assert!(storage.try_split().is_err());
+    }
 }
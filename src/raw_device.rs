@@ -0,0 +1,460 @@
+//! A generic TX/RX task for raw Ethernet devices, e.g. MCU MACs or SPI Ethernet controllers.
+
+use crate::{error::Error, fmt, PduRx, PduTx};
+use core::{
+    future::{poll_fn, Future},
+    pin::pin,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+/// A network device capable of sending and receiving raw Ethernet II frames.
+///
+/// Implement this trait for a MAC or Ethernet controller driver, then run [`tx_rx_device`] to
+/// connect it to the PDU loop. Methods follow the same polling convention as
+/// [`Future::poll`](core::future::Future::poll): if the device is not ready, `Poll::Pending` is
+/// returned and the waker in `cx` will be woken once the device can make progress.
+///
+/// # Examples
+///
+/// An implementation for a device using the [`embassy-net-driver`](https://docs.rs/embassy-net-driver)
+/// traits. This adapter is provided as `embassy::EmbassyDevice` when the `embassy` feature is
+/// enabled.
+///
+/// ```rust,ignore
+/// use core::task::{Context, Poll};
+/// use embassy_net_driver::{Driver, RxToken, TxToken};
+/// use ethercrab::{error::Error, RawEthernetDevice};
+///
+/// struct EmbassyDevice<D>(D);
+///
+/// impl<D: Driver> RawEthernetDevice for EmbassyDevice<D> {
+///     fn poll_transmit(&mut self, cx: &mut Context<'_>, frame: &[u8]) -> Poll<Result<(), Error>> {
+///         match self.0.transmit(cx) {
+///             Some(tx) => {
+///                 tx.consume(frame.len(), |buf| buf.copy_from_slice(frame));
+///
+///                 Poll::Ready(Ok(()))
+///             }
+///             None => Poll::Pending,
+///         }
+///     }
+///
+///     fn poll_receive(
+///         &mut self,
+///         cx: &mut Context<'_>,
+///         on_frame: impl FnOnce(&[u8]),
+///     ) -> Poll<Result<(), Error>> {
+///         match self.0.receive(cx) {
+///             Some((rx, _tx)) => {
+///                 rx.consume(|frame| on_frame(frame));
+///
+///                 Poll::Ready(Ok(()))
+///             }
+///             None => Poll::Pending,
+///         }
+///     }
+/// }
+/// ```
+pub trait RawEthernetDevice {
+    /// Send an Ethernet II frame.
+    ///
+    /// If the device cannot accept a frame right now, no data must be sent and `Poll::Pending`
+    /// returned. The same frame will be passed again once `cx` is woken.
+    ///
+    /// A driver error should be returned as [`Error::SendFrame`].
+    fn poll_transmit(&mut self, cx: &mut Context<'_>, frame: &[u8]) -> Poll<Result<(), Error>>;
+
+    /// Receive a single Ethernet II frame, passing it to `on_frame`.
+    ///
+    /// `Poll::Pending` must be returned if no frame is available. A driver error should be returned
+    /// as [`Error::ReceiveFrame`].
+    fn poll_receive(
+        &mut self,
+        cx: &mut Context<'_>,
+        on_frame: impl FnOnce(&[u8]),
+    ) -> Poll<Result<(), Error>>;
+}
+
+/// Send and receive EtherCAT frames using a [`RawEthernetDevice`].
+///
+/// This future must be run concurrently with the application, e.g. spawned as its own task. It
+/// only returns if an error occurs.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// #[embassy_executor::task]
+/// async fn tx_rx_task(
+///     device: EmbassyDevice<Ethernet<'static, ETH, GenericSMI>>,
+///     pdu_tx: PduTx<'static>,
+///     pdu_rx: PduRx<'static>,
+/// ) {
+///     if let Err(e) = ethercrab::tx_rx_device(device, pdu_tx, pdu_rx).await {
+///         defmt::error!("TX/RX task failed: {}", e);
+///     }
+/// }
+/// ```
+pub async fn tx_rx_device<D>(
+    mut device: D,
+    mut pdu_tx: PduTx<'_>,
+    mut pdu_rx: PduRx<'_>,
+) -> Result<(), Error>
+where
+    D: RawEthernetDevice,
+{
+    poll_fn(|cx| poll_device(cx, &mut device, &mut pdu_tx, &mut pdu_rx)).await
+}
+
+/// Run `future` to completion on the current thread, sending and receiving its frames with
+/// `device`, without an async executor.
+///
+/// This is intended for RTOS and bare metal applications that run their control loop outside an
+/// executor, e.g. from a timer interrupt. `future` and `device` are polled in a busy loop until
+/// `future` completes, so this function blocks for as long as the network takes to respond. PDU
+/// timeouts still apply, so the timer used by EtherCrab (`embassy-time` on `no_std` targets) must be
+/// running.
+///
+/// Any EtherCrab future can be run this way, e.g. a group's
+/// [`tx_rx`](crate::SubDeviceGroup::tx_rx) or a SubDevice SDO read. Frames queued by other tasks
+/// are also sent and received while this function runs.
+///
+/// # Errors
+///
+/// An error is returned if the device fails to send or receive a frame. Errors from `future` are
+/// returned in the `Ok` variant.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut device = EmbassyDevice(eth);
+///
+/// // In the control loop
+/// loop {
+///     let response = ethercrab::send_receive_blocking(
+///         &mut device,
+///         &mut pdu_tx,
+///         &mut pdu_rx,
+///         group.tx_rx(&maindevice),
+///     )??;
+///
+///     // Process inputs and outputs
+///
+///     wait_for_next_cycle();
+/// }
+/// ```
+pub fn send_receive_blocking<D, F>(
+    device: &mut D,
+    pdu_tx: &mut PduTx<'_>,
+    pdu_rx: &mut PduRx<'_>,
+    future: F,
+) -> Result<F::Output, Error>
+where
+    D: RawEthernetDevice,
+    F: Future,
+{
+    let mut future = pin!(future);
+
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        // Poll the future first so any frames it queues are sent straight away
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return Ok(output);
+        }
+
+        if let Poll::Ready(Err(e)) = poll_device(&mut cx, device, pdu_tx, pdu_rx) {
+            return Err(e);
+        }
+
+        core::hint::spin_loop();
+    }
+}
+
+/// A waker that does nothing, for futures that are polled in a loop anyway, like those in
+/// [`send_receive_blocking`].
+pub(crate) fn noop_waker() -> Waker {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(|_| RAW, |_| (), |_| (), |_| ());
+    const RAW: RawWaker = RawWaker::new(core::ptr::null(), &VTABLE);
+
+    // SAFETY: The waker's data pointer is never used and every vtable function is a no-op.
+    unsafe { Waker::from_raw(RAW) }
+}
+
+/// Receive all frames buffered by `device`, then send all queued frames.
+///
+/// Only returns `Poll::Ready` if an error occurs.
+fn poll_device<D>(
+    cx: &mut Context<'_>,
+    device: &mut D,
+    pdu_tx: &mut PduTx<'_>,
+    pdu_rx: &mut PduRx<'_>,
+) -> Poll<Result<(), Error>>
+where
+    D: RawEthernetDevice,
+{
+    // Re-register waker to make sure this future is polled again when new frames are queued
+    pdu_tx.replace_waker(cx.waker());
+
+    // Receive everything the device has buffered before sending more frames
+    loop {
+        let mut result = Ok(());
+
+        // Responses are kept for their futures even if they haven't registered a waker yet, e.g.
+        // because they're still being polled on another thread when the response arrives.
+        match device.poll_receive(cx, |frame| result = pdu_rx.handle_received_frame(frame)) {
+            Poll::Ready(Ok(())) => (),
+            Poll::Ready(Err(e)) => {
+                fmt::error!("Receive frame failed: {}", e);
+
+                return Poll::Ready(Err(e));
+            }
+            Poll::Pending => break,
+        }
+
+        match result {
+            Ok(()) => (),
+            Err(e) => {
+                fmt::error!("Failed to receive frame: {}", e);
+
+                return Poll::Ready(Err(Error::ReceiveFrame));
+            }
+        }
+    }
+
+    while let Some(frame) = pdu_tx.next_sendable_frame() {
+        let mut device_busy = false;
+
+        let result = frame.send_blocking(|data| match device.poll_transmit(cx, data) {
+            Poll::Ready(Ok(())) => Ok(data.len()),
+            Poll::Ready(Err(e)) => Err(e),
+            Poll::Pending => {
+                device_busy = true;
+
+                // Releases the frame so it is sent again next time this future is polled
+                Err(Error::SendFrame)
+            }
+        });
+
+        if device_busy {
+            break;
+        }
+
+        if let Err(e) = result {
+            fmt::error!("Send frame failed: {}", e);
+
+            return Poll::Ready(Err(e));
+        }
+    }
+
+    Poll::Pending
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ethernet::EthernetFrame, Command, MainDevice, MainDeviceConfig, PduStorage,
+        RegisterAddress, Timeouts, VlanTag,
+    };
+    use core::{task::Waker, time::Duration};
+    use std::collections::VecDeque;
+
+    /// Returns every sent frame as if it passed through one SubDevice that didn't process it.
+    #[derive(Default)]
+    struct Loopback {
+        frames: VecDeque<Vec<u8>>,
+        waker: Option<Waker>,
+    }
+
+    impl RawEthernetDevice for Loopback {
+        fn poll_transmit(
+            &mut self,
+            _cx: &mut Context<'_>,
+            frame: &[u8],
+        ) -> Poll<Result<(), Error>> {
+            let mut frame = frame.to_vec();
+
+            // SubDevices set the U/L bit of the source address
+            EthernetFrame::new_unchecked(&mut frame).set_src_addr(
+                crate::ethernet::EthernetAddress([0x12, 0x10, 0x10, 0x10, 0x10, 0x10]),
+            );
+
+            self.frames.push_back(frame);
+
+            if let Some(waker) = self.waker.take() {
+                waker.wake();
+            }
+
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_receive(
+            &mut self,
+            cx: &mut Context<'_>,
+            on_frame: impl FnOnce(&[u8]),
+        ) -> Poll<Result<(), Error>> {
+            match self.frames.pop_front() {
+                Some(frame) => {
+                    on_frame(&frame);
+
+                    Poll::Ready(Ok(()))
+                }
+                None => {
+                    self.waker = Some(cx.waker().clone());
+
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn loopback() {
+        static STORAGE: PduStorage<4, { PduStorage::element_size(32) }> = PduStorage::new();
+
+        let (tx, rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let maindevice = MainDevice::new(
+            pdu_loop,
+            Timeouts {
+                pdu: Duration::from_millis(100),
+                ..Timeouts::default()
+            },
+            MainDeviceConfig::default(),
+        );
+
+        let result = futures_lite::future::block_on(futures_lite::future::or(
+            async {
+                Command::brd(RegisterAddress::Type.into())
+                    .receive_wkc::<u8>(&maindevice)
+                    .await
+            },
+            async {
+                tx_rx_device(Loopback::default(), tx, rx).await?;
+
+                unreachable!()
+            },
+        ));
+
+        // No SubDevices processed the frame
+        assert_eq!(result, Ok(0));
+        assert_eq!(maindevice.statistics().frames_received, 1);
+    }
+
+    #[test]
+    fn blocking_loopback() {
+        static STORAGE: PduStorage<4, { PduStorage::element_size(32) }> = PduStorage::new();
+
+        let (mut tx, mut rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let maindevice = MainDevice::new(
+            pdu_loop,
+            Timeouts {
+                pdu: Duration::from_millis(100),
+                ..Timeouts::default()
+            },
+            MainDeviceConfig::default(),
+        );
+
+        let mut device = Loopback::default();
+
+        for i in 1..=3 {
+            let result = send_receive_blocking(
+                &mut device,
+                &mut tx,
+                &mut rx,
+                Command::brd(RegisterAddress::Type.into()).receive_wkc::<u8>(&maindevice),
+            );
+
+            assert_eq!(result, Ok(Ok(0)));
+            assert_eq!(maindevice.statistics().frames_received, i);
+        }
+    }
+
+    #[test]
+    fn frame_hooks() {
+        use crate::FrameHooks;
+        use std::sync::Mutex;
+
+        static SENT: Mutex<Vec<(usize, Duration)>> = Mutex::new(Vec::new());
+        static RECEIVED: Mutex<Vec<(usize, Duration)>> = Mutex::new(Vec::new());
+
+        static HOOKS: FrameHooks = FrameHooks::new(
+            |frame, timestamp| SENT.lock().unwrap().push((frame.len(), timestamp)),
+            |frame, timestamp| RECEIVED.lock().unwrap().push((frame.len(), timestamp)),
+        );
+
+        static STORAGE: PduStorage<4, { PduStorage::element_size(32) }> = PduStorage::new();
+
+        let (mut tx, mut rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        tx.set_frame_tap(&HOOKS);
+        rx.set_frame_tap(&HOOKS);
+
+        let maindevice = MainDevice::new(
+            pdu_loop,
+            Timeouts {
+                pdu: Duration::from_millis(100),
+                ..Timeouts::default()
+            },
+            MainDeviceConfig::default(),
+        );
+
+        let result = send_receive_blocking(
+            &mut Loopback::default(),
+            &mut tx,
+            &mut rx,
+            Command::brd(RegisterAddress::Type.into()).receive_wkc::<u8>(&maindevice),
+        );
+
+        assert_eq!(result, Ok(Ok(0)));
+
+        let sent = SENT.lock().unwrap().clone();
+        let received = RECEIVED.lock().unwrap().clone();
+
+        assert_eq!(sent.len(), 1);
+        assert_eq!(received.len(), 1);
+
+        // Ethernet header, EtherCAT header, PDU header, one byte of data and working counter
+        assert_eq!(sent[0].0, 14 + 2 + 10 + 1 + 2);
+        assert_eq!(received[0].0, sent[0].0);
+        assert!(received[0].1 >= sent[0].1);
+    }
+
+    #[test]
+    fn vlan_loopback() {
+        static STORAGE: PduStorage<4, { PduStorage::element_size(32) }> = PduStorage::new();
+
+        let (tx, rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let maindevice = MainDevice::new(
+            pdu_loop,
+            Timeouts {
+                pdu: Duration::from_millis(100),
+                ..Timeouts::default()
+            },
+            MainDeviceConfig {
+                vlan: Some(VlanTag { vid: 100, pcp: 5 }),
+                ..MainDeviceConfig::default()
+            },
+        );
+
+        let result = futures_lite::future::block_on(futures_lite::future::or(
+            async {
+                Command::brd(RegisterAddress::Type.into())
+                    .receive_wkc::<u8>(&maindevice)
+                    .await
+            },
+            async {
+                tx_rx_device(Loopback::default(), tx, rx).await?;
+
+                unreachable!()
+            },
+        ));
+
+        // The tagged frame was received and the tag stripped
+        assert_eq!(result, Ok(0));
+        assert_eq!(maindevice.statistics().frames_received, 1);
+    }
+}
@@ -1,9 +1,53 @@
+//! EtherCAT SubDevice Controller (ESC) register addresses and value layouts.
+//!
+//! [`RegisterAddress`] names the registers EtherCrab uses. The constants in this module hold the
+//! same addresses as plain `u16`s for use in `const` contexts, and the structs re-exported here
+//! decode and encode the register contents. All of them work with the raw register API, e.g.
+//! [`SubDeviceRef::register_read`](crate::SubDeviceRef::register_read).
+//!
+//! Register layouts are defined in ETG1000.4 and the ET1100 datasheet.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use ethercrab::{
+//!     register::{self, DlStatus, SyncManagerChannel},
+//!     SubDeviceRef, SubDevice,
+//! };
+//!
+//! # async fn case(subdevice: SubDeviceRef<'_, &SubDevice>) -> Result<(), ethercrab::error::Error> {
+//! let dl_status = subdevice.register_read::<DlStatus>(register::DL_STATUS).await?;
+//!
+//! if !dl_status.link_port1 {
+//!     println!("Nothing connected to port 1");
+//! }
+//!
+//! // Sync manager 2, usually the process data outputs
+//! let sm2 = subdevice
+//!     .register_read::<SyncManagerChannel>(register::SYNC_MANAGER_BASE + 2 * register::SYNC_MANAGER_LEN)
+//!     .await?;
+//!
+//! println!("SM2: {}", sm2);
+//! # Ok(()) }
+//! ```
+
+pub use crate::{
+    al_control::AlControl,
+    al_status_code::AlStatusCode,
+    dl_status::DlStatus,
+    fmmu::Fmmu,
+    sync_manager_channel::{
+        BufferState, Control, Direction, Enable, OperationMode, Status, SyncManagerChannel,
+    },
+};
+
 /// SubDevice device register address abstraction.
 ///
 /// This enum makes it easier to work with raw EtherCAT addresses by giving them nice names.
 ///
 /// Defined in ETG1000.4, Table 31.
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u16)]
 pub enum RegisterAddress {
     /// Type, `u8`.
@@ -40,6 +84,21 @@ pub enum RegisterAddress {
     /// Application Layer (AL) status code register.
     AlStatusCode = 0x0134,
 
+    /// Start of the per-port invalid frame and RX error counters, 2x `u8` for each of ports 0-3.
+    ///
+    /// Writing to any of the RX error counters clears all of them. See ETG1000.4 Table 37.
+    RxErrorCounter = 0x0300,
+    /// Start of the per-port forwarded RX error counters, `u8` for each of ports 0-3.
+    ForwardedRxErrorCounter = 0x0308,
+    /// ECAT processing unit error counter, `u8`.
+    ProcessingUnitErrorCounter = 0x030C,
+    /// PDI error counter, `u8`.
+    PdiErrorCounter = 0x030D,
+    /// Start of the per-port lost link counters, `u8` for each of ports 0-3.
+    ///
+    /// Writing to any of the lost link counters clears all of them.
+    LostLinkCounter = 0x0310,
+
     /// Watchdog divider, `u16`.
     ///
     /// See ETG1000.4 section 6.3 Watchdogs.
@@ -270,19 +329,111 @@ impl RegisterAddress {
     }
 }
 
+/// ESC type, `u8`.
+pub const TYPE: u16 = RegisterAddress::Type as u16;
+/// ESC revision, `u8`.
+pub const REVISION: u16 = RegisterAddress::Revision as u16;
+/// ESC build, `u16`.
+pub const BUILD: u16 = RegisterAddress::Build as u16;
+/// Number of supported FMMUs, `u8`.
+pub const FMMU_COUNT: u16 = RegisterAddress::FmmuCount as u16;
+/// Number of supported sync manager channels, `u8`.
+pub const SYNC_MANAGER_COUNT: u16 = RegisterAddress::SyncManagerChannels as u16;
+/// Supported ESC features, [`SupportFlags`].
+pub const SUPPORT_FLAGS: u16 = RegisterAddress::SupportFlags as u16;
+/// Configured station address, `u16`.
+pub const CONFIGURED_STATION_ADDRESS: u16 = RegisterAddress::ConfiguredStationAddress as u16;
+/// Configured station alias, `u16`.
+pub const CONFIGURED_STATION_ALIAS: u16 = RegisterAddress::ConfiguredStationAlias as u16;
+
+/// DL control, `u32`. Port loop control and forwarding rules.
+///
+/// Defined in ETG1000.4 Table 33.
+pub const DL_CONTROL: u16 = 0x0100;
+/// DL status, [`DlStatus`].
+pub const DL_STATUS: u16 = RegisterAddress::DlStatus as u16;
+
+/// AL control, [`AlControl`]. Written to request a state change.
+pub const AL_CONTROL: u16 = RegisterAddress::AlControl as u16;
+/// AL status, [`AlControl`]. The current state and error flag, in the same layout as AL control.
+pub const AL_STATUS: u16 = RegisterAddress::AlStatus as u16;
+/// AL status code, [`AlStatusCode`].
+pub const AL_STATUS_CODE: u16 = RegisterAddress::AlStatusCode as u16;
+
+/// The first FMMU, [`Fmmu`]. FMMU `n` is at `FMMU_BASE + n * FMMU_LEN`.
+pub const FMMU_BASE: u16 = RegisterAddress::Fmmu0 as u16;
+/// The length of each FMMU in bytes.
+pub const FMMU_LEN: u16 = 16;
+
+/// The first sync manager, [`SyncManagerChannel`]. Sync manager `n` is at
+/// `SYNC_MANAGER_BASE + n * SYNC_MANAGER_LEN`.
+pub const SYNC_MANAGER_BASE: u16 = RegisterAddress::Sm0 as u16;
+/// The length of each sync manager in bytes.
+pub const SYNC_MANAGER_LEN: u16 = 8;
+
+/// DC receive time of port 0 in ns, `u32`. Ports 1-3 follow at 4 byte intervals.
+pub const DC_TIME_PORT0: u16 = RegisterAddress::DcTimePort0 as u16;
+/// DC system time in ns, `u64`.
+pub const DC_SYSTEM_TIME: u16 = RegisterAddress::DcSystemTime as u16;
+/// DC local receive time of the processing unit in ns, `u64`.
+pub const DC_RECEIVE_TIME: u16 = RegisterAddress::DcReceiveTime as u16;
+/// DC system time offset in ns, `i64`.
+pub const DC_SYSTEM_TIME_OFFSET: u16 = RegisterAddress::DcSystemTimeOffset as u16;
+/// DC transmission delay from the reference clock in ns, `u32`.
+pub const DC_SYSTEM_TIME_DELAY: u16 = RegisterAddress::DcSystemTimeTransmissionDelay as u16;
+/// DC system time difference in ns, `u32`. Bit 31 is the sign, set if the local copy of the
+/// system time is smaller than the received system time.
+pub const DC_SYSTEM_TIME_DIFFERENCE: u16 = RegisterAddress::DcSystemTimeDifference as u16;
+/// DC cyclic unit control, `u8`.
+pub const DC_CYCLIC_UNIT_CONTROL: u16 = RegisterAddress::DcCyclicUnitControl as u16;
+/// DC SYNC signal activation, [`DcSyncActivation`].
+pub const DC_SYNC_ACTIVE: u16 = RegisterAddress::DcSyncActive as u16;
+/// DC start time of cyclic operation in ns, `u32` or `u64` depending on
+/// [`SupportFlags::has_64bit_dc`].
+pub const DC_SYNC_START_TIME: u16 = RegisterAddress::DcSyncStartTime as u16;
+/// DC SYNC0 cycle time in ns, `u32`.
+pub const DC_SYNC0_CYCLE_TIME: u16 = RegisterAddress::DcSync0CycleTime as u16;
+/// DC SYNC1 cycle time in ns, `u32`.
+pub const DC_SYNC1_CYCLE_TIME: u16 = RegisterAddress::DcSync1CycleTime as u16;
+
+/// DC SYNC signal activation, register `0x0981`.
+///
+/// Defined in ETG1000.6 Table 27. The high byte of an ESI file's `AssignActivate` value has the same
+/// layout.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq, ethercrab_wire::EtherCrabWireReadWrite)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[wire(bytes = 1)]
+pub struct DcSyncActivation {
+    /// Enable cyclic operation, generating SYNC pulses from the start time.
+    #[wire(bits = 1)]
+    pub cyclic_operation: bool,
+    /// Generate SYNC0 pulses.
+    #[wire(bits = 1)]
+    pub sync0: bool,
+    /// Generate SYNC1 pulses.
+    #[wire(bits = 1, post_skip = 5)]
+    pub sync1: bool,
+}
+
+/// Physical port type, 2 bits for each of ports 0-3 in the port descriptors register `0x0007`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ethercrab_wire::EtherCrabWireRead)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum PortType {
+    /// The port is not implemented.
     NotImplemented = 0x00u8,
+    /// The port is not configured in the ESC's EEPROM.
     NotConfigured = 0x01,
+    /// EBUS, e.g. the E-bus connections between Beckhoff terminals.
     Ebus = 0x02,
+    /// MII, i.e. an Ethernet PHY.
     Mii = 0x03,
 }
 
-/// Features supported by a SubDevice.
+/// Features supported by a SubDevice, register `0x0008`.
 ///
 /// Described in ETG1000.4 Table 31 - DL information.
-#[derive(Default, Clone, Debug, PartialEq)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(not(test), derive(ethercrab_wire::EtherCrabWireRead))]
 #[cfg_attr(
     test,
@@ -291,8 +442,10 @@ pub enum PortType {
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bytes = 2)]
 pub struct SupportFlags {
+    /// FMMUs only support whole bytes if set.
     #[wire(bits = 1)]
     pub fmmu_supports_bit_ops: bool,
+    /// Reserved registers are not supported if set.
     #[wire(bits = 1)]
     pub reserved_register_support: bool,
     /// This parameter is set to 1 if at least distributed clock receive times are supported.
@@ -300,14 +453,19 @@ pub struct SupportFlags {
     /// ETG1000.4 page 49
     #[wire(bits = 1)]
     pub dc_supported: bool,
+    /// DC system time is 64 bits wide instead of 32.
     #[wire(bits = 1)]
     pub has_64bit_dc: bool,
+    /// Low jitter DC receive times are supported.
     #[wire(bits = 1)]
     pub low_jitter: bool,
+    /// Enhanced link detection is supported on EBUS ports.
     #[wire(bits = 1)]
     pub ebus_enhanced_link_detection: bool,
+    /// Enhanced link detection is supported on MII ports.
     #[wire(bits = 1)]
     pub mii_enhanced_link_detection: bool,
+    /// FCS errors are counted separately.
     #[wire(bits = 1)]
     pub separate_fcs_error_handling: bool,
     /// Indicates whether registers `0x0981` - `0x0984` are usable.
@@ -317,15 +475,19 @@ pub struct SupportFlags {
     /// ETG1000.4 Table 31 – DL information / ETG1000.4 page 49.
     #[wire(bits = 1)]
     pub enhanced_dc_sync: bool,
+    /// The `LRW` command is not supported if set.
     #[wire(bits = 1)]
     pub lrw_not_supported: bool,
+    /// The `BRW`, `APRW` and `FPRW` commands are not supported if set.
     #[wire(bits = 1)]
     pub brw_aprw_fprw_not_supported: bool,
+    /// Special FMMU/sync manager configuration: FMMU 0 is used for SM0, FMMU 1 for SM1.
     #[wire(bits = 1, post_skip = 4)]
     pub special_fmmu: bool,
 }
 
 impl SupportFlags {
+    /// The level of DC support indicated by these flags.
     pub fn dc_support(&self) -> DcSupport {
         if !self.dc_supported {
             DcSupport::None
@@ -366,6 +528,7 @@ impl core::fmt::Display for SupportFlags {
 
 /// SubDevice DC support status.
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DcSupport {
     /// No support at all.
     None,
@@ -395,7 +558,9 @@ impl DcSupport {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireWrite};
+    use ethercrab_wire::{
+        EtherCrabWireRead, EtherCrabWireSized, EtherCrabWireWrite, EtherCrabWireWriteSized,
+    };
 
     #[test]
     #[cfg_attr(miri, ignore)]
@@ -413,6 +578,42 @@ mod tests {
         });
     }
 
+    #[test]
+    fn constants() {
+        assert_eq!(AL_STATUS, 0x0130);
+        assert_eq!(DL_STATUS, 0x0110);
+        assert_eq!(DC_SYNC_ACTIVE, 0x0981);
+        assert_eq!(
+            FMMU_BASE + 3 * FMMU_LEN,
+            u16::from(RegisterAddress::fmmu(3))
+        );
+        assert_eq!(
+            SYNC_MANAGER_BASE + 3 * SYNC_MANAGER_LEN,
+            u16::from(RegisterAddress::sync_manager(3))
+        );
+        assert_eq!(FMMU_LEN as usize, Fmmu::PACKED_LEN);
+        assert_eq!(SYNC_MANAGER_LEN as usize, SyncManagerChannel::PACKED_LEN);
+    }
+
+    #[test]
+    fn dc_sync_activation() {
+        let activation = DcSyncActivation {
+            cyclic_operation: true,
+            sync0: true,
+            sync1: false,
+        };
+
+        assert_eq!(activation.pack(), [0b0000_0011]);
+        assert_eq!(
+            DcSyncActivation::unpack_from_slice(&[0b0000_0111]),
+            Ok(DcSyncActivation {
+                cyclic_operation: true,
+                sync0: true,
+                sync1: true,
+            })
+        );
+    }
+
     #[test]
     fn enhanced_dc_el2828() {
         // EL2828 supports DC SYNC0
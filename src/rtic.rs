@@ -0,0 +1,316 @@
+//! Run EtherCrab from [RTIC](https://rtic.rs) tasks.
+//!
+//! Requires the `rtic` feature.
+//!
+//! RTIC applications have no general purpose async executor to run the TX/RX loop, so this module
+//! splits it in two:
+//!
+//! - A hardware task bound to the Ethernet interrupt passes every received frame to
+//!   [`PduRx::handle_received_frame`].
+//! - The cycle task, usually bound to a hardware timer, drives an EtherCrab future to completion
+//!   with [`block_on`], sending any queued frames as it goes.
+//!
+//! The Ethernet interrupt must have a higher priority than the task calling [`block_on`],
+//! otherwise responses are never received and the future only completes with a timeout error.
+//!
+//! On `no_std` targets, all EtherCrab timeouts are measured with the clock registered with
+//! [`set_monotonic`], which must be called once at startup, before any EtherCrab futures are
+//! polled. This is usually a thin wrapper around the application's RTIC monotonic. The `std` and
+//! `embassy` features take precedence, using their own clocks for timeouts instead.
+//!
+//! [`block_on`] checks for expired timeouts every time it polls its future. EtherCrab futures run
+//! in RTIC async tasks instead must be woken when a timeout expires by calling [`wake_timers`]
+//! periodically, e.g. from a hardware timer task.
+//!
+//! The application must provide a [`critical-section`](https://docs.rs/critical-section)
+//! implementation, e.g. with the `critical-section-single-core` feature of `cortex-m`.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! #[rtic::app(device = stm32f4xx_hal::pac, dispatchers = [USART1])]
+//! mod app {
+//!     use ethercrab::{
+//!         rtic::{block_on, ethercat_now, set_monotonic},
+//!         MainDevice, MainDeviceConfig, PduRx, PduStorage, PduTx, SubDeviceGroup, Timeouts,
+//!     };
+//!
+//!     static PDU_STORAGE: PduStorage<8, { PduStorage::element_size(256) }> = PduStorage::new();
+//!
+//!     fn now_nanos() -> u64 {
+//!         Mono::now().duration_since_epoch().to_nanos()
+//!     }
+//!
+//!     #[shared]
+//!     struct Shared {}
+//!
+//!     #[local]
+//!     struct Local {
+//!         eth: board::Ethernet,
+//!         pdu_tx: PduTx<'static>,
+//!         pdu_rx: PduRx<'static>,
+//!         maindevice: MainDevice<'static>,
+//!         group: SubDeviceGroup<16, 256>,
+//!     }
+//!
+//!     #[init]
+//!     fn init(cx: init::Context) -> (Shared, Local) {
+//!         Mono::start(cx.core.SYST, 168_000_000);
+//!
+//!         set_monotonic(now_nanos);
+//!
+//!         let (mut pdu_tx, pdu_rx, pdu_loop) =
+//!             PDU_STORAGE.try_split().expect("can only split once");
+//!
+//!         let mut eth = board::ethernet(cx.device);
+//!
+//!         let maindevice =
+//!             MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+//!
+//!         // The Ethernet interrupt is already running, so frames can be sent and received here
+//!         let group = block_on(
+//!             maindevice.init_single_group::<16, 256>(ethercat_now),
+//!             &mut pdu_tx,
+//!             |frame| eth.transmit(frame),
+//!         )
+//!         .expect("send/receive")
+//!         .expect("init");
+//!
+//!         // ... transition the group to OP the same way
+//!
+//!         (Shared {}, Local { eth, pdu_tx, pdu_rx, maindevice, group })
+//!     }
+//!
+//!     /// Process data cycle, triggered by a hardware timer every 1 ms.
+//!     #[task(binds = TIM2, priority = 1, local = [eth, pdu_tx, maindevice, group])]
+//!     fn cycle(cx: cycle::Context) {
+//!         let eth = cx.local.eth;
+//!
+//!         match block_on(cx.local.group.tx_rx(cx.local.maindevice), cx.local.pdu_tx, |frame| {
+//!             eth.transmit(frame)
+//!         }) {
+//!             Ok(Ok(_response)) => {
+//!                 // Process inputs and outputs
+//!             }
+//!             Ok(Err(e)) => defmt::error!("TX/RX failed: {}", e),
+//!             Err(e) => defmt::error!("Send failed: {}", e),
+//!         }
+//!     }
+//!
+//!     /// Ethernet receive interrupt.
+//!     #[task(binds = ETH, priority = 2, local = [pdu_rx])]
+//!     fn eth_rx(cx: eth_rx::Context) {
+//!         while let Some(frame) = board::ethernet_receive() {
+//!             if let Err(e) = cx.local.pdu_rx.handle_received_frame(&frame) {
+//!                 defmt::error!("Bad frame: {}", e);
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::{
+    atomic::{AtomicPtr, Ordering},
+    error::Error,
+    PduTx,
+};
+use core::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+/// The clock registered with [`set_monotonic`], stored as a type-erased `fn() -> u64`.
+static MONOTONIC: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Register the clock used to measure all EtherCrab timeouts.
+///
+/// `now` must return a monotonic time in nanoseconds since an arbitrary point, e.g. the current
+/// value of the application's RTIC monotonic.
+///
+/// This must be called before any EtherCrab futures are polled or [`ethercat_now`] is called.
+pub fn set_monotonic(now: fn() -> u64) {
+    MONOTONIC.store(now as *mut (), Ordering::Release);
+}
+
+/// Get the time in nanoseconds from the clock registered with [`set_monotonic`].
+///
+/// This can be passed to e.g. [`MainDevice::init`](crate::MainDevice::init).
+///
+/// # Panics
+///
+/// This function will panic if no clock has been registered with [`set_monotonic`].
+pub fn ethercat_now() -> u64 {
+    let now = MONOTONIC.load(Ordering::Acquire);
+
+    assert!(
+        !now.is_null(),
+        "no EtherCrab clock registered, call ethercrab::rtic::set_monotonic at startup"
+    );
+
+    // SAFETY: The only non-null value ever stored in `MONOTONIC` is a `fn() -> u64`, in
+    // `set_monotonic`.
+    let now = unsafe { core::mem::transmute::<*mut (), fn() -> u64>(now) };
+
+    now()
+}
+
+#[cfg(all(not(feature = "std"), not(feature = "embassy")))]
+pub(crate) fn now() -> core::time::Duration {
+    core::time::Duration::from_nanos(ethercat_now())
+}
+
+/// Wake every task waiting for an EtherCrab timeout that has expired.
+///
+/// This is not needed for futures run with [`block_on`].
+pub fn wake_timers() {
+    crate::timer_factory::wake_poll_timers()
+}
+
+/// Run `future` to completion, sending queued frames with `send` until it is done.
+///
+/// Received frames must be passed to
+/// [`PduRx::handle_received_frame`](crate::PduRx::handle_received_frame) from a higher priority
+/// task, usually the Ethernet interrupt, while this function runs. Any EtherCrab future can be run
+/// this way, e.g. a group's [`tx_rx`](crate::SubDeviceGroup::tx_rx) from a hardware timer task, or
+/// MainDevice initialisation from RTIC's `init`.
+///
+/// `send` must transmit the whole Ethernet frame it is given.
+///
+/// # Errors
+///
+/// An error is returned if a frame fails to send. Errors from `future` are returned in the `Ok`
+/// variant.
+pub fn block_on<F>(
+    future: F,
+    pdu_tx: &mut PduTx<'_>,
+    mut send: impl FnMut(&[u8]) -> Result<(), Error>,
+) -> Result<F::Output, Error>
+where
+    F: Future,
+{
+    let mut future = pin!(future);
+
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        // Poll the future first so any frames it queues are sent straight away
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return Ok(output);
+        }
+
+        while let Some(frame) = pdu_tx.next_sendable_frame() {
+            frame.send_blocking(|data| send(data).map(|_| data.len()))?;
+        }
+
+        core::hint::spin_loop();
+    }
+}
+
+/// A waker that does nothing, as [`block_on`] polls its future in a loop anyway.
+fn noop_waker() -> Waker {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(|_| RAW, |_| (), |_| (), |_| ());
+    const RAW: RawWaker = RawWaker::new(core::ptr::null(), &VTABLE);
+
+    // SAFETY: The waker's data pointer is never used and every vtable function is a no-op.
+    unsafe { Waker::from_raw(RAW) }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod tests {
+    use super::*;
+    use crate::{
+        atomic::AtomicBool,
+        sim::{SimNetwork, SimSubDevice},
+        MainDevice, MainDeviceConfig, PduStorage, RawEthernetDevice, SubDeviceIdentity, Timeouts,
+    };
+    use core::time::Duration;
+
+    fn test_now() -> u64 {
+        static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+
+        START
+            .get_or_init(std::time::Instant::now)
+            .elapsed()
+            .as_nanos() as u64
+    }
+
+    #[test]
+    fn cycle() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(256) }> = PduStorage::new();
+
+        set_monotonic(test_now);
+
+        let mut network = SimNetwork::new([SimSubDevice::new(
+            "EL1004",
+            SubDeviceIdentity {
+                vendor_id: 0x2,
+                product_id: 0x03ec_3052,
+                revision: 0x0011_0000,
+                serial: 0,
+            },
+        )
+        .with_inputs(1)]);
+
+        network.with_subdevice(0, |subdevice| subdevice.inputs_mut()[0] = 0x5a);
+
+        let (mut pdu_tx, mut pdu_rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let maindevice = MainDevice::new(
+            pdu_loop,
+            Timeouts {
+                // The cycle thread spins, so the receiving thread may not be scheduled for a while
+                pdu: Duration::from_millis(500),
+                eeprom: Duration::from_millis(500),
+                ..Timeouts::default()
+            },
+            MainDeviceConfig::default(),
+        );
+
+        let done = AtomicBool::new(false);
+        let mut rx_network = network.clone();
+
+        std::thread::scope(|s| {
+            // Stands in for the Ethernet interrupt
+            s.spawn(|| {
+                let waker = noop_waker();
+                let mut cx = Context::from_waker(&waker);
+
+                while !done.load(Ordering::Relaxed) {
+                    let _ = rx_network.poll_receive(&mut cx, |frame| {
+                        pdu_rx.handle_received_frame(frame).expect("receive")
+                    });
+                }
+            });
+
+            let waker = noop_waker();
+            let mut cx = Context::from_waker(&waker);
+
+            let mut send = |frame: &[u8]| match network.poll_transmit(&mut cx, frame) {
+                Poll::Ready(result) => result,
+                Poll::Pending => Err(Error::SendFrame),
+            };
+
+            let result = (|| {
+                let group = block_on(
+                    maindevice.init_single_group::<2, 8>(ethercat_now),
+                    &mut pdu_tx,
+                    &mut send,
+                )??;
+
+                let group = block_on(group.into_op(&maindevice), &mut pdu_tx, &mut send)??;
+
+                block_on(group.tx_rx(&maindevice), &mut pdu_tx, &mut send)??;
+
+                let subdevice = group.subdevice(&maindevice, 0)?;
+
+                Ok::<_, Error>(subdevice.inputs_raw().to_vec())
+            })();
+
+            done.store(true, Ordering::Relaxed);
+
+            assert_eq!(result, Ok(vec![0x5a]));
+        });
+    }
+}
@@ -0,0 +1,15 @@
+//! Typed access to the SubDevice Information Interface (SII) categories stored in a SubDevice's
+//! EEPROM.
+//!
+//! Use [`SubDeviceRef::eeprom`](crate::SubDeviceRef::eeprom) to read the categories from a
+//! SubDevice.
+
+pub use crate::base_data_types::PrimitiveDataType;
+pub use crate::eeprom::esi::{EsiDevice, EsiPdo, EsiPdoEntry, EsiSyncManager, SiiMismatch};
+pub use crate::eeprom::types::{
+    BootstrapMailbox, CoeDetails, DefaultMailbox, Flags, FmmuEx, FmmuUsage, MailboxProtocols, Pdo,
+    PdoEntry, PdoFlags, PdoType, PortStatus, PortStatuses, SiiDistributedClock, SiiGeneral,
+    SyncManager, SyncManagerEnable, SyncManagerType,
+};
+pub use crate::subdevice::Eeprom;
+pub use crate::sync_manager_channel::{Control, Direction, OperationMode};
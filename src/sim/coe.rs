@@ -0,0 +1,694 @@
+//! CoE object dictionary and SDO server for simulated SubDevices.
+
+use crate::{base_data_types::PrimitiveDataType, fmt, SubDeviceState};
+use ethercrab_wire::EtherCrabWireWrite;
+use std::collections::BTreeMap;
+
+/// Mailbox type for CoE, ETG1000.6 Table 29.
+const MAILBOX_TYPE_COE: u8 = 0x03;
+/// CoE service for emergency messages.
+const SERVICE_EMERGENCY: u8 = 0x01;
+/// CoE service for SDO requests and abort transfer requests.
+const SERVICE_SDO_REQUEST: u8 = 0x02;
+/// CoE service for SDO responses.
+const SERVICE_SDO_RESPONSE: u8 = 0x03;
+/// CoE service for SDO Information requests and responses.
+const SERVICE_SDO_INFORMATION: u8 = 0x08;
+
+/// Client command specifier for an initiate download request.
+const COMMAND_DOWNLOAD: u8 = 0x01;
+/// Client command specifier for an initiate upload request.
+const COMMAND_UPLOAD: u8 = 0x02;
+
+/// SDO header byte for a download response.
+const DOWNLOAD_RESPONSE: u8 = 0x03 << 5;
+/// SDO header byte for a normal upload response with size indicator.
+const UPLOAD_RESPONSE_NORMAL: u8 = (0x02 << 5) | 0x01;
+/// SDO header byte for an expedited upload response with size indicator. The number of unused data
+/// bytes is stored in bits 2 and 3.
+const UPLOAD_RESPONSE_EXPEDITED: u8 = (0x02 << 5) | 0x02 | 0x01;
+/// SDO header byte for an abort transfer request.
+const ABORT: u8 = 0x04 << 5;
+
+// SDO Information OpCodes, ETG1000.6 Table 43. Responses are the request OpCode plus one.
+const INFO_GET_OD_LIST: u8 = 0x01;
+const INFO_GET_OBJECT_DESCRIPTION: u8 = 0x03;
+const INFO_GET_ENTRY_DESCRIPTION: u8 = 0x05;
+const INFO_ERROR: u8 = 0x07;
+
+/// Object code of an object with a single value, ETG1000.6 Table 46.
+const OBJECT_CODE_VAR: u8 = 0x07;
+/// Object code of an object with multiple values.
+const OBJECT_CODE_RECORD: u8 = 0x09;
+
+// SDO abort codes, ETG1000.6 Table 41.
+const ABORT_COMMAND_SPECIFIER: u32 = 0x0504_0001;
+const ABORT_UNSUPPORTED_ACCESS: u32 = 0x0601_0000;
+const ABORT_WRITE_ONLY: u32 = 0x0601_0001;
+const ABORT_READ_ONLY: u32 = 0x0601_0002;
+const ABORT_NO_OBJECT: u32 = 0x0602_0000;
+const ABORT_PDO_MAPPING: u32 = 0x0604_0041;
+const ABORT_LENGTH_MISMATCH: u32 = 0x0607_0010;
+const ABORT_NO_SUB_INDEX: u32 = 0x0609_0011;
+const ABORT_VALUE_TOO_LARGE: u32 = 0x0609_0031;
+const ABORT_GENERAL: u32 = 0x0800_0000;
+const ABORT_DEVICE_STATE: u32 = 0x0800_0022;
+
+/// Who can access an object in a simulated SubDevice's object dictionary over CoE.
+///
+/// Access rules only apply to the MainDevice. The application can always change an object with
+/// [`SimSubDevice::set_sdo`](crate::sim::SimSubDevice::set_sdo).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SdoAccess {
+    /// The object can be read but not written.
+    ReadOnly,
+    /// The object can be written but not read.
+    WriteOnly,
+    /// The object can be read and written in any state.
+    ReadWrite,
+    /// The object can be read in any state, but only written in PRE-OP, like PDO mapping and
+    /// assignment objects.
+    ReadWritePreOp,
+}
+
+/// An object declared in a simulated SubDevice's object dictionary with
+/// [`SimSubDevice::with_object`](crate::sim::SimSubDevice::with_object).
+///
+/// Objects are read-write by default. The data type sets the length of values the MainDevice
+/// can write, and the length of the object when it is mapped into a PDO.
+///
+/// # Examples
+///
+/// ```rust
+/// use ethercrab::{
+///     sii::PrimitiveDataType,
+///     sim::{SdoAccess, SimObject},
+/// };
+///
+/// // Status word, which can be read or mapped into the inputs
+/// let status_word =
+///     SimObject::new(PrimitiveDataType::U16, 0x0250u16).with_access(SdoAccess::ReadOnly);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimObject {
+    data_type: PrimitiveDataType,
+    access: SdoAccess,
+    name: String,
+    value: Vec<u8>,
+}
+
+impl SimObject {
+    /// A read-write object of the given type, holding `value` until it is written.
+    ///
+    /// Use [`PrimitiveDataType::Unknown`] for objects of other types, e.g. strings, which are
+    /// then written with values of the same length as `value`.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the length of `value` does not match `data_type`.
+    pub fn new(data_type: PrimitiveDataType, value: impl EtherCrabWireWrite) -> Self {
+        let mut buf = vec![0u8; value.packed_len()];
+
+        value.pack_to_slice_unchecked(&mut buf);
+
+        if let Some(bit_len) = type_bit_len(data_type) {
+            assert_eq!(
+                buf.len(),
+                usize::from(bit_len.div_ceil(8)),
+                "value length does not match data type {:?}",
+                data_type
+            );
+        }
+
+        Self {
+            data_type,
+            access: SdoAccess::ReadWrite,
+            name: String::new(),
+            value: buf,
+        }
+    }
+
+    /// Set who can access the object.
+    pub fn with_access(mut self, access: SdoAccess) -> Self {
+        self.access = access;
+
+        self
+    }
+
+    /// Set the name returned for the object by the SDO Information service.
+    ///
+    /// The name of sub index 0 is also used as the name of the whole object.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+
+        self
+    }
+
+    /// The object's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The object's data type.
+    pub fn data_type(&self) -> PrimitiveDataType {
+        self.data_type
+    }
+
+    /// Who can access the object.
+    pub fn access(&self) -> SdoAccess {
+        self.access
+    }
+
+    /// An object of unknown type holding a raw value, accessible like other objects at `index`.
+    fn raw(index: u16, value: Vec<u8>) -> Self {
+        Self {
+            data_type: PrimitiveDataType::Unknown,
+            access: if is_pdo_config(index) {
+                SdoAccess::ReadWritePreOp
+            } else {
+                SdoAccess::ReadWrite
+            },
+            name: String::new(),
+            value,
+        }
+    }
+
+    /// Length of the object in bits when mapped into a PDO.
+    fn bit_len(&self) -> usize {
+        type_bit_len(self.data_type)
+            .map(usize::from)
+            .unwrap_or(self.value.len() * 8)
+    }
+}
+
+/// Length of a value of the given type in bits, or `None` for unknown types.
+fn type_bit_len(data_type: PrimitiveDataType) -> Option<u16> {
+    use PrimitiveDataType::*;
+
+    let bit_len = match data_type {
+        Bool | Bit1 => 1,
+        Bit2 | Bit3 | Bit4 | Bit5 | Bit6 | Bit7 | Bit8 | Bit9 | Bit10 | Bit11 | Bit12 | Bit13
+        | Bit14 | Bit15 | Bit16 => u16::from(data_type as u8 - Bit1 as u8) + 1,
+        Byte | BitArr8 | I8 | U8 => 8,
+        Word | BitArr16 | I16 | U16 => 16,
+        I24 | U24 => 24,
+        DWord | BitArr32 | I32 | U32 | F32 => 32,
+        I40 | U40 => 40,
+        I48 | U48 => 48,
+        I56 | U56 => 56,
+        I64 | U64 | F64 => 64,
+        Unknown => return None,
+    };
+
+    Some(bit_len)
+}
+
+/// The objects of a simulated SubDevice, stored as raw little endian values.
+#[derive(Debug, Default)]
+pub(super) struct ObjectDictionary {
+    objects: BTreeMap<(u16, u8), SimObject>,
+}
+
+impl ObjectDictionary {
+    pub(super) fn get(&self, index: u16, sub_index: u8) -> Option<&[u8]> {
+        self.objects
+            .get(&(index, sub_index))
+            .map(|object| object.value.as_slice())
+    }
+
+    /// Declare an object, replacing any existing one.
+    pub(super) fn declare(&mut self, index: u16, sub_index: u8, object: SimObject) {
+        self.objects.insert((index, sub_index), object);
+    }
+
+    /// Set the value of an object, keeping its type and access if it already exists.
+    pub(super) fn insert(&mut self, index: u16, sub_index: u8, value: Vec<u8>) {
+        self.objects
+            .entry((index, sub_index))
+            .and_modify(|object| object.value.clone_from(&value))
+            .or_insert_with(|| SimObject::raw(index, value.clone()));
+    }
+
+    /// Declare an object unless the application already provided one.
+    pub(super) fn insert_default(&mut self, index: u16, sub_index: u8, object: SimObject) {
+        self.objects.entry((index, sub_index)).or_insert(object);
+    }
+
+    /// All object indices, in ascending order.
+    fn indices(&self) -> Vec<u16> {
+        let mut indices = self
+            .objects
+            .keys()
+            .map(|(index, _)| *index)
+            .collect::<Vec<_>>();
+
+        indices.dedup();
+
+        indices
+    }
+
+    fn has_index(&self, index: u16) -> bool {
+        self.objects
+            .range((index, 0)..=(index, u8::MAX))
+            .next()
+            .is_some()
+    }
+
+    /// The highest sub index of the object at `index`.
+    fn max_sub_index(&self, index: u16) -> Option<u8> {
+        self.objects
+            .range((index, 0)..=(index, u8::MAX))
+            .next_back()
+            .map(|((_, sub_index), _)| *sub_index)
+    }
+
+    /// Total bit length of all PDOs assigned by the sync manager PDO assignment object at `assign`,
+    /// e.g. `0x1c12`.
+    pub(super) fn assigned_bit_len(&self, assign: u16) -> u16 {
+        let sub_index_0 = |index| {
+            self.get(index, 0)
+                .and_then(|value| value.first())
+                .copied()
+                .unwrap_or(0)
+        };
+
+        (1..=sub_index_0(assign))
+            .filter_map(|i| self.get(assign, i)?.first_chunk().copied())
+            .map(u16::from_le_bytes)
+            .map(|pdo| {
+                (1..=sub_index_0(pdo))
+                    // The lowest byte of a mapping entry is its bit length
+                    .filter_map(|i| self.get(pdo, i)?.first().copied())
+                    .map(u16::from)
+                    .sum::<u16>()
+            })
+            .sum()
+    }
+
+    fn lookup(&self, index: u16, sub_index: u8) -> Result<&SimObject, u32> {
+        self.objects
+            .get(&(index, sub_index))
+            .ok_or(if self.has_index(index) {
+                ABORT_NO_SUB_INDEX
+            } else {
+                ABORT_NO_OBJECT
+            })
+    }
+
+    /// Check a value written to a PDO mapping or assignment object refers to objects that exist.
+    fn check_pdo_config(&self, index: u16, sub_index: u8, data: &[u8]) -> Result<(), u32> {
+        if !is_pdo_config(index) {
+            return Ok(());
+        }
+
+        if sub_index == 0 {
+            let count = data.first().copied().unwrap_or(0);
+
+            return if count > self.max_sub_index(index).unwrap_or(0) {
+                Err(ABORT_VALUE_TOO_LARGE)
+            } else {
+                Ok(())
+            };
+        }
+
+        match index {
+            // Mapping entries: index in the high WORD, then sub index and bit length
+            0x1600..=0x17ff | 0x1a00..=0x1bff => {
+                let entry = data
+                    .first_chunk()
+                    .copied()
+                    .map(u32::from_le_bytes)
+                    .ok_or(ABORT_PDO_MAPPING)?;
+
+                let mapped_index = (entry >> 16) as u16;
+                let mapped_sub_index = (entry >> 8) as u8;
+                let bit_len = usize::from(entry as u8);
+
+                // Index zero is a gap of `bit_len` bits
+                if entry == 0 || mapped_index == 0 {
+                    return Ok(());
+                }
+
+                match self.objects.get(&(mapped_index, mapped_sub_index)) {
+                    Some(object) if object.bit_len() == bit_len => Ok(()),
+                    _ => Err(ABORT_PDO_MAPPING),
+                }
+            }
+            // Assigned PDOs
+            _ => {
+                let pdo = data
+                    .first_chunk()
+                    .copied()
+                    .map(u16::from_le_bytes)
+                    .ok_or(ABORT_PDO_MAPPING)?;
+
+                if pdo == 0 || self.has_index(pdo) {
+                    Ok(())
+                } else {
+                    Err(ABORT_PDO_MAPPING)
+                }
+            }
+        }
+    }
+}
+
+/// Whether the object at `index` configures the process data layout, so may only be written in
+/// PRE-OP.
+fn is_pdo_config(index: u16) -> bool {
+    matches!(index, 0x1600..=0x1bff | 0x1c10..=0x1c2f)
+}
+
+/// Handle a mailbox request written by the MainDevice, returning the responses to put in the read
+/// mailbox in order.
+///
+/// Only expedited and normal SDO transfers and SDO Information requests are supported. Requests
+/// for other mailbox protocols are ignored.
+pub(super) fn handle_request(
+    objects: &mut ObjectDictionary,
+    state: SubDeviceState,
+    request: &[u8],
+    mailbox_len: usize,
+) -> Vec<Vec<u8>> {
+    let Some(header) = request.get(0..12) else {
+        return Vec::new();
+    };
+
+    let mailbox_type = header[5] & 0x0f;
+    let counter = (header[5] >> 4) & 0x07;
+    let service = header[7] >> 4;
+
+    if mailbox_type == MAILBOX_TYPE_COE && service == SERVICE_SDO_INFORMATION {
+        return sdo_info(objects, counter, request, mailbox_len);
+    }
+
+    if mailbox_type != MAILBOX_TYPE_COE || service != SERVICE_SDO_REQUEST {
+        fmt::warn!(
+            "Simulated SubDevice ignoring mailbox type {:#04x}, CoE service {:#04x}",
+            mailbox_type,
+            service
+        );
+
+        return Vec::new();
+    }
+
+    let command = header[8];
+    let index = u16::from_le_bytes([header[9], header[10]]);
+    let sub_index = header[11];
+
+    let result = if command & 0x10 != 0 {
+        Err(ABORT_UNSUPPORTED_ACCESS)
+    } else {
+        match command >> 5 {
+            COMMAND_DOWNLOAD => download(objects, state, command, index, sub_index, request),
+            COMMAND_UPLOAD => upload(objects, index, sub_index, mailbox_len),
+            _ => Err(ABORT_COMMAND_SPECIFIER),
+        }
+    };
+
+    let response = match result {
+        Ok(sdo) => coe_response(counter, SERVICE_SDO_RESPONSE, &sdo),
+        Err(code) => {
+            fmt::debug!(
+                "Simulated SubDevice aborting SDO {:#06x}:{} with code {:#010x}",
+                index,
+                sub_index,
+                code
+            );
+
+            let mut sdo = sdo_header(ABORT, index, sub_index);
+            sdo.extend_from_slice(&code.to_le_bytes());
+
+            coe_response(counter, SERVICE_SDO_REQUEST, &sdo)
+        }
+    };
+
+    vec![response]
+}
+
+/// An emergency message, sent in place of the response to `request`.
+pub(super) fn emergency(request: &[u8], error_code: u16, error_register: u8) -> Vec<u8> {
+    let counter = request.get(5).map_or(0, |header| (header >> 4) & 0x07);
+
+    // Error code, error register and 5 bytes of manufacturer specific data
+    let mut data = error_code.to_le_bytes().to_vec();
+    data.push(error_register);
+    data.extend_from_slice(&[0; 5]);
+
+    coe_response(counter, SERVICE_EMERGENCY, &data)
+}
+
+/// Handle an SDO Information request, splitting the response into as many fragments as needed to
+/// fit in the read mailbox.
+fn sdo_info(
+    objects: &ObjectDictionary,
+    counter: u8,
+    request: &[u8],
+    mailbox_len: usize,
+) -> Vec<Vec<u8>> {
+    let op_code = request[8] & 0x7f;
+    let data = &request[12..];
+
+    let result = match op_code {
+        INFO_GET_OD_LIST => od_list(objects, data),
+        INFO_GET_OBJECT_DESCRIPTION => object_description(objects, data),
+        INFO_GET_ENTRY_DESCRIPTION => entry_description(objects, data),
+        _ => Err(ABORT_COMMAND_SPECIFIER),
+    };
+
+    let payload = match result {
+        Ok(payload) => payload,
+        Err(code) => {
+            fmt::debug!(
+                "Simulated SubDevice SDO Information request {:#04x} failed with code {:#010x}",
+                op_code,
+                code
+            );
+
+            let mut info = vec![INFO_ERROR, 0, 0, 0];
+            info.extend_from_slice(&code.to_le_bytes());
+
+            return vec![coe_response(counter, SERVICE_SDO_INFORMATION, &info)];
+        }
+    };
+
+    // Mailbox header, CoE header and SDO Information header
+    let fragment_len = mailbox_len.saturating_sub(12).max(2);
+    let fragments = payload.chunks(fragment_len).collect::<Vec<_>>();
+    let last = fragments.len().saturating_sub(1);
+
+    fragments
+        .iter()
+        .enumerate()
+        .map(|(i, fragment)| {
+            let fragments_left = (last - i) as u16;
+            let incomplete = if fragments_left > 0 { 0x80 } else { 0x00 };
+
+            let mut info = vec![(op_code + 1) | incomplete, 0];
+            info.extend_from_slice(&fragments_left.to_le_bytes());
+            info.extend_from_slice(fragment);
+
+            coe_response(counter, SERVICE_SDO_INFORMATION, &info)
+        })
+        .collect()
+}
+
+fn od_list(objects: &ObjectDictionary, data: &[u8]) -> Result<Vec<u8>, u32> {
+    let list_type = data.get(0..2).ok_or(ABORT_GENERAL)?;
+
+    // Only the list of all objects is supported
+    if list_type != [0x01, 0x00] {
+        return Err(ABORT_UNSUPPORTED_ACCESS);
+    }
+
+    let mut payload = list_type.to_vec();
+
+    for index in objects.indices() {
+        payload.extend_from_slice(&index.to_le_bytes());
+    }
+
+    Ok(payload)
+}
+
+fn object_description(objects: &ObjectDictionary, data: &[u8]) -> Result<Vec<u8>, u32> {
+    let index = data
+        .first_chunk()
+        .copied()
+        .map(u16::from_le_bytes)
+        .ok_or(ABORT_GENERAL)?;
+
+    let max_sub_index = objects.max_sub_index(index).ok_or(ABORT_NO_OBJECT)?;
+
+    let first = objects
+        .objects
+        .range((index, 0)..=(index, u8::MAX))
+        .next()
+        .map(|(_, object)| object)
+        .ok_or(ABORT_NO_OBJECT)?;
+
+    let (data_type, object_code) = if max_sub_index == 0 {
+        (first.data_type as u16, OBJECT_CODE_VAR)
+    } else {
+        (0, OBJECT_CODE_RECORD)
+    };
+
+    let name = objects
+        .objects
+        .get(&(index, 0))
+        .map_or("", |object| object.name.as_str());
+
+    let mut payload = index.to_le_bytes().to_vec();
+    payload.extend_from_slice(&data_type.to_le_bytes());
+    payload.push(max_sub_index);
+    payload.push(object_code);
+    payload.extend_from_slice(name.as_bytes());
+
+    Ok(payload)
+}
+
+fn entry_description(objects: &ObjectDictionary, data: &[u8]) -> Result<Vec<u8>, u32> {
+    // Unit, default, minimum and maximum values requested in the value info byte are not
+    // supported, so the value info is always zero in the response.
+    let [index_lo, index_hi, sub_index, _value_info] =
+        data.first_chunk().copied().ok_or(ABORT_GENERAL)?;
+    let index = u16::from_le_bytes([index_lo, index_hi]);
+
+    let object = objects.lookup(index, sub_index)?;
+
+    // Read and write bits for PRE-OP, SAFE-OP and OP, ETG1000.6 Table 48
+    let access: u16 = match object.access {
+        SdoAccess::ReadOnly => 0x0007,
+        SdoAccess::WriteOnly => 0x0038,
+        SdoAccess::ReadWrite => 0x003f,
+        SdoAccess::ReadWritePreOp => 0x000f,
+    };
+
+    let mut payload = vec![index_lo, index_hi, sub_index, 0];
+    payload.extend_from_slice(&(object.data_type as u16).to_le_bytes());
+    payload.extend_from_slice(&(object.bit_len() as u16).to_le_bytes());
+    payload.extend_from_slice(&access.to_le_bytes());
+    payload.extend_from_slice(object.name.as_bytes());
+
+    Ok(payload)
+}
+
+fn download(
+    objects: &mut ObjectDictionary,
+    state: SubDeviceState,
+    command: u8,
+    index: u16,
+    sub_index: u8,
+    request: &[u8],
+) -> Result<Vec<u8>, u32> {
+    let data = if command & 0x02 != 0 {
+        let unused = if command & 0x01 != 0 {
+            usize::from((command >> 2) & 0x03)
+        } else {
+            0
+        };
+
+        request.get(12..(16 - unused))
+    } else {
+        request.get(12..16).and_then(|size| {
+            let size = u32::from_le_bytes(size.try_into().ok()?);
+
+            request.get(16..(16 + size as usize))
+        })
+    }
+    .ok_or(ABORT_GENERAL)?;
+
+    let object = objects.lookup(index, sub_index)?;
+
+    match object.access {
+        SdoAccess::ReadOnly => return Err(ABORT_READ_ONLY),
+        SdoAccess::ReadWritePreOp if state != SubDeviceState::PreOp => {
+            return Err(ABORT_DEVICE_STATE)
+        }
+        _ => (),
+    }
+
+    if object.value.len() != data.len() {
+        return Err(ABORT_LENGTH_MISMATCH);
+    }
+
+    objects.check_pdo_config(index, sub_index, data)?;
+
+    objects.insert(index, sub_index, data.to_vec());
+
+    let mut sdo = sdo_header(DOWNLOAD_RESPONSE, index, sub_index);
+    sdo.extend_from_slice(&[0u8; 4]);
+
+    Ok(sdo)
+}
+
+fn upload(
+    objects: &ObjectDictionary,
+    index: u16,
+    sub_index: u8,
+    mailbox_len: usize,
+) -> Result<Vec<u8>, u32> {
+    let object = objects.lookup(index, sub_index)?;
+
+    if object.access == SdoAccess::WriteOnly {
+        return Err(ABORT_WRITE_ONLY);
+    }
+
+    let value = object.value.as_slice();
+
+    let sdo = if value.len() <= 4 {
+        let unused = 4 - value.len();
+
+        let mut sdo = sdo_header(
+            UPLOAD_RESPONSE_EXPEDITED | (unused as u8) << 2,
+            index,
+            sub_index,
+        );
+        sdo.extend_from_slice(value);
+        sdo.resize(sdo.len() + unused, 0);
+
+        sdo
+    } else {
+        // Mailbox header, CoE header, SDO header and complete size
+        if 16 + value.len() > mailbox_len {
+            fmt::error!(
+                "Simulated SubDevice SDO {:#06x}:{} is too long for a normal upload. Segmented transfers are not supported",
+                index,
+                sub_index
+            );
+
+            return Err(ABORT_GENERAL);
+        }
+
+        let mut sdo = sdo_header(UPLOAD_RESPONSE_NORMAL, index, sub_index);
+        sdo.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        sdo.extend_from_slice(value);
+
+        sdo
+    };
+
+    Ok(sdo)
+}
+
+fn sdo_header(command: u8, index: u16, sub_index: u8) -> Vec<u8> {
+    let [index_lo, index_hi] = index.to_le_bytes();
+
+    vec![command, index_lo, index_hi, sub_index]
+}
+
+/// Prepend the mailbox and CoE headers to an SDO.
+fn coe_response(counter: u8, service: u8, sdo: &[u8]) -> Vec<u8> {
+    // CoE header and SDO
+    let length = 2 + sdo.len() as u16;
+
+    let mut response = Vec::with_capacity(6 + usize::from(length));
+
+    response.extend_from_slice(&length.to_le_bytes());
+    // Address
+    response.extend_from_slice(&[0, 0]);
+    // Channel and priority
+    response.push(0);
+    response.push(MAILBOX_TYPE_COE | counter << 4);
+    response.extend_from_slice(&(u16::from(service) << 12).to_le_bytes());
+    response.extend_from_slice(sdo);
+
+    response
+}
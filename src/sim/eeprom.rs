@@ -0,0 +1,192 @@
+//! SII EEPROM image generation for simulated SubDevices.
+
+use super::subdevice::{
+    SimSubDevice, BOOTSTRAP_MAILBOX_LEN, BOOTSTRAP_MAILBOX_READ_START,
+    BOOTSTRAP_MAILBOX_WRITE_START, INPUT_OBJECT, MAILBOX_LEN, OUTPUT_OBJECT, RX_PDO, TX_PDO,
+};
+use crate::{
+    base_data_types::PrimitiveDataType,
+    eeprom::{
+        sii_checksum,
+        types::{CategoryType, CoeDetails, MailboxProtocols, SyncManagerType},
+        SII_CHECKSUM_WORD,
+    },
+};
+
+/// EEPROM size in bytes. Words past the end of the generated image read as `0xffff`, like an erased
+/// EEPROM.
+const EEPROM_LEN: usize = 2048;
+
+/// Byte address of the first category, ETG2010 Table 2.
+const FIRST_CATEGORY: usize = 0x0040 * 2;
+
+/// Generate an EEPROM image describing the SubDevice's identity, mailbox, sync managers, FMMUs and
+/// PDOs.
+pub(super) fn sii_image(subdevice: &SimSubDevice) -> Vec<u8> {
+    let mut image = vec![0u8; FIRST_CATEGORY];
+
+    let identity = subdevice.identity();
+
+    for (word, value) in [
+        (0x0008, identity.vendor_id),
+        (0x000a, identity.product_id),
+        (0x000c, identity.revision),
+        (0x000e, identity.serial),
+    ] {
+        image[word * 2..][..4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    if subdevice.has_coe() {
+        let sms = subdevice.sync_managers();
+
+        let protocols = MailboxProtocols::COE.bits();
+
+        for (word, value) in [
+            (0x0018, sms[0].start),
+            (0x0019, MAILBOX_LEN),
+            (0x001a, sms[1].start),
+            (0x001b, MAILBOX_LEN),
+            (0x001c, u16::from(protocols)),
+        ] {
+            image[word * 2..][..2].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    if subdevice.has_bootstrap() {
+        for (word, value) in [
+            (0x0014, BOOTSTRAP_MAILBOX_WRITE_START),
+            (0x0015, BOOTSTRAP_MAILBOX_LEN),
+            (0x0016, BOOTSTRAP_MAILBOX_READ_START),
+            (0x0017, BOOTSTRAP_MAILBOX_LEN),
+        ] {
+            image[word * 2..][..2].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    // EEPROM size in KiBit minus one, and version
+    image[0x003e * 2..][..4].copy_from_slice(&[(EEPROM_LEN * 8 / 1024 - 1) as u8, 0, 1, 0]);
+
+    image[usize::from(SII_CHECKSUM_WORD) * 2] = sii_checksum(&image[0..14]);
+
+    let name = subdevice.name().as_bytes();
+
+    let mut strings = vec![1, name.len() as u8];
+    strings.extend_from_slice(name);
+    push_category(&mut image, CategoryType::Strings, &strings);
+
+    let coe_details = if subdevice.has_coe() {
+        CoeDetails::ENABLE_SDO
+            | CoeDetails::ENABLE_SDO_INFO
+            | CoeDetails::ENABLE_PDO_ASSIGN
+            | CoeDetails::ENABLE_PDO_CONFIG
+    } else {
+        CoeDetails::empty()
+    };
+
+    let mut general = [0u8; 32];
+    // Order and name string indices
+    general[2] = 1;
+    general[3] = 1;
+    general[5] = coe_details.bits();
+    // Ports 0 and 1 are EBUS
+    general[14] = 0x33;
+    push_category(&mut image, CategoryType::General, &general);
+
+    let fmmus = subdevice
+        .fmmus()
+        .into_iter()
+        .map(u8::from)
+        .collect::<Vec<_>>();
+
+    if !fmmus.is_empty() {
+        push_category(&mut image, CategoryType::Fmmu, &fmmus);
+    }
+
+    let sms = subdevice.sync_managers();
+
+    if !sms.is_empty() {
+        let sm_category = sms
+            .iter()
+            .flat_map(|sm| {
+                let [start_lo, start_hi] = sm.start.to_le_bytes();
+                let [len_lo, len_hi] = sm.len.to_le_bytes();
+
+                [
+                    start_lo,
+                    start_hi,
+                    len_lo,
+                    len_hi,
+                    sm.control,
+                    0,
+                    // Enabled
+                    0x01,
+                    u8::from(sm.usage),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        push_category(&mut image, CategoryType::SyncManager, &sm_category);
+    }
+
+    for (category, usage, pdo, object) in [
+        (
+            CategoryType::TxPdo,
+            SyncManagerType::ProcessDataRead,
+            TX_PDO,
+            INPUT_OBJECT,
+        ),
+        (
+            CategoryType::RxPdo,
+            SyncManagerType::ProcessDataWrite,
+            RX_PDO,
+            OUTPUT_OBJECT,
+        ),
+    ] {
+        let Some((sm_index, sm)) = sms.iter().enumerate().find(|(_, sm)| sm.usage == usage) else {
+            continue;
+        };
+
+        if sm.len == 0 {
+            continue;
+        }
+
+        let num_entries = sm.len as u8;
+
+        let [pdo_lo, pdo_hi] = pdo.to_le_bytes();
+
+        let mut pdo_category = vec![pdo_lo, pdo_hi, num_entries, sm_index as u8, 0, 0, 0, 0];
+
+        let [object_lo, object_hi] = object.to_le_bytes();
+
+        for sub_index in 1..=num_entries {
+            pdo_category.extend_from_slice(&[
+                object_lo,
+                object_hi,
+                sub_index,
+                0,
+                u8::from(PrimitiveDataType::U8),
+                8,
+                0,
+                0,
+            ]);
+        }
+
+        push_category(&mut image, category, &pdo_category);
+    }
+
+    image.extend_from_slice(&u16::from(CategoryType::End).to_le_bytes());
+
+    image.resize(EEPROM_LEN.max(image.len()), 0xff);
+
+    image
+}
+
+/// Append a category header and data, padded to a whole number of words.
+fn push_category(image: &mut Vec<u8>, category: CategoryType, data: &[u8]) {
+    let len_words = data.len().div_ceil(2) as u16;
+
+    image.extend_from_slice(&u16::from(category).to_le_bytes());
+    image.extend_from_slice(&len_words.to_le_bytes());
+    image.extend_from_slice(data);
+    image.resize(image.len() + data.len() % 2, 0);
+}
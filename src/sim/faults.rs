@@ -0,0 +1,389 @@
+//! Fault injection for any [`RawEthernetDevice`].
+
+use crate::{
+    error::Error,
+    timer_factory::{timer, Timer},
+    RawEthernetDevice,
+};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// Which faults [`FaultyDevice`] injects and how often.
+///
+/// Each probability is the chance, from `0.0` (never) to `1.0` (always), that the fault is applied
+/// to a received frame. Faults are independent of each other, so a frame may be e.g. both delayed
+/// and duplicated.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FaultPolicy {
+    /// Seed of the random number generator deciding which frames are affected.
+    ///
+    /// The same seed and traffic always produces the same faults.
+    pub seed: u64,
+
+    /// Probability that a frame is lost.
+    pub drop: f32,
+
+    /// Probability that a frame is received twice.
+    pub duplicate: f32,
+
+    /// Probability that a frame is held back for a random time of up to
+    /// [`max_delay`](FaultPolicy::max_delay).
+    ///
+    /// Frames received in the meantime are passed on, so delayed frames can arrive out of order.
+    pub delay: f32,
+
+    /// The longest time a delayed frame is held back for.
+    pub max_delay: Duration,
+
+    /// Probability that a frame is cut short at a random length.
+    pub truncate: f32,
+
+    /// Probability that a single random bit of a frame is inverted.
+    pub bit_flip: f32,
+}
+
+impl Default for FaultPolicy {
+    /// No faults.
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            drop: 0.0,
+            duplicate: 0.0,
+            delay: 0.0,
+            max_delay: Duration::from_millis(10),
+            truncate: 0.0,
+            bit_flip: 0.0,
+        }
+    }
+}
+
+/// The number of faults injected by a [`FaultyDevice`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct FaultCounts {
+    /// Frames that were lost.
+    pub dropped: u32,
+
+    /// Frames that were received twice.
+    pub duplicated: u32,
+
+    /// Frames that were held back.
+    pub delayed: u32,
+
+    /// Frames that were cut short.
+    pub truncated: u32,
+
+    /// Frames with an inverted bit.
+    pub bit_flipped: u32,
+}
+
+#[derive(Debug)]
+struct Faults {
+    policy: FaultPolicy,
+    rng: Rng,
+    counts: FaultCounts,
+    /// Frames ready to be received, in order.
+    ready: VecDeque<Vec<u8>>,
+    /// Frames held back until their timer expires.
+    delayed: Vec<(Timer, Vec<u8>)>,
+}
+
+impl Faults {
+    fn new(policy: FaultPolicy) -> Self {
+        Self {
+            rng: Rng::new(policy.seed),
+            policy,
+            counts: FaultCounts::default(),
+            ready: VecDeque::new(),
+            delayed: Vec::new(),
+        }
+    }
+
+    /// Apply faults to a frame received from the wrapped device.
+    fn inject(&mut self, mut frame: Vec<u8>) {
+        let policy = self.policy;
+
+        if self.rng.chance(policy.drop) {
+            self.counts.dropped += 1;
+
+            return;
+        }
+
+        if self.rng.chance(policy.truncate) {
+            self.counts.truncated += 1;
+
+            frame.truncate(self.rng.below(frame.len() as u64) as usize);
+        }
+
+        if !frame.is_empty() && self.rng.chance(policy.bit_flip) {
+            self.counts.bit_flipped += 1;
+
+            let bit = self.rng.below(frame.len() as u64 * 8) as usize;
+
+            frame[bit / 8] ^= 1 << (bit % 8);
+        }
+
+        let duplicate = self.rng.chance(policy.duplicate).then(|| frame.clone());
+
+        if self.rng.chance(policy.delay) {
+            self.counts.delayed += 1;
+
+            let max_nanos = policy.max_delay.as_nanos().min(u128::from(u64::MAX)) as u64;
+
+            let delay = Duration::from_nanos(self.rng.below(max_nanos.saturating_add(1)));
+
+            self.delayed.push((timer(delay), frame));
+        } else {
+            self.ready.push_back(frame);
+        }
+
+        if let Some(duplicate) = duplicate {
+            self.counts.duplicated += 1;
+
+            self.ready.push_back(duplicate);
+        }
+    }
+
+    /// Take the next frame that is ready to be received, registering `cx` with the timers of
+    /// frames that are still delayed.
+    fn next_frame(&mut self, cx: &mut Context<'_>) -> Option<Vec<u8>> {
+        if let Some(frame) = self.ready.pop_front() {
+            return Some(frame);
+        }
+
+        let expired = self
+            .delayed
+            .iter_mut()
+            .position(|(timer, _)| Pin::new(timer).poll(cx).is_ready())?;
+
+        Some(self.delayed.remove(expired).1)
+    }
+}
+
+/// A [`RawEthernetDevice`] wrapper that drops, duplicates, delays, truncates or corrupts received
+/// frames, to test how an application and EtherCrab recover from network faults.
+///
+/// Faults are chosen randomly according to a [`FaultPolicy`], using a seeded random number
+/// generator so failures are reproducible. Combined with [`block_on`](crate::sim::block_on),
+/// a test run always sees the same faults at the same virtual time. Any device can be wrapped,
+/// e.g. a [`SimNetwork`](crate::sim::SimNetwork) or a hardware device driver.
+///
+/// Faults are applied to frames as they are received from the wrapped device. Sent frames have
+/// therefore already been processed by the SubDevices, so a dropped frame behaves like a response
+/// lost on its way back to the MainDevice.
+///
+/// Dropped, delayed and duplicated frames are handled by EtherCrab's timeouts and
+/// [retries](crate::MainDeviceConfig::retry_behaviour). A truncated frame or one with a flipped
+/// bit may be received with corrupt data, be ignored, or make
+/// [`tx_rx_device`](crate::tx_rx_device) fail with [`Error::ReceiveFrame`], depending on which
+/// part of the frame is affected.
+///
+/// Retries happen within other timeouts, e.g. [`Timeouts::eeprom`](crate::Timeouts::eeprom), so
+/// those should be long enough for a lost frame to time out and be resent. Otherwise a single
+/// dropped frame still fails the operation.
+///
+/// Like [`SimNetwork`](crate::sim::SimNetwork), clones share their faults and frames in flight, so
+/// one clone can be passed to `tx_rx_device` while another is used to read
+/// [`counts`](FaultyDevice::counts) or change the policy.
+///
+/// # Examples
+///
+/// Initialise a network which loses one in five responses:
+///
+/// ```rust
+/// use core::time::Duration;
+/// use ethercrab::{
+///     sim::{self, FaultPolicy, FaultyDevice, SimNetwork, SimSubDevice},
+///     tx_rx_device, MainDevice, MainDeviceConfig, PduStorage, RetryBehaviour, SubDeviceIdentity,
+///     Timeouts,
+/// };
+///
+/// static PDU_STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+///
+/// let identity = SubDeviceIdentity {
+///     vendor_id: 0x2,
+///     product_id: 0x07d43052,
+///     revision: 0x00100000,
+///     serial: 0,
+/// };
+///
+/// let network = SimNetwork::new([SimSubDevice::new("EL2004", identity).with_outputs(1)]);
+///
+/// let device = FaultyDevice::new(
+///     network,
+///     FaultPolicy {
+///         seed: 1234,
+///         drop: 0.2,
+///         ..FaultPolicy::default()
+///     },
+/// );
+///
+/// let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// let maindevice = MainDevice::new(
+///     pdu_loop,
+///     Timeouts {
+///         pdu: Duration::from_millis(10),
+///         eeprom: Duration::from_millis(100),
+///         ..Timeouts::default()
+///     },
+///     MainDeviceConfig {
+///         retry_behaviour: RetryBehaviour::Forever,
+///         ..MainDeviceConfig::default()
+///     },
+/// );
+///
+/// let num_subdevices = sim::block_on(futures_lite::future::or(
+///     async {
+///         let group = maindevice
+///             .init_single_group::<8, 8>(sim::ethercat_now)
+///             .await
+///             .expect("init");
+///
+///         group.len()
+///     },
+///     async {
+///         tx_rx_device(device.clone(), tx, rx).await.expect("TX/RX task");
+///
+///         unreachable!()
+///     },
+/// ));
+///
+/// assert_eq!(num_subdevices, 1);
+/// assert!(device.counts().dropped > 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FaultyDevice<D> {
+    inner: D,
+    faults: Arc<Mutex<Faults>>,
+}
+
+impl<D> FaultyDevice<D> {
+    /// Wrap `inner`, injecting faults into the frames it receives according to `policy`.
+    pub fn new(inner: D, policy: FaultPolicy) -> Self {
+        Self {
+            inner,
+            faults: Arc::new(Mutex::new(Faults::new(policy))),
+        }
+    }
+
+    /// Replace the fault policy, e.g. to only inject faults once a network has been initialised.
+    ///
+    /// The random number generator is reseeded with the new policy's
+    /// [`seed`](FaultPolicy::seed). Frames that are already delayed are still received once their
+    /// delay expires.
+    pub fn set_policy(&self, policy: FaultPolicy) {
+        let mut faults = self.lock();
+
+        faults.policy = policy;
+        faults.rng = Rng::new(policy.seed);
+    }
+
+    /// The number of faults injected so far.
+    pub fn counts(&self) -> FaultCounts {
+        self.lock().counts
+    }
+
+    /// Get a reference to the wrapped device.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Faults> {
+        // A panic while holding the lock leaves no invariants broken, so carry on regardless
+        self.faults.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<D: RawEthernetDevice> RawEthernetDevice for FaultyDevice<D> {
+    fn poll_transmit(&mut self, cx: &mut Context<'_>, frame: &[u8]) -> Poll<Result<(), Error>> {
+        self.inner.poll_transmit(cx, frame)
+    }
+
+    fn poll_receive(
+        &mut self,
+        cx: &mut Context<'_>,
+        on_frame: impl FnOnce(&[u8]),
+    ) -> Poll<Result<(), Error>> {
+        let mut faults = self.faults.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Keep receiving until a frame survives, so dropped and delayed frames don't leave the
+        // caller without a registered waker
+        loop {
+            if let Some(frame) = faults.next_frame(cx) {
+                // Don't hold the lock while the frame is processed
+                drop(faults);
+
+                on_frame(&frame);
+
+                return Poll::Ready(Ok(()));
+            }
+
+            let mut received = None;
+
+            match self
+                .inner
+                .poll_receive(cx, |frame| received = Some(frame.to_vec()))
+            {
+                Poll::Ready(Ok(())) => (),
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+
+            if let Some(frame) = received {
+                faults.inject(frame);
+            }
+        }
+    }
+}
+
+/// A small, fast pseudo random number generator (xorshift64*), so no external crate is needed.
+#[derive(Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Mix the seed with SplitMix64 so similar seeds give unrelated sequences and a seed of
+        // zero doesn't leave the generator stuck at zero
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        Self(z.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A random number in `0..bound`, or zero if `bound` is zero.
+    fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return 0;
+        }
+
+        self.next_u64() % bound
+    }
+
+    /// Returns `true` with the given probability.
+    fn chance(&mut self, probability: f32) -> bool {
+        if probability <= 0.0 {
+            return false;
+        }
+
+        // 24 random bits, the precision of an `f32` mantissa
+        ((self.next_u64() >> 40) as f32 / (1u32 << 24) as f32) < probability
+    }
+}
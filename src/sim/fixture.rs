@@ -0,0 +1,90 @@
+//! Shared SubDevices and runners for tests against a [`SimNetwork`].
+
+use super::{block_on, SdoAccess, SimNetwork, SimObject, SimSubDevice};
+use crate::{tx_rx_device, MainDevice, MainDeviceConfig, PduStorage, SubDeviceIdentity, Timeouts};
+use core::{future::Future, time::Duration};
+
+pub(crate) fn identity(product_id: u32) -> SubDeviceIdentity {
+    SubDeviceIdentity {
+        vendor_id: 0x2,
+        product_id,
+        revision: 0x0011_0000,
+        serial: 0,
+    }
+}
+
+pub(crate) fn network() -> SimNetwork {
+    SimNetwork::new([
+        SimSubDevice::new("EK1100", identity(0x044c_2c52)),
+        SimSubDevice::new("EL2004", identity(0x07d4_3052)).with_outputs(1),
+        SimSubDevice::new("EL1004", identity(0x03ec_3052)).with_inputs(1),
+        SimSubDevice::new("Drive", identity(0x1234))
+            .with_inputs(4)
+            .with_outputs(2)
+            .with_coe()
+            .with_sdo(0x2000, 0, 0x1234u16),
+    ])
+}
+
+pub(crate) fn run<'sto, const N: usize, const DATA: usize, T>(
+    network: &SimNetwork,
+    storage: &'sto PduStorage<N, DATA>,
+    f: impl FnOnce(MainDevice<'sto>) -> T,
+) -> T::Output
+where
+    T: Future,
+{
+    run_with_config(network, storage, MainDeviceConfig::default(), f)
+}
+
+pub(crate) fn run_with_config<'sto, const N: usize, const DATA: usize, T>(
+    network: &SimNetwork,
+    storage: &'sto PduStorage<N, DATA>,
+    config: MainDeviceConfig,
+    f: impl FnOnce(MainDevice<'sto>) -> T,
+) -> T::Output
+where
+    T: Future,
+{
+    let (tx, rx, pdu_loop) = storage.try_split().unwrap();
+
+    let maindevice = MainDevice::new(
+        pdu_loop,
+        Timeouts {
+            pdu: Duration::from_millis(100),
+            state_transition: Duration::from_millis(500),
+            mailbox_response: Duration::from_millis(500),
+            ..Timeouts::default()
+        },
+        config,
+    );
+
+    block_on(futures_lite::future::or(f(maindevice), async {
+        tx_rx_device(network.clone(), tx, rx).await.unwrap();
+
+        unreachable!()
+    }))
+}
+
+pub(crate) fn servo() -> SimSubDevice {
+    use crate::base_data_types::PrimitiveDataType::{I32, I8, U16};
+
+    let read_only = |object: SimObject| object.with_access(SdoAccess::ReadOnly);
+
+    SimSubDevice::new("ELP-EC400S", identity(0x0400))
+        .with_inputs(2)
+        .with_outputs(2)
+        .with_coe()
+        .with_pdo_entries(4)
+        .with_object(0x6040, 0, SimObject::new(U16, 0u16))
+        .with_object(0x6041, 0, read_only(SimObject::new(U16, 0u16)))
+        .with_object(0x6060, 0, SimObject::new(I8, 0i8))
+        .with_object(0x6064, 0, read_only(SimObject::new(I32, 0i32)))
+        .with_object(0x606c, 0, read_only(SimObject::new(I32, 0i32)))
+        .with_object(0x60ff, 0, SimObject::new(I32, 0i32))
+        .with_object(
+            0x2000,
+            0,
+            SimObject::new(U16, 0u16).with_access(SdoAccess::WriteOnly),
+        )
+}
@@ -0,0 +1,888 @@
+//! A simulated EtherCAT network, so EtherCrab can be run without any hardware.
+//!
+//! [`SimNetwork`] models a line of [`SimSubDevice`]s, each with a minimal EtherCAT SubDevice
+//! Controller (ESC): registers, the AL state machine, SII EEPROM emulation, mailbox sync managers
+//! with a CoE SDO server, and byte-wide process data mapped through FMMUs. It implements
+//! [`RawEthernetDevice`] so it can be driven by [`tx_rx_device`](crate::tx_rx_device) in place of a
+//! network interface. Every sent frame is passed through each SubDevice in turn and returned
+//! immediately, so a response may arrive before the future that sent it is polled again.
+//!
+//! This allows [`MainDevice::init`](crate::MainDevice::init) and full group process data cycles to
+//! be run in unit tests and examples. Distributed clocks, segmented SDO transfers and mailbox
+//! protocols other than CoE are not simulated.
+//!
+//! Each SubDevice with a CoE mailbox has an object dictionary. Objects can be declared with a type,
+//! default value and access rules using [`SimObject`], so configuration code like DS402 PDO
+//! remapping can be checked against the SDO aborts a real SubDevice would return.
+//! [`SimSubDevice::emergency`] sends an emergency message in place of the next SDO response.
+//!
+//! [`block_on`] runs a future in virtual time, so timeouts, retries and delays complete instantly
+//! and deterministically. [`ethercat_now`] reads the virtual time and [`sleep`] waits in it.
+//!
+//! [`SimNetwork::record_transcript`] records every PDU exchanged with the network into a
+//! [`Transcript`], which can be compared to a golden copy to catch regressions in e.g. the
+//! initialisation sequence.
+//!
+//! [`FaultyDevice`] wraps a `SimNetwork`, or any other [`RawEthernetDevice`], to drop, duplicate,
+//! delay, truncate or corrupt frames according to a seeded [`FaultPolicy`], so recovery from
+//! network faults can be tested reproducibly.
+//!
+//! Requires the `sim` feature.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use ethercrab::{
+//!     sim::{SimNetwork, SimSubDevice},
+//!     tx_rx_device, MainDevice, MainDeviceConfig, PduStorage, SubDeviceIdentity, Timeouts,
+//! };
+//!
+//! static PDU_STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+//!
+//! let identity = SubDeviceIdentity {
+//!     vendor_id: 0x2,
+//!     product_id: 0x07d43052,
+//!     revision: 0x00100000,
+//!     serial: 0,
+//! };
+//!
+//! let network = SimNetwork::new([SimSubDevice::new("EL2004", identity).with_outputs(1)]);
+//!
+//! let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+//!
+//! let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+//!
+//! futures_lite::future::block_on(futures_lite::future::or(
+//!     async {
+//!         let group = maindevice
+//!             .init_single_group::<8, 8>(|| 0)
+//!             .await
+//!             .expect("init");
+//!
+//!         let group = group.into_op(&maindevice).await.expect("PRE-OP -> OP");
+//!
+//!         group
+//!             .subdevice(&maindevice, 0)
+//!             .expect("SubDevice")
+//!             .outputs_raw_mut()[0] = 0xaa;
+//!
+//!         group.tx_rx(&maindevice).await.expect("TX/RX");
+//!     },
+//!     async {
+//!         tx_rx_device(network.clone(), tx, rx).await.expect("TX/RX task");
+//!     },
+//! ));
+//!
+//! assert_eq!(network.with_subdevice(0, |subdevice| subdevice.outputs()[0]), Some(0xaa));
+//! ```
+
+mod coe;
+mod eeprom;
+mod faults;
+#[cfg(test)]
+pub(crate) mod fixture;
+mod subdevice;
+pub(crate) mod time;
+mod transcript;
+
+pub use coe::{SdoAccess, SimObject};
+pub use faults::{FaultCounts, FaultPolicy, FaultyDevice};
+pub use subdevice::SimSubDevice;
+pub use time::{block_on, ethercat_now, sleep};
+pub use transcript::{Transcript, UPDATE_TRANSCRIPTS_VAR};
+
+use crate::{
+    error::Error,
+    ethernet::{EthernetAddress, EthernetFrame},
+    RawEthernetDevice, ETHERCAT_ETHERTYPE,
+};
+use core::task::{Context, Poll, Waker};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Debug)]
+struct Network {
+    subdevices: Vec<SimSubDevice>,
+    /// The number of SubDevices frames reach before returning to the MainDevice.
+    connected: usize,
+    responses: VecDeque<Vec<u8>>,
+    waker: Option<Waker>,
+    transcript: Option<Transcript>,
+}
+
+/// A simulated network of SubDevices, connected in a line.
+///
+/// The network is cheaply cloneable: clones share the same SubDevices, so one clone can be passed
+/// to [`tx_rx_device`](crate::tx_rx_device) while another is used to inspect and modify SubDevice
+/// state with [`with_subdevice`](SimNetwork::with_subdevice).
+///
+/// See the [module documentation](crate::sim) for an example.
+#[derive(Debug, Clone)]
+pub struct SimNetwork {
+    inner: Arc<Mutex<Network>>,
+}
+
+impl SimNetwork {
+    /// Create a network of SubDevices, in the order they are connected to the MainDevice.
+    ///
+    /// All SubDevices start powered on, in INIT.
+    pub fn new(subdevices: impl IntoIterator<Item = SimSubDevice>) -> Self {
+        let mut subdevices = subdevices.into_iter().collect::<Vec<_>>();
+
+        let count = subdevices.len();
+
+        for (i, subdevice) in subdevices.iter_mut().enumerate() {
+            subdevice.power_on(i + 1 == count);
+        }
+
+        Self {
+            inner: Arc::new(Mutex::new(Network {
+                subdevices,
+                connected: count,
+                responses: VecDeque::new(),
+                waker: None,
+                transcript: None,
+            })),
+        }
+    }
+
+    /// The number of SubDevices in the network.
+    pub fn len(&self) -> usize {
+        self.lock().subdevices.len()
+    }
+
+    /// Whether the network has no SubDevices.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Access the SubDevice at `index`, e.g. to set its inputs or check its outputs.
+    ///
+    /// Returns `None` if there is no SubDevice at the given index.
+    pub fn with_subdevice<R>(
+        &self,
+        index: usize,
+        f: impl FnOnce(&mut SimSubDevice) -> R,
+    ) -> Option<R> {
+        self.lock().subdevices.get_mut(index).map(f)
+    }
+
+    /// Simulate a broken cable between the SubDevice at `index` and the next one.
+    ///
+    /// The SubDevice at `index` closes its outgoing port and returns frames to the MainDevice, so
+    /// SubDevices after it no longer see any traffic.
+    pub fn disconnect(&self, index: usize) {
+        let mut network = self.lock();
+
+        network.connected = network.connected.min(index + 1);
+
+        if let Some(subdevice) = network.subdevices.get_mut(index) {
+            subdevice.set_last(true);
+        }
+    }
+
+    /// Simulate the SubDevice at `index` losing power and restarting.
+    ///
+    /// All registers are reset, so the SubDevice loses its configured station address and returns
+    /// to INIT.
+    pub fn power_cycle(&self, index: usize) {
+        let mut network = self.lock();
+
+        let last = index + 1 >= network.connected;
+
+        if let Some(subdevice) = network.subdevices.get_mut(index) {
+            subdevice.power_on(last);
+        }
+    }
+
+    /// Start recording a [`Transcript`] of every PDU returned by the network, discarding any
+    /// transcript already being recorded.
+    pub fn record_transcript(&self) {
+        self.lock().transcript = Some(Transcript::default());
+    }
+
+    /// Stop recording and return the transcript recorded since
+    /// [`record_transcript`](SimNetwork::record_transcript) was called.
+    ///
+    /// Returns an empty transcript if nothing was being recorded.
+    pub fn take_transcript(&self) -> Transcript {
+        self.lock().transcript.take().unwrap_or_default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Network> {
+        // A panic while holding the lock leaves no invariants broken, so carry on regardless
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl RawEthernetDevice for SimNetwork {
+    fn poll_transmit(&mut self, _cx: &mut Context<'_>, frame: &[u8]) -> Poll<Result<(), Error>> {
+        let mut network = self.lock();
+
+        let mut frame = frame.to_vec();
+
+        let mut ethernet_frame =
+            EthernetFrame::new_checked(&mut frame).map_err(|_| Error::SendFrame)?;
+
+        // SubDevices set the U/L bit of the source address of every frame they forward
+        let mut src = ethernet_frame.src_addr();
+        src.0[0] |= 0x02;
+        ethernet_frame.set_src_addr(EthernetAddress(src.0));
+
+        if ethernet_frame.ethertype() == ETHERCAT_ETHERTYPE {
+            let payload = ethernet_frame.payload_mut();
+
+            let connected = network.connected;
+
+            for subdevice in network.subdevices.iter_mut().take(connected) {
+                subdevice.process_frame(payload);
+            }
+
+            if let Some(transcript) = network.transcript.as_mut() {
+                transcript.record(payload);
+            }
+        }
+
+        network.responses.push_back(frame);
+
+        if let Some(waker) = network.waker.take() {
+            waker.wake();
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_receive(
+        &mut self,
+        cx: &mut Context<'_>,
+        on_frame: impl FnOnce(&[u8]),
+    ) -> Poll<Result<(), Error>> {
+        let mut network = self.lock();
+
+        match network.responses.pop_front() {
+            Some(frame) => {
+                // Don't hold the lock while the frame is processed
+                drop(network);
+
+                on_frame(&frame);
+
+                Poll::Ready(Ok(()))
+            }
+            None => {
+                network.waker = Some(cx.waker().clone());
+
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fixture::*, *};
+    use crate::{
+        error::{ErrorContext, MailboxError, Operation},
+        tx_rx_device, AlStatusCode, ConfiguredAddress, MainDevice, MainDeviceConfig, PduStorage,
+        RetryBackoff, RetryBehaviour, SubDeviceState, Timeouts,
+    };
+    use core::time::Duration;
+
+    #[test]
+    fn init_and_cycle() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        network.with_subdevice(2, |subdevice| subdevice.inputs_mut()[0] = 0x5a);
+        network.with_subdevice(3, |subdevice| {
+            subdevice
+                .inputs_mut()
+                .copy_from_slice(&[0x01, 0x02, 0x03, 0x04])
+        });
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            assert_eq!(group.len(), 4);
+
+            {
+                let drive = group.subdevice(&maindevice, 3).unwrap();
+
+                assert_eq!(drive.name(), "Drive");
+                assert_eq!(drive.identity(), identity(0x1234));
+                assert_eq!(drive.sdo_read::<u32>(0x1018, 2).await, Ok(0x1234));
+                assert_eq!(drive.sdo_read::<u16>(0x2000, 0).await, Ok(0x1234));
+                assert_eq!(drive.sdo_write(0x2000, 0, 0xabcdu16).await, Ok(()));
+
+                // Identity is stored at word 8 of the EEPROM
+                let mut eeprom = [0u8; 32];
+
+                assert_eq!(drive.eeprom_read_into(0, &mut eeprom).await, Ok(()));
+                assert_eq!(
+                    eeprom[16..24],
+                    [0x02, 0x00, 0x00, 0x00, 0x34, 0x12, 0x00, 0x00]
+                );
+            }
+
+            let group = group.into_op(&maindevice).await.expect("PRE-OP -> OP");
+
+            assert_eq!(group.all_op(&maindevice).await, Ok(true));
+
+            group.subdevice(&maindevice, 1).unwrap().outputs_raw_mut()[0] = 0xaa;
+            group
+                .subdevice(&maindevice, 3)
+                .unwrap()
+                .outputs_raw_mut()
+                .copy_from_slice(&[0x11, 0x22]);
+
+            // Outputs are written on the first cycle and inputs read back
+            let wkc = group.tx_rx(&maindevice).await.expect("TX/RX");
+
+            // Two SubDevices with a single read or write FMMU, one with both
+            assert_eq!(wkc, 2 + 1 + (1 + 2));
+
+            assert_eq!(
+                group.subdevice(&maindevice, 2).unwrap().inputs_raw(),
+                &[0x5a]
+            );
+            assert_eq!(
+                group.subdevice(&maindevice, 3).unwrap().inputs_raw(),
+                &[0x01, 0x02, 0x03, 0x04]
+            );
+
+            assert_eq!(
+                net.with_subdevice(1, |subdevice| subdevice.outputs().to_vec()),
+                Some(vec![0xaa])
+            );
+            assert_eq!(
+                net.with_subdevice(3, |subdevice| subdevice.outputs().to_vec()),
+                Some(vec![0x11, 0x22])
+            );
+            assert_eq!(
+                net.with_subdevice(3, |subdevice| subdevice.sdo(0x2000, 0).map(<[u8]>::to_vec)),
+                Some(Some(vec![0xcd, 0xab]))
+            );
+        });
+    }
+
+    #[test]
+    fn fault() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            let group = group.into_op(&maindevice).await.expect("PRE-OP -> OP");
+
+            assert_eq!(group.all_op(&maindevice).await, Ok(true));
+
+            net.with_subdevice(1, |subdevice| {
+                subdevice.fault(AlStatusCode::SyncManagerWatchdog)
+            });
+
+            assert_eq!(
+                group.all_op(&maindevice).await,
+                Err(Error::StateTransition {
+                    configured_address: ConfiguredAddress::new(0x1001),
+                    requested: SubDeviceState::Op,
+                    current: SubDeviceState::SafeOp,
+                    status_code: AlStatusCode::SyncManagerWatchdog,
+                })
+            );
+            assert_eq!(
+                group.subdevice(&maindevice, 1).unwrap().status().await,
+                Err(Error::SubDevice(AlStatusCode::SyncManagerWatchdog))
+            );
+        });
+
+        assert_eq!(
+            network.with_subdevice(1, |subdevice| subdevice.state()),
+            Some(SubDeviceState::SafeOp)
+        );
+    }
+
+    #[test]
+    fn invalid_transition() {
+        let mut subdevice = SimSubDevice::new("EL2004", identity(0x07d4_3052)).with_outputs(1);
+
+        subdevice.power_on(true);
+
+        // FPWR AL control to OP, to the default station address of 0
+        let mut frame = vec![
+            // EtherCAT header: length 14, type PDU
+            0x0e, 0x10, //
+            // FPWR, index 0, address 0x0000:0x0120, length 2
+            0x05, 0x00, 0x00, 0x00, 0x20, 0x01, 0x02, 0x00, 0x00, 0x00, //
+            // Data
+            0x08, 0x00, //
+            // Working counter
+            0x00, 0x00,
+        ];
+
+        subdevice.process_frame(&mut frame);
+
+        assert_eq!(frame[14..16], [0x01, 0x00]);
+        assert_eq!(subdevice.state(), SubDeviceState::Init);
+        assert_eq!(
+            subdevice.status_code(),
+            AlStatusCode::InvalidRequestedStateChange
+        );
+    }
+
+    /// Drops the first `drop` frames sent.
+    struct Lossy {
+        network: SimNetwork,
+        drop: usize,
+    }
+
+    impl RawEthernetDevice for Lossy {
+        fn poll_transmit(&mut self, cx: &mut Context<'_>, frame: &[u8]) -> Poll<Result<(), Error>> {
+            if self.drop > 0 {
+                self.drop -= 1;
+
+                return Poll::Ready(Ok(()));
+            }
+
+            self.network.poll_transmit(cx, frame)
+        }
+
+        fn poll_receive(
+            &mut self,
+            cx: &mut Context<'_>,
+            on_frame: impl FnOnce(&[u8]),
+        ) -> Poll<Result<(), Error>> {
+            self.network.poll_receive(cx, on_frame)
+        }
+    }
+
+    #[test]
+    fn virtual_time_retries() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let (tx, rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let maindevice = MainDevice::new(
+            pdu_loop,
+            Timeouts {
+                pdu: Duration::from_secs(1),
+                ..Timeouts::default()
+            },
+            MainDeviceConfig {
+                retry_behaviour: RetryBehaviour::Count(2),
+                retry_backoff: RetryBackoff::Fixed(Duration::from_millis(250)),
+                ..MainDeviceConfig::default()
+            },
+        );
+
+        let lossy = Lossy {
+            network: network(),
+            drop: 2,
+        };
+
+        let started = std::time::Instant::now();
+
+        let (num_subdevices, elapsed) = block_on(futures_lite::future::or(
+            async {
+                let group = maindevice
+                    .init_single_group::<8, 32>(ethercat_now)
+                    .await
+                    .expect("init");
+
+                (group.len(), Duration::from_nanos(ethercat_now()))
+            },
+            async {
+                tx_rx_device(lossy, tx, rx).await.unwrap();
+
+                unreachable!()
+            },
+        ));
+
+        assert_eq!(num_subdevices, 4);
+
+        // Two timeouts and a backoff before each resend
+        assert_eq!(elapsed, Duration::from_millis(2500));
+
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    fn run_faulty<const N: usize, const DATA: usize>(
+        storage: &PduStorage<N, DATA>,
+        device: FaultyDevice<SimNetwork>,
+    ) -> (Result<(usize, u32), Error>, Duration, crate::Statistics) {
+        let (tx, rx, pdu_loop) = storage.try_split().unwrap();
+
+        let maindevice = MainDevice::new(
+            pdu_loop,
+            Timeouts {
+                pdu: Duration::from_millis(10),
+                // Operations that wait on PDUs must leave time for them to be retried
+                eeprom: Duration::from_millis(500),
+                mailbox_echo: Duration::from_millis(500),
+                ..Timeouts::default()
+            },
+            MainDeviceConfig {
+                retry_behaviour: RetryBehaviour::Forever,
+                ..MainDeviceConfig::default()
+            },
+        );
+
+        let (result, elapsed) = block_on(async {
+            let result = futures_lite::future::or(
+                async {
+                    let group = maindevice.init_single_group::<8, 32>(ethercat_now).await?;
+
+                    let group = group.into_op(&maindevice).await?;
+
+                    // Process data isn't retried, so a lost cycle is reported and the next one
+                    // sent
+                    let mut failed_cycles = 0;
+
+                    for _ in 0..50 {
+                        match group.tx_rx(&maindevice).await {
+                            Ok(_) => (),
+                            Err(Error::Timeout) => failed_cycles += 1,
+                            Err(e) => return Err(e),
+                        }
+                    }
+
+                    Ok((group.len(), failed_cycles))
+                },
+                async {
+                    tx_rx_device(device, tx, rx).await?;
+
+                    unreachable!()
+                },
+            )
+            .await;
+
+            (result, Duration::from_nanos(ethercat_now()))
+        });
+
+        (result, elapsed, maindevice.statistics())
+    }
+
+    #[test]
+    fn fault_injection_recovery() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let policy = FaultPolicy {
+            seed: 0xec47,
+            drop: 0.1,
+            duplicate: 0.1,
+            delay: 0.1,
+            max_delay: Duration::from_millis(20),
+            ..FaultPolicy::default()
+        };
+
+        let device = FaultyDevice::new(network(), policy);
+
+        let (result, _, statistics) = run_faulty(&STORAGE, device.clone());
+
+        assert!(matches!(result, Ok((4, _))), "{result:?}");
+
+        let counts = device.counts();
+
+        assert!(counts.dropped > 0, "{counts:?}");
+        assert!(counts.duplicated > 0, "{counts:?}");
+        assert!(counts.delayed > 0, "{counts:?}");
+        assert_eq!(counts.truncated, 0);
+        assert_eq!(counts.bit_flipped, 0);
+
+        // Lost and late responses are resent, duplicates and late responses are ignored
+        assert!(statistics.pdu_retries >= counts.dropped, "{statistics:?}");
+        assert!(statistics.ignored_responses > 0, "{statistics:?}");
+    }
+
+    #[test]
+    fn fault_injection_is_reproducible() {
+        static STORAGE_A: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+        static STORAGE_B: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let policy = FaultPolicy {
+            seed: 7,
+            drop: 0.2,
+            delay: 0.2,
+            ..FaultPolicy::default()
+        };
+
+        let a = FaultyDevice::new(network(), policy);
+        let b = FaultyDevice::new(network(), policy);
+
+        let (result_a, elapsed_a, _) = run_faulty(&STORAGE_A, a.clone());
+        let (result_b, elapsed_b, _) = run_faulty(&STORAGE_B, b.clone());
+
+        assert!(matches!(result_a, Ok((4, _))), "{result_a:?}");
+        assert_eq!(result_a, result_b);
+        assert_eq!(elapsed_a, elapsed_b);
+        assert_eq!(a.counts(), b.counts());
+        assert!(a.counts().dropped > 0);
+    }
+
+    #[test]
+    fn fault_injection_corruption() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let device = FaultyDevice::new(
+            network(),
+            FaultPolicy {
+                truncate: 1.0,
+                ..FaultPolicy::default()
+            },
+        );
+
+        let (result, _, _) = run_faulty(&STORAGE, device.clone());
+
+        assert_eq!(result, Err(Error::ReceiveFrame));
+        assert!(device.counts().truncated > 0);
+
+        // The network works again once faults are turned off
+        static STORAGE_2: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        device.set_policy(FaultPolicy::default());
+
+        let (result, _, _) = run_faulty(&STORAGE_2, device);
+
+        assert_eq!(result, Ok((4, 0)));
+    }
+
+    #[test]
+    fn pdo_remapping() {
+        use crate::ds402::{OperationMode, PdoLayout};
+
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = SimNetwork::new([servo()]);
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            let layout = PdoLayout::configure(
+                &group.subdevice(&maindevice, 0).unwrap(),
+                OperationMode::CyclicSynchronousVelocity,
+            )
+            .await
+            .expect("configure PDOs");
+
+            assert_eq!(layout.target_velocity, Some(2));
+            assert_eq!(layout.velocity_actual, Some(6));
+
+            let group = group.into_op(&maindevice).await.expect("PRE-OP -> OP");
+
+            let servo = group.subdevice(&maindevice, 0).unwrap();
+
+            // Control word and target velocity out, status word, actual position and velocity in
+            assert_eq!(servo.outputs_raw().len(), 2 + 4);
+            assert_eq!(servo.inputs_raw().len(), 2 + 4 + 4);
+        });
+
+        let sdo = |index, sub_index| {
+            net.with_subdevice(0, |subdevice| {
+                subdevice.sdo(index, sub_index).map(<[u8]>::to_vec)
+            })
+            .flatten()
+        };
+
+        assert_eq!(sdo(0x1600, 0), Some(vec![2]));
+        assert_eq!(sdo(0x1600, 1), Some(0x6040_0010u32.to_le_bytes().to_vec()));
+        assert_eq!(sdo(0x1600, 2), Some(0x60ff_0020u32.to_le_bytes().to_vec()));
+        assert_eq!(sdo(0x1a00, 0), Some(vec![3]));
+        assert_eq!(sdo(0x1a00, 3), Some(0x606c_0020u32.to_le_bytes().to_vec()));
+        assert_eq!(sdo(0x6060, 0), Some(vec![9]));
+    }
+
+    #[test]
+    fn object_access() {
+        use crate::coe::abort_code::CoeAbortCode;
+
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = SimNetwork::new([servo()]);
+
+        fn aborted<T>(code: CoeAbortCode, address: u16, sub_index: u8) -> Result<T, Error> {
+            Err(Error::Mailbox(MailboxError::Aborted {
+                code,
+                address,
+                sub_index,
+            }))
+        }
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            {
+                let servo = group.subdevice(&maindevice, 0).unwrap();
+
+                assert_eq!(
+                    servo
+                        .sdo_write(0x6041, 0, 1u16)
+                        .await
+                        .map_err(|e| e.context()),
+                    Err(Some(ErrorContext::new(
+                        servo.configured_address(),
+                        Operation::SdoWrite {
+                            index: 0x6041,
+                            sub_index: 0
+                        }
+                    )))
+                );
+                assert_eq!(
+                    servo
+                        .sdo_write(0x6041, 0, 1u16)
+                        .await
+                        .map_err(|e| e.cause()),
+                    aborted(CoeAbortCode::ReadOnlyWrite, 0x6041, 0)
+                );
+                assert_eq!(
+                    servo
+                        .sdo_read::<u16>(0x2000, 0)
+                        .await
+                        .map_err(|e| e.cause()),
+                    aborted(CoeAbortCode::WriteOnlyRead, 0x2000, 0)
+                );
+                assert_eq!(servo.sdo_write(0x2000, 0, 1u16).await, Ok(()));
+                assert_eq!(
+                    servo
+                        .sdo_write(0x60ff, 0, 1u16)
+                        .await
+                        .map_err(|e| e.cause()),
+                    aborted(CoeAbortCode::DataLengthMismatch, 0x60ff, 0)
+                );
+
+                // Mapped objects must exist and have the same length as the mapping entry
+                assert_eq!(
+                    servo
+                        .sdo_write(0x1600, 1, 0x6040_0020u32)
+                        .await
+                        .map_err(|e| e.cause()),
+                    aborted(CoeAbortCode::PdoMappingFailed, 0x1600, 1)
+                );
+                assert_eq!(
+                    servo
+                        .sdo_write(0x1600, 1, 0x6050_0010u32)
+                        .await
+                        .map_err(|e| e.cause()),
+                    aborted(CoeAbortCode::PdoMappingFailed, 0x1600, 1)
+                );
+                assert_eq!(
+                    servo.sdo_write(0x1600, 0, 5u8).await.map_err(|e| e.cause()),
+                    aborted(CoeAbortCode::ValueTooLarge, 0x1600, 0)
+                );
+                assert_eq!(
+                    servo
+                        .sdo_write(0x1c12, 1, 0x1601u16)
+                        .await
+                        .map_err(|e| e.cause()),
+                    aborted(CoeAbortCode::PdoMappingFailed, 0x1c12, 1)
+                );
+                // The default mapping of the output bytes
+                assert_eq!(servo.sdo_write(0x1600, 1, 0x7000_0108u32).await, Ok(()));
+            }
+
+            let group = group.into_op(&maindevice).await.expect("PRE-OP -> OP");
+
+            let servo = group.subdevice(&maindevice, 0).unwrap();
+
+            assert_eq!(
+                servo.sdo_write(0x1600, 0, 0u8).await.map_err(|e| e.cause()),
+                aborted(CoeAbortCode::InvalidState, 0x1600, 0)
+            );
+            assert_eq!(servo.sdo_write(0x60ff, 0, 100i32).await, Ok(()));
+        });
+    }
+
+    fn init_transcript<const N: usize, const DATA: usize>(
+        storage: &PduStorage<N, DATA>,
+    ) -> Transcript {
+        let network = network();
+
+        network.record_transcript();
+
+        run(&network, storage, |maindevice| async move {
+            maindevice
+                .init_single_group::<8, 32>(ethercat_now)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+        });
+
+        network.take_transcript()
+    }
+
+    #[test]
+    fn golden_init_transcript() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let transcript = init_transcript(&STORAGE);
+
+        // Each SubDevice increments the address of broadcast PDUs
+        assert_eq!(
+            transcript.lines().nth(1),
+            Some("BWR(addr 0x0004, reg 0x0120) AL control, 2 bytes, WKC 4: Init (ack)")
+        );
+
+        transcript.assert_matches(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/transcripts/sim-init.txt"
+        ));
+    }
+
+    #[test]
+    fn transcript_mismatch() {
+        static STORAGE_A: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+        static STORAGE_B: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        // Transcripts are written instead of compared when updating golden files
+        if std::env::var_os(UPDATE_TRANSCRIPTS_VAR).is_some() {
+            return;
+        }
+
+        let golden = std::env::temp_dir().join(format!(
+            "ethercrab-transcript-mismatch-{}.txt",
+            std::process::id()
+        ));
+
+        let mut lines = init_transcript(&STORAGE_A)
+            .lines()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        // Swap two PDUs, as if a refactor reordered register writes
+        lines.swap(10, 11);
+
+        std::fs::write(&golden, lines.join("\n")).unwrap();
+
+        let result =
+            std::panic::catch_unwind(|| init_transcript(&STORAGE_B).assert_matches(&golden));
+
+        std::fs::remove_file(&golden).unwrap();
+
+        let message = result.expect_err("transcripts should differ");
+        let message = message.downcast_ref::<String>().unwrap();
+
+        assert!(message.contains("first difference at line 11"), "{message}");
+    }
+}
@@ -0,0 +1,1191 @@
+//! A simulated EtherCAT SubDevice Controller (ESC) and the application behind it.
+
+use super::{
+    coe::{self, ObjectDictionary, SdoAccess, SimObject},
+    eeprom::sii_image,
+};
+use crate::{
+    base_data_types::PrimitiveDataType,
+    eeprom::types::{FmmuUsage, SiiAddressSize, SyncManagerType},
+    fmt, AlStatusCode, RegisterAddress, SubDeviceIdentity, SubDeviceState,
+};
+use core::time::Duration;
+use ethercrab_wire::EtherCrabWireWrite;
+use std::{collections::VecDeque, ops::Range};
+
+/// ESC memory size, covering the whole physical address space.
+const MEMORY_LEN: usize = 0x10000;
+
+/// Length of each PDU header: command, index, address, length and IRQ.
+const PDU_HEADER_LEN: usize = 10;
+
+/// Length of the mailboxes in bytes.
+pub(super) const MAILBOX_LEN: u16 = 128;
+
+/// Length of the bootstrap mailboxes in bytes.
+pub(super) const BOOTSTRAP_MAILBOX_LEN: u16 = 512;
+
+/// Index of the RxPDO that maps all outputs.
+pub(super) const RX_PDO: u16 = 0x1600;
+/// Index of the TxPDO that maps all inputs.
+pub(super) const TX_PDO: u16 = 0x1a00;
+/// Object that output bytes are mapped from.
+pub(super) const OUTPUT_OBJECT: u16 = 0x7000;
+/// Object that input bytes are mapped from.
+pub(super) const INPUT_OBJECT: u16 = 0x6000;
+
+// Physical start addresses of the mailboxes and process data buffers
+const MAILBOX_WRITE_START: u16 = 0x1000;
+const MAILBOX_READ_START: u16 = 0x1080;
+const OUTPUTS_START: u16 = 0x1100;
+const INPUTS_START: u16 = 0x1400;
+pub(super) const BOOTSTRAP_MAILBOX_WRITE_START: u16 = 0x1800;
+pub(super) const BOOTSTRAP_MAILBOX_READ_START: u16 = 0x1a00;
+
+// Sync manager control bytes, ETG1000.4 Table 59
+const SM_CONTROL_MAILBOX_WRITE: u8 = 0x26;
+const SM_CONTROL_MAILBOX_READ: u8 = 0x22;
+const SM_CONTROL_OUTPUTS: u8 = 0x64;
+const SM_CONTROL_INPUTS: u8 = 0x20;
+
+/// Sync manager status bit set when a mailbox is full.
+const SM_STATUS_MAILBOX_FULL: u8 = 0x08;
+
+/// AL status flag set when a state transition failed or an error occurred.
+const AL_STATUS_ERROR: u8 = 0x10;
+
+fn register(register: RegisterAddress) -> usize {
+    usize::from(u16::from(register))
+}
+
+/// Registers the MainDevice cannot write: ESC information, DL status, AL status, watchdog status
+/// and sync manager status.
+fn is_read_only(address: usize) -> bool {
+    matches!(
+        address,
+        0x0000..=0x000f | 0x0110..=0x0111 | 0x0130..=0x0135 | 0x0440..=0x0441
+    ) || ((0x0800..0x0880).contains(&address) && address % 8 == 5)
+}
+
+/// Distributed clock registers, which the simulated ESC does not implement.
+fn is_unimplemented(range: &Range<usize>) -> bool {
+    range.start >= 0x0900 && range.end <= 0x0a00
+}
+
+fn overlaps(a: &Range<usize>, b: Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// Current virtual time, or zero outside [`block_on`](super::block_on).
+fn now() -> Duration {
+    super::time::now().unwrap_or_default()
+}
+
+/// Memory access of a PDU.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    fn reads(self) -> bool {
+        self != Access::Write
+    }
+
+    fn writes(self) -> bool {
+        self != Access::Read
+    }
+}
+
+/// A sync manager described in the simulated SubDevice's EEPROM.
+#[derive(Debug, Copy, Clone)]
+pub(super) struct SimSyncManager {
+    pub start: u16,
+    pub len: u16,
+    pub control: u8,
+    pub usage: SyncManagerType,
+}
+
+/// A sync manager as currently configured in the ESC registers.
+#[derive(Debug, Copy, Clone)]
+struct SyncManagerRegister {
+    start: u16,
+    len: u16,
+    control: u8,
+    status: u8,
+    active: bool,
+}
+
+impl SyncManagerRegister {
+    fn is_mailbox(&self) -> bool {
+        self.active && self.len > 0 && self.control & 0x03 == 0x02
+    }
+
+    /// Whether the MainDevice writes into this sync manager's buffer.
+    fn is_write(&self) -> bool {
+        self.control & 0x0c == 0x04
+    }
+
+    /// Whether writes to this sync manager trigger the process data watchdog.
+    fn triggers_watchdog(&self) -> bool {
+        self.active && self.len > 0 && self.control & 0x40 != 0
+    }
+
+    fn last_byte(&self) -> usize {
+        usize::from(self.start) + usize::from(self.len) - 1
+    }
+}
+
+/// A simulated SubDevice, for use in a [`SimNetwork`](crate::sim::SimNetwork).
+///
+/// The SubDevice has no process data or mailbox by default, like an EK1100 coupler. Byte-wide
+/// inputs and outputs can be added with [`with_inputs`](SimSubDevice::with_inputs) and
+/// [`with_outputs`](SimSubDevice::with_outputs), a CoE mailbox with
+/// [`with_coe`](SimSubDevice::with_coe) and BOOT state support with
+/// [`with_bootstrap`](SimSubDevice::with_bootstrap).
+///
+/// # Examples
+///
+/// ```rust
+/// use ethercrab::{sim::SimSubDevice, SubDeviceIdentity};
+///
+/// let subdevice = SimSubDevice::new(
+///     "EL2004",
+///     SubDeviceIdentity {
+///         vendor_id: 0x2,
+///         product_id: 0x07d43052,
+///         revision: 0x00100000,
+///         serial: 0,
+///     },
+/// )
+/// .with_outputs(1);
+/// ```
+pub struct SimSubDevice {
+    name: String,
+    identity: SubDeviceIdentity,
+    inputs: u8,
+    outputs: u8,
+    coe: bool,
+    bootstrap: bool,
+    pdo_entries: u8,
+    objects: ObjectDictionary,
+    memory: Box<[u8]>,
+    eeprom: Vec<u8>,
+    mailbox_responses: VecDeque<Vec<u8>>,
+    mailbox_delay: Duration,
+    /// When the next mailbox response may be put into the read mailbox, in virtual time.
+    next_response_at: Duration,
+    refused_transitions: u8,
+    refusal_code: AlStatusCode,
+    /// Error code and error register of an emergency to send instead of the next mailbox
+    /// response.
+    emergency: Option<(u16, u8)>,
+}
+
+impl core::fmt::Debug for SimSubDevice {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SimSubDevice")
+            .field("name", &self.name)
+            .field("identity", &self.identity)
+            .field("state", &self.state())
+            .field("status_code", &self.status_code())
+            .finish()
+    }
+}
+
+impl SimSubDevice {
+    /// Create a SubDevice with the given name and identity, and no process data or mailbox.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if `name` is longer than 255 bytes.
+    pub fn new(name: &str, identity: SubDeviceIdentity) -> Self {
+        assert!(name.len() <= 255, "SubDevice name is too long");
+
+        Self {
+            name: name.to_string(),
+            identity,
+            inputs: 0,
+            outputs: 0,
+            coe: false,
+            bootstrap: false,
+            pdo_entries: 0,
+            objects: ObjectDictionary::default(),
+            memory: vec![0u8; MEMORY_LEN].into_boxed_slice(),
+            eeprom: Vec::new(),
+            mailbox_responses: VecDeque::new(),
+            mailbox_delay: Duration::ZERO,
+            next_response_at: Duration::ZERO,
+            refused_transitions: 0,
+            refusal_code: AlStatusCode::NoError,
+            emergency: None,
+        }
+    }
+
+    /// Map `len` bytes of inputs (SubDevice to MainDevice) into the PDI.
+    pub fn with_inputs(mut self, len: u8) -> Self {
+        self.inputs = len;
+
+        self
+    }
+
+    /// Map `len` bytes of outputs (MainDevice to SubDevice) into the PDI.
+    pub fn with_outputs(mut self, len: u8) -> Self {
+        self.outputs = len;
+
+        self
+    }
+
+    /// Add a mailbox supporting CoE SDO uploads and downloads, and SDO Information requests.
+    ///
+    /// The SubDevice then reports its PDO assignment and mapping over CoE, in objects `0x1c12`,
+    /// `0x1c13`, `0x1600` and `0x1a00`. Writing to these in PRE-OP changes the PDI length. Each
+    /// input and output byte is mapped from object `0x6000` or `0x7000` respectively, with the byte
+    /// number as the sub index. The identity is available in read-only object `0x1018`.
+    pub fn with_coe(mut self) -> Self {
+        self.coe = true;
+
+        self
+    }
+
+    /// Support the BOOT state, using a bootstrap mailbox with a different location and length to
+    /// the standard mailbox.
+    ///
+    /// This has no effect unless the SubDevice also has a mailbox, added with
+    /// [`with_coe`](SimSubDevice::with_coe).
+    pub fn with_bootstrap(mut self) -> Self {
+        self.bootstrap = true;
+
+        self
+    }
+
+    /// Take `delay` to respond to each mailbox request, like a SubDevice whose application is slow
+    /// to process SDO requests.
+    ///
+    /// The delay is measured in virtual time, so has no effect outside [`block_on`](super::block_on).
+    pub fn with_mailbox_delay(mut self, delay: Duration) -> Self {
+        self.mailbox_delay = delay;
+
+        self
+    }
+
+    /// Allow up to `count` objects to be mapped into each of the PDOs `0x1600` and `0x1a00`, to
+    /// test dynamic PDO mapping.
+    ///
+    /// Unused mapping entries are zero. Entries written by the MainDevice must refer to objects in
+    /// the object dictionary with the same bit length, which can be added with
+    /// [`with_object`](SimSubDevice::with_object).
+    pub fn with_pdo_entries(mut self, count: u8) -> Self {
+        self.pdo_entries = count;
+
+        self
+    }
+
+    /// Declare an object in the SubDevice's object dictionary with its type, default value and
+    /// access rules, or replace a default one.
+    ///
+    /// Objects are only accessible by the MainDevice if the SubDevice has a CoE mailbox.
+    ///
+    /// # Examples
+    ///
+    /// Test a DS402 PDO mapping by declaring the objects it maps:
+    ///
+    /// ```rust
+    /// use ethercrab::{
+    ///     sii::PrimitiveDataType,
+    ///     sim::{SdoAccess, SimObject, SimSubDevice},
+    ///     SubDeviceIdentity,
+    /// };
+    ///
+    /// let identity = SubDeviceIdentity {
+    ///     vendor_id: 0x0000_00a9,
+    ///     product_id: 0x0000_0001,
+    ///     revision: 0,
+    ///     serial: 0,
+    /// };
+    ///
+    /// let drive = SimSubDevice::new("Servo", identity)
+    ///     .with_coe()
+    ///     .with_pdo_entries(4)
+    ///     // Control word
+    ///     .with_object(0x6040, 0, SimObject::new(PrimitiveDataType::U16, 0u16))
+    ///     // Status word
+    ///     .with_object(
+    ///         0x6041,
+    ///         0,
+    ///         SimObject::new(PrimitiveDataType::U16, 0u16).with_access(SdoAccess::ReadOnly),
+    ///     );
+    /// ```
+    pub fn with_object(mut self, index: u16, sub_index: u8, object: SimObject) -> Self {
+        self.objects.declare(index, sub_index, object);
+
+        self
+    }
+
+    /// Add an object to the SubDevice's object dictionary, or replace a default one.
+    ///
+    /// The object has an unknown type, and is read-write unless it is a PDO mapping or assignment
+    /// object, which are only writable in PRE-OP. Use [`with_object`](SimSubDevice::with_object)
+    /// to declare its type and access rules.
+    ///
+    /// Objects are only accessible by the MainDevice if the SubDevice has a CoE mailbox.
+    pub fn with_sdo(mut self, index: u16, sub_index: u8, value: impl EtherCrabWireWrite) -> Self {
+        self.set_sdo(index, sub_index, value);
+
+        self
+    }
+
+    /// The SubDevice name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The SubDevice identity.
+    pub fn identity(&self) -> SubDeviceIdentity {
+        self.identity
+    }
+
+    /// The current AL state.
+    pub fn state(&self) -> SubDeviceState {
+        SubDeviceState::from(self.memory[register(RegisterAddress::AlStatus)] & 0x0f)
+    }
+
+    /// The AL status code describing the last error, if any.
+    pub fn status_code(&self) -> AlStatusCode {
+        AlStatusCode::from(self.register_u16(RegisterAddress::AlStatusCode.into()))
+    }
+
+    /// The outputs last written by the MainDevice.
+    pub fn outputs(&self) -> &[u8] {
+        &self.memory[usize::from(OUTPUTS_START)..][..usize::from(self.output_len())]
+    }
+
+    /// The inputs that will be read by the MainDevice.
+    pub fn inputs_mut(&mut self) -> &mut [u8] {
+        let len = usize::from(self.input_len());
+
+        &mut self.memory[usize::from(INPUTS_START)..][..len]
+    }
+
+    /// Read the raw value of an object from the object dictionary.
+    pub fn sdo(&self, index: u16, sub_index: u8) -> Option<&[u8]> {
+        self.objects.get(index, sub_index)
+    }
+
+    /// Set the value of an object in the object dictionary, e.g. to simulate a changing input.
+    ///
+    /// The type and access rules of an existing object are kept, and are not checked.
+    pub fn set_sdo(&mut self, index: u16, sub_index: u8, value: impl EtherCrabWireWrite) {
+        let mut buf = vec![0u8; value.packed_len()];
+
+        value.pack_to_slice_unchecked(&mut buf);
+
+        self.objects.insert(index, sub_index, buf);
+    }
+
+    /// Simulate an application error, e.g. a lost sync signal.
+    ///
+    /// The SubDevice falls back to SAFE-OP if it is in OP, and sets its AL status error flag and
+    /// status code until the MainDevice acknowledges the error.
+    pub fn fault(&mut self, code: AlStatusCode) {
+        let state = match self.state() {
+            SubDeviceState::Op => SubDeviceState::SafeOp,
+            state => state,
+        };
+
+        self.set_al_status(state, true, code);
+    }
+
+    /// Refuse the next `count` requests to change to a state other than INIT, e.g. to simulate an
+    /// application that is not ready yet.
+    ///
+    /// Each refused request sets the AL status error flag and `code`, which must be acknowledged by
+    /// the MainDevice before another request is accepted.
+    pub fn refuse_transitions(&mut self, count: u8, code: AlStatusCode) {
+        self.refused_transitions = count;
+        self.refusal_code = code;
+    }
+
+    /// Send a CoE emergency message with the given error code and error register in place of the
+    /// response to the next mailbox request, e.g. to simulate a drive reporting a fault.
+    pub fn emergency(&mut self, error_code: u16, error_register: u8) {
+        self.emergency = Some((error_code, error_register));
+    }
+
+    /// Simulate a corrupted frame arriving on `port`, incrementing its RX error counter.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if `port` is greater than 3.
+    pub fn rx_error(&mut self, port: usize) {
+        assert!(port < 4, "SubDevices have at most 4 ports");
+
+        let counter = &mut self.memory[register(RegisterAddress::RxErrorCounter) + port * 2 + 1];
+
+        *counter = counter.saturating_add(1);
+    }
+
+    /// Simulate the process data watchdog expiring because no outputs were written within the
+    /// watchdog timeout.
+    pub fn expire_sm_watchdog(&mut self) {
+        self.memory[register(RegisterAddress::SyncManagerWatchdogStatus)] = 0x00;
+
+        let counter = &mut self.memory[register(RegisterAddress::SyncManagerWatchdogCounter)];
+
+        *counter = counter.saturating_add(1);
+    }
+
+    pub(super) fn has_coe(&self) -> bool {
+        self.coe
+    }
+
+    pub(super) fn has_bootstrap(&self) -> bool {
+        self.coe && self.bootstrap
+    }
+
+    /// Sync managers as described in the EEPROM.
+    pub(super) fn sync_managers(&self) -> Vec<SimSyncManager> {
+        let mailboxes = [
+            SimSyncManager {
+                start: MAILBOX_WRITE_START,
+                len: MAILBOX_LEN,
+                control: SM_CONTROL_MAILBOX_WRITE,
+                usage: SyncManagerType::MailboxWrite,
+            },
+            SimSyncManager {
+                start: MAILBOX_READ_START,
+                len: MAILBOX_LEN,
+                control: SM_CONTROL_MAILBOX_READ,
+                usage: SyncManagerType::MailboxRead,
+            },
+        ];
+
+        let process_data = [
+            SimSyncManager {
+                start: OUTPUTS_START,
+                len: self.outputs.into(),
+                control: SM_CONTROL_OUTPUTS,
+                usage: SyncManagerType::ProcessDataWrite,
+            },
+            SimSyncManager {
+                start: INPUTS_START,
+                len: self.inputs.into(),
+                control: SM_CONTROL_INPUTS,
+                usage: SyncManagerType::ProcessDataRead,
+            },
+        ];
+
+        // CoE SubDevices use fixed SM indices so the PDO assignment objects 0x1c12 and 0x1c13
+        // always refer to SM2 and SM3.
+        if self.coe {
+            mailboxes.into_iter().chain(process_data).collect()
+        } else {
+            process_data.into_iter().filter(|sm| sm.len > 0).collect()
+        }
+    }
+
+    /// FMMU usage as described in the EEPROM.
+    pub(super) fn fmmus(&self) -> Vec<FmmuUsage> {
+        [
+            (self.outputs, FmmuUsage::Outputs),
+            (self.inputs, FmmuUsage::Inputs),
+        ]
+        .into_iter()
+        .filter(|(len, _)| self.coe || *len > 0)
+        .map(|(_, usage)| usage)
+        .collect()
+    }
+
+    /// Current output length in bytes, taking PDO assignment changes over CoE into account.
+    fn output_len(&self) -> u16 {
+        if self.coe {
+            self.objects.assigned_bit_len(0x1c12).div_ceil(8)
+        } else {
+            self.outputs.into()
+        }
+    }
+
+    /// Current input length in bytes, taking PDO assignment changes over CoE into account.
+    fn input_len(&self) -> u16 {
+        if self.coe {
+            self.objects.assigned_bit_len(0x1c13).div_ceil(8)
+        } else {
+            self.inputs.into()
+        }
+    }
+
+    /// Reset the ESC as if it was just powered on.
+    ///
+    /// `last` is true if the SubDevice is at the end of the network, so has port 1 closed.
+    pub(super) fn power_on(&mut self, last: bool) {
+        self.memory.fill(0);
+        self.mailbox_responses.clear();
+
+        self.eeprom = sii_image(self);
+
+        if self.coe {
+            self.default_objects();
+        }
+
+        // ESC type, FMMU count, SM count, RAM size in KiB and port descriptor
+        self.memory[0x0000] = 0x11;
+        self.memory[0x0004..0x0008].copy_from_slice(&[8, 8, 8, 0x0f]);
+
+        self.set_last(last);
+
+        // 8 byte SII reads
+        self.memory[register(RegisterAddress::SiiControl)] = 0x40;
+
+        // Default watchdog divider of 100us and 100ms watchdog timeouts
+        self.set_register_u16(RegisterAddress::WatchdogDivider.into(), 2498);
+        self.set_register_u16(RegisterAddress::PdiWatchdog.into(), 1000);
+        self.set_register_u16(RegisterAddress::SyncManagerWatchdog.into(), 1000);
+
+        self.set_al_status(SubDeviceState::Init, false, AlStatusCode::NoError);
+    }
+
+    /// Update the DL status register for whether anything is connected to port 1.
+    pub(super) fn set_last(&mut self, last: bool) {
+        // PDI operational, port 0 link and communication, ports 2 and 3 closed
+        let mut dl_status = 0x0001 | 0x0010 | 0x0200 | 0x1000 | 0x4000;
+
+        dl_status |= if last {
+            // Port 1 closed
+            0x0400
+        } else {
+            // Port 1 link and communication
+            0x0020 | 0x0800
+        };
+
+        self.set_register_u16(RegisterAddress::DlStatus.into(), dl_status);
+    }
+
+    fn default_objects(&mut self) {
+        use PrimitiveDataType::{Unknown, U16, U32, U8};
+
+        let identity = self.identity;
+        let objects = &mut self.objects;
+
+        let read_only = |object: SimObject| object.with_access(SdoAccess::ReadOnly);
+        let pre_op = |object: SimObject| object.with_access(SdoAccess::ReadWritePreOp);
+
+        objects.insert_default(0x1000, 0, read_only(SimObject::new(U32, 0u32)));
+        objects.insert_default(
+            0x1008,
+            0,
+            read_only(SimObject::new(Unknown, self.name.as_bytes())),
+        );
+
+        objects.insert_default(0x1018, 0, read_only(SimObject::new(U8, 4u8)));
+
+        for (sub_index, value) in [
+            identity.vendor_id,
+            identity.product_id,
+            identity.revision,
+            identity.serial,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            objects.insert_default(
+                0x1018,
+                sub_index as u8 + 1,
+                read_only(SimObject::new(U32, value)),
+            );
+        }
+
+        objects.insert_default(0x1c00, 0, read_only(SimObject::new(U8, 4u8)));
+
+        for (sub_index, sm) in [
+            SyncManagerType::MailboxWrite,
+            SyncManagerType::MailboxRead,
+            SyncManagerType::ProcessDataWrite,
+            SyncManagerType::ProcessDataRead,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            objects.insert_default(
+                0x1c00,
+                sub_index as u8 + 1,
+                read_only(SimObject::new(U8, u8::from(sm))),
+            );
+        }
+
+        for (assign, pdo, object, len) in [
+            (0x1c12, RX_PDO, OUTPUT_OBJECT, self.outputs),
+            (0x1c13, TX_PDO, INPUT_OBJECT, self.inputs),
+        ] {
+            objects.insert_default(assign, 0, pre_op(SimObject::new(U8, 1u8)));
+            objects.insert_default(assign, 1, pre_op(SimObject::new(U16, pdo)));
+
+            objects.insert_default(pdo, 0, pre_op(SimObject::new(U8, len)));
+
+            for sub_index in 1..=len.max(self.pdo_entries) {
+                // Bit length, sub index and index of the mapped object
+                let mapping = if sub_index <= len {
+                    u32::from(object) << 16 | u32::from(sub_index) << 8 | 8
+                } else {
+                    0
+                };
+
+                objects.insert_default(pdo, sub_index, pre_op(SimObject::new(U32, mapping)));
+            }
+
+            // Inputs are written by the application, not the MainDevice
+            let access = if object == INPUT_OBJECT {
+                SdoAccess::ReadOnly
+            } else {
+                SdoAccess::ReadWrite
+            };
+
+            for sub_index in 1..=len {
+                objects.insert_default(
+                    object,
+                    sub_index,
+                    SimObject::new(U8, 0u8).with_access(access),
+                );
+            }
+        }
+    }
+
+    fn register_u16(&self, address: u16) -> u16 {
+        let address = usize::from(address);
+
+        u16::from_le_bytes([self.memory[address], self.memory[address + 1]])
+    }
+
+    fn register_u32(&self, address: u16) -> u32 {
+        let address = usize::from(address);
+
+        u32::from_le_bytes([
+            self.memory[address],
+            self.memory[address + 1],
+            self.memory[address + 2],
+            self.memory[address + 3],
+        ])
+    }
+
+    fn set_register_u16(&mut self, address: u16, value: u16) {
+        self.memory[usize::from(address)..][..2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn sync_manager(&self, index: usize) -> SyncManagerRegister {
+        let base = register(RegisterAddress::Sm0) + index * 8;
+        let sm = &self.memory[base..][..8];
+
+        SyncManagerRegister {
+            start: u16::from_le_bytes([sm[0], sm[1]]),
+            len: u16::from_le_bytes([sm[2], sm[3]]),
+            control: sm[4],
+            status: sm[5],
+            active: sm[6] & 0x01 != 0,
+        }
+    }
+
+    fn set_sync_manager_status(&mut self, index: usize, status: u8) {
+        self.memory[usize::from(RegisterAddress::sync_manager_status(index as u8))] = status;
+    }
+
+    fn set_al_status(&mut self, state: SubDeviceState, error: bool, code: AlStatusCode) {
+        let error = if error { AL_STATUS_ERROR } else { 0 };
+
+        self.memory[register(RegisterAddress::AlStatus)] = u8::from(state) | error;
+        self.set_register_u16(RegisterAddress::AlStatusCode.into(), code.into());
+    }
+
+    /// Process every PDU in an EtherCAT frame, as it passes through the SubDevice.
+    ///
+    /// `frame` starts with the EtherCAT frame header.
+    pub(super) fn process_frame(&mut self, frame: &mut [u8]) {
+        // A delayed mailbox response may be ready by now
+        self.deliver_mailbox_response();
+
+        let mut pdus = frame.get_mut(2..).unwrap_or_default();
+
+        loop {
+            if pdus.len() < PDU_HEADER_LEN {
+                break;
+            }
+
+            let (header, rest) = core::mem::take(&mut pdus).split_at_mut(PDU_HEADER_LEN);
+
+            let flags = u16::from_le_bytes([header[6], header[7]]);
+            let len = usize::from(flags & 0x07ff);
+            let more_follows = flags & 0x8000 != 0;
+
+            if rest.len() < len + 2 {
+                fmt::warn!("Simulated SubDevice received truncated PDU");
+
+                break;
+            }
+
+            let (data, rest) = rest.split_at_mut(len);
+            let (wkc, rest) = rest.split_at_mut(2);
+
+            let mut working_counter = u16::from_le_bytes([wkc[0], wkc[1]]);
+
+            let (command, address) = header.split_at_mut(2);
+
+            self.process_pdu(command[0], &mut address[0..4], data, &mut working_counter);
+
+            wkc.copy_from_slice(&working_counter.to_le_bytes());
+
+            if !more_follows {
+                break;
+            }
+
+            pdus = rest;
+        }
+    }
+
+    fn process_pdu(&mut self, command: u8, address: &mut [u8], data: &mut [u8], wkc: &mut u16) {
+        let adp = u16::from_le_bytes([address[0], address[1]]);
+        let ado = u16::from_le_bytes([address[2], address[3]]);
+
+        let configured_address =
+            self.register_u16(RegisterAddress::ConfiguredStationAddress.into());
+
+        let access = match command {
+            0x01 | 0x04 | 0x07 | 0x0a => Access::Read,
+            0x02 | 0x05 | 0x08 | 0x0b => Access::Write,
+            _ => Access::ReadWrite,
+        };
+
+        match command {
+            // APRD, APWR, APRW
+            0x01..=0x03 => {
+                if adp == 0 {
+                    self.physical(access, ado, data, false, wkc);
+                }
+            }
+            // FPRD, FPWR, FPRW
+            0x04..=0x06 => {
+                if adp == configured_address {
+                    self.physical(access, ado, data, false, wkc);
+                }
+            }
+            // BRD, BWR, BRW
+            0x07..=0x09 => self.physical(access, ado, data, true, wkc),
+            // LRD, LWR, LRW
+            0x0a..=0x0c => self.logical(
+                access,
+                u32::from_le_bytes([address[0], address[1], address[2], address[3]]),
+                data,
+                wkc,
+            ),
+            // ARMW
+            0x0d => {
+                let access = if adp == 0 {
+                    Access::Read
+                } else {
+                    Access::Write
+                };
+
+                self.physical(access, ado, data, false, wkc);
+            }
+            // FRMW
+            0x0e => {
+                let access = if adp == configured_address {
+                    Access::Read
+                } else {
+                    Access::Write
+                };
+
+                self.physical(access, ado, data, false, wkc);
+            }
+            // NOP or unknown command
+            _ => (),
+        }
+
+        // Auto increment and broadcast addresses are incremented by every SubDevice
+        if matches!(command, 0x01..=0x03 | 0x07..=0x09 | 0x0d) {
+            address[0..2].copy_from_slice(&adp.wrapping_add(1).to_le_bytes());
+        }
+    }
+
+    fn physical(
+        &mut self,
+        access: Access,
+        address: u16,
+        data: &mut [u8],
+        broadcast: bool,
+        wkc: &mut u16,
+    ) {
+        let start = usize::from(address);
+        let range = start..(start + data.len()).min(MEMORY_LEN);
+
+        if is_unimplemented(&range) {
+            return;
+        }
+
+        let data = &mut data[0..range.len()];
+
+        match access {
+            Access::Read => {
+                self.read_memory(range, data, broadcast);
+
+                *wkc = wkc.wrapping_add(1);
+            }
+            Access::Write => {
+                self.write_memory(start, data);
+
+                *wkc = wkc.wrapping_add(1);
+            }
+            Access::ReadWrite => {
+                let written = data.to_vec();
+
+                self.read_memory(range, data, broadcast);
+                self.write_memory(start, &written);
+
+                *wkc = wkc.wrapping_add(3);
+            }
+        }
+    }
+
+    fn logical(&mut self, access: Access, address: u32, data: &mut [u8], wkc: &mut u16) {
+        // Process data sync managers are only enabled by the application in SAFE-OP and OP
+        if !matches!(self.state(), SubDeviceState::SafeOp | SubDeviceState::Op) {
+            return;
+        }
+
+        let address = u64::from(address);
+        let mut read = false;
+        let mut written = false;
+
+        for index in 0..16 {
+            let base = register(RegisterAddress::Fmmu0) + index * 16;
+            let fmmu = &self.memory[base..][..16];
+
+            if fmmu[12] & 0x01 == 0 {
+                continue;
+            }
+
+            let logical_start = u64::from(u32::from_le_bytes([fmmu[0], fmmu[1], fmmu[2], fmmu[3]]));
+            let len = u64::from(u16::from_le_bytes([fmmu[4], fmmu[5]]));
+            let physical_start = usize::from(u16::from_le_bytes([fmmu[8], fmmu[9]]));
+            let (fmmu_reads, fmmu_writes) = (fmmu[11] & 0x01 != 0, fmmu[11] & 0x02 != 0);
+
+            let start = address.max(logical_start);
+            let end = (address + data.len() as u64).min(logical_start + len);
+
+            if start >= end {
+                continue;
+            }
+
+            let data = &mut data[(start - address) as usize..(end - address) as usize];
+            let physical = physical_start + (start - logical_start) as usize;
+            let physical = physical..(physical + data.len()).min(MEMORY_LEN);
+            let data = &mut data[0..physical.len()];
+
+            if fmmu_reads && access.reads() {
+                self.read_memory(physical.clone(), data, false);
+
+                read = true;
+            }
+
+            if fmmu_writes && access.writes() {
+                self.write_memory(physical.start, data);
+
+                written = true;
+            }
+        }
+
+        if read {
+            *wkc = wkc.wrapping_add(1);
+        }
+
+        if written {
+            *wkc = wkc.wrapping_add(if access == Access::ReadWrite { 2 } else { 1 });
+        }
+    }
+
+    fn read_memory(&mut self, range: Range<usize>, data: &mut [u8], broadcast: bool) {
+        for (byte, value) in data.iter_mut().zip(&self.memory[range.clone()]) {
+            if broadcast {
+                *byte |= *value;
+            } else {
+                *byte = *value;
+            }
+        }
+
+        // Reading the last byte of a full read mailbox empties it
+        for index in 0..16 {
+            let sm = self.sync_manager(index);
+
+            if sm.is_mailbox()
+                && !sm.is_write()
+                && sm.status & SM_STATUS_MAILBOX_FULL != 0
+                && range.contains(&sm.last_byte())
+            {
+                self.set_sync_manager_status(index, sm.status & !SM_STATUS_MAILBOX_FULL);
+
+                self.deliver_mailbox_response();
+            }
+        }
+    }
+
+    fn write_memory(&mut self, start: usize, data: &[u8]) {
+        let range = start..(start + data.len());
+
+        for (address, byte) in range.clone().zip(data) {
+            if !is_read_only(address) {
+                self.memory[address] = *byte;
+            }
+        }
+
+        if overlaps(&range, register(RegisterAddress::AlControl)..0x0122) {
+            self.al_control();
+        }
+
+        if overlaps(&range, register(RegisterAddress::SiiControl)..0x0504) {
+            self.sii_command();
+        }
+
+        for index in 0..16 {
+            let sm = self.sync_manager(index);
+
+            // Deactivating a sync manager clears its status
+            if !sm.active && sm.status != 0 {
+                self.set_sync_manager_status(index, 0);
+            }
+
+            if sm.is_mailbox() && sm.is_write() && range.contains(&sm.last_byte()) {
+                self.mailbox_written(sm);
+            }
+
+            if sm.triggers_watchdog() && range.contains(&usize::from(sm.start)) {
+                self.memory[register(RegisterAddress::SyncManagerWatchdogStatus)] = 0x01;
+            }
+        }
+    }
+
+    /// The MainDevice wrote a complete request into the write mailbox.
+    fn mailbox_written(&mut self, sm: SyncManagerRegister) {
+        let request = self.memory[usize::from(sm.start)..=sm.last_byte()].to_vec();
+
+        let mailbox_len = (0..16)
+            .map(|index| self.sync_manager(index))
+            .find(|sm| sm.is_mailbox() && !sm.is_write())
+            .map_or(0, |sm| usize::from(sm.len));
+
+        if !self.coe {
+            fmt::warn!("Simulated SubDevice {} has no mailbox", self.name);
+
+            return;
+        }
+
+        let state = self.state();
+
+        let responses = match self.emergency.take() {
+            Some((error_code, error_register)) => {
+                vec![coe::emergency(&request, error_code, error_register)]
+            }
+            None => coe::handle_request(&mut self.objects, state, &request, mailbox_len),
+        };
+
+        if self.mailbox_responses.is_empty() && !responses.is_empty() {
+            self.next_response_at = now() + self.mailbox_delay;
+        }
+
+        self.mailbox_responses.extend(responses);
+
+        // The write mailbox is emptied immediately as the request has been handled
+        self.deliver_mailbox_response();
+    }
+
+    /// Put the next mailbox response into the read mailbox if it is empty.
+    fn deliver_mailbox_response(&mut self) {
+        let Some((index, sm)) = (0..16)
+            .map(|index| (index, self.sync_manager(index)))
+            .find(|(_, sm)| sm.is_mailbox() && !sm.is_write())
+        else {
+            return;
+        };
+
+        if sm.status & SM_STATUS_MAILBOX_FULL != 0 || now() < self.next_response_at {
+            return;
+        }
+
+        let Some(mut response) = self.mailbox_responses.pop_front() else {
+            return;
+        };
+
+        self.next_response_at = now() + self.mailbox_delay;
+
+        response.resize(usize::from(sm.len), 0);
+
+        self.memory[usize::from(sm.start)..=sm.last_byte()].copy_from_slice(&response);
+
+        self.set_sync_manager_status(index, sm.status | SM_STATUS_MAILBOX_FULL);
+    }
+
+    /// Run an EEPROM command written to the SII control register.
+    fn sii_command(&mut self) {
+        let control = self.register_u16(RegisterAddress::SiiControl.into());
+
+        // 8 byte reads, 1 address byte
+        let mut status = 0x0040;
+
+        // Like a real 16 kbit EEPROM, upper address bits are ignored so out of range accesses wrap
+        // around to the start.
+        let word = (self.register_u32(RegisterAddress::SiiAddress.into())
+            % SiiAddressSize::U8.max_words()) as usize;
+
+        // Read
+        if control & 0x0100 != 0 {
+            for (i, byte) in self.memory[register(RegisterAddress::SiiData)..][..8]
+                .iter_mut()
+                .enumerate()
+            {
+                *byte = self.eeprom.get(word * 2 + i).copied().unwrap_or(0xff);
+            }
+        }
+        // Write, which must be enabled in the same frame
+        else if control & 0x0201 == 0x0201 && word * 2 + 2 <= self.eeprom.len() {
+            let data = &self.memory[register(RegisterAddress::SiiData)..][..2];
+
+            self.eeprom[word * 2..][..2].copy_from_slice(data);
+        }
+        // Reload, writes without write access, or writes past the end are not supported
+        else if control & 0x0600 != 0 {
+            fmt::warn!(
+                "Simulated SubDevice {} rejected EEPROM command {:#06x} at word {:#06x}",
+                self.name,
+                control,
+                word
+            );
+
+            // Command error
+            status |= 0x2000;
+        }
+
+        self.set_register_u16(RegisterAddress::SiiControl.into(), status);
+    }
+
+    /// Handle a write to the AL control register.
+    fn al_control(&mut self) {
+        let control = self.memory[register(RegisterAddress::AlControl)];
+        let status = self.memory[register(RegisterAddress::AlStatus)];
+
+        let requested = SubDeviceState::from(control & 0x0f);
+        let acknowledge = control & AL_STATUS_ERROR != 0;
+        let current = self.state();
+
+        if status & AL_STATUS_ERROR != 0 && !acknowledge {
+            fmt::debug!(
+                "Simulated SubDevice {} ignoring request for {} until error is acknowledged",
+                self.name,
+                requested
+            );
+
+            return;
+        }
+
+        let result = match (current, requested) {
+            (_, SubDeviceState::Init) => Ok(()),
+            (current, requested) if current != requested && self.refused_transitions > 0 => {
+                self.refused_transitions -= 1;
+
+                Err(self.refusal_code)
+            }
+            (current, requested) if current == requested => Ok(()),
+            (SubDeviceState::Init, SubDeviceState::PreOp) => self.check_mailboxes(),
+            (SubDeviceState::SafeOp | SubDeviceState::Op, SubDeviceState::PreOp) => Ok(()),
+            (SubDeviceState::PreOp, SubDeviceState::SafeOp) => self.check_process_data(),
+            (SubDeviceState::Op, SubDeviceState::SafeOp) => Ok(()),
+            (SubDeviceState::SafeOp, SubDeviceState::Op) => Ok(()),
+            (SubDeviceState::Init, SubDeviceState::Bootstrap) if self.has_bootstrap() => {
+                self.check_bootstrap_mailboxes()
+            }
+            (_, SubDeviceState::Bootstrap) => Err(AlStatusCode::BootstrapNotSupported),
+            (_, SubDeviceState::PreOp | SubDeviceState::SafeOp | SubDeviceState::Op) => {
+                Err(AlStatusCode::InvalidRequestedStateChange)
+            }
+            _ => Err(AlStatusCode::UnknownRequestedState),
+        };
+
+        match result {
+            Ok(()) => {
+                if requested == SubDeviceState::Init {
+                    self.mailbox_responses.clear();
+                }
+
+                self.set_al_status(requested, false, AlStatusCode::NoError);
+            }
+            Err(code) => {
+                fmt::debug!(
+                    "Simulated SubDevice {} failed to transition from {} to {}: {}",
+                    self.name,
+                    current,
+                    requested,
+                    code
+                );
+
+                self.set_al_status(current, true, code);
+            }
+        }
+    }
+
+    /// Check the mailbox sync managers before entering PRE-OP.
+    fn check_mailboxes(&self) -> Result<(), AlStatusCode> {
+        if !self.coe {
+            return Ok(());
+        }
+
+        for (index, expected) in self.sync_managers().iter().enumerate().take(2) {
+            let sm = self.sync_manager(index);
+
+            if !sm.active || sm.start != expected.start || sm.len != expected.len {
+                return Err(AlStatusCode::InvalidMailboxConfiguration2);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the mailbox sync managers use the bootstrap layout before entering BOOT.
+    fn check_bootstrap_mailboxes(&self) -> Result<(), AlStatusCode> {
+        for (index, start) in [BOOTSTRAP_MAILBOX_WRITE_START, BOOTSTRAP_MAILBOX_READ_START]
+            .into_iter()
+            .enumerate()
+        {
+            let sm = self.sync_manager(index);
+
+            if !sm.active || sm.start != start || sm.len != BOOTSTRAP_MAILBOX_LEN {
+                return Err(AlStatusCode::InvalidMailboxConfiguration);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the process data sync managers match the PDO mapping before entering SAFE-OP.
+    fn check_process_data(&self) -> Result<(), AlStatusCode> {
+        for (index, expected) in self.sync_managers().iter().enumerate() {
+            let (len, code) = match expected.usage {
+                SyncManagerType::ProcessDataWrite => {
+                    (self.output_len(), AlStatusCode::InvalidOutputConfiguration)
+                }
+                SyncManagerType::ProcessDataRead => {
+                    (self.input_len(), AlStatusCode::InvalidInputConfiguration)
+                }
+                _ => continue,
+            };
+
+            if len == 0 {
+                continue;
+            }
+
+            let sm = self.sync_manager(index);
+
+            if !sm.active || sm.start != expected.start || sm.len != len {
+                return Err(code);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+//! Virtual time, so timeouts can be tested without waiting for them.
+
+use core::{
+    future::Future,
+    pin::{pin, Pin},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::Wake,
+    thread::{self, Thread},
+};
+
+#[derive(Default)]
+struct Clock {
+    now: Duration,
+    /// Wakers of pending timers, keyed by deadline and a unique timer ID.
+    timers: BTreeMap<(Duration, u64), Waker>,
+    next_id: u64,
+}
+
+thread_local! {
+    static CLOCK: RefCell<Option<Clock>> = const { RefCell::new(None) };
+}
+
+/// Current virtual time, or `None` if not running in [`block_on`].
+pub(crate) fn now() -> Option<Duration> {
+    CLOCK.with_borrow(|clock| clock.as_ref().map(|clock| clock.now))
+}
+
+/// Create a timer measured in virtual time, or `None` if not running in [`block_on`].
+pub(crate) fn timer(duration: Duration) -> Option<VirtualTimer> {
+    now().map(|now| VirtualTimer {
+        deadline: now.saturating_add(duration),
+        id: None,
+    })
+}
+
+/// A timer that completes once virtual time passes a deadline.
+#[derive(Debug)]
+pub(crate) struct VirtualTimer {
+    deadline: Duration,
+    /// ID of the timer's registered waker, if any.
+    id: Option<u64>,
+}
+
+impl Future for VirtualTimer {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        CLOCK.with_borrow_mut(|clock| {
+            let clock = clock
+                .as_mut()
+                .expect("virtual timer polled outside sim::block_on");
+
+            if clock.now >= this.deadline {
+                return Poll::Ready(());
+            }
+
+            let id = *this.id.get_or_insert_with(|| {
+                clock.next_id += 1;
+
+                clock.next_id
+            });
+
+            clock.timers.insert((this.deadline, id), cx.waker().clone());
+
+            Poll::Pending
+        })
+    }
+}
+
+impl Drop for VirtualTimer {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            // The clock may already be gone if the timer outlives `block_on`
+            let _ = CLOCK.try_with(|clock| {
+                if let Some(clock) = clock.borrow_mut().as_mut() {
+                    clock.timers.remove(&(self.deadline, id));
+                }
+            });
+        }
+    }
+}
+
+/// Get the current virtual time in nanoseconds.
+///
+/// This can be passed to e.g. [`MainDevice::init`](crate::MainDevice::init) so distributed clocks
+/// are configured with virtual time. Returns zero if called outside [`block_on`].
+pub fn ethercat_now() -> u64 {
+    now().unwrap_or_default().as_nanos() as u64
+}
+
+/// Wait for `duration` to pass.
+///
+/// In [`block_on`] this waits in virtual time, otherwise in real time with the same timer
+/// EtherCrab uses for its timeouts.
+pub async fn sleep(duration: Duration) {
+    crate::timer_factory::timer(duration).await
+}
+
+/// Run `future` to completion on the current thread, measuring all EtherCrab timeouts in virtual
+/// time.
+///
+/// Virtual time starts at zero and only moves forward when `future` can make no other progress,
+/// jumping straight to the next timer deadline. Timeouts, retries and delays therefore complete
+/// instantly and always in the same order, so tests of them are fast and deterministic.
+///
+/// The TX/RX task must be polled by `future` too, e.g. by joining it with
+/// `futures_lite::future::or`, as it would otherwise look like nothing is happening and time
+/// would be advanced before responses arrive. [`ethercat_now`] and [`sleep`] can be used to read
+/// the virtual time and wait in it.
+///
+/// If `future` is waiting for neither a timer nor a wakeup, this function blocks until it is woken,
+/// like any other executor.
+///
+/// # Examples
+///
+/// A PDU timeout of an hour completes immediately:
+///
+/// ```rust
+/// use core::time::Duration;
+/// use ethercrab::{
+///     error::Error,
+///     sim,
+///     MainDevice, MainDeviceConfig, PduStorage, Timeouts,
+/// };
+///
+/// static PDU_STORAGE: PduStorage<8, { PduStorage::element_size(64) }> = PduStorage::new();
+///
+/// let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// let timeouts = Timeouts {
+///     pdu: Duration::from_secs(3600),
+///     ..Timeouts::default()
+/// };
+///
+/// let maindevice = MainDevice::new(pdu_loop, timeouts, MainDeviceConfig::default());
+///
+/// // No TX/RX task is running, so nothing will ever respond
+/// let result = sim::block_on(async {
+///     let result = maindevice.init_single_group::<8, 8>(sim::ethercat_now).await;
+///
+///     (result.map(|_| ()), sim::ethercat_now())
+/// });
+///
+/// assert_eq!(result, (Err(Error::Timeout), 3600 * 1_000_000_000));
+/// ```
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let _clock = ClockGuard::install();
+
+    let signal = Arc::new(Signal {
+        woken: AtomicBool::new(false),
+        thread: thread::current(),
+    });
+
+    let waker = Waker::from(signal.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let mut future = pin!(future);
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+
+        if signal.woken.swap(false, Ordering::AcqRel) {
+            continue;
+        }
+
+        // Nothing else to do, so skip to the next deadline and wake everything waiting for it
+        let expired = CLOCK.with_borrow_mut(|clock| {
+            let clock = clock.as_mut().expect("clock installed");
+
+            let (deadline, _) = *clock.timers.keys().next()?;
+
+            clock.now = clock.now.max(deadline);
+
+            let pending = clock.timers.split_off(&(deadline, u64::MAX));
+
+            Some(core::mem::replace(&mut clock.timers, pending))
+        });
+
+        match expired {
+            Some(expired) => expired.into_values().for_each(Waker::wake),
+            None => {
+                while !signal.woken.swap(false, Ordering::AcqRel) {
+                    thread::park();
+                }
+            }
+        }
+    }
+}
+
+/// Wakes [`block_on`]'s thread and records that it should poll again.
+struct Signal {
+    woken: AtomicBool,
+    thread: Thread,
+}
+
+impl Wake for Signal {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Release);
+        self.thread.unpark();
+    }
+}
+
+/// Installs a fresh virtual clock on the current thread, restoring the previous one when dropped.
+struct ClockGuard {
+    previous: Option<Clock>,
+}
+
+impl ClockGuard {
+    fn install() -> Self {
+        Self {
+            previous: CLOCK.replace(Some(Clock::default())),
+        }
+    }
+}
+
+impl Drop for ClockGuard {
+    fn drop(&mut self) {
+        let _ = CLOCK.try_with(|clock| clock.replace(self.previous.take()));
+    }
+}
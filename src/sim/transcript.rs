@@ -0,0 +1,202 @@
+//! Transcripts of the PDUs exchanged with a simulated network.
+
+use crate::{pdu_loop::frame_header::EthercatFrameHeader, PduDissector};
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
+use std::{fmt, path::Path};
+
+/// Environment variable which, when set, makes [`Transcript::assert_matches`] write the transcript
+/// to its golden file instead of comparing them.
+pub const UPDATE_TRANSCRIPTS_VAR: &str = "ETHERCRAB_UPDATE_TRANSCRIPTS";
+
+/// The lines of context shown around the first difference between two transcripts.
+const DIFF_CONTEXT: usize = 3;
+
+/// A record of every PDU exchanged with a [`SimNetwork`](crate::sim::SimNetwork), captured with
+/// [`SimNetwork::record_transcript`](crate::sim::SimNetwork::record_transcript).
+///
+/// Each PDU is rendered on its own line as it was returned by the SubDevices, with its command,
+/// address, payload and working counter, like [`PduDissector`]. PDU indices are left out as they
+/// depend on the PDU storage used. As the simulated network is deterministic, the same sequence
+/// of operations always produces the same transcript, so a transcript can be compared to a known
+/// good "golden" copy to catch unintended changes to e.g. the order of registers written during
+/// [`MainDevice::init`](crate::MainDevice::init).
+///
+/// Run the MainDevice with [`block_on`](crate::sim::block_on) and a fixed or virtual
+/// `now` function so distributed clock values are repeatable.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use ethercrab::{
+///     sim::{self, SimNetwork, SimSubDevice},
+///     tx_rx_device, MainDevice, MainDeviceConfig, PduStorage, SubDeviceIdentity, Timeouts,
+/// };
+///
+/// static PDU_STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+///
+/// let identity = SubDeviceIdentity {
+///     vendor_id: 0x2,
+///     product_id: 0x07d43052,
+///     revision: 0x00100000,
+///     serial: 0,
+/// };
+///
+/// let network = SimNetwork::new([SimSubDevice::new("EL2004", identity).with_outputs(1)]);
+///
+/// let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+///
+/// network.record_transcript();
+///
+/// sim::block_on(futures_lite::future::or(
+///     async {
+///         maindevice
+///             .init_single_group::<8, 8>(sim::ethercat_now)
+///             .await
+///             .expect("init");
+///     },
+///     async {
+///         tx_rx_device(network.clone(), tx, rx).await.expect("TX/RX task");
+///     },
+/// ));
+///
+/// // Set `ETHERCRAB_UPDATE_TRANSCRIPTS=1` to write the file after an intended change
+/// network
+///     .take_transcript()
+///     .assert_matches("tests/transcripts/el2004-init.txt");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Transcript {
+    lines: Vec<String>,
+}
+
+impl Transcript {
+    /// Record the PDUs in the EtherCAT payload of a frame returned by the network.
+    pub(super) fn record(&mut self, ethercat_payload: &[u8]) {
+        let Ok(header) = EthercatFrameHeader::unpack_from_slice(ethercat_payload) else {
+            self.lines.push("truncated EtherCAT header".to_string());
+
+            return;
+        };
+
+        let pdus = &ethercat_payload[EthercatFrameHeader::PACKED_LEN..];
+        let pdus = pdus.get(..usize::from(header.payload_len)).unwrap_or(pdus);
+
+        self.lines.extend(
+            PduDissector::transcript(pdus)
+                .to_string()
+                .lines()
+                .map(str::to_string),
+        );
+    }
+
+    /// The recorded PDUs, one per line.
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(String::as_str)
+    }
+
+    /// The number of recorded PDUs.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Whether no PDUs were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Compare the transcript to a golden copy stored in the file at `path`.
+    ///
+    /// If the [`UPDATE_TRANSCRIPTS_VAR`] environment variable is set, the file is written with this
+    /// transcript instead, e.g. to create it or accept an intended change.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the transcript does not match the file, showing the first
+    /// differing line, or if the file cannot be read or written.
+    #[track_caller]
+    pub fn assert_matches(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+
+        let actual = self.to_string();
+
+        if std::env::var_os(UPDATE_TRANSCRIPTS_VAR).is_some() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).unwrap_or_else(|e| {
+                    panic!("failed to create directory {}: {}", parent.display(), e)
+                });
+            }
+
+            std::fs::write(path, actual)
+                .unwrap_or_else(|e| panic!("failed to write {}: {}", path.display(), e));
+
+            return;
+        }
+
+        let expected = std::fs::read_to_string(path).unwrap_or_else(|e| {
+            panic!(
+                "failed to read transcript {}: {}. Set {} to create it",
+                path.display(),
+                e,
+                UPDATE_TRANSCRIPTS_VAR
+            )
+        });
+
+        if let Some(diff) = first_difference(&expected, &actual) {
+            panic!(
+                "transcript does not match {}. Set {} to update it if the change is intended\n{}",
+                path.display(),
+                UPDATE_TRANSCRIPTS_VAR,
+                diff
+            );
+        }
+    }
+}
+
+impl fmt::Display for Transcript {
+    /// One PDU per line, each ending with a newline.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Describe the first line that differs between two transcripts with a few lines of context, or
+/// return `None` if they are the same.
+fn first_difference(expected: &str, actual: &str) -> Option<String> {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+
+    let line = expected
+        .iter()
+        .zip(actual.iter())
+        .position(|(expected, actual)| expected != actual)
+        .or_else(|| (expected.len() != actual.len()).then(|| expected.len().min(actual.len())))?;
+
+    let start = line.saturating_sub(DIFF_CONTEXT);
+
+    let mut diff = format!(
+        "first difference at line {} ({} expected lines, {} actual):\n",
+        line + 1,
+        expected.len(),
+        actual.len()
+    );
+
+    for context in &expected[start..line] {
+        diff.push_str(&format!("  {}\n", context));
+    }
+
+    for expected in expected.iter().skip(line).take(DIFF_CONTEXT) {
+        diff.push_str(&format!("- {}\n", expected));
+    }
+
+    for actual in actual.iter().skip(line).take(DIFF_CONTEXT) {
+        diff.push_str(&format!("+ {}\n", actual));
+    }
+
+    Some(diff)
+}
@@ -0,0 +1,206 @@
+//! [`RawEthernetDevice`] adapter for [`smoltcp`] network devices.
+
+use crate::{error::Error, RawEthernetDevice};
+use core::task::{Context, Poll};
+use smoltcp::{
+    phy::{Device, RxToken, TxToken},
+    time::Instant,
+};
+
+/// Run the PDU loop over any [`smoltcp::phy::Device`] with [`tx_rx_device`](crate::tx_rx_device).
+///
+/// smoltcp devices have no way of signalling that a frame was received or that a transmit buffer
+/// is available, so the TX/RX future busy polls the device: it wakes itself whenever the device is
+/// not ready, yielding to the executor in between. Running it in its own high priority task or
+/// executor is recommended.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use ethercrab::{tx_rx_device, PduStorage, SmoltcpDevice};
+///
+/// static PDU_STORAGE: PduStorage<8, { PduStorage::element_size(256) }> = PduStorage::new();
+///
+/// let (pdu_tx, pdu_rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// // Any existing smoltcp driver for the board
+/// let device = board::ethernet_device();
+///
+/// spawner.spawn(tx_rx_device(SmoltcpDevice::new(device), pdu_tx, pdu_rx));
+/// ```
+#[derive(Debug)]
+pub struct SmoltcpDevice<D> {
+    device: D,
+    now: fn() -> Instant,
+}
+
+impl<D> SmoltcpDevice<D>
+where
+    D: Device,
+{
+    /// Wrap a smoltcp device.
+    ///
+    /// Devices are passed a zero timestamp when sending and receiving. Use
+    /// [`with_clock`](SmoltcpDevice::with_clock) if the device uses timestamps, e.g. for packet
+    /// capture.
+    pub fn new(device: D) -> Self {
+        Self::with_clock(device, || Instant::ZERO)
+    }
+
+    /// Wrap a smoltcp device, passing it the time returned by `now` when sending and receiving.
+    pub fn with_clock(device: D, now: fn() -> Instant) -> Self {
+        Self { device, now }
+    }
+
+    /// Get the wrapped device back.
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+}
+
+impl<D> RawEthernetDevice for SmoltcpDevice<D>
+where
+    D: Device,
+{
+    fn poll_transmit(&mut self, cx: &mut Context<'_>, frame: &[u8]) -> Poll<Result<(), Error>> {
+        match self.device.transmit((self.now)()) {
+            Some(tx) => {
+                tx.consume(frame.len(), |buf| buf.copy_from_slice(frame));
+
+                Poll::Ready(Ok(()))
+            }
+            None => {
+                cx.waker().wake_by_ref();
+
+                Poll::Pending
+            }
+        }
+    }
+
+    fn poll_receive(
+        &mut self,
+        cx: &mut Context<'_>,
+        on_frame: impl FnOnce(&[u8]),
+    ) -> Poll<Result<(), Error>> {
+        match self.device.receive((self.now)()) {
+            Some((rx, _tx)) => {
+                rx.consume(|frame| on_frame(frame));
+
+                Poll::Ready(Ok(()))
+            }
+            None => {
+                cx.waker().wake_by_ref();
+
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ethernet::{EthernetAddress, EthernetFrame},
+        tx_rx_device, Command, MainDevice, MainDeviceConfig, PduStorage, RegisterAddress, Timeouts,
+    };
+    use core::time::Duration;
+    use smoltcp::phy::{DeviceCapabilities, Medium};
+    use std::collections::VecDeque;
+
+    /// Returns every sent frame as if it passed through one SubDevice that didn't process it.
+    #[derive(Default)]
+    struct Loopback {
+        frames: VecDeque<Vec<u8>>,
+    }
+
+    struct Rx(Vec<u8>);
+
+    impl RxToken for Rx {
+        fn consume<R, F>(mut self, f: F) -> R
+        where
+            F: FnOnce(&mut [u8]) -> R,
+        {
+            f(&mut self.0)
+        }
+    }
+
+    struct Tx<'a>(&'a mut VecDeque<Vec<u8>>);
+
+    impl TxToken for Tx<'_> {
+        fn consume<R, F>(self, len: usize, f: F) -> R
+        where
+            F: FnOnce(&mut [u8]) -> R,
+        {
+            let mut frame = vec![0u8; len];
+
+            let result = f(&mut frame);
+
+            // SubDevices set the U/L bit of the source address
+            EthernetFrame::new_unchecked(&mut frame)
+                .set_src_addr(EthernetAddress([0x12, 0x10, 0x10, 0x10, 0x10, 0x10]));
+
+            self.0.push_back(frame);
+
+            result
+        }
+    }
+
+    impl Device for Loopback {
+        type RxToken<'a> = Rx;
+        type TxToken<'a> = Tx<'a>;
+
+        fn receive(
+            &mut self,
+            _timestamp: Instant,
+        ) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+            let frame = self.frames.pop_front()?;
+
+            Some((Rx(frame), Tx(&mut self.frames)))
+        }
+
+        fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+            Some(Tx(&mut self.frames))
+        }
+
+        fn capabilities(&self) -> DeviceCapabilities {
+            let mut caps = DeviceCapabilities::default();
+
+            caps.medium = Medium::Ethernet;
+
+            caps
+        }
+    }
+
+    #[test]
+    fn loopback() {
+        static STORAGE: PduStorage<4, { PduStorage::element_size(32) }> = PduStorage::new();
+
+        let (tx, rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let maindevice = MainDevice::new(
+            pdu_loop,
+            Timeouts {
+                pdu: Duration::from_millis(100),
+                ..Timeouts::default()
+            },
+            MainDeviceConfig::default(),
+        );
+
+        let result = futures_lite::future::block_on(futures_lite::future::or(
+            async {
+                Command::brd(RegisterAddress::Type.into())
+                    .receive_wkc::<u8>(&maindevice)
+                    .await
+            },
+            async {
+                tx_rx_device(SmoltcpDevice::new(Loopback::default()), tx, rx).await?;
+
+                unreachable!()
+            },
+        ));
+
+        // No SubDevices processed the frame
+        assert_eq!(result, Ok(0));
+    }
+}
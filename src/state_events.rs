@@ -0,0 +1,597 @@
+//! SubDevice state changes observed in the MainDevice's own traffic.
+
+use crate::{
+    al_control::AlControl,
+    atomic::{AtomicU8, AtomicUsize, Ordering},
+    ethernet::EthernetFrame,
+    fmt,
+    pdu_loop::{frame_header::EthercatFrameHeader, pdu_header::PduHeader},
+    AlStatusCode, Command, ConfiguredAddress, FrameDirection, FrameTap, Reads, RegisterAddress,
+    SubDeviceState, ETHERCAT_ETHERTYPE,
+};
+use atomic_waker::AtomicWaker;
+use core::task::Poll;
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
+use futures_lite::Stream;
+use heapless::mpmc::MpMcQueue;
+
+/// The SubDevice has been seen at least once.
+const SEEN: u8 = 0x40;
+/// The SubDevice did not respond to the last AL status read.
+const LOST: u8 = 0x80;
+/// AL state and error flag bits, as laid out in the first byte of the AL status register.
+const STATUS: u8 = 0x1f;
+
+/// A SubDevice state change reported by [`StateEvents`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StateEvent {
+    /// A SubDevice's AL state or error flag changed.
+    Changed {
+        /// SubDevice configured station address.
+        configured_address: ConfiguredAddress,
+        /// Last known state, or [`SubDeviceState::None`] if this is the first time the state of
+        /// this SubDevice was seen.
+        previous: SubDeviceState,
+        /// New state.
+        current: SubDeviceState,
+        /// The AL status error flag is set, e.g. after dropping from OP to SAFE-OP.
+        error: bool,
+        /// The AL status code, if it was read along with the AL status.
+        status_code: Option<AlStatusCode>,
+    },
+    /// A SubDevice did not respond to an AL status read.
+    Lost {
+        /// SubDevice configured station address.
+        configured_address: ConfiguredAddress,
+    },
+    /// A SubDevice responded again after being lost.
+    Found {
+        /// SubDevice configured station address.
+        configured_address: ConfiguredAddress,
+        /// Current state.
+        state: SubDeviceState,
+    },
+}
+
+impl core::fmt::Display for StateEvent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            StateEvent::Changed {
+                configured_address,
+                previous,
+                current,
+                error,
+                status_code,
+            } => {
+                write!(
+                    f,
+                    "SubDevice {:#06x} {} -> {}",
+                    configured_address, previous, current
+                )?;
+
+                match (error, status_code) {
+                    (true, Some(code)) => write!(f, " (error: {})", code),
+                    (true, None) => f.write_str(" (error)"),
+                    (false, _) => Ok(()),
+                }
+            }
+            StateEvent::Lost { configured_address } => {
+                write!(f, "SubDevice {:#06x} lost", configured_address)
+            }
+            StateEvent::Found {
+                configured_address,
+                state,
+            } => write!(
+                f,
+                "SubDevice {:#06x} found in {}",
+                configured_address, state
+            ),
+        }
+    }
+}
+
+/// A queue of SubDevice state changes, fed by the frames received by the MainDevice.
+///
+/// `StateEvents` is a [`FrameTap`] and is attached to the TX/RX task with
+/// [`PduRx::set_frame_tap`](crate::PduRx::set_frame_tap). It watches every `FPRD` of the AL status
+/// register, whether sent by a state transition, [`SubDeviceGroup::all_op`], a
+/// [`HealthMonitor`](crate::HealthMonitor) or the application, and queues a [`StateEvent`] whenever
+/// a SubDevice's state changes or it stops responding. No extra traffic is generated, so changes
+/// are only seen as often as the AL status is read.
+///
+/// Events can be received with [`try_recv`](StateEvents::try_recv), [`recv`](StateEvents::recv) or
+/// as a [`Stream`] with [`stream`](StateEvents::stream). Only one task should wait for events at
+/// a time.
+///
+/// States are tracked for up to `MAX_SUBDEVICES` SubDevices. Up to `QUEUE_LEN` events are queued,
+/// which must be a power of two less than 256. Events that don't fit in the queue are counted by
+/// [`dropped`](StateEvents::dropped).
+///
+/// [`SubDeviceGroup::all_op`]: crate::SubDeviceGroup::all_op
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use ethercrab::{PduStorage, StateEvents};
+///
+/// static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+/// static STATE_EVENTS: StateEvents<16> = StateEvents::new();
+///
+/// let (tx, mut rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// rx.set_frame_tap(&STATE_EVENTS);
+///
+/// // Spawn the TX/RX task and initialise the MainDevice here
+///
+/// # async {
+/// while let Some(event) = futures_lite::StreamExt::next(&mut STATE_EVENTS.stream()).await {
+///     println!("{}", event);
+/// }
+/// # };
+/// ```
+pub struct StateEvents<const MAX_SUBDEVICES: usize, const QUEUE_LEN: usize = 32> {
+    states: [AtomicU8; MAX_SUBDEVICES],
+    queue: MpMcQueue<StateEvent, QUEUE_LEN>,
+    waker: AtomicWaker,
+    dropped: AtomicUsize,
+}
+
+impl<const MAX_SUBDEVICES: usize, const QUEUE_LEN: usize> core::fmt::Debug
+    for StateEvents<MAX_SUBDEVICES, QUEUE_LEN>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("StateEvents")
+            .field("states", &self.states)
+            .field("dropped", &self.dropped)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<const MAX_SUBDEVICES: usize, const QUEUE_LEN: usize> Default
+    for StateEvents<MAX_SUBDEVICES, QUEUE_LEN>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const MAX_SUBDEVICES: usize, const QUEUE_LEN: usize> StateEvents<MAX_SUBDEVICES, QUEUE_LEN> {
+    /// Create an empty event queue with no known SubDevices.
+    pub const fn new() -> Self {
+        Self {
+            states: [const { AtomicU8::new(0) }; MAX_SUBDEVICES],
+            queue: MpMcQueue::new(),
+            waker: AtomicWaker::new(),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Take the next event from the queue, if any.
+    pub fn try_recv(&self) -> Option<StateEvent> {
+        self.queue.dequeue()
+    }
+
+    /// Wait for the next event.
+    pub async fn recv(&self) -> StateEvent {
+        core::future::poll_fn(|cx| {
+            if let Some(event) = self.queue.dequeue() {
+                return Poll::Ready(event);
+            }
+
+            self.waker.register(cx.waker());
+
+            // An event may have been queued before the waker was registered
+            match self.queue.dequeue() {
+                Some(event) => Poll::Ready(event),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    /// An endless stream of events.
+    pub fn stream(&self) -> impl Stream<Item = StateEvent> + Unpin + '_ {
+        futures_lite::stream::poll_fn(move |cx| {
+            if let Some(event) = self.queue.dequeue() {
+                return Poll::Ready(Some(event));
+            }
+
+            self.waker.register(cx.waker());
+
+            match self.queue.dequeue() {
+                Some(event) => Poll::Ready(Some(event)),
+                None => Poll::Pending,
+            }
+        })
+    }
+
+    /// The number of events dropped because the queue was full.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// The last known state of the SubDevice with the given configured station address, or `None`
+    /// if it hasn't been seen yet or did not respond to the last AL status read.
+    pub fn state(&self, configured_address: ConfiguredAddress) -> Option<SubDeviceState> {
+        let raw = self.slot(configured_address)?.load(Ordering::Relaxed);
+
+        (raw & SEEN != 0 && raw & LOST == 0).then(|| decode(raw).state)
+    }
+
+    fn slot(&self, configured_address: ConfiguredAddress) -> Option<&AtomicU8> {
+        configured_address
+            .index()
+            .and_then(|index| self.states.get(usize::from(index)))
+    }
+
+    fn emit(&self, event: StateEvent) {
+        fmt::debug!("{}", event);
+
+        if self.queue.enqueue(event).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.waker.wake();
+    }
+
+    /// Update the state of a SubDevice from the response to an AL status read.
+    fn observe(&self, configured_address: ConfiguredAddress, data: &[u8], working_counter: u16) {
+        let Some(slot) = self.slot(configured_address) else {
+            return;
+        };
+
+        if working_counter == 0 {
+            // SubDevices that have never responded can't be lost
+            let previous = slot.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |raw| {
+                (raw & SEEN != 0).then_some(raw | LOST)
+            });
+
+            if previous.is_ok_and(|previous| previous & LOST == 0) {
+                self.emit(StateEvent::Lost { configured_address });
+            }
+
+            return;
+        }
+
+        let Some(status) = data.first() else {
+            return;
+        };
+
+        let current = SEEN | (status & STATUS);
+        let previous = slot.swap(current, Ordering::Relaxed);
+        let control = decode(current);
+
+        if previous & LOST != 0 {
+            self.emit(StateEvent::Found {
+                configured_address,
+                state: control.state,
+            });
+        }
+
+        if previous & !LOST != current {
+            let previous = if previous & SEEN != 0 {
+                decode(previous).state
+            } else {
+                SubDeviceState::None
+            };
+
+            // The AL status code register follows the AL status register after 2 reserved bytes
+            let status_code = data
+                .get(4..)
+                .and_then(|raw| AlStatusCode::unpack_from_slice(raw).ok());
+
+            self.emit(StateEvent::Changed {
+                configured_address,
+                previous,
+                current: control.state,
+                error: control.error,
+                status_code: status_code.filter(|_| control.error),
+            });
+        }
+    }
+}
+
+fn decode(raw: u8) -> AlControl {
+    AlControl::unpack_from_slice(&[raw & STATUS, 0]).unwrap_or_default()
+}
+
+impl<const MAX_SUBDEVICES: usize, const QUEUE_LEN: usize> FrameTap
+    for StateEvents<MAX_SUBDEVICES, QUEUE_LEN>
+{
+    fn frame(&self, direction: FrameDirection, ethernet_frame: &[u8]) {
+        if direction != FrameDirection::Received {
+            return;
+        }
+
+        let Ok(frame) = EthernetFrame::new_checked(ethernet_frame) else {
+            return;
+        };
+
+        if frame.inner_ethertype() != ETHERCAT_ETHERTYPE {
+            return;
+        }
+
+        let payload = frame.inner_payload();
+
+        let Ok(header) = EthercatFrameHeader::unpack_from_slice(payload) else {
+            return;
+        };
+
+        let mut pdus = payload
+            .get(
+                EthercatFrameHeader::PACKED_LEN
+                    ..(EthercatFrameHeader::PACKED_LEN + usize::from(header.payload_len)),
+            )
+            .unwrap_or_default();
+
+        while let Ok(pdu_header) = PduHeader::unpack_from_slice(pdus) {
+            let data_len = usize::from(pdu_header.flags.len());
+
+            let (Some(data), Some(working_counter)) = (
+                pdus.get(PduHeader::PACKED_LEN..(PduHeader::PACKED_LEN + data_len)),
+                pdus.get((PduHeader::PACKED_LEN + data_len)..)
+                    .and_then(|rest| u16::unpack_from_slice(rest).ok()),
+            ) else {
+                return;
+            };
+
+            if let Some(Command::Read(Reads::Fprd { address, register })) =
+                Command::parse(pdu_header.command_code, pdu_header.command_raw)
+            {
+                if register == u16::from(RegisterAddress::AlStatus) {
+                    self.observe(ConfiguredAddress::new(address), data, working_counter);
+                }
+            }
+
+            if !pdu_header.flags.more_follows {
+                break;
+            }
+
+            pdus = pdus
+                .get((PduHeader::PACKED_LEN + data_len + 2)..)
+                .unwrap_or_default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn al_status(state: SubDeviceState, error: bool, code: u16) -> [u8; 6] {
+        let mut raw = [0u8; 6];
+
+        raw[0] = u8::from(state) | if error { 0x10 } else { 0 };
+        raw[4..6].copy_from_slice(&code.to_le_bytes());
+
+        raw
+    }
+
+    #[test]
+    fn changes() {
+        let events = StateEvents::<4, 8>::new();
+        let configured_address = ConfiguredAddress::new(0x1001);
+
+        events.observe(
+            configured_address,
+            &al_status(SubDeviceState::PreOp, false, 0),
+            1,
+        );
+        // No change
+        events.observe(
+            configured_address,
+            &al_status(SubDeviceState::PreOp, false, 0),
+            1,
+        );
+        events.observe(
+            configured_address,
+            &al_status(SubDeviceState::Op, false, 0),
+            1,
+        );
+        events.observe(
+            configured_address,
+            &al_status(SubDeviceState::SafeOp, true, 0x001b),
+            1,
+        );
+        // Not tracked
+        events.observe(
+            ConfiguredAddress::new(0x1004),
+            &al_status(SubDeviceState::Op, false, 0),
+            1,
+        );
+
+        assert_eq!(
+            events.try_recv(),
+            Some(StateEvent::Changed {
+                configured_address,
+                previous: SubDeviceState::None,
+                current: SubDeviceState::PreOp,
+                error: false,
+                status_code: None,
+            })
+        );
+        assert_eq!(
+            events.try_recv(),
+            Some(StateEvent::Changed {
+                configured_address,
+                previous: SubDeviceState::PreOp,
+                current: SubDeviceState::Op,
+                error: false,
+                status_code: None,
+            })
+        );
+        assert_eq!(
+            events.try_recv(),
+            Some(StateEvent::Changed {
+                configured_address,
+                previous: SubDeviceState::Op,
+                current: SubDeviceState::SafeOp,
+                error: true,
+                status_code: Some(AlStatusCode::SyncManagerWatchdog),
+            })
+        );
+        assert_eq!(events.try_recv(), None);
+        assert_eq!(
+            events.state(configured_address),
+            Some(SubDeviceState::SafeOp)
+        );
+        assert_eq!(events.state(ConfiguredAddress::new(0x1002)), None);
+    }
+
+    #[test]
+    fn lost_and_found() {
+        let events = StateEvents::<4, 8>::new();
+        let configured_address = ConfiguredAddress::new(0x1000);
+
+        // Never seen, so not lost
+        events.observe(configured_address, &[0, 0], 0);
+        assert_eq!(events.try_recv(), None);
+
+        events.observe(
+            configured_address,
+            &al_status(SubDeviceState::Op, false, 0),
+            1,
+        );
+        events.observe(configured_address, &[0, 0], 0);
+        events.observe(configured_address, &[0, 0], 0);
+
+        assert!(matches!(
+            events.try_recv(),
+            Some(StateEvent::Changed { .. })
+        ));
+        assert_eq!(
+            events.try_recv(),
+            Some(StateEvent::Lost { configured_address })
+        );
+        assert_eq!(events.try_recv(), None);
+        assert_eq!(events.state(configured_address), None);
+
+        events.observe(
+            configured_address,
+            &al_status(SubDeviceState::Init, false, 0),
+            1,
+        );
+
+        assert_eq!(
+            events.try_recv(),
+            Some(StateEvent::Found {
+                configured_address,
+                state: SubDeviceState::Init
+            })
+        );
+        assert_eq!(
+            events.try_recv(),
+            Some(StateEvent::Changed {
+                configured_address,
+                previous: SubDeviceState::Op,
+                current: SubDeviceState::Init,
+                error: false,
+                status_code: None,
+            })
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let events = StateEvents::<1, 2>::new();
+        let configured_address = ConfiguredAddress::new(0x1000);
+
+        for state in [
+            SubDeviceState::Init,
+            SubDeviceState::PreOp,
+            SubDeviceState::SafeOp,
+        ] {
+            events.observe(configured_address, &al_status(state, false, 0), 1);
+        }
+
+        assert_eq!(events.dropped(), 1);
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        sim::fixture::*, tx_rx_device, AlStatusCode, ConfiguredAddress, MainDevice,
+        MainDeviceConfig, PduStorage, StateEvent, StateEvents, SubDeviceState, Timeouts,
+    };
+
+    #[test]
+    fn state_events() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+        static EVENTS: StateEvents<8> = StateEvents::new();
+
+        let network = network();
+
+        let (tx, mut rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        rx.set_frame_tap(&EVENTS);
+
+        let maindevice =
+            MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+
+        let net = network.clone();
+
+        futures_lite::future::block_on(futures_lite::future::or(
+            async {
+                let group = maindevice
+                    .init_single_group::<8, 32>(|| 0)
+                    .await
+                    .expect("init")
+                    .into_op(&maindevice)
+                    .await
+                    .expect("PRE-OP -> OP");
+
+                let mut last = [SubDeviceState::None; 4];
+
+                while let Some(event) = EVENTS.try_recv() {
+                    if let StateEvent::Changed {
+                        configured_address,
+                        current,
+                        ..
+                    } = event
+                    {
+                        last[usize::from(configured_address.get() - 0x1000)] = current;
+                    }
+                }
+
+                assert_eq!(last, [SubDeviceState::Op; 4]);
+
+                net.with_subdevice(1, |subdevice| {
+                    subdevice.fault(AlStatusCode::SyncManagerWatchdog)
+                });
+
+                group.all_op(&maindevice).await.ok();
+
+                assert_eq!(
+                    EVENTS.recv().await,
+                    StateEvent::Changed {
+                        configured_address: ConfiguredAddress::new(0x1001),
+                        previous: SubDeviceState::Op,
+                        current: SubDeviceState::SafeOp,
+                        error: true,
+                        status_code: None,
+                    }
+                );
+
+                net.disconnect(2);
+
+                group.all_op(&maindevice).await.ok();
+
+                assert_eq!(
+                    EVENTS.recv().await,
+                    StateEvent::Lost {
+                        configured_address: ConfiguredAddress::new(0x1003)
+                    }
+                );
+                assert_eq!(EVENTS.try_recv(), None);
+                assert_eq!(EVENTS.dropped(), 0);
+            },
+            async {
+                tx_rx_device(network.clone(), tx, rx).await.unwrap();
+
+                unreachable!()
+            },
+        ));
+    }
+}
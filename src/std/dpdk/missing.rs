@@ -0,0 +1,20 @@
+//! Stand-in for the DPDK TX/RX loop when DPDK wasn't found at build time.
+
+use crate::{PduRx, PduTx};
+use std::io;
+
+/// Create a blocking TX/RX loop using a NIC bound to a DPDK poll mode driver.
+///
+/// DPDK 21.11 or newer was not found with `pkg-config` when EtherCrab was built, so this function
+/// always returns an [`io::ErrorKind::Unsupported`] error. Install DPDK and rebuild to use it.
+pub fn tx_rx_task_dpdk<'sto>(
+    _eal_args: &[&str],
+    _device: &str,
+    _pdu_tx: PduTx<'sto>,
+    _pdu_rx: PduRx<'sto>,
+) -> Result<(), io::Error> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "EtherCrab was built without DPDK",
+    ))
+}
@@ -0,0 +1,306 @@
+//! A busy-polling TX/RX loop using a NIC driven by a [DPDK](https://www.dpdk.org) poll mode driver.
+
+use crate::{
+    error::{Error, PduError},
+    fmt, PduRx, PduTx,
+};
+use core::{ptr, slice};
+use std::{
+    ffi::{c_char, c_int, c_uint, CStr, CString},
+    io, thread,
+};
+
+/// Maximum number of frames to receive from the RX queue at once.
+const RX_BURST: usize = 32;
+
+/// Number of mbufs in the pool shared between the RX and TX queues.
+const POOL_SIZE: c_uint = 1023;
+
+/// Number of mbufs cached per lcore.
+const POOL_CACHE_SIZE: c_uint = 32;
+
+/// `RTE_MBUF_DEFAULT_BUF_SIZE`: a 2048 byte data room plus the default 128 byte headroom. Large
+/// enough for any EtherCAT frame, so frames never span multiple mbufs.
+const MBUF_BUF_SIZE: u16 = 2048 + 128;
+
+/// Requested number of RX and TX descriptors. Adjusted to the NIC's limits during setup.
+const RING_SIZE: u16 = 512;
+
+/// `struct rte_mempool`.
+#[repr(C)]
+struct RteMempool {
+    _private: [u8; 0],
+}
+
+/// `struct rte_mbuf`.
+#[repr(C)]
+struct RteMbuf {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    fn rte_eal_init(argc: c_int, argv: *mut *mut c_char) -> c_int;
+    fn rte_socket_id() -> c_uint;
+    fn rte_strerror(errnum: c_int) -> *const c_char;
+    fn rte_eth_dev_get_port_by_name(name: *const c_char, port_id: *mut u16) -> c_int;
+    fn rte_eth_dev_stop(port_id: u16) -> c_int;
+    fn rte_mempool_lookup(name: *const c_char) -> *mut RteMempool;
+    fn rte_pktmbuf_pool_create(
+        name: *const c_char,
+        n: c_uint,
+        cache_size: c_uint,
+        priv_size: u16,
+        data_room_size: u16,
+        socket_id: c_int,
+    ) -> *mut RteMempool;
+
+    // Defined in `shim.c`
+    fn ethercrab_dpdk_errno() -> c_int;
+    fn ethercrab_dpdk_port_start(
+        port_id: u16,
+        pool: *mut RteMempool,
+        rx_desc: u16,
+        tx_desc: u16,
+    ) -> c_int;
+    fn ethercrab_dpdk_rx_burst(port_id: u16, pkts: *mut *mut RteMbuf, nb_pkts: u16) -> u16;
+    fn ethercrab_dpdk_tx_burst(port_id: u16, pkts: *mut *mut RteMbuf, nb_pkts: u16) -> u16;
+    fn ethercrab_dpdk_pktmbuf_alloc(pool: *mut RteMempool) -> *mut RteMbuf;
+    fn ethercrab_dpdk_pktmbuf_free(m: *mut RteMbuf);
+    fn ethercrab_dpdk_pktmbuf_append(m: *mut RteMbuf, len: u16) -> *mut c_char;
+    fn ethercrab_dpdk_pktmbuf_data(m: *const RteMbuf, len: *mut u16) -> *const u8;
+}
+
+/// Create an error from the current value of `rte_errno`.
+fn rte_error(context: &str) -> io::Error {
+    // SAFETY: Both functions are always safe to call. `rte_strerror` returns a valid C string.
+    let message = unsafe { CStr::from_ptr(rte_strerror(ethercrab_dpdk_errno())) };
+
+    io::Error::other(format!("{}: {}", context, message.to_string_lossy()))
+}
+
+/// Create an error from a negative errno value returned by an ethdev function.
+fn ethdev_error(res: c_int) -> io::Error {
+    io::Error::from_raw_os_error(-res)
+}
+
+/// Initialise the DPDK environment abstraction layer. Does nothing if it is already initialised.
+fn eal_init(eal_args: &[&str]) -> io::Result<()> {
+    // The EAL may keep pointers into the arguments for the lifetime of the process, so they are
+    // leaked.
+    let mut argv = core::iter::once("ethercrab")
+        .chain(eal_args.iter().copied())
+        .map(|arg| {
+            CString::new(arg)
+                .map(CString::into_raw)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid EAL argument"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // SAFETY: `argv` contains `argv.len()` valid, NUL terminated strings.
+    let res = unsafe { rte_eal_init(argv.len() as c_int, argv.as_mut_ptr()) };
+
+    // SAFETY: Always safe to call.
+    if res < 0 && unsafe { ethercrab_dpdk_errno() } != libc::EALREADY {
+        return Err(rte_error("failed to initialise EAL"));
+    }
+
+    Ok(())
+}
+
+/// An mbuf owned by this crate, returned to its pool when dropped.
+struct Mbuf(*mut RteMbuf);
+
+impl Mbuf {
+    /// The frame stored in the mbuf.
+    fn data(&self) -> &[u8] {
+        let mut len = 0;
+
+        // SAFETY: `self.0` is a valid mbuf. The returned pointer is valid for `len` bytes until
+        // the mbuf is freed.
+        unsafe {
+            let data = ethercrab_dpdk_pktmbuf_data(self.0, &mut len);
+
+            slice::from_raw_parts(data, usize::from(len))
+        }
+    }
+}
+
+impl Drop for Mbuf {
+    fn drop(&mut self) {
+        // SAFETY: `self.0` is a valid mbuf that is not used after this.
+        unsafe { ethercrab_dpdk_pktmbuf_free(self.0) }
+    }
+}
+
+/// A started DPDK port using a single RX and TX queue. The port is stopped when dropped.
+struct DpdkPort {
+    port_id: u16,
+    pool: *mut RteMempool,
+}
+
+impl DpdkPort {
+    fn new(device: &str) -> io::Result<Self> {
+        let name = CString::new(device)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid device name"))?;
+
+        let mut port_id = 0;
+
+        // SAFETY: `name` is a valid C string and `port_id` is a valid `u16`.
+        let res = unsafe { rte_eth_dev_get_port_by_name(name.as_ptr(), &mut port_id) };
+
+        if res < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no DPDK port named {}", device),
+            ));
+        }
+
+        // Reuse the pool if this port was opened before, as mempools can't be freed while the
+        // NIC may still hold mbufs from them.
+        let pool_name = CString::new(format!("ethercrab_{}", port_id)).expect("valid pool name");
+
+        // SAFETY: `pool_name` is a valid C string.
+        let mut pool = unsafe { rte_mempool_lookup(pool_name.as_ptr()) };
+
+        if pool.is_null() {
+            // SAFETY: `pool_name` is a valid C string. `rte_socket_id` is always safe to call.
+            pool = unsafe {
+                rte_pktmbuf_pool_create(
+                    pool_name.as_ptr(),
+                    POOL_SIZE,
+                    POOL_CACHE_SIZE,
+                    0,
+                    MBUF_BUF_SIZE,
+                    rte_socket_id() as c_int,
+                )
+            };
+        }
+
+        if pool.is_null() {
+            return Err(rte_error("failed to create mbuf pool"));
+        }
+
+        // SAFETY: `pool` is a valid mempool.
+        let res = unsafe { ethercrab_dpdk_port_start(port_id, pool, RING_SIZE, RING_SIZE) };
+
+        if res < 0 {
+            return Err(ethdev_error(res));
+        }
+
+        Ok(Self { port_id, pool })
+    }
+
+    /// Receive up to [`RX_BURST`] frames from the RX queue.
+    fn receive(&mut self, mbufs: &mut Vec<Mbuf>) {
+        let mut pkts = [ptr::null_mut(); RX_BURST];
+
+        // SAFETY: `pkts` is valid for `RX_BURST` entries.
+        let count =
+            unsafe { ethercrab_dpdk_rx_burst(self.port_id, pkts.as_mut_ptr(), RX_BURST as u16) };
+
+        mbufs.extend(pkts[0..usize::from(count)].iter().copied().map(Mbuf));
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<usize, Error> {
+        let len = u16::try_from(data.len()).map_err(|_| Error::Pdu(PduError::TooLong))?;
+
+        // SAFETY: `self.pool` is a valid mempool.
+        let mbuf = unsafe { ethercrab_dpdk_pktmbuf_alloc(self.pool) };
+
+        if mbuf.is_null() {
+            fmt::error!("DPDK mbuf pool is exhausted");
+
+            return Err(Error::SendFrame);
+        }
+
+        let mbuf = Mbuf(mbuf);
+
+        // SAFETY: `mbuf` is a valid, empty mbuf.
+        let buf = unsafe { ethercrab_dpdk_pktmbuf_append(mbuf.0, len) };
+
+        if buf.is_null() {
+            return Err(Error::Pdu(PduError::TooLong));
+        }
+
+        // SAFETY: `buf` is valid for `len` bytes and does not overlap `data`.
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), buf.cast(), data.len()) };
+
+        let mut pkt = mbuf.0;
+
+        // The TX queue may be full until the NIC has sent earlier frames.
+        // SAFETY: `pkt` is a valid mbuf.
+        while unsafe { ethercrab_dpdk_tx_burst(self.port_id, &mut pkt, 1) } == 0 {
+            thread::yield_now();
+        }
+
+        // The mbuf is now owned by the NIC driver, which frees it once it has been sent.
+        core::mem::forget(mbuf);
+
+        Ok(data.len())
+    }
+}
+
+impl Drop for DpdkPort {
+    fn drop(&mut self) {
+        // SAFETY: `self.port_id` is a started port.
+        unsafe { rte_eth_dev_stop(self.port_id) };
+    }
+}
+
+/// Create a blocking TX/RX loop using a NIC bound to a DPDK poll mode driver.
+///
+/// Instead of waiting for an async socket to become ready, this loop continuously polls the NIC's
+/// RX queue from userspace, so one CPU core is kept fully busy. Pin the calling thread to an
+/// isolated core for the lowest latency, e.g. with
+/// [`RealtimeConfig`](crate::std::RealtimeConfig).
+///
+/// Received frames are parsed straight from the mbuf the NIC wrote them into, so responses are
+/// copied only once, into the PDU storage. Sent frames are copied from the PDU storage into a new
+/// mbuf.
+///
+/// `eal_args` are the DPDK EAL arguments, not including the program name, e.g. `["-l", "2",
+/// "-a", "0000:01:00.0"]`. The EAL is only initialised on the first call to this function, so
+/// later calls ignore `eal_args`. `device` is the DPDK device name, usually its PCI address, e.g.
+/// `0000:01:00.0`.
+///
+/// This function is only available on `linux` targets with the `dpdk` feature enabled, and
+/// requires DPDK 21.11 or newer to be installed and discoverable with `pkg-config`. Hugepages must
+/// be set up, and the NIC bound to a DPDK compatible driver like `vfio-pci` beforehand.
+pub fn tx_rx_task_dpdk<'sto>(
+    eal_args: &[&str],
+    device: &str,
+    mut pdu_tx: PduTx<'sto>,
+    mut pdu_rx: PduRx<'sto>,
+) -> Result<(), io::Error> {
+    eal_init(eal_args)?;
+
+    let mut port = DpdkPort::new(device)?;
+
+    fmt::debug!("Opening DPDK port {} ({})", port.port_id, device);
+
+    let mut received = Vec::with_capacity(RX_BURST);
+
+    loop {
+        while let Some(frame) = pdu_tx.next_sendable_frame() {
+            frame
+                .send_blocking(|data: &[u8]| port.send(data))
+                .map_err(io::Error::other)?;
+        }
+
+        port.receive(&mut received);
+
+        for mbuf in received.drain(..) {
+            loop {
+                match pdu_rx.receive_frame(mbuf.data()) {
+                    Ok(()) => break,
+                    Err(Error::Pdu(PduError::NoWaker)) => {
+                        fmt::trace!("No waker for received frame, retrying receive");
+
+                        thread::yield_now();
+                    }
+                    Err(e) => return Err(io::Error::other(e)),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+/*
+ * Wrappers around DPDK functions that are `static inline` in the DPDK headers, or use structs
+ * whose layout changes between DPDK releases, so can't be called from Rust directly.
+ */
+
+#include <string.h>
+
+#include <rte_errno.h>
+#include <rte_ethdev.h>
+#include <rte_mbuf.h>
+
+int ethercrab_dpdk_errno(void)
+{
+    return rte_errno;
+}
+
+int ethercrab_dpdk_port_start(uint16_t port_id, struct rte_mempool *pool, uint16_t rx_desc,
+                              uint16_t tx_desc)
+{
+    struct rte_eth_conf conf;
+    int socket_id = rte_eth_dev_socket_id(port_id);
+    int ret;
+
+    memset(&conf, 0, sizeof(conf));
+
+    /* A single RX and TX queue is enough for EtherCAT traffic */
+    ret = rte_eth_dev_configure(port_id, 1, 1, &conf);
+    if (ret < 0)
+        return ret;
+
+    ret = rte_eth_dev_adjust_nb_rx_tx_desc(port_id, &rx_desc, &tx_desc);
+    if (ret < 0)
+        return ret;
+
+    ret = rte_eth_rx_queue_setup(port_id, 0, rx_desc, socket_id, NULL, pool);
+    if (ret < 0)
+        return ret;
+
+    ret = rte_eth_tx_queue_setup(port_id, 0, tx_desc, socket_id, NULL);
+    if (ret < 0)
+        return ret;
+
+    ret = rte_eth_dev_start(port_id);
+    if (ret < 0)
+        return ret;
+
+    /* EtherCAT frames are sent to the broadcast address and returned with a modified source MAC */
+    return rte_eth_promiscuous_enable(port_id);
+}
+
+uint16_t ethercrab_dpdk_rx_burst(uint16_t port_id, struct rte_mbuf **pkts, uint16_t nb_pkts)
+{
+    return rte_eth_rx_burst(port_id, 0, pkts, nb_pkts);
+}
+
+uint16_t ethercrab_dpdk_tx_burst(uint16_t port_id, struct rte_mbuf **pkts, uint16_t nb_pkts)
+{
+    return rte_eth_tx_burst(port_id, 0, pkts, nb_pkts);
+}
+
+struct rte_mbuf *ethercrab_dpdk_pktmbuf_alloc(struct rte_mempool *pool)
+{
+    return rte_pktmbuf_alloc(pool);
+}
+
+void ethercrab_dpdk_pktmbuf_free(struct rte_mbuf *m)
+{
+    rte_pktmbuf_free(m);
+}
+
+char *ethercrab_dpdk_pktmbuf_append(struct rte_mbuf *m, uint16_t len)
+{
+    return rte_pktmbuf_append(m, len);
+}
+
+const uint8_t *ethercrab_dpdk_pktmbuf_data(const struct rte_mbuf *m, uint16_t *len)
+{
+    /* Only the first segment is returned. EtherCAT frames always fit in a single mbuf. */
+    *len = rte_pktmbuf_data_len(m);
+
+    return rte_pktmbuf_mtod(m, const uint8_t *);
+}
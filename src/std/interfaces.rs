@@ -0,0 +1,254 @@
+//! Network interface enumeration.
+
+use crate::fmt;
+use std::io;
+
+/// A network interface, returned by [`interfaces`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interface {
+    /// The name to pass to `tx_rx_task`, e.g. `eth0` on Linux or `\Device\NPF_{GUID}` on Windows.
+    pub name: String,
+
+    /// The user facing name of the interface, if any.
+    ///
+    /// On Windows this is the name shown in the network settings, e.g. `Ethernet 2`. On Linux this
+    /// is the interface alias, set with e.g. `ip link set eth0 alias "EtherCAT"`.
+    ///
+    /// This name can be passed to `tx_rx_task` instead of [`name`](Interface::name).
+    pub friendly_name: Option<String>,
+
+    /// A description of the network adapter if available.
+    ///
+    /// On Windows this is the adapter model, and on Linux the name of the driver, e.g. `igb`.
+    pub description: Option<String>,
+
+    /// The hardware address of the interface, if it has one.
+    pub mac: Option<[u8; 6]>,
+
+    /// Whether the interface is up and has a link.
+    pub link_up: bool,
+}
+
+/// List the network interfaces on this system.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// for interface in ethercrab::std::interfaces().expect("Failed to list interfaces") {
+///     println!(
+///         "{} ({}): link {}",
+///         interface.name,
+///         interface.friendly_name.as_deref().unwrap_or("no friendly name"),
+///         if interface.link_up { "up" } else { "down" }
+///     );
+/// }
+/// ```
+pub fn interfaces() -> io::Result<Vec<Interface>> {
+    platform_interfaces()
+}
+
+/// Resolve a friendly name, e.g. `Ethernet 2`, to the interface name used to open a socket.
+///
+/// `name` is returned unchanged if it is already an interface name, or if no interface with a
+/// matching friendly name could be found.
+pub(crate) fn resolve_interface(name: &str) -> String {
+    let Ok(interfaces) = interfaces() else {
+        return name.to_string();
+    };
+
+    if interfaces.iter().any(|interface| interface.name == name) {
+        return name.to_string();
+    }
+
+    interfaces
+        .into_iter()
+        .find(|interface| interface.friendly_name.as_deref() == Some(name))
+        .map(|interface| {
+            fmt::debug!("Resolved interface {} to {}", name, interface.name);
+
+            interface.name
+        })
+        .unwrap_or_else(|| name.to_string())
+}
+
+#[cfg(unix)]
+fn platform_interfaces() -> io::Result<Vec<Interface>> {
+    use nix::net::if_::InterfaceFlags;
+
+    let mut interfaces: Vec<Interface> = Vec::new();
+
+    // Each interface is returned once per address
+    for address in nix::ifaddrs::getifaddrs()? {
+        let mac = address
+            .address
+            .as_ref()
+            .and_then(|addr| addr.as_link_addr()?.addr());
+
+        if let Some(interface) = interfaces
+            .iter_mut()
+            .find(|interface| interface.name == address.interface_name)
+        {
+            interface.mac = interface.mac.or(mac);
+
+            continue;
+        }
+
+        interfaces.push(Interface {
+            friendly_name: sys_class_net(&address.interface_name, "ifalias"),
+            description: driver_name(&address.interface_name),
+            mac,
+            link_up: address
+                .flags
+                .contains(InterfaceFlags::IFF_UP | InterfaceFlags::IFF_RUNNING),
+            name: address.interface_name,
+        });
+    }
+
+    Ok(interfaces)
+}
+
+/// Read an attribute of an interface from `/sys/class/net`.
+#[cfg(unix)]
+fn sys_class_net(_interface: &str, _attribute: &str) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let value = std::fs::read_to_string(format!("/sys/class/net/{_interface}/{_attribute}"))
+            .ok()?
+            .trim()
+            .to_string();
+
+        (!value.is_empty()).then_some(value)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    None
+}
+
+#[cfg(unix)]
+fn driver_name(_interface: &str) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let driver =
+            std::fs::read_link(format!("/sys/class/net/{_interface}/device/driver")).ok()?;
+
+        Some(driver.file_name()?.to_string_lossy().into_owned())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn platform_interfaces() -> io::Result<Vec<Interface>> {
+    use std::ffi::CStr;
+    use windows_sys::Win32::{
+        Foundation::{ERROR_BUFFER_OVERFLOW, NO_ERROR},
+        NetworkManagement::{
+            IpHelper::{
+                GetAdaptersAddresses, GAA_FLAG_SKIP_ANYCAST, GAA_FLAG_SKIP_DNS_SERVER,
+                GAA_FLAG_SKIP_MULTICAST, IP_ADAPTER_ADDRESSES_LH,
+            },
+            Ndis::IfOperStatusUp,
+        },
+        Networking::WinSock::AF_UNSPEC,
+    };
+
+    let flags = GAA_FLAG_SKIP_ANYCAST | GAA_FLAG_SKIP_MULTICAST | GAA_FLAG_SKIP_DNS_SERVER;
+
+    // Initial size recommended by the `GetAdaptersAddresses` docs. `u64` keeps the buffer aligned
+    // for `IP_ADAPTER_ADDRESSES_LH`.
+    let mut size: u32 = 15_000;
+    let mut buf: Vec<u64> = Vec::new();
+
+    loop {
+        buf.resize((size as usize).div_ceil(8), 0);
+
+        // SAFETY: `buf` is at least `size` bytes long.
+        let res = unsafe {
+            GetAdaptersAddresses(
+                u32::from(AF_UNSPEC),
+                flags,
+                core::ptr::null(),
+                buf.as_mut_ptr().cast(),
+                &mut size,
+            )
+        };
+
+        match res {
+            NO_ERROR => break,
+            // `size` now holds the required buffer size
+            ERROR_BUFFER_OVERFLOW => continue,
+            e => return Err(io::Error::from_raw_os_error(e as i32)),
+        }
+    }
+
+    let mut interfaces = Vec::new();
+    let mut adapter = buf.as_ptr().cast::<IP_ADAPTER_ADDRESSES_LH>();
+
+    // SAFETY: `GetAdaptersAddresses` returns a linked list of adapters within `buf`.
+    while let Some(current) = unsafe { adapter.as_ref() } {
+        // SAFETY: `AdapterName` is a NUL terminated string, e.g. `{GUID}`.
+        let guid = unsafe { CStr::from_ptr(current.AdapterName.cast()) }.to_string_lossy();
+
+        let mac = (current.PhysicalAddressLength == 6).then(|| {
+            let mut mac = [0u8; 6];
+
+            mac.copy_from_slice(&current.PhysicalAddress[0..6]);
+
+            mac
+        });
+
+        interfaces.push(Interface {
+            // Npcap device name
+            name: format!("\\Device\\NPF_{}", guid),
+            friendly_name: wide_string(current.FriendlyName),
+            description: wide_string(current.Description),
+            mac,
+            link_up: current.OperStatus == IfOperStatusUp,
+        });
+
+        adapter = current.Next;
+    }
+
+    Ok(interfaces)
+}
+
+/// Convert a NUL terminated UTF-16 string to a `String`, returning `None` if it is empty.
+#[cfg(target_os = "windows")]
+fn wide_string(ptr: *const u16) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    // SAFETY: `ptr` points to a NUL terminated string.
+    let s = unsafe {
+        let len = (0..).take_while(|&i| *ptr.add(i) != 0).count();
+
+        String::from_utf16_lossy(core::slice::from_raw_parts(ptr, len))
+    };
+
+    (!s.is_empty()).then_some(s)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loopback() {
+        let interfaces = interfaces().expect("List interfaces");
+
+        let lo = interfaces
+            .iter()
+            .find(|interface| interface.name == "lo")
+            .expect("No loopback interface");
+
+        assert_eq!(lo.mac, Some([0u8; 6]));
+    }
+
+    #[test]
+    fn resolve_unknown() {
+        assert_eq!(resolve_interface("lo"), "lo");
+        assert_eq!(resolve_interface("not-an-interface"), "not-an-interface");
+    }
+}
@@ -4,12 +4,11 @@ use crate::{
     std::unix::RawSocketDesc,
     PduRx, PduTx,
 };
-use core::{mem::MaybeUninit, task::Waker};
-use io_uring::{opcode, IoUring};
-use smallvec::{smallvec, SmallVec};
+use core::task::Waker;
+use io_uring::{opcode, squeue::Entry, types, IoUring};
 use std::{
     io,
-    os::fd::AsRawFd,
+    os::fd::{AsRawFd, RawFd},
     sync::Arc,
     task::Wake,
     thread::{self, Thread},
@@ -19,6 +18,103 @@ use std::{
 /// Use the upper bit of a u64 to mark whether a frame is a write (`1`) or a read (`0`).
 const WRITE_MASK: u64 = 1 << 63;
 const ENTRIES: usize = 256;
+/// Twice as many buffers as ring entries are required as each frame requires a send _and_ receive
+/// buffer.
+const BUFFERS: usize = ENTRIES * 2;
+
+/// Frame buffers shared with the kernel.
+///
+/// Each in-flight send or receive owns one MTU-sized chunk of a single allocation, indexed by its
+/// slab key. If the buffers could be registered with the ring, `ReadFixed` and `WriteFixed` are used
+/// so the kernel doesn't have to map the buffer for every frame.
+struct FrameBuffers {
+    data: Box<[u8]>,
+    mtu: usize,
+    registered: bool,
+}
+
+impl FrameBuffers {
+    fn new(mtu: usize) -> Self {
+        Self {
+            data: vec![0u8; BUFFERS * mtu].into_boxed_slice(),
+            mtu,
+            registered: false,
+        }
+    }
+
+    /// Attempt to register the buffers with `ring`.
+    ///
+    /// The buffers must outlive the ring.
+    fn register(&mut self, ring: &IoUring) {
+        let iovecs = self
+            .data
+            .chunks_exact_mut(self.mtu)
+            .map(|chunk| libc::iovec {
+                iov_base: chunk.as_mut_ptr().cast(),
+                iov_len: chunk.len(),
+            })
+            .collect::<Vec<_>>();
+
+        // SAFETY: `data` is never reallocated and is created before and therefore dropped after the
+        // ring in `tx_rx_task_io_uring`. Dropping the ring unregisters the buffers.
+        self.registered = match unsafe { ring.submitter().register_buffers(&iovecs) } {
+            Ok(()) => true,
+            Err(e) => {
+                // Registered buffers count towards `RLIMIT_MEMLOCK` on older kernels which may be
+                // too small.
+                fmt::warn!(
+                    "Failed to register io_uring buffers, falling back to unregistered buffers: {}",
+                    e
+                );
+
+                false
+            }
+        };
+    }
+
+    fn get(&self, key: usize) -> Result<&[u8], Error> {
+        self.data
+            .chunks_exact(self.mtu)
+            .nth(key)
+            .ok_or(Error::Internal)
+    }
+
+    fn get_mut(&mut self, key: usize) -> Result<&mut [u8], Error> {
+        self.data
+            .chunks_exact_mut(self.mtu)
+            .nth(key)
+            .ok_or(Error::Internal)
+    }
+
+    /// Create a write of the first `len` bytes of the buffer for `key`.
+    fn write_entry(&self, fd: RawFd, key: usize, len: usize) -> Result<Entry, Error> {
+        let buf = self.get(key)?.get(0..len).ok_or(Error::Internal)?;
+        let fd = types::Fd(fd);
+
+        let entry = if self.registered {
+            opcode::WriteFixed::new(fd, buf.as_ptr(), buf.len() as _, key as u16).build()
+        } else {
+            opcode::Write::new(fd, buf.as_ptr(), buf.len() as _).build()
+        };
+
+        Ok(entry)
+    }
+
+    /// Create a read into the buffer for `key`.
+    fn read_entry(&mut self, fd: RawFd, key: usize) -> Result<Entry, Error> {
+        let registered = self.registered;
+        let buf = self.get_mut(key)?;
+        let fd = types::Fd(fd);
+
+        let entry = if registered {
+            opcode::ReadFixed::new(fd, buf.as_mut_ptr(), buf.len() as _, key as u16).build()
+        } else {
+            opcode::Read::new(fd, buf.as_mut_ptr(), buf.len() as _).build()
+        };
+
+        Ok(entry)
+    }
+}
 
 struct ParkSignal {
     current_thread: Thread,
@@ -50,6 +146,10 @@ impl Wake for ParkSignal {
 ///
 /// This function is only available on `linux` targets as it requires `io_uring` support. Older
 /// kernels may not support `io_uring`.
+///
+/// Frames are sent and received from buffers registered with the ring where possible, avoiding the
+/// per-frame cost of mapping each buffer into the kernel. If registration fails, e.g. because
+/// `RLIMIT_MEMLOCK` is too low, unregistered buffers are used instead.
 pub fn tx_rx_task_io_uring<'sto>(
     interface: &str,
     mut pdu_tx: PduTx<'sto>,
@@ -68,16 +168,21 @@ pub fn tx_rx_task_io_uring<'sto>(
     // MTU is payload size. We need to add the layer 2 header which is 18 bytes.
     let mtu = mtu + 18;
 
-    // SAFETY: Max entries is 256 because `PduStorage::N` is checked to be in 0..u8::MAX, and will
-    // eventually be a `u8` once const generics get there. Twice as much space is reserved as each
-    // frame requires a send _and_ receive buffer.
-    //
-    // This data MUST NOT MOVE or be reordered once created as io_uring holds pointers into it.
-    let mut bufs: slab::Slab<(io_uring::squeue::Entry, SmallVec<[u8; 1518]>)> =
-        slab::Slab::with_capacity(ENTRIES * 2);
+    // This data MUST NOT MOVE once created as io_uring holds pointers into it. It must also be
+    // created before the ring so it is dropped after it.
+    let mut buffers = FrameBuffers::new(mtu);
 
     let mut ring = IoUring::new(ENTRIES as u32)?;
 
+    buffers.register(&ring);
+
+    // Max entries is 256 because `PduStorage::N` is checked to be in 0..u8::MAX, and will
+    // eventually be a `u8` once const generics get there, so slab keys are always valid buffer
+    // indices.
+    let mut bufs: slab::Slab<Entry> = slab::Slab::with_capacity(BUFFERS);
+
+    let fd = socket.as_raw_fd();
+
     let mut high_water_mark = 0;
 
     let signal = Arc::new(ParkSignal::new());
@@ -93,29 +198,24 @@ pub fn tx_rx_task_io_uring<'sto>(
 
             let tx_b = bufs.vacant_entry();
             let tx_key = tx_b.key();
-            let (tx_entry, tx_buf) = tx_b.insert((
-                unsafe { MaybeUninit::zeroed().assume_init() },
-                smallvec![0; mtu],
-            ));
 
             frame
                 .send_blocking(|data: &[u8]| {
-                    *tx_entry = opcode::Write::new(
-                        io_uring::types::Fd(socket.as_raw_fd()),
-                        data.as_ptr(),
-                        data.len() as _,
-                    )
-                    .build()
-                    // Distinguish sent frames from received frames by using the upper bit of
-                    // the user data as a flag.
-                    .user_data(tx_key as u64 | WRITE_MASK);
-
                     // TODO: Zero copy
-                    tx_buf
+                    buffers
+                        .get_mut(tx_key)?
                         .get_mut(0..data.len())
                         .ok_or(Error::Internal)?
                         .copy_from_slice(data);
 
+                    let tx_entry = tx_b.insert(
+                        buffers
+                            .write_entry(fd, tx_key, data.len())?
+                            // Distinguish sent frames from received frames by using the upper bit
+                            // of the user data as a flag.
+                            .user_data(tx_key as u64 | WRITE_MASK),
+                    );
+
                     while unsafe { ring.submission().push(tx_entry).is_err() } {
                         // If the submission queue is full, flush it to the kernel
                         ring.submit().expect("Internal error, failed to submit ops");
@@ -129,18 +229,12 @@ pub fn tx_rx_task_io_uring<'sto>(
 
             let rx_b = bufs.vacant_entry();
             let rx_key = rx_b.key();
-            let (rx_entry, rx_buf) = rx_b.insert((
-                unsafe { MaybeUninit::zeroed().assume_init() },
-                smallvec![0; mtu],
-            ));
-
-            *rx_entry = opcode::Read::new(
-                io_uring::types::Fd(socket.as_raw_fd()),
-                rx_buf.as_mut_ptr() as _,
-                rx_buf.len() as _,
-            )
-            .build()
-            .user_data(rx_key as u64);
+            let rx_entry = rx_b.insert(
+                buffers
+                    .read_entry(fd, rx_key)
+                    .map_err(io::Error::other)?
+                    .user_data(rx_key as u64),
+            );
 
             fmt::trace!(
                 "Insert frame TX {:#04x}, key {}, RX key {}",
@@ -213,7 +307,7 @@ pub fn tx_rx_task_io_uring<'sto>(
             if recv.result() == -libc::EWOULDBLOCK {
                 fmt::trace!("Frame key {} would block. Queuing for retry", key);
 
-                let (rx_entry, _buf) = bufs.get(key as usize).expect("Could not get retry entry");
+                let rx_entry = bufs.get(key as usize).expect("Could not get retry entry");
 
                 // SAFETY: `submission_shared` must not be held at the same time this one is
                 while unsafe { ring.submission_shared().push(rx_entry).is_err() } {
@@ -221,7 +315,9 @@ pub fn tx_rx_task_io_uring<'sto>(
                     ring.submit().expect("Internal error, failed to submit ops");
                 }
             } else {
-                let (_entry, frame) = bufs.remove(key as usize);
+                bufs.remove(key as usize);
+
+                let frame = buffers.get(key as usize).map_err(io::Error::other)?;
 
                 let frame_index = frame
                     .get(0x11)
@@ -235,7 +331,7 @@ pub fn tx_rx_task_io_uring<'sto>(
                 );
 
                 loop {
-                    match pdu_rx.receive_frame(&frame) {
+                    match pdu_rx.receive_frame(frame) {
                         Ok(()) => break,
                         Err(Error::Pdu(PduError::NoWaker)) => {
                             fmt::trace!(
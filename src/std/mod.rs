@@ -1,16 +1,61 @@
 //! Items required for running in `std` environments.
 
+#[cfg(all(target_os = "linux", feature = "dpdk", not(ethercrab_dpdk_missing)))]
+mod dpdk;
+#[cfg(all(target_os = "linux", feature = "dpdk", ethercrab_dpdk_missing))]
+#[path = "dpdk/missing.rs"]
+mod dpdk;
+mod interfaces;
 #[cfg(target_os = "linux")]
 mod io_uring;
+#[cfg(feature = "pcap")]
+mod pcap;
+mod prometheus;
+#[cfg(target_os = "linux")]
+mod realtime;
+#[cfg(feature = "pcap")]
+mod replay;
+#[cfg(target_os = "linux")]
+mod socket;
+#[cfg(target_os = "linux")]
+mod timestamps;
+#[cfg(all(unix, feature = "tokio"))]
+mod tokio;
+mod transport;
+mod udp;
 #[cfg(unix)]
 mod unix;
 #[cfg(target_os = "windows")]
 mod windows;
+#[cfg(target_os = "linux")]
+mod xdp;
 
+#[cfg(all(target_os = "linux", feature = "dpdk"))]
+pub use self::dpdk::tx_rx_task_dpdk;
+pub use self::interfaces::{interfaces, Interface};
+#[cfg(feature = "pcap")]
+pub use self::pcap::PcapCapture;
+pub use self::prometheus::PrometheusEncoder;
+#[cfg(feature = "pcap")]
+pub use self::replay::tx_rx_task_replay;
+#[cfg(all(unix, feature = "tokio"))]
+pub use self::tokio::tx_rx_task_tokio;
+pub use self::transport::{Transport, TxRxTask};
+pub use self::udp::{tx_rx_task_udp, ETHERCAT_UDP_PORT};
 #[cfg(target_os = "windows")]
 pub use self::windows::{ethercat_now, tx_rx_task};
 #[cfg(unix)]
-pub use unix::{ethercat_now, tx_rx_task};
+pub use unix::{ethercat_now, monitor_task, tx_rx_task, tx_rx_task_split};
 // io_uring is Linux-only
 #[cfg(target_os = "linux")]
 pub use io_uring::tx_rx_task_io_uring;
+#[cfg(target_os = "linux")]
+pub use realtime::RealtimeConfig;
+#[cfg(target_os = "linux")]
+pub use socket::SocketConfig;
+#[cfg(target_os = "linux")]
+pub use timestamps::{FrameTimestamp, FrameTimestamps, TimestampSource};
+#[cfg(target_os = "linux")]
+pub use unix::tx_rx_task_with_config;
+#[cfg(target_os = "linux")]
+pub use xdp::tx_rx_task_xdp;
@@ -0,0 +1,257 @@
+//! Record sent and received frames to a `.pcapng` file.
+
+use crate::{fmt, FrameDirection, FrameTap};
+use pcap_file::{
+    pcapng::{
+        blocks::{
+            enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption},
+            interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption},
+        },
+        PcapNgWriter,
+    },
+    DataLink, PcapError,
+};
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+/// `epb_flags` inbound direction.
+const FLAG_INBOUND: u32 = 0b01;
+/// `epb_flags` outbound direction.
+const FLAG_OUTBOUND: u32 = 0b10;
+
+/// Write every frame sent and received by the TX/RX task to a `.pcapng` file, which can be opened
+/// in Wireshark.
+///
+/// The capture is attached to the PDU loop with [`PduTx::set_frame_tap`](crate::PduTx::set_frame_tap)
+/// and [`PduRx::set_frame_tap`](crate::PduRx::set_frame_tap), and can then be started, stopped and
+/// rotated at any time while the application is running. Frames are discarded while no capture is
+/// running.
+///
+/// Requires the `pcap` feature.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use ethercrab::{std::PcapCapture, PduStorage};
+///
+/// static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+/// static CAPTURE: PcapCapture = PcapCapture::new();
+///
+/// let (mut tx, mut rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// tx.set_frame_tap(&CAPTURE);
+/// rx.set_frame_tap(&CAPTURE);
+///
+/// // Spawn the TX/RX task and initialise the MainDevice here
+///
+/// CAPTURE.start("ethercat.pcapng").expect("Start capture");
+///
+/// // ...
+///
+/// CAPTURE.stop().expect("Stop capture");
+/// ```
+#[derive(Default)]
+pub struct PcapCapture {
+    writer: Mutex<Option<PcapNgWriter<BufWriter<File>>>>,
+}
+
+impl core::fmt::Debug for PcapCapture {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PcapCapture")
+            .field("capturing", &self.is_capturing())
+            .finish()
+    }
+}
+
+impl PcapCapture {
+    /// Create a new capture that is not recording.
+    pub const fn new() -> Self {
+        Self {
+            writer: Mutex::new(None),
+        }
+    }
+
+    /// Start recording frames to a new file at `path`, overwriting it if it exists.
+    ///
+    /// If a capture is already running, its file is finished first and recording continues in the
+    /// new file.
+    pub fn start(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+
+        let writer = create_writer(path)?;
+
+        let previous = self.lock().replace(writer);
+
+        fmt::debug!("Started capture to {}", path.display());
+
+        finish(previous)
+    }
+
+    /// Finish the current capture file and continue recording in a new file at `path`.
+    ///
+    /// Unlike [`start`](PcapCapture::start), this method returns an error if no capture is
+    /// running, so a periodic rotation does not restart a capture that was stopped.
+    pub fn rotate(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if !self.is_capturing() {
+            return Err(io::Error::new(io::ErrorKind::Other, "no capture running"));
+        }
+
+        self.start(path)
+    }
+
+    /// Stop recording and flush the current capture file.
+    ///
+    /// This method does nothing if no capture is running.
+    pub fn stop(&self) -> io::Result<()> {
+        let previous = self.lock().take();
+
+        finish(previous)
+    }
+
+    /// Whether frames are currently being recorded.
+    pub fn is_capturing(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<PcapNgWriter<BufWriter<File>>>> {
+        // A panic while writing leaves at worst a truncated packet, so the capture can continue
+        self.writer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl FrameTap for PcapCapture {
+    fn frame(&self, direction: FrameDirection, ethernet_frame: &[u8]) {
+        let mut writer = self.lock();
+
+        let Some(w) = writer.as_mut() else {
+            return;
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+
+        let flags = match direction {
+            FrameDirection::Sent => FLAG_OUTBOUND,
+            FrameDirection::Received => FLAG_INBOUND,
+        };
+
+        let block = EnhancedPacketBlock {
+            interface_id: 0,
+            timestamp,
+            original_len: ethernet_frame.len() as u32,
+            data: Cow::Borrowed(ethernet_frame),
+            options: vec![EnhancedPacketOption::Flags(flags)],
+        };
+
+        if let Err(e) = w.write_pcapng_block(block) {
+            fmt::error!("Failed to write frame to capture, stopping: {}", e);
+
+            // Stop capturing so a full disk doesn't log an error for every frame
+            *writer = None;
+        }
+    }
+}
+
+fn create_writer(path: &Path) -> io::Result<PcapNgWriter<BufWriter<File>>> {
+    let file = BufWriter::new(File::create(path)?);
+
+    let mut writer = PcapNgWriter::new(file).map_err(pcap_error)?;
+
+    writer
+        .write_pcapng_block(InterfaceDescriptionBlock {
+            linktype: DataLink::ETHERNET,
+            snaplen: 0,
+            // `EnhancedPacketBlock` timestamps are written in nanoseconds
+            options: vec![InterfaceDescriptionOption::IfTsResol(9)],
+        })
+        .map_err(pcap_error)?;
+
+    Ok(writer)
+}
+
+fn finish(writer: Option<PcapNgWriter<BufWriter<File>>>) -> io::Result<()> {
+    match writer {
+        Some(writer) => writer.into_inner().flush(),
+        None => Ok(()),
+    }
+}
+
+fn pcap_error(e: PcapError) -> io::Error {
+    match e {
+        PcapError::IoError(e) => e,
+        e => io::Error::new(io::ErrorKind::Other, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pcap_file::pcapng::{Block, PcapNgReader};
+
+    fn read_frames(path: &Path) -> Vec<(u32, Vec<u8>)> {
+        let mut reader = PcapNgReader::new(File::open(path).unwrap()).unwrap();
+        let mut frames = Vec::new();
+
+        while let Some(block) = reader.next_block() {
+            if let Block::EnhancedPacket(packet) = block.unwrap() {
+                let flags = packet
+                    .options
+                    .iter()
+                    .find_map(|option| match option {
+                        EnhancedPacketOption::Flags(flags) => Some(*flags),
+                        _ => None,
+                    })
+                    .unwrap();
+
+                frames.push((flags, packet.data.into_owned()));
+            }
+        }
+
+        frames
+    }
+
+    #[test]
+    fn start_stop_rotate() {
+        let dir = std::env::temp_dir();
+        let first = dir.join(format!("ethercrab-capture-{}-1.pcapng", std::process::id()));
+        let second = dir.join(format!("ethercrab-capture-{}-2.pcapng", std::process::id()));
+
+        let capture = PcapCapture::new();
+
+        // Not capturing, so these are dropped
+        capture.frame(FrameDirection::Sent, &[0xaa; 60]);
+        assert!(capture.rotate(&first).is_err());
+
+        capture.start(&first).unwrap();
+        capture.frame(FrameDirection::Sent, &[0x01; 60]);
+        capture.frame(FrameDirection::Received, &[0x02; 60]);
+
+        capture.rotate(&second).unwrap();
+        capture.frame(FrameDirection::Sent, &[0x03; 60]);
+
+        capture.stop().unwrap();
+        assert!(!capture.is_capturing());
+        capture.frame(FrameDirection::Sent, &[0xbb; 60]);
+
+        assert_eq!(
+            read_frames(&first),
+            vec![
+                (FLAG_OUTBOUND, vec![0x01; 60]),
+                (FLAG_INBOUND, vec![0x02; 60])
+            ]
+        );
+        assert_eq!(read_frames(&second), vec![(FLAG_OUTBOUND, vec![0x03; 60])]);
+
+        std::fs::remove_file(first).unwrap();
+        std::fs::remove_file(second).unwrap();
+    }
+}
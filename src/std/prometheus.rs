@@ -0,0 +1,178 @@
+use crate::{Label, Metric, MetricKind, MetricsSink};
+use std::{fmt::Write, string::String};
+
+/// A [`MetricsSink`] that renders metrics in the
+/// [Prometheus text exposition format](https://prometheus.io/docs/instrumenting/exposition_formats/).
+///
+/// The rendered text can be served over HTTP with a `Content-Type` of
+/// `text/plain; version=0.0.4` to be scraped by Prometheus or any other compatible monitoring
+/// system.
+///
+/// # Examples
+///
+/// Serve metrics from a separate thread to one HTTP client at a time:
+///
+/// ```rust,no_run
+/// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts, std::PrometheusEncoder};
+/// use std::{io::{Read, Write}, net::TcpListener};
+///
+/// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+/// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+/// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+/// let diagnostics = maindevice.diagnostics();
+///
+/// std::thread::scope(|s| {
+///     s.spawn(move || {
+///         let listener = TcpListener::bind("0.0.0.0:9100").expect("bind");
+///
+///         for mut stream in listener.incoming().flatten() {
+///             // Discard the request
+///             let _ = stream.read(&mut [0u8; 1024]);
+///
+///             let mut encoder = PrometheusEncoder::new();
+///
+///             if let Ok(snapshot) = smol::block_on(diagnostics.metrics::<16>()) {
+///                 snapshot.write(&mut encoder);
+///             }
+///
+///             let body = encoder.finish();
+///
+///             let _ = write!(
+///                 stream,
+///                 "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
+///                 body.len(),
+///                 body
+///             );
+///         }
+///     });
+///
+///     // Run the process data cycle with `maindevice` here
+/// });
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct PrometheusEncoder {
+    out: String,
+}
+
+impl PrometheusEncoder {
+    /// Create an encoder with an empty output buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the rendered metrics.
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+impl MetricsSink for PrometheusEncoder {
+    fn describe(&mut self, metric: &Metric) {
+        let kind = match metric.kind {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+        };
+
+        // Writing to a `String` can't fail
+        let _ = writeln!(self.out, "# HELP {} {}", metric.name, metric.help);
+        let _ = writeln!(self.out, "# TYPE {} {}", metric.name, kind);
+    }
+
+    fn sample(&mut self, metric: &Metric, labels: &[Label], value: f64) {
+        self.out.push_str(metric.name);
+
+        if !labels.is_empty() {
+            self.out.push('{');
+
+            for (i, label) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.out.push(',');
+                }
+
+                // Label values are numbers so never need escaping
+                let _ = write!(self.out, "{}=\"{}\"", label.name, label.value);
+            }
+
+            self.out.push('}');
+        }
+
+        let _ = writeln!(self.out, " {}", value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ConfiguredAddress, ErrorCounters, LabelValue, MetricsSnapshot, PortErrors, Statistics,
+    };
+
+    #[test]
+    fn encode() {
+        let metric = Metric {
+            name: "ethercrab_port_rx_errors",
+            help: "Physical layer RX errors on a SubDevice port.",
+            kind: MetricKind::Gauge,
+        };
+
+        let mut encoder = PrometheusEncoder::new();
+
+        encoder.describe(&metric);
+        encoder.sample(
+            &metric,
+            &[
+                Label {
+                    name: "address",
+                    value: LabelValue::Address(ConfiguredAddress::new(0x1001)),
+                },
+                Label {
+                    name: "port",
+                    value: LabelValue::Port(1),
+                },
+            ],
+            3.0,
+        );
+
+        assert_eq!(
+            encoder.finish(),
+            "# HELP ethercrab_port_rx_errors Physical layer RX errors on a SubDevice port.\n\
+            # TYPE ethercrab_port_rx_errors gauge\n\
+            ethercrab_port_rx_errors{address=\"0x1001\",port=\"1\"} 3\n"
+        );
+    }
+
+    #[test]
+    fn encode_snapshot() {
+        let mut error_counters = heapless::Vec::new();
+
+        error_counters
+            .push(ErrorCounters {
+                configured_address: ConfiguredAddress::new(0x1000),
+                ports: [PortErrors::default(); 4],
+                processing_unit_errors: 0,
+                pdi_errors: 0,
+            })
+            .unwrap();
+
+        let snapshot = MetricsSnapshot::<1> {
+            statistics: Statistics {
+                pdu_retries: 4,
+                ..Statistics::default()
+            },
+            latency: None,
+            error_counters,
+        };
+
+        let mut encoder = PrometheusEncoder::new();
+
+        snapshot.write(&mut encoder);
+
+        let text = encoder.finish();
+
+        assert!(text.contains(
+            "# TYPE ethercrab_pdu_retries_total counter\nethercrab_pdu_retries_total 4\n"
+        ));
+        assert!(text.contains("ethercrab_port_lost_links{address=\"0x1000\",port=\"3\"} 0\n"));
+        assert!(text.contains("ethercrab_pdi_errors{address=\"0x1000\"} 0\n"));
+    }
+}
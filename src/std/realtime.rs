@@ -0,0 +1,213 @@
+//! Real-time scheduling helpers for threads running the TX/RX loop or process data cycles.
+
+use crate::fmt;
+use std::{hint::black_box, io, mem};
+
+/// How much of the current thread's stack to touch after locking memory, so later stack growth up
+/// to this size does not cause a page fault.
+const STACK_PREFAULT: usize = 128 * 1024;
+
+/// Real-time options applied to the current thread with [`RealtimeConfig::apply`].
+///
+/// The default configuration changes nothing.
+///
+/// Setting a `SCHED_FIFO` priority requires either root or a suitable `rtprio` limit, e.g.
+/// `<user> hard rtprio 99` and `<user> soft rtprio 99` in `/etc/security/limits.conf`. A kernel
+/// with `PREEMPT_RT` is recommended for consistent cycle times.
+///
+/// # Examples
+///
+/// Run the blocking `io_uring` TX/RX loop at priority 49, pinned to core 0:
+///
+/// ```rust,no_run
+/// use ethercrab::{
+///     std::{tx_rx_task_io_uring, RealtimeConfig},
+///     PduStorage,
+/// };
+///
+/// static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+///
+/// let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// std::thread::Builder::new()
+///     .name("tx-rx-thread".into())
+///     .spawn(move || {
+///         RealtimeConfig {
+///             priority: Some(49),
+///             cpu: Some(0),
+///             lock_memory: true,
+///         }
+///         .apply()
+///         .expect("Real-time config");
+///
+///         tx_rx_task_io_uring("eth0", tx, rx).expect("TX/RX task");
+///     })
+///     .expect("Spawn TX/RX thread");
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct RealtimeConfig {
+    /// Run the thread with the `SCHED_FIFO` policy at this priority, from 1 (lowest) to 99
+    /// (highest).
+    ///
+    /// Kernel interrupt threads run at priority 50 on `PREEMPT_RT` systems. Using a higher priority
+    /// can starve the network driver's interrupt handler.
+    pub priority: Option<u8>,
+
+    /// Pin the thread to this CPU core, e.g. one isolated with the `isolcpus` kernel parameter.
+    pub cpu: Option<usize>,
+
+    /// Lock all current and future memory of the process into RAM and prefault the thread's stack
+    /// so the thread is not delayed by page faults.
+    pub lock_memory: bool,
+}
+
+impl RealtimeConfig {
+    /// Apply this configuration to the calling thread.
+    ///
+    /// Options are applied in the order CPU affinity, memory locking, then priority, so the stack
+    /// is faulted in on the target core before the thread starts running at real-time priority.
+    pub fn apply(&self) -> io::Result<()> {
+        if let Some(cpu) = self.cpu {
+            pin_to_cpu(cpu)?;
+        }
+
+        if self.lock_memory {
+            lock_memory()?;
+        }
+
+        if let Some(priority) = self.priority {
+            set_fifo_priority(priority)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn pin_to_cpu(cpu: usize) -> io::Result<()> {
+    if cpu >= libc::CPU_SETSIZE as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("CPU {} out of range", cpu),
+        ));
+    }
+
+    // SAFETY: `cpu_set_t` is a plain bitmask and `cpu` was checked to be in range.
+    let res = unsafe {
+        let mut set: libc::cpu_set_t = mem::zeroed();
+
+        libc::CPU_SET(cpu, &mut set);
+
+        // A PID of 0 sets the affinity of the calling thread, not the whole process
+        libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    fmt::debug!("Pinned thread to CPU {}", cpu);
+
+    Ok(())
+}
+
+fn lock_memory() -> io::Result<()> {
+    // SAFETY: No pointers are passed.
+    let res = unsafe { libc::mlockall(libc::MCL_CURRENT | libc::MCL_FUTURE) };
+
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    prefault_stack();
+
+    fmt::debug!("Locked process memory");
+
+    Ok(())
+}
+
+/// Touch a block of stack so its pages are mapped, and therefore locked, before they're needed.
+#[inline(never)]
+fn prefault_stack() {
+    let mut stack = [0u8; STACK_PREFAULT];
+
+    black_box(&mut stack);
+}
+
+fn set_fifo_priority(priority: u8) -> io::Result<()> {
+    let priority = i32::from(priority);
+
+    // SAFETY: These functions only read their arguments.
+    let (min, max) = unsafe {
+        (
+            libc::sched_get_priority_min(libc::SCHED_FIFO),
+            libc::sched_get_priority_max(libc::SCHED_FIFO),
+        )
+    };
+
+    if !(min..=max).contains(&priority) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "SCHED_FIFO priority {} must be in {}..={}",
+                priority, min, max
+            ),
+        ));
+    }
+
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+
+    // SAFETY: `param` is a valid `sched_param`.
+    let res =
+        unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) };
+
+    // Unlike most libc functions, the error is returned instead of set in `errno`
+    if res != 0 {
+        let e = io::Error::from_raw_os_error(res);
+
+        if e.kind() == io::ErrorKind::PermissionDenied {
+            fmt::warn!(
+                "Setting SCHED_FIFO priority not permitted. Check the rtprio limit with `ulimit -Hr`"
+            );
+        }
+
+        return Err(e);
+    }
+
+    fmt::debug!("Set SCHED_FIFO priority {}", priority);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_does_nothing() {
+        assert!(RealtimeConfig::default().apply().is_ok());
+    }
+
+    #[test]
+    fn invalid_options() {
+        let cpu = RealtimeConfig {
+            cpu: Some(libc::CPU_SETSIZE as usize),
+            ..RealtimeConfig::default()
+        };
+
+        let priority = RealtimeConfig {
+            priority: Some(0),
+            ..RealtimeConfig::default()
+        };
+
+        assert_eq!(
+            cpu.apply().map_err(|e| e.kind()),
+            Err(io::ErrorKind::InvalidInput)
+        );
+        assert_eq!(
+            priority.apply().map_err(|e| e.kind()),
+            Err(io::ErrorKind::InvalidInput)
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! Replay a recorded capture as the network, so EtherCrab can be tested without hardware.
+
+use crate::{
+    error::Error,
+    ethernet::EthernetFrame,
+    fmt,
+    pdu_loop::{frame_header::EthercatFrameHeader, pdu_header::PduHeader},
+    PduRx, PduTx, ETHERCAT_ETHERTYPE,
+};
+use core::{future::Future, pin::Pin, task::Poll};
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
+use pcap_file::pcapng::{blocks::enhanced_packet::EnhancedPacketOption, Block, PcapNgReader};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io,
+    path::Path,
+};
+
+/// `epb_flags` direction mask.
+const FLAG_DIRECTION: u32 = 0b11;
+/// `epb_flags` outbound direction.
+const FLAG_OUTBOUND: u32 = 0b10;
+/// `epb_flags` inbound direction.
+const FLAG_INBOUND: u32 = 0b01;
+
+/// The parts of a frame used to pair a sent frame with its recorded response.
+///
+/// Fields that SubDevices modify, like working counters and position addresses, are ignored.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct FrameKey {
+    header: EthercatFrameHeader,
+    command_code: u8,
+    index: u8,
+    /// Only kept for commands that use a configured station address, as auto increment and
+    /// broadcast addresses are incremented by every SubDevice.
+    address: Option<[u8; 4]>,
+    irq: u16,
+}
+
+impl FrameKey {
+    fn new(ethernet_frame: &[u8]) -> Option<Self> {
+        let frame = EthernetFrame::new_checked(ethernet_frame).ok()?;
+
+        if frame.ethertype() != ETHERCAT_ETHERTYPE {
+            return None;
+        }
+
+        let payload = frame.payload();
+
+        let header = EthercatFrameHeader::unpack_from_slice(payload).ok()?;
+        let pdu =
+            PduHeader::unpack_from_slice(payload.get(EthercatFrameHeader::PACKED_LEN..)?).ok()?;
+
+        Some(Self {
+            header,
+            command_code: pdu.command_code,
+            index: pdu.index,
+            // FPRD and FPWR
+            address: matches!(pdu.command_code, 4 | 5).then_some(pdu.command_raw),
+            irq: pdu.irq,
+        })
+    }
+}
+
+/// Recorded frames, along with their packet number in the capture file.
+type FrameQueue = HashMap<FrameKey, VecDeque<(Vec<u8>, usize)>>;
+
+struct ReplayFut<'a> {
+    tx: PduTx<'a>,
+    rx: PduRx<'a>,
+    sends: FrameQueue,
+    responses: FrameQueue,
+}
+
+impl ReplayFut<'_> {
+    /// Check a sent frame against the recording and return the key of its response.
+    fn expect_sent(sends: &mut FrameQueue, sent: &[u8]) -> Result<FrameKey, Error> {
+        let key = FrameKey::new(sent).ok_or(Error::SendFrame)?;
+
+        let Some((expected, packet_number)) = sends.get_mut(&key).and_then(VecDeque::pop_front)
+        else {
+            fmt::error!("Sent frame {:?} is not in the recording", key);
+
+            return Err(Error::SendFrame);
+        };
+
+        if expected != sent {
+            fmt::error!(
+                "Sent frame does not match recorded packet {}:\n  expected {:02x?}\n  got {:02x?}",
+                packet_number,
+                expected,
+                sent
+            );
+
+            return Err(Error::SendFrame);
+        }
+
+        Ok(key)
+    }
+}
+
+impl Future for ReplayFut<'_> {
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut core::task::Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        this.tx.replace_waker(ctx.waker());
+
+        while let Some(frame) = this.tx.next_sendable_frame() {
+            let mut key = None;
+
+            let res = frame.send_blocking(|sent| {
+                key = Some(Self::expect_sent(&mut this.sends, sent)?);
+
+                Ok(sent.len())
+            });
+
+            let key = match (res, key) {
+                (Ok(_), Some(key)) => key,
+                (Err(e), _) => return Poll::Ready(Err(e)),
+                (Ok(_), None) => return Poll::Ready(Err(Error::Internal)),
+            };
+
+            let Some((response, _packet_number)) =
+                this.responses.get_mut(&key).and_then(VecDeque::pop_front)
+            else {
+                fmt::error!("No recorded response for {:?}", key);
+
+                return Poll::Ready(Err(Error::ReceiveFrame));
+            };
+
+            if let Err(e) = this.rx.receive_frame(&response) {
+                fmt::error!("Failed to receive recorded frame: {}", e);
+
+                return Poll::Ready(Err(Error::ReceiveFrame));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Create a TX/RX task that answers sent frames from a recorded `.pcapng` capture instead of a
+/// network interface.
+///
+/// This allows initialisation, configuration and process data logic to be regression tested
+/// without any hardware, e.g. in CI. Captures can be recorded with
+/// [`PcapCapture`](crate::std::PcapCapture), Wireshark or `tshark`, and should only contain
+/// EtherCAT frames.
+///
+/// Each sent frame is paired with a recorded one by its EtherCAT and first PDU headers, then
+/// compared byte for byte. The future returns an error if a frame differs from the recording or no
+/// matching frame is left. Frames are treated as responses if they are marked as inbound in the
+/// capture, or otherwise if their source MAC address has the locally administered bit set, as
+/// SubDevices set this bit in every frame they forward.
+///
+/// Requires the `pcap` feature.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use ethercrab::{std::tx_rx_task_replay, MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+///
+/// static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// tokio::spawn(tx_rx_task_replay("tests/ek1100.pcapng", tx, rx).expect("open replay"));
+///
+/// let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+///
+/// let group = maindevice
+///     .init_single_group::<16, 32>(|| 0)
+///     .await
+///     .expect("Init");
+/// # }
+/// ```
+pub fn tx_rx_task_replay<'sto>(
+    capture: impl AsRef<Path>,
+    pdu_tx: PduTx<'sto>,
+    pdu_rx: PduRx<'sto>,
+) -> Result<impl Future<Output = Result<(), Error>> + 'sto, io::Error> {
+    let mut reader = PcapNgReader::new(File::open(capture.as_ref())?)
+        .map_err(|e| invalid_data(e.to_string()))?;
+
+    let mut sends = FrameQueue::new();
+    let mut responses = FrameQueue::new();
+
+    // Indexed from 1 in the Wireshark UI
+    let mut packet_number = 0;
+
+    while let Some(block) = reader.next_block() {
+        packet_number += 1;
+
+        let block = block.map_err(|e| invalid_data(e.to_string()))?;
+
+        let Block::EnhancedPacket(packet) = block else {
+            continue;
+        };
+
+        let Some(key) = FrameKey::new(&packet.data) else {
+            return Err(invalid_data(format!(
+                "packet {} is not an EtherCAT frame",
+                packet_number
+            )));
+        };
+
+        let direction = packet.options.iter().find_map(|option| match option {
+            EnhancedPacketOption::Flags(flags) => Some(flags & FLAG_DIRECTION),
+            _ => None,
+        });
+
+        let is_response = match direction {
+            Some(FLAG_INBOUND) => true,
+            Some(FLAG_OUTBOUND) => false,
+            // Checked to be a valid Ethernet frame by `FrameKey::new`
+            _ => EthernetFrame::new_unchecked(&*packet.data).src_addr().0[0] & 0x02 != 0,
+        };
+
+        let queue = if is_response {
+            &mut responses
+        } else {
+            &mut sends
+        };
+
+        queue
+            .entry(key)
+            .or_default()
+            .push_back((packet.data.into_owned(), packet_number));
+    }
+
+    fmt::debug!(
+        "Loaded {} sent and {} response frames from capture",
+        sends.values().map(VecDeque::len).sum::<usize>(),
+        responses.values().map(VecDeque::len).sum::<usize>()
+    );
+
+    Ok(ReplayFut {
+        tx: pdu_tx,
+        rx: pdu_rx,
+        sends,
+        responses,
+    })
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
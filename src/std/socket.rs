@@ -0,0 +1,79 @@
+//! Tuning options for the Linux raw socket TX/RX task.
+
+use crate::std::FrameTimestamps;
+use core::time::Duration;
+
+/// Options for the raw socket opened by
+/// [`tx_rx_task_with_config`](crate::std::tx_rx_task_with_config), and how the task receives from
+/// it.
+///
+/// The default configuration leaves the kernel defaults in place.
+///
+/// Regardless of these options, the socket only receives EtherCAT frames sent by SubDevices. Other
+/// traffic on the interface, as well as frames sent by the MainDevice itself, is dropped by a BPF
+/// filter in the kernel so it doesn't wake the TX/RX task.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use ethercrab::{
+///     std::{tx_rx_task_with_config, SocketConfig},
+///     PduStorage,
+/// };
+/// use std::time::Duration;
+///
+/// static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// let config = SocketConfig {
+///     receive_buffer: Some(256 * 1024),
+///     busy_poll: Some(Duration::from_micros(200)),
+///     ..SocketConfig::default()
+/// };
+///
+/// tokio::spawn(tx_rx_task_with_config("eth0", tx, rx, config).expect("spawn TX/RX task"));
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SocketConfig {
+    /// Socket receive buffer size in bytes, `SO_RCVBUF`.
+    ///
+    /// With `CAP_NET_ADMIN`, the `net.core.rmem_max` limit is ignored. Otherwise the size is
+    /// silently capped to it. Note that the kernel doubles the requested value to allow for
+    /// bookkeeping overhead.
+    pub receive_buffer: Option<usize>,
+
+    /// Socket send buffer size in bytes, `SO_SNDBUF`.
+    ///
+    /// With `CAP_NET_ADMIN`, the `net.core.wmem_max` limit is ignored. Otherwise the size is
+    /// silently capped to it.
+    pub send_buffer: Option<usize>,
+
+    /// Timestamp every sent and received frame, `SO_TIMESTAMPING`.
+    ///
+    /// Timestamps are read through the given [`FrameTimestamps`] handle, and are taken either by
+    /// the kernel or, where the NIC supports it, in hardware. See [`FrameTimestamps`] for an
+    /// example.
+    ///
+    /// With software timestamps, the time between the kernel receiving each frame and the TX/RX
+    /// task reading it is also logged at `trace` level, which helps diagnose scheduling delays in
+    /// the receive path.
+    pub timestamping: Option<FrameTimestamps>,
+
+    /// Busy poll the socket for up to this long before waiting to be woken by the reactor.
+    ///
+    /// Each time the TX/RX task is polled, it keeps checking for received frames and new frames to
+    /// send until this budget runs out, instead of returning straight away. This removes the
+    /// reactor and executor wakeup latency, often tens of microseconds, from each response at the
+    /// cost of spinning a CPU core. It should only be used when the TX/RX task runs on its own
+    /// thread, ideally pinned to an isolated core with
+    /// [`RealtimeConfig`](crate::std::RealtimeConfig), as other tasks on the same executor thread
+    /// are blocked while it spins.
+    ///
+    /// A budget slightly longer than the expected network round trip time is recommended, so
+    /// responses are picked up while spinning but the thread still sleeps between cycles.
+    pub busy_poll: Option<Duration>,
+}
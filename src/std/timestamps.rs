@@ -0,0 +1,171 @@
+//! Kernel or NIC timestamps of sent and received EtherCAT frames.
+
+use core::time::Duration;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Where frame timestamps are taken.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimestampSource {
+    /// Timestamps are taken by the kernel network stack, using `CLOCK_REALTIME`.
+    ///
+    /// This works with any NIC, but includes driver and interrupt latency.
+    Software,
+
+    /// Timestamps are taken by the NIC as frames leave and arrive on the wire, using the NIC's PTP
+    /// hardware clock.
+    ///
+    /// Not all NICs support this. Check with `ethtool -T <interface>`. Hardware timestamping is
+    /// enabled for the whole interface, which requires `CAP_NET_ADMIN`.
+    Hardware,
+}
+
+/// Timestamps of one EtherCAT frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FrameTimestamp {
+    /// EtherCAT frame index.
+    pub frame_index: u8,
+
+    /// When the frame was sent.
+    ///
+    /// This is `None` if no transmit timestamp was reported before the response was received.
+    pub sent: Option<Duration>,
+
+    /// When the response to the frame was received.
+    pub received: Duration,
+}
+
+impl FrameTimestamp {
+    /// The time between the frame being sent and its response being received.
+    pub fn round_trip(&self) -> Option<Duration> {
+        self.sent.and_then(|sent| self.received.checked_sub(sent))
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    /// Transmit timestamps of frames still waiting for a response, indexed by frame index.
+    sent: [Option<Duration>; 256],
+    last: Option<FrameTimestamp>,
+}
+
+/// A handle to read frame timestamps captured by
+/// [`tx_rx_task_with_config`](crate::std::tx_rx_task_with_config).
+///
+/// Pass a clone of this handle in [`SocketConfig::timestamping`](crate::std::SocketConfig) to
+/// enable timestamping, then read the timestamps of the most recent frame with
+/// [`last`](FrameTimestamps::last), e.g. after each call to
+/// [`SubDeviceGroup::tx_rx`](crate::SubDeviceGroup::tx_rx).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use ethercrab::{
+///     std::{tx_rx_task_with_config, FrameTimestamps, SocketConfig, TimestampSource},
+///     PduStorage,
+/// };
+///
+/// static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// let timestamps = FrameTimestamps::new(TimestampSource::Hardware);
+///
+/// let config = SocketConfig {
+///     timestamping: Some(timestamps.clone()),
+///     ..SocketConfig::default()
+/// };
+///
+/// tokio::spawn(tx_rx_task_with_config("eth0", tx, rx, config).expect("spawn TX/RX task"));
+///
+/// // Send some frames...
+///
+/// if let Some(round_trip) = timestamps.last().and_then(|ts| ts.round_trip()) {
+///     println!("Round trip time {} ns", round_trip.as_nanos());
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrameTimestamps {
+    source: TimestampSource,
+    state: Arc<Mutex<State>>,
+}
+
+impl FrameTimestamps {
+    /// Create a new, empty set of timestamps.
+    pub fn new(source: TimestampSource) -> Self {
+        Self {
+            source,
+            state: Arc::new(Mutex::new(State {
+                sent: [None; 256],
+                last: None,
+            })),
+        }
+    }
+
+    /// Where timestamps are taken.
+    pub fn source(&self) -> TimestampSource {
+        self.source
+    }
+
+    /// Timestamps of the most recently received frame.
+    pub fn last(&self) -> Option<FrameTimestamp> {
+        self.lock().last
+    }
+
+    pub(in crate::std) fn record_sent(&self, frame_index: u8, timestamp: Duration) {
+        self.lock().sent[usize::from(frame_index)] = Some(timestamp);
+    }
+
+    pub(in crate::std) fn record_received(&self, frame_index: u8, timestamp: Duration) {
+        let mut state = self.lock();
+
+        let sent = state.sent[usize::from(frame_index)].take();
+
+        state.last = Some(FrameTimestamp {
+            frame_index,
+            sent,
+            received: timestamp,
+        });
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // Timestamps are plain data, so are still valid if another thread panicked
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let timestamps = FrameTimestamps::new(TimestampSource::Software);
+
+        assert_eq!(timestamps.last(), None);
+
+        timestamps.record_sent(3, Duration::from_micros(100));
+        timestamps.record_received(3, Duration::from_micros(150));
+
+        assert_eq!(
+            timestamps.last(),
+            Some(FrameTimestamp {
+                frame_index: 3,
+                sent: Some(Duration::from_micros(100)),
+                received: Duration::from_micros(150),
+            })
+        );
+        assert_eq!(
+            timestamps.last().and_then(|ts| ts.round_trip()),
+            Some(Duration::from_micros(50))
+        );
+
+        // Frame index reused without a transmit timestamp
+        timestamps.record_received(3, Duration::from_micros(300));
+
+        assert_eq!(timestamps.last().map(|ts| ts.sent), Some(None));
+        assert_eq!(timestamps.last().and_then(|ts| ts.round_trip()), None);
+    }
+}
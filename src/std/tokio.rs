@@ -0,0 +1,293 @@
+//! A TX/RX task driven by the `tokio` reactor.
+
+use crate::{
+    error::{saturate_u16, Error, PduError},
+    fmt,
+    pdu_loop::{PduRx, PduTx},
+    std::unix::RawSocketDesc,
+};
+use core::{future::Future, pin::Pin, task::Poll};
+use std::{
+    io::{self, Read, Write},
+    os::fd::AsRawFd,
+    thread,
+};
+use tokio::io::unix::AsyncFd;
+
+/// Sends and receives frames over a non-blocking socket registered with the `tokio` reactor. Each
+/// read or write must transfer exactly one Ethernet frame.
+struct TxRxFut<'a, S: AsRawFd> {
+    socket: AsyncFd<S>,
+    buf: Vec<u8>,
+    tx: PduTx<'a>,
+    rx: PduRx<'a>,
+}
+
+impl<S> Future for TxRxFut<'_, S>
+where
+    S: AsRawFd + Read + Write + Unpin,
+{
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut core::task::Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        // Re-register waker to make sure this future is polled again
+        this.tx.replace_waker(ctx.waker());
+
+        while let Some(frame) = this.tx.next_sendable_frame() {
+            let mut socket_busy = false;
+
+            let res = frame.send_blocking(|data| loop {
+                let mut guard = match this.socket.poll_write_ready_mut(ctx) {
+                    Poll::Ready(Ok(guard)) => guard,
+                    Poll::Ready(Err(e)) => {
+                        fmt::error!("Send PDU failed: {}", e);
+
+                        return Err(Error::SendFrame);
+                    }
+                    Poll::Pending => {
+                        socket_busy = true;
+
+                        // Releases the frame so it is sent again once the socket is writable
+                        return Err(Error::SendFrame);
+                    }
+                };
+
+                match guard.try_io(|socket| socket.get_mut().write(data)) {
+                    Ok(Ok(bytes_written)) if bytes_written != data.len() => {
+                        fmt::error!("Only wrote {} of {} bytes", bytes_written, data.len());
+
+                        return Err(Error::PartialSend {
+                            len: saturate_u16(data.len()),
+                            sent: saturate_u16(bytes_written),
+                        });
+                    }
+                    Ok(Ok(bytes_written)) => return Ok(bytes_written),
+                    Ok(Err(e)) => {
+                        fmt::error!("Send PDU failed: {}", e);
+
+                        return Err(Error::SendFrame);
+                    }
+                    // Readiness was cleared, so poll it again
+                    Err(_would_block) => continue,
+                }
+            });
+
+            if socket_busy {
+                break;
+            }
+
+            if let Err(e) = res {
+                fmt::error!("Send PDU failed: {}", e);
+
+                return Poll::Ready(Err(e));
+            }
+        }
+
+        // Read until the socket would block, which also registers the waker with the reactor
+        loop {
+            let mut guard = match this.socket.poll_read_ready_mut(ctx) {
+                Poll::Ready(Ok(guard)) => guard,
+                Poll::Ready(Err(e)) => {
+                    fmt::error!("Receive PDU failed: {}", e);
+
+                    break;
+                }
+                Poll::Pending => break,
+            };
+
+            let buf = &mut this.buf;
+
+            let n = match guard.try_io(|socket| socket.get_mut().read(buf)) {
+                Ok(Ok(n)) => n,
+                Ok(Err(e)) => {
+                    fmt::error!("Receive PDU failed: {}", e);
+
+                    break;
+                }
+                Err(_would_block) => continue,
+            };
+
+            if n == 0 {
+                fmt::warn!("Received zero bytes");
+            }
+
+            let packet = this.buf.get(0..n).ok_or(Error::Internal)?;
+
+            loop {
+                match this.rx.receive_frame(packet) {
+                    // Wait for frame RX future waker to be registered
+                    Err(Error::Pdu(PduError::NoWaker)) => thread::yield_now(),
+                    Err(e) => {
+                        fmt::error!("Failed to receive frame: {}", e);
+
+                        return Poll::Ready(Err(Error::ReceiveFrame));
+                    }
+                    Ok(()) => break,
+                }
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Create a TX/RX task that is driven by the `tokio` reactor.
+///
+/// This is equivalent to [`tx_rx_task`](crate::std::tx_rx_task), but registers the raw socket with
+/// the `tokio` reactor instead of `async-io`. The returned future should be spawned onto a `tokio`
+/// runtime.
+///
+/// Requires the `tokio` feature. The runtime needs the IO driver enabled. EtherCrab timeouts don't
+/// depend on `tokio`, so other EtherCrab futures can be run on any executor.
+///
+/// # Panics
+///
+/// This function panics if it is not called from within a `tokio` runtime.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use ethercrab::{std::tx_rx_task_tokio, MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+///
+/// static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// tokio::spawn(tx_rx_task_tokio("eth0", tx, rx).expect("spawn TX/RX task"));
+///
+/// let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+/// # }
+/// ```
+pub fn tx_rx_task_tokio<'sto>(
+    interface: &str,
+    pdu_tx: PduTx<'sto>,
+    #[allow(unused_mut)] mut pdu_rx: PduRx<'sto>,
+) -> Result<impl Future<Output = Result<(), Error>> + 'sto, io::Error> {
+    let mut socket = RawSocketDesc::new(interface)?;
+
+    // See `tx_rx_task` for why this is required on macOS.
+    #[cfg(all(not(target_os = "linux"), unix))]
+    if let Some(mac) = socket.mac().ok().flatten() {
+        fmt::debug!("Setting source MAC to {}", mac);
+
+        pdu_rx.set_source_mac(mac);
+    }
+
+    let mtu = socket.interface_mtu()?;
+
+    fmt::debug!("Opening {} with MTU {} using tokio", interface, mtu);
+
+    Ok(TxRxFut {
+        socket: AsyncFd::new(socket)?,
+        buf: vec![0; mtu],
+        tx: pdu_tx,
+        rx: pdu_rx,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Command, ConfiguredAddress, PduStorage, RetryBackoff};
+    use core::time::Duration;
+    use std::os::{fd::RawFd, unix::net::UnixDatagram};
+
+    /// One end of a datagram socket pair, standing in for a raw socket.
+    struct Datagram(UnixDatagram);
+
+    impl AsRawFd for Datagram {
+        fn as_raw_fd(&self) -> RawFd {
+            self.0.as_raw_fd()
+        }
+    }
+
+    impl Read for Datagram {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.recv(buf)
+        }
+    }
+
+    impl Write for Datagram {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.send(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn socketpair_round_trip() {
+        static STORAGE: PduStorage<1, 128> = PduStorage::new();
+        let (tx, rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let (socket, peer) = UnixDatagram::pair().unwrap();
+
+        socket.set_nonblocking(true).unwrap();
+
+        // Responds to every frame with a working counter of 1
+        let echo = thread::spawn(move || {
+            let mut buf = [0u8; 128];
+
+            let n = peer.recv(&mut buf).unwrap();
+
+            // SubDevices set the U/L bit of the source address of every frame they forward
+            buf[6] |= 0x02;
+
+            // Working counter after the Ethernet header, EtherCAT header, PDU header and 2 data
+            // bytes
+            buf[28] = 1;
+
+            peer.send(&buf[0..n]).unwrap();
+
+            buf[0..n].to_vec()
+        });
+
+        let task = TxRxFut {
+            socket: AsyncFd::new(Datagram(socket)).unwrap(),
+            buf: vec![0; 128],
+            tx,
+            rx,
+        };
+
+        let mut frame = pdu_loop.alloc_frame().unwrap();
+
+        let handle = frame
+            .push_pdu(
+                Command::fpwr(ConfiguredAddress::new(0x1000), 0x0120).into(),
+                [0xaau8, 0xbb],
+                None,
+            )
+            .unwrap();
+
+        let frame = frame.mark_sendable(
+            &pdu_loop,
+            Duration::from_secs(1),
+            usize::MAX,
+            RetryBackoff::None,
+        );
+
+        pdu_loop.wake_sender();
+
+        let response =
+            futures_lite::future::or(async { Ok(frame.await) }, async { Err(task.await) })
+                .await
+                .expect("task exited")
+                .expect("response");
+
+        let sent = echo.join().unwrap();
+
+        // EtherCAT EtherType
+        assert_eq!(&sent[12..14], &[0x88, 0xa4]);
+
+        let pdu = response.first_pdu(handle).unwrap();
+
+        assert_eq!(pdu.working_counter, 1);
+        assert_eq!(&*pdu, &[0xaa, 0xbb]);
+    }
+}
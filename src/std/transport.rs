@@ -0,0 +1,172 @@
+//! Choose how frames are sent and received at runtime.
+
+use crate::{error::Error, fmt, PduRx, PduTx};
+use core::{future::Future, pin::Pin, str::FromStr};
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr},
+};
+
+#[cfg(feature = "pcap")]
+use std::path::PathBuf;
+
+#[cfg(feature = "sim")]
+use crate::sim::SimNetwork;
+
+/// A boxed TX/RX task returned by [`Transport::into_task`].
+pub type TxRxTask<'sto> = Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'sto>>;
+
+/// The network transport used to send and receive EtherCAT frames.
+///
+/// Each variant corresponds to one of the TX/RX task functions in this module. Use this type
+/// instead of calling those functions directly if the transport is chosen at runtime, e.g. from a
+/// configuration file or command line argument.
+///
+/// A transport can be parsed from a string with [`FromStr`]:
+///
+/// | String                     | Transport                           |
+/// | -------------------------- | ----------------------------------- |
+/// | `eth0`, `raw:eth0`         | [`Transport::RawSocket`]            |
+/// | `udp:192.168.1.10`         | [`Transport::Udp`], default port    |
+/// | `udp:192.168.1.10:34980`   | [`Transport::Udp`]                  |
+/// | `replay:capture.pcapng`    | `Transport::Replay`, `pcap` feature |
+///
+/// UDP transports parsed from a string bind to [`ETHERCAT_UDP_PORT`](crate::std::ETHERCAT_UDP_PORT)
+/// on all interfaces.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use ethercrab::{std::Transport, MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+///
+/// static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let transport = std::env::args()
+///     .nth(1)
+///     .expect("Provide a transport, e.g. eth0 or udp:192.168.1.10")
+///     .parse::<Transport>()
+///     .expect("invalid transport");
+///
+/// let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// tokio::spawn(transport.into_task(tx, rx).expect("spawn TX/RX task"));
+///
+/// let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Transport {
+    /// Send raw Ethernet frames on a network interface with
+    /// [`tx_rx_task`](crate::std::tx_rx_task).
+    RawSocket {
+        /// Interface name or friendly name.
+        interface: String,
+    },
+    /// Send frames in UDP datagrams with [`tx_rx_task_udp`](crate::std::tx_rx_task_udp).
+    Udp {
+        /// Local address to bind to.
+        bind: SocketAddr,
+        /// Remote host, with an optional port.
+        remote: String,
+    },
+    /// Answer frames from a recorded capture with
+    /// [`tx_rx_task_replay`](crate::std::tx_rx_task_replay).
+    ///
+    /// Requires the `pcap` feature.
+    #[cfg(feature = "pcap")]
+    Replay {
+        /// Path to a `.pcapng` file.
+        capture: PathBuf,
+    },
+    /// Run the PDU loop against a simulated network with [`tx_rx_device`](crate::tx_rx_device).
+    ///
+    /// Requires the `sim` feature. See the [`sim`](crate::sim) module for why the returned task
+    /// should be run on the same thread as the application.
+    #[cfg(feature = "sim")]
+    Sim(SimNetwork),
+}
+
+impl Transport {
+    /// Open the transport and create its TX/RX task.
+    ///
+    /// The returned future must be spawned or otherwise polled concurrently with the application.
+    /// It only resolves if an error occurs.
+    pub fn into_task<'sto>(
+        self,
+        pdu_tx: PduTx<'sto>,
+        pdu_rx: PduRx<'sto>,
+    ) -> Result<TxRxTask<'sto>, io::Error> {
+        fmt::debug!("Opening transport {:?}", self);
+
+        Ok(match self {
+            Transport::RawSocket { interface } => {
+                Box::pin(crate::std::tx_rx_task(&interface, pdu_tx, pdu_rx)?)
+            }
+            Transport::Udp { bind, remote } => {
+                Box::pin(crate::std::tx_rx_task_udp(bind, &remote, pdu_tx, pdu_rx)?)
+            }
+            #[cfg(feature = "pcap")]
+            Transport::Replay { capture } => {
+                Box::pin(crate::std::tx_rx_task_replay(capture, pdu_tx, pdu_rx)?)
+            }
+            #[cfg(feature = "sim")]
+            Transport::Sim(network) => Box::pin(crate::tx_rx_device(network, pdu_tx, pdu_rx)),
+        })
+    }
+}
+
+impl FromStr for Transport {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((kind, rest)) = s.split_once(':') else {
+            return Ok(Transport::RawSocket {
+                interface: s.to_string(),
+            });
+        };
+
+        match kind {
+            "raw" => Ok(Transport::RawSocket {
+                interface: rest.to_string(),
+            }),
+            "udp" => Ok(Transport::Udp {
+                bind: SocketAddr::from((Ipv4Addr::UNSPECIFIED, crate::std::ETHERCAT_UDP_PORT)),
+                remote: rest.to_string(),
+            }),
+            #[cfg(feature = "pcap")]
+            "replay" => Ok(Transport::Replay {
+                capture: PathBuf::from(rest),
+            }),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown transport {:?}", kind),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert!(matches!(
+            "eth0".parse(),
+            Ok(Transport::RawSocket { interface }) if interface == "eth0"
+        ));
+        assert!(matches!(
+            "raw:Ethernet 2".parse(),
+            Ok(Transport::RawSocket { interface }) if interface == "Ethernet 2"
+        ));
+        assert!(matches!(
+            "udp:192.168.1.10:1234".parse(),
+            Ok(Transport::Udp { bind, remote })
+                if remote == "192.168.1.10:1234" && bind.port() == crate::std::ETHERCAT_UDP_PORT
+        ));
+        assert!("tcp:192.168.1.10".parse::<Transport>().is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! EtherCAT frames encapsulated in UDP/IP datagrams.
+
+use crate::{
+    error::{saturate_u16, Error, PduError},
+    ethernet::{EthernetAddress, EthernetFrame, ETHERNET_HEADER_LEN},
+    fmt,
+    pdu_loop::{PduRx, PduTx},
+    ETHERCAT_ETHERTYPE, MASTER_ADDR,
+};
+use async_io::Async;
+use core::{future::Future, pin::Pin, task::Poll};
+use std::{
+    io,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    thread,
+};
+
+/// The UDP port used by EtherCAT, `0x88A4`.
+pub const ETHERCAT_UDP_PORT: u16 = 0x88a4;
+
+/// Largest UDP payload that fits in a standard 1500 byte Ethernet MTU: 20 bytes of IPv4 header and
+/// 8 bytes of UDP header are subtracted.
+const MAX_DATAGRAM: usize = 1500 - 20 - 8;
+
+/// Source address of the Ethernet frames reconstructed from received datagrams.
+///
+/// This is the MainDevice address with the locally administered bit set, as it would be if a
+/// SubDevice had forwarded the frame, so it passes the filter in [`PduRx`].
+const RESPONSE_SOURCE: EthernetAddress = EthernetAddress([0x12, 0x10, 0x10, 0x10, 0x10, 0x10]);
+
+struct UdpFut<'a> {
+    socket: Async<UdpSocket>,
+    /// Received datagrams are read after an Ethernet header so they can be passed to `rx` as a
+    /// complete frame.
+    buf: Vec<u8>,
+    tx: PduTx<'a>,
+    rx: PduRx<'a>,
+}
+
+impl Future for UdpFut<'_> {
+    type Output = Result<(), Error>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut core::task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        // Re-register waker to make sure this future is polled again
+        this.tx.replace_waker(ctx.waker());
+
+        while let Some(frame) = this.tx.next_sendable_frame() {
+            let mut socket_busy = false;
+
+            let res = frame.send_blocking(|data| {
+                // Only the EtherCAT header and PDUs are sent. The IP and UDP headers take the
+                // place of the Ethernet header.
+                let datagram = data.get(ETHERNET_HEADER_LEN..).ok_or(Error::Internal)?;
+
+                match this.socket.poll_writable(ctx) {
+                    Poll::Ready(Ok(())) => (),
+                    Poll::Ready(Err(e)) => {
+                        fmt::error!("Send PDU failed: {}", e);
+
+                        return Err(Error::SendFrame);
+                    }
+                    Poll::Pending => {
+                        socket_busy = true;
+
+                        // Releases the frame so it is sent again once the socket is writable
+                        return Err(Error::SendFrame);
+                    }
+                }
+
+                match this.socket.get_ref().send(datagram) {
+                    Ok(bytes_written) if bytes_written != datagram.len() => {
+                        fmt::error!("Only wrote {} of {} bytes", bytes_written, datagram.len());
+
+                        Err(Error::PartialSend {
+                            len: saturate_u16(datagram.len()),
+                            sent: saturate_u16(bytes_written),
+                        })
+                    }
+                    Ok(_) => Ok(data.len()),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        socket_busy = true;
+
+                        Err(Error::SendFrame)
+                    }
+                    Err(e) => {
+                        fmt::error!("Send PDU failed: {}", e);
+
+                        Err(Error::SendFrame)
+                    }
+                }
+            });
+
+            if socket_busy {
+                break;
+            }
+
+            if let Err(e) = res {
+                fmt::error!("Send PDU failed: {}", e);
+
+                return Poll::Ready(Err(e));
+            }
+        }
+
+        // Read until the socket would block, which also registers the waker with the reactor
+        loop {
+            let n = match this
+                .socket
+                .get_ref()
+                .recv(&mut this.buf[ETHERNET_HEADER_LEN..])
+            {
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    match this.socket.poll_readable(ctx) {
+                        // Became readable between `recv` and registering the waker
+                        Poll::Ready(Ok(())) => continue,
+                        Poll::Ready(Err(e)) => {
+                            fmt::error!("Receive PDU failed: {}", e);
+                        }
+                        Poll::Pending => (),
+                    }
+
+                    break;
+                }
+                Err(e) => {
+                    // E.g. `ECONNREFUSED` caused by an ICMP response to a previous datagram
+                    fmt::error!("Receive PDU failed: {}", e);
+
+                    continue;
+                }
+            };
+
+            if n == 0 {
+                fmt::warn!("Received zero bytes");
+            }
+
+            let packet = &this.buf[0..(ETHERNET_HEADER_LEN + n)];
+
+            loop {
+                match this.rx.receive_frame(packet) {
+                    // Wait for frame RX future waker to be registered
+                    Err(Error::Pdu(PduError::NoWaker)) => thread::yield_now(),
+                    Err(e) => {
+                        fmt::error!("Failed to receive frame: {}", e);
+
+                        return Poll::Ready(Err(Error::ReceiveFrame));
+                    }
+                    Ok(()) => break,
+                }
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Create a TX/RX task that sends EtherCAT frames in UDP datagrams.
+///
+/// The EtherCAT header and PDUs of each frame are sent to port [`ETHERCAT_UDP_PORT`] on `remote`,
+/// and responses are accepted only from `remote`. This allows EtherCAT to be used where raw
+/// sockets aren't available or permitted, e.g. with simulators, test rigs or couplers that accept
+/// EtherCAT over UDP. If `remote` includes a port, it is used instead of the default.
+///
+/// The local socket is bound to `bind`. Devices commonly send responses back to the port the frame
+/// was addressed to, so `bind` should normally use [`ETHERCAT_UDP_PORT`] too.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use ethercrab::{
+///     std::{tx_rx_task_udp, ETHERCAT_UDP_PORT},
+///     MainDevice, MainDeviceConfig, PduStorage, Timeouts,
+/// };
+///
+/// static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// tokio::spawn(
+///     tx_rx_task_udp(("0.0.0.0", ETHERCAT_UDP_PORT), "192.168.1.10", tx, rx)
+///         .expect("spawn TX/RX task"),
+/// );
+///
+/// let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+/// # }
+/// ```
+pub fn tx_rx_task_udp<'sto>(
+    bind: impl ToSocketAddrs,
+    remote: &str,
+    pdu_tx: PduTx<'sto>,
+    pdu_rx: PduRx<'sto>,
+) -> Result<impl Future<Output = Result<(), Error>> + 'sto, io::Error> {
+    let remote = resolve_remote(remote)?;
+
+    let socket = UdpSocket::bind(bind)?;
+
+    socket.connect(remote)?;
+
+    fmt::debug!("Opening UDP socket {} to {}", socket.local_addr()?, remote);
+
+    let mut buf = vec![0; ETHERNET_HEADER_LEN + MAX_DATAGRAM];
+
+    let mut header = EthernetFrame::new_unchecked(&mut buf);
+
+    header.set_dst_addr(MASTER_ADDR);
+    header.set_src_addr(RESPONSE_SOURCE);
+    header.set_ethertype(ETHERCAT_ETHERTYPE);
+
+    Ok(UdpFut {
+        socket: Async::new(socket)?,
+        buf,
+        tx: pdu_tx,
+        rx: pdu_rx,
+    })
+}
+
+/// Resolve a remote address, adding [`ETHERCAT_UDP_PORT`] if no port is given.
+fn resolve_remote(remote: &str) -> Result<SocketAddr, io::Error> {
+    let mut addrs = match remote.to_socket_addrs() {
+        Ok(addrs) => addrs,
+        Err(_) => (remote, ETHERCAT_UDP_PORT).to_socket_addrs()?,
+    };
+
+    addrs.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("could not resolve {}", remote),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Command, ConfiguredAddress, PduStorage, RetryBackoff};
+    use core::time::Duration;
+
+    #[test]
+    fn loopback_round_trip() {
+        static STORAGE: PduStorage<1, 128> = PduStorage::new();
+        let (tx, rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        // Responds to every datagram with a working counter of 1
+        let peer = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let peer_addr = peer.local_addr().unwrap();
+
+        let task = tx_rx_task_udp("127.0.0.1:0", &peer_addr.to_string(), tx, rx).unwrap();
+
+        let echo = thread::spawn(move || {
+            let mut buf = [0u8; MAX_DATAGRAM];
+
+            let (n, from) = peer.recv_from(&mut buf).unwrap();
+
+            buf[n - 2] = 1;
+
+            peer.send_to(&buf[0..n], from).unwrap();
+
+            buf[0..n].to_vec()
+        });
+
+        let mut frame = pdu_loop.alloc_frame().unwrap();
+
+        let handle = frame
+            .push_pdu(
+                Command::fpwr(ConfiguredAddress::new(0x1000), 0x0120).into(),
+                [0xaau8, 0xbb],
+                None,
+            )
+            .unwrap();
+
+        let frame = frame.mark_sendable(
+            &pdu_loop,
+            Duration::from_secs(1),
+            usize::MAX,
+            RetryBackoff::None,
+        );
+
+        pdu_loop.wake_sender();
+
+        let response = smol::block_on(futures_lite::future::or(async { Ok(frame.await) }, async {
+            Err(task.await)
+        }))
+        .expect("task exited")
+        .expect("response");
+
+        let sent = echo.join().unwrap();
+
+        // No Ethernet header is sent
+        assert_eq!(&sent[0..2], &[0x0e, 0x10]);
+
+        let pdu = response.first_pdu(handle).unwrap();
+
+        assert_eq!(pdu.working_counter, 1);
+        assert_eq!(&*pdu, &[0xaa, 0xbb]);
+    }
+}
@@ -7,11 +7,13 @@ use crate::{
     ethernet::{EthernetAddress, ETHERNET_HEADER_LEN},
     fmt,
     std::unix::{ifreq, ifreq_for},
+    ETHERCAT_ETHERTYPE,
 };
 use async_io::IoSafe;
 use std::{
     io, mem,
     os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd},
+    sync::Mutex,
 };
 
 #[cfg(not(target_os = "netbsd"))]
@@ -55,6 +57,30 @@ const BIOCGBLEN: libc::c_ulong = 0x40044266;
     target_os = "freebsd"
 ))]
 const BIOCIMMEDIATE: libc::c_ulong = 0x80044270;
+/// set read filter program
+#[cfg(all(
+    any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "freebsd"
+    ),
+    target_pointer_width = "64"
+))]
+const BIOCSETF: libc::c_ulong = 0x80104267;
+/// set read filter program
+#[cfg(all(
+    any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "freebsd"
+    ),
+    target_pointer_width = "32"
+))]
+const BIOCSETF: libc::c_ulong = 0x80084267;
 /// set bpf_hdr struct size
 #[cfg(any(target_os = "macos", target_os = "ios", target_os = "netbsd"))]
 const SIZEOF_BPF_HDR: usize = 18;
@@ -77,6 +103,57 @@ const BPF_HDRLEN: usize = (((SIZEOF_BPF_HDR + ETHERNET_HEADER_LEN) + mem::align_
     & !(mem::align_of::<u32>() - 1))
     - ETHERNET_HEADER_LEN;
 
+/// A classic BPF instruction, `struct bpf_insn`.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct bpf_insn {
+    code: u16,
+    jt: u8,
+    jf: u8,
+    k: u32,
+}
+
+/// `struct bpf_program`.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct bpf_program {
+    bf_len: libc::c_uint,
+    bf_insns: *const bpf_insn,
+}
+
+/// Only accept EtherCAT frames so other traffic on the interface, e.g. from the host OS, isn't
+/// copied into userspace.
+static ETHERCAT_FILTER: [bpf_insn; 4] = [
+    // ldh [12] - load EtherType
+    bpf_insn {
+        code: 0x28,
+        jt: 0,
+        jf: 0,
+        k: 12,
+    },
+    // jeq #ETHERCAT_ETHERTYPE, accept, drop
+    bpf_insn {
+        code: 0x15,
+        jt: 0,
+        jf: 1,
+        k: ETHERCAT_ETHERTYPE as u32,
+    },
+    // accept: ret #-1 - return the entire frame
+    bpf_insn {
+        code: 0x06,
+        jt: 0,
+        jf: 0,
+        k: u32::MAX,
+    },
+    // drop: ret #0
+    bpf_insn {
+        code: 0x06,
+        jt: 0,
+        jf: 0,
+        k: 0,
+    },
+];
+
 #[cfg_attr(not(unix), allow(unused_macros))]
 macro_rules! try_ioctl {
     ($fd:expr,$cmd:expr,$req:expr) => {
@@ -97,7 +174,10 @@ pub struct BpfDevice {
     /// Interface name like `en11`.
     name: String,
     /// Holds additional frame data if more than one frame was returned in the `read` call.
-    buf: Vec<u8>,
+    ///
+    /// Behind a lock so the device can be shared between a TX and an RX task. Only the RX task
+    /// reads, so the lock is never contended.
+    buf: Mutex<Vec<u8>>,
 }
 
 impl AsRawFd for BpfDevice {
@@ -135,7 +215,7 @@ impl BpfDevice {
             fd: open_device()?,
             ifreq: ifreq_for(name),
             name: name.to_string(),
-            buf: Vec::with_capacity(4096),
+            buf: Mutex::new(Vec::with_capacity(4096)),
         };
 
         self_.bind_interface()?;
@@ -150,6 +230,13 @@ impl BpfDevice {
         try_ioctl!(self.fd, BIOCIMMEDIATE, &mut bufsize as *mut libc::c_int);
         try_ioctl!(self.fd, BIOCSETIF, &mut self.ifreq);
 
+        let mut filter = bpf_program {
+            bf_len: ETHERCAT_FILTER.len() as libc::c_uint,
+            bf_insns: ETHERCAT_FILTER.as_ptr(),
+        };
+
+        try_ioctl!(self.fd, BIOCSETF, &mut filter as *mut bpf_program);
+
         Ok(())
     }
 
@@ -207,11 +294,22 @@ unsafe impl IoSafe for BpfDevice {}
 
 impl io::Read for BpfDevice {
     fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        io::Read::read(&mut &*self, buffer)
+    }
+}
+
+impl io::Read for &BpfDevice {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut buf = self
+            .buf
+            .lock()
+            .map_err(|_| io::Error::other("poisoned BPF buffer"))?;
+
         // If more than one packet was returned in the previous call to `read`, the second and
         // further packets will be present in our buffer. We'll read the rest of the buffer out for
         // processing instead of reading the network interface.
-        let len = if !self.buf.is_empty() {
-            let len = self.buf.len().min(buffer.len());
+        let len = if !buf.is_empty() {
+            let len = buf.len().min(buffer.len());
 
             debug_assert!(
                 len >= BPF_HDRLEN,
@@ -222,11 +320,11 @@ impl io::Read for BpfDevice {
 
             fmt::trace!("{} bytes left from previous read", len);
 
-            let (cached_chunk, rest) = self.buf.split_at(len);
+            let (cached_chunk, rest) = buf.split_at(len);
 
             buffer[0..len].copy_from_slice(cached_chunk);
 
-            self.buf = rest.to_vec();
+            *buf = rest.to_vec();
 
             len
         } else {
@@ -276,7 +374,7 @@ impl io::Read for BpfDevice {
 
             // Store next chunk(s - there could be more than one packet waiting) of [BPF header,
             // Ethernet II frame] in cache for next time round.
-            self.buf = buffer[start..len].to_vec();
+            *buf = buffer[start..len].to_vec();
         }
 
         // Strip BPF header from beginning of buffer
@@ -294,6 +392,16 @@ impl io::Read for BpfDevice {
 }
 
 impl io::Write for BpfDevice {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        io::Write::write(&mut &*self, buffer)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl io::Write for &BpfDevice {
     fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
         unsafe {
             let len = libc::write(
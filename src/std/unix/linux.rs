@@ -2,11 +2,19 @@
 //! [https://github.com/embassy-rs/embassy](https://github.com/embassy-rs/embassy/blob/master/examples/std/src/tuntap.rs).
 
 use crate::{
-    std::unix::{ifreq, ifreq_for},
+    fmt,
+    std::{
+        interfaces::resolve_interface,
+        unix::{ifreq, ifreq_for},
+        FrameTimestamps, SocketConfig, TimestampSource,
+    },
     ETHERCAT_ETHERTYPE,
 };
 use async_io::IoSafe;
-use core::ptr::addr_of;
+use core::{
+    ptr::{addr_of, addr_of_mut},
+    time::Duration,
+};
 use std::{
     io, mem,
     os::{
@@ -15,13 +23,77 @@ use std::{
     },
 };
 
+/// Only accept EtherCAT frames that were received from the network. Other traffic on the interface,
+/// and the copies of frames sent by this socket that the kernel loops back to packet sockets, is
+/// dropped before it is queued on the socket.
+static ETHERCAT_FILTER: [libc::sock_filter; 6] = [
+    // ld [SKF_AD_OFF + SKF_AD_PKTTYPE] - load packet type
+    libc::sock_filter {
+        code: 0x20,
+        jt: 0,
+        jf: 0,
+        k: (libc::SKF_AD_OFF + libc::SKF_AD_PKTTYPE) as u32,
+    },
+    // jeq #PACKET_OUTGOING, drop, next
+    libc::sock_filter {
+        code: 0x15,
+        jt: 3,
+        jf: 0,
+        k: libc::PACKET_OUTGOING as u32,
+    },
+    // ldh [12] - load EtherType
+    libc::sock_filter {
+        code: 0x28,
+        jt: 0,
+        jf: 0,
+        k: 12,
+    },
+    // jeq #ETHERCAT_ETHERTYPE, accept, drop
+    libc::sock_filter {
+        code: 0x15,
+        jt: 0,
+        jf: 1,
+        k: ETHERCAT_ETHERTYPE as u32,
+    },
+    // accept: ret #-1 - return the entire frame
+    libc::sock_filter {
+        code: 0x06,
+        jt: 0,
+        jf: 0,
+        k: u32::MAX,
+    },
+    // drop: ret #0
+    libc::sock_filter {
+        code: 0x06,
+        jt: 0,
+        jf: 0,
+        k: 0,
+    },
+];
+
+/// Offset of the EtherCAT frame index (the first PDU's index) in an Ethernet II frame.
+const FRAME_INDEX_OFFSET: usize = 0x11;
+
+/// `struct ifreq` with a pointer in its union, as used by `SIOCSHWTSTAMP`.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct ifreq_data {
+    ifr_name: [libc::c_char; libc::IF_NAMESIZE],
+    ifr_data: *mut libc::c_void,
+    /// Pad to the size of the largest union member, `struct ifmap`.
+    _pad: [u8; 16],
+}
+
 pub struct RawSocketDesc {
     lower: i32,
     ifreq: ifreq,
+    /// Set if `SO_TIMESTAMPING` is enabled, so frames must be read with `recvmsg`.
+    timestamps: Option<FrameTimestamps>,
 }
 
 impl RawSocketDesc {
     pub fn new(name: &str) -> io::Result<Self> {
+        let name = resolve_interface(name);
         let protocol = ETHERCAT_ETHERTYPE as i16;
 
         let lower = unsafe {
@@ -39,14 +111,259 @@ impl RawSocketDesc {
 
         let mut self_ = RawSocketDesc {
             lower,
-            ifreq: ifreq_for(name),
+            ifreq: ifreq_for(&name),
+            timestamps: None,
         };
 
+        // Attach the filter before binding so no unwanted frames are queued in between
+        self_.attach_filter()?;
         self_.bind_interface()?;
 
         Ok(self_)
     }
 
+    /// Open a raw socket on the given interface and apply `config` to it.
+    pub fn with_config(name: &str, config: &SocketConfig) -> io::Result<Self> {
+        let mut self_ = Self::new(name)?;
+
+        if let Some(size) = config.receive_buffer {
+            self_.set_buffer_size(libc::SO_RCVBUFFORCE, libc::SO_RCVBUF, size)?;
+        }
+
+        if let Some(size) = config.send_buffer {
+            self_.set_buffer_size(libc::SO_SNDBUFFORCE, libc::SO_SNDBUF, size)?;
+        }
+
+        if let Some(timestamps) = &config.timestamping {
+            self_.enable_timestamping(timestamps.source())?;
+
+            self_.timestamps = Some(timestamps.clone());
+        }
+
+        Ok(self_)
+    }
+
+    fn enable_timestamping(&mut self, source: TimestampSource) -> io::Result<()> {
+        let flags = match source {
+            TimestampSource::Software => {
+                libc::SOF_TIMESTAMPING_TX_SOFTWARE
+                    | libc::SOF_TIMESTAMPING_RX_SOFTWARE
+                    | libc::SOF_TIMESTAMPING_SOFTWARE
+            }
+            TimestampSource::Hardware => {
+                self.enable_hardware_timestamping()?;
+
+                libc::SOF_TIMESTAMPING_TX_HARDWARE
+                    | libc::SOF_TIMESTAMPING_RX_HARDWARE
+                    | libc::SOF_TIMESTAMPING_RAW_HARDWARE
+            }
+        };
+
+        setsockopt(self.lower, libc::SO_TIMESTAMPING, flags as libc::c_int)
+    }
+
+    /// Ask the NIC driver to timestamp all sent and received frames.
+    fn enable_hardware_timestamping(&mut self) -> io::Result<()> {
+        let mut config = libc::hwtstamp_config {
+            flags: 0,
+            tx_type: libc::HWTSTAMP_TX_ON as libc::c_int,
+            rx_filter: libc::HWTSTAMP_FILTER_ALL as libc::c_int,
+        };
+
+        let mut ifreq = ifreq_data {
+            ifr_name: self.ifreq.ifr_name,
+            ifr_data: addr_of_mut!(config).cast(),
+            _pad: [0; 16],
+        };
+
+        // SAFETY: `ifreq` points to a valid `hwtstamp_config` which outlives the call.
+        #[allow(trivial_casts)]
+        let res = unsafe {
+            #[cfg(target_env = "musl")]
+            let res = libc::ioctl(
+                self.lower,
+                libc::SIOCSHWTSTAMP as libc::c_int,
+                &mut ifreq as *mut ifreq_data,
+            );
+            #[cfg(not(target_env = "musl"))]
+            let res = libc::ioctl(
+                self.lower,
+                libc::SIOCSHWTSTAMP,
+                &mut ifreq as *mut ifreq_data,
+            );
+
+            res
+        };
+
+        if res == -1 {
+            let e = io::Error::last_os_error();
+
+            fmt::error!(
+                "Failed to enable hardware timestamping: {}. Check NIC support with `ethtool -T`",
+                e
+            );
+
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    fn attach_filter(&mut self) -> io::Result<()> {
+        let filter = libc::sock_fprog {
+            len: ETHERCAT_FILTER.len() as libc::c_ushort,
+            // The kernel only reads the program
+            filter: ETHERCAT_FILTER.as_ptr().cast_mut(),
+        };
+
+        // SAFETY: `filter` points to a valid program which outlives the call.
+        let res = unsafe {
+            libc::setsockopt(
+                self.lower,
+                libc::SOL_SOCKET,
+                libc::SO_ATTACH_FILTER,
+                addr_of!(filter).cast(),
+                mem::size_of::<libc::sock_fprog>() as libc::socklen_t,
+            )
+        };
+
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Set a socket buffer size, ignoring the system limit if the process is allowed to.
+    fn set_buffer_size(
+        &mut self,
+        force_option: libc::c_int,
+        option: libc::c_int,
+        size: usize,
+    ) -> io::Result<()> {
+        let size = libc::c_int::try_from(size).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("socket buffer size {} too large", size),
+            )
+        })?;
+
+        match setsockopt(self.lower, force_option, size) {
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                fmt::debug!("No CAP_NET_ADMIN, socket buffer size is capped by the system limit");
+
+                setsockopt(self.lower, option, size)
+            }
+            res => res,
+        }
+    }
+
+    /// Read a frame, recording its receive timestamp and the transmit timestamps of any frames sent
+    /// since the last read.
+    fn read_timestamped(&self, timestamps: &FrameTimestamps, buf: &mut [u8]) -> io::Result<usize> {
+        let source = timestamps.source();
+
+        // Transmit timestamps are returned on the error queue along with a copy of the sent frame.
+        // `buf` is used as scratch space as it is overwritten by the received frame below.
+        loop {
+            match self.recv_timestamped(buf, libc::MSG_ERRQUEUE | libc::MSG_DONTWAIT, source) {
+                Ok((len, Some(sent))) if len > FRAME_INDEX_OFFSET => {
+                    timestamps.record_sent(buf[FRAME_INDEX_OFFSET], sent);
+                }
+                Ok(_) => (),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let (len, received) = self.recv_timestamped(buf, 0, source)?;
+
+        if let (Some(received), true) = (received, len > FRAME_INDEX_OFFSET) {
+            timestamps.record_received(buf[FRAME_INDEX_OFFSET], received);
+
+            if source == TimestampSource::Software {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default();
+
+                fmt::trace!(
+                    "Read frame {} ns after kernel receive",
+                    now.saturating_sub(received).as_nanos()
+                );
+            }
+        }
+
+        Ok(len)
+    }
+
+    /// Receive a frame with `recvmsg`, returning its length and timestamp.
+    fn recv_timestamped(
+        &self,
+        buf: &mut [u8],
+        flags: libc::c_int,
+        source: TimestampSource,
+    ) -> io::Result<(usize, Option<Duration>)> {
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr().cast(),
+            iov_len: buf.len(),
+        };
+
+        // Aligned for `cmsghdr`, and large enough for a `scm_timestamping` control message and a
+        // `sock_extended_err` on the error queue
+        let mut control = [0u64; 32];
+
+        // SAFETY: `msghdr` is valid when zeroed.
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_iov = addr_of_mut!(iov);
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        // `size_t` on glibc, `socklen_t` on musl
+        #[allow(trivial_numeric_casts)]
+        {
+            msg.msg_controllen = mem::size_of_val(&control) as _;
+        }
+
+        // SAFETY: `msg` points to buffers that are valid for the duration of the call.
+        let len = unsafe { libc::recvmsg(self.lower, &mut msg, flags) };
+
+        if len == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut timestamp = None;
+
+        // SAFETY: The control messages were filled in by the kernel and are bounded by
+        // `msg_controllen`.
+        unsafe {
+            let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+
+            while !cmsg.is_null() {
+                if (*cmsg).cmsg_level == libc::SOL_SOCKET
+                    && (*cmsg).cmsg_type == libc::SCM_TIMESTAMPING
+                {
+                    // `struct scm_timestamping`: software, deprecated, then raw hardware timestamp
+                    let ts = libc::CMSG_DATA(cmsg)
+                        .cast::<[libc::timespec; 3]>()
+                        .read_unaligned();
+
+                    let ts = match source {
+                        TimestampSource::Software => ts[0],
+                        TimestampSource::Hardware => ts[2],
+                    };
+
+                    // A zero timestamp means none was taken, e.g. the driver doesn't support it
+                    if ts.tv_sec != 0 || ts.tv_nsec != 0 {
+                        timestamp = Some(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32));
+                    }
+                }
+
+                cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+            }
+        }
+
+        Ok((len as usize, timestamp))
+    }
+
     fn bind_interface(&mut self) -> io::Result<()> {
         let protocol = ETHERCAT_ETHERTYPE as i16;
 
@@ -107,6 +424,18 @@ impl Drop for RawSocketDesc {
 
 impl io::Read for RawSocketDesc {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::Read::read(&mut &*self, buf)
+    }
+}
+
+// Reads and writes don't touch any Rust-side state, so the socket can be shared between a TX and
+// an RX task.
+impl io::Read for &RawSocketDesc {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(timestamps) = &self.timestamps {
+            return self.read_timestamped(timestamps, buf);
+        }
+
         let len = unsafe { libc::read(self.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
         if len == -1 {
             Err(io::Error::last_os_error())
@@ -117,6 +446,16 @@ impl io::Read for RawSocketDesc {
 }
 
 impl io::Write for RawSocketDesc {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::Write::write(&mut &*self, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl io::Write for &RawSocketDesc {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         let len = unsafe { libc::write(self.as_raw_fd(), buf.as_ptr().cast(), buf.len()) };
         if len == -1 {
@@ -131,6 +470,25 @@ impl io::Write for RawSocketDesc {
     }
 }
 
+fn setsockopt(lower: libc::c_int, option: libc::c_int, value: libc::c_int) -> io::Result<()> {
+    // SAFETY: `value` is a valid `c_int` which outlives the call.
+    let res = unsafe {
+        libc::setsockopt(
+            lower,
+            libc::SOL_SOCKET,
+            option,
+            addr_of!(value).cast(),
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
 fn ifreq_ioctl(
     lower: libc::c_int,
     ifreq: &mut ifreq,
@@ -6,44 +6,54 @@ mod bpf;
 mod linux;
 
 #[cfg(all(not(target_os = "linux"), unix))]
-use self::bpf::BpfDevice as RawSocketDesc;
+pub(in crate::std) use self::bpf::BpfDevice as RawSocketDesc;
 #[cfg(target_os = "linux")]
 pub(in crate::std) use self::linux::RawSocketDesc;
 
+#[cfg(target_os = "linux")]
+use crate::std::SocketConfig;
 use crate::{
-    error::{Error, PduError},
+    error::{saturate_u16, Error, PduError},
     fmt,
     pdu_loop::{PduRx, PduTx},
+    Monitor, MonitorEvent,
 };
 use async_io::Async;
 use core::{future::Future, pin::Pin, task::Poll};
-use futures_lite::{AsyncRead, AsyncWrite};
-use std::thread;
+use futures_lite::{AsyncRead, AsyncReadExt, AsyncWrite};
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 
-struct TxRxFut<'a> {
-    socket: Async<RawSocketDesc>,
-    mtu: usize,
+/// Sends frames as soon as they are queued, independently of any responses being received.
+struct TxFut<'a> {
+    socket: Arc<Async<RawSocketDesc>>,
     tx: PduTx<'a>,
-    rx: PduRx<'a>,
 }
 
-impl Future for TxRxFut<'_> {
+impl Future for TxFut<'_> {
     type Output = Result<(), Error>;
 
-    fn poll(mut self: Pin<&mut Self>, ctx: &mut core::task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, ctx: &mut core::task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
         // Re-register waker to make sure this future is polled again
-        self.tx.replace_waker(ctx.waker());
+        this.tx.replace_waker(ctx.waker());
+
+        while let Some(frame) = this.tx.next_sendable_frame() {
+            let mut socket_busy = false;
 
-        while let Some(frame) = self.tx.next_sendable_frame() {
             let res = frame.send_blocking(|data| {
-                match Pin::new(&mut self.socket).poll_write(ctx, data) {
+                match Pin::new(&mut &*this.socket).poll_write(ctx, data) {
                     Poll::Ready(Ok(bytes_written)) => {
                         if bytes_written != data.len() {
                             fmt::error!("Only wrote {} of {} bytes", bytes_written, data.len());
 
                             Err(Error::PartialSend {
-                                len: data.len(),
-                                sent: bytes_written,
+                                len: saturate_u16(data.len()),
+                                sent: saturate_u16(bytes_written),
                             })
                         } else {
                             Ok(bytes_written)
@@ -55,10 +65,19 @@ impl Future for TxRxFut<'_> {
 
                         Err(Error::SendFrame)
                     }
-                    Poll::Pending => Ok(0),
+                    Poll::Pending => {
+                        socket_busy = true;
+
+                        // Releases the frame so it is sent again once the socket is writable
+                        Err(Error::SendFrame)
+                    }
                 }
             });
 
+            if socket_busy {
+                break;
+            }
+
             if let Err(e) = res {
                 fmt::error!("Send PDU failed: {}", e);
 
@@ -66,40 +85,74 @@ impl Future for TxRxFut<'_> {
             }
         }
 
-        let mut buf = vec![0; self.mtu];
+        Poll::Pending
+    }
+}
+
+/// Receives response frames and wakes the futures waiting on them.
+struct RxFut<'a> {
+    socket: Arc<Async<RawSocketDesc>>,
+    buf: Vec<u8>,
+    rx: PduRx<'a>,
+    /// How long to keep polling the socket for a received frame before waiting to be woken.
+    busy_poll: Option<Duration>,
+}
 
-        match Pin::new(&mut self.socket).poll_read(ctx, &mut buf) {
-            Poll::Ready(Ok(n)) => {
-                fmt::trace!("Poll ready");
-                // Wake again in case there are more frames to consume. This is additionally
-                // important for macOS as multiple packets may be received for one `poll_read`
-                // call, but will only be returned during the _next_ `poll_read`. If this line
-                // is removed, PDU response frames are missed, causing timeout errors.
-                ctx.waker().wake_by_ref();
+impl Future for RxFut<'_> {
+    type Output = Result<(), Error>;
 
-                let packet = buf.get(0..n).ok_or(Error::Internal)?;
+    fn poll(self: Pin<&mut Self>, ctx: &mut core::task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
 
-                if n == 0 {
-                    fmt::warn!("Received zero bytes");
-                }
+        let spin_until = this.busy_poll.map(|budget| Instant::now() + budget);
+
+        // Read until the socket would block, which also registers the waker with the reactor. This
+        // is additionally important for macOS as multiple packets may be received for one
+        // `poll_read` call, but will only be returned during the _next_ `poll_read`.
+        loop {
+            match Pin::new(&mut &*this.socket).poll_read(ctx, &mut this.buf) {
+                Poll::Ready(Ok(n)) => {
+                    fmt::trace!("Poll ready");
 
-                loop {
-                    match self.rx.receive_frame(packet) {
-                        // Wait for frame RX future waker to be registered
-                        Err(Error::Pdu(PduError::NoWaker)) => thread::yield_now(),
-                        Err(e) => {
-                            fmt::error!("Failed to receive frame: {}", e);
+                    let packet = this.buf.get(0..n).ok_or(Error::Internal)?;
+
+                    if n == 0 {
+                        fmt::warn!("Received zero bytes");
+                    }
 
-                            return Poll::Ready(Err(Error::ReceiveFrame));
+                    loop {
+                        match this.rx.receive_frame(packet) {
+                            // Wait for frame RX future waker to be registered
+                            Err(Error::Pdu(PduError::NoWaker)) => thread::yield_now(),
+                            Err(e) => {
+                                fmt::error!("Failed to receive frame: {}", e);
+
+                                return Poll::Ready(Err(Error::ReceiveFrame));
+                            }
+                            Ok(()) => break,
                         }
-                        Ok(()) => break,
                     }
                 }
+                Poll::Ready(Err(e)) => {
+                    fmt::error!("Receive PDU failed: {}", e);
+
+                    // Make sure this future is polled again as no waker was registered
+                    ctx.waker().wake_by_ref();
+
+                    break;
+                }
+                Poll::Pending => {
+                    // Keep checking for received frames without waiting for the reactor to wake
+                    // this task, until the spin budget runs out
+                    if spin_until.is_some_and(|deadline| Instant::now() < deadline) {
+                        core::hint::spin_loop();
+
+                        continue;
+                    }
+
+                    break;
+                }
             }
-            Poll::Ready(Err(e)) => {
-                fmt::error!("Receive PDU failed: {}", e);
-            }
-            Poll::Pending => (),
         }
 
         Poll::Pending
@@ -107,13 +160,83 @@ impl Future for TxRxFut<'_> {
 }
 
 /// Spawn a TX and RX task.
+///
+/// The returned future drives both halves of [`tx_rx_task_split`], so frames are sent as soon as
+/// they are queued, even while earlier responses are still being processed.
 pub fn tx_rx_task<'sto>(
     interface: &str,
     pdu_tx: PduTx<'sto>,
-    #[allow(unused_mut)] mut pdu_rx: PduRx<'sto>,
+    pdu_rx: PduRx<'sto>,
 ) -> Result<impl Future<Output = Result<(), Error>> + 'sto, std::io::Error> {
-    let mut socket = RawSocketDesc::new(interface)?;
+    let (tx, rx) = tx_rx_task_split(interface, pdu_tx, pdu_rx)?;
 
+    Ok(futures_lite::future::or(tx, rx))
+}
+
+/// Create separate TX and RX tasks that share one raw socket.
+///
+/// The TX future sends queued frames and the RX future processes responses. They can be spawned
+/// onto different executors or threads so the next frame, e.g. the next part of a multi-frame PDI,
+/// is never held back by the processing of a previous response. Both futures must be polled for
+/// the MainDevice to make progress.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use ethercrab::{std::tx_rx_task_split, PduStorage};
+///
+/// static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+///
+/// let (tx, rx, _pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+///
+/// let (tx_task, rx_task) = tx_rx_task_split("eth0", tx, rx).expect("create TX/RX tasks");
+///
+/// std::thread::spawn(move || smol::block_on(tx_task));
+/// std::thread::spawn(move || smol::block_on(rx_task));
+/// ```
+pub fn tx_rx_task_split<'sto>(
+    interface: &str,
+    pdu_tx: PduTx<'sto>,
+    pdu_rx: PduRx<'sto>,
+) -> Result<
+    (
+        impl Future<Output = Result<(), Error>> + 'sto,
+        impl Future<Output = Result<(), Error>> + 'sto,
+    ),
+    std::io::Error,
+> {
+    let socket = RawSocketDesc::new(interface)?;
+
+    tx_rx_task_socket(interface, socket, pdu_tx, pdu_rx, None)
+}
+
+/// Spawn a TX and RX task, applying extra options to the raw socket.
+///
+/// This is equivalent to [`tx_rx_task`](crate::std::tx_rx_task), but allows socket buffer sizes,
+/// kernel timestamping and busy polling to be configured. See [`SocketConfig`] for details.
+#[cfg(target_os = "linux")]
+pub fn tx_rx_task_with_config<'sto>(
+    interface: &str,
+    pdu_tx: PduTx<'sto>,
+    pdu_rx: PduRx<'sto>,
+    config: SocketConfig,
+) -> Result<impl Future<Output = Result<(), Error>> + 'sto, std::io::Error> {
+    let socket = RawSocketDesc::with_config(interface, &config)?;
+
+    fmt::debug!("Applied socket config {:?}", config);
+
+    let (tx, rx) = tx_rx_task_socket(interface, socket, pdu_tx, pdu_rx, config.busy_poll)?;
+
+    Ok(futures_lite::future::or(tx, rx))
+}
+
+fn tx_rx_task_socket<'sto>(
+    interface: &str,
+    #[allow(unused_mut)] mut socket: RawSocketDesc,
+    pdu_tx: PduTx<'sto>,
+    #[allow(unused_mut)] mut pdu_rx: PduRx<'sto>,
+    busy_poll: Option<Duration>,
+) -> Result<(TxFut<'sto>, RxFut<'sto>), std::io::Error> {
     // macOS forcibly sets the source address to the NIC's MAC, so instead of using `MASTER_ADDR`
     // for filtering returned packets, we must set the address to compare to the NIC MAC.
     #[cfg(all(not(target_os = "linux"), unix))]
@@ -127,16 +250,74 @@ pub fn tx_rx_task<'sto>(
 
     fmt::debug!("Opening {} with MTU {}", interface, mtu);
 
-    let async_socket = Async::new(socket)?;
+    let socket = Arc::new(Async::new(socket)?);
 
-    let task = TxRxFut {
-        socket: async_socket,
-        mtu,
+    let tx = TxFut {
+        socket: Arc::clone(&socket),
         tx: pdu_tx,
+    };
+
+    let rx = RxFut {
+        socket,
+        buf: vec![0; mtu],
         rx: pdu_rx,
+        busy_poll,
     };
 
-    Ok(task)
+    Ok((tx, rx))
+}
+
+/// Capture frames on a network interface and pass them to a [`Monitor`] without sending anything.
+///
+/// The interface should be connected to a mirror port or network tap that sees the traffic of
+/// another MainDevice. Frames that can't be decoded are logged and skipped. The returned future
+/// only resolves if the interface can no longer be read.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use ethercrab::{std::monitor_task, Monitor, MonitorEvent};
+///
+/// let task = monitor_task("eth1", Monitor::<64>::new(), |event| {
+///     if let MonitorEvent::StateChanged { address, current, .. } = event {
+///         println!("SubDevice {:#06x} is now {}", address, current);
+///     }
+/// })
+/// .expect("open interface");
+///
+/// smol::block_on(task).expect("monitor failed");
+/// ```
+pub fn monitor_task<const MAX_SUBDEVICES: usize>(
+    interface: &str,
+    mut monitor: Monitor<MAX_SUBDEVICES>,
+    mut on_event: impl FnMut(MonitorEvent<'_>),
+) -> Result<impl Future<Output = Result<(), Error>>, std::io::Error> {
+    #[allow(unused_mut)]
+    let mut socket = RawSocketDesc::new(interface)?;
+
+    let mtu = socket.interface_mtu()?;
+
+    fmt::debug!("Monitoring {} with MTU {}", interface, mtu);
+
+    let mut socket = Async::new(socket)?;
+
+    Ok(async move {
+        let mut buf = vec![0; mtu];
+
+        loop {
+            let n = socket.read(&mut buf).await.map_err(|e| {
+                fmt::error!("Receive frame failed: {}", e);
+
+                Error::ReceiveFrame
+            })?;
+
+            let frame = buf.get(0..n).ok_or(Error::Internal)?;
+
+            if let Err(e) = monitor.process_frame(frame, &mut on_event) {
+                fmt::warn!("Failed to decode frame: {}", e);
+            }
+        }
+    })
 }
 
 /// Get the current time in nanoseconds from the EtherCAT epoch, 2000-01-01.
@@ -4,6 +4,7 @@ use crate::ethernet::EthernetFrame;
 use crate::{
     error::Error,
     pdu_loop::{PduRx, PduTx},
+    std::interfaces::resolve_interface,
 };
 use core::future::Future;
 use pnet_datalink::{self, channel, Channel, DataLinkReceiver, DataLinkSender};
@@ -13,6 +14,8 @@ use std::{thread, time::SystemTime};
 fn get_tx_rx(
     device: &str,
 ) -> Result<(Box<dyn DataLinkSender>, Box<dyn DataLinkReceiver>), std::io::Error> {
+    let device = resolve_interface(device);
+
     let interfaces = pnet_datalink::interfaces();
 
     let interface = match interfaces.iter().find(|interface| interface.name == device) {
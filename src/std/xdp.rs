@@ -0,0 +1,797 @@
+//! A blocking TX/RX loop using an `AF_XDP` socket, bypassing the kernel network stack.
+
+use crate::{
+    atomic::{AtomicU32, Ordering},
+    error::{Error, PduError},
+    fmt,
+    std::interfaces::resolve_interface,
+    PduRx, PduTx, ETHERCAT_ETHERTYPE,
+};
+use core::{
+    ffi::c_void,
+    mem,
+    ptr::{self, addr_of},
+    task::Waker,
+};
+use std::{
+    ffi::CString,
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    sync::Arc,
+    task::Wake,
+    thread,
+};
+
+/// Size of each UMEM chunk. Must be a power of two between 2048 and the page size.
+const FRAME_SIZE: usize = 2048;
+
+/// Number of descriptors in each ring. Must be a power of two.
+const RING_SIZE: u32 = 512;
+
+/// Number of UMEM chunks. The first half is used for receiving, the second half for sending.
+const FRAME_COUNT: usize = RING_SIZE as usize * 2;
+
+/// Frames are only received on this NIC queue.
+const QUEUE_ID: u32 = 0;
+
+// Values from `include/uapi/linux/bpf.h`
+const BPF_MAP_CREATE: libc::c_long = 0;
+const BPF_MAP_UPDATE_ELEM: libc::c_long = 2;
+const BPF_PROG_LOAD: libc::c_long = 5;
+const BPF_LINK_CREATE: libc::c_long = 28;
+const BPF_MAP_TYPE_XSKMAP: u32 = 17;
+const BPF_PROG_TYPE_XDP: u32 = 6;
+const BPF_XDP: u32 = 37;
+const BPF_PSEUDO_MAP_FD: u8 = 1;
+const BPF_FUNC_REDIRECT_MAP: i32 = 51;
+const XDP_PASS: i32 = 2;
+
+/// A single eBPF instruction, `struct bpf_insn`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct BpfInsn {
+    code: u8,
+    regs: u8,
+    off: i16,
+    imm: i32,
+}
+
+impl BpfInsn {
+    const fn new(code: u8, dst: u8, src: u8, off: i16, imm: i32) -> Self {
+        // The register nibbles are stored as bitfields so their order depends on endianness.
+        #[cfg(target_endian = "little")]
+        let regs = dst | src << 4;
+        #[cfg(target_endian = "big")]
+        let regs = dst << 4 | src;
+
+        Self {
+            code,
+            regs,
+            off,
+            imm,
+        }
+    }
+}
+
+/// An XDP program that redirects EtherCAT frames to the socket in `xsk_map` for the queue the frame
+/// was received on. All other traffic is passed on to the kernel network stack.
+fn xdp_program(xsk_map: RawFd) -> [BpfInsn; 16] {
+    // `ldxh` loads the EtherType in native endianness.
+    let ethertype = i32::from(u16::from_ne_bytes(ETHERCAT_ETHERTYPE.to_be_bytes()));
+
+    [
+        // r6 = ctx
+        BpfInsn::new(0xbf, 6, 1, 0, 0),
+        // r2 = ctx->data
+        BpfInsn::new(0x61, 2, 6, 0, 0),
+        // r3 = ctx->data_end
+        BpfInsn::new(0x61, 3, 6, 4, 0),
+        // r4 = r2 + 14
+        BpfInsn::new(0xbf, 4, 2, 0, 0),
+        BpfInsn::new(0x07, 4, 0, 0, 14),
+        // Frame is too short to contain an Ethernet header
+        // if r4 > r3 goto pass
+        BpfInsn::new(0x2d, 4, 3, 8, 0),
+        // r4 = EtherType
+        BpfInsn::new(0x69, 4, 2, 12, 0),
+        // if r4 != ETHERCAT_ETHERTYPE goto pass
+        BpfInsn::new(0x55, 4, 0, 6, ethertype),
+        // r2 = ctx->rx_queue_index
+        BpfInsn::new(0x61, 2, 6, 16, 0),
+        // r1 = xsk_map (two instructions wide)
+        BpfInsn::new(0x18, 1, BPF_PSEUDO_MAP_FD, 0, xsk_map),
+        BpfInsn::new(0, 0, 0, 0, 0),
+        // r3 = XDP_PASS, returned if there is no socket for this queue
+        BpfInsn::new(0xb7, 3, 0, 0, XDP_PASS),
+        // return bpf_redirect_map(r1, r2, r3)
+        BpfInsn::new(0x85, 0, 0, 0, BPF_FUNC_REDIRECT_MAP),
+        BpfInsn::new(0x95, 0, 0, 0, 0),
+        // pass: return XDP_PASS
+        BpfInsn::new(0xb7, 0, 0, 0, XDP_PASS),
+        BpfInsn::new(0x95, 0, 0, 0, 0),
+    ]
+}
+
+#[repr(C)]
+struct MapCreateAttr {
+    map_type: u32,
+    key_size: u32,
+    value_size: u32,
+    max_entries: u32,
+    map_flags: u32,
+}
+
+#[repr(C)]
+struct MapUpdateAttr {
+    map_fd: u32,
+    key: u64,
+    value: u64,
+    flags: u64,
+}
+
+#[repr(C)]
+struct ProgLoadAttr {
+    prog_type: u32,
+    insn_cnt: u32,
+    insns: u64,
+    license: u64,
+    log_level: u32,
+    log_size: u32,
+    log_buf: u64,
+    kern_version: u32,
+    prog_flags: u32,
+}
+
+#[repr(C)]
+struct LinkCreateAttr {
+    prog_fd: u32,
+    target_ifindex: u32,
+    attach_type: u32,
+    flags: u32,
+}
+
+/// Call the `bpf` syscall, returning the created file descriptor if there is one.
+fn bpf<T>(cmd: libc::c_long, attr: &T) -> io::Result<libc::c_long> {
+    // SAFETY: All attribute structs are `repr(C)` prefixes of `union bpf_attr`. The kernel zero
+    // fills any fields after `size_of::<T>()`.
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            cmd,
+            ptr::from_ref(attr),
+            mem::size_of::<T>() as libc::c_uint,
+        )
+    };
+
+    if res < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(res)
+    }
+}
+
+fn bpf_fd<T>(cmd: libc::c_long, attr: &T) -> io::Result<OwnedFd> {
+    let fd = bpf(cmd, attr)?;
+
+    // SAFETY: `bpf` returns a new file descriptor for the create/load/link commands.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+}
+
+/// The XDP program, the socket map it redirects into, and the link attaching it to the interface.
+///
+/// Dropping this detaches the program from the interface.
+struct XdpProgram {
+    _link: OwnedFd,
+    _prog: OwnedFd,
+    _map: OwnedFd,
+}
+
+impl XdpProgram {
+    fn attach(ifindex: u32, socket: RawFd) -> io::Result<Self> {
+        let map = bpf_fd(
+            BPF_MAP_CREATE,
+            &MapCreateAttr {
+                map_type: BPF_MAP_TYPE_XSKMAP,
+                key_size: mem::size_of::<u32>() as u32,
+                value_size: mem::size_of::<u32>() as u32,
+                max_entries: QUEUE_ID + 1,
+                map_flags: 0,
+            },
+        )?;
+
+        let key = QUEUE_ID;
+        let value = socket as u32;
+
+        bpf(
+            BPF_MAP_UPDATE_ELEM,
+            &MapUpdateAttr {
+                map_fd: map.as_raw_fd() as u32,
+                key: addr_of!(key) as u64,
+                value: addr_of!(value) as u64,
+                flags: 0,
+            },
+        )?;
+
+        let insns = xdp_program(map.as_raw_fd());
+        let license = b"Dual MIT/GPL\0";
+
+        let prog = bpf_fd(
+            BPF_PROG_LOAD,
+            &ProgLoadAttr {
+                prog_type: BPF_PROG_TYPE_XDP,
+                insn_cnt: insns.len() as u32,
+                insns: insns.as_ptr() as u64,
+                license: license.as_ptr() as u64,
+                log_level: 0,
+                log_size: 0,
+                log_buf: 0,
+                kern_version: 0,
+                prog_flags: 0,
+            },
+        )?;
+
+        let link = bpf_fd(
+            BPF_LINK_CREATE,
+            &LinkCreateAttr {
+                prog_fd: prog.as_raw_fd() as u32,
+                target_ifindex: ifindex,
+                attach_type: BPF_XDP,
+                flags: 0,
+            },
+        )?;
+
+        Ok(Self {
+            _link: link,
+            _prog: prog,
+            _map: map,
+        })
+    }
+}
+
+/// A memory mapped region, unmapped on drop.
+struct Mmap {
+    ptr: *mut c_void,
+    len: usize,
+}
+
+impl Mmap {
+    fn new(fd: RawFd, offset: libc::off_t, len: usize) -> io::Result<Self> {
+        let flags = if fd < 0 {
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS
+        } else {
+            libc::MAP_SHARED | libc::MAP_POPULATE
+        };
+
+        // SAFETY: Arguments are checked by the kernel. The mapping is only accessed through
+        // `Ring` or `FrameBuffer` methods which stay within `len`.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                flags,
+                fd,
+                offset,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self { ptr, len })
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` and `len` came from a successful `mmap`.
+        unsafe { libc::munmap(self.ptr, self.len) };
+    }
+}
+
+/// A single-producer, single-consumer ring shared with the kernel.
+struct Ring<T> {
+    producer: *const AtomicU32,
+    consumer: *const AtomicU32,
+    descs: *mut T,
+    /// Cached producer index for rings we produce into, consumer index for rings we consume.
+    head: u32,
+    _mmap: Mmap,
+}
+
+impl<T: Copy> Ring<T> {
+    fn new(fd: RawFd, offsets: &libc::xdp_ring_offset, pgoff: libc::off_t) -> io::Result<Self> {
+        let len = offsets.desc as usize + RING_SIZE as usize * mem::size_of::<T>();
+
+        let mmap = Mmap::new(fd, pgoff, len)?;
+        let base = mmap.ptr.cast::<u8>();
+
+        // SAFETY: Offsets are provided by the kernel and are within the mapping.
+        let (producer, consumer, descs) = unsafe {
+            (
+                base.add(offsets.producer as usize).cast::<AtomicU32>(),
+                base.add(offsets.consumer as usize).cast::<AtomicU32>(),
+                base.add(offsets.desc as usize).cast::<T>(),
+            )
+        };
+
+        // SAFETY: The pointers are valid for the lifetime of the mapping.
+        let head = unsafe { (*producer).load(Ordering::Acquire) };
+
+        Ok(Self {
+            producer,
+            consumer,
+            descs,
+            head,
+            _mmap: mmap,
+        })
+    }
+
+    /// Push an item into a ring produced by userspace, e.g. the fill or TX ring.
+    fn push(&mut self, item: T) -> bool {
+        // SAFETY: The pointers are valid for the lifetime of the mapping.
+        let consumer = unsafe { (*self.consumer).load(Ordering::Acquire) };
+
+        if self.head.wrapping_sub(consumer) >= RING_SIZE {
+            return false;
+        }
+
+        // SAFETY: The index is masked to be within the ring, and the slot is not owned by the
+        // kernel until the producer index is advanced.
+        unsafe {
+            self.descs
+                .add((self.head & (RING_SIZE - 1)) as usize)
+                .write_volatile(item);
+        }
+
+        self.head = self.head.wrapping_add(1);
+
+        // SAFETY: The pointers are valid for the lifetime of the mapping.
+        unsafe { (*self.producer).store(self.head, Ordering::Release) };
+
+        true
+    }
+
+    /// Pop an item from a ring produced by the kernel, e.g. the completion or RX ring.
+    fn pop(&mut self) -> Option<T> {
+        // SAFETY: The pointers are valid for the lifetime of the mapping.
+        let producer = unsafe { (*self.producer).load(Ordering::Acquire) };
+
+        if producer == self.head {
+            return None;
+        }
+
+        // SAFETY: The index is masked to be within the ring, and the slot is owned by userspace
+        // until the consumer index is advanced.
+        let item = unsafe {
+            self.descs
+                .add((self.head & (RING_SIZE - 1)) as usize)
+                .read_volatile()
+        };
+
+        self.head = self.head.wrapping_add(1);
+
+        // SAFETY: The pointers are valid for the lifetime of the mapping.
+        unsafe { (*self.consumer).store(self.head, Ordering::Release) };
+
+        Some(item)
+    }
+}
+
+impl<T> Ring<T> {
+    /// Create a ring we consume from. The cached head must track the consumer index instead.
+    fn into_consumer(mut self) -> Self {
+        // SAFETY: The pointers are valid for the lifetime of the mapping.
+        self.head = unsafe { (*self.consumer).load(Ordering::Acquire) };
+
+        self
+    }
+}
+
+/// An `AF_XDP` socket with its UMEM and rings.
+struct XdpSocket {
+    // Field order is drop order: the program must be detached before the socket is closed.
+    _program: XdpProgram,
+    fill: Ring<u64>,
+    completion: Ring<u64>,
+    rx: Ring<libc::xdp_desc>,
+    tx: Ring<libc::xdp_desc>,
+    fd: OwnedFd,
+    umem: Mmap,
+    /// UMEM addresses of TX chunks not currently owned by the kernel.
+    tx_free: Vec<u64>,
+}
+
+impl XdpSocket {
+    fn new(interface: &str) -> io::Result<Self> {
+        let name = CString::new(resolve_interface(interface)).map_err(io::Error::other)?;
+
+        // SAFETY: `name` is a valid C string.
+        let ifindex = unsafe { libc::if_nametoindex(name.as_ptr()) };
+
+        if ifindex == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: Plain syscall, result is checked.
+        let fd = unsafe { libc::socket(libc::AF_XDP, libc::SOCK_RAW, 0) };
+
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: `fd` is a new, valid file descriptor.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let umem = Mmap::new(-1, 0, FRAME_COUNT * FRAME_SIZE)?;
+
+        setsockopt(
+            &fd,
+            libc::XDP_UMEM_REG,
+            &libc::xdp_umem_reg {
+                addr: umem.ptr as u64,
+                len: umem.len as u64,
+                chunk_size: FRAME_SIZE as u32,
+                headroom: 0,
+                flags: 0,
+                tx_metadata_len: 0,
+            },
+        )?;
+
+        for ring in [
+            libc::XDP_UMEM_FILL_RING,
+            libc::XDP_UMEM_COMPLETION_RING,
+            libc::XDP_RX_RING,
+            libc::XDP_TX_RING,
+        ] {
+            setsockopt(&fd, ring, &RING_SIZE)?;
+        }
+
+        let offsets = mmap_offsets(&fd)?;
+        let raw = fd.as_raw_fd();
+
+        let mut fill = Ring::new(
+            raw,
+            &offsets.fr,
+            libc::XDP_UMEM_PGOFF_FILL_RING as libc::off_t,
+        )?;
+        let completion = Ring::new(
+            raw,
+            &offsets.cr,
+            libc::XDP_UMEM_PGOFF_COMPLETION_RING as libc::off_t,
+        )?
+        .into_consumer();
+        let rx = Ring::new(raw, &offsets.rx, libc::XDP_PGOFF_RX_RING)?.into_consumer();
+        let tx = Ring::new(raw, &offsets.tx, libc::XDP_PGOFF_TX_RING)?;
+
+        // Prefer zero copy mode, where the NIC DMAs directly into UMEM. Not all drivers support it.
+        bind(&fd, ifindex, libc::XDP_ZEROCOPY).or_else(|e| {
+            fmt::debug!(
+                "XDP zero copy mode not supported ({}), falling back to copy mode",
+                e
+            );
+
+            bind(&fd, ifindex, libc::XDP_COPY)
+        })?;
+
+        // Give the first half of UMEM to the kernel to receive frames into
+        for chunk in 0..FRAME_COUNT / 2 {
+            if !fill.push((chunk * FRAME_SIZE) as u64) {
+                return Err(io::Error::other(Error::Internal));
+            }
+        }
+
+        let tx_free = (FRAME_COUNT / 2..FRAME_COUNT)
+            .map(|chunk| (chunk * FRAME_SIZE) as u64)
+            .collect();
+
+        let program = XdpProgram::attach(ifindex, raw)?;
+
+        Ok(Self {
+            _program: program,
+            fill,
+            completion,
+            rx,
+            tx,
+            fd,
+            umem,
+            tx_free,
+        })
+    }
+
+    fn chunk(&mut self, addr: u64, len: usize) -> Result<&mut [u8], Error> {
+        let addr = addr as usize;
+
+        if addr % FRAME_SIZE + len > FRAME_SIZE || addr + len > self.umem.len {
+            return Err(Error::Internal);
+        }
+
+        // SAFETY: Bounds are checked above, and the chunk is not owned by the kernel.
+        Ok(unsafe { core::slice::from_raw_parts_mut(self.umem.ptr.cast::<u8>().add(addr), len) })
+    }
+
+    /// Reclaim TX chunks that the kernel has finished sending.
+    fn reclaim(&mut self) {
+        while let Some(addr) = self.completion.pop() {
+            self.tx_free.push(addr);
+        }
+    }
+
+    /// Tell the kernel there are frames in the TX ring.
+    fn kick(&self) -> io::Result<()> {
+        // SAFETY: A zero length send on an `AF_XDP` socket only triggers TX ring processing.
+        let res = unsafe {
+            libc::sendto(
+                self.fd.as_raw_fd(),
+                ptr::null(),
+                0,
+                libc::MSG_DONTWAIT,
+                ptr::null(),
+                0,
+            )
+        };
+
+        if res < 0 {
+            let e = io::Error::last_os_error();
+
+            // The kernel is still busy with previous frames, which is fine.
+            if !matches!(
+                e.raw_os_error(),
+                Some(libc::EAGAIN | libc::EBUSY | libc::ENOBUFS)
+            ) {
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copy a frame into a free TX chunk and queue it for sending.
+    fn send(&mut self, data: &[u8]) -> Result<usize, Error> {
+        let addr = loop {
+            self.reclaim();
+
+            if let Some(addr) = self.tx_free.pop() {
+                break addr;
+            }
+
+            // All TX chunks are in flight. Make sure the kernel is processing them.
+            self.kick().map_err(|_| Error::SendFrame)?;
+
+            thread::yield_now();
+        };
+
+        self.chunk(addr, data.len())?.copy_from_slice(data);
+
+        let queued = self.tx.push(libc::xdp_desc {
+            addr,
+            len: data.len() as u32,
+            options: 0,
+        });
+
+        // There are as many TX chunks as TX ring entries, so the ring can never be full here.
+        if !queued {
+            return Err(Error::Internal);
+        }
+
+        Ok(data.len())
+    }
+}
+
+fn setsockopt<T>(fd: &OwnedFd, name: libc::c_int, value: &T) -> io::Result<()> {
+    // SAFETY: `value` is valid for `size_of::<T>()` bytes.
+    let res = unsafe {
+        libc::setsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_XDP,
+            name,
+            ptr::from_ref(value).cast(),
+            mem::size_of::<T>() as libc::socklen_t,
+        )
+    };
+
+    if res < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+fn mmap_offsets(fd: &OwnedFd) -> io::Result<libc::xdp_mmap_offsets> {
+    // SAFETY: All-zero is a valid value for this plain C struct.
+    let mut offsets: libc::xdp_mmap_offsets = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::xdp_mmap_offsets>() as libc::socklen_t;
+
+    // SAFETY: `offsets` is valid for `len` bytes.
+    let res = unsafe {
+        libc::getsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_XDP,
+            libc::XDP_MMAP_OFFSETS,
+            ptr::from_mut(&mut offsets).cast(),
+            &mut len,
+        )
+    };
+
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // Kernels before 5.4 return a smaller struct without ring flags
+    if len as usize != mem::size_of::<libc::xdp_mmap_offsets>() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "AF_XDP requires Linux 5.9 or newer",
+        ));
+    }
+
+    Ok(offsets)
+}
+
+fn bind(fd: &OwnedFd, ifindex: u32, flags: u16) -> io::Result<()> {
+    let addr = libc::sockaddr_xdp {
+        sxdp_family: libc::AF_XDP as u16,
+        sxdp_flags: flags,
+        sxdp_ifindex: ifindex,
+        sxdp_queue_id: QUEUE_ID,
+        sxdp_shared_umem_fd: 0,
+    };
+
+    // SAFETY: `addr` is a valid `sockaddr_xdp`.
+    let res = unsafe {
+        libc::bind(
+            fd.as_raw_fd(),
+            addr_of!(addr).cast(),
+            mem::size_of::<libc::sockaddr_xdp>() as libc::socklen_t,
+        )
+    };
+
+    if res < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Wakes the TX/RX loop from `poll` when new frames are ready to send.
+struct EventFdSignal {
+    fd: OwnedFd,
+}
+
+impl EventFdSignal {
+    fn new() -> io::Result<Self> {
+        // SAFETY: Plain syscall, result is checked.
+        let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK) };
+
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: `fd` is a new, valid file descriptor.
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        })
+    }
+
+    fn clear(&self) {
+        let mut value = 0;
+
+        // SAFETY: `value` is a valid `eventfd_t`. An error just means nothing was signalled.
+        unsafe { libc::eventfd_read(self.fd.as_raw_fd(), &mut value) };
+    }
+}
+
+impl Wake for EventFdSignal {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        // SAFETY: Writing to an eventfd has no memory safety requirements.
+        unsafe { libc::eventfd_write(self.fd.as_raw_fd(), 1) };
+    }
+}
+
+/// Create a blocking TX/RX loop using an `AF_XDP` socket.
+///
+/// EtherCAT frames are sent and received through shared memory (UMEM) without passing through the
+/// kernel network stack. A small XDP program is attached to the interface to redirect received
+/// EtherCAT frames to the socket; all other traffic is passed through to the kernel as normal. The
+/// program is detached when this function returns.
+///
+/// If the NIC driver supports it, the socket is bound in `XDP_ZEROCOPY` mode where the NIC reads
+/// and writes UMEM directly, otherwise `XDP_COPY` mode is used. This only affects the path between
+/// the NIC and UMEM: sent frames are still copied from the PDU loop storage into UMEM, and received
+/// PDU data is copied out of UMEM into the PDU loop storage, so this is not zero copy end to end.
+///
+/// Frames are only received on NIC queue 0. For NICs with multiple queues, use e.g. `ethtool -L
+/// <interface> combined 1` to make sure returning EtherCAT frames arrive on this queue.
+///
+/// This function is only available on `linux` targets and requires Linux 5.9 or newer. The
+/// `CAP_NET_ADMIN`, `CAP_NET_RAW` and `CAP_BPF` capabilities (or root) are required.
+pub fn tx_rx_task_xdp<'sto>(
+    interface: &str,
+    mut pdu_tx: PduTx<'sto>,
+    mut pdu_rx: PduRx<'sto>,
+) -> Result<(), io::Error> {
+    let mut socket = XdpSocket::new(interface)?;
+
+    fmt::debug!("Opening {} using AF_XDP", interface);
+
+    let signal = Arc::new(EventFdSignal::new()?);
+    let waker = Waker::from(Arc::clone(&signal));
+
+    loop {
+        pdu_tx.replace_waker(&waker);
+
+        let mut sent = false;
+
+        while let Some(frame) = pdu_tx.next_sendable_frame() {
+            frame
+                .send_blocking(|data: &[u8]| socket.send(data))
+                .map_err(io::Error::other)?;
+
+            sent = true;
+        }
+
+        if sent {
+            socket.kick()?;
+        }
+
+        while let Some(desc) = socket.rx.pop() {
+            let frame = socket
+                .chunk(desc.addr, desc.len as usize)
+                .map_err(io::Error::other)?;
+
+            loop {
+                match pdu_rx.receive_frame(frame) {
+                    Ok(()) => break,
+                    Err(Error::Pdu(PduError::NoWaker)) => {
+                        fmt::trace!("No waker for received frame, retrying receive");
+
+                        thread::yield_now();
+                    }
+                    Err(e) => return Err(io::Error::other(e)),
+                }
+            }
+
+            // Give the chunk back to the kernel to receive into. Chunk addresses in the RX ring
+            // may have an offset, so align back to the start of the chunk.
+            let chunk = desc.addr - desc.addr % FRAME_SIZE as u64;
+
+            if !socket.fill.push(chunk) {
+                return Err(io::Error::other(Error::Internal));
+            }
+        }
+
+        socket.reclaim();
+
+        // Wait for a received frame, or to be woken with new frames to send
+        let mut fds = [
+            libc::pollfd {
+                fd: socket.fd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: signal.fd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+
+        // SAFETY: `fds` is valid for `fds.len()` entries.
+        let res = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) };
+
+        if res < 0 {
+            let e = io::Error::last_os_error();
+
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e);
+            }
+        }
+
+        signal.clear();
+    }
+}
@@ -1,18 +1,23 @@
 use super::{SubDevice, SubDeviceRef};
 use crate::{
+    al_control::AlControl,
     coe::{SdoExpedited, SubIndex},
     eeprom::types::{
         CoeDetails, FmmuUsage, MailboxProtocols, SiiOwner, SyncManager, SyncManagerEnable,
         SyncManagerType,
     },
-    error::{Error, Item},
+    error::{Error, Item, MailboxError},
     fmmu::Fmmu,
     fmt,
     pdi::{PdiOffset, PdiSegment},
     register::RegisterAddress,
-    subdevice::types::{Mailbox, MailboxConfig},
+    subdevice::{
+        types::{Mailbox, MailboxConfig},
+        SubDeviceOverride,
+    },
     subdevice_state::SubDeviceState,
     sync_manager_channel::{Enable, Status, SyncManagerChannel, SM_BASE_ADDRESS, SM_TYPE_ADDRESS},
+    LogicalAddress,
 };
 use core::ops::DerefMut;
 
@@ -25,16 +30,48 @@ where
     ///
     /// Continue configuration by calling
     /// [`configure_fmmus`](crate::SubDeviceGroup::configure_fmmus).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(configured_address = %self.configured_address)
+        )
+    )]
     pub(crate) async fn configure_mailboxes(&mut self) -> Result<(), Error> {
         // Force EEPROM into master mode. Some SubDevices require PDI mode for INIT -> PRE-OP
         // transition. This is mentioned in ETG2010 p. 146 under "Eeprom/@AssignToPd". We'll reset
         // to master mode here, now that the transition is complete.
         self.set_eeprom_mode(SiiOwner::Master).await?;
 
-        let sync_managers = self.eeprom().sync_managers().await?;
+        let sync_managers = self.sync_manager_config().await?;
+
+        let config_override = self.config_override();
+
+        self.state.config.mailbox_only = config_override.is_some_and(|config| config.mailbox_only);
 
         // Mailboxes must be configured in INIT state
-        self.configure_mailbox_sms(&sync_managers).await?;
+        if config_override.is_some_and(|config| config.skip_mailbox) {
+            fmt::debug!(
+                "SubDevice {:#06x} mailbox configuration skipped by override",
+                self.configured_address
+            );
+        } else {
+            self.configure_mailbox_sms(&sync_managers).await?;
+        }
+
+        for write in config_override.map_or(&[][..], |config| config.init_writes) {
+            fmt::debug!(
+                "SubDevice {:#06x} override write to {:#06x}: {:?}",
+                self.configured_address,
+                write.register,
+                write.data
+            );
+
+            self.write(write.register)
+                .send_slice(self.maindevice, write.data)
+                .await?;
+        }
 
         // Some SubDevices must be in PDI EEPROM mode to transition from INIT to PRE-OP. This is
         // mentioned in ETG2010 p. 146 under "Eeprom/@AssignToPd"
@@ -99,17 +136,94 @@ where
         Ok(())
     }
 
+    /// Reconfigure a SubDevice that has lost its configuration, e.g. after a power cycle.
+    ///
+    /// Mailbox, SM and FMMU configuration is written again, using the PDI mapping computed during
+    /// initialisation. The SubDevice is left in PRE-OP.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(configured_address = %self.configured_address)
+        )
+    )]
+    pub(crate) async fn reconfigure(
+        &mut self,
+        group_start_address: LogicalAddress,
+    ) -> Result<(), Error> {
+        let io = self.state.config.io.clone();
+
+        fmt::debug!("Reconfiguring SubDevice {:#06x}", self.configured_address);
+
+        self.write(RegisterAddress::AlControl)
+            .send(self.maindevice, AlControl::reset())
+            .await?;
+
+        self.wait_for_state(SubDeviceState::Init).await?;
+
+        // Clear FMMUs and SMs so FMMU config is built up from scratch. See ETG1000.4 Table 57 and
+        // Table 59 for section lengths.
+        self.write(RegisterAddress::Fmmu0)
+            .send(self.maindevice, [0u8; 0xff])
+            .await?;
+        self.write(RegisterAddress::Sm0)
+            .send(self.maindevice, [0u8; 0x7f])
+            .await?;
+
+        self.configure_mailboxes().await?;
+
+        for (segment, direction) in [
+            (&io.input, PdoDirection::MasterRead),
+            (&io.output, PdoDirection::MasterWrite),
+        ] {
+            let offset = PdiOffset {
+                start_address: group_start_address.offset(segment.bytes.start as u32),
+            };
+
+            self.configure_fmmus(offset, group_start_address, direction)
+                .await?;
+        }
+
+        // PDO mappings differ from those read during init, so the SubDevice's PDI would overlap
+        // with others in the group.
+        if self.state.config.io != io {
+            fmt::error!(
+                "SubDevice {:#06x} PDI mapping changed during reconfiguration: inputs {:?} -> {:?}, outputs {:?} -> {:?}",
+                self.configured_address,
+                io.input,
+                self.state.config.io.input,
+                io.output,
+                self.state.config.io.output
+            );
+
+            self.state.config.io = io;
+
+            return Err(Error::Topology);
+        }
+
+        Ok(())
+    }
+
     /// Second state configuration (PRE-OP -> SAFE-OP).
     ///
     /// PDOs must be configured in the PRE-OP state.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(configured_address = %self.configured_address)
+        )
+    )]
     pub(crate) async fn configure_fmmus(
         &mut self,
         mut global_offset: PdiOffset,
-        group_start_address: u32,
+        group_start_address: LogicalAddress,
         direction: PdoDirection,
     ) -> Result<PdiOffset, Error> {
-        let sync_managers = self.eeprom().sync_managers().await?;
-        let fmmu_usage = self.eeprom().fmmus().await?;
+        let sync_managers = self.sync_manager_config().await?;
+        let fmmu_usage = self.fmmu_config().await?;
 
         let state = self.state().await?;
 
@@ -128,6 +242,19 @@ where
             });
         }
 
+        if self.state.config.mailbox_only {
+            fmt::debug!(
+                "SubDevice {:#06x} is mailbox only, no process data mapped",
+                self.configured_address
+            );
+
+            if matches!(direction, PdoDirection::MasterWrite) {
+                self.state.eeprom_cache.get_mut().clear();
+            }
+
+            return Ok(global_offset);
+        }
+
         let has_coe = self.state.config.mailbox.has_coe;
 
         fmt::debug!(
@@ -147,20 +274,26 @@ where
         match direction {
             PdoDirection::MasterRead => {
                 self.state.config.io.input = PdiSegment {
-                    bytes: (range.bytes.start - group_start_address as usize)
-                        ..(range.bytes.end - group_start_address as usize),
+                    bytes: (range.bytes.start - group_start_address.get() as usize)
+                        ..(range.bytes.end - group_start_address.get() as usize),
                     ..range
                 };
             }
             PdoDirection::MasterWrite => {
                 self.state.config.io.output = PdiSegment {
-                    bytes: (range.bytes.start - group_start_address as usize)
-                        ..(range.bytes.end - group_start_address as usize),
+                    bytes: (range.bytes.start - group_start_address.get() as usize)
+                        ..(range.bytes.end - group_start_address.get() as usize),
                     ..range
                 };
             }
         };
 
+        // Outputs are configured last, so nothing else needs to be read from the EEPROM during
+        // init.
+        if matches!(direction, PdoDirection::MasterWrite) {
+            self.state.eeprom_cache.get_mut().clear();
+        }
+
         fmt::debug!(
             "SubDevice {:#06x} PDI inputs: {:?} ({} bytes), outputs: {:?} ({} bytes)",
             self.configured_address,
@@ -173,6 +306,45 @@ where
         Ok(global_offset)
     }
 
+    /// The first [`SubDeviceOverride`] matching this SubDevice's identity, if any.
+    fn config_override(&self) -> Option<&'static SubDeviceOverride> {
+        self.maindevice
+            .config
+            .subdevice_overrides
+            .iter()
+            .find(|config| config.matches(&self.state.identity))
+    }
+
+    /// Sync manager configuration from an override, or the EEPROM if none is set.
+    async fn sync_manager_config(&self) -> Result<heapless::Vec<SyncManager, 8>, Error> {
+        match self
+            .config_override()
+            .and_then(|config| config.sync_managers)
+        {
+            Some(sync_managers) => heapless::Vec::from_slice(sync_managers)
+                .map_err(|_| Error::Capacity(Item::SyncManager)),
+            None => {
+                self.cached_sii_reader(&self.state.eeprom_cache)
+                    .sync_managers()
+                    .await
+            }
+        }
+    }
+
+    /// FMMU usage from an override, or the EEPROM if none is set.
+    async fn fmmu_config(&self) -> Result<heapless::Vec<FmmuUsage, 16>, Error> {
+        match self.config_override().and_then(|config| config.fmmus) {
+            Some(fmmus) => {
+                heapless::Vec::from_slice(fmmus).map_err(|_| Error::Capacity(Item::Fmmu))
+            }
+            None => {
+                self.cached_sii_reader(&self.state.eeprom_cache)
+                    .fmmus()
+                    .await
+            }
+        }
+    }
+
     async fn write_sm_config(
         &self,
         sync_manager_index: u8,
@@ -209,9 +381,15 @@ where
     /// Configure SM0 and SM1 for mailbox communication.
     async fn configure_mailbox_sms(&mut self, sync_managers: &[SyncManager]) -> Result<(), Error> {
         // Read default mailbox configuration from SubDevice information area
-        let mailbox_config = self.eeprom().mailbox_config().await?;
+        let mailbox_config = self
+            .cached_sii_reader(&self.state.eeprom_cache)
+            .mailbox_config()
+            .await?;
 
-        let general = self.eeprom().general().await?;
+        let general = self
+            .cached_sii_reader(&self.state.eeprom_cache)
+            .general()
+            .await?;
 
         fmt::trace!(
             "SubDevice {:#06x} Mailbox configuration: {:#?}",
@@ -219,7 +397,12 @@ where
             mailbox_config
         );
 
-        if !mailbox_config.has_mailbox() {
+        // Mailbox sizes are taken from overridden sync managers instead of the EEPROM
+        let sm_override = self
+            .config_override()
+            .is_some_and(|config| config.sync_managers.is_some());
+
+        if !sm_override && !mailbox_config.has_mailbox() {
             fmt::trace!(
                 "SubDevice {:#06x} has no valid mailbox configuration",
                 self.configured_address
@@ -237,30 +420,34 @@ where
             // Mailboxes are configured in INIT state
             match sync_manager.usage_type {
                 SyncManagerType::MailboxWrite => {
-                    self.write_sm_config(
-                        sync_manager_index,
-                        sync_manager,
-                        mailbox_config.subdevice_receive_size,
-                    )
-                    .await?;
+                    let len = if sm_override {
+                        sync_manager.length
+                    } else {
+                        mailbox_config.subdevice_receive_size
+                    };
+
+                    self.write_sm_config(sync_manager_index, sync_manager, len)
+                        .await?;
 
                     write_mailbox = Some(Mailbox {
                         address: sync_manager.start_addr,
-                        len: mailbox_config.subdevice_receive_size,
+                        len,
                         sync_manager: sync_manager_index,
                     });
                 }
                 SyncManagerType::MailboxRead => {
-                    self.write_sm_config(
-                        sync_manager_index,
-                        sync_manager,
-                        mailbox_config.subdevice_send_size,
-                    )
-                    .await?;
+                    let len = if sm_override {
+                        sync_manager.length
+                    } else {
+                        mailbox_config.subdevice_send_size
+                    };
+
+                    self.write_sm_config(sync_manager_index, sync_manager, len)
+                        .await?;
 
                     read_mailbox = Some(Mailbox {
                         address: sync_manager.start_addr,
-                        len: mailbox_config.subdevice_send_size,
+                        len,
                         sync_manager: sync_manager_index,
                     });
                 }
@@ -285,6 +472,86 @@ where
         Ok(())
     }
 
+    /// Configure SM0 and SM1 using the bootstrap mailbox layout, ready for the BOOT state.
+    ///
+    /// Must be called in INIT.
+    pub(crate) async fn configure_bootstrap_mailbox(&mut self) -> Result<(), Error> {
+        let bootstrap = self.sii_reader().bootstrap_mailbox_config().await?;
+
+        fmt::trace!(
+            "SubDevice {:#06x} bootstrap mailbox configuration: {:#?}",
+            self.configured_address,
+            bootstrap
+        );
+
+        if !bootstrap.has_mailbox() {
+            fmt::error!(
+                "SubDevice {:#06x} has no bootstrap mailbox",
+                self.configured_address
+            );
+
+            return Err(Error::Mailbox(MailboxError::NoMailbox));
+        }
+
+        let sync_managers = self.sii_reader().sync_managers().await?;
+
+        let mut read_mailbox = None;
+        let mut write_mailbox = None;
+
+        for (sync_manager_index, sync_manager) in sync_managers.iter().enumerate() {
+            let sync_manager_index = sync_manager_index as u8;
+
+            // Same SMs as the standard mailbox, but the bootstrap layout is often different.
+            let (address, len) = match sync_manager.usage_type {
+                SyncManagerType::MailboxWrite => (
+                    bootstrap.subdevice_receive_offset,
+                    bootstrap.subdevice_receive_size,
+                ),
+                SyncManagerType::MailboxRead => (
+                    bootstrap.subdevice_send_offset,
+                    bootstrap.subdevice_send_size,
+                ),
+                _ => continue,
+            };
+
+            self.write_sm_config(
+                sync_manager_index,
+                &SyncManager {
+                    start_addr: address,
+                    ..*sync_manager
+                },
+                len,
+            )
+            .await?;
+
+            let mailbox = Some(Mailbox {
+                address,
+                len,
+                sync_manager: sync_manager_index,
+            });
+
+            if sync_manager.usage_type == SyncManagerType::MailboxWrite {
+                write_mailbox = mailbox;
+            } else {
+                read_mailbox = mailbox;
+            }
+        }
+
+        if read_mailbox.is_none() || write_mailbox.is_none() {
+            fmt::error!(
+                "SubDevice {:#06x} has no mailbox SyncManagers",
+                self.configured_address
+            );
+
+            return Err(Error::Mailbox(MailboxError::NoMailbox));
+        }
+
+        self.state.config.mailbox.read = read_mailbox;
+        self.state.config.mailbox.write = write_mailbox;
+
+        Ok(())
+    }
+
     /// Configure PDOs from CoE registers.
     async fn configure_pdos_coe(
         &self,
@@ -328,7 +595,7 @@ where
                     .get(usize::from(sync_manager_index))
                     .ok_or(Error::NotFound {
                         item: Item::SyncManager,
-                        index: Some(usize::from(sync_manager_index)),
+                        index: Some(u16::from(sync_manager_index)),
                     })?;
 
             if *sm_type != desired_sm_type {
@@ -502,14 +769,20 @@ where
     ) -> Result<PdiSegment, Error> {
         let pdos = match direction {
             PdoDirection::MasterRead => {
-                let read_pdos = self.eeprom().maindevice_read_pdos().await?;
+                let read_pdos = self
+                    .cached_sii_reader(&self.state.eeprom_cache)
+                    .maindevice_read_pdos()
+                    .await?;
 
                 fmt::trace!("SubDevice inputs PDOs {:#?}", read_pdos);
 
                 read_pdos
             }
             PdoDirection::MasterWrite => {
-                let write_pdos = self.eeprom().maindevice_write_pdos().await?;
+                let write_pdos = self
+                    .cached_sii_reader(&self.state.eeprom_cache)
+                    .maindevice_write_pdos()
+                    .await?;
 
                 fmt::trace!("SubDevice outputs PDOs {:#?}", write_pdos);
 
@@ -517,7 +790,17 @@ where
             }
         };
 
-        let fmmu_sm_mappings = self.eeprom().fmmu_mappings().await?;
+        // FMMU_EX refers to the FMMUs described in the EEPROM, so is ignored if they're overridden
+        let fmmu_sm_mappings = if self
+            .config_override()
+            .is_some_and(|config| config.fmmus.is_some())
+        {
+            heapless::Vec::new()
+        } else {
+            self.cached_sii_reader(&self.state.eeprom_cache)
+                .fmmu_mappings()
+                .await?
+        };
 
         let start_offset = *offset;
         let mut total_bit_len = 0;
@@ -585,6 +868,7 @@ where
 }
 
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PdoDirection {
     MasterRead,
     MasterWrite,
@@ -598,3 +882,77 @@ impl PdoDirection {
         }
     }
 }
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        sim::fixture::*, subdevice_group::RecoveryConfig, MainDeviceConfig, PduStorage,
+        SubDeviceOverride, SubDeviceState,
+    };
+
+    #[test]
+    fn mailbox_only() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        static OVERRIDES: &[SubDeviceOverride] = &[SubDeviceOverride {
+            mailbox_only: true,
+            ..SubDeviceOverride::new(0x2, 0x1234)
+        }];
+
+        let network = network();
+
+        let net = network.clone();
+
+        let config = MainDeviceConfig {
+            subdevice_overrides: OVERRIDES,
+            ..MainDeviceConfig::default()
+        };
+
+        run_with_config(&network, &STORAGE, config, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            assert_eq!(group.len(), 4);
+            assert_eq!(group.all_op(&maindevice).await, Ok(true));
+            // Only the EL2004 and EL1004 exchange process data
+            assert_eq!(group.tx_rx(&maindevice).await, Ok(2 + 1));
+
+            {
+                let drive = group.subdevice(&maindevice, 3).unwrap();
+
+                assert!(drive.is_mailbox_only());
+                assert!(drive.inputs_raw().is_empty());
+                assert_eq!(drive.sdo_read::<u32>(0x1018, 2).await, Ok(0x1234));
+            }
+
+            assert_eq!(
+                group
+                    .recover(&maindevice, RecoveryConfig::default(), |event| panic!(
+                        "unexpected event {:?}",
+                        event
+                    ))
+                    .await,
+                Ok(0)
+            );
+
+            group
+                .into_safe_op(&maindevice)
+                .await
+                .expect("OP -> SAFE-OP");
+        });
+
+        assert_eq!(
+            net.with_subdevice(3, |subdevice| subdevice.state()),
+            Some(SubDeviceState::PreOp)
+        );
+        assert_eq!(
+            net.with_subdevice(1, |subdevice| subdevice.state()),
+            Some(SubDeviceState::SafeOp)
+        );
+    }
+}
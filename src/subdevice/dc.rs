@@ -1,9 +1,16 @@
 //! Distributed Clock configuration for a single SubDevice.
 
+use crate::{
+    eeprom::types::SiiDistributedClock, register::DcSyncActivation,
+    subdevice_group::DcConfiguration,
+};
 use core::{fmt, time::Duration};
+use ethercrab_wire::EtherCrabWireRead;
 
 /// DC sync configuration for a SubDevice.
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DcSync {
     /// DC sync is disabled for this SubDevice.
     #[default]
@@ -34,3 +41,149 @@ impl fmt::Display for DcSync {
         }
     }
 }
+
+/// Recommended DC sync settings read from a SubDevice's SII "DC" category.
+///
+/// Use [`Eeprom::dc_defaults`](crate::sii::Eeprom::dc_defaults) to read these from a SubDevice, or
+/// [`DcDefaults::from_sii`] to compute them from a specific operation mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DcDefaults {
+    /// Sync mode to pass to [`SubDeviceRef::set_dc_sync`](crate::SubDeviceRef::set_dc_sync).
+    pub dc_sync: DcSync,
+
+    /// SYNC0 cycle time.
+    pub sync0_period: Duration,
+
+    /// Shift time relative to the SYNC0 pulse.
+    pub sync0_shift: Duration,
+}
+
+impl DcDefaults {
+    /// Compute the sync settings for an SII DC operation mode when running the process data cycle
+    /// every `cycle_time`.
+    ///
+    /// The SYNC0 cycle time is taken from the operation mode if it is set, otherwise it is derived
+    /// from `cycle_time` and the SYNC0 cycle factor. A positive factor multiplies the cycle time and
+    /// a negative factor divides it.
+    pub fn from_sii(mode: &SiiDistributedClock, cycle_time: Duration) -> Self {
+        let sync0_period = if mode.cycle_time_0 > 0 {
+            Duration::from_nanos(mode.cycle_time_0.into())
+        } else {
+            apply_factor(cycle_time, mode.sync0_cycle_factor)
+        };
+
+        // The high byte of `AssignActivate` is written to the SYNC activation register `0x0981`
+        let [_, activate] = mode.assign_activate.to_le_bytes();
+
+        let activate = DcSyncActivation::unpack_from_slice(&[activate]).unwrap_or_default();
+
+        let dc_sync = if activate.sync1 {
+            DcSync::Sync01 {
+                sync1_period: apply_factor(sync0_period, mode.sync1_cycle_factor),
+            }
+        } else if activate.sync0 {
+            DcSync::Sync0
+        } else {
+            DcSync::Disabled
+        };
+
+        Self {
+            dc_sync,
+            sync0_period,
+            sync0_shift: Duration::from_nanos(mode.shift_time_0.into()),
+        }
+    }
+
+    /// Create a group DC configuration from these settings.
+    pub fn dc_configuration(&self, start_delay: Duration) -> DcConfiguration {
+        DcConfiguration {
+            start_delay,
+            sync0_period: self.sync0_period,
+            sync0_shift: self.sync0_shift,
+        }
+    }
+}
+
+/// Scale a cycle time by an SII cycle factor. Factors of `0` leave the cycle time unchanged.
+fn apply_factor(cycle_time: Duration, factor: i16) -> Duration {
+    match factor {
+        0 => cycle_time,
+        f if f > 0 => cycle_time * u32::from(f.unsigned_abs()),
+        f => cycle_time / u32::from(f.unsigned_abs()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(assign_activate: u16) -> SiiDistributedClock {
+        SiiDistributedClock {
+            cycle_time_0: 0,
+            shift_time_0: 0,
+            shift_time_1: 0,
+            sync1_cycle_factor: 0,
+            assign_activate,
+            sync0_cycle_factor: 1,
+            name_string_idx: 0,
+            description_string_idx: 0,
+        }
+    }
+
+    #[test]
+    fn sync0() {
+        let cycle_time = Duration::from_millis(1);
+
+        assert_eq!(
+            DcDefaults::from_sii(&mode(0x0300), cycle_time),
+            DcDefaults {
+                dc_sync: DcSync::Sync0,
+                sync0_period: cycle_time,
+                sync0_shift: Duration::ZERO,
+            }
+        );
+    }
+
+    #[test]
+    fn free_run() {
+        assert_eq!(
+            DcDefaults::from_sii(&mode(0x0000), Duration::from_millis(1)).dc_sync,
+            DcSync::Disabled
+        );
+    }
+
+    #[test]
+    fn sync01_with_factors() {
+        let mode = SiiDistributedClock {
+            shift_time_0: 250_000,
+            sync1_cycle_factor: 2,
+            sync0_cycle_factor: -4,
+            ..mode(0x0700)
+        };
+
+        assert_eq!(
+            DcDefaults::from_sii(&mode, Duration::from_millis(2)),
+            DcDefaults {
+                dc_sync: DcSync::Sync01 {
+                    sync1_period: Duration::from_millis(1),
+                },
+                sync0_period: Duration::from_micros(500),
+                sync0_shift: Duration::from_micros(250),
+            }
+        );
+    }
+
+    #[test]
+    fn fixed_cycle_time() {
+        let mode = SiiDistributedClock {
+            cycle_time_0: 125_000,
+            ..mode(0x0300)
+        };
+
+        assert_eq!(
+            DcDefaults::from_sii(&mode, Duration::from_millis(1)).sync0_period,
+            Duration::from_micros(125)
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Sync manager and watchdog status of a single SubDevice.
+
+use crate::{
+    error::Error,
+    register::RegisterAddress,
+    sync_manager_channel::{Direction, OperationMode, SyncManagerChannel},
+    ConfiguredAddress,
+};
+use core::time::Duration;
+use ethercrab_wire::EtherCrabWireRead;
+
+/// Number of bytes read from the ESC, starting at [`RegisterAddress::WatchdogDivider`].
+pub(crate) const WATCHDOG_LEN: u16 = 0x0444 - 0x0400;
+
+/// Maximum number of sync managers an ESC can have.
+pub(crate) const MAX_SYNC_MANAGERS: u8 = 16;
+
+/// The state of one sync manager, read from its ESC registers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SyncManagerStatus {
+    /// Sync manager index.
+    pub index: u8,
+
+    /// Physical start address of the buffer in the ESC memory.
+    pub start_address: u16,
+
+    /// Buffer length in bytes.
+    pub length: u16,
+
+    /// `true` for a mailbox sync manager, `false` for a buffered process data sync manager.
+    pub mailbox: bool,
+
+    /// `true` if the MainDevice writes to this sync manager, e.g. outputs or the write mailbox.
+    pub writable: bool,
+
+    /// The sync manager is activated by the MainDevice.
+    pub enabled: bool,
+
+    /// The sync manager has been deactivated by the SubDevice application.
+    ///
+    /// This is set by some SubDevices when they detect an error with the process data or mailbox
+    /// configuration.
+    pub pdi_disabled: bool,
+
+    /// The sync manager watchdog is triggered by writes to this sync manager.
+    pub watchdog_enabled: bool,
+
+    /// For mailbox sync managers, the mailbox contains data that has not been read yet.
+    pub mailbox_full: bool,
+}
+
+impl SyncManagerStatus {
+    fn parse(index: u8, raw: &[u8]) -> Result<Self, Error> {
+        let channel = SyncManagerChannel::unpack_from_slice(raw)?;
+
+        Ok(Self {
+            index,
+            start_address: channel.physical_start_address,
+            length: channel.length_bytes,
+            mailbox: channel.control.operation_mode == OperationMode::Mailbox,
+            writable: channel.control.direction == Direction::MasterWrite,
+            enabled: channel.enable.enable,
+            pdi_disabled: channel.enable.channel_pdi_disabled,
+            watchdog_enabled: channel.control.watchdog_enable,
+            mailbox_full: channel.status.mailbox_full,
+        })
+    }
+}
+
+/// Process data and PDI watchdog configuration and status of a SubDevice.
+///
+/// See ETG1000.4 section 6.3 Watchdogs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WatchdogStatus {
+    /// The time without a write to a watchdog enabled sync manager after which the SubDevice stops
+    /// driving its outputs, or `None` if the watchdog is disabled.
+    pub sm_watchdog_timeout: Option<Duration>,
+
+    /// The time without any PDI activity after which the PDI watchdog expires, or `None` if the
+    /// watchdog is disabled.
+    pub pdi_watchdog_timeout: Option<Duration>,
+
+    /// The process data watchdog has expired.
+    ///
+    /// This flag is also set after a SubDevice is powered on, before the first process data is
+    /// written.
+    pub sm_watchdog_expired: bool,
+
+    /// The number of times the process data watchdog has expired, saturating at 255.
+    pub sm_watchdog_expirations: u8,
+
+    /// The number of times the PDI watchdog has expired, saturating at 255.
+    pub pdi_watchdog_expirations: u8,
+}
+
+impl WatchdogStatus {
+    /// Parse the raw register contents starting at [`RegisterAddress::WatchdogDivider`].
+    fn parse(raw: &[u8]) -> Result<Self, Error> {
+        let raw = raw
+            .get(..usize::from(WATCHDOG_LEN))
+            .ok_or(Error::Internal)?;
+
+        let base = u16::from(RegisterAddress::WatchdogDivider);
+        let offset = |register: RegisterAddress| usize::from(u16::from(register) - base);
+        let word = |register: RegisterAddress| {
+            let start = offset(register);
+
+            u16::from_le_bytes([raw[start], raw[start + 1]])
+        };
+
+        // The divider counts 40ns ticks, minus 2
+        let tick =
+            Duration::from_nanos(40) * (u32::from(word(RegisterAddress::WatchdogDivider)) + 2);
+
+        let timeout = |register: RegisterAddress| match word(register) {
+            0 => None,
+            ticks => Some(tick * u32::from(ticks)),
+        };
+
+        Ok(Self {
+            sm_watchdog_timeout: timeout(RegisterAddress::SyncManagerWatchdog),
+            pdi_watchdog_timeout: timeout(RegisterAddress::PdiWatchdog),
+            // Bit is set while the watchdog is running or disabled
+            sm_watchdog_expired: word(RegisterAddress::SyncManagerWatchdogStatus) & 0x0001 == 0,
+            sm_watchdog_expirations: raw[offset(RegisterAddress::SyncManagerWatchdogCounter)],
+            pdi_watchdog_expirations: raw[offset(RegisterAddress::PdiWatchdogCounter)],
+        })
+    }
+}
+
+/// A snapshot of the sync manager and watchdog status of a SubDevice.
+///
+/// Returned by [`SubDeviceRef::diagnostics`](crate::SubDeviceRef::diagnostics).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SubDeviceDiagnostics {
+    /// The configured station address of the SubDevice the status was read from.
+    pub configured_address: ConfiguredAddress,
+
+    /// Watchdog configuration and status.
+    pub watchdog: WatchdogStatus,
+
+    /// Status of every sync manager supported by the SubDevice.
+    pub sync_managers: heapless::Vec<SyncManagerStatus, { MAX_SYNC_MANAGERS as usize }>,
+}
+
+impl SubDeviceDiagnostics {
+    /// Parse the watchdog registers and `raw_sync_managers`, the registers of consecutive sync
+    /// managers starting at SM0.
+    pub(crate) fn parse(
+        configured_address: ConfiguredAddress,
+        raw_watchdog: &[u8],
+        raw_sync_managers: &[u8],
+    ) -> Result<Self, Error> {
+        let sync_managers = raw_sync_managers
+            .chunks_exact(8)
+            .take(usize::from(MAX_SYNC_MANAGERS))
+            .enumerate()
+            .map(|(index, raw)| SyncManagerStatus::parse(index as u8, raw))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            configured_address,
+            watchdog: WatchdogStatus::parse(raw_watchdog)?,
+            sync_managers,
+        })
+    }
+
+    /// Returns `true` if the SubDevice has stopped driving its outputs because the process data
+    /// watchdog expired.
+    ///
+    /// This happens when no process data has been written to a watchdog enabled sync manager within
+    /// [`WatchdogStatus::sm_watchdog_timeout`], e.g. because the process data cycle stalled or
+    /// frames were lost.
+    pub fn outputs_frozen(&self) -> bool {
+        self.watchdog.sm_watchdog_expired
+            && self.watchdog.sm_watchdog_timeout.is_some()
+            && self
+                .sync_managers
+                .iter()
+                .any(|sm| sm.enabled && sm.watchdog_enabled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watchdog_raw(status: u16) -> [u8; WATCHDOG_LEN as usize] {
+        let mut raw = [0u8; WATCHDOG_LEN as usize];
+
+        // Default divider of 100us
+        raw[0x00..0x02].copy_from_slice(&2498u16.to_le_bytes());
+        // PDI watchdog disabled, SM watchdog 100ms
+        raw[0x20..0x22].copy_from_slice(&1000u16.to_le_bytes());
+        raw[0x40..0x42].copy_from_slice(&status.to_le_bytes());
+        raw[0x42] = 3;
+        raw[0x43] = 1;
+
+        raw
+    }
+
+    #[test]
+    fn parse_watchdog() {
+        assert_eq!(
+            WatchdogStatus::parse(&watchdog_raw(0x0001)),
+            Ok(WatchdogStatus {
+                sm_watchdog_timeout: Some(Duration::from_millis(100)),
+                pdi_watchdog_timeout: None,
+                sm_watchdog_expired: false,
+                sm_watchdog_expirations: 3,
+                pdi_watchdog_expirations: 1,
+            })
+        );
+
+        assert_eq!(WatchdogStatus::parse(&[0u8; 8]), Err(Error::Internal));
+    }
+
+    #[test]
+    fn outputs_frozen() {
+        let sync_managers = [
+            // Write mailbox, enabled
+            0x00, 0x10, 0x80, 0x00, 0x26, 0x00, 0x01, 0x00, //
+            // Outputs, watchdog enabled
+            0x00, 0x11, 0x02, 0x00, 0x64, 0x00, 0x01, 0x00,
+        ];
+
+        let diagnostics = SubDeviceDiagnostics::parse(
+            ConfiguredAddress::new(0x1001),
+            &watchdog_raw(0x0000),
+            &sync_managers,
+        )
+        .unwrap();
+
+        assert_eq!(
+            diagnostics.sync_managers[1],
+            SyncManagerStatus {
+                index: 1,
+                start_address: 0x1100,
+                length: 2,
+                mailbox: false,
+                writable: true,
+                enabled: true,
+                pdi_disabled: false,
+                watchdog_enabled: true,
+                mailbox_full: false,
+            }
+        );
+        assert!(diagnostics.sync_managers[0].mailbox);
+        assert!(diagnostics.outputs_frozen());
+
+        // Outputs SM not enabled yet, e.g. in INIT
+        let mut disabled = sync_managers;
+        disabled[14] = 0x00;
+
+        let diagnostics = SubDeviceDiagnostics::parse(
+            ConfiguredAddress::new(0x1001),
+            &watchdog_raw(0x0000),
+            &disabled,
+        )
+        .unwrap();
+
+        assert!(!diagnostics.outputs_frozen());
+
+        let diagnostics = SubDeviceDiagnostics::parse(
+            ConfiguredAddress::new(0x1001),
+            &watchdog_raw(0x0001),
+            &sync_managers,
+        )
+        .unwrap();
+
+        assert!(!diagnostics.outputs_frozen());
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        sim::{fixture::*, SimSubDevice},
+        ConfiguredAddress, PduStorage,
+    };
+    use core::time::Duration;
+
+    #[test]
+    fn sm_watchdog() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            group.tx_rx(&maindevice).await.expect("TX/RX");
+
+            let outputs = group.subdevice(&maindevice, 1).unwrap();
+
+            let diagnostics = outputs.diagnostics().await.expect("read");
+
+            assert_eq!(
+                diagnostics.configured_address,
+                ConfiguredAddress::new(0x1001)
+            );
+            assert_eq!(diagnostics.sync_managers.len(), 8);
+            assert_eq!(
+                diagnostics.watchdog.sm_watchdog_timeout,
+                Some(Duration::from_millis(100))
+            );
+            assert!(diagnostics
+                .sync_managers
+                .iter()
+                .any(|sm| sm.enabled && sm.writable && sm.watchdog_enabled));
+            assert!(!diagnostics.outputs_frozen());
+
+            net.with_subdevice(1, SimSubDevice::expire_sm_watchdog);
+
+            let diagnostics = outputs.diagnostics().await.expect("read");
+
+            assert!(diagnostics.outputs_frozen());
+            assert_eq!(diagnostics.watchdog.sm_watchdog_expirations, 1);
+
+            // Process data restarts the watchdog
+            group.tx_rx(&maindevice).await.expect("TX/RX");
+
+            assert!(!outputs.diagnostics().await.expect("read").outputs_frozen());
+
+            // Inputs only SubDevice has no watchdog enabled sync managers
+            let inputs = group.subdevice(&maindevice, 2).unwrap();
+
+            net.with_subdevice(2, SimSubDevice::expire_sm_watchdog);
+
+            assert!(!inputs.diagnostics().await.expect("read").outputs_frozen());
+        });
+    }
+}
@@ -1,15 +1,19 @@
 use crate::{
-    eeprom::types::{CategoryType, DefaultMailbox, PdoEntry, SiiGeneral},
+    eeprom::types::{
+        BootstrapMailbox, CategoryType, DefaultMailbox, PdoEntry, SiiDistributedClock, SiiGeneral,
+    },
     eeprom::{
-        device_reader::SII_FIRST_CATEGORY_START,
+        device_reader::{DeviceEeprom, SII_FIRST_CATEGORY_START},
+        esi::{EsiDevice, EsiPdo, EsiSyncManager, SiiMismatch},
+        sii_checksum,
         types::{FmmuEx, FmmuUsage, Pdo, PdoType, SyncManager},
-        ChunkReader, EepromDataProvider,
+        ChunkReader, EepromDataProvider, SII_CHECKSUM_WORD,
     },
-    error::{EepromError, Error, Item},
+    error::{saturate_u16, EepromError, Error, Item},
     fmt,
-    subdevice::SubDeviceIdentity,
+    subdevice::{DcDefaults, DcSync, SubDeviceIdentity},
 };
-use core::marker::PhantomData;
+use core::{marker::PhantomData, time::Duration};
 use embedded_io_async::{Read, ReadExactError};
 use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireReadSized, EtherCrabWireSized};
 
@@ -28,23 +32,35 @@ where
 
     /// Start a reader at the given address in words, returning at most `len` bytes.
     fn start_at(&self, word_addr: u16, len_bytes: u16) -> ChunkReader<P> {
-        ChunkReader::new(self.provider.clone(), word_addr, len_bytes / 2)
+        ChunkReader::new(
+            self.provider.clone(),
+            word_addr.into(),
+            (len_bytes / 2).into(),
+        )
     }
 
     /// Search for a given category and return a reader over the bytes contained within the category
     /// if it is found.
     async fn category(&self, category: CategoryType) -> Result<Option<ChunkReader<P>>, Error> {
+        if let Some(cached) = self.provider.cached_category(category) {
+            return Ok(cached.map(|(word_addr, len_words)| {
+                ChunkReader::new(self.provider.clone(), word_addr, len_words)
+            }));
+        }
+
         let mut reader = self.provider.clone();
 
-        let mut word_addr = SII_FIRST_CATEGORY_START;
+        let mut word_addr = u32::from(SII_FIRST_CATEGORY_START);
 
         loop {
             let chunk = reader.read_chunk(word_addr).await?;
 
             word_addr += 2;
 
-            let (c1, chunk) = fmt::unwrap_opt!(chunk.split_first_chunk::<2>());
-            let (c2, _chunk) = fmt::unwrap_opt!(chunk.split_first_chunk::<2>());
+            let (c1, c2) = chunk
+                .split_first_chunk::<2>()
+                .and_then(|(c1, chunk)| Some((c1, chunk.split_first_chunk::<2>()?.0)))
+                .ok_or(Error::Eeprom(EepromError::Decode))?;
 
             let category_type = CategoryType::from(u16::from_le_bytes(*c1));
             let len_words = u16::from_le_bytes(*c2);
@@ -57,12 +73,15 @@ where
                 len_words
             );
 
+            self.provider
+                .cache_category(category_type, word_addr, len_words.into());
+
             match category_type {
                 cat if cat == category => {
                     break Ok(Some(ChunkReader::new(
                         self.provider.clone(),
                         word_addr,
-                        len_words,
+                        len_words.into(),
                     )));
                 }
                 CategoryType::End => break Ok(None),
@@ -70,7 +89,7 @@ where
             }
 
             // Next category starts after the current category's data. This is a WORD address.
-            word_addr += len_words;
+            word_addr += u32::from(len_words);
         }
     }
 
@@ -118,6 +137,41 @@ where
         Ok(DefaultMailbox::unpack_from_slice(&buf)?)
     }
 
+    /// Check the configuration area checksum.
+    pub(crate) async fn verify_checksum(&self) -> Result<(), Error> {
+        let mut config_area = [0u8; 16];
+
+        self.read_into(0, &mut config_area).await?;
+
+        let expected = sii_checksum(&config_area[0..usize::from(SII_CHECKSUM_WORD) * 2]);
+        let actual = config_area[usize::from(SII_CHECKSUM_WORD) * 2];
+
+        if expected != actual {
+            fmt::error!(
+                "EEPROM configuration area checksum is {:#04x}, expected {:#04x}",
+                actual,
+                expected
+            );
+
+            return Err(Error::Eeprom(EepromError::Checksum { expected, actual }));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn bootstrap_mailbox_config(&self) -> Result<BootstrapMailbox, Error> {
+        // Bootstrap mailbox config is 8 bytes long, directly before the standard mailbox config.
+        let mut reader = self.start_at(0x0014, BootstrapMailbox::PACKED_LEN as u16);
+
+        fmt::trace!("Get bootstrap mailbox config");
+
+        let mut buf = BootstrapMailbox::buffer();
+
+        reader.read_exact(&mut buf).await?;
+
+        Ok(BootstrapMailbox::unpack_from_slice(&buf)?)
+    }
+
     pub(crate) async fn general(&self) -> Result<SiiGeneral, Error> {
         let mut reader = self
             .category(CategoryType::General)
@@ -213,7 +267,7 @@ where
         Ok(mappings)
     }
 
-    async fn pdos(&self, direction: PdoType) -> Result<heapless::Vec<Pdo, 64>, Error> {
+    pub(crate) async fn pdos(&self, direction: PdoType) -> Result<heapless::Vec<Pdo, 64>, Error> {
         let mut pdos = heapless::Vec::new();
 
         fmt::trace!("Get {:?} PDOs", direction);
@@ -256,6 +310,224 @@ where
         self.pdos(PdoType::Rx).await
     }
 
+    /// Read the entries of the PDO with the given index, or `None` if the PDO is not present.
+    pub(crate) async fn pdo_entries<const N: usize>(
+        &self,
+        direction: PdoType,
+        pdo_index: u16,
+    ) -> Result<Option<heapless::Vec<PdoEntry, N>>, Error> {
+        fmt::trace!("Get entries for {:?} PDO {:#06x}", direction, pdo_index);
+
+        let mut cat = self.items::<Pdo>(CategoryType::from(direction)).await?;
+
+        while let Some(pdo) = cat.next().await? {
+            let mut entries = heapless::Vec::new();
+
+            for idx in 0..pdo.num_entries {
+                let Some(entry) = cat.next_sub_item::<PdoEntry>().await? else {
+                    fmt::error!("Failed to read PDO entry {}", idx);
+
+                    return Err(Error::Eeprom(EepromError::Decode));
+                };
+
+                // Entries of other PDOs still need to be read to get to the next PDO
+                if pdo.index == pdo_index {
+                    entries
+                        .push(entry)
+                        .map_err(|_| Error::Capacity(Item::PdoEntry))?;
+                }
+            }
+
+            if pdo.index == pdo_index {
+                return Ok(Some(entries));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Distributed Clock operation modes.
+    pub(crate) async fn distributed_clock<const N: usize>(
+        &self,
+    ) -> Result<heapless::Vec<SiiDistributedClock, N>, Error> {
+        let mut modes = heapless::Vec::new();
+
+        fmt::trace!("Get DC operation modes");
+
+        let mut cat = self
+            .items::<SiiDistributedClock>(CategoryType::DistributedClock)
+            .await?;
+
+        while let Some(mode) = cat.next().await? {
+            modes
+                .push(mode)
+                .map_err(|_| Error::Eeprom(EepromError::SectionOverrun))?;
+        }
+
+        fmt::debug!("Discovered DC operation modes:\n{:#?}", modes);
+
+        Ok(modes)
+    }
+
+    /// Sync settings from the first DC operation mode that uses SYNC0.
+    pub(crate) async fn dc_defaults(
+        &self,
+        cycle_time: Duration,
+    ) -> Result<Option<DcDefaults>, Error> {
+        let modes = self.distributed_clock::<16>().await?;
+
+        Ok(modes
+            .iter()
+            .map(|mode| DcDefaults::from_sii(mode, cycle_time))
+            .find(|defaults| defaults.dc_sync != DcSync::Disabled))
+    }
+
+    /// Compare identity, SyncManagers and PDOs against an ESI description, returning at most `N`
+    /// differences.
+    pub(crate) async fn check_esi<const N: usize>(
+        &self,
+        esi: &EsiDevice<'_>,
+    ) -> Result<heapless::Vec<SiiMismatch, N>, Error> {
+        let mut mismatches = heapless::Vec::new();
+
+        // Any differences past `N` are dropped
+        let mut report = |mismatch: SiiMismatch| {
+            fmt::debug!("SII differs from ESI: {:?}", mismatch);
+
+            let _ = mismatches.push(mismatch);
+        };
+
+        let identity = self.identity().await?;
+
+        if identity.vendor_id != esi.vendor_id {
+            report(SiiMismatch::VendorId {
+                esi: esi.vendor_id,
+                sii: identity.vendor_id,
+            });
+        }
+
+        if identity.product_id != esi.product_code {
+            report(SiiMismatch::ProductCode {
+                esi: esi.product_code,
+                sii: identity.product_id,
+            });
+        }
+
+        if identity.revision != esi.revision {
+            report(SiiMismatch::Revision {
+                esi: esi.revision,
+                sii: identity.revision,
+            });
+        }
+
+        let sync_managers = self.sync_managers().await?;
+
+        if sync_managers.len() != esi.sync_managers.len() {
+            report(SiiMismatch::SyncManagerCount {
+                esi: esi.sync_managers.len(),
+                sii: sync_managers.len(),
+            });
+        }
+
+        for (index, (sii, esi)) in sync_managers
+            .iter()
+            .map(|sm| EsiSyncManager::from(*sm))
+            .zip(esi.sync_managers.iter().copied())
+            .enumerate()
+        {
+            let length_matches = esi.length.map_or(true, |length| sii.length == Some(length));
+
+            if sii.start_addr != esi.start_addr
+                || !length_matches
+                || sii.control != esi.control
+                || sii.enable != esi.enable
+            {
+                report(SiiMismatch::SyncManager {
+                    index: index as u8,
+                    esi,
+                    sii,
+                });
+            }
+        }
+
+        for (pdo_type, esi_pdos) in [(PdoType::Rx, esi.rx_pdos), (PdoType::Tx, esi.tx_pdos)] {
+            let mut sii_indices = heapless::Vec::<u16, 64>::new();
+
+            let mut cat = self.items::<Pdo>(CategoryType::from(pdo_type)).await?;
+
+            while let Some(pdo) = cat.next().await? {
+                sii_indices
+                    .push(pdo.index)
+                    .map_err(|_| Error::Capacity(Item::Pdo))?;
+
+                let esi_pdo = esi_pdos.iter().find(|esi_pdo| esi_pdo.index == pdo.index);
+
+                let mut entries_match = esi_pdo.map_or(false, |esi_pdo| {
+                    esi_pdo.entries.len() == usize::from(pdo.num_entries)
+                });
+
+                // Entries must always be read to get to the next PDO
+                for idx in 0..pdo.num_entries {
+                    let Some(entry) = cat.next_sub_item::<PdoEntry>().await? else {
+                        fmt::error!("Failed to read PDO entry {}", idx);
+
+                        return Err(Error::Eeprom(EepromError::Decode));
+                    };
+
+                    let expected =
+                        esi_pdo.and_then(|esi_pdo| esi_pdo.entries.get(usize::from(idx)));
+
+                    entries_match &= expected.map_or(false, |expected| {
+                        expected.index == entry.index
+                            && expected.sub_index == entry.sub_index
+                            && expected.bit_len == entry.data_length_bits
+                    });
+                }
+
+                let Some(esi_pdo) = esi_pdo else {
+                    report(SiiMismatch::UnexpectedPdo {
+                        pdo_type,
+                        index: pdo.index,
+                    });
+
+                    continue;
+                };
+
+                if let Some(sync_manager) = esi_pdo
+                    .sync_manager
+                    .filter(|sync_manager| *sync_manager != pdo.sync_manager)
+                {
+                    report(SiiMismatch::PdoSyncManager {
+                        pdo_type,
+                        index: pdo.index,
+                        esi: sync_manager,
+                        sii: pdo.sync_manager,
+                    });
+                }
+
+                if !entries_match {
+                    report(SiiMismatch::PdoEntries {
+                        pdo_type,
+                        index: pdo.index,
+                    });
+                }
+            }
+
+            for EsiPdo { index, .. } in esi_pdos
+                .iter()
+                .filter(|esi_pdo| esi_pdo.sync_manager.is_some())
+                .filter(|esi_pdo| !sii_indices.contains(&esi_pdo.index))
+            {
+                report(SiiMismatch::MissingPdo {
+                    pdo_type,
+                    index: *index,
+                });
+            }
+        }
+
+        Ok(mismatches)
+    }
+
     /// Find a string in the device EEPROM.
     ///
     /// An index of 0 denotes an empty string and will always return `Ok(None)`.
@@ -301,8 +573,8 @@ where
 
             if string_len > N {
                 return Err(Error::StringTooLong {
-                    max_length: N,
-                    string_length: string_len,
+                    max_length: saturate_u16(N),
+                    string_length: saturate_u16(string_len),
                 });
             }
 
@@ -345,6 +617,31 @@ where
         }
     }
 
+    /// EEPROM size in bytes, from the `Size` field at word `0x003e`.
+    ///
+    /// Defined in ETG2010 Table 2 as the size in KiBit, minus one.
+    pub(crate) async fn size(&self) -> Result<usize, Error> {
+        let mut reader = self.start_at(0x003e, 2);
+
+        let mut buf = [0u8; 2];
+
+        reader.read_exact(&mut buf).await?;
+
+        Ok((usize::from(u16::from_le_bytes(buf)) + 1) * 1024 / 8)
+    }
+
+    /// Read `buf.len()` bytes starting at the given word address into `buf`.
+    pub(crate) async fn read_into(&self, start_word: u32, buf: &mut [u8]) -> Result<(), Error> {
+        let len_words = u32::try_from(buf.len().div_ceil(2))
+            .map_err(|_| Error::Eeprom(EepromError::SectionOverrun))?;
+
+        let mut reader = ChunkReader::new(self.provider.clone(), start_word, len_words);
+
+        reader.read_exact(buf).await?;
+
+        Ok(())
+    }
+
     pub(crate) async fn items<T>(
         &self,
         category: CategoryType,
@@ -364,6 +661,232 @@ where
     }
 }
 
+/// Typed access to the SubDevice Information Interface (SII) categories stored in a SubDevice's
+/// EEPROM.
+///
+/// Created with [`SubDeviceRef::eeprom`](crate::SubDeviceRef::eeprom). Every method reads and
+/// parses its category from the device when it is called. Nothing is cached.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts, std::ethercat_now};
+/// use ethercrab::sii::PdoType;
+///
+/// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+/// # async fn example() -> Result<(), ethercrab::error::Error> {
+/// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+/// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+/// # let group = maindevice.init_single_group::<8, 8>(ethercat_now).await?;
+/// let subdevice = group.subdevice(&maindevice, 0)?;
+///
+/// let eeprom = subdevice.eeprom();
+///
+/// let general = eeprom.general().await?;
+///
+/// log::info!(
+///     "Order number {:?}, CoE {:?}",
+///     eeprom.string::<64>(general.order_string_idx).await?,
+///     general.coe_details
+/// );
+///
+/// for pdo in eeprom.pdos(PdoType::Tx).await? {
+///     log::info!("Input PDO {:#06x}, {} bits", pdo.index, pdo.bit_len);
+///
+///     for entry in eeprom.pdo_entries::<16>(PdoType::Tx, pdo.index).await?.unwrap_or_default() {
+///         log::info!("--> {:#06x}:{}", entry.index, entry.sub_index);
+///     }
+/// }
+/// # Ok(()) }
+/// ```
+pub struct Eeprom<'subdevice> {
+    inner: SubDeviceEeprom<DeviceEeprom<'subdevice>>,
+}
+
+impl<'subdevice> Eeprom<'subdevice> {
+    pub(crate) fn new(inner: SubDeviceEeprom<DeviceEeprom<'subdevice>>) -> Self {
+        Self { inner }
+    }
+
+    /// Vendor ID, product code, revision and serial number.
+    pub async fn identity(&self) -> Result<SubDeviceIdentity, Error> {
+        self.inner.identity().await
+    }
+
+    /// Standard mailbox configuration.
+    pub async fn mailbox_config(&self) -> Result<DefaultMailbox, Error> {
+        self.inner.mailbox_config().await
+    }
+
+    /// Mailbox configuration used in the BOOT state.
+    pub async fn bootstrap_mailbox_config(&self) -> Result<BootstrapMailbox, Error> {
+        self.inner.bootstrap_mailbox_config().await
+    }
+
+    /// The "General" category.
+    ///
+    /// Returns [`EepromError::NoCategory`] if the category is not present.
+    pub async fn general(&self) -> Result<SiiGeneral, Error> {
+        self.inner.general().await
+    }
+
+    /// Read a string from the "Strings" category.
+    ///
+    /// String indices are 1-based, as used by e.g. [`SiiGeneral::name_string_idx`]. An index of 0
+    /// denotes an empty string and will always return `Ok(None)`. Non-ASCII characters are
+    /// replaced with `'?'`.
+    ///
+    /// Returns [`Error::StringTooLong`] if the string is longer than `N` bytes.
+    pub async fn string<const N: usize>(
+        &self,
+        index: u8,
+    ) -> Result<Option<heapless::String<N>>, Error> {
+        self.inner.find_string(index).await
+    }
+
+    /// How each FMMU is used, from the "FMMU" category.
+    pub async fn fmmus(&self) -> Result<heapless::Vec<FmmuUsage, 16>, Error> {
+        self.inner.fmmus().await
+    }
+
+    /// The SyncManager each FMMU is mapped to, from the "FMMU_EX" category.
+    pub async fn fmmu_mappings(&self) -> Result<heapless::Vec<FmmuEx, 16>, Error> {
+        self.inner.fmmu_mappings().await
+    }
+
+    /// SyncManager configuration, from the "SyncManager" category.
+    pub async fn sync_managers(&self) -> Result<heapless::Vec<SyncManager, 8>, Error> {
+        self.inner.sync_managers().await
+    }
+
+    /// PDOs from the "TxPDO" (inputs) or "RxPDO" (outputs) category.
+    pub async fn pdos(&self, direction: PdoType) -> Result<heapless::Vec<Pdo, 64>, Error> {
+        self.inner.pdos(direction).await
+    }
+
+    /// Entries of the PDO with index `pdo_index`, or `None` if no such PDO exists.
+    ///
+    /// Returns [`Error::Capacity`] if the PDO has more than `N` entries.
+    pub async fn pdo_entries<const N: usize>(
+        &self,
+        direction: PdoType,
+        pdo_index: u16,
+    ) -> Result<Option<heapless::Vec<PdoEntry, N>>, Error> {
+        self.inner.pdo_entries(direction, pdo_index).await
+    }
+
+    /// Distributed Clock operation modes, from the "DC" category.
+    ///
+    /// Returns [`EepromError::SectionOverrun`] if there are more than `N` operation modes.
+    pub async fn distributed_clock<const N: usize>(
+        &self,
+    ) -> Result<heapless::Vec<SiiDistributedClock, N>, Error> {
+        self.inner.distributed_clock().await
+    }
+
+    /// Recommended DC sync settings from the first operation mode in the "DC" category that uses
+    /// SYNC0, for a process data cycle running every `cycle_time`.
+    ///
+    /// Returns `None` if the SubDevice does not publish any DC operation modes that use SYNC0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts, std::ethercat_now};
+    /// # use core::time::Duration;
+    /// use ethercrab::subdevice_group::DcConfiguration;
+    ///
+    /// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # async fn example() -> Result<(), ethercrab::error::Error> {
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// let mut group = maindevice.init_single_group::<8, 8>(ethercat_now).await?;
+    ///
+    /// let cycle_time = Duration::from_millis(1);
+    ///
+    /// let mut dc_conf = DcConfiguration {
+    ///     start_delay: Duration::from_millis(100),
+    ///     sync0_period: cycle_time,
+    ///     sync0_shift: Duration::ZERO,
+    /// };
+    ///
+    /// for mut subdevice in group.iter(&maindevice) {
+    ///     if let Some(defaults) = subdevice.eeprom().dc_defaults(cycle_time).await? {
+    ///         subdevice.set_dc_sync(defaults.dc_sync);
+    ///
+    ///         dc_conf = defaults.dc_configuration(dc_conf.start_delay);
+    ///     }
+    /// }
+    ///
+    /// let group = group
+    ///     .into_pre_op_pdi(&maindevice)
+    ///     .await?
+    ///     .configure_dc_sync(&maindevice, dc_conf)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn dc_defaults(&self, cycle_time: Duration) -> Result<Option<DcDefaults>, Error> {
+        self.inner.dc_defaults(cycle_time).await
+    }
+
+    /// Compare the SII contents against the device description from an ESI file, e.g. to find
+    /// SubDevices flashed with the wrong or an outdated SII.
+    ///
+    /// The identity, SyncManagers and PDO mappings are checked. An empty list means no differences
+    /// were found. At most `N` differences are returned.
+    ///
+    /// Returns [`Error::Capacity`] if the SII contains more than 64 input or output PDOs.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts, std::ethercat_now};
+    /// use ethercrab::sii::{EsiDevice, EsiPdo, EsiPdoEntry, EsiSyncManager};
+    ///
+    /// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # async fn example() -> Result<(), ethercrab::error::Error> {
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # let group = maindevice.init_single_group::<8, 8>(ethercat_now).await?;
+    /// let subdevice = group.subdevice(&maindevice, 0)?;
+    ///
+    /// // Values taken from the EL2828's `<Device>` element in the Beckhoff ESI file
+    /// let esi = EsiDevice {
+    ///     vendor_id: 0x0000_0002,
+    ///     product_code: 0x0b0c_3052,
+    ///     revision: 0x0011_0000,
+    ///     sync_managers: &[EsiSyncManager {
+    ///         start_addr: 0x0f00,
+    ///         length: None,
+    ///         control: 0x44,
+    ///         enable: true,
+    ///     }],
+    ///     rx_pdos: &[EsiPdo {
+    ///         index: 0x1600,
+    ///         sync_manager: Some(0),
+    ///         entries: &[EsiPdoEntry {
+    ///             index: 0x7000,
+    ///             sub_index: 1,
+    ///             bit_len: 1,
+    ///         }],
+    ///     }],
+    ///     tx_pdos: &[],
+    /// };
+    ///
+    /// for mismatch in subdevice.eeprom().check_esi::<16>(&esi).await? {
+    ///     log::warn!("{}: {:?}", subdevice.name(), mismatch);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub async fn check_esi<const N: usize>(
+        &self,
+        esi: &EsiDevice<'_>,
+    ) -> Result<heapless::Vec<SiiMismatch, N>, Error> {
+        self.inner.check_esi(esi).await
+    }
+}
+
 pub struct CategoryIterator<P, T> {
     reader: ChunkReader<P>,
     item: PhantomData<T>,
@@ -417,7 +940,9 @@ mod tests {
 
     use super::*;
     use crate::{
+        base_data_types::PrimitiveDataType,
         eeprom::{
+            esi::EsiPdoEntry,
             file_reader::EepromFile,
             types::{
                 CoeDetails, Flags, MailboxProtocols, PdoFlags, PortStatus, PortStatuses,
@@ -545,7 +1070,7 @@ mod tests {
             e.find_string::<8>(idx).await,
             Err(Error::StringTooLong {
                 max_length: 8,
-                string_length: expected.len(),
+                string_length: expected.len() as u16,
             }),
             "Read should fail if buffer is too small"
         );
@@ -736,6 +1261,42 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn checksum() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        for dump in ["akd", "ek1100", "el2828", "el2889"] {
+            let e = SubDeviceEeprom::new(EepromFile::new(format!("dumps/eeprom/{dump}.hex")));
+
+            assert_eq!(e.verify_checksum().await, Ok(()), "{dump}");
+        }
+    }
+
+    #[tokio::test]
+    async fn get_bootstrap_mailbox_config() {
+        let e = SubDeviceEeprom::new(EepromFile::new("dumps/eeprom/akd.hex"));
+
+        let bootstrap = e.bootstrap_mailbox_config().await;
+
+        assert_eq!(
+            bootstrap,
+            Ok(BootstrapMailbox {
+                subdevice_receive_offset: 0x1800,
+                subdevice_receive_size: 0x0400,
+                subdevice_send_offset: 0x1c00,
+                subdevice_send_size: 0x0400,
+            })
+        );
+        assert_eq!(bootstrap.map(|b| b.has_mailbox()), Ok(true));
+
+        let e = SubDeviceEeprom::new(EepromFile::new("dumps/eeprom/el2828.hex"));
+
+        assert_eq!(
+            e.bootstrap_mailbox_config().await.map(|b| b.has_mailbox()),
+            Ok(false)
+        );
+    }
+
     #[tokio::test]
     async fn default_mailbox_config_matches_sms() {
         let e = SubDeviceEeprom::new(EepromFile::new("dumps/eeprom/akd.hex"));
@@ -946,4 +1507,455 @@ mod tests {
             "device description"
         );
     }
+
+    #[tokio::test]
+    async fn eeprom_size() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        assert_eq!(
+            SubDeviceEeprom::new(EepromFile::new("dumps/eeprom/el2889.hex"))
+                .size()
+                .await,
+            Ok(2048)
+        );
+        assert_eq!(
+            SubDeviceEeprom::new(EepromFile::new("dumps/eeprom/hbm_clipx_eeprom_dump.bin"))
+                .size()
+                .await,
+            Ok(4096)
+        );
+    }
+
+    #[tokio::test]
+    async fn el2262_pdo_entries() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let e = SubDeviceEeprom::new(EepromFile::new("dumps/eeprom/el2262.bin"));
+
+        // PDO in the middle of the category, so all entries of previous PDOs must be skipped
+        assert_eq!(
+            e.pdo_entries::<4>(PdoType::Rx, 0x1680).await,
+            Ok(Some(
+                heapless::Vec::from_slice(&[PdoEntry {
+                    index: 0x7000,
+                    sub_index: 2,
+                    name_string_idx: 18,
+                    data_type: PrimitiveDataType::Bool,
+                    data_length_bits: 1,
+                    flags: 0,
+                }])
+                .unwrap()
+            ))
+        );
+
+        assert_eq!(
+            e.pdo_entries::<4>(PdoType::Tx, 0x1702).await,
+            Ok(Some(
+                heapless::Vec::from_slice(&[PdoEntry {
+                    index: 0x1d09,
+                    sub_index: 0x98,
+                    name_string_idx: 9,
+                    data_type: PrimitiveDataType::U32,
+                    data_length_bits: 32,
+                    flags: 0,
+                }])
+                .unwrap()
+            ))
+        );
+
+        assert_eq!(e.pdo_entries::<4>(PdoType::Tx, 0x1680).await, Ok(None));
+        assert_eq!(
+            e.pdo_entries::<0>(PdoType::Rx, 0x1680).await,
+            Err(Error::Capacity(Item::PdoEntry))
+        );
+    }
+
+    #[tokio::test]
+    async fn akd_distributed_clock() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let e = SubDeviceEeprom::new(EepromFile::new("dumps/eeprom/akd.hex"));
+
+        let expected = [
+            SiiDistributedClock {
+                cycle_time_0: 0,
+                shift_time_0: 0,
+                shift_time_1: 0,
+                sync1_cycle_factor: 0,
+                assign_activate: 0x0300,
+                sync0_cycle_factor: 1,
+                name_string_idx: 6,
+                description_string_idx: 0,
+            },
+            SiiDistributedClock {
+                cycle_time_0: 0,
+                shift_time_0: 0,
+                shift_time_1: 0,
+                sync1_cycle_factor: 0,
+                assign_activate: 0x0000,
+                sync0_cycle_factor: 1,
+                name_string_idx: 7,
+                description_string_idx: 0,
+            },
+        ];
+
+        assert_eq!(
+            e.distributed_clock::<4>().await,
+            Ok(heapless::Vec::<_, 4>::from_slice(&expected).unwrap())
+        );
+
+        assert_eq!(
+            e.find_string::<16>(expected[0].name_string_idx).await,
+            Ok(Some("DcSync".try_into().unwrap()))
+        );
+
+        assert_eq!(
+            e.distributed_clock::<1>().await,
+            Err(Error::Eeprom(EepromError::SectionOverrun))
+        );
+    }
+
+    #[tokio::test]
+    async fn akd_dc_defaults() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let e = SubDeviceEeprom::new(EepromFile::new("dumps/eeprom/akd.hex"));
+
+        let cycle_time = Duration::from_millis(1);
+
+        assert_eq!(
+            e.dc_defaults(cycle_time).await,
+            Ok(Some(DcDefaults {
+                dc_sync: DcSync::Sync0,
+                sync0_period: cycle_time,
+                sync0_shift: Duration::ZERO,
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn no_dc_defaults() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let e = SubDeviceEeprom::new(EepromFile::new("dumps/eeprom/el2889.hex"));
+
+        assert_eq!(e.dc_defaults(Duration::from_millis(1)).await, Ok(None));
+    }
+
+    const EL2828_RX_PDOS: [EsiPdo<'static>; 8] = {
+        const fn pdo(n: u16) -> EsiPdo<'static> {
+            EsiPdo {
+                index: 0x1600 + n,
+                sync_manager: Some(0),
+                entries: &[],
+            }
+        }
+
+        [
+            pdo(0),
+            pdo(1),
+            pdo(2),
+            pdo(3),
+            pdo(4),
+            pdo(5),
+            pdo(6),
+            pdo(7),
+        ]
+    };
+
+    fn el2828_esi<'esi>(rx_pdos: &'esi [EsiPdo<'esi>]) -> EsiDevice<'esi> {
+        EsiDevice {
+            vendor_id: 0x0000_0002,
+            product_code: 0x0b0c_3052,
+            revision: 0x0011_0000,
+            sync_managers: &[EsiSyncManager {
+                start_addr: 0x0f00,
+                length: None,
+                control: 0x44,
+                enable: true,
+            }],
+            rx_pdos,
+            tx_pdos: &[],
+        }
+    }
+
+    /// Fill in the single entry of each EL2828 output PDO.
+    fn el2828_entries() -> [[EsiPdoEntry; 1]; 8] {
+        core::array::from_fn(|n| {
+            [EsiPdoEntry {
+                index: 0x7000 + 0x10 * n as u16,
+                sub_index: 1,
+                bit_len: 1,
+            }]
+        })
+    }
+
+    #[tokio::test]
+    async fn esi_matches() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let e = SubDeviceEeprom::new(EepromFile::new("dumps/eeprom/el2828.hex"));
+
+        let entries = el2828_entries();
+
+        let rx_pdos: [EsiPdo; 8] = core::array::from_fn(|n| EsiPdo {
+            entries: &entries[n],
+            ..EL2828_RX_PDOS[n]
+        });
+
+        assert_eq!(
+            e.check_esi::<16>(&el2828_esi(&rx_pdos)).await,
+            Ok(heapless::Vec::new())
+        );
+    }
+
+    #[tokio::test]
+    async fn esi_mismatches() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let e = SubDeviceEeprom::new(EepromFile::new("dumps/eeprom/el2828.hex"));
+
+        let mut entries = el2828_entries();
+
+        // Wrong object mapped
+        entries[2][0].index = 0x7021;
+
+        let mut rx_pdos: [EsiPdo; 8] = core::array::from_fn(|n| EsiPdo {
+            entries: &entries[n],
+            ..EL2828_RX_PDOS[n]
+        });
+
+        // Assigned to another SM
+        rx_pdos[1].sync_manager = Some(1);
+        // Not in the SII
+        rx_pdos[7].index = 0x1610;
+
+        let esi = EsiDevice {
+            revision: 0x0012_0000,
+            sync_managers: &[EsiSyncManager {
+                start_addr: 0x0f00,
+                length: Some(2),
+                control: 0x44,
+                enable: true,
+            }],
+            tx_pdos: &[
+                // Optional PDOs don't need to be present
+                EsiPdo {
+                    index: 0x1a00,
+                    sync_manager: None,
+                    entries: &[],
+                },
+            ],
+            ..el2828_esi(&rx_pdos)
+        };
+
+        pretty_assertions::assert_eq!(
+            e.check_esi::<16>(&esi).await,
+            Ok(heapless::Vec::from_slice(&[
+                SiiMismatch::Revision {
+                    esi: 0x0012_0000,
+                    sii: 0x0011_0000
+                },
+                SiiMismatch::SyncManager {
+                    index: 0,
+                    esi: EsiSyncManager {
+                        start_addr: 0x0f00,
+                        length: Some(2),
+                        control: 0x44,
+                        enable: true,
+                    },
+                    sii: EsiSyncManager {
+                        start_addr: 0x0f00,
+                        length: Some(1),
+                        control: 0x44,
+                        enable: true,
+                    },
+                },
+                SiiMismatch::PdoSyncManager {
+                    pdo_type: PdoType::Rx,
+                    index: 0x1601,
+                    esi: 1,
+                    sii: 0
+                },
+                SiiMismatch::PdoEntries {
+                    pdo_type: PdoType::Rx,
+                    index: 0x1602
+                },
+                SiiMismatch::UnexpectedPdo {
+                    pdo_type: PdoType::Rx,
+                    index: 0x1607
+                },
+                SiiMismatch::MissingPdo {
+                    pdo_type: PdoType::Rx,
+                    index: 0x1610
+                },
+            ])
+            .unwrap())
+        );
+
+        // Only the first differences are returned if there are too many
+        assert_eq!(e.check_esi::<2>(&esi).await.map(|m| m.len()), Ok(2));
+    }
+
+    #[tokio::test]
+    async fn no_distributed_clock() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let e = SubDeviceEeprom::new(EepromFile::new("dumps/eeprom/el2889.hex"));
+
+        assert_eq!(e.distributed_clock::<4>().await, Ok(heapless::Vec::new()));
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        eeprom::device_reader::DeviceEeprom,
+        error::{EepromError, Error, ErrorContext, Operation},
+        sim::fixture::*,
+        PduStorage,
+    };
+
+    #[test]
+    fn eeprom_dump_restore() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            let subdevice = group.subdevice(&maindevice, 0).expect("SubDevice");
+
+            let mut original = Vec::new();
+
+            assert_eq!(subdevice.eeprom_dump(&mut original).await.ok(), Some(2048));
+            assert_eq!(original.len(), 2048);
+
+            // Change the configured station alias and the first byte of the last word
+            let mut modified = original.clone();
+            modified[0x0004 * 2..][..2].copy_from_slice(&0xabcdu16.to_le_bytes());
+            modified[2046] = 0x12;
+
+            assert_eq!(subdevice.eeprom_restore(&modified[..2047]).await, Ok(()));
+
+            let mut restored = Vec::new();
+
+            subdevice.eeprom_dump(&mut restored).await.expect("dump");
+
+            assert_eq!(restored, modified);
+
+            assert_eq!(
+                subdevice.eeprom_restore(&[0u8; 2050]).await,
+                Err(Error::Eeprom(EepromError::SectionOverrun))
+            );
+
+            assert_eq!(subdevice.eeprom_restore(&original).await, Ok(()));
+
+            let mut restored = Vec::new();
+
+            subdevice.eeprom_dump(&mut restored).await.expect("dump");
+
+            assert_eq!(restored, original);
+        });
+    }
+
+    #[test]
+    fn eeprom_checksum() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            let subdevice = group.subdevice(&maindevice, 1).expect("SubDevice");
+
+            assert_eq!(subdevice.eeprom_verify_checksum().await, Ok(()));
+            assert_eq!(subdevice.eeprom_repair_checksum().await, Ok(false));
+
+            let mut config_area = [0u8; 16];
+
+            subdevice
+                .eeprom_read_into(0, &mut config_area)
+                .await
+                .expect("read");
+
+            let checksum = config_area[14];
+
+            // Simulate an interrupted write by changing a configuration area WORD but not the
+            // checksum
+            assert_eq!(
+                subdevice.eeprom_restore(&[0u8, 0, 0, 0, 0x34, 0x12]).await,
+                Ok(())
+            );
+
+            assert!(matches!(
+                subdevice.eeprom_verify_checksum().await,
+                Err(Error::Eeprom(EepromError::Checksum { actual, .. })) if actual == checksum
+            ));
+
+            assert_eq!(subdevice.eeprom_repair_checksum().await, Ok(true));
+            assert_eq!(subdevice.eeprom_verify_checksum().await, Ok(()));
+        });
+    }
+
+    #[test]
+    fn eeprom_address_range() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            let subdevice = group.subdevice(&maindevice, 1).expect("SubDevice");
+
+            let mut start = [0u8; 16];
+            let mut last = [0u8; 2];
+
+            assert_eq!(subdevice.eeprom_read_into(0, &mut start).await, Ok(()));
+            assert_eq!(subdevice.eeprom_read_into(0x03ff, &mut last).await, Ok(()));
+
+            // The 16 kbit EEPROM uses 1 address byte, so this read would wrap around to the start
+            let mut wrapped = [0u8; 16];
+
+            assert_eq!(
+                subdevice.eeprom_read_into(0x0400, &mut wrapped).await,
+                Err(
+                    Error::Eeprom(EepromError::AddressOutOfRange).with_context(ErrorContext::new(
+                        subdevice.configured_address(),
+                        Operation::EepromRead { word: 0x0400 }
+                    ))
+                )
+            );
+
+            // Writes must not wrap around and overwrite the configuration area
+            assert_eq!(
+                DeviceEeprom::new(&maindevice, subdevice.configured_address())
+                    .write_word(0x0400, 0xabcd)
+                    .await,
+                Err(
+                    Error::Eeprom(EepromError::AddressOutOfRange).with_context(ErrorContext::new(
+                        subdevice.configured_address(),
+                        Operation::EepromWrite { word: 0x0400 }
+                    ))
+                )
+            );
+
+            let mut after = [0u8; 16];
+
+            assert_eq!(subdevice.eeprom_read_into(0, &mut after).await, Ok(()));
+            assert_eq!(after, start);
+        });
+    }
 }
@@ -1,6 +1,9 @@
 pub(crate) mod configuration;
 mod dc;
-mod eeprom;
+mod diagnostics;
+pub(crate) mod eeprom;
+mod object_dictionary;
+mod overrides;
 pub mod pdi;
 pub mod ports;
 mod types;
@@ -8,40 +11,58 @@ mod types;
 use crate::{
     al_control::AlControl,
     al_status_code::AlStatusCode,
+    atomic::{AtomicBool, AtomicU8, Ordering},
     coe::{
-        self, abort_code::CoeAbortCode, services::CoeServiceRequest, CoeCommand, CoeService,
-        SdoExpedited, SubIndex,
+        self,
+        services::{CoeServiceRequest, SdoInfo},
+        SdoExpedited, SdoInfoOpCode, SubIndex,
     },
     command::Command,
     dl_status::DlStatus,
-    eeprom::{device_reader::DeviceEeprom, types::SiiOwner},
-    error::{Error, MailboxError, PduError},
+    eeprom::{
+        cache::{CachedEeprom, EepromCache},
+        device_reader::DeviceEeprom,
+        types::SiiOwner,
+        SII_CHECKSUM_WORD,
+    },
+    error::{
+        EepromError, Error, ErrorContext, Item, MailboxError, Operation, PduError,
+        StateTransitionAttempt, StateTransitionHistory,
+    },
+    fmmu::Fmmu,
     fmt,
-    mailbox::{MailboxHeader, MailboxType},
+    mailbox::MailboxHeader,
     maindevice::MainDevice,
+    parse::{self, SdoUpload},
     pdu_loop::ReceivedPdu,
     register::{DcSupport, RegisterAddress, SupportFlags},
     subdevice::{ports::Ports, types::SubDeviceConfig},
     subdevice_state::SubDeviceState,
-    timer_factory::IntoTimeout,
-    WrappedRead, WrappedWrite,
+    timer_factory::{timer, IntoTimeout},
+    AliasAddress, ConfiguredAddress, WrappedRead, WrappedWrite,
 };
+use atomic_refcell::AtomicRefCell;
 use core::{
     any::type_name,
     fmt::{Debug, Write},
     ops::{Deref, DerefMut},
-    sync::atomic::{AtomicU8, Ordering},
+    time::Duration,
 };
 use ethercrab_wire::{
-    EtherCrabWireRead, EtherCrabWireReadSized, EtherCrabWireReadWrite, EtherCrabWireSized,
-    EtherCrabWireWrite,
+    EtherCrabWireRead, EtherCrabWireReadSized, EtherCrabWireReadWrite, EtherCrabWireWrite,
+    EtherCrabWireWriteSized,
 };
 
+pub use self::eeprom::Eeprom;
 pub use self::pdi::SubDevicePdi;
+pub use self::ports::Topology;
 pub use self::types::IoRanges;
 pub use self::types::SubDeviceIdentity;
 use self::{eeprom::SubDeviceEeprom, types::Mailbox};
-pub use dc::DcSync;
+pub use dc::{DcDefaults, DcSync};
+pub use diagnostics::{SubDeviceDiagnostics, SyncManagerStatus, WatchdogStatus};
+pub use object_dictionary::{ObjectAccess, ObjectCode, ObjectDictionary, ObjectEntry};
+pub use overrides::{RegisterWrite, SubDeviceOverride};
 
 /// SubDevice device metadata. See [`SubDeviceRef`] for richer behaviour.
 #[doc(alias = "Slave")]
@@ -51,9 +72,9 @@ pub use dc::DcSync;
 #[cfg_attr(test, derive(Default))]
 pub struct SubDevice {
     /// Configured station address.
-    pub(crate) configured_address: u16,
+    pub(crate) configured_address: ConfiguredAddress,
 
-    pub(crate) alias_address: u16,
+    pub(crate) alias_address: AliasAddress,
 
     pub(crate) config: SubDeviceConfig,
 
@@ -86,8 +107,22 @@ pub struct SubDevice {
     /// The 1-7 cyclic counter used when working with mailbox requests.
     pub(crate) mailbox_counter: AtomicU8,
 
+    /// Set while a mailbox transaction is in progress so that requests from multiple tasks to the
+    /// same SubDevice don't interleave.
+    pub(crate) mailbox_busy: AtomicBool,
+
+    /// Set from when a mailbox request is written until its response is read.
+    ///
+    /// If a request is cancelled or fails in between, the SubDevice may still put a response in
+    /// its OUT mailbox. This flag makes the next request wait for and discard that response so
+    /// it isn't mistaken for its own.
+    pub(crate) mailbox_pending: AtomicBool,
+
     /// DC config.
     pub(crate) dc_sync: DcSync,
+
+    /// EEPROM reads made during initialisation, cleared once the SubDevice is configured.
+    pub(crate) eeprom_cache: AtomicRefCell<EepromCache>,
 }
 
 // Only required for tests, also doesn't make much sense - consumers of EtherCrab should be
@@ -108,7 +143,7 @@ impl PartialEq for SubDevice {
             && self.parent_index == other.parent_index
             && self.propagation_delay == other.propagation_delay
             && self.dc_sync == other.dc_sync
-        // NOTE: No mailbox_counter
+        // NOTE: No mailbox_counter, mailbox_busy, mailbox_pending or eeprom_cache
     }
 }
 
@@ -123,7 +158,7 @@ impl Clone for SubDevice {
             config: self.config.clone(),
             identity: self.identity,
             name: self.name.clone(),
-            flags: self.flags.clone(),
+            flags: self.flags,
             ports: self.ports,
             dc_receive_time: self.dc_receive_time,
             index: self.index,
@@ -131,6 +166,9 @@ impl Clone for SubDevice {
             propagation_delay: self.propagation_delay,
             dc_sync: self.dc_sync,
             mailbox_counter: AtomicU8::new(self.mailbox_counter.load(Ordering::Acquire)),
+            mailbox_busy: AtomicBool::new(false),
+            mailbox_pending: AtomicBool::new(false),
+            eeprom_cache: AtomicRefCell::new(EepromCache::default()),
         }
     }
 }
@@ -140,10 +178,19 @@ impl SubDevice {
     ///
     /// This method reads the SubDevices's name and other identifying information, but does not
     /// configure it.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "subdevice_init",
+            level = "debug",
+            skip_all,
+            fields(configured_address, index)
+        )
+    )]
     pub(crate) async fn new<'sto>(
         maindevice: &'sto MainDevice<'sto>,
         index: u16,
-        configured_address: u16,
+        configured_address: ConfiguredAddress,
     ) -> Result<Self, Error> {
         let subdevice_ref = SubDeviceRef::new(maindevice, configured_address, ());
 
@@ -155,13 +202,31 @@ impl SubDevice {
 
         subdevice_ref.wait_for_state(SubDeviceState::Init).await?;
 
+        Self::read_details(maindevice, index, configured_address).await
+    }
+
+    /// Read a SubDevice's name, identity, alias address and port status, regardless of its current
+    /// state.
+    pub(crate) async fn read_details<'sto>(
+        maindevice: &'sto MainDevice<'sto>,
+        index: u16,
+        configured_address: ConfiguredAddress,
+    ) -> Result<Self, Error> {
+        let subdevice_ref = SubDeviceRef::new(maindevice, configured_address, ());
+
         // Make sure master has access to SubDevice EEPROM
         subdevice_ref.set_eeprom_mode(SiiOwner::Master).await?;
 
-        let identity = subdevice_ref.eeprom().identity().await?;
+        // Reused by the configuration phases later on in init
+        let eeprom_cache = AtomicRefCell::new(EepromCache::default());
+
+        let identity = subdevice_ref
+            .cached_sii_reader(&eeprom_cache)
+            .identity()
+            .await?;
 
         let name = subdevice_ref
-            .eeprom()
+            .cached_sii_reader(&eeprom_cache)
             .device_name()
             .await?
             .unwrap_or_else(|| {
@@ -214,7 +279,7 @@ impl SubDevice {
 
         Ok(Self {
             configured_address,
-            alias_address,
+            alias_address: AliasAddress::new(alias_address),
             config: SubDeviceConfig::default(),
             index,
             parent_index: None,
@@ -227,6 +292,9 @@ impl SubDevice {
             dc_sync: DcSync::Disabled,
             // 0 is a reserved value, so we initialise the cycle at 1. The cycle repeats 1 - 7.
             mailbox_counter: AtomicU8::new(1),
+            mailbox_busy: AtomicBool::new(false),
+            mailbox_pending: AtomicBool::new(false),
+            eeprom_cache,
         })
     }
 
@@ -250,7 +318,7 @@ impl SubDevice {
     ) -> Result<Option<heapless::String<64>>, Error> {
         let subdevice_ref = SubDeviceRef::new(maindevice, self.configured_address, ());
 
-        subdevice_ref.eeprom().device_description().await
+        subdevice_ref.sii_reader().device_description().await
     }
 
     /// Get additional identifying details for the SubDevice.
@@ -259,15 +327,36 @@ impl SubDevice {
     }
 
     /// Get the configured station address of the SubDevice.
-    pub fn configured_address(&self) -> u16 {
+    pub fn configured_address(&self) -> ConfiguredAddress {
         self.configured_address
     }
 
     /// Get alias address for the SubDevice.
-    pub fn alias_address(&self) -> u16 {
+    pub fn alias_address(&self) -> AliasAddress {
         self.alias_address
     }
 
+    /// Get the position of the SubDevice in the network, starting at `0` for the SubDevice
+    /// closest to the MainDevice.
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// Get the index of the SubDevice this SubDevice is connected to, or `None` if it is the first
+    /// SubDevice in the network.
+    ///
+    /// Note that before [`MainDevice::init`](crate::MainDevice::init) or
+    /// [`MainDevice::scan`](crate::MainDevice::scan) is called, this method will always return
+    /// `None`.
+    pub fn parent_index(&self) -> Option<u16> {
+        self.parent_index
+    }
+
+    /// Get the topology of this SubDevice, determined by how many of its ports are open.
+    pub fn topology(&self) -> Topology {
+        self.ports.topology()
+    }
+
     /// Get the network propagation delay of this device in nanoseconds.
     ///
     /// Note that before [`MainDevice::init`](crate::MainDevice::init) is called, this method will
@@ -285,6 +374,23 @@ impl SubDevice {
         &self.config.io
     }
 
+    /// Whether the SubDevice is left in PRE-OP with no process data by
+    /// [`SubDeviceOverride::mailbox_only`].
+    pub fn is_mailbox_only(&self) -> bool {
+        self.config.mailbox_only
+    }
+
+    /// The state this SubDevice should be requested into when its group is requested into
+    /// `state`.
+    pub(crate) fn group_state(&self, state: SubDeviceState) -> SubDeviceState {
+        match state {
+            SubDeviceState::SafeOp | SubDeviceState::Op if self.config.mailbox_only => {
+                SubDeviceState::PreOp
+            }
+            state => state,
+        }
+    }
+
     /// Check if the current SubDevice is a child of `parent`.
     ///
     /// A SubDevice is a child of a parent if it is connected to an intermediate port of the
@@ -319,7 +425,7 @@ impl SubDevice {
 #[doc(alias = "SlaveRef")]
 pub struct SubDeviceRef<'a, S> {
     pub(crate) maindevice: &'a MainDevice<'a>,
-    pub(crate) configured_address: u16,
+    pub(crate) configured_address: ConfiguredAddress,
     state: S,
 }
 
@@ -344,6 +450,60 @@ where
     pub fn set_dc_sync(&mut self, dc_sync: DcSync) {
         self.state.dc_sync = dc_sync;
     }
+
+    /// Put the SubDevice into the BOOT state, e.g. to update its firmware using FoE.
+    ///
+    /// The SubDevice is moved to INIT first, then its mailbox SyncManagers are reconfigured with
+    /// the bootstrap mailbox offsets and sizes from the SII, which often differ from the standard
+    /// mailbox layout. Mailbox requests to this SubDevice use the bootstrap layout from then on.
+    ///
+    /// BOOT can only be left by going back to INIT, e.g. with
+    /// [`leave_bootstrap`](SubDeviceRef::leave_bootstrap). Most SubDevices restart once a firmware
+    /// update is complete, after which the network should be initialised again.
+    ///
+    /// Returns [`MailboxError::NoMailbox`] if the SubDevice has no bootstrap mailbox, or an
+    /// [`Error::StateTransition`] if it does not support the BOOT state.
+    pub async fn into_bootstrap(&mut self) -> Result<(), Error> {
+        self.request_subdevice_state(SubDeviceState::Init).await?;
+
+        self.set_eeprom_mode(SiiOwner::Master).await?;
+
+        self.configure_bootstrap_mailbox().await?;
+
+        fmt::debug!(
+            "SubDevice {:#06x} bootstrap mailbox configured. Transitioning to BOOT",
+            self.configured_address
+        );
+
+        self.request_subdevice_state(SubDeviceState::Bootstrap)
+            .await
+    }
+
+    /// Take the SubDevice out of the BOOT state entered with
+    /// [`into_bootstrap`](SubDeviceRef::into_bootstrap), leaving it in PRE-OP.
+    ///
+    /// The SubDevice is moved to INIT and its standard mailbox SyncManagers are configured again,
+    /// so mailbox requests use the standard layout from then on. Process data SyncManagers and
+    /// FMMUs are not reconfigured, so the SubDevice's group may not be able to enter SAFE-OP if
+    /// its PDO mapping changed, e.g. after a firmware update.
+    ///
+    /// Returns an [`Error::StateTransition`] if the SubDevice refuses to leave BOOT or to enter
+    /// PRE-OP with the standard mailbox configuration.
+    pub async fn leave_bootstrap(&mut self) -> Result<(), Error> {
+        self.request_subdevice_state(SubDeviceState::Init).await?;
+
+        self.configure_mailboxes().await?;
+
+        // Free EEPROM data cached while reading the mailbox configuration
+        self.state.eeprom_cache.get_mut().clear();
+
+        fmt::debug!(
+            "SubDevice {:#06x} standard mailbox configured. Transitioning to PRE-OP",
+            self.configured_address
+        );
+
+        self.request_subdevice_state(SubDeviceState::PreOp).await
+    }
 }
 
 impl<'a, S> SubDeviceRef<'a, S>
@@ -372,10 +532,27 @@ where
     }
 
     /// Get alias address for the SubDevice.
-    pub fn alias_address(&self) -> u16 {
+    pub fn alias_address(&self) -> AliasAddress {
         self.state.alias_address
     }
 
+    /// Get the position of the SubDevice in the network, starting at `0` for the SubDevice
+    /// closest to the MainDevice.
+    pub fn index(&self) -> u16 {
+        self.state.index
+    }
+
+    /// Get the index of the SubDevice this SubDevice is connected to, or `None` if it is the first
+    /// SubDevice in the network.
+    pub fn parent_index(&self) -> Option<u16> {
+        self.state.parent_index
+    }
+
+    /// Get the topology of this SubDevice, determined by how many of its ports are open.
+    pub fn topology(&self) -> Topology {
+        self.state.ports.topology()
+    }
+
     /// Get the network propagation delay of this device in nanoseconds.
     ///
     /// Note that before [`MainDevice::init`](crate::MainDevice::init) is called, this method will
@@ -389,6 +566,12 @@ where
         self.state.flags.dc_support()
     }
 
+    /// Whether the SubDevice is left in PRE-OP with no process data by
+    /// [`SubDeviceOverride::mailbox_only`].
+    pub fn is_mailbox_only(&self) -> bool {
+        self.state.config.mailbox_only
+    }
+
     pub(crate) fn dc_sync(&self) -> DcSync {
         self.state.dc_sync
     }
@@ -411,8 +594,42 @@ where
         ))
     }
 
+    /// Wait for exclusive access to the SubDevice's mailbox.
+    ///
+    /// The mailbox is released when the returned guard is dropped. If `timeout` is `None`,
+    /// [`Timeouts::mailbox_response`](crate::Timeouts::mailbox_response) is used.
+    async fn lock_mailbox(&self, timeout: Option<Duration>) -> Result<MailboxGuard<'_>, Error> {
+        async {
+            loop {
+                if self
+                    .state
+                    .mailbox_busy
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    break Ok(MailboxGuard(&self.state.mailbox_busy));
+                }
+
+                self.maindevice.timeouts.loop_tick().await;
+
+                // The loop delay may be zero, so make sure the task holding the mailbox can run
+                futures_lite::future::yield_now().await;
+            }
+        }
+        .timeout(timeout.unwrap_or(self.maindevice.timeouts.mailbox_response))
+        .await
+        .inspect_err(|_| {
+            fmt::error!(
+                "Timed out waiting for mailbox of SubDevice {:#06x} to be free",
+                self.configured_address
+            )
+        })
+    }
+
     /// Get CoE read/write mailboxes.
-    async fn coe_mailboxes(&self) -> Result<(Mailbox, Mailbox), Error> {
+    ///
+    /// If `timeout` is `None`, [`Timeouts::mailbox_echo`](crate::Timeouts::mailbox_echo) is used.
+    async fn coe_mailboxes(&self, timeout: Option<Duration>) -> Result<(Mailbox, Mailbox), Error> {
         let write_mailbox = self
             .state
             .config
@@ -439,6 +656,25 @@ where
         let mailbox_write_sm_status =
             RegisterAddress::sync_manager_status(write_mailbox.sync_manager);
 
+        // A previous request was cancelled or failed after it was written. Wait for its response
+        // so it's cleared below instead of being read as the response to the next request.
+        if self.state.mailbox_pending.load(Ordering::Acquire) {
+            fmt::debug!(
+                "SubDevice {:#06x} may still respond to a previous mailbox request, waiting for it",
+                self.configured_address()
+            );
+
+            if self
+                .wait_for_response(&read_mailbox, timeout)
+                .await
+                .is_err()
+            {
+                fmt::debug!("--> No late response");
+            }
+
+            self.state.mailbox_pending.store(false, Ordering::Release);
+        }
+
         // Ensure SubDevice OUT (master IN) mailbox is empty. We'll retry this multiple times in
         // case the SubDevice is still busy or bugged or something.
         for i in 0..10 {
@@ -488,7 +724,7 @@ where
                 self.maindevice.timeouts.loop_tick().await;
             }
         }
-        .timeout(self.maindevice.timeouts.mailbox_echo)
+        .timeout(timeout.unwrap_or(self.maindevice.timeouts.mailbox_echo))
         .await
         .map_err(|e| {
             fmt::error!(
@@ -503,11 +739,16 @@ where
         Ok((read_mailbox, write_mailbox))
     }
 
-    /// Wait for a mailbox response
-    async fn coe_response(&self, read_mailbox: &Mailbox) -> Result<ReceivedPdu, Error> {
+    /// Wait for the SubDevice OUT mailbox to hold a response.
+    ///
+    /// If `timeout` is `None`, [`Timeouts::mailbox_echo`](crate::Timeouts::mailbox_echo) is used.
+    async fn wait_for_response(
+        &self,
+        read_mailbox: &Mailbox,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
         let mailbox_read_sm = RegisterAddress::sync_manager_status(read_mailbox.sync_manager);
 
-        // Wait for SubDevice OUT mailbox to be ready
         async {
             loop {
                 let sm_status = self
@@ -522,17 +763,30 @@ where
                 self.maindevice.timeouts.loop_tick().await;
             }
         }
-        .timeout(self.maindevice.timeouts.mailbox_echo)
+        .timeout(timeout.unwrap_or(self.maindevice.timeouts.mailbox_echo))
         .await
-        .map_err(|e| {
-            fmt::error!(
-                "Response mailbox IN error for SubDevice {:#06x}: {}",
-                self.configured_address,
-                e
-            );
+    }
 
-            e
-        })?;
+    /// Wait for a mailbox response.
+    ///
+    /// If `timeout` is `None`, [`Timeouts::mailbox_echo`](crate::Timeouts::mailbox_echo) is used.
+    async fn coe_response(
+        &self,
+        read_mailbox: &Mailbox,
+        timeout: Option<Duration>,
+    ) -> Result<ReceivedPdu, Error> {
+        // Wait for SubDevice OUT mailbox to be ready
+        self.wait_for_response(read_mailbox, timeout)
+            .await
+            .map_err(|e| {
+                fmt::error!(
+                    "Response mailbox IN error for SubDevice {:#06x}: {}",
+                    self.configured_address,
+                    e
+                );
+
+                e
+            })?;
 
         // Read acknowledgement from SubDevice OUT mailbox
         let response = self
@@ -545,136 +799,151 @@ where
         Ok(response)
     }
 
-    /// Send a mailbox request, wait for response mailbox to be ready, read response from mailbox
-    /// and return as a slice.
-    async fn send_coe_service<R>(&'a self, request: R) -> Result<(R, ReceivedPdu), Error>
-    where
-        R: CoeServiceRequest + Debug,
-    {
-        let (read_mailbox, write_mailbox) = self.coe_mailboxes().await?;
+    /// Write a raw request into the SubDevice IN mailbox and read the first response from the
+    /// SubDevice OUT mailbox, without checking its contents.
+    async fn send_mailbox_request(
+        &self,
+        request: &[u8],
+        counter: u8,
+        timeout: Option<Duration>,
+    ) -> Result<(Mailbox, ReceivedPdu), Error> {
+        let (read_mailbox, write_mailbox) = self.coe_mailboxes(timeout).await?;
 
-        let counter = request.counter();
+        // Set before the request is sent as it may reach the SubDevice even if this future is
+        // dropped before the write completes.
+        self.state.mailbox_pending.store(true, Ordering::Release);
 
         // Send data to SubDevice IN mailbox
         self.write(write_mailbox.address)
             .with_len(write_mailbox.len)
-            .send(self.maindevice, &request.pack().as_ref())
+            .send(self.maindevice, request)
             .await?;
 
-        let mut response = self.coe_response(&read_mailbox).await?;
-
-        /// A super generalised version of the various header shapes for responses, extracting only
-        /// what we need in this method.
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, ethercrab_wire::EtherCrabWireRead)]
-        #[wire(bytes = 12)]
-        struct HeadersRaw {
-            #[wire(bytes = 8)]
-            header: MailboxHeader,
-
-            #[wire(pre_skip = 5, bits = 3)]
-            command: CoeCommand,
-
-            // 9 bytes up to here
-
-            // SAFETY: These fields will be garbage (but not invalid) if the response is NOT an
-            // abort transfer request. Use with caution!
-            #[wire(bytes = 2)]
-            address: u16,
-            #[wire(bytes = 1)]
-            sub_index: u8,
-        }
+        let response = self.coe_response(&read_mailbox, timeout).await?;
 
-        let headers = HeadersRaw::unpack_from_slice(&response)?;
+        self.state.mailbox_pending.store(false, Ordering::Release);
 
-        assert_ne!(headers.header.service, CoeService::Emergency);
+        let header = MailboxHeader::unpack_from_slice(&response)?;
 
-        if headers.header.counter != counter {
+        if header.counter != counter {
             fmt::warn!(
                 "Invalid count received: {} (expected {})",
-                headers.header.counter,
+                header.counter,
                 counter
             );
         }
 
-        if headers.header.service == CoeService::Emergency {
-            #[derive(Debug, Copy, Clone, ethercrab_wire::EtherCrabWireRead)]
-            #[wire(bytes = 8)]
-            struct EmergencyData {
-                #[wire(bytes = 2)]
-                error_code: u16,
-                #[wire(bytes = 1)]
-                error_register: u8,
-                #[wire(bytes = 5)]
-                extra_data: [u8; 5],
+        Ok((read_mailbox, response))
+    }
+
+    /// Send an SDO Information request and pass the data of each fragment of the response to
+    /// `fragment`, in order.
+    ///
+    /// `index` and `sub_index` are used in errors.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(configured_address = %self.configured_address)
+        )
+    )]
+    async fn send_sdo_info(
+        &self,
+        request: SdoInfo,
+        index: u16,
+        sub_index: u8,
+        timeout: Option<Duration>,
+        mut fragment: impl FnMut(&[u8]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let response_op_code = match request.info_header.op_code {
+            SdoInfoOpCode::GetOdListRequest => SdoInfoOpCode::GetOdListResponse,
+            SdoInfoOpCode::GetObjectDescriptionRequest => {
+                SdoInfoOpCode::GetObjectDescriptionResponse
             }
+            SdoInfoOpCode::GetEntryDescriptionRequest => SdoInfoOpCode::GetEntryDescriptionResponse,
+            _ => return Err(Error::Internal),
+        };
 
-            response.trim_front(HeadersRaw::PACKED_LEN);
+        let (read_mailbox, mut response) = self
+            .send_mailbox_request(request.pack().as_ref(), request.header.counter, timeout)
+            .await?;
 
-            let decoded = EmergencyData::unpack_from_slice(&response)?;
+        loop {
+            let (header, data) =
+                parse::sdo_info_response(&response, response_op_code, index, sub_index)
+                    .inspect_err(|e| self.maindevice.pdu_loop.record_error(e))?;
 
-            #[cfg(not(feature = "defmt"))]
-            fmt::error!(
-                "Mailbox emergency code {:#06x}, register {:#04x}, data {:#04x?}",
-                decoded.error_code,
-                decoded.error_register,
-                decoded.extra_data
-            );
-            #[cfg(feature = "defmt")]
-            fmt::error!(
-                "Mailbox emergency code {:#06x}, register {:#04x}, data {=[u8]}",
-                decoded.error_code,
-                decoded.error_register,
-                decoded.extra_data
-            );
+            fragment(data)?;
 
-            return Err(Error::Mailbox(MailboxError::Emergency {
-                error_code: decoded.error_code,
-                error_register: decoded.error_register,
-            }));
-        } else if headers.command == CoeCommand::Abort {
-            let code = CoeAbortCode::Incompatible;
+            if header.fragments_left == 0 {
+                break Ok(());
+            }
 
-            fmt::error!(
-                "Mailbox error for SubDevice {:#06x} (supports complete access: {}): {}",
-                self.configured_address,
-                self.state.config.mailbox.complete_access,
-                code
-            );
+            // The SubDevice sends the remaining fragments without being asked, so they must be
+            // discarded if this future is dropped.
+            self.state.mailbox_pending.store(true, Ordering::Release);
 
-            Err(Error::Mailbox(MailboxError::Aborted {
-                code,
-                address: headers.address,
-                sub_index: headers.sub_index,
-            }))
+            response = self.coe_response(&read_mailbox, timeout).await?;
+
+            self.state.mailbox_pending.store(false, Ordering::Release);
         }
-        // Validate that the mailbox response is to the request we just sent
-        else if headers.header.mailbox_type != MailboxType::Coe
-            || !request.validate_response(headers.address, headers.sub_index)
-        {
-            fmt::error!(
-                "Invalid SDO response. Type: {:?} (expected {:?}), index {}, subindex {}",
-                headers.header.mailbox_type,
-                MailboxType::Coe,
-                headers.address,
-                headers.sub_index,
-            );
+    }
 
-            Err(Error::Mailbox(MailboxError::SdoResponseInvalid {
-                address: headers.address,
-                sub_index: headers.sub_index,
-            }))
-        } else {
-            let headers = R::unpack_from_slice(&response)?;
+    /// Send a mailbox request, wait for response mailbox to be ready, read response from mailbox
+    /// and return as a slice.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(configured_address = %self.configured_address)
+        )
+    )]
+    async fn send_coe_service<R>(
+        &'a self,
+        request: R,
+        timeout: Option<Duration>,
+    ) -> Result<(R, ReceivedPdu), Error>
+    where
+        R: CoeServiceRequest + Debug,
+    {
+        let (_read_mailbox, response) = self
+            .send_mailbox_request(request.pack().as_ref(), request.counter(), timeout)
+            .await?;
 
-            response.trim_front(HeadersRaw::PACKED_LEN);
+        let result = parse::coe_response(&request, &response)
+            .map(|headers| (headers, response))
+            .inspect_err(|e| {
+                if let Error::Mailbox(MailboxError::Aborted { code, .. }) = e {
+                    fmt::error!(
+                        "Mailbox error for SubDevice {:#06x} (supports complete access: {}): {}",
+                        self.configured_address,
+                        self.state.config.mailbox.complete_access,
+                        code
+                    );
+                }
+            });
 
-            Ok((headers, response))
-        }
+        result.inspect_err(|e| self.maindevice.pdu_loop.record_error(e))
     }
 
     /// Write a value to the given SDO index (address) and sub-index.
     ///
     /// Note that this method currently only supports expedited SDO downloads (4 bytes maximum).
+    ///
+    /// # Cancel safety
+    ///
+    /// If the returned future is dropped after the request was sent, the SubDevice may still write
+    /// the value. Its response is discarded by the next mailbox request to this SubDevice.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(configured_address = %self.configured_address, index)
+        )
+    )]
     pub async fn sdo_write<T>(
         &self,
         index: u16,
@@ -686,6 +955,73 @@ where
     {
         let sub_index = sub_index.into();
 
+        self.sdo_write_inner(index, sub_index, value, None)
+            .await
+            .map_err(|e| {
+                self.error_context(
+                    e,
+                    Operation::SdoWrite {
+                        index,
+                        sub_index: sub_index.sub_index(),
+                    },
+                )
+            })
+    }
+
+    /// Write a value to the given SDO index (address) and sub-index, overriding the mailbox
+    /// [`Timeouts`](crate::Timeouts) for this call only.
+    ///
+    /// The whole write, including waiting for the mailbox to become free, must complete within
+    /// `timeout`, otherwise an error caused by [`Error::Timeout`] is returned. This is useful for
+    /// objects that take a SubDevice much longer or shorter to process than usual.
+    ///
+    /// See [`sdo_write`](SubDeviceRef::sdo_write) for more details.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(configured_address = %self.configured_address, index)
+        )
+    )]
+    pub async fn sdo_write_with_timeout<T>(
+        &self,
+        index: u16,
+        sub_index: impl Into<SubIndex>,
+        value: T,
+        timeout: Duration,
+    ) -> Result<(), Error>
+    where
+        T: EtherCrabWireWrite,
+    {
+        let sub_index = sub_index.into();
+
+        self.sdo_write_inner(index, sub_index, value, Some(timeout))
+            .timeout(timeout)
+            .await
+            .map_err(|e| {
+                self.error_context(
+                    e,
+                    Operation::SdoWrite {
+                        index,
+                        sub_index: sub_index.sub_index(),
+                    },
+                )
+            })
+    }
+
+    async fn sdo_write_inner<T>(
+        &self,
+        index: u16,
+        sub_index: SubIndex,
+        value: T,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error>
+    where
+        T: EtherCrabWireWrite,
+    {
+        let _mailbox = self.lock_mailbox(timeout).await?;
+
         let counter = self.mailbox_counter();
 
         if value.packed_len() > 4 {
@@ -704,7 +1040,7 @@ where
 
         fmt::trace!("CoE download");
 
-        let (_response, _data) = self.send_coe_service(request).await?;
+        let (_response, _data) = self.send_coe_service(request, timeout).await?;
 
         // TODO: Validate reply?
 
@@ -751,6 +1087,14 @@ where
     /// # Ok::<(), ethercrab::error::Error>(())
     /// # };
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(configured_address = %self.configured_address, index)
+        )
+    )]
     pub async fn sdo_write_array<T>(&self, index: u16, values: impl AsRef<[T]>) -> Result<(), Error>
     where
         T: EtherCrabWireWrite,
@@ -771,6 +1115,14 @@ where
         Ok(())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(configured_address = %self.configured_address, index)
+        )
+    )]
     pub(crate) async fn sdo_read_expedited<T>(
         &self,
         index: u16,
@@ -787,73 +1139,182 @@ where
 
         let sub_index = sub_index.into();
 
+        let _mailbox = self.lock_mailbox(None).await?;
+
         let request = coe::services::upload(self.mailbox_counter(), index, sub_index);
 
         fmt::trace!("CoE upload {:#06x} {:?}", index, sub_index);
 
-        let (headers, response) = self.send_coe_service(request).await?;
-        let data: &[u8] = &response;
-
-        // Expedited transfers where the data is 4 bytes or less long, denoted in the SDO header
-        // size value.
-        if headers.sdo_header.expedited_transfer {
-            let data_len = 4usize.saturating_sub(usize::from(headers.sdo_header.size));
+        let (headers, response) = self.send_coe_service(request, None).await?;
 
-            Ok(T::unpack_from_slice(
-                data.get(0..data_len).ok_or(Error::Internal)?,
-            )?)
-        } else {
-            Err(Error::Internal)
+        match parse::upload_response(&headers, &response)? {
+            SdoUpload::Expedited(data) => Ok(T::unpack_from_slice(data)?),
+            _ => Err(Error::Internal),
         }
     }
 
     /// Read a value from an SDO (Service Data Object) from the given index (address) and sub-index.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. If the returned future is dropped before the SubDevice responds,
+    /// the late response is discarded by the next mailbox request to this SubDevice instead of
+    /// being returned for it.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(configured_address = %self.configured_address, index)
+        )
+    )]
     pub async fn sdo_read<T>(&self, index: u16, sub_index: impl Into<SubIndex>) -> Result<T, Error>
     where
         T: EtherCrabWireReadSized,
     {
         let sub_index = sub_index.into();
 
+        self.sdo_read_inner(index, sub_index, None)
+            .await
+            .map_err(|e| {
+                self.error_context(
+                    e,
+                    Operation::SdoRead {
+                        index,
+                        sub_index: sub_index.sub_index(),
+                    },
+                )
+            })
+    }
+
+    /// Read a value from an SDO (Service Data Object), overriding the mailbox
+    /// [`Timeouts`](crate::Timeouts) for this call only.
+    ///
+    /// The whole read, including waiting for the mailbox to become free and every segment of a
+    /// segmented upload, must complete within `timeout`, otherwise an error caused by
+    /// [`Error::Timeout`] is returned.
+    ///
+    /// See [`sdo_read`](SubDeviceRef::sdo_read) for more details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{
+    /// #     error::Error, MainDevice, MainDeviceConfig, PduStorage, Timeouts, std::ethercat_now
+    /// # };
+    /// # use std::time::Duration;
+    /// # static PDU_STORAGE: PduStorage<8, 32> = PduStorage::new();
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # async {
+    /// # let mut group = maindevice
+    /// #     .init_single_group::<8, 8>(ethercat_now)
+    /// #     .await
+    /// #     .expect("Init");
+    /// let subdevice = group.subdevice(&maindevice, 0).expect("No subdevice!");
+    ///
+    /// // Give up quickly if the SubDevice doesn't respond, instead of waiting for the default
+    /// // mailbox timeouts.
+    /// let serial = subdevice
+    ///     .sdo_read_with_timeout::<u32>(0x1018, 4, Duration::from_millis(50))
+    ///     .await?;
+    /// # Ok::<(), ethercrab::error::Error>(())
+    /// # };
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(configured_address = %self.configured_address, index)
+        )
+    )]
+    pub async fn sdo_read_with_timeout<T>(
+        &self,
+        index: u16,
+        sub_index: impl Into<SubIndex>,
+        timeout: Duration,
+    ) -> Result<T, Error>
+    where
+        T: EtherCrabWireReadSized,
+    {
+        let sub_index = sub_index.into();
+
+        self.sdo_read_inner(index, sub_index, Some(timeout))
+            .timeout(timeout)
+            .await
+            .map_err(|e| {
+                self.error_context(
+                    e,
+                    Operation::SdoRead {
+                        index,
+                        sub_index: sub_index.sub_index(),
+                    },
+                )
+            })
+    }
+
+    async fn sdo_read_inner<T>(
+        &self,
+        index: u16,
+        sub_index: SubIndex,
+        timeout: Option<Duration>,
+    ) -> Result<T, Error>
+    where
+        T: EtherCrabWireReadSized,
+    {
         let mut storage = T::buffer();
         let buf = storage.as_mut();
 
-        let request = coe::services::upload(self.mailbox_counter(), index, sub_index);
+        let len = self.sdo_upload_into(index, sub_index, buf, timeout).await?;
 
-        fmt::trace!("CoE upload {:#06x} {:?}", index, sub_index);
+        let response_payload = buf.get(0..len).ok_or(Error::Internal)?;
+
+        T::unpack_from_slice(response_payload).map_err(|_| {
+            fmt::error!(
+                "SDO expedited data decode T: {} (len {}) data {:?} (len {})",
+                type_name::<T>(),
+                T::PACKED_LEN,
+                response_payload,
+                response_payload.len()
+            );
+
+            Error::Pdu(PduError::Decode)
+        })
+    }
 
-        let (headers, response) = self.send_coe_service(request).await?;
-        let data: &[u8] = &response;
+    /// Read the raw value of an SDO into `buf`, returning its length in bytes.
+    async fn sdo_upload_into(
+        &self,
+        index: u16,
+        sub_index: SubIndex,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> Result<usize, Error> {
+        let _mailbox = self.lock_mailbox(timeout).await?;
 
-        // Expedited transfers where the data is 4 bytes or less long, denoted in the SDO header
-        // size value.
-        let response_payload = if headers.sdo_header.expedited_transfer {
-            let data_len = 4usize.saturating_sub(usize::from(headers.sdo_header.size));
+        let request = coe::services::upload(self.mailbox_counter(), index, sub_index);
 
-            data.get(0..data_len).ok_or(Error::Internal)?
-        }
-        // Data is either a normal upload or a segmented upload
-        else {
-            let data_length = headers.header.length.saturating_sub(0x0a);
+        fmt::trace!("CoE upload {:#06x} {:?}", index, sub_index);
 
-            let complete_size = u32::unpack_from_slice(data)?;
-            let data = data.get(u32::PACKED_LEN..).ok_or(Error::Internal)?;
+        let (headers, response) = self.send_coe_service(request, timeout).await?;
 
+        let data = match parse::upload_response(&headers, &response)? {
+            SdoUpload::Expedited(data) => data,
             // The provided buffer isn't long enough to contain all mailbox data.
-            if complete_size > buf.len() as u32 {
+            SdoUpload::Normal { complete_size, .. } | SdoUpload::Segmented { complete_size }
+                if complete_size > buf.len() as u32 =>
+            {
                 return Err(Error::Mailbox(MailboxError::TooLong {
                     address: headers.sdo_header.index,
                     sub_index: headers.sdo_header.sub_index,
                 }));
             }
-
             // If it's a normal upload, the response payload is returned in the initial mailbox read
-            if complete_size <= u32::from(data_length) {
-                data.get(0..usize::from(data_length))
-                    .ok_or(Error::Internal)?
-            }
+            SdoUpload::Normal { data, .. } => data,
             // If it's a segmented upload, we must make subsequent requests to load all segment data
             // from the read mailbox.
-            else {
+            SdoUpload::Segmented { .. } => {
                 let mut toggle = false;
                 let mut total_len = 0usize;
 
@@ -862,55 +1323,57 @@ where
 
                     fmt::trace!("CoE upload segmented");
 
-                    let (headers, data) = self.send_coe_service(request).await?;
-
-                    // The spec defines the data length as n-3, so we'll just go with that magic
-                    // number...
-                    let mut chunk_len = usize::from(headers.header.length - 3);
+                    let (headers, response) = self.send_coe_service(request, timeout).await?;
 
-                    // Special case as per spec: Minimum response size is 7 bytes. For smaller
-                    // responses, we must remove the number of unused bytes at the end of the
-                    // response. Extremely weird.
-                    if chunk_len == 7 {
-                        chunk_len -= usize::from(headers.sdo_header.segment_data_size);
-                    }
-
-                    let data = data.get(0..chunk_len).ok_or(Error::Internal)?;
+                    let segment = parse::upload_segment_response(&headers, &response)?;
 
-                    buf.get_mut(total_len..(total_len + chunk_len))
-                        .ok_or(Error::Internal)?
-                        .copy_from_slice(data);
+                    buf.get_mut(total_len..(total_len + segment.data.len()))
+                        .ok_or(Error::Mailbox(MailboxError::TooLong {
+                            address: index,
+                            sub_index: sub_index.sub_index(),
+                        }))?
+                        .copy_from_slice(segment.data);
 
-                    total_len += chunk_len;
+                    total_len += segment.data.len();
 
-                    if headers.sdo_header.is_last_segment {
+                    if segment.is_last {
                         break;
                     }
 
                     toggle = !toggle;
                 }
 
-                buf.get(0..total_len).ok_or(Error::Internal)?
+                return Ok(total_len);
             }
         };
 
-        T::unpack_from_slice(response_payload).map_err(|_| {
-            fmt::error!(
-                "SDO expedited data decode T: {} (len {}) data {:?} (len {})",
-                type_name::<T>(),
-                T::PACKED_LEN,
-                response_payload,
-                response_payload.len()
-            );
+        buf.get_mut(0..data.len())
+            .ok_or(Error::Mailbox(MailboxError::TooLong {
+                address: index,
+                sub_index: sub_index.sub_index(),
+            }))?
+            .copy_from_slice(data);
 
-            Error::Pdu(PduError::Decode)
-        })
+        Ok(data.len())
+    }
+}
+
+/// Exclusive access to a SubDevice's mailbox, released on drop.
+struct MailboxGuard<'a>(&'a AtomicBool);
+
+impl Drop for MailboxGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
     }
 }
 
 // General impl with no bounds
 impl<'a, S> SubDeviceRef<'a, S> {
-    pub(crate) fn new(maindevice: &'a MainDevice<'a>, configured_address: u16, state: S) -> Self {
+    pub(crate) fn new(
+        maindevice: &'a MainDevice<'a>,
+        configured_address: ConfiguredAddress,
+        state: S,
+    ) -> Self {
         Self {
             maindevice,
             configured_address,
@@ -919,7 +1382,7 @@ impl<'a, S> SubDeviceRef<'a, S> {
     }
 
     /// Get the configured station address of the SubDevice.
-    pub fn configured_address(&self) -> u16 {
+    pub fn configured_address(&self) -> ConfiguredAddress {
         self.configured_address
     }
 
@@ -961,10 +1424,89 @@ impl<'a, S> SubDeviceRef<'a, S> {
         futures_lite::future::try_zip(self.state(), code).await
     }
 
-    fn eeprom(&self) -> SubDeviceEeprom<DeviceEeprom> {
+    /// Read the status of every sync manager and the process data and PDI watchdogs.
+    ///
+    /// Use [`SubDeviceDiagnostics::outputs_frozen`] to check whether the SubDevice has stopped
+    /// driving its outputs because no process data was received within the watchdog timeout.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+    /// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # async {
+    /// # let mut group = maindevice.init_single_group::<8, 32>(|| 0).await?.into_op(&maindevice).await?;
+    /// for subdevice in group.iter(&maindevice) {
+    ///     let diagnostics = subdevice.diagnostics().await?;
+    ///
+    ///     if diagnostics.outputs_frozen() {
+    ///         log::error!(
+    ///             "SubDevice {:#06x} outputs frozen, SM watchdog expired {} times",
+    ///             diagnostics.configured_address,
+    ///             diagnostics.watchdog.sm_watchdog_expirations
+    ///         );
+    ///     }
+    /// }
+    /// # Ok::<(), ethercrab::error::Error>(())
+    /// # };
+    /// ```
+    pub async fn diagnostics(&self) -> Result<SubDeviceDiagnostics, Error> {
+        let num_sync_managers = self
+            .read(RegisterAddress::SyncManagerChannels)
+            .receive::<u8>(self.maindevice)
+            .await?
+            .min(diagnostics::MAX_SYNC_MANAGERS);
+
+        let watchdog = self
+            .read(RegisterAddress::WatchdogDivider)
+            .receive_slice(self.maindevice, diagnostics::WATCHDOG_LEN)
+            .await?;
+
+        let sync_managers = if num_sync_managers > 0 {
+            Some(
+                self.read(RegisterAddress::Sm0)
+                    .receive_slice(self.maindevice, u16::from(num_sync_managers) * 8)
+                    .await?,
+            )
+        } else {
+            None
+        };
+
+        SubDeviceDiagnostics::parse(
+            self.configured_address,
+            &watchdog,
+            sync_managers.as_deref().unwrap_or_default(),
+        )
+    }
+
+    fn sii_reader(&self) -> SubDeviceEeprom<DeviceEeprom<'a>> {
         SubDeviceEeprom::new(DeviceEeprom::new(self.maindevice, self.configured_address))
     }
 
+    /// An EEPROM reader that reuses category positions and data read earlier during init, if
+    /// [`MainDeviceConfig::eeprom_cache`](crate::MainDeviceConfig::eeprom_cache) is enabled.
+    fn cached_sii_reader<'cache>(
+        &self,
+        cache: &'cache AtomicRefCell<EepromCache>,
+    ) -> SubDeviceEeprom<CachedEeprom<'cache, DeviceEeprom<'a>>> {
+        SubDeviceEeprom::new(CachedEeprom::new(
+            DeviceEeprom::new(self.maindevice, self.configured_address),
+            self.maindevice.config.eeprom_cache.then_some(cache),
+        ))
+    }
+
+    /// Get typed access to the SII categories stored in the SubDevice's EEPROM, e.g. the
+    /// "General" category or the PDOs.
+    ///
+    /// The MainDevice must have access to the EEPROM, which is the case unless the SubDevice has
+    /// been configured to assign it to the PDI. See [`Eeprom`](crate::sii::Eeprom) for
+    /// examples.
+    pub fn eeprom(&self) -> Eeprom<'a> {
+        Eeprom::new(self.sii_reader())
+    }
+
     /// Read a register.
     ///
     /// Note that while this method is marked safe, raw alterations to SubDevice config or behaviour can
@@ -973,7 +1515,12 @@ impl<'a, S> SubDeviceRef<'a, S> {
     where
         T: EtherCrabWireReadSized,
     {
-        self.read(register.into()).receive(self.maindevice).await
+        let register = register.into();
+
+        self.read(register)
+            .receive(self.maindevice)
+            .await
+            .map_err(|e| self.error_context(e, Operation::RegisterRead { register }))
     }
 
     /// Write a register.
@@ -984,11 +1531,311 @@ impl<'a, S> SubDeviceRef<'a, S> {
     where
         T: EtherCrabWireReadWrite,
     {
-        self.write(register.into())
+        let register = register.into();
+
+        self.write(register)
             .send_receive(self.maindevice, value)
             .await
+            .map_err(|e| self.error_context(e, Operation::RegisterWrite { register }))
+    }
+
+    /// Read `buf.len()` bytes of SubDevice memory starting at `register` into `buf`.
+    ///
+    /// Reads longer than the PDU storage frame size are split into multiple PDUs, with each
+    /// response written directly into `buf`. See [`WrappedRead::receive_into`].
+    pub async fn register_read_into(
+        &self,
+        register: impl Into<u16>,
+        buf: &mut [u8],
+    ) -> Result<(), Error> {
+        self.read(register.into())
+            .receive_into(self.maindevice, buf)
+            .await
     }
 
+    /// Write `data` to SubDevice memory starting at `register`.
+    ///
+    /// Writes longer than the PDU storage frame size are split into multiple PDUs. See
+    /// [`WrappedWrite::send_slice`].
+    ///
+    /// Note that while this method is marked safe, raw alterations to SubDevice config or behaviour can
+    /// break higher level interactions with EtherCrab.
+    pub async fn register_write_slice(
+        &self,
+        register: impl Into<u16>,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        self.write(register.into())
+            .send_slice(self.maindevice, data)
+            .await
+    }
+
+    /// Read the configuration of FMMU `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NotFound`] if `index` is greater than 15.
+    pub async fn fmmu(&self, index: u8) -> Result<Fmmu, Error> {
+        self.read(Self::fmmu_register(index)?)
+            .receive(self.maindevice)
+            .await
+    }
+
+    /// Manually configure FMMU `index`, e.g. to map some of the SubDevice's process data into a
+    /// custom area of logical memory.
+    ///
+    /// EtherCrab uses the FMMUs described in the SubDevice's EEPROM to map process data into its
+    /// group's PDI, starting at FMMU 0, so an unused FMMU should be chosen. The SubDevice must
+    /// usually be in SAFE-OP or OP for its process data sync managers to be enabled. Logical
+    /// addresses are not checked against group PDIs, so the mapped area should be placed after the
+    /// last group. Data can then be exchanged with [`Command::lrw`] and friends.
+    ///
+    /// Note that mappings are cleared when the SubDevice is reset, e.g. during
+    /// [`SubDeviceGroup::recover`](crate::SubDeviceGroup::recover).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NotFound`] if `index` is greater than 15, or
+    /// [`Error::WorkingCounter`] if the SubDevice didn't accept the configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{std::ethercat_now, Command, Fmmu, LogicalAddress, MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+    /// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # async fn case() -> Result<(), ethercrab::error::Error> {
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// let group = maindevice
+    ///     .init_single_group::<16, 64>(ethercat_now)
+    ///     .await?
+    ///     .into_safe_op(&maindevice)
+    ///     .await?;
+    ///
+    /// let subdevice = group.subdevice(&maindevice, 2)?;
+    ///
+    /// // Also map the first input byte of the SubDevice into logical address 0x0001_0000
+    /// subdevice
+    ///     .configure_fmmu(3, Fmmu::inputs(LogicalAddress::new(0x0001_0000), 0x1000, 1))
+    ///     .await?;
+    ///
+    /// let input = Command::lrd(LogicalAddress::new(0x0001_0000))
+    ///     .receive::<u8>(&maindevice)
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn configure_fmmu(&self, index: u8, fmmu: Fmmu) -> Result<(), Error> {
+        self.write(Self::fmmu_register(index)?)
+            .send_receive::<Fmmu>(self.maindevice, fmmu)
+            .await?;
+
+        fmt::debug!(
+            "SubDevice {:#06x} FMMU{} manually configured: {}",
+            self.configured_address,
+            index,
+            fmmu
+        );
+
+        Ok(())
+    }
+
+    fn fmmu_register(index: u8) -> Result<RegisterAddress, Error> {
+        if index >= 16 {
+            return Err(Error::NotFound {
+                item: Item::Fmmu,
+                index: Some(u16::from(index)),
+            });
+        }
+
+        Ok(RegisterAddress::fmmu(index))
+    }
+
+    /// Read `buf.len()` bytes of the SubDevice's SII EEPROM, starting at word address
+    /// `start_word`, into `buf`.
+    ///
+    /// This can be used to dump the entire EEPROM contents without staging them through the PDU
+    /// storage. The MainDevice must have access to the EEPROM, which is the case unless the
+    /// SubDevice has been configured to assign it to the PDI.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts, std::ethercat_now};
+    /// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # async fn example() -> Result<(), ethercrab::error::Error> {
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # let group = maindevice.init_single_group::<8, 8>(ethercat_now).await?;
+    /// let subdevice = group.subdevice(&maindevice, 0)?;
+    ///
+    /// // Read the first 2KiB of the EEPROM
+    /// let mut eeprom = vec![0u8; 2048];
+    ///
+    /// subdevice.eeprom_read_into(0, &mut eeprom).await?;
+    /// # Ok(()) }
+    /// ```
+    pub async fn eeprom_read_into(&self, start_word: u32, buf: &mut [u8]) -> Result<(), Error> {
+        self.sii_reader().read_into(start_word, buf).await
+    }
+
+    /// Get the size of the SubDevice's SII EEPROM in bytes, as stored in the EEPROM itself.
+    pub async fn eeprom_size(&self) -> Result<usize, Error> {
+        self.sii_reader().size().await
+    }
+
+    /// Write the entire SII EEPROM contents of the SubDevice into `writer`, e.g. to back it up
+    /// before experimenting with the SubDevice's configuration.
+    ///
+    /// The EEPROM is read in small chunks, so the image is never held in memory in its entirety.
+    /// Returns the number of bytes written, which is the size given by
+    /// [`eeprom_size`](SubDeviceRef::eeprom_size). The image can be written back to the SubDevice
+    /// with [`eeprom_restore`](SubDeviceRef::eeprom_restore).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{MainDevice, MainDeviceConfig, PduStorage, Timeouts, std::ethercat_now};
+    /// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # let group = maindevice.init_single_group::<8, 8>(ethercat_now).await?;
+    /// let subdevice = group.subdevice(&maindevice, 0)?;
+    ///
+    /// let mut file = std::fs::File::create(format!("{}.bin", subdevice.name()))?;
+    ///
+    /// subdevice.eeprom_dump(&mut file).await?;
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "std")]
+    pub async fn eeprom_dump(&self, writer: &mut impl std::io::Write) -> std::io::Result<usize> {
+        let size = self.eeprom_size().await.map_err(std::io::Error::other)?;
+
+        let mut buf = [0u8; 256];
+
+        for start in (0..size).step_by(buf.len()) {
+            let chunk = &mut buf[..(size - start).min(256)];
+
+            self.eeprom_read_into((start / 2) as u32, chunk)
+                .await
+                .map_err(std::io::Error::other)?;
+
+            writer.write_all(chunk)?;
+        }
+
+        Ok(size)
+    }
+
+    /// Write `image` into the SubDevice's SII EEPROM, starting at the first word.
+    ///
+    /// This can be used to restore an image created with
+    /// [`eeprom_dump`](SubDeviceRef::eeprom_dump) or
+    /// [`eeprom_read_into`](SubDeviceRef::eeprom_read_into), e.g. after a vendor tool has
+    /// corrupted the EEPROM contents. The EEPROM is written one WORD at a time, and WORDs that
+    /// already hold the desired value are skipped. If `image` has an odd length, the high byte of
+    /// the last WORD is left unchanged.
+    ///
+    /// The new contents are only used by the SubDevice after it has been power cycled.
+    ///
+    /// Writing an EEPROM WORD can take a few milliseconds, so
+    /// [`Timeouts::eeprom`](crate::Timeouts::eeprom) may need to be increased for slow devices.
+    ///
+    /// Returns [`EepromError::SectionOverrun`] if `image` is larger than
+    /// [`eeprom_size`](SubDeviceRef::eeprom_size), or [`EepromError::Write`] if the SubDevice
+    /// rejects a write.
+    ///
+    /// Note that while this method is marked safe, writing an invalid image can leave the SubDevice
+    /// unable to communicate until its EEPROM is reprogrammed with a vendor tool.
+    pub async fn eeprom_restore(&self, image: &[u8]) -> Result<(), Error> {
+        if image.len() > self.eeprom_size().await? {
+            return Err(Error::Eeprom(EepromError::SectionOverrun));
+        }
+
+        let device = DeviceEeprom::new(self.maindevice, self.configured_address);
+
+        let mut current = [0u8; 64];
+
+        for (chunk_idx, chunk) in image.chunks(current.len()).enumerate() {
+            let start_word = (chunk_idx * current.len() / 2) as u32;
+
+            let current = &mut current[..chunk.len().next_multiple_of(2)];
+
+            self.eeprom_read_into(start_word, current).await?;
+
+            for (word_idx, (new, old)) in chunk.chunks(2).zip(current.chunks(2)).enumerate() {
+                // The high byte of a trailing odd byte is left unchanged
+                let new = [new[0], new.get(1).copied().unwrap_or(old[1])];
+
+                if new != old {
+                    device
+                        .write_word(start_word + word_idx as u32, u16::from_le_bytes(new))
+                        .await?;
+                }
+            }
+        }
+
+        fmt::debug!(
+            "Restored {} byte EEPROM image of SubDevice {:#06x}",
+            image.len(),
+            self.configured_address
+        );
+
+        Ok(())
+    }
+
+    /// Check the checksum of the SII EEPROM configuration area, i.e. the first 8 WORDs.
+    ///
+    /// SubDevices may refuse to load their configuration if the checksum is invalid, e.g. after an
+    /// EEPROM write was interrupted. Returns [`EepromError::Checksum`] if the checksum does not
+    /// match. Use [`eeprom_repair_checksum`](SubDeviceRef::eeprom_repair_checksum) to fix it.
+    pub async fn eeprom_verify_checksum(&self) -> Result<(), Error> {
+        self.sii_reader().verify_checksum().await
+    }
+
+    /// Write the correct checksum for the SII EEPROM configuration area if the stored one is
+    /// invalid.
+    ///
+    /// Returns `true` if the checksum was repaired, or `false` if it was already correct. As with
+    /// [`eeprom_restore`](SubDeviceRef::eeprom_restore), the new contents are only used by the
+    /// SubDevice after it has been power cycled.
+    pub async fn eeprom_repair_checksum(&self) -> Result<bool, Error> {
+        match self.eeprom_verify_checksum().await {
+            Ok(()) => Ok(false),
+            Err(Error::Eeprom(EepromError::Checksum { expected, actual })) => {
+                let mut word = [0u8; 2];
+
+                self.eeprom_read_into(SII_CHECKSUM_WORD.into(), &mut word)
+                    .await?;
+
+                // The high byte of the checksum WORD is reserved, so is left unchanged
+                DeviceEeprom::new(self.maindevice, self.configured_address)
+                    .write_word(
+                        SII_CHECKSUM_WORD.into(),
+                        u16::from_le_bytes([expected, word[1]]),
+                    )
+                    .await?;
+
+                fmt::debug!(
+                    "Repaired EEPROM checksum of SubDevice {:#06x}: {:#04x} -> {:#04x}",
+                    self.configured_address,
+                    actual,
+                    expected
+                );
+
+                Ok(true)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(configured_address = %self.configured_address, state = %desired_state)
+        )
+    )]
     pub(crate) async fn wait_for_state(&self, desired_state: SubDeviceState) -> Result<(), Error> {
         async {
             loop {
@@ -1002,11 +1849,28 @@ impl<'a, S> SubDeviceRef<'a, S> {
                     break Ok(());
                 }
 
+                if status.error {
+                    break Err(self.state_transition_error(desired_state).await);
+                }
+
                 self.maindevice.timeouts.loop_tick().await;
             }
         }
         .timeout(self.maindevice.timeouts.state_transition)
         .await
+        .map_err(|e| {
+            self.error_context(
+                e,
+                Operation::StateTransition {
+                    requested: desired_state,
+                },
+            )
+        })
+    }
+
+    /// Attach the operation being performed on this SubDevice to an error.
+    fn error_context(&self, error: Error, operation: Operation) -> Error {
+        error.with_context(ErrorContext::new(self.configured_address, operation))
     }
 
     pub(crate) fn write(&self, register: impl Into<u16>) -> WrappedWrite {
@@ -1017,6 +1881,14 @@ impl<'a, S> SubDeviceRef<'a, S> {
         Command::fprd(self.configured_address, register.into())
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(configured_address = %self.configured_address, state = %desired_state)
+        )
+    )]
     pub(crate) async fn request_subdevice_state_nowait(
         &self,
         desired_state: SubDeviceState,
@@ -1034,31 +1906,121 @@ impl<'a, S> SubDeviceRef<'a, S> {
             .await?;
 
         if response.error {
-            let error = self
-                .read(RegisterAddress::AlStatus)
-                .receive::<AlStatusCode>(self.maindevice)
-                .await?;
-
-            fmt::error!(
-                "Error occurred transitioning SubDevice {:#06x} to {:?}: {}",
-                self.configured_address,
-                desired_state,
-                error,
-            );
-
-            return Err(Error::StateTransition);
+            return Err(self.state_transition_error(desired_state).await);
         }
 
         Ok(())
     }
 
+    /// Read the current state and AL status code of the SubDevice to build an
+    /// [`Error::StateTransition`] describing why a transition to `requested` failed.
+    ///
+    /// Any errors encountered while reading the status registers are ignored so the original
+    /// transition failure is always reported.
+    pub(crate) async fn state_transition_error(&self, requested: SubDeviceState) -> Error {
+        let current = self
+            .read(RegisterAddress::AlStatus)
+            .ignore_wkc()
+            .receive::<AlControl>(self.maindevice)
+            .await
+            .map(|status| status.state)
+            .unwrap_or(SubDeviceState::None);
+
+        let status_code = self
+            .read(RegisterAddress::AlStatusCode)
+            .ignore_wkc()
+            .receive::<AlStatusCode>(self.maindevice)
+            .await
+            .unwrap_or(AlStatusCode::UnspecifiedError);
+
+        fmt::error!(
+            "Error occurred transitioning SubDevice {:#06x} from {} to {}: {}",
+            self.configured_address,
+            current,
+            requested,
+            status_code,
+        );
+
+        let error = Error::StateTransition {
+            configured_address: self.configured_address,
+            requested,
+            current,
+            status_code,
+        };
+
+        self.maindevice.pdu_loop.record_error(&error);
+
+        error
+    }
+
+    /// Request a state and wait for the SubDevice to reach it.
+    ///
+    /// Refused transitions are acknowledged and requested again according to
+    /// [`MainDeviceConfig::state_transition_retry`](crate::MainDeviceConfig::state_transition_retry).
     pub(crate) async fn request_subdevice_state(
         &self,
         desired_state: SubDeviceState,
     ) -> Result<(), Error> {
-        self.request_subdevice_state_nowait(desired_state).await?;
+        let policy = self.maindevice.config.state_transition_retry;
+
+        let mut history = StateTransitionHistory::new();
+
+        loop {
+            let result = async {
+                self.request_subdevice_state_nowait(desired_state).await?;
+
+                self.wait_for_state(desired_state).await
+            }
+            .await;
+
+            let (error, current) = match result {
+                Err(
+                    error @ Error::StateTransition {
+                        current,
+                        status_code,
+                        ..
+                    },
+                ) => {
+                    history.push(StateTransitionAttempt {
+                        current,
+                        status_code,
+                    });
+
+                    (error, current)
+                }
+                result => return result,
+            };
+
+            if history.attempts() > u16::from(policy.retries) {
+                if policy.retries == 0 {
+                    return Err(error);
+                }
+
+                return Err(Error::StateTransitionRetries {
+                    configured_address: self.configured_address,
+                    requested: desired_state,
+                    history,
+                });
+            }
 
-        self.wait_for_state(desired_state).await
+            fmt::warn!(
+                "SubDevice {:#06x} refused transition to {}, retrying ({} of {})",
+                self.configured_address,
+                desired_state,
+                history.attempts(),
+                policy.retries
+            );
+
+            self.write(RegisterAddress::AlControl)
+                .send(self.maindevice, AlControl::acknowledge(current))
+                .await?;
+
+            let delay = policy.backoff.delay(u32::from(history.attempts() - 1));
+
+            if !delay.is_zero() {
+                timer(delay).await;
+            }
+        }
     }
 
     pub(crate) async fn set_eeprom_mode(&self, mode: SiiOwner) -> Result<(), Error> {
@@ -1075,3 +2037,267 @@ impl<'a, S> SubDeviceRef<'a, S> {
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        error::{Error, MailboxError, StateTransitionAttempt},
+        sim::{block_on, ethercat_now, fixture::*, sleep, SimNetwork, SimSubDevice},
+        tx_rx_device, AlStatusCode, ConfiguredAddress, MainDevice, MainDeviceConfig, PduStorage,
+        RegisterAddress, RetryBackoff, StateTransitionRetry, SubDeviceState, Timeouts,
+    };
+    use core::time::Duration;
+
+    #[test]
+    fn state_transition_retry() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        network.with_subdevice(1, |subdevice| {
+            subdevice.refuse_transitions(2, AlStatusCode::SubDeviceNeedsColdStart)
+        });
+
+        let config = MainDeviceConfig {
+            state_transition_retry: StateTransitionRetry {
+                retries: 2,
+                backoff: RetryBackoff::Fixed(Duration::from_millis(1)),
+            },
+            ..MainDeviceConfig::default()
+        };
+
+        run_with_config(&network, &STORAGE, config, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            let group = group.into_op(&maindevice).await.expect("PRE-OP -> OP");
+
+            assert_eq!(group.all_op(&maindevice).await, Ok(true));
+        });
+    }
+
+    #[test]
+    fn state_transition_retries_exhausted() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        network.with_subdevice(1, |subdevice| {
+            subdevice.refuse_transitions(3, AlStatusCode::SubDeviceNeedsColdStart)
+        });
+
+        let config = MainDeviceConfig {
+            state_transition_retry: StateTransitionRetry {
+                retries: 2,
+                backoff: RetryBackoff::None,
+            },
+            ..MainDeviceConfig::default()
+        };
+
+        let result = run_with_config(&network, &STORAGE, config, |maindevice| async move {
+            maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .map(|_| ())
+        });
+
+        let Err(Error::StateTransitionRetries {
+            configured_address,
+            requested,
+            history,
+        }) = result
+        else {
+            panic!("expected retries to be exhausted, got {:?}", result);
+        };
+
+        assert_eq!(configured_address, ConfiguredAddress::new(0x1001));
+        assert_eq!(requested, SubDeviceState::PreOp);
+        assert_eq!(history.attempts(), 3);
+        assert!(history.iter().all(|attempt| attempt
+            == StateTransitionAttempt {
+                current: SubDeviceState::Init,
+                status_code: AlStatusCode::SubDeviceNeedsColdStart,
+            }));
+    }
+
+    #[test]
+    fn state_transition_refused() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        network.with_subdevice(1, |subdevice| {
+            subdevice.refuse_transitions(1, AlStatusCode::InvalidSyncManagerConfiguration)
+        });
+
+        let result = run(&network, &STORAGE, |maindevice| async move {
+            maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .map(|_| ())
+        });
+
+        // The refusal is reported as soon as the AL status error flag is seen, instead of waiting
+        // for the state transition to time out.
+        assert_eq!(
+            result.map_err(|e| e.cause()),
+            Err(Error::StateTransition {
+                configured_address: ConfiguredAddress::new(0x1001),
+                requested: SubDeviceState::PreOp,
+                current: SubDeviceState::Init,
+                status_code: AlStatusCode::InvalidSyncManagerConfiguration,
+            })
+        );
+    }
+
+    #[test]
+    fn bootstrap() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = SimNetwork::new([
+            SimSubDevice::new("EK1100", identity(0x044c_2c52)),
+            SimSubDevice::new("Drive", identity(0x1234))
+                .with_inputs(4)
+                .with_outputs(2)
+                .with_coe()
+                .with_bootstrap(),
+        ]);
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            assert_eq!(
+                group
+                    .subdevice(&maindevice, 0)
+                    .unwrap()
+                    .into_bootstrap()
+                    .await,
+                Err(Error::Mailbox(MailboxError::NoMailbox))
+            );
+
+            let mut subdevice = group.subdevice(&maindevice, 1).unwrap();
+
+            assert_eq!(subdevice.into_bootstrap().await, Ok(()));
+
+            // SM0 and SM1 physical start addresses and lengths
+            assert_eq!(
+                subdevice
+                    .register_read::<[u16; 2]>(RegisterAddress::Sm0)
+                    .await,
+                Ok([0x1800, 512])
+            );
+            assert_eq!(
+                subdevice
+                    .register_read::<[u16; 2]>(RegisterAddress::Sm1)
+                    .await,
+                Ok([0x1a00, 512])
+            );
+        });
+
+        assert_eq!(
+            network.with_subdevice(1, |subdevice| subdevice.state()),
+            Some(SubDeviceState::Bootstrap)
+        );
+    }
+
+    #[test]
+    fn leave_bootstrap() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = SimNetwork::new([
+            SimSubDevice::new("EK1100", identity(0x044c_2c52)),
+            SimSubDevice::new("Drive", identity(0x1234))
+                .with_inputs(4)
+                .with_outputs(2)
+                .with_coe()
+                .with_bootstrap(),
+        ]);
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init");
+
+            let mut subdevice = group.subdevice(&maindevice, 1).unwrap();
+
+            assert_eq!(subdevice.into_bootstrap().await, Ok(()));
+            assert_eq!(subdevice.leave_bootstrap().await, Ok(()));
+
+            // Standard mailbox layout is restored
+            assert_eq!(
+                subdevice
+                    .register_read::<[u16; 2]>(RegisterAddress::Sm0)
+                    .await,
+                Ok([0x1000, 128])
+            );
+            assert_eq!(subdevice.sdo_read::<u32>(0x1018, 2).await, Ok(0x1234));
+        });
+
+        assert_eq!(
+            network.with_subdevice(1, |subdevice| subdevice.state()),
+            Some(SubDeviceState::PreOp)
+        );
+    }
+
+    #[test]
+    fn cancelled_sdo() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let (tx, rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let maindevice = MainDevice::new(
+            pdu_loop,
+            Timeouts {
+                wait_loop_delay: Duration::from_millis(1),
+                ..Timeouts::default()
+            },
+            MainDeviceConfig::default(),
+        );
+
+        let network = SimNetwork::new([SimSubDevice::new("Drive", identity(0x1234))
+            .with_coe()
+            .with_mailbox_delay(Duration::from_millis(20))
+            .with_sdo(0x2000, 0, 0x1234u16)
+            .with_sdo(0x2001, 0, 0x5678u16)]);
+
+        block_on(futures_lite::future::or(
+            async {
+                let group = maindevice
+                    .init_single_group::<2, 8>(ethercat_now)
+                    .await
+                    .expect("init");
+
+                let drive = group.subdevice(&maindevice, 0).unwrap();
+
+                // E.g. a `select!` timeout drops the read after the request was sent but before
+                // the SubDevice responded
+                let cancelled = futures_lite::future::or(
+                    async { Some(drive.sdo_read::<u16>(0x2000, 0).await) },
+                    async {
+                        sleep(Duration::from_millis(10)).await;
+
+                        None
+                    },
+                )
+                .await;
+
+                assert_eq!(cancelled, None);
+
+                // The late response to the cancelled read must not be taken as this response
+                assert_eq!(drive.sdo_read::<u16>(0x2001, 0).await, Ok(0x5678));
+                assert_eq!(drive.sdo_read::<u16>(0x2000, 0).await, Ok(0x1234));
+            },
+            async {
+                tx_rx_device(network, tx, rx).await.unwrap();
+
+                unreachable!()
+            },
+        ));
+    }
+}
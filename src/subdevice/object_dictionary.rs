@@ -0,0 +1,685 @@
+//! Browse a SubDevice's CoE object dictionary with the SDO Information service.
+
+use super::{SubDevice, SubDeviceRef};
+use crate::{
+    base_data_types::PrimitiveDataType,
+    coe::{self, SubIndex},
+    error::{Error, Item, MailboxError, Operation, PduError},
+    fmt,
+};
+use core::ops::Deref;
+use ethercrab_wire::EtherCrabWireRead;
+
+/// Maximum length of an object or entry name in bytes. Longer names are truncated.
+const MAX_NAME_LEN: usize = 64;
+
+/// Maximum length of a value read by [`ObjectDictionary::next`] in bytes.
+const MAX_VALUE_LEN: usize = 64;
+
+bitflags::bitflags! {
+    /// Access rights and PDO mapping support of an object dictionary entry.
+    ///
+    /// Defined in ETG1000.6 Section 5.6.3.6.2 Table 48 – Get Entry Description Response.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct ObjectAccess: u16 {
+        /// Readable in PRE-OP.
+        const READ_PRE_OP = 0x0001;
+        /// Readable in SAFE-OP.
+        const READ_SAFE_OP = 0x0002;
+        /// Readable in OP.
+        const READ_OP = 0x0004;
+        /// Writable in PRE-OP.
+        const WRITE_PRE_OP = 0x0008;
+        /// Writable in SAFE-OP.
+        const WRITE_SAFE_OP = 0x0010;
+        /// Writable in OP.
+        const WRITE_OP = 0x0020;
+        /// Can be mapped into an RxPDO (outputs).
+        const RX_PDO_MAPPABLE = 0x0040;
+        /// Can be mapped into a TxPDO (inputs).
+        const TX_PDO_MAPPABLE = 0x0080;
+        /// Used for backup.
+        const BACKUP = 0x0100;
+        /// Used for settings.
+        const SETTINGS = 0x0200;
+    }
+}
+
+// Can't derive, so manual impl
+#[cfg(feature = "defmt")]
+impl defmt::Format for ObjectAccess {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=u16:b}", self.bits())
+    }
+}
+
+impl ObjectAccess {
+    /// The entry can be read in at least one state.
+    pub fn is_readable(&self) -> bool {
+        self.intersects(Self::READ_PRE_OP | Self::READ_SAFE_OP | Self::READ_OP)
+    }
+
+    /// The entry can be written in at least one state.
+    pub fn is_writable(&self) -> bool {
+        self.intersects(Self::WRITE_PRE_OP | Self::WRITE_SAFE_OP | Self::WRITE_OP)
+    }
+}
+
+/// The kind of an object in the object dictionary.
+///
+/// Defined in ETG1000.6 Section 5.6.3.5.2 Table 46 – Get Object Description Response.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ObjectCode {
+    /// A single value at sub-index 0.
+    Variable,
+    /// Multiple values of the same type. Sub-index 0 holds the number of values.
+    Array,
+    /// Multiple values of different types. Sub-index 0 holds the highest sub-index.
+    Record,
+    /// An unknown object code.
+    Unknown(u8),
+}
+
+impl From<u8> for ObjectCode {
+    fn from(value: u8) -> Self {
+        match value {
+            0x07 => Self::Variable,
+            0x08 => Self::Array,
+            0x09 => Self::Record,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+/// A single entry in a SubDevice's object dictionary, returned by [`ObjectDictionary::next`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ObjectEntry {
+    /// Object index.
+    pub index: u16,
+
+    /// Sub-index of this entry in the object.
+    pub sub_index: u8,
+
+    /// The kind of object this entry belongs to.
+    pub object_code: ObjectCode,
+
+    /// Name of the object, e.g. `Identity`.
+    pub object_name: heapless::String<MAX_NAME_LEN>,
+
+    /// Name of the entry, e.g. `Vendor ID`.
+    pub name: heapless::String<MAX_NAME_LEN>,
+
+    /// Data type of the entry, or [`PrimitiveDataType::Unknown`] for other types like strings.
+    pub data_type: PrimitiveDataType,
+
+    /// Raw data type index from ETG1000.6 Table 64, e.g. `0x0009` for `VISIBLE_STRING`.
+    pub data_type_index: u16,
+
+    /// Length of the entry in bits.
+    pub bit_len: u16,
+
+    /// Access rights and PDO mapping support.
+    pub access: ObjectAccess,
+
+    /// The current value of the entry, as raw little endian bytes.
+    ///
+    /// This is `None` if the entry can't be read, the SubDevice aborted the read or the value is
+    /// longer than 64 bytes.
+    pub value: Option<heapless::Vec<u8, MAX_VALUE_LEN>>,
+}
+
+/// Object description from an SDO Information Get Object Description response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ObjectDescription {
+    index: u16,
+    max_sub_index: u8,
+    object_code: ObjectCode,
+    name: heapless::String<MAX_NAME_LEN>,
+}
+
+impl ObjectDescription {
+    fn parse(raw: &[u8]) -> Result<Self, Error> {
+        let (fields, name) = raw
+            .split_first_chunk::<6>()
+            .ok_or(Error::Pdu(PduError::Decode))?;
+
+        Ok(Self {
+            index: u16::unpack_from_slice(&fields[0..2])?,
+            // The object's data type is skipped as each entry has its own
+            max_sub_index: fields[4],
+            object_code: ObjectCode::from(fields[5]),
+            name: parse_name(name),
+        })
+    }
+}
+
+/// Entry description from an SDO Information Get Entry Description response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EntryDescription {
+    index: u16,
+    sub_index: u8,
+    data_type: u16,
+    bit_len: u16,
+    access: ObjectAccess,
+    name: heapless::String<MAX_NAME_LEN>,
+}
+
+impl EntryDescription {
+    fn parse(raw: &[u8]) -> Result<Self, Error> {
+        let (fields, name) = raw
+            .split_first_chunk::<10>()
+            .ok_or(Error::Pdu(PduError::Decode))?;
+
+        // The value info byte is zero in the request, so no unit, default, minimum or maximum
+        // values come before the name.
+        Ok(Self {
+            index: u16::unpack_from_slice(&fields[0..2])?,
+            sub_index: fields[2],
+            data_type: u16::unpack_from_slice(&fields[4..6])?,
+            bit_len: u16::unpack_from_slice(&fields[6..8])?,
+            access: ObjectAccess::from_bits_truncate(u16::unpack_from_slice(&fields[8..10])?),
+            name: parse_name(name),
+        })
+    }
+}
+
+/// Decode a name, stopping at the first null byte or invalid UTF-8 and truncating it if it's too
+/// long.
+fn parse_name(raw: &[u8]) -> heapless::String<MAX_NAME_LEN> {
+    let raw = raw.split(|b| *b == 0).next().unwrap_or_default();
+
+    let valid = match core::str::from_utf8(raw) {
+        Ok(name) => name,
+        Err(e) => core::str::from_utf8(&raw[0..e.valid_up_to()]).unwrap_or_default(),
+    };
+
+    let mut name = heapless::String::new();
+
+    for c in valid.chars() {
+        if name.push(c).is_err() {
+            break;
+        }
+    }
+
+    name
+}
+
+/// Convert a data type index to one of the primitive types.
+fn primitive_data_type(data_type: u16) -> PrimitiveDataType {
+    u8::try_from(data_type)
+        .ok()
+        .and_then(|data_type| PrimitiveDataType::unpack_from_slice(&[data_type]).ok())
+        .unwrap_or(PrimitiveDataType::Unknown)
+}
+
+/// An iterator over the entries of a SubDevice's CoE object dictionary, created with
+/// [`SubDeviceRef::object_dictionary`].
+///
+/// Each call to [`next`](ObjectDictionary::next) reads the description and current value of the
+/// next entry from the SubDevice.
+#[derive(Debug)]
+pub struct ObjectDictionary<'sub, 'a, S, const MAX_OBJECTS: usize> {
+    subdevice: &'sub SubDeviceRef<'a, S>,
+    indices: heapless::Vec<u16, MAX_OBJECTS>,
+    /// Position of the next object to describe in `indices`.
+    position: usize,
+    /// The object whose entries are currently being read.
+    object: Option<ObjectDescription>,
+    /// The next sub-index of `object` to read. This is a `u16` so it can go past sub-index 255.
+    next_sub_index: u16,
+}
+
+impl<'sub, 'a, S, const MAX_OBJECTS: usize> ObjectDictionary<'sub, 'a, S, MAX_OBJECTS>
+where
+    S: Deref<Target = SubDevice>,
+{
+    /// The indices of all objects in the object dictionary, in the order they are returned.
+    pub fn indices(&self) -> &[u16] {
+        &self.indices
+    }
+
+    /// Read the next entry from the SubDevice, or return `None` when all entries have been read.
+    ///
+    /// Sub-indices that the SubDevice has no description for, e.g. gaps in a record, are skipped.
+    pub async fn next(&mut self) -> Result<Option<ObjectEntry>, Error> {
+        loop {
+            let Some(object) = self
+                .object
+                .as_ref()
+                .filter(|object| self.next_sub_index <= u16::from(object.max_sub_index))
+            else {
+                let Some(&index) = self.indices.get(self.position) else {
+                    return Ok(None);
+                };
+
+                self.position += 1;
+                self.object = Some(self.subdevice.object_description(index).await?);
+                self.next_sub_index = 0;
+
+                continue;
+            };
+
+            let sub_index = self.next_sub_index as u8;
+
+            self.next_sub_index += 1;
+
+            let entry = match self
+                .subdevice
+                .entry_description(object.index, sub_index)
+                .await
+            {
+                Ok(entry) => entry,
+                Err(Error::Mailbox(MailboxError::Aborted { .. })) => {
+                    fmt::debug!(
+                        "No description for entry {:#06x}:{}, skipping",
+                        object.index,
+                        sub_index
+                    );
+
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            let value = if entry.access.is_readable() {
+                self.subdevice
+                    .entry_value(entry.index, entry.sub_index)
+                    .await?
+            } else {
+                None
+            };
+
+            break Ok(Some(ObjectEntry {
+                index: entry.index,
+                sub_index: entry.sub_index,
+                object_code: object.object_code,
+                object_name: object.name.clone(),
+                name: entry.name,
+                data_type: primitive_data_type(entry.data_type),
+                data_type_index: entry.data_type,
+                bit_len: entry.bit_len,
+                access: entry.access,
+                value,
+            }));
+        }
+    }
+}
+
+impl<'a, S> SubDeviceRef<'a, S>
+where
+    S: Deref<Target = SubDevice>,
+{
+    /// Browse the SubDevice's CoE object dictionary, reading the name, data type and current value
+    /// of every entry.
+    ///
+    /// The list of object indices is read when this method is called, and each entry is read as
+    /// the returned [`ObjectDictionary`] is iterated. `MAX_OBJECTS` is the maximum number of
+    /// objects the SubDevice may have. If it has more, an [`Error::Capacity`] is returned.
+    ///
+    /// The SubDevice must support the SDO Information service, which is indicated by
+    /// [`CoeDetails::ENABLE_SDO_INFO`](crate::sii::CoeDetails::ENABLE_SDO_INFO) in its EEPROM.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{
+    /// #     error::Error, MainDevice, MainDeviceConfig, PduStorage, Timeouts, std::ethercat_now
+    /// # };
+    /// # static PDU_STORAGE: PduStorage<8, 32> = PduStorage::new();
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # async {
+    /// # let mut group = maindevice
+    /// #     .init_single_group::<8, 8>(ethercat_now)
+    /// #     .await
+    /// #     .expect("Init");
+    /// let subdevice = group.subdevice(&maindevice, 0).expect("No subdevice!");
+    ///
+    /// let mut objects = subdevice.object_dictionary::<256>().await?;
+    ///
+    /// while let Some(entry) = objects.next().await? {
+    ///     log::info!(
+    ///         "{:#06x}:{} {} / {} ({:?}): {:02x?}",
+    ///         entry.index,
+    ///         entry.sub_index,
+    ///         entry.object_name,
+    ///         entry.name,
+    ///         entry.data_type,
+    ///         entry.value
+    ///     );
+    /// }
+    /// # Ok::<(), ethercrab::error::Error>(())
+    /// # };
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(configured_address = %self.configured_address)
+        )
+    )]
+    pub async fn object_dictionary<const MAX_OBJECTS: usize>(
+        &self,
+    ) -> Result<ObjectDictionary<'_, 'a, S, MAX_OBJECTS>, Error> {
+        let mut indices = heapless::Vec::new();
+
+        {
+            let _mailbox = self.lock_mailbox(None).await?;
+
+            let request = coe::services::get_od_list(self.mailbox_counter());
+
+            // The list type is only sent in the first fragment
+            let mut first = true;
+            // Indices may be split across two fragments
+            let mut partial = None;
+
+            self.send_sdo_info(request, 0, 0, None, |mut data| {
+                if first {
+                    data = data.get(2..).ok_or(Error::Pdu(PduError::Decode))?;
+
+                    first = false;
+                }
+
+                for byte in data {
+                    let Some(lo) = partial.take() else {
+                        partial = Some(*byte);
+
+                        continue;
+                    };
+
+                    indices
+                        .push(u16::from_le_bytes([lo, *byte]))
+                        .map_err(|_| Error::Capacity(Item::Object))?;
+                }
+
+                Ok(())
+            })
+            .await?;
+        }
+
+        fmt::debug!(
+            "SubDevice {:#06x} has {} objects",
+            self.configured_address,
+            indices.len()
+        );
+
+        Ok(ObjectDictionary {
+            subdevice: self,
+            indices,
+            position: 0,
+            object: None,
+            next_sub_index: 0,
+        })
+    }
+
+    async fn object_description(&self, index: u16) -> Result<ObjectDescription, Error> {
+        let mut buf = [0u8; 6 + MAX_NAME_LEN];
+
+        let len = self
+            .sdo_info_description(
+                coe::services::get_object_description(self.mailbox_counter(), index),
+                index,
+                0,
+                &mut buf,
+            )
+            .await?;
+
+        ObjectDescription::parse(&buf[0..len])
+    }
+
+    async fn entry_description(
+        &self,
+        index: u16,
+        sub_index: u8,
+    ) -> Result<EntryDescription, Error> {
+        let mut buf = [0u8; 10 + MAX_NAME_LEN];
+
+        let len = self
+            .sdo_info_description(
+                coe::services::get_entry_description(self.mailbox_counter(), index, sub_index),
+                index,
+                sub_index,
+                &mut buf,
+            )
+            .await?;
+
+        EntryDescription::parse(&buf[0..len])
+    }
+
+    /// Collect the response to an object or entry description request into `buf`, dropping any
+    /// part of the name that doesn't fit.
+    async fn sdo_info_description(
+        &self,
+        request: coe::services::SdoInfo,
+        index: u16,
+        sub_index: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Error> {
+        let _mailbox = self.lock_mailbox(None).await?;
+
+        let mut len = 0;
+
+        self.send_sdo_info(request, index, sub_index, None, |data| {
+            let rest = &mut buf[len..];
+            let chunk = data.len().min(rest.len());
+
+            rest[0..chunk].copy_from_slice(&data[0..chunk]);
+
+            len += chunk;
+
+            Ok(())
+        })
+        .await?;
+
+        Ok(len)
+    }
+
+    /// Read the current value of an entry, or `None` if the SubDevice refuses or it's too long.
+    async fn entry_value(
+        &self,
+        index: u16,
+        sub_index: u8,
+    ) -> Result<Option<heapless::Vec<u8, MAX_VALUE_LEN>>, Error> {
+        let mut buf = [0u8; MAX_VALUE_LEN];
+
+        match self
+            .sdo_upload_into(index, SubIndex::Index(sub_index), &mut buf, None)
+            .await
+        {
+            Ok(len) => Ok(heapless::Vec::from_slice(&buf[0..len]).ok()),
+            Err(Error::Mailbox(MailboxError::Aborted { .. } | MailboxError::TooLong { .. })) => {
+                Ok(None)
+            }
+            Err(e) => Err(self.error_context(e, Operation::SdoRead { index, sub_index })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn object_description() {
+        // Identity object, from an EL3004
+        let raw = [
+            0x18, 0x10, 0x23, 0x00, 0x04, 0x09, b'I', b'd', b'e', b'n', b't', b'i', b't', b'y',
+        ];
+
+        let expected = ObjectDescription {
+            index: 0x1018,
+            max_sub_index: 4,
+            object_code: ObjectCode::Record,
+            name: heapless::String::try_from("Identity").unwrap(),
+        };
+
+        pretty_assertions::assert_eq!(ObjectDescription::parse(&raw), Ok(expected));
+    }
+
+    #[test]
+    fn entry_description() {
+        let raw = [
+            0x18, 0x10, 0x01, 0x00, 0x07, 0x00, 0x20, 0x00, 0x07, 0x00, b'V', b'e', b'n', b'd',
+            b'o', b'r', b' ', b'I', b'D',
+        ];
+
+        let expected = EntryDescription {
+            index: 0x1018,
+            sub_index: 1,
+            data_type: 0x0007,
+            bit_len: 32,
+            access: ObjectAccess::READ_PRE_OP | ObjectAccess::READ_SAFE_OP | ObjectAccess::READ_OP,
+            name: heapless::String::try_from("Vendor ID").unwrap(),
+        };
+
+        let parsed = EntryDescription::parse(&raw);
+
+        pretty_assertions::assert_eq!(parsed, Ok(expected));
+        assert_eq!(
+            primitive_data_type(parsed.unwrap().data_type),
+            PrimitiveDataType::U32
+        );
+    }
+
+    #[test]
+    fn truncated_description() {
+        assert_eq!(
+            EntryDescription::parse(&[0x18, 0x10, 0x01]),
+            Err(Error::Pdu(PduError::Decode))
+        );
+    }
+
+    #[test]
+    fn long_name() {
+        let raw = [b'a'; 100];
+
+        assert_eq!(parse_name(&raw).len(), MAX_NAME_LEN);
+        assert_eq!(parse_name(b"Status\0garbage").as_str(), "Status");
+        assert_eq!(parse_name(&[b'O', b'K', 0xff, b'x']).as_str(), "OK");
+    }
+
+    #[test]
+    fn string_data_type() {
+        assert_eq!(primitive_data_type(0x0009), PrimitiveDataType::Unknown);
+        assert_eq!(primitive_data_type(0x0800), PrimitiveDataType::Unknown);
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        sii::PrimitiveDataType,
+        sim::{block_on, ethercat_now, fixture::*, SdoAccess, SimNetwork, SimObject, SimSubDevice},
+        tx_rx_device, MainDevice, MainDeviceConfig, ObjectCode, PduStorage, Timeouts,
+    };
+
+    #[test]
+    fn object_dictionary() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let (tx, rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let maindevice =
+            MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+
+        // Enough objects that the object list doesn't fit in one mailbox response
+        let subdevice = (0x3000..0x3040).fold(
+            SimSubDevice::new("Drive", identity(0x1234))
+                .with_coe()
+                .with_object(
+                    0x6041,
+                    0,
+                    SimObject::new(PrimitiveDataType::U16, 0x0250u16)
+                        .with_access(SdoAccess::ReadOnly)
+                        .with_name("Status word"),
+                )
+                .with_object(
+                    0x2000,
+                    0,
+                    SimObject::new(PrimitiveDataType::U8, 3u8).with_name("Settings"),
+                )
+                .with_object(
+                    0x2000,
+                    1,
+                    SimObject::new(PrimitiveDataType::U32, 100_000u32).with_name("Speed"),
+                )
+                .with_object(
+                    0x2000,
+                    3,
+                    SimObject::new(PrimitiveDataType::I16, -5i16)
+                        .with_access(SdoAccess::WriteOnly)
+                        .with_name("Offset"),
+                ),
+            |subdevice, index| subdevice.with_sdo(index, 0, 0u8),
+        );
+
+        let network = SimNetwork::new([subdevice]);
+
+        block_on(futures_lite::future::or(
+            async {
+                let group = maindevice
+                    .init_single_group::<2, 8>(ethercat_now)
+                    .await
+                    .expect("init");
+
+                let drive = group.subdevice(&maindevice, 0).unwrap();
+
+                let mut objects = drive.object_dictionary::<128>().await.expect("OD list");
+
+                assert!(objects.indices().contains(&0x6041));
+                assert!((0x3000..0x3040).all(|index| objects.indices().contains(&index)));
+
+                let mut entries = Vec::new();
+
+                while let Some(entry) = objects.next().await.expect("entry") {
+                    entries.push(entry);
+                }
+
+                let entry = |index, sub_index| {
+                    entries
+                        .iter()
+                        .find(|entry| entry.index == index && entry.sub_index == sub_index)
+                        .cloned()
+                };
+
+                let status_word = entry(0x6041, 0).expect("status word");
+
+                assert_eq!(status_word.object_code, ObjectCode::Variable);
+                assert_eq!(status_word.object_name.as_str(), "Status word");
+                assert_eq!(status_word.name.as_str(), "Status word");
+                assert_eq!(status_word.data_type, PrimitiveDataType::U16);
+                assert_eq!(status_word.bit_len, 16);
+                assert!(status_word.access.is_readable());
+                assert!(!status_word.access.is_writable());
+                assert_eq!(status_word.value.as_deref(), Some([0x50, 0x02].as_slice()));
+
+                let speed = entry(0x2000, 1).expect("speed");
+
+                assert_eq!(speed.object_code, ObjectCode::Record);
+                assert_eq!(speed.object_name.as_str(), "Settings");
+                assert_eq!(speed.name.as_str(), "Speed");
+                assert_eq!(speed.data_type, PrimitiveDataType::U32);
+                assert_eq!(
+                    speed.value.as_deref(),
+                    Some(100_000u32.to_le_bytes().as_slice())
+                );
+
+                // Gap in the record
+                assert_eq!(entry(0x2000, 2), None);
+
+                let offset = entry(0x2000, 3).expect("offset");
+
+                assert_eq!(offset.data_type, PrimitiveDataType::I16);
+                assert_eq!(offset.value, None);
+            },
+            async {
+                tx_rx_device(network, tx, rx).await.unwrap();
+
+                unreachable!()
+            },
+        ));
+    }
+}
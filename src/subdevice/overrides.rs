@@ -0,0 +1,266 @@
+//! Hand-tuned configuration for SubDevices that don't describe themselves correctly in EEPROM.
+
+use super::SubDeviceIdentity;
+use crate::eeprom::types::{FmmuUsage, SyncManager};
+
+/// A raw register write performed during SubDevice initialisation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegisterWrite {
+    /// Register address.
+    pub register: u16,
+
+    /// Data to write, starting at `register`.
+    pub data: &'static [u8],
+}
+
+/// Replace parts of the configuration EtherCrab normally reads from the EEPROM of matching
+/// SubDevices.
+///
+/// Overrides are set with
+/// [`MainDeviceConfig::subdevice_overrides`](crate::MainDeviceConfig::subdevice_overrides) and are
+/// applied during [`MainDevice::init`](crate::MainDevice::init), and when a SubDevice is
+/// reconfigured by [`SubDeviceGroup::recover`](crate::SubDeviceGroup::recover). The first override
+/// matching a SubDevice's identity is used.
+///
+/// # Examples
+///
+/// ```rust
+/// use ethercrab::{
+///     sii::{Control, Direction, OperationMode, SyncManager, SyncManagerEnable, SyncManagerType},
+///     MainDeviceConfig, RegisterWrite, SubDeviceOverride,
+/// };
+///
+/// const OUTPUTS_SM: SyncManager = SyncManager {
+///     start_addr: 0x0f00,
+///     length: 1,
+///     control: Control {
+///         operation_mode: OperationMode::Normal,
+///         direction: Direction::MasterWrite,
+///         ecat_event_enable: false,
+///         dls_user_event_enable: true,
+///         watchdog_enable: true,
+///     },
+///     enable: SyncManagerEnable::ENABLE,
+///     usage_type: SyncManagerType::ProcessDataWrite,
+/// };
+///
+/// static OVERRIDES: &[SubDeviceOverride] = &[SubDeviceOverride {
+///     // The EEPROM claims a mailbox, but the SubDevice doesn't have one
+///     skip_mailbox: true,
+///     sync_managers: Some(&[OUTPUTS_SM]),
+///     // Vendor specific register that must be set before the SubDevice will enter PRE-OP
+///     init_writes: &[RegisterWrite {
+///         register: 0x0f80,
+///         data: &[0x01],
+///     }],
+///     ..SubDeviceOverride::new(0x0000_1234, 0x0000_0001)
+/// }];
+///
+/// let config = MainDeviceConfig {
+///     subdevice_overrides: OVERRIDES,
+///     ..MainDeviceConfig::default()
+/// };
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SubDeviceOverride {
+    /// Vendor ID of the SubDevices to apply this override to.
+    pub vendor_id: u32,
+
+    /// Product ID of the SubDevices to apply this override to.
+    pub product_id: u32,
+
+    /// Only apply this override to SubDevices with this revision, or `None` to match any revision.
+    pub revision: Option<u32>,
+
+    /// Sync manager configuration to use instead of the EEPROM "SyncManager" category, indexed by
+    /// sync manager number.
+    ///
+    /// The lengths of mailbox sync managers are used as the mailbox sizes. Process data sync
+    /// manager lengths are still computed from the PDO mapping.
+    pub sync_managers: Option<&'static [SyncManager]>,
+
+    /// FMMU usage to use instead of the EEPROM "FMMU" category, indexed by FMMU number.
+    ///
+    /// If this is set, the "FMMU_EX" category is ignored.
+    pub fmmus: Option<&'static [FmmuUsage]>,
+
+    /// Do not configure the mailbox sync managers, e.g. for simple SubDevices whose EEPROM
+    /// describes a mailbox they don't have.
+    ///
+    /// Mailbox protocols such as CoE will not be available for the SubDevice.
+    pub skip_mailbox: bool,
+
+    /// Leave the SubDevice in PRE-OP when the rest of its group is requested into SAFE-OP or OP,
+    /// e.g. for gateways or SubDevices that are only used for mailbox communication.
+    ///
+    /// No process data is mapped for the SubDevice, so it doesn't take up any space in the group's
+    /// PDI and doesn't contribute to the process data working counter. Note that network-wide AL
+    /// status checks, e.g. [`OutputStatus::is_ok`](crate::subdevice_group::OutputStatus::is_ok),
+    /// will no longer see every SubDevice in OP.
+    pub mailbox_only: bool,
+
+    /// Register writes performed in INIT after the mailbox is configured, before PRE-OP is
+    /// requested.
+    pub init_writes: &'static [RegisterWrite],
+}
+
+impl SubDeviceOverride {
+    /// Create an override for SubDevices with the given vendor and product ID that changes
+    /// nothing.
+    pub const fn new(vendor_id: u32, product_id: u32) -> Self {
+        Self {
+            vendor_id,
+            product_id,
+            revision: None,
+            sync_managers: None,
+            fmmus: None,
+            skip_mailbox: false,
+            mailbox_only: false,
+            init_writes: &[],
+        }
+    }
+
+    /// Whether this override applies to a SubDevice with the given identity.
+    pub(crate) fn matches(&self, identity: &SubDeviceIdentity) -> bool {
+        self.vendor_id == identity.vendor_id
+            && self.product_id == identity.product_id
+            && self
+                .revision
+                .map_or(true, |revision| revision == identity.revision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_identity() {
+        let identity = SubDeviceIdentity {
+            vendor_id: 0x2,
+            product_id: 0x07d4_3052,
+            revision: 0x0011_0000,
+            serial: 1234,
+        };
+
+        assert!(SubDeviceOverride::new(0x2, 0x07d4_3052).matches(&identity));
+        assert!(!SubDeviceOverride::new(0x2, 0x03ec_3052).matches(&identity));
+        assert!(SubDeviceOverride {
+            revision: Some(0x0011_0000),
+            ..SubDeviceOverride::new(0x2, 0x07d4_3052)
+        }
+        .matches(&identity));
+        assert!(!SubDeviceOverride {
+            revision: Some(0x0010_0000),
+            ..SubDeviceOverride::new(0x2, 0x07d4_3052)
+        }
+        .matches(&identity));
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        error::Error,
+        sii::{
+            Control, Direction, FmmuUsage, OperationMode, SyncManager, SyncManagerEnable,
+            SyncManagerType,
+        },
+        sim::fixture::*,
+        AlStatusCode, ConfiguredAddress, MainDeviceConfig, PduStorage, RegisterWrite,
+        SubDeviceOverride, SubDeviceState,
+    };
+
+    #[test]
+    fn subdevice_overrides() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        static OVERRIDES: &[SubDeviceOverride] = &[SubDeviceOverride {
+            // Same as the EEPROM but with the watchdog disabled
+            sync_managers: Some(&[SyncManager {
+                start_addr: 0x1100,
+                length: 1,
+                control: Control {
+                    operation_mode: OperationMode::Normal,
+                    direction: Direction::MasterWrite,
+                    ecat_event_enable: false,
+                    dls_user_event_enable: true,
+                    watchdog_enable: false,
+                },
+                enable: SyncManagerEnable::ENABLE,
+                usage_type: SyncManagerType::ProcessDataWrite,
+            }]),
+            fmmus: Some(&[FmmuUsage::Outputs]),
+            init_writes: &[RegisterWrite {
+                register: 0x0f80,
+                data: &[0xaa, 0x55],
+            }],
+            ..SubDeviceOverride::new(0x2, 0x07d4_3052)
+        }];
+
+        let network = network();
+
+        let config = MainDeviceConfig {
+            subdevice_overrides: OVERRIDES,
+            ..MainDeviceConfig::default()
+        };
+
+        run_with_config(&network, &STORAGE, config, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_safe_op(&maindevice)
+                .await
+                .expect("PRE-OP -> SAFE-OP");
+
+            let subdevice = group.subdevice(&maindevice, 1).unwrap();
+
+            assert_eq!(subdevice.register_read::<u16>(0x0f80u16).await, Ok(0x55aa));
+            // SM0 control without the watchdog enable bit
+            assert_eq!(subdevice.register_read::<u8>(0x0804u16).await, Ok(0x24));
+
+            // Other SubDevices are configured from EEPROM
+            let subdevice = group.subdevice(&maindevice, 3).unwrap();
+
+            assert_eq!(subdevice.register_read::<u8>(0x0814u16).await, Ok(0x64));
+        });
+    }
+
+    #[test]
+    fn skip_mailbox_override() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        static OVERRIDES: &[SubDeviceOverride] = &[SubDeviceOverride {
+            skip_mailbox: true,
+            ..SubDeviceOverride::new(0x2, 0x1234)
+        }];
+
+        let network = network();
+
+        let config = MainDeviceConfig {
+            subdevice_overrides: OVERRIDES,
+            ..MainDeviceConfig::default()
+        };
+
+        let result = run_with_config(&network, &STORAGE, config, |maindevice| async move {
+            maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .map(|_| ())
+        });
+
+        // The simulated drive refuses PRE-OP without a mailbox
+        assert_eq!(
+            result,
+            Err(Error::StateTransition {
+                configured_address: ConfiguredAddress::new(0x1003),
+                requested: SubDeviceState::PreOp,
+                current: SubDeviceState::Init,
+                status_code: AlStatusCode::InvalidMailboxConfiguration2,
+            })
+        );
+    }
+}
@@ -1,6 +1,78 @@
 use super::{SubDevice, SubDeviceRef};
+use crate::atomic::{AtomicU32, Ordering};
+use crate::waiters::Waiters;
 use atomic_refcell::AtomicRefMut;
-use core::ops::Deref;
+use core::{
+    future::Future,
+    ops::Deref,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Counts the process data exchanges of a group so tasks can wait for fresh inputs.
+#[derive(Debug)]
+pub(crate) struct PdiCycle {
+    count: AtomicU32,
+    waiters: Waiters,
+}
+
+impl PdiCycle {
+    pub(crate) const fn new() -> Self {
+        Self {
+            count: AtomicU32::new(0),
+            waiters: Waiters::new(),
+        }
+    }
+
+    /// Called when new inputs have been written into the group's PDI.
+    pub(crate) fn advance(&self) {
+        self.count.fetch_add(1, Ordering::AcqRel);
+
+        self.waiters.wake_all();
+    }
+
+    fn count(&self) -> u32 {
+        self.count.load(Ordering::Acquire)
+    }
+}
+
+/// Waits for the cycle count to move on from `start`.
+struct NextCycle<'a> {
+    cycle: &'a PdiCycle,
+    start: u32,
+    waiter_slot: Option<usize>,
+}
+
+impl Future for NextCycle<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.waiter_slot.is_none() {
+            self.waiter_slot = self.cycle.waiters.claim();
+        }
+
+        match self.waiter_slot {
+            // Register before checking the count so a cycle completed in between is not missed.
+            Some(slot) => self.cycle.waiters.register(slot, cx.waker()),
+            // All waiter slots are taken, so poll again as soon as possible instead.
+            None => cx.waker().wake_by_ref(),
+        }
+
+        if self.cycle.count() != self.start {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for NextCycle<'_> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.waiter_slot.take() {
+            self.cycle.waiters.release(slot);
+        }
+    }
+}
 
 /// Process Data Image (PDI) segments for a given SubDevice.
 ///
@@ -13,6 +85,8 @@ pub struct SubDevicePdi<'group> {
     inputs: &'group [u8],
 
     outputs: &'group mut [u8],
+
+    cycle: &'group PdiCycle,
 }
 
 impl<'group> Deref for SubDevicePdi<'group> {
@@ -28,11 +102,13 @@ impl<'group> SubDevicePdi<'group> {
         subdevice: AtomicRefMut<'group, SubDevice>,
         inputs: &'group [u8],
         outputs: &'group mut [u8],
+        cycle: &'group PdiCycle,
     ) -> Self {
         Self {
             subdevice,
             inputs,
             outputs,
+            cycle,
         }
     }
 }
@@ -121,4 +197,61 @@ impl<'a, 'group> SubDeviceRef<'a, SubDevicePdi<'group>> {
     pub fn outputs_raw_mut(&mut self) -> &mut [u8] {
         self.state.outputs
     }
+
+    /// Wait until `condition` returns `true` for this SubDevice's inputs.
+    ///
+    /// The condition is checked immediately, then again each time the group's process data is
+    /// exchanged by another task with e.g. [`SubDeviceGroup::tx_rx`](crate::SubDeviceGroup::tx_rx).
+    /// This future never resolves if the group is not being cycled, so wrap it in a timeout where
+    /// necessary.
+    ///
+    /// # Examples
+    ///
+    /// Wait for the first input bit to be set:
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{
+    /// #     error::Error, std::tx_rx_task, MainDevice, MainDeviceConfig, PduStorage, Timeouts,
+    /// # };
+    /// # async fn case() {
+    /// # static PDU_STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # let (tx, rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # let group = maindevice.init_single_group::<8, 8>(ethercrab::std::ethercat_now).await.expect("Init");
+    /// let group = group.into_op(&maindevice).await.expect("Op");
+    ///
+    /// let cycle = async {
+    ///     loop {
+    ///         group.tx_rx(&maindevice).await.expect("TX/RX");
+    ///
+    ///         smol::Timer::after(core::time::Duration::from_millis(1)).await;
+    ///     }
+    /// };
+    ///
+    /// let subdevice = group.subdevice(&maindevice, 0).expect("No device");
+    ///
+    /// let wait = subdevice.wait_for(|inputs| inputs[0] & 0b1 != 0);
+    ///
+    /// futures_lite::future::or(cycle, wait).await;
+    /// # }
+    /// ```
+    pub async fn wait_for<F>(&self, mut condition: F)
+    where
+        F: FnMut(&[u8]) -> bool,
+    {
+        loop {
+            let start = self.state.cycle.count();
+
+            if condition(self.state.inputs) {
+                break;
+            }
+
+            NextCycle {
+                cycle: self.state.cycle,
+                start,
+                waiter_slot: None,
+            }
+            .await;
+        }
+    }
 }
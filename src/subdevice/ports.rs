@@ -26,8 +26,10 @@ impl Port {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// The topology of a single SubDevice, determined by its number of open ports.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Topology {
     /// The SubDevice has two open ports, with only upstream and downstream subdevices.
     Passthrough,
@@ -40,6 +42,7 @@ pub enum Topology {
 }
 
 impl Topology {
+    /// Whether this SubDevice is a fork or cross, i.e. has more than one downstream port.
     pub fn is_junction(&self) -> bool {
         matches!(self, Self::Fork | Self::Cross)
     }
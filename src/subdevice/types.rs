@@ -7,6 +7,7 @@ use core::fmt::{self, Debug};
 /// SubDevice identity information (vendor ID, product ID, etc).
 #[derive(Default, Copy, Clone, PartialEq, ethercrab_wire::EtherCrabWireRead)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[wire(bytes = 16)]
 #[doc(alias = "SlaveIdentity")]
 pub struct SubDeviceIdentity {
@@ -48,6 +49,9 @@ impl Debug for SubDeviceIdentity {
 pub struct SubDeviceConfig {
     pub io: IoRanges,
     pub mailbox: MailboxConfig,
+    /// Held in PRE-OP with no process data. See
+    /// [`SubDeviceOverride::mailbox_only`](crate::SubDeviceOverride::mailbox_only).
+    pub mailbox_only: bool,
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -49,6 +49,15 @@ impl<'a> SubDeviceGroupRef<'a> {
     /// Initialise all SubDevices in the group and place them in PRE-OP.
     // Clippy: shush
     #[allow(clippy::wrong_self_convention)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "group_into_pre_op",
+            level = "debug",
+            skip_all,
+            fields(subdevices = self.inner.subdevices.len())
+        )
+    )]
     pub(crate) async fn into_pre_op<'sto>(
         &mut self,
         pdi_position: PdiOffset,
@@ -62,7 +71,7 @@ impl<'a> SubDeviceGroupRef<'a> {
         fmt::debug!(
             "Going to configure group with {} SubDevice(s), starting PDI offset {:#08x}",
             inner.subdevices.len(),
-            inner.pdi_start.start_address
+            inner.pdi_start.start_address.get()
         );
 
         // Configure master read PDI mappings in the first section of the PDI
@@ -0,0 +1,404 @@
+//! Fall back to SAFE-OP when process data cycles keep failing.
+
+use super::{Op, SubDeviceGroup};
+use crate::{
+    al_control::AlControl,
+    error::{Error, PduCommand},
+    fmt,
+    subdevice::SubDeviceRef,
+    ConfiguredAddress, MainDevice, RegisterAddress, SubDeviceState,
+};
+
+/// Configuration for a [`SafeOpFallback`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FallbackConfig {
+    /// The number of consecutive failed cycles after which the group is commanded to SAFE-OP.
+    ///
+    /// Defaults to 10.
+    pub max_failures: u32,
+
+    /// The working counter returned by every successful cycle, or `None` to only count cycles that
+    /// return an error as failed.
+    ///
+    /// This is usually the working counter of the first cycle after the group reaches OP.
+    ///
+    /// Defaults to `None`.
+    pub expected_wkc: Option<u16>,
+}
+
+impl Default for FallbackConfig {
+    fn default() -> Self {
+        Self {
+            max_failures: 10,
+            expected_wkc: None,
+        }
+    }
+}
+
+/// An event emitted by [`SubDeviceGroup::tx_rx_fallback`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FallbackEvent {
+    /// A process data cycle failed.
+    CycleFailed {
+        /// The number of consecutive failed cycles, including this one.
+        consecutive: u32,
+        /// The error returned by the cycle, or an [`Error::WorkingCounter`] if the cycle returned a
+        /// working counter other than [`FallbackConfig::expected_wkc`].
+        error: Error,
+    },
+    /// A SubDevice in the group could not be commanded to SAFE-OP.
+    CommandFailed {
+        /// SubDevice address.
+        configured_address: ConfiguredAddress,
+        /// The error returned when writing the AL control register.
+        error: Error,
+    },
+    /// [`FallbackConfig::max_failures`] consecutive cycles failed and every SubDevice in the group
+    /// was commanded to SAFE-OP.
+    SafeOp {
+        /// The number of consecutive failed cycles.
+        failures: u32,
+        /// The number of SubDevices that accepted the SAFE-OP request.
+        commanded: usize,
+    },
+}
+
+/// Consecutive process data cycle failures of a group, used by
+/// [`SubDeviceGroup::tx_rx_fallback`].
+///
+/// Once [`FallbackConfig::max_failures`] is reached the fallback becomes active and no more
+/// failures are counted until [`reset`](SafeOpFallback::reset) is called.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SafeOpFallback {
+    config: FallbackConfig,
+    failures: u32,
+    active: bool,
+}
+
+impl SafeOpFallback {
+    /// Create a new fallback with no failures counted.
+    pub const fn new(config: FallbackConfig) -> Self {
+        Self {
+            config,
+            failures: 0,
+            active: false,
+        }
+    }
+
+    /// The number of consecutive failed cycles.
+    pub fn consecutive_failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Whether the group has been commanded to SAFE-OP.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Clear the failure count and deactivate the fallback, e.g. once the group has been brought
+    /// back into OP with [`SubDeviceGroup::recover`].
+    pub fn reset(&mut self) {
+        self.failures = 0;
+        self.active = false;
+    }
+
+    /// Count the result of a cycle, returning the error if the cycle failed.
+    fn record(&mut self, result: &Result<u16, Error>) -> Option<Error> {
+        let error = match (result, self.config.expected_wkc) {
+            (Err(error), _) => *error,
+            (Ok(received), Some(expected)) if *received != expected => Error::WorkingCounter {
+                expected,
+                received: *received,
+                command: PduCommand::NOP,
+            },
+            (Ok(_), _) => {
+                self.failures = 0;
+
+                return None;
+            }
+        };
+
+        self.failures = self.failures.saturating_add(1);
+
+        Some(error)
+    }
+
+    /// Whether enough consecutive cycles have failed to fall back to SAFE-OP.
+    fn should_activate(&self) -> bool {
+        !self.active && self.failures >= self.config.max_failures
+    }
+}
+
+impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>
+    SubDeviceGroup<MAX_SUBDEVICES, MAX_PDI, Op, DC>
+{
+    /// Drive the SubDevice group's inputs and outputs like [`tx_rx`](SubDeviceGroup::tx_rx),
+    /// commanding every SubDevice in the group to SAFE-OP after too many consecutive failed cycles.
+    ///
+    /// A cycle fails if it returns an error, or a working counter other than
+    /// [`FallbackConfig::expected_wkc`]. Once [`FallbackConfig::max_failures`] consecutive cycles
+    /// have failed, each SubDevice in the group is requested into SAFE-OP, where its outputs are
+    /// held in a safe state but inputs are still updated. Progress is reported through
+    /// `on_event`.
+    ///
+    /// Process data cycles continue to be sent while the fallback is active, so inputs remain
+    /// valid. Call [`recover`](SubDeviceGroup::recover) to bring the group back into OP once the
+    /// fault is resolved, then [`SafeOpFallback::reset`].
+    ///
+    /// This method returns the result of the cycle unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{
+    /// #     std::ethercat_now,
+    /// #     subdevice_group::{FallbackConfig, FallbackEvent, RecoveryConfig, SafeOpFallback},
+    /// #     MainDevice, MainDeviceConfig, PduStorage, Timeouts,
+    /// # };
+    /// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # async fn case() -> Result<(), ethercrab::error::Error> {
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// let group = maindevice
+    ///     .init_single_group::<16, 64>(ethercat_now)
+    ///     .await?
+    ///     .into_op(&maindevice)
+    ///     .await?;
+    ///
+    /// let expected_wkc = group.tx_rx(&maindevice).await?;
+    ///
+    /// let mut fallback = SafeOpFallback::new(FallbackConfig {
+    ///     max_failures: 5,
+    ///     expected_wkc: Some(expected_wkc),
+    /// });
+    ///
+    /// loop {
+    ///     let _ = group
+    ///         .tx_rx_fallback(&maindevice, &mut fallback, |event| {
+    ///             if let FallbackEvent::SafeOp { .. } = event {
+    ///                 log::error!("Network is faulty, outputs disabled");
+    ///             }
+    ///         })
+    ///         .await;
+    ///
+    ///     if fallback.is_active()
+    ///         && group
+    ///             .recover(&maindevice, RecoveryConfig::default(), |_| ())
+    ///             .await
+    ///             .is_ok_and(|recovered| recovered > 0)
+    ///     {
+    ///         fallback.reset();
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn tx_rx_fallback<'sto>(
+        &self,
+        maindevice: &'sto MainDevice<'sto>,
+        fallback: &mut SafeOpFallback,
+        mut on_event: impl FnMut(FallbackEvent),
+    ) -> Result<u16, Error> {
+        let result = self.tx_rx(maindevice).await;
+
+        if fallback.is_active() {
+            return result;
+        }
+
+        let Some(error) = fallback.record(&result) else {
+            return result;
+        };
+
+        on_event(FallbackEvent::CycleFailed {
+            consecutive: fallback.failures,
+            error,
+        });
+
+        if fallback.should_activate() {
+            fmt::error!(
+                "{} consecutive process data cycles failed, commanding group to SAFE-OP",
+                fallback.failures
+            );
+
+            let commanded = self.command_safe_op(maindevice, &mut on_event).await;
+
+            fallback.active = true;
+
+            on_event(FallbackEvent::SafeOp {
+                failures: fallback.failures,
+                commanded,
+            });
+        }
+
+        result
+    }
+
+    /// Request every SubDevice in the group into SAFE-OP, returning the number of requests that
+    /// were accepted.
+    async fn command_safe_op(
+        &self,
+        maindevice: &MainDevice<'_>,
+        on_event: &mut impl FnMut(FallbackEvent),
+    ) -> usize {
+        let mut commanded = 0;
+
+        for &configured_address in self.inner().addresses.iter() {
+            let result = SubDeviceRef::new(maindevice, configured_address, ())
+                .write(RegisterAddress::AlControl)
+                .send_receive::<AlControl>(maindevice, AlControl::new(SubDeviceState::SafeOp))
+                .await;
+
+            match result {
+                Ok(_) => commanded += 1,
+                Err(error) => {
+                    fmt::warn!(
+                        "Failed to command SubDevice {:#06x} to SAFE-OP: {}",
+                        configured_address,
+                        error
+                    );
+
+                    on_event(FallbackEvent::CommandFailed {
+                        configured_address,
+                        error,
+                    });
+                }
+            }
+        }
+
+        commanded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consecutive_failures() {
+        let mut fallback = SafeOpFallback::new(FallbackConfig {
+            max_failures: 2,
+            expected_wkc: Some(3),
+        });
+
+        assert_eq!(fallback.record(&Err(Error::Timeout)), Some(Error::Timeout));
+        assert!(!fallback.should_activate());
+
+        // A good cycle resets the count
+        assert_eq!(fallback.record(&Ok(3)), None);
+        assert_eq!(fallback.consecutive_failures(), 0);
+
+        assert_eq!(
+            fallback.record(&Ok(1)),
+            Some(Error::WorkingCounter {
+                expected: 3,
+                received: 1,
+                command: PduCommand::NOP,
+            })
+        );
+        assert_eq!(fallback.record(&Err(Error::Timeout)), Some(Error::Timeout));
+        assert!(fallback.should_activate());
+
+        fallback.active = true;
+
+        assert!(!fallback.should_activate());
+
+        fallback.reset();
+
+        assert_eq!(fallback.consecutive_failures(), 0);
+        assert!(!fallback.is_active());
+    }
+
+    #[test]
+    fn any_wkc() {
+        let mut fallback = SafeOpFallback::new(FallbackConfig::default());
+
+        assert_eq!(fallback.record(&Ok(0)), None);
+        assert_eq!(fallback.record(&Ok(100)), None);
+        assert_eq!(fallback.consecutive_failures(), 0);
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        sim::fixture::*,
+        subdevice_group::{FallbackConfig, FallbackEvent, SafeOpFallback},
+        ConfiguredAddress, PduStorage, SubDeviceState,
+    };
+
+    #[test]
+    fn safe_op_fallback() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            let expected_wkc = group.tx_rx(&maindevice).await.expect("TX/RX");
+
+            let mut fallback = SafeOpFallback::new(FallbackConfig {
+                max_failures: 3,
+                expected_wkc: Some(expected_wkc),
+            });
+
+            let mut events = Vec::new();
+
+            group
+                .tx_rx_fallback(&maindevice, &mut fallback, |event| events.push(event))
+                .await
+                .expect("TX/RX");
+
+            assert!(events.is_empty());
+
+            net.disconnect(1);
+
+            for _ in 0..5 {
+                let _ = group
+                    .tx_rx_fallback(&maindevice, &mut fallback, |event| events.push(event))
+                    .await;
+            }
+
+            assert!(fallback.is_active());
+            assert_eq!(
+                events
+                    .iter()
+                    .filter(|event| matches!(event, FallbackEvent::CycleFailed { .. }))
+                    .count(),
+                3
+            );
+            assert!(events.contains(&FallbackEvent::SafeOp {
+                failures: 3,
+                commanded: 2,
+            }));
+            assert!(events.iter().any(|event| matches!(
+                event,
+                FallbackEvent::CommandFailed {
+                    configured_address,
+                    ..
+                } if *configured_address == ConfiguredAddress::new(0x1003)
+            )));
+        });
+
+        for index in 0..2 {
+            assert_eq!(
+                network.with_subdevice(index, |subdevice| subdevice.state()),
+                Some(SubDeviceState::SafeOp)
+            );
+        }
+        assert_eq!(
+            network.with_subdevice(3, |subdevice| subdevice.state()),
+            Some(SubDeviceState::Op)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 /// A group's unique ID.
 #[doc(hidden)]
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GroupId(pub(in crate::subdevice_group) usize);
 
 impl From<GroupId> for usize {
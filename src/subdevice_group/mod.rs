@@ -4,35 +4,54 @@
 //! potentially at different tick rates.
 
 mod configurator;
+mod fallback;
 mod group_id;
 mod handle;
 mod iterator;
+mod output_check;
+mod recovery;
 
 use crate::{
     al_control::AlControl,
+    atomic::AtomicUsize,
     command::Command,
-    error::{DistributedClockError, Error, Item, PduError},
+    error::{saturate_u16, saturate_u32, DistributedClockError, Error, Item, PduError},
     fmt,
     pdi::PdiOffset,
+    pdu_loop::{CreatedFrame, FramePriority, PduResponseHandle, ReceivedFrame},
+    register::DcSyncActivation,
     subdevice::{
-        configuration::PdoDirection, pdi::SubDevicePdi, IoRanges, SubDevice, SubDeviceRef,
+        configuration::PdoDirection,
+        pdi::{PdiCycle, SubDevicePdi},
+        IoRanges, SubDevice, SubDeviceRef,
     },
     timer_factory::IntoTimeout,
-    DcSync, MainDevice, RegisterAddress, SubDeviceState,
-};
-use atomic_refcell::{AtomicRefCell, AtomicRefMut};
-use core::{
-    cell::UnsafeCell, marker::PhantomData, slice, sync::atomic::AtomicUsize, time::Duration,
+    ConfiguredAddress, DcSync, MainDevice, PdiTransfer, RegisterAddress, RetryBackoff,
+    SubDeviceState,
 };
+use atomic_refcell::{AtomicRef, AtomicRefCell, AtomicRefMut};
+use core::{cell::UnsafeCell, marker::PhantomData, ptr::NonNull, slice, time::Duration};
 use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
 
+pub use self::fallback::{FallbackConfig, FallbackEvent, SafeOpFallback};
 pub use self::group_id::GroupId;
 pub use self::handle::SubDeviceGroupHandle;
 pub use self::iterator::GroupSubDeviceIterator;
+pub use self::output_check::OutputStatus;
+pub use self::recovery::{RecoveryConfig, RecoveryEvent};
 pub use configurator::SubDeviceGroupRef;
 
 static GROUP_ID: AtomicUsize = AtomicUsize::new(0);
 
+/// Handles to the PDUs carrying a group's PDI in a frame.
+enum PdiHandles {
+    Lrw(PduResponseHandle),
+    LrdLwr {
+        inputs: PduResponseHandle,
+        outputs: Option<PduResponseHandle>,
+    },
+}
+
 /// A typestate for [`SubDeviceGroup`] representing a group that is shut down.
 ///
 /// This corresponds to the EtherCAT states INIT.
@@ -70,7 +89,7 @@ pub struct HasDc {
     sync0_period: u64,
     sync0_shift: u64,
     /// Configured address of the DC reference SubDevice.
-    reference: u16,
+    reference: ConfiguredAddress,
 }
 
 /// Marker trait for `SubDeviceGroup` typestates where all SubDevices have a PDI.
@@ -91,14 +110,19 @@ impl IsPreOp for PreOpPdi {}
 struct GroupInner<const MAX_SUBDEVICES: usize> {
     subdevices: heapless::Vec<AtomicRefCell<SubDevice>, MAX_SUBDEVICES>,
     pdi_start: PdiOffset,
+    /// Configured addresses of SubDevices with outputs, set once the PDI has been configured.
+    ///
+    /// Stored separately so output checks don't need to borrow SubDevices during a cycle.
+    outputs: heapless::Vec<ConfiguredAddress, MAX_SUBDEVICES>,
+    /// Configured addresses of all SubDevices in the group except mailbox-only ones, set once the
+    /// PDI has been configured.
+    addresses: heapless::Vec<ConfiguredAddress, MAX_SUBDEVICES>,
 }
 
-const CYCLIC_OP_ENABLE: u8 = 0b0000_0001;
-const SYNC0_ACTIVATE: u8 = 0b0000_0010;
-const SYNC1_ACTIVATE: u8 = 0b0000_0100;
-
 /// Group distributed clock configuration.
-#[derive(Default, Debug, Copy, Clone)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DcConfiguration {
     /// How long the SubDevices in the group should wait before starting SYNC0 pulse generation.
     pub start_delay: Duration,
@@ -115,6 +139,7 @@ pub struct DcConfiguration {
 
 /// Information useful to a process data cycle.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct CycleInfo {
     /// Distributed Clock System time in nanoseconds.
     pub dc_system_time: u64,
@@ -132,6 +157,35 @@ pub struct CycleInfo {
     pub cycle_start_offset: Duration,
 }
 
+/// Resend policy for a group's process data frames.
+///
+/// By default, a lost process data frame is handled like any other PDU, waiting for
+/// [`Timeouts::pdu`](crate::Timeouts::pdu) and retrying according to
+/// [`MainDeviceConfig::retry_behaviour`](crate::MainDeviceConfig::retry_behaviour). This is usually
+/// much longer than the process data cycle time. Setting a `PdiResend` with
+/// [`SubDeviceGroup::set_pdi_resend`] allows a lost frame to be detected and resent within the
+/// same cycle.
+///
+/// The frame is resent with the same PDU index, and the first complete response to either the
+/// original or a resent frame is used. Any response arriving after that is ignored and counted in
+/// [`Statistics::ignored_responses`](crate::Statistics::ignored_responses), so process data is
+/// never mixed from two responses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PdiResend {
+    /// How long to wait for a process data response before resending the frame.
+    ///
+    /// This is a fixed timeout. It is not adjusted from measured round trip times, so should be
+    /// chosen as a small multiple of the network round trip time, e.g. from
+    /// [`MainDevice::latency_histogram`](crate::MainDevice::latency_histogram), and well under the
+    /// cycle time.
+    pub response_timeout: Duration,
+
+    /// The maximum number of times to resend a lost frame before returning an
+    /// [`Error::Timeout`].
+    pub max_resends: usize,
+}
+
 /// A group of one or more EtherCAT SubDevices.
 ///
 /// Groups are created during EtherCrab initialisation, and are the only way to access individual
@@ -144,6 +198,10 @@ pub struct SubDeviceGroup<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S =
     read_pdi_len: usize,
     /// The total length (I and O) of the PDI for this group.
     pdi_len: usize,
+    /// Resend policy for lost process data frames.
+    pdi_resend: Option<PdiResend>,
+    /// Counts PDI exchanges for tasks waiting on inputs.
+    cycle: PdiCycle,
     inner: UnsafeCell<GroupInner<MAX_SUBDEVICES>>,
     dc_conf: DC,
     _state: PhantomData<S>,
@@ -161,7 +219,7 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>
         fmt::debug!(
             "Going to configure group with {} SubDevice(s), starting PDI offset {:#010x}",
             inner.subdevices.len(),
-            inner.pdi_start.start_address
+            inner.pdi_start.start_address.get()
         );
 
         // Configure master read PDI mappings in the first section of the PDI
@@ -176,9 +234,10 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>
                 .await?;
         }
 
-        self.read_pdi_len = (pdi_position.start_address - inner.pdi_start.start_address) as usize;
+        self.read_pdi_len =
+            (pdi_position.start_address.get() - inner.pdi_start.start_address.get()) as usize;
 
-        fmt::debug!("SubDevice mailboxes configured and init hooks called");
+        fmt::debug!("SubDevice read PDI mappings configured");
 
         // We configured all read PDI mappings as a contiguous block in the previous loop. Now we'll
         // configure the write mappings in a separate loop. This means we have IIIIOOOO instead of
@@ -200,19 +259,36 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>
 
         fmt::debug!("SubDevice FMMUs configured for group. Able to move to SAFE-OP");
 
-        self.pdi_len = (pdi_position.start_address - inner.pdi_start.start_address) as usize;
+        inner.addresses = inner
+            .subdevices
+            .iter_mut()
+            .map(AtomicRefCell::get_mut)
+            .filter(|subdevice| !subdevice.is_mailbox_only())
+            .map(|subdevice| subdevice.configured_address())
+            .collect();
+
+        inner.outputs = inner
+            .subdevices
+            .iter_mut()
+            .map(AtomicRefCell::get_mut)
+            .filter(|subdevice| !subdevice.io_segments().output.is_empty())
+            .map(|subdevice| subdevice.configured_address())
+            .collect();
+
+        self.pdi_len =
+            (pdi_position.start_address.get() - inner.pdi_start.start_address.get()) as usize;
 
         fmt::debug!(
             "Group PDI length: start {:#010x}, {} total bytes ({} input bytes)",
-            inner.pdi_start.start_address,
+            inner.pdi_start.start_address.get(),
             self.pdi_len,
             self.read_pdi_len
         );
 
         if self.pdi_len > MAX_PDI {
             return Err(Error::PdiTooLong {
-                max_length: MAX_PDI,
-                desired_length: self.pdi_len,
+                max_length: saturate_u32(MAX_PDI),
+                desired_length: saturate_u32(self.pdi_len),
             });
         }
 
@@ -242,7 +318,7 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>
             .get(index)
             .ok_or(Error::NotFound {
                 item: Item::SubDevice,
-                index: Some(index),
+                index: Some(saturate_u16(index)),
             })?
             .try_borrow_mut()
             .map_err(|_e| {
@@ -287,6 +363,8 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>
             pdi: self.pdi,
             read_pdi_len: self.read_pdi_len,
             pdi_len: self.pdi_len,
+            pdi_resend: self.pdi_resend,
+            cycle: self.cycle,
             inner: UnsafeCell::new(self.inner.into_inner()),
             dc_conf: self.dc_conf,
             _state: PhantomData,
@@ -316,6 +394,38 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>
     }
 
     /// Get an iterator over all SubDevices in this group.
+    ///
+    /// SubDevices are configured in PRE-OP by iterating over the group and calling e.g.
+    /// [`SubDeviceRef::sdo_write`] inline, before the group is transitioned with
+    /// [`into_op`](SubDeviceGroup::into_op). Configuration code is ordinary `async` code in the
+    /// caller, so it needs no allocation or dynamic dispatch.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{
+    /// #     error::Error, MainDevice, MainDeviceConfig, PduStorage, Timeouts, std::ethercat_now
+    /// # };
+    /// # static PDU_STORAGE: PduStorage<8, 32> = PduStorage::new();
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// # async {
+    /// let mut group = maindevice
+    ///     .init_single_group::<8, 8>(ethercat_now)
+    ///     .await
+    ///     .expect("Init");
+    ///
+    /// for subdevice in group.iter(&maindevice) {
+    ///     // EL3004 analog inputs: disable the filter on channel 1
+    ///     if subdevice.identity().product_id == 0x0bbc_3052 {
+    ///         subdevice.sdo_write(0x8000, 0x06, false).await?;
+    ///     }
+    /// }
+    ///
+    /// let group = group.into_op(&maindevice).await?;
+    /// # Ok::<(), ethercrab::error::Error>(())
+    /// # };
+    /// ```
     pub fn iter<'group, 'maindevice>(
         &'group mut self,
         maindevice: &'maindevice MainDevice<'maindevice>,
@@ -361,6 +471,8 @@ where
             pdi: self.pdi,
             read_pdi_len: self.read_pdi_len,
             pdi_len: self.pdi_len,
+            pdi_resend: self.pdi_resend,
+            cycle: self.cycle,
             inner: UnsafeCell::new(self.inner.into_inner()),
             dc_conf: NoDc,
             _state: PhantomData::<PreOp>,
@@ -420,20 +532,27 @@ where
                 .send(maindevice, sync0_period)
                 .await?;
 
-            let flags = if let DcSync::Sync01 { sync1_period } = subdevice.dc_sync() {
+            let sync1 = if let DcSync::Sync01 { sync1_period } = subdevice.dc_sync() {
                 subdevice
                     .write(RegisterAddress::DcSync1CycleTime)
                     .send(maindevice, sync1_period.as_nanos() as u64)
                     .await?;
 
-                SYNC1_ACTIVATE | SYNC0_ACTIVATE | CYCLIC_OP_ENABLE
+                true
             } else {
-                SYNC0_ACTIVATE | CYCLIC_OP_ENABLE
+                false
             };
 
             subdevice
                 .write(RegisterAddress::DcSyncActive)
-                .send(maindevice, flags)
+                .send(
+                    maindevice,
+                    DcSyncActivation {
+                        cyclic_operation: true,
+                        sync0: true,
+                        sync1,
+                    },
+                )
                 .await?;
         }
 
@@ -442,6 +561,8 @@ where
             pdi: self_.pdi,
             read_pdi_len: self_.read_pdi_len,
             pdi_len: self_.pdi_len,
+            pdi_resend: self_.pdi_resend,
+            cycle: self_.cycle,
             inner: UnsafeCell::new(self_.inner.into_inner()),
             dc_conf: HasDc {
                 sync0_period: sync0_period.as_nanos() as u64,
@@ -541,8 +662,10 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>
             .iter_mut()
             .map(|subdevice| subdevice.get_mut())
         {
+            let state = subdevice.group_state(SubDeviceState::Op);
+
             SubDeviceRef::new(maindevice, subdevice.configured_address(), subdevice)
-                .request_subdevice_state_nowait(SubDeviceState::Op)
+                .request_subdevice_state_nowait(state)
                 .await?;
         }
 
@@ -551,6 +674,8 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>
             pdi: self.pdi,
             read_pdi_len: self.read_pdi_len,
             pdi_len: self.pdi_len,
+            pdi_resend: self.pdi_resend,
+            cycle: self.cycle,
             inner: UnsafeCell::new(self.inner.into_inner()),
             dc_conf: self.dc_conf,
             _state: PhantomData,
@@ -569,7 +694,12 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>
         self.transition_to(maindevice, SubDeviceState::SafeOp).await
     }
 
-    /// Returns true if all SubDevices in the group are in OP state
+    /// Returns true if all SubDevices in the group are in OP state.
+    ///
+    /// # Errors
+    ///
+    /// If any SubDevice in the group has failed to reach OP and has its AL status error flag set,
+    /// an [`Error::StateTransition`] is returned containing that SubDevice's AL status code.
     pub async fn all_op(&self, maindevice: &MainDevice<'_>) -> Result<bool, Error> {
         self.is_state(maindevice, SubDeviceState::Op).await
     }
@@ -589,10 +719,12 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S> Default
 {
     fn default() -> Self {
         Self {
-            id: GroupId(GROUP_ID.fetch_add(1, core::sync::atomic::Ordering::Relaxed)),
+            id: GroupId(GROUP_ID.fetch_add(1, crate::atomic::Ordering::Relaxed)),
             pdi: UnsafeCell::new([0u8; MAX_PDI]),
             read_pdi_len: Default::default(),
             pdi_len: Default::default(),
+            pdi_resend: None,
+            cycle: PdiCycle::new(),
             inner: UnsafeCell::new(GroupInner::default()),
             dc_conf: NoDc,
             _state: PhantomData,
@@ -610,6 +742,45 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S, DC>
         unsafe { &*self.inner.get() }
     }
 
+    /// Borrow a SubDevice immutably, e.g. for mailbox access from another task.
+    ///
+    /// Multiple shared borrows of the same SubDevice are allowed, but this method will return
+    /// [`Error::Borrow`] if the SubDevice is currently borrowed with
+    /// [`subdevice`](SubDeviceGroup::subdevice).
+    pub(crate) fn subdevice_shared<'maindevice, 'group>(
+        &'group self,
+        maindevice: &'maindevice MainDevice<'maindevice>,
+        index: usize,
+    ) -> Result<SubDeviceRef<'maindevice, AtomicRef<'group, SubDevice>>, Error> {
+        let subdevice = self
+            .inner()
+            .subdevices
+            .get(index)
+            .ok_or(Error::NotFound {
+                item: Item::SubDevice,
+                index: Some(saturate_u16(index)),
+            })?
+            .try_borrow()
+            .map_err(|_e| {
+                fmt::error!("SubDevice index {} already mutably borrowed", index);
+
+                Error::Borrow
+            })?;
+
+        Ok(SubDeviceRef::new(
+            maindevice,
+            subdevice.configured_address(),
+            subdevice,
+        ))
+    }
+
+    /// Set how quickly a lost process data frame is detected and resent.
+    ///
+    /// Pass `None` to use the MainDevice's PDU timeout and retry behaviour (default).
+    pub fn set_pdi_resend(&mut self, resend: Option<PdiResend>) {
+        self.pdi_resend = resend;
+    }
+
     /// Get the number of SubDevices in this group.
     pub fn len(&self) -> usize {
         self.inner().subdevices.len()
@@ -634,6 +805,9 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S, DC>
     }
 
     /// Check if all SubDevices in the group are the given desired state.
+    ///
+    /// If a SubDevice has its AL status error flag set and has not reached the desired state, an
+    /// [`Error::StateTransition`] is returned containing the SubDevice's AL status code.
     async fn is_state(
         &self,
         maindevice: &MainDevice<'_>,
@@ -645,14 +819,21 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S, DC>
 
         let mut frame_idx = 0;
 
+        // The number of SubDevices checked in previous frames, used to map responses back to a
+        // SubDevice index.
+        let mut num_checked = 0;
+
         // Send as many frames as required to check statuses of all subdevices
         loop {
-            let mut frame = maindevice.pdu_loop.alloc_frame()?;
+            let mut frame = maindevice
+                .pdu_loop
+                .alloc_frame_wait(maindevice.timeouts.pdu, FramePriority::Low)
+                .await?;
 
             let mut num_in_this_frame = 0;
 
             // Fill frame with status requests
-            while let Some(sd) = subdevices.next() {
+            for sd in subdevices.by_ref() {
                 match frame.push_pdu(
                     Command::fprd(
                         sd.borrow().configured_address(),
@@ -697,22 +878,54 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S, DC>
                 &maindevice.pdu_loop,
                 maindevice.timeouts.pdu,
                 maindevice.config.retry_behaviour.retry_count(),
+                maindevice.config.retry_backoff,
             );
 
             maindevice.pdu_loop.wake_sender();
 
             let received = frame.await?;
 
-            for pdu in received.into_iter() {
+            let mut failed = None;
+
+            for (idx, pdu) in received.into_iter().enumerate() {
                 let pdu = pdu?;
 
                 let result = AlControl::unpack_from_slice(&pdu)?;
 
+                let desired_state = self
+                    .inner()
+                    .subdevices
+                    .get(num_checked + idx)
+                    .ok_or(Error::Internal)?
+                    .borrow()
+                    .group_state(desired_state);
+
                 if result.state != desired_state {
+                    if result.error {
+                        failed = Some((num_checked + idx, desired_state));
+
+                        break;
+                    }
+
                     return Ok(false);
                 }
             }
 
+            if let Some((idx, desired_state)) = failed {
+                let configured_address = self
+                    .inner()
+                    .subdevices
+                    .get(idx)
+                    .ok_or(Error::Internal)?
+                    .borrow()
+                    .configured_address();
+
+                return Err(SubDeviceRef::new(maindevice, configured_address, ())
+                    .state_transition_error(desired_state)
+                    .await);
+            }
+
+            num_checked += num_in_this_frame;
             frame_idx += 1;
         }
 
@@ -739,6 +952,14 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S, DC>
     }
 
     /// Transition to a new state.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(group = usize::from(self.id), state = %desired_state)
+        )
+    )]
     async fn transition_to<TO>(
         mut self,
         maindevice: &MainDevice<'_>,
@@ -753,8 +974,10 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S, DC>
             .iter_mut()
             .map(AtomicRefCell::get_mut)
         {
+            let state = subdevice.group_state(desired_state);
+
             SubDeviceRef::new(maindevice, subdevice.configured_address(), subdevice)
-                .request_subdevice_state_nowait(desired_state)
+                .request_subdevice_state_nowait(state)
                 .await?;
         }
 
@@ -769,6 +992,8 @@ impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S, DC>
             pdi: self.pdi,
             read_pdi_len: self.read_pdi_len,
             pdi_len: self.pdi_len,
+            pdi_resend: self.pdi_resend,
+            cycle: self.cycle,
             inner: UnsafeCell::new(self.inner.into_inner()),
             dc_conf: self.dc_conf,
             _state: PhantomData,
@@ -799,7 +1024,7 @@ where
             .get(index)
             .ok_or(Error::NotFound {
                 item: Item::SubDevice,
-                index: Some(index),
+                index: Some(saturate_u16(index)),
             })?
             .try_borrow_mut()
             .map_err(|_e| {
@@ -856,7 +1081,7 @@ where
             // SAFETY: A given SubDevice contained in a `SubDevicePdi` MUST only be borrowed once
             // (currently enforced by `AtomicRefCell`). If it is borrowed more than once, immutable
             // APIs in `SubDeviceRef<SubDevicePdi>` will be unsound.
-            SubDevicePdi::new(subdevice, inputs, outputs),
+            SubDevicePdi::new(subdevice, inputs, outputs, &self.cycle),
         ))
     }
 
@@ -871,10 +1096,17 @@ where
     /// Drive the SubDevice group's inputs and outputs.
     ///
     /// A `SubDeviceGroup` will not process any inputs or outputs unless this method is called
-    /// periodically. It will send an `LRW` to update SubDevice outputs and read SubDevice inputs.
+    /// periodically. It will send an `LRW` to update SubDevice outputs and read SubDevice inputs, or
+    /// an `LRD` and `LWR` if [`PdiTransfer::LrdLwr`] is configured.
     ///
     /// This method returns the working counter on success.
     ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. If the returned future is dropped, e.g. by a `select!` timeout,
+    /// its frame is released for reuse and the group's inputs are left as they were, or updated
+    /// from a response that arrived before the future was dropped.
+    ///
     /// # Errors
     ///
     /// This method will return with an error if the PDU could not be sent over the network, or the
@@ -885,10 +1117,14 @@ where
     /// This method will panic if the frame data length of the group is too large to fit in the
     /// configured maximum PDU length set by the `DATA` const generic of
     /// [`PduStorage`](crate::PduStorage).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(group = usize::from(self.id)))
+    )]
     pub async fn tx_rx<'sto>(&self, maindevice: &'sto MainDevice<'sto>) -> Result<u16, Error> {
         fmt::trace!(
             "Group TX/RX, start address {:#010x}, data len {}, of which read bytes: {}",
-            self.inner().pdi_start.start_address,
+            self.inner().pdi_start.start_address.get(),
             self.pdi().len(),
             self.read_pdi_len
         );
@@ -900,19 +1136,54 @@ where
             maindevice.max_frame_data()
         );
 
-        let data = Command::lrw(self.inner().pdi_start.start_address)
-            .ignore_wkc()
-            .send_receive_slice(maindevice, self.pdi())
+        let (timeout, retries, backoff) = self.pdi_retry(maindevice);
+
+        let mut frame = maindevice
+            .pdu_loop
+            .alloc_frame_wait(timeout, FramePriority::High)
             .await?;
 
-        self.process_pdi_response(&data)
+        let pdi_handles = self.push_pdi(&mut frame, maindevice.config.pdi_transfer)?;
+
+        let frame = frame.mark_sendable(&maindevice.pdu_loop, timeout, retries, backoff);
+
+        maindevice.pdu_loop.wake_sender();
+
+        let received = frame.await?;
+
+        self.process_pdi_handles(&received, pdi_handles)
+    }
+
+    /// Drive the SubDevice group's inputs and outputs like [`tx_rx`](SubDeviceGroup::tx_rx), but
+    /// give up if the exchange doesn't complete before `deadline`.
+    ///
+    /// The deadline covers waiting for a free frame, sending it, and any resends configured with
+    /// [`set_pdi_resend`](SubDeviceGroup::set_pdi_resend), so a cyclic application can bound the
+    /// time spent on process data in each cycle regardless of the MainDevice's
+    /// [`Timeouts`](crate::Timeouts).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Timeout`] if the deadline expires, otherwise the same errors as
+    /// [`tx_rx`](SubDeviceGroup::tx_rx).
+    ///
+    /// # Panics
+    ///
+    /// See [`tx_rx`](SubDeviceGroup::tx_rx).
+    pub async fn tx_rx_with_timeout<'sto>(
+        &self,
+        maindevice: &'sto MainDevice<'sto>,
+        deadline: Duration,
+    ) -> Result<u16, Error> {
+        self.tx_rx(maindevice).timeout(deadline).await
     }
 
     /// Drive the SubDevice group's inputs and outputs and synchronise EtherCAT system time with
     /// `FRMW`.
     ///
     /// A `SubDeviceGroup` will not process any inputs or outputs unless this method is called
-    /// periodically. It will send an `LRW` to update SubDevice outputs and read SubDevice inputs.
+    /// periodically. It will send an `LRW` to update SubDevice outputs and read SubDevice inputs, or
+    /// an `LRD` and `LWR` if [`PdiTransfer::LrdLwr`] is configured.
     ///
     /// This method returns the working counter and the current EtherCAT system time in nanoseconds
     /// on success.
@@ -927,6 +1198,10 @@ where
     /// This method will panic if the frame data length of the group is too large to fit in the
     /// configured maximum PDU length set by the `DATA` const generic of
     /// [`PduStorage`](crate::PduStorage).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(group = usize::from(self.id)))
+    )]
     pub async fn tx_rx_sync_system_time<'sto>(
         &self,
         maindevice: &'sto MainDevice<'sto>,
@@ -940,13 +1215,18 @@ where
 
         fmt::trace!(
             "Group TX/RX with DC sync, start address {:#010x}, data len {}, of which read bytes: {}",
-            self.inner().pdi_start.start_address,
+            self.inner().pdi_start.start_address.get(),
             self.pdi().len(),
             self.read_pdi_len
         );
 
         if let Some(dc_ref) = maindevice.dc_ref_address() {
-            let mut frame = maindevice.pdu_loop.alloc_frame()?;
+            let (timeout, retries, backoff) = self.pdi_retry(maindevice);
+
+            let mut frame = maindevice
+                .pdu_loop
+                .alloc_frame_wait(timeout, FramePriority::High)
+                .await?;
 
             let dc_handle = frame.push_pdu(
                 Command::frmw(dc_ref, RegisterAddress::DcSystemTime.into()).into(),
@@ -954,26 +1234,17 @@ where
                 None,
             )?;
 
-            let pdu_handle = frame.push_pdu(
-                Command::lrw(self.inner().pdi_start.start_address).into(),
-                self.pdi(),
-                None,
-            )?;
+            let pdi_handles = self.push_pdi(&mut frame, maindevice.config.pdi_transfer)?;
 
-            let frame = frame.mark_sendable(
-                &maindevice.pdu_loop,
-                maindevice.timeouts.pdu,
-                maindevice.config.retry_behaviour.retry_count(),
-            );
+            let frame = frame.mark_sendable(&maindevice.pdu_loop, timeout, retries, backoff);
 
             maindevice.pdu_loop.wake_sender();
 
             let received = frame.await?;
 
-            let (time, wkc) = self.process_pdi_response_with_time(
-                &received.pdu(dc_handle)?,
-                &received.pdu(pdu_handle)?,
-            )?;
+            let time = u64::unpack_from_slice(&received.pdu(dc_handle)?)?;
+
+            let wkc = self.process_pdi_handles(&received, pdi_handles)?;
 
             Ok((wkc, Some(time)))
         } else {
@@ -981,17 +1252,123 @@ where
         }
     }
 
-    fn process_pdi_response_with_time(
+    /// Timeout, retry count and backoff to use for frames containing this group's PDI.
+    fn pdi_retry(&self, maindevice: &MainDevice<'_>) -> (Duration, usize, RetryBackoff) {
+        match self.pdi_resend {
+            Some(PdiResend {
+                response_timeout,
+                max_resends,
+            }) => (response_timeout, max_resends, RetryBackoff::None),
+            None => (
+                maindevice.timeouts.pdu,
+                maindevice.config.retry_behaviour.retry_count(),
+                maindevice.config.retry_backoff,
+            ),
+        }
+    }
+
+    /// Push the PDU(s) to exchange this group's PDI into a frame.
+    ///
+    /// Inputs are written directly from the received frame into the group's PDI, so the frame
+    /// must be awaited while `self` is borrowed.
+    fn push_pdi(
+        &self,
+        frame: &mut CreatedFrame<'_>,
+        transfer: PdiTransfer,
+    ) -> Result<PdiHandles, Error> {
+        let start_address = self.inner().pdi_start.start_address;
+
+        let inputs = NonNull::slice_from_raw_parts(
+            NonNull::new(self.pdi.get().cast::<u8>()).ok_or(Error::Internal)?,
+            self.read_pdi_len,
+        );
+
+        match transfer {
+            PdiTransfer::Lrw => {
+                // SAFETY: The inputs are only written by `tx_rx*` methods, which hold `&self`
+                // until the response is received or the frame is released.
+                let handle = unsafe {
+                    frame.push_pdu_direct_rx(
+                        Command::lrw(start_address).into(),
+                        self.pdi(),
+                        None,
+                        inputs,
+                    )?
+                };
+
+                Ok(PdiHandles::Lrw(handle))
+            }
+            PdiTransfer::LrdLwr => {
+                // SAFETY: As above.
+                let inputs = unsafe {
+                    frame.push_pdu_direct_rx(
+                        Command::lrd(start_address).into(),
+                        (),
+                        Some(u16::try_from(self.read_pdi_len)?),
+                        inputs,
+                    )?
+                };
+
+                // Outputs are mapped directly after inputs in the logical address space
+                let outputs = self.pdi().get(self.read_pdi_len..).ok_or(Error::Internal)?;
+
+                let outputs = if outputs.is_empty() {
+                    None
+                } else {
+                    Some(frame.push_pdu(
+                        Command::lwr(start_address.offset(self.read_pdi_len as u32)).into(),
+                        outputs,
+                        None,
+                    )?)
+                };
+
+                Ok(PdiHandles::LrdLwr { inputs, outputs })
+            }
+        }
+    }
+
+    /// Check the PDU(s) pushed by [`push_pdi`](SubDeviceGroup::push_pdi). Inputs have already been
+    /// written into the group's memory when the response was received.
+    ///
+    /// Returns working counter on success.
+    fn process_pdi_handles<'sto>(
         &self,
-        dc: &crate::pdu_loop::ReceivedPdu,
-        data: &crate::pdu_loop::ReceivedPdu,
-    ) -> Result<(u64, u16), Error> {
-        let time = u64::unpack_from_slice(dc)?;
+        received: &'sto ReceivedFrame<'sto>,
+        handles: PdiHandles,
+    ) -> Result<u16, Error> {
+        let wkc = match handles {
+            PdiHandles::Lrw(handle) => self.process_pdi_response(&received.pdu(handle)?)?,
+            PdiHandles::LrdLwr { inputs, outputs } => {
+                let inputs = received.pdu(inputs)?;
+
+                if inputs.len() != self.read_pdi_len {
+                    fmt::error!(
+                        "Data length {} does not match input length {}",
+                        inputs.len(),
+                        self.read_pdi_len
+                    );
+
+                    return Err(Error::Pdu(PduError::Decode));
+                }
 
-        Ok((time, self.process_pdi_response(data)?))
+                // Match the working counter an LRW would produce: +1 per read, +2 per write
+                let outputs_wkc = match outputs {
+                    Some(outputs) => received.pdu(outputs)?.working_counter,
+                    None => 0,
+                };
+
+                inputs
+                    .working_counter
+                    .wrapping_add(outputs_wkc.wrapping_mul(2))
+            }
+        };
+
+        self.cycle.advance();
+
+        Ok(wkc)
     }
 
-    /// Take a received PDI and copy its inputs into the group's memory.
+    /// Check a received PDI whose inputs have been written into the group's memory.
     ///
     /// Returns working counter on success.
     fn process_pdi_response(&self, data: &crate::pdu_loop::ReceivedPdu) -> Result<u16, Error> {
@@ -1005,14 +1382,7 @@ where
             return Err(Error::Pdu(PduError::Decode));
         }
 
-        let wkc = data.working_counter;
-
-        self.pdi_mut()
-            .get_mut(0..self.read_pdi_len)
-            .ok_or(Error::Internal)?
-            .copy_from_slice(data.get(0..self.read_pdi_len).ok_or(Error::Internal)?);
-
-        Ok(wkc)
+        Ok(data.working_counter)
     }
 }
 
@@ -1026,7 +1396,8 @@ where
     /// and return cycle timing information.
     ///
     /// A `SubDeviceGroup` will not process any inputs or outputs unless this method is called
-    /// periodically. It will send an `LRW` to update SubDevice outputs and read SubDevice inputs.
+    /// periodically. It will send an `LRW` to update SubDevice outputs and read SubDevice inputs, or
+    /// an `LRD` and `LWR` if [`PdiTransfer::LrdLwr`] is configured.
     ///
     /// This method returns the working counter and a [`CycleInfo`], containing values that can be
     /// used to synchronise the MainDevice to the network SYNC0 event.
@@ -1133,6 +1504,10 @@ where
     /// }
     /// # }) }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(group = usize::from(self.id)))
+    )]
     pub async fn tx_rx_dc<'sto>(
         &self,
         maindevice: &'sto MainDevice<'sto>,
@@ -1146,12 +1521,17 @@ where
 
         fmt::trace!(
             "Group TX/RX with DC sync, start address {:#010x}, data len {}, of which read bytes: {}",
-            self.inner().pdi_start.start_address,
+            self.inner().pdi_start.start_address.get(),
             self.pdi().len(),
             self.read_pdi_len
         );
 
-        let mut frame = maindevice.pdu_loop.alloc_frame()?;
+        let (timeout, retries, backoff) = self.pdi_retry(maindevice);
+
+        let mut frame = maindevice
+            .pdu_loop
+            .alloc_frame_wait(timeout, FramePriority::High)
+            .await?;
 
         let dc_handle = frame.push_pdu(
             Command::frmw(self.dc_conf.reference, RegisterAddress::DcSystemTime.into()).into(),
@@ -1159,27 +1539,23 @@ where
             None,
         )?;
 
-        let pdu_handle = frame.push_pdu(
-            Command::lrw(self.inner().pdi_start.start_address).into(),
-            self.pdi(),
-            None,
-        )?;
+        let pdi_handles = self.push_pdi(&mut frame, maindevice.config.pdi_transfer)?;
 
-        let frame = frame.mark_sendable(
-            &maindevice.pdu_loop,
-            maindevice.timeouts.pdu,
-            maindevice.config.retry_behaviour.retry_count(),
-        );
+        let frame = frame.mark_sendable(&maindevice.pdu_loop, timeout, retries, backoff);
 
         maindevice.pdu_loop.wake_sender();
 
         let received = frame.await?;
 
-        let (time, wkc) = self.process_pdi_response_with_time(
-            &received.pdu(dc_handle)?,
-            &received.pdu(pdu_handle)?,
-        )?;
+        let time = u64::unpack_from_slice(&received.pdu(dc_handle)?)?;
+
+        let wkc = self.process_pdi_handles(&received, pdi_handles)?;
 
+        Ok((wkc, self.cycle_info(time)))
+    }
+
+    /// Compute cycle timing information from the DC system time received in a process data frame.
+    fn cycle_info(&self, time: u64) -> CycleInfo {
         // Nanoseconds from the start of the cycle. This works because the first SYNC0 pulse
         // time is rounded to a whole number of `sync0_period`-length cycles.
         let cycle_start_offset = time % self.dc_conf.sync0_period;
@@ -1187,13 +1563,291 @@ where
         let time_to_next_iter =
             (self.dc_conf.sync0_period - cycle_start_offset) + self.dc_conf.sync0_shift;
 
-        Ok((
-            wkc,
-            CycleInfo {
-                dc_system_time: time,
-                cycle_start_offset: Duration::from_nanos(cycle_start_offset),
-                next_cycle_wait: Duration::from_nanos(time_to_next_iter),
+        CycleInfo {
+            dc_system_time: time,
+            cycle_start_offset: Duration::from_nanos(cycle_start_offset),
+            next_cycle_wait: Duration::from_nanos(time_to_next_iter),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        error::Error,
+        sim::{block_on, ethercat_now, fixture::*, SimNetwork},
+        subdevice_group::PdiResend,
+        tx_rx_device, MainDevice, MainDeviceConfig, PduStorage, RawEthernetDevice, Timeouts,
+    };
+    use core::{
+        task::{Context, Poll},
+        time::Duration,
+    };
+    use std::sync::Arc;
+
+    /// Holds back the response to the first `LRW` sent once `armed` is set and returns it after
+    /// the next response instead, as if it had been delayed in the network.
+    struct LateLrw {
+        network: SimNetwork,
+        armed: Arc<std::sync::atomic::AtomicBool>,
+        held: Option<Vec<u8>>,
+        late: Option<Vec<u8>>,
+    }
+
+    impl RawEthernetDevice for LateLrw {
+        fn poll_transmit(&mut self, cx: &mut Context<'_>, frame: &[u8]) -> Poll<Result<(), Error>> {
+            let result = self.network.poll_transmit(cx, frame);
+
+            // The first PDU's command follows the Ethernet and EtherCAT headers
+            let is_lrw = frame.get(16) == Some(&0x0c);
+
+            if is_lrw && self.armed.swap(false, std::sync::atomic::Ordering::Relaxed) {
+                let held = &mut self.held;
+
+                let _ = self
+                    .network
+                    .poll_receive(cx, |response| *held = Some(response.to_vec()));
+
+                // Inputs change before the frame is resent, so the two responses can be told apart
+                self.network
+                    .with_subdevice(2, |subdevice| subdevice.inputs_mut()[0] = 0x22);
+            }
+
+            result
+        }
+
+        fn poll_receive(
+            &mut self,
+            cx: &mut Context<'_>,
+            on_frame: impl FnOnce(&[u8]),
+        ) -> Poll<Result<(), Error>> {
+            if let Some(late) = self.late.take() {
+                on_frame(&late);
+
+                return Poll::Ready(Ok(()));
+            }
+
+            let (held, late) = (&mut self.held, &mut self.late);
+
+            self.network.poll_receive(cx, |frame| {
+                on_frame(frame);
+
+                *late = held.take();
+            })
+        }
+    }
+
+    #[test]
+    fn pdi_resend() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let (tx, rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        let maindevice = MainDevice::new(
+            pdu_loop,
+            Timeouts {
+                pdu: Duration::from_millis(100),
+                ..Timeouts::default()
             },
-        ))
+            MainDeviceConfig::default(),
+        );
+
+        let network = network();
+
+        network.with_subdevice(2, |subdevice| subdevice.inputs_mut()[0] = 0x11);
+
+        let armed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        let device = LateLrw {
+            network: network.clone(),
+            armed: armed.clone(),
+            held: None,
+            late: None,
+        };
+
+        block_on(futures_lite::future::or(
+            async {
+                let mut group = maindevice
+                    .init_single_group::<8, 32>(ethercat_now)
+                    .await
+                    .expect("init")
+                    .into_op(&maindevice)
+                    .await
+                    .expect("PRE-OP -> OP");
+
+                group.set_pdi_resend(Some(PdiResend {
+                    response_timeout: Duration::from_millis(1),
+                    max_resends: 1,
+                }));
+
+                let expected_wkc = group.tx_rx(&maindevice).await.expect("TX/RX");
+
+                assert_eq!(
+                    group.subdevice(&maindevice, 2).unwrap().inputs_raw(),
+                    &[0x11]
+                );
+
+                let before = maindevice.statistics();
+
+                armed.store(true, std::sync::atomic::Ordering::Relaxed);
+
+                let start = ethercat_now();
+
+                assert_eq!(group.tx_rx(&maindevice).await, Ok(expected_wkc));
+
+                // Resent after the group's timeout instead of the MainDevice's PDU timeout
+                assert_eq!(
+                    Duration::from_nanos(ethercat_now() - start),
+                    Duration::from_millis(1)
+                );
+
+                // Inputs come from the resent frame, and the late response to the original frame
+                // is ignored
+                assert_eq!(
+                    group.subdevice(&maindevice, 2).unwrap().inputs_raw(),
+                    &[0x22]
+                );
+
+                let after = maindevice.statistics();
+
+                assert_eq!(after.pdu_retries - before.pdu_retries, 1);
+                assert_eq!(after.ignored_responses - before.ignored_responses, 1);
+
+                // The late response isn't picked up by the next cycle either
+                network.with_subdevice(2, |subdevice| subdevice.inputs_mut()[0] = 0x33);
+
+                assert_eq!(group.tx_rx(&maindevice).await, Ok(expected_wkc));
+                assert_eq!(
+                    group.subdevice(&maindevice, 2).unwrap().inputs_raw(),
+                    &[0x33]
+                );
+                assert_eq!(
+                    maindevice.statistics().ignored_responses,
+                    after.ignored_responses
+                );
+            },
+            async {
+                tx_rx_device(device, tx, rx).await.unwrap();
+
+                unreachable!()
+            },
+        ));
+    }
+
+    /// Stops returning frames while `unplugged` is set.
+    struct Unpluggable {
+        network: SimNetwork,
+        unplugged: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl RawEthernetDevice for Unpluggable {
+        fn poll_transmit(&mut self, cx: &mut Context<'_>, frame: &[u8]) -> Poll<Result<(), Error>> {
+            if self.unplugged.load(std::sync::atomic::Ordering::Relaxed) {
+                return Poll::Ready(Ok(()));
+            }
+
+            self.network.poll_transmit(cx, frame)
+        }
+
+        fn poll_receive(
+            &mut self,
+            cx: &mut Context<'_>,
+            on_frame: impl FnOnce(&[u8]),
+        ) -> Poll<Result<(), Error>> {
+            self.network.poll_receive(cx, on_frame)
+        }
+    }
+
+    #[test]
+    fn per_call_timeouts() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let (tx, rx, pdu_loop) = STORAGE.try_split().unwrap();
+
+        // Much longer than the per-call timeouts, so any timeout below comes from the override
+        let maindevice = MainDevice::new(
+            pdu_loop,
+            Timeouts {
+                pdu: Duration::from_secs(10),
+                mailbox_echo: Duration::from_secs(10),
+                mailbox_response: Duration::from_secs(10),
+                ..Timeouts::default()
+            },
+            MainDeviceConfig::default(),
+        );
+
+        let unplugged = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        let device = Unpluggable {
+            network: network(),
+            unplugged: unplugged.clone(),
+        };
+
+        block_on(futures_lite::future::or(
+            async {
+                let group = maindevice
+                    .init_single_group::<8, 32>(ethercat_now)
+                    .await
+                    .expect("init");
+
+                let group = group.into_op(&maindevice).await.expect("PRE-OP -> OP");
+
+                let drive = group.subdevice(&maindevice, 3).unwrap();
+
+                assert_eq!(
+                    drive
+                        .sdo_read_with_timeout::<u16>(0x2000, 0, Duration::from_millis(50))
+                        .await,
+                    Ok(0x1234)
+                );
+                assert_eq!(
+                    drive
+                        .sdo_write_with_timeout(0x2000, 0, 0xabcdu16, Duration::from_millis(50))
+                        .await,
+                    Ok(())
+                );
+                assert_eq!(
+                    group
+                        .tx_rx_with_timeout(&maindevice, Duration::from_millis(1))
+                        .await,
+                    Ok(6)
+                );
+
+                unplugged.store(true, std::sync::atomic::Ordering::Relaxed);
+
+                let start = ethercat_now();
+
+                assert_eq!(
+                    drive
+                        .sdo_read_with_timeout::<u16>(0x2000, 0, Duration::from_millis(50))
+                        .await
+                        .map_err(|e| e.cause()),
+                    Err(Error::Timeout)
+                );
+                assert_eq!(
+                    drive
+                        .sdo_write_with_timeout(0x2000, 0, 0u16, Duration::from_millis(50))
+                        .await
+                        .map_err(|e| e.cause()),
+                    Err(Error::Timeout)
+                );
+                assert_eq!(
+                    group
+                        .tx_rx_with_timeout(&maindevice, Duration::from_millis(1))
+                        .await,
+                    Err(Error::Timeout)
+                );
+
+                assert_eq!(
+                    Duration::from_nanos(ethercat_now() - start),
+                    Duration::from_millis(101)
+                );
+            },
+            async {
+                tx_rx_device(device, tx, rx).await.unwrap();
+
+                unreachable!()
+            },
+        ));
     }
 }
@@ -0,0 +1,350 @@
+//! Per-cycle detection of SubDevices that have stopped driving their outputs.
+
+use super::{CycleInfo, HasDc, HasPdi, SubDeviceGroup};
+use crate::{
+    al_control::AlControl,
+    command::Command,
+    error::Error,
+    fmt,
+    pdu_loop::{CreatedFrame, FramePriority, PduResponseHandle, ReceivedFrame},
+    AlStatusSummary, ConfiguredAddress, MainDevice, RegisterAddress, SubDeviceState,
+};
+use ethercrab_wire::{EtherCrabWireRead, EtherCrabWireSized};
+
+/// The output status of a group, read in the same frame as its process data by
+/// [`SubDeviceGroup::tx_rx_checked`] and [`SubDeviceGroup::tx_rx_dc_checked`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutputStatus<const MAX_SUBDEVICES: usize> {
+    /// The AL status of every SubDevice on the network ORed together.
+    ///
+    /// A SubDevice whose process data watchdog expires will usually fall back to SAFE-OP with its
+    /// error flag set, which shows up here. This covers all SubDevices on the network, not just
+    /// those in this group.
+    pub al_status: AlStatusSummary,
+
+    /// Configured addresses of SubDevices in this group whose process data watchdog had expired
+    /// before this cycle's outputs were written.
+    ///
+    /// These SubDevices stopped driving their outputs at some point since the previous cycle.
+    /// Outputs are written again by the same frame, so a SubDevice will only be listed again if the
+    /// watchdog expires again.
+    pub watchdog_expired: heapless::Vec<ConfiguredAddress, MAX_SUBDEVICES>,
+}
+
+impl<const MAX_SUBDEVICES: usize> OutputStatus<MAX_SUBDEVICES> {
+    /// Returns `true` if `expected_subdevices` SubDevices responded, all are in OP without an
+    /// error, and no process data watchdog in this group has expired.
+    pub fn is_ok(&self, expected_subdevices: usize) -> bool {
+        self.al_status
+            .all_in_state(SubDeviceState::Op, expected_subdevices)
+            && self.watchdog_expired.is_empty()
+    }
+}
+
+/// Handles to the status check PDUs pushed into a process data frame.
+struct CheckHandles<const MAX_SUBDEVICES: usize> {
+    al_status: PduResponseHandle,
+    watchdogs: heapless::Vec<(ConfiguredAddress, PduResponseHandle), MAX_SUBDEVICES>,
+}
+
+impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S, DC>
+    SubDeviceGroup<MAX_SUBDEVICES, MAX_PDI, S, DC>
+where
+    S: HasPdi,
+{
+    /// Drive the SubDevice group's inputs and outputs like [`tx_rx`](SubDeviceGroup::tx_rx), and
+    /// check whether any SubDevice has stopped driving its outputs.
+    ///
+    /// A `BRD` of the AL status register and an `FPRD` of the sync manager watchdog status of each
+    /// SubDevice in the group with outputs are sent in the same frame as the process data, so no
+    /// extra round trip is needed. These PDUs take 14 bytes of frame space each.
+    ///
+    /// This method returns the working counter and the [`OutputStatus`] of the group on success.
+    /// Note that SubDevices report an expired watchdog until the first process data is written
+    /// after power on, so the first checked cycle after entering SAFE-OP will usually list every
+    /// SubDevice with outputs.
+    ///
+    /// # Errors
+    ///
+    /// This method will return with an error if the PDU could not be sent over the network, the
+    /// response times out, or the process data and status checks do not fit in a single frame.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ethercrab::{std::ethercat_now, MainDevice, MainDeviceConfig, PduStorage, Timeouts};
+    /// # static PDU_STORAGE: PduStorage<16, { PduStorage::element_size(1100) }> = PduStorage::new();
+    /// # async fn case() -> Result<(), ethercrab::error::Error> {
+    /// # let (_tx, _rx, pdu_loop) = PDU_STORAGE.try_split().expect("can only split once");
+    /// # let maindevice = MainDevice::new(pdu_loop, Timeouts::default(), MainDeviceConfig::default());
+    /// let group = maindevice
+    ///     .init_single_group::<16, 64>(ethercat_now)
+    ///     .await?
+    ///     .into_op(&maindevice)
+    ///     .await?;
+    ///
+    /// loop {
+    ///     let (_wkc, status) = group.tx_rx_checked(&maindevice).await?;
+    ///
+    ///     for configured_address in status.watchdog_expired.iter() {
+    ///         log::warn!("SubDevice {:#06x} outputs were disabled", configured_address);
+    ///     }
+    ///
+    ///     if !status.is_ok(maindevice.num_subdevices()) {
+    ///         // Stop the machine, start recovery, etc.
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(group = usize::from(self.id)))
+    )]
+    pub async fn tx_rx_checked<'sto>(
+        &self,
+        maindevice: &'sto MainDevice<'sto>,
+    ) -> Result<(u16, OutputStatus<MAX_SUBDEVICES>), Error> {
+        assert!(
+            self.len() <= maindevice.max_frame_data(),
+            "Chunked sends not yet supported. Buffer len {} B too long to send in {} B frame",
+            self.len(),
+            maindevice.max_frame_data()
+        );
+
+        let (timeout, retries, backoff) = self.pdi_retry(maindevice);
+
+        let mut frame = maindevice
+            .pdu_loop
+            .alloc_frame_wait(timeout, FramePriority::High)
+            .await?;
+
+        let check_handles = self.push_output_checks(&mut frame)?;
+
+        let pdi_handles = self.push_pdi(&mut frame, maindevice.config.pdi_transfer)?;
+
+        let frame = frame.mark_sendable(&maindevice.pdu_loop, timeout, retries, backoff);
+
+        maindevice.pdu_loop.wake_sender();
+
+        let received = frame.await?;
+
+        let status = self.process_output_checks(&received, check_handles)?;
+
+        let wkc = self.process_pdi_handles(&received, pdi_handles)?;
+
+        Ok((wkc, status))
+    }
+
+    /// Push status checks into a frame. They must be pushed before the PDI so watchdogs are read
+    /// before this cycle's outputs restart them.
+    fn push_output_checks(
+        &self,
+        frame: &mut CreatedFrame<'_>,
+    ) -> Result<CheckHandles<MAX_SUBDEVICES>, Error> {
+        let al_status = frame.push_pdu(
+            Command::brd(RegisterAddress::AlStatus.into()).into(),
+            (),
+            Some(AlControl::PACKED_LEN as u16),
+        )?;
+
+        let mut watchdogs = heapless::Vec::new();
+
+        for &configured_address in self.inner().outputs.iter() {
+            let handle = frame.push_pdu(
+                Command::fprd(
+                    configured_address,
+                    RegisterAddress::SyncManagerWatchdogStatus.into(),
+                )
+                .into(),
+                (),
+                Some(u16::PACKED_LEN as u16),
+            )?;
+
+            watchdogs
+                .push((configured_address, handle))
+                .map_err(|_| Error::Internal)?;
+        }
+
+        Ok(CheckHandles {
+            al_status,
+            watchdogs,
+        })
+    }
+
+    fn process_output_checks<'sto>(
+        &self,
+        received: &'sto ReceivedFrame<'sto>,
+        handles: CheckHandles<MAX_SUBDEVICES>,
+    ) -> Result<OutputStatus<MAX_SUBDEVICES>, Error> {
+        let response = received.pdu(handles.al_status)?;
+
+        let al_control = AlControl::unpack_from_slice(&response)?;
+
+        let al_status = AlStatusSummary {
+            state: al_control.state,
+            error: al_control.error,
+            working_counter: response.working_counter,
+        };
+
+        let mut watchdog_expired = heapless::Vec::new();
+
+        for (configured_address, handle) in handles.watchdogs {
+            let response = received.pdu(handle)?;
+
+            // SubDevices that didn't respond are visible in the AL status working counter
+            if response.working_counter != 1 {
+                continue;
+            }
+
+            // Bit 0 is cleared when the watchdog expires
+            if u16::unpack_from_slice(&response)? & 0x0001 == 0 {
+                fmt::warn!(
+                    "SubDevice {:#06x} process data watchdog expired, outputs disabled",
+                    configured_address
+                );
+
+                watchdog_expired
+                    .push(configured_address)
+                    .map_err(|_| Error::Internal)?;
+            }
+        }
+
+        Ok(OutputStatus {
+            al_status,
+            watchdog_expired,
+        })
+    }
+}
+
+impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, S>
+    SubDeviceGroup<MAX_SUBDEVICES, MAX_PDI, S, HasDc>
+where
+    S: HasPdi,
+{
+    /// Drive the SubDevice group's inputs and outputs like
+    /// [`tx_rx_dc`](SubDeviceGroup::tx_rx_dc), and check whether any SubDevice has stopped driving
+    /// its outputs.
+    ///
+    /// See [`tx_rx_checked`](SubDeviceGroup::tx_rx_checked) for details on the checks performed.
+    ///
+    /// # Errors
+    ///
+    /// This method will return with an error if the PDU could not be sent over the network, the
+    /// response times out, or the process data and status checks do not fit in a single frame.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(group = usize::from(self.id)))
+    )]
+    pub async fn tx_rx_dc_checked<'sto>(
+        &self,
+        maindevice: &'sto MainDevice<'sto>,
+    ) -> Result<(u16, CycleInfo, OutputStatus<MAX_SUBDEVICES>), Error> {
+        assert!(
+            self.len() <= maindevice.max_frame_data(),
+            "Chunked sends not yet supported. Buffer len {} B too long to send in {} B frame",
+            self.len(),
+            maindevice.max_frame_data()
+        );
+
+        let (timeout, retries, backoff) = self.pdi_retry(maindevice);
+
+        let mut frame = maindevice
+            .pdu_loop
+            .alloc_frame_wait(timeout, FramePriority::High)
+            .await?;
+
+        let dc_handle = frame.push_pdu(
+            Command::frmw(self.dc_conf.reference, RegisterAddress::DcSystemTime.into()).into(),
+            0u64,
+            None,
+        )?;
+
+        let check_handles = self.push_output_checks(&mut frame)?;
+
+        let pdi_handles = self.push_pdi(&mut frame, maindevice.config.pdi_transfer)?;
+
+        let frame = frame.mark_sendable(&maindevice.pdu_loop, timeout, retries, backoff);
+
+        maindevice.pdu_loop.wake_sender();
+
+        let received = frame.await?;
+
+        let time = u64::unpack_from_slice(&received.pdu(dc_handle)?)?;
+
+        let status = self.process_output_checks(&received, check_handles)?;
+
+        let wkc = self.process_pdi_handles(&received, pdi_handles)?;
+
+        Ok((wkc, self.cycle_info(time), status))
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        sim::{fixture::*, SimSubDevice},
+        AlStatusCode, ConfiguredAddress, PduStorage, SubDeviceState,
+    };
+
+    #[test]
+    fn output_check() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            // No process data has been written since power on
+            let (_wkc, status) = group.tx_rx_checked(&maindevice).await.expect("TX/RX");
+
+            assert_eq!(
+                status.watchdog_expired.as_slice(),
+                &[
+                    ConfiguredAddress::new(0x1001),
+                    ConfiguredAddress::new(0x1003)
+                ]
+            );
+            assert!(!status.is_ok(4));
+
+            let (_wkc, status) = group.tx_rx_checked(&maindevice).await.expect("TX/RX");
+
+            assert_eq!(status.al_status.state, SubDeviceState::Op);
+            assert_eq!(status.al_status.working_counter, 4);
+            assert!(status.watchdog_expired.is_empty());
+            assert!(status.is_ok(4));
+
+            net.with_subdevice(3, SimSubDevice::expire_sm_watchdog);
+
+            let (_wkc, status) = group.tx_rx_checked(&maindevice).await.expect("TX/RX");
+
+            assert_eq!(
+                status.watchdog_expired.as_slice(),
+                &[ConfiguredAddress::new(0x1003)]
+            );
+
+            // Outputs written by the previous cycle restarted the watchdog
+            let (_wkc, status) = group.tx_rx_checked(&maindevice).await.expect("TX/RX");
+
+            assert!(status.is_ok(4));
+
+            net.with_subdevice(1, |subdevice| {
+                subdevice.fault(AlStatusCode::SyncManagerWatchdog)
+            });
+
+            let (_wkc, status) = group.tx_rx_checked(&maindevice).await.expect("TX/RX");
+
+            assert!(status.al_status.error);
+            assert!(!status.is_ok(4));
+        });
+    }
+}
@@ -0,0 +1,749 @@
+//! Automatic recovery of SubDevices that have dropped out of OP.
+
+use super::{Op, SubDeviceGroup};
+use crate::{
+    al_control::AlControl, al_status_code::AlStatusCode, command::Command, error::Error, fmt,
+    subdevice::SubDeviceRef, timer_factory::timer, AutoIncrementAddress, ConfiguredAddress,
+    MainDevice, RegisterAddress, SubDeviceState,
+};
+use core::time::Duration;
+
+/// Configuration for [`SubDeviceGroup::recover`] and [`SubDeviceGroup::supervise`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RecoveryConfig {
+    /// How often [`SubDeviceGroup::supervise`] checks the AL status of the network.
+    ///
+    /// Defaults to 100ms.
+    pub check_interval: Duration,
+
+    /// Reconfigure SubDevices that have fallen back to INIT, e.g. after losing power.
+    ///
+    /// A SubDevice that no longer responds at its configured address is checked at its position
+    /// in the network. If it has lost its configured address, e.g. because it was power cycled,
+    /// the address is assigned again and [`RecoveryEvent::PowerCycled`] is emitted.
+    ///
+    /// The mailbox, sync manager and FMMU configuration is written again using the PDI mapping
+    /// computed during initialisation. Any configuration performed by the application in PRE-OP,
+    /// e.g. PDO assignment through SDO writes, is not reapplied.
+    ///
+    /// If this is `false`, SubDevices found in INIT will not be recovered.
+    ///
+    /// Defaults to `false`.
+    pub reconfigure: bool,
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: Duration::from_millis(100),
+            reconfigure: false,
+        }
+    }
+}
+
+/// An event emitted while checking and recovering SubDevices in a group.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RecoveryEvent {
+    /// A SubDevice was found to no longer be in OP.
+    StateLost {
+        /// SubDevice address.
+        configured_address: ConfiguredAddress,
+        /// The state the SubDevice is currently in.
+        state: SubDeviceState,
+        /// The AL status code read from the SubDevice.
+        status_code: AlStatusCode,
+    },
+    /// A SubDevice had lost its configured address, e.g. after being power cycled, and was given
+    /// it again.
+    PowerCycled {
+        /// SubDevice address.
+        configured_address: ConfiguredAddress,
+        /// The position of the SubDevice in the network, used to assign its address.
+        position: u16,
+    },
+    /// A SubDevice found in INIT was reconfigured and is now in PRE-OP.
+    Reconfigured {
+        /// SubDevice address.
+        configured_address: ConfiguredAddress,
+    },
+    /// A SubDevice was brought back into OP, or PRE-OP if it is
+    /// [mailbox only](crate::SubDeviceOverride::mailbox_only).
+    Recovered {
+        /// SubDevice address.
+        configured_address: ConfiguredAddress,
+    },
+    /// Recovery of a SubDevice failed. Another attempt will be made on the next check.
+    RecoveryFailed {
+        /// SubDevice address.
+        configured_address: ConfiguredAddress,
+        /// The error that caused recovery to fail.
+        error: Error,
+    },
+}
+
+/// Whether a network-wide `BRD` of the AL status register indicates all SubDevices are in OP.
+///
+/// The AL status of every SubDevice is ORed together by a `BRD`, so any SubDevice in a state other
+/// than OP or with its error flag set will be visible here.
+fn network_is_op(status: Result<AlControl, Error>) -> Result<bool, Error> {
+    match status {
+        Ok(status) => Ok(status.state == SubDeviceState::Op && !status.error),
+        // One or more SubDevices did not respond
+        Err(Error::WorkingCounter { .. }) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+impl<const MAX_SUBDEVICES: usize, const MAX_PDI: usize, DC>
+    SubDeviceGroup<MAX_SUBDEVICES, MAX_PDI, Op, DC>
+{
+    /// Check that all SubDevices in the group are still in OP, and attempt to bring back any that
+    /// have dropped out, e.g. due to a watchdog timeout or loss of DC sync.
+    ///
+    /// A single `BRD` is used to check the state of the entire network. SubDevices in this group
+    /// are only checked individually if the `BRD` indicates something is wrong. Note that if other
+    /// groups are deliberately left in a state other than OP, every SubDevice in this group will be
+    /// checked each time this method is called.
+    ///
+    /// For each SubDevice that is not in OP, any error is acknowledged and the SubDevice is
+    /// transitioned back to OP. [Mailbox only](crate::SubDeviceOverride::mailbox_only) SubDevices
+    /// are expected to be in PRE-OP instead. SubDevices found in INIT are only recovered if
+    /// [`RecoveryConfig::reconfigure`] is set. Progress is reported through `on_event`.
+    ///
+    /// The process data cycle should continue to run while this method is executing so that
+    /// SubDevices can pass through SAFE-OP without their watchdogs timing out.
+    ///
+    /// Returns the number of SubDevices that were brought back into OP.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if the network-wide status check fails. Failures to recover
+    /// individual SubDevices are reported with [`RecoveryEvent::RecoveryFailed`] instead.
+    pub async fn recover(
+        &self,
+        maindevice: &MainDevice<'_>,
+        config: RecoveryConfig,
+        mut on_event: impl FnMut(RecoveryEvent),
+    ) -> Result<usize, Error> {
+        let status = Command::brd(RegisterAddress::AlStatus.into())
+            .with_wkc(maindevice.num_subdevices() as u16)
+            .receive::<AlControl>(maindevice)
+            .await;
+
+        if network_is_op(status)? {
+            return Ok(0);
+        }
+
+        fmt::trace!("Network is not in OP, checking group SubDevices individually");
+
+        let mut recovered = 0;
+
+        for (index, subdevice) in self.inner().subdevices.iter().enumerate() {
+            let Ok((configured_address, position, target)) =
+                subdevice.try_borrow().map(|subdevice| {
+                    (
+                        subdevice.configured_address(),
+                        subdevice.index(),
+                        subdevice.group_state(SubDeviceState::Op),
+                    )
+                })
+            else {
+                fmt::warn!(
+                    "SubDevice index {} is borrowed, skipping recovery check",
+                    index
+                );
+
+                continue;
+            };
+
+            let subdevice = SubDeviceRef::new(maindevice, configured_address, ());
+
+            let status = match subdevice
+                .read(RegisterAddress::AlStatus)
+                .receive::<AlControl>(maindevice)
+                .await
+            {
+                Ok(status) => status,
+                Err(Error::WorkingCounter { .. }) if config.reconfigure => {
+                    match self
+                        .restore_address(maindevice, configured_address, position)
+                        .await
+                    {
+                        Ok(status) => {
+                            on_event(RecoveryEvent::PowerCycled {
+                                configured_address,
+                                position,
+                            });
+
+                            status
+                        }
+                        Err(error) => {
+                            on_event(RecoveryEvent::RecoveryFailed {
+                                configured_address,
+                                error,
+                            });
+
+                            continue;
+                        }
+                    }
+                }
+                Err(error) => {
+                    on_event(RecoveryEvent::RecoveryFailed {
+                        configured_address,
+                        error,
+                    });
+
+                    continue;
+                }
+            };
+
+            if status.state == target && !status.error {
+                continue;
+            }
+
+            let status_code = subdevice
+                .read(RegisterAddress::AlStatusCode)
+                .receive::<AlStatusCode>(maindevice)
+                .await
+                .unwrap_or(AlStatusCode::UnspecifiedError);
+
+            fmt::warn!(
+                "SubDevice {:#06x} dropped out of {} into {}: {}",
+                configured_address,
+                target,
+                status.state,
+                status_code
+            );
+
+            on_event(RecoveryEvent::StateLost {
+                configured_address,
+                state: status.state,
+                status_code,
+            });
+
+            match self
+                .recover_subdevice(
+                    maindevice,
+                    index,
+                    configured_address,
+                    status.state,
+                    target,
+                    config,
+                    &mut on_event,
+                )
+                .await
+            {
+                Ok(()) => {
+                    fmt::info!(
+                        "SubDevice {:#06x} recovered into {}",
+                        configured_address,
+                        target
+                    );
+
+                    recovered += 1;
+
+                    on_event(RecoveryEvent::Recovered { configured_address });
+                }
+                Err(error) => {
+                    fmt::error!(
+                        "Failed to recover SubDevice {:#06x}: {}",
+                        configured_address,
+                        error
+                    );
+
+                    on_event(RecoveryEvent::RecoveryFailed {
+                        configured_address,
+                        error,
+                    });
+                }
+            }
+        }
+
+        Ok(recovered)
+    }
+
+    /// Call [`recover`](SubDeviceGroup::recover) every [`RecoveryConfig::check_interval`].
+    ///
+    /// This future is intended to be run alongside the application's process data loop, e.g. with
+    /// `join` or `select`. Lost frames during the network-wide status check are ignored and retried
+    /// on the next check.
+    ///
+    /// # Errors
+    ///
+    /// This method only returns if an error other than [`Error::Timeout`] is encountered during
+    /// the network-wide status check.
+    pub async fn supervise(
+        &self,
+        maindevice: &MainDevice<'_>,
+        config: RecoveryConfig,
+        mut on_event: impl FnMut(RecoveryEvent),
+    ) -> Result<(), Error> {
+        loop {
+            timer(config.check_interval).await;
+
+            match self.recover(maindevice, config, &mut on_event).await {
+                Ok(_) => (),
+                Err(e) if e.cause() == Error::Timeout => {
+                    fmt::warn!("Network status check timed out, retrying on next check");
+                }
+                Err(e) => break Err(e),
+            }
+        }
+    }
+
+    /// Assign the configured address of a SubDevice that no longer responds to it, using its
+    /// position in the network, and return its AL status.
+    ///
+    /// Only SubDevices that have lost their address entirely are readdressed, so a different
+    /// SubDevice that has taken this position is left alone.
+    async fn restore_address(
+        &self,
+        maindevice: &MainDevice<'_>,
+        configured_address: ConfiguredAddress,
+        position: u16,
+    ) -> Result<AlControl, Error> {
+        let current = Command::aprd(
+            AutoIncrementAddress::new(position),
+            RegisterAddress::ConfiguredStationAddress.into(),
+        )
+        .receive::<u16>(maindevice)
+        .await?;
+
+        if current != 0 {
+            fmt::error!(
+                "SubDevice at position {} has address {:#06x}, expected {:#06x}",
+                position,
+                current,
+                configured_address
+            );
+
+            return Err(Error::Topology);
+        }
+
+        fmt::warn!(
+            "SubDevice at position {} lost its address, assigning {:#06x}",
+            position,
+            configured_address
+        );
+
+        Command::apwr(
+            AutoIncrementAddress::new(position),
+            RegisterAddress::ConfiguredStationAddress.into(),
+        )
+        .send_receive::<u16>(maindevice, configured_address.get())
+        .await?;
+
+        SubDeviceRef::new(maindevice, configured_address, ())
+            .read(RegisterAddress::AlStatus)
+            .receive::<AlControl>(maindevice)
+            .await
+    }
+
+    /// Acknowledge any error on a single SubDevice and bring it back into `target`, either OP or
+    /// PRE-OP.
+    #[allow(clippy::too_many_arguments)]
+    async fn recover_subdevice(
+        &self,
+        maindevice: &MainDevice<'_>,
+        index: usize,
+        configured_address: ConfiguredAddress,
+        mut state: SubDeviceState,
+        target: SubDeviceState,
+        config: RecoveryConfig,
+        on_event: &mut impl FnMut(RecoveryEvent),
+    ) -> Result<(), Error> {
+        if state == SubDeviceState::Init {
+            if !config.reconfigure {
+                return Err(Error::InvalidState {
+                    expected: SubDeviceState::SafeOp,
+                    actual: state,
+                    configured_address,
+                });
+            }
+
+            let subdevice = self
+                .inner()
+                .subdevices
+                .get(index)
+                .ok_or(Error::Internal)?
+                .try_borrow_mut()
+                .map_err(|_| Error::Borrow)?;
+
+            SubDeviceRef::new(maindevice, configured_address, subdevice)
+                .reconfigure(self.inner().pdi_start.start_address)
+                .await?;
+
+            on_event(RecoveryEvent::Reconfigured { configured_address });
+
+            state = SubDeviceState::PreOp;
+        }
+
+        let subdevice = SubDeviceRef::new(maindevice, configured_address, ());
+
+        subdevice
+            .write(RegisterAddress::AlControl)
+            .send(maindevice, AlControl::acknowledge(state))
+            .await?;
+
+        if target == SubDeviceState::PreOp {
+            return subdevice.request_subdevice_state(target).await;
+        }
+
+        if state != SubDeviceState::SafeOp {
+            subdevice
+                .request_subdevice_state(SubDeviceState::SafeOp)
+                .await?;
+        }
+
+        subdevice.request_subdevice_state(SubDeviceState::Op).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PduCommand;
+
+    #[test]
+    fn all_op() {
+        assert_eq!(
+            network_is_op(Ok(AlControl::new(SubDeviceState::Op))),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn one_in_safe_op() {
+        // OP | SAFE-OP when ORed together by the BRD
+        assert_eq!(
+            network_is_op(Ok(AlControl::new(SubDeviceState::Other(0x0c)))),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn error_flag() {
+        assert_eq!(
+            network_is_op(Ok(AlControl::acknowledge(SubDeviceState::Op))),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn missing_subdevice() {
+        assert_eq!(
+            network_is_op(Err(Error::WorkingCounter {
+                expected: 3,
+                received: 2,
+                command: PduCommand::NOP,
+            })),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn other_error() {
+        assert_eq!(network_is_op(Err(Error::Timeout)), Err(Error::Timeout));
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{
+        al_control::AlControl,
+        error::Error,
+        sim::{fixture::*, sleep},
+        subdevice_group::{RecoveryConfig, RecoveryEvent},
+        AlStatusCode, Command, ConfiguredAddress, PduStorage, RegisterAddress, SubDeviceState,
+    };
+
+    #[test]
+    fn recover_from_safe_op() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            let mut events = Vec::new();
+
+            assert_eq!(
+                group
+                    .recover(&maindevice, RecoveryConfig::default(), |event| events
+                        .push(event))
+                    .await,
+                Ok(0)
+            );
+            assert_eq!(events, []);
+
+            net.with_subdevice(2, |subdevice| {
+                subdevice.fault(AlStatusCode::SyncManagerWatchdog)
+            });
+
+            assert_eq!(
+                group
+                    .recover(&maindevice, RecoveryConfig::default(), |event| events
+                        .push(event))
+                    .await,
+                Ok(1)
+            );
+            assert_eq!(
+                events,
+                [
+                    RecoveryEvent::StateLost {
+                        configured_address: ConfiguredAddress::new(0x1002),
+                        state: SubDeviceState::SafeOp,
+                        status_code: AlStatusCode::SyncManagerWatchdog
+                    },
+                    RecoveryEvent::Recovered {
+                        configured_address: ConfiguredAddress::new(0x1002)
+                    },
+                ]
+            );
+            assert_eq!(group.all_op(&maindevice).await, Ok(true));
+        });
+
+        assert_eq!(
+            network.with_subdevice(2, |subdevice| subdevice.status_code()),
+            Some(AlStatusCode::NoError)
+        );
+    }
+
+    #[test]
+    fn recover_from_init() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            let expected_wkc = group.tx_rx(&maindevice).await.expect("TX/RX");
+
+            Command::fpwr(
+                ConfiguredAddress::new(0x1001),
+                RegisterAddress::AlControl.into(),
+            )
+            .send(&maindevice, AlControl::new(SubDeviceState::Init))
+            .await
+            .expect("OP -> INIT");
+
+            let mut events = Vec::new();
+
+            // SubDevices in INIT are left alone without reconfiguration
+            assert_eq!(
+                group
+                    .recover(&maindevice, RecoveryConfig::default(), |event| events
+                        .push(event))
+                    .await,
+                Ok(0)
+            );
+            assert_eq!(
+                events,
+                [
+                    RecoveryEvent::StateLost {
+                        configured_address: ConfiguredAddress::new(0x1001),
+                        state: SubDeviceState::Init,
+                        status_code: AlStatusCode::NoError
+                    },
+                    RecoveryEvent::RecoveryFailed {
+                        configured_address: ConfiguredAddress::new(0x1001),
+                        error: Error::InvalidState {
+                            expected: SubDeviceState::SafeOp,
+                            actual: SubDeviceState::Init,
+                            configured_address: ConfiguredAddress::new(0x1001),
+                        }
+                    },
+                ]
+            );
+
+            events.clear();
+
+            let config = RecoveryConfig {
+                reconfigure: true,
+                ..RecoveryConfig::default()
+            };
+
+            assert_eq!(
+                group
+                    .recover(&maindevice, config, |event| events.push(event))
+                    .await,
+                Ok(1)
+            );
+            assert_eq!(
+                events,
+                [
+                    RecoveryEvent::StateLost {
+                        configured_address: ConfiguredAddress::new(0x1001),
+                        state: SubDeviceState::Init,
+                        status_code: AlStatusCode::NoError
+                    },
+                    RecoveryEvent::Reconfigured {
+                        configured_address: ConfiguredAddress::new(0x1001)
+                    },
+                    RecoveryEvent::Recovered {
+                        configured_address: ConfiguredAddress::new(0x1001)
+                    },
+                ]
+            );
+
+            assert_eq!(group.all_op(&maindevice).await, Ok(true));
+            assert_eq!(group.tx_rx(&maindevice).await, Ok(expected_wkc));
+        });
+    }
+
+    #[test]
+    fn supervise() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            let config = RecoveryConfig::default();
+
+            let mut events = Vec::new();
+
+            futures_lite::future::or(
+                async {
+                    group
+                        .supervise(&maindevice, config, |event| events.push(event))
+                        .await
+                        .expect("supervise");
+                },
+                async {
+                    net.with_subdevice(1, |subdevice| {
+                        subdevice.fault(AlStatusCode::SyncManagerWatchdog)
+                    });
+
+                    sleep(config.check_interval * 3).await;
+                },
+            )
+            .await;
+
+            // Recovered on the first check, then left alone
+            assert_eq!(
+                events,
+                [
+                    RecoveryEvent::StateLost {
+                        configured_address: ConfiguredAddress::new(0x1001),
+                        state: SubDeviceState::SafeOp,
+                        status_code: AlStatusCode::SyncManagerWatchdog
+                    },
+                    RecoveryEvent::Recovered {
+                        configured_address: ConfiguredAddress::new(0x1001)
+                    },
+                ]
+            );
+            assert_eq!(group.all_op(&maindevice).await, Ok(true));
+        });
+    }
+
+    #[test]
+    fn power_cycle_recovery() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            let expected_wkc = group.tx_rx(&maindevice).await.expect("TX/RX");
+
+            net.power_cycle(2);
+            net.with_subdevice(2, |subdevice| subdevice.inputs_mut()[0] = 0x5a);
+
+            let mut events = Vec::new();
+
+            // Not readdressed without reconfiguration
+            assert_eq!(
+                group
+                    .recover(&maindevice, RecoveryConfig::default(), |event| events
+                        .push(event))
+                    .await,
+                Ok(0)
+            );
+            assert!(matches!(
+                events.as_slice(),
+                [RecoveryEvent::RecoveryFailed {
+                    configured_address,
+                    error: Error::WorkingCounter { .. }
+                }] if *configured_address == ConfiguredAddress::new(0x1002)
+            ));
+
+            events.clear();
+
+            let config = RecoveryConfig {
+                reconfigure: true,
+                ..RecoveryConfig::default()
+            };
+
+            assert_eq!(
+                group
+                    .recover(&maindevice, config, |event| events.push(event))
+                    .await,
+                Ok(1)
+            );
+            assert_eq!(
+                events,
+                [
+                    RecoveryEvent::PowerCycled {
+                        configured_address: ConfiguredAddress::new(0x1002),
+                        position: 2
+                    },
+                    RecoveryEvent::StateLost {
+                        configured_address: ConfiguredAddress::new(0x1002),
+                        state: SubDeviceState::Init,
+                        status_code: AlStatusCode::NoError
+                    },
+                    RecoveryEvent::Reconfigured {
+                        configured_address: ConfiguredAddress::new(0x1002)
+                    },
+                    RecoveryEvent::Recovered {
+                        configured_address: ConfiguredAddress::new(0x1002)
+                    },
+                ]
+            );
+
+            assert_eq!(group.tx_rx(&maindevice).await, Ok(expected_wkc));
+            assert_eq!(
+                group.subdevice(&maindevice, 2).unwrap().inputs_raw(),
+                &[0x5a]
+            );
+        });
+    }
+}
@@ -11,14 +11,19 @@ pub const SM_BASE_ADDRESS: u16 = 0x1c10;
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bytes = 8)]
 pub struct SyncManagerChannel {
+    /// Start address of the buffer in ESC memory.
     #[wire(bytes = 2)]
     pub physical_start_address: u16,
+    /// Buffer length in bytes.
     #[wire(bytes = 2)]
     pub length_bytes: u16,
+    /// Control register.
     #[wire(bytes = 1)]
     pub control: Control,
+    /// Status register.
     #[wire(bytes = 1)]
     pub status: Status,
+    /// Activation register.
     #[wire(bytes = 2)]
     pub enable: Enable,
 }
@@ -59,49 +64,70 @@ impl core::fmt::Display for SyncManagerChannel {
     }
 }
 
+/// Sync manager control register.
+///
+/// Described in ETG1000.4 6.7.2 Sync Manager Attributes
 #[derive(Default, Copy, Clone, Debug, PartialEq, Eq, ethercrab_wire::EtherCrabWireReadWrite)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bytes = 1)]
 pub struct Control {
+    /// Buffered (process data) or mailbox mode.
     #[wire(bits = 2)]
     pub operation_mode: OperationMode,
+    /// Transfer direction.
     #[wire(bits = 2)]
     pub direction: Direction,
+    /// Enable an interrupt in the EtherCAT event request register.
     #[wire(bits = 1)]
     pub ecat_event_enable: bool,
+    /// Enable an interrupt in the AL event request register.
     #[wire(bits = 1)]
     pub dls_user_event_enable: bool,
+    /// Enable the SyncManager watchdog.
     #[wire(bits = 1, post_skip = 1)]
     pub watchdog_enable: bool,
     // reserved1: bool
 }
 
+/// Sync manager status register.
+///
+/// Described in ETG1000.4 6.7.2 Sync Manager Attributes
 #[derive(Default, Copy, Clone, Debug, PartialEq, Eq, ethercrab_wire::EtherCrabWireReadWrite)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bytes = 1)]
 pub struct Status {
+    /// The buffer has been written.
     #[wire(bits = 1)]
     pub has_write_event: bool,
+    /// The buffer has been read.
     #[wire(bits = 1, post_skip = 1)]
     pub has_read_event: bool,
     // reserved1: bool
+    /// Mailbox mode only: the mailbox is full.
     #[wire(bits = 1)]
     pub mailbox_full: bool,
+    /// Buffered mode only: the last written buffer.
     #[wire(bits = 2)]
     pub buffer_state: BufferState,
+    /// The read buffer is in use.
     #[wire(bits = 1)]
     pub read_buffer_open: bool,
+    /// The write buffer is in use.
     #[wire(bits = 1)]
     pub write_buffer_open: bool,
 }
 
+/// Sync manager activation register.
+///
 /// Described in ETG1000.4 6.7.2 Sync Manager Attributes
 #[derive(Default, Copy, Clone, Debug, PartialEq, Eq, ethercrab_wire::EtherCrabWireReadWrite)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bytes = 2)]
 pub struct Enable {
+    /// Enable the sync manager.
     #[wire(bits = 1)]
     pub enable: bool,
+    /// Repeat the last mailbox write, used to recover a lost mailbox response.
     #[wire(bits = 1, post_skip = 4)]
     pub repeat: bool,
     // reserved4: u8
@@ -117,30 +143,38 @@ pub struct Enable {
     #[wire(bits = 1)]
     pub enable_dc_event_local_write: bool,
 
+    /// The SubDevice application has disabled the sync manager.
     #[wire(bits = 1)]
     pub channel_pdi_disabled: bool,
+    /// The SubDevice application has acknowledged a [`repeat`](Enable::repeat) request.
     #[wire(bits = 1, post_skip = 6)]
     pub repeat_ack: bool,
     // reserved6: u8,
 }
 
+/// Sync manager operation mode.
 #[derive(Default, Copy, Clone, Debug, PartialEq, Eq, ethercrab_wire::EtherCrabWireReadWrite)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bits = 2)]
 #[repr(u8)]
 pub enum OperationMode {
+    /// Buffered mode, used for process data.
     #[default]
     Normal = 0x00,
+    /// Mailbox mode.
     Mailbox = 0x02,
 }
 
+/// Sync manager transfer direction, from the MainDevice's perspective.
 #[derive(Default, Copy, Clone, Debug, PartialEq, Eq, ethercrab_wire::EtherCrabWireReadWrite)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[wire(bits = 2)]
 #[repr(u8)]
 pub enum Direction {
+    /// The MainDevice reads from the SyncManager buffer.
     #[default]
     MasterRead = 0x00,
+    /// The MainDevice writes to the SyncManager buffer.
     MasterWrite = 0x01,
 }
 
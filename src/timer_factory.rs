@@ -1,23 +1,261 @@
 use crate::error::Error;
 use core::{future::Future, pin::Pin, task::Poll, time::Duration};
 
-#[cfg(not(feature = "std"))]
-pub(crate) type Timer = embassy_time::Timer;
+// Timer backend precedence: `std`, then `embassy`, then `rtic`, then `cortex-m`, then
+// `embassy-time` on `no_std` targets without any of these features.
+
+#[cfg(all(
+    not(feature = "std"),
+    any(
+        feature = "embassy",
+        all(not(feature = "rtic"), not(feature = "cortex-m"))
+    )
+))]
+pub(crate) type PlatformTimer = embassy_time::Timer;
 #[cfg(feature = "std")]
-pub(crate) type Timer = async_io::Timer;
+pub(crate) type PlatformTimer = async_io::Timer;
+#[cfg(all(
+    any(feature = "rtic", feature = "cortex-m"),
+    not(feature = "std"),
+    not(feature = "embassy")
+))]
+pub(crate) type PlatformTimer = PollTimer;
 
-#[cfg(not(feature = "std"))]
-pub(crate) fn timer(duration: Duration) -> Timer {
+#[cfg(all(
+    not(feature = "std"),
+    any(
+        feature = "embassy",
+        all(not(feature = "rtic"), not(feature = "cortex-m"))
+    )
+))]
+fn platform_timer(duration: Duration) -> PlatformTimer {
     embassy_time::Timer::after(embassy_time::Duration::from_micros(
         duration.as_micros() as u64
     ))
 }
 
 #[cfg(feature = "std")]
-pub(crate) fn timer(duration: Duration) -> Timer {
+fn platform_timer(duration: Duration) -> PlatformTimer {
     async_io::Timer::after(duration)
 }
 
+#[cfg(all(
+    any(feature = "rtic", feature = "cortex-m"),
+    not(feature = "std"),
+    not(feature = "embassy")
+))]
+fn platform_timer(duration: Duration) -> PlatformTimer {
+    PollTimer::after(duration)
+}
+
+/// Monotonic time since an arbitrary point, used to measure durations.
+#[cfg(all(
+    not(feature = "std"),
+    any(
+        feature = "embassy",
+        all(not(feature = "rtic"), not(feature = "cortex-m"))
+    )
+))]
+fn platform_now() -> Duration {
+    Duration::from_micros(embassy_time::Instant::now().as_micros())
+}
+
+/// Monotonic time since an arbitrary point, used to measure durations.
+#[cfg(all(feature = "rtic", not(feature = "std"), not(feature = "embassy")))]
+fn platform_now() -> Duration {
+    crate::rtic::now()
+}
+
+/// Monotonic time since an arbitrary point, used to measure durations.
+#[cfg(all(
+    feature = "cortex-m",
+    not(feature = "rtic"),
+    not(feature = "std"),
+    not(feature = "embassy")
+))]
+fn platform_now() -> Duration {
+    crate::cortex_m::now()
+}
+
+/// Monotonic time since an arbitrary point, used to measure durations.
+#[cfg(feature = "std")]
+fn platform_now() -> Duration {
+    static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+
+    EPOCH.get_or_init(std::time::Instant::now).elapsed()
+}
+
+/// Maximum number of tasks that can wait for a [`PollTimer`] at once.
+#[cfg(all(
+    any(feature = "rtic", feature = "cortex-m"),
+    not(feature = "std"),
+    not(feature = "embassy")
+))]
+const MAX_POLL_TIMER_WAKERS: usize = 8;
+
+/// Tasks waiting for a [`PollTimer`], along with the earliest deadline each is waiting for.
+#[cfg(all(
+    any(feature = "rtic", feature = "cortex-m"),
+    not(feature = "std"),
+    not(feature = "embassy")
+))]
+static POLL_TIMER_WAKERS: critical_section::Mutex<
+    core::cell::RefCell<heapless::Vec<(Duration, core::task::Waker), MAX_POLL_TIMER_WAKERS>>,
+> = critical_section::Mutex::new(core::cell::RefCell::new(heapless::Vec::new()));
+
+/// A timer that completes once [`platform_now`] passes a deadline.
+///
+/// Used with clocks that have no way of waking a task when a deadline passes. The deadline is
+/// checked every time the timer is polled, and the waiting task is woken by
+/// [`wake_poll_timers`], which the application calls periodically.
+///
+/// If more than [`MAX_POLL_TIMER_WAKERS`] tasks are waiting at once, the waker of any task that
+/// doesn't fit is woken immediately instead, so the timer is polled again as soon as possible.
+#[cfg(all(
+    any(feature = "rtic", feature = "cortex-m"),
+    not(feature = "std"),
+    not(feature = "embassy")
+))]
+#[derive(Debug)]
+pub(crate) struct PollTimer {
+    deadline: Duration,
+}
+
+#[cfg(all(
+    any(feature = "rtic", feature = "cortex-m"),
+    not(feature = "std"),
+    not(feature = "embassy")
+))]
+impl PollTimer {
+    pub(crate) fn after(duration: Duration) -> Self {
+        Self {
+            deadline: platform_now().saturating_add(duration),
+        }
+    }
+}
+
+#[cfg(all(
+    any(feature = "rtic", feature = "cortex-m"),
+    not(feature = "std"),
+    not(feature = "embassy")
+))]
+impl Future for PollTimer {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut core::task::Context<'_>) -> Poll<Self::Output> {
+        if platform_now() >= self.deadline {
+            return Poll::Ready(());
+        }
+
+        critical_section::with(|cs| {
+            let mut wakers = POLL_TIMER_WAKERS.borrow_ref_mut(cs);
+
+            if let Some((deadline, _)) = wakers
+                .iter_mut()
+                .find(|(_, waker)| waker.will_wake(cx.waker()))
+            {
+                *deadline = (*deadline).min(self.deadline);
+            } else if wakers.push((self.deadline, cx.waker().clone())).is_err() {
+                cx.waker().wake_by_ref();
+            }
+        });
+
+        Poll::Pending
+    }
+}
+
+/// Wake every task waiting for a [`PollTimer`] whose deadline has passed.
+#[cfg(all(
+    any(feature = "rtic", feature = "cortex-m"),
+    not(feature = "std"),
+    not(feature = "embassy")
+))]
+pub(crate) fn wake_poll_timers() {
+    let now = platform_now();
+
+    critical_section::with(|cs| {
+        POLL_TIMER_WAKERS
+            .borrow_ref_mut(cs)
+            .retain(|(deadline, waker)| {
+                if now >= *deadline {
+                    waker.wake_by_ref();
+
+                    false
+                } else {
+                    true
+                }
+            });
+    });
+}
+
+/// Other platform timers wake their tasks themselves.
+#[cfg(all(
+    any(feature = "rtic", feature = "cortex-m"),
+    any(feature = "std", feature = "embassy")
+))]
+pub(crate) fn wake_poll_timers() {}
+
+#[cfg(not(feature = "sim"))]
+pub(crate) type Timer = PlatformTimer;
+
+/// A timer measured in virtual time when running in [`sim::block_on`](crate::sim::block_on), or
+/// real time otherwise.
+#[cfg(feature = "sim")]
+pub(crate) enum Timer {
+    Platform(PlatformTimer),
+    Virtual(crate::sim::time::VirtualTimer),
+}
+
+// Not every platform timer implements `Debug`, e.g. `embassy_time::Timer`
+#[cfg(feature = "sim")]
+impl core::fmt::Debug for Timer {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Timer::Platform(_) => f.write_str("Platform(..)"),
+            Timer::Virtual(timer) => f.debug_tuple("Virtual").field(timer).finish(),
+        }
+    }
+}
+
+#[cfg(feature = "sim")]
+impl Future for Timer {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut core::task::Context<'_>) -> Poll<Self::Output> {
+        match self.get_mut() {
+            Timer::Platform(timer) => Pin::new(timer).poll(cx).map(|_| ()),
+            Timer::Virtual(timer) => Pin::new(timer).poll(cx),
+        }
+    }
+}
+
+#[cfg(not(feature = "sim"))]
+pub(crate) fn timer(duration: Duration) -> Timer {
+    platform_timer(duration)
+}
+
+#[cfg(feature = "sim")]
+pub(crate) fn timer(duration: Duration) -> Timer {
+    match crate::sim::time::timer(duration) {
+        Some(timer) => Timer::Virtual(timer),
+        None => Timer::Platform(platform_timer(duration)),
+    }
+}
+
+/// Monotonic time since an arbitrary point, used to measure durations.
+#[cfg(not(feature = "sim"))]
+pub(crate) fn now() -> Duration {
+    platform_now()
+}
+
+/// Monotonic time since an arbitrary point, used to measure durations.
+///
+/// This is virtual time when running in [`sim::block_on`](crate::sim::block_on).
+#[cfg(feature = "sim")]
+pub(crate) fn now() -> Duration {
+    crate::sim::time::now().unwrap_or_else(platform_now)
+}
+
 pub(crate) trait IntoTimeout<O> {
     fn timeout(self, timeout: Duration) -> TimeoutFuture<impl Future<Output = Result<O, Error>>>;
 }
@@ -63,7 +301,13 @@ where
 }
 
 /// Timeout configuration for the EtherCrab master.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct Timeouts {
     /// How long to wait for a SubDevice state change, e.g. SAFE-OP to OP.
     ///
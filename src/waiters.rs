@@ -0,0 +1,129 @@
+//! A fixed set of wakers for tasks waiting on the same event.
+
+use crate::atomic::{AtomicU16, Ordering};
+use atomic_waker::AtomicWaker;
+use core::task::Waker;
+
+/// The maximum number of tasks that can be woken by a [`Waiters`].
+///
+/// Any further tasks waiting for the event are polled again immediately instead.
+const MAX_WAITERS: usize = 16;
+
+/// Wakers of tasks waiting for an event, e.g. a frame becoming free.
+pub(crate) struct Waiters {
+    /// Bitmap of claimed `wakers` slots.
+    claimed: AtomicU16,
+    wakers: [AtomicWaker; MAX_WAITERS],
+}
+
+impl Waiters {
+    pub(crate) const fn new() -> Self {
+        Self {
+            claimed: AtomicU16::new(0),
+            wakers: [const { AtomicWaker::new() }; MAX_WAITERS],
+        }
+    }
+
+    /// Claim a waker slot, returning `None` if all slots are in use.
+    pub(crate) fn claim(&self) -> Option<usize> {
+        let mut claimed = self.claimed.load(Ordering::Relaxed);
+
+        loop {
+            let slot = claimed.trailing_ones() as usize;
+
+            if slot >= MAX_WAITERS {
+                return None;
+            }
+
+            match self.claimed.compare_exchange_weak(
+                claimed,
+                claimed | (1 << slot),
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(slot),
+                Err(actual) => claimed = actual,
+            }
+        }
+    }
+
+    pub(crate) fn register(&self, slot: usize, waker: &Waker) {
+        self.wakers[slot].register(waker);
+    }
+
+    pub(crate) fn release(&self, slot: usize) {
+        self.wakers[slot].take();
+
+        self.claimed.fetch_and(!(1 << slot), Ordering::AcqRel);
+    }
+
+    /// Wake every waiting task.
+    pub(crate) fn wake_all(&self) {
+        let claimed = self.claimed.load(Ordering::Acquire);
+
+        for (slot, waker) in self.wakers.iter().enumerate() {
+            if claimed & (1 << slot) != 0 {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl core::fmt::Debug for Waiters {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Waiters")
+            .field("claimed", &self.claimed)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod sim_tests {
+    use crate::{sim::fixture::*, PduStorage};
+
+    #[test]
+    fn wait_for_inputs() {
+        static STORAGE: PduStorage<8, { PduStorage::element_size(1100) }> = PduStorage::new();
+
+        let network = network();
+
+        let net = network.clone();
+
+        run(&network, &STORAGE, |maindevice| async move {
+            let group = maindevice
+                .init_single_group::<8, 32>(|| 0)
+                .await
+                .expect("init")
+                .into_op(&maindevice)
+                .await
+                .expect("PRE-OP -> OP");
+
+            let cycles = core::cell::Cell::new(0);
+
+            let cycle = async {
+                for i in 1..=10 {
+                    if i == 4 {
+                        net.with_subdevice(2, |subdevice| subdevice.inputs_mut()[0] = 0x01);
+                    }
+
+                    group.tx_rx(&maindevice).await.expect("TX/RX");
+
+                    cycles.set(i);
+                }
+            };
+
+            let subdevice = group.subdevice(&maindevice, 2).unwrap();
+
+            let wait = async {
+                subdevice.wait_for(|inputs| inputs[0] == 0x01).await;
+
+                cycles.get()
+            };
+
+            let ((), resolved_at) = futures_lite::future::zip(cycle, wait).await;
+
+            // Resolved on the cycle that read the new inputs
+            assert_eq!(resolved_at, 4);
+        });
+    }
+}
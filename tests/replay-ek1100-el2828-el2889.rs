@@ -44,6 +44,8 @@ async fn replay_ek1100_el2828_el2889() -> Result<(), Error> {
         Timeouts::default(),
         MainDeviceConfig {
             dc_static_sync_iterations: 100,
+            // Recorded before EEPROM reads were cached during init
+            eeprom_cache: false,
             ..Default::default()
         },
     );
@@ -28,6 +28,8 @@ async fn replay_ek1914_el3004_configure() -> Result<(), Error> {
         Timeouts::default(),
         MainDeviceConfig {
             dc_static_sync_iterations: 100,
+            // Recorded before EEPROM reads were cached during init
+            eeprom_cache: false,
             ..Default::default()
         },
     );
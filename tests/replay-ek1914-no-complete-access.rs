@@ -29,7 +29,10 @@ async fn replay_ek1914_no_complete_access() -> Result<(), Error> {
         },
         MainDeviceConfig {
             dc_static_sync_iterations: 100,
+            // Recorded before EEPROM reads were cached during init
+            eeprom_cache: false,
             retry_behaviour: RetryBehaviour::None,
+            ..MainDeviceConfig::default()
         },
     );
 
@@ -27,6 +27,8 @@ async fn replay_ek1914_segmented_upload() -> Result<(), Error> {
         Timeouts::default(),
         MainDeviceConfig {
             dc_static_sync_iterations: 100,
+            // Recorded before EEPROM reads were cached during init
+            eeprom_cache: false,
             ..Default::default()
         },
     );